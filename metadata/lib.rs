@@ -111,6 +111,12 @@ pub struct Source {
     compiler: SourceCompiler,
     #[serde(skip_serializing_if = "Option::is_none")]
     wasm: Option<SourceWasm>,
+    /// Where the source code of the contract is published.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    url: Option<Url>,
+    /// The settings the contract was built with, for reproducing the build.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    build_info: Option<Map<String, Value>>,
 }
 
 impl Source {
@@ -126,8 +132,22 @@ impl Source {
             language,
             compiler,
             wasm,
+            url: None,
+            build_info: None,
         }
     }
+
+    /// Links the source code of the contract, e.g. the repository at the built commit.
+    pub fn with_url(mut self, url: Url) -> Self {
+        self.url = Some(url);
+        self
+    }
+
+    /// Records the settings the contract was built with, e.g. the versions of the tools.
+    pub fn with_build_info(mut self, build_info: Map<String, Value>) -> Self {
+        self.build_info = Some(build_info);
+        self
+    }
 }
 
 /// The bytes of the compiled Wasm smart contract.
@@ -638,4 +658,52 @@ mod tests {
 
         assert_eq!(json, expected);
     }
+
+    #[test]
+    fn json_with_build_info() {
+        let language = SourceLanguage::new(Language::Ink, Version::new(2, 1, 0));
+        let compiler =
+            SourceCompiler::new(Compiler::RustC, Version::parse("1.46.0-nightly").unwrap());
+        let build_info = json! {
+            {
+                "rustc_version": "1.46.0-nightly",
+                "build_mode": "release"
+            }
+        }
+        .as_object()
+        .unwrap()
+        .clone();
+        let source =
+            Source::new(None, CodeHash([0u8; 32]), language, compiler).with_build_info(build_info);
+        let json = serde_json::to_value(&source).unwrap();
+
+        let expected = json! {
+            {
+                "hash": "0x0000000000000000000000000000000000000000000000000000000000000000",
+                "language": "ink! 2.1.0",
+                "compiler": "rustc 1.46.0-nightly",
+                "build_info": {
+                    "rustc_version": "1.46.0-nightly",
+                    "build_mode": "release"
+                }
+            }
+        };
+
+        assert_eq!(json, expected);
+    }
+
+    #[test]
+    fn json_with_source_url() {
+        let language = SourceLanguage::new(Language::Ink, Version::new(2, 1, 0));
+        let compiler =
+            SourceCompiler::new(Compiler::RustC, Version::parse("1.46.0-nightly").unwrap());
+        let source = Source::new(None, CodeHash([0u8; 32]), language, compiler)
+            .with_url(Url::parse("https://github.com/paritytech/ink/tree/v3.0.0").unwrap());
+        let json = serde_json::to_value(&source).unwrap();
+
+        assert_eq!(
+            json["url"],
+            json!("https://github.com/paritytech/ink/tree/v3.0.0")
+        );
+    }
 }
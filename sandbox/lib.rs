@@ -0,0 +1,538 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Executes smart contracts of substrate's contracts pallet in-process, without a node.
+//!
+//! The [`Sandbox`] holds the state of a chain in memory: the balances of accounts, the uploaded
+//! code and the instantiated contracts with their storage. A call which traps or reverts leaves
+//! the state as it was.
+//!
+//! The sandbox does not run the contracts pallet. It is an interpreter of its own, built on
+//! `wasmi`, which implements the `seal0` host functions of the pallet. Gas is metered with a unit
+//! per executed Wasm instruction instead of the weights of the pallet, and no storage deposit is
+//! charged, so neither matches what a node would charge.
+//!
+//! # Example
+//!
+//! ```no_run
+//! # use contract_sandbox::*;
+//! # let wasm = Vec::new();
+//! let mut sandbox = Sandbox::new();
+//! sandbox.set_balance(ALICE, 1_000_000_000_000);
+//!
+//! let code_hash = sandbox.upload_code(&wasm).unwrap();
+//! // the selector of the `new` constructor followed by its arguments
+//! let deploy = sandbox.instantiate(ALICE, code_hash, 0, vec![0x9b, 0xae, 0x9d, 0x5e, 0x01], vec![], DEFAULT_GAS_LIMIT);
+//! let contract = deploy.result.unwrap().account_id;
+//!
+//! // the selector of the `get` message
+//! let get = sandbox.call(ALICE, contract, 0, vec![0x25, 0x44, 0x4a, 0xfe], DEFAULT_GAS_LIMIT);
+//! assert_eq!(get.result.unwrap().data, vec![0x01]);
+//! ```
+
+mod runtime;
+
+use std::{collections::BTreeMap, fmt, rc::Rc};
+
+use codec::{Decode, Encode};
+
+use self::runtime::Ext;
+
+/// The id of an account, the public key of its owner or the address of a contract.
+pub type AccountId = [u8; 32];
+
+/// The hash of uploaded code, and of the topics of events.
+pub type Hash = [u8; 32];
+
+/// A balance, in the smallest unit of the token.
+pub type Balance = u128;
+
+/// The account of the `//Alice` development key, which calls the contracts by default.
+pub const ALICE: AccountId = [
+    0xd4, 0x35, 0x93, 0xc7, 0x15, 0xfd, 0xd3, 0x1c, 0x61, 0x14, 0x1a, 0xbd, 0x04, 0xa9, 0x9f, 0xd6,
+    0x82, 0x2c, 0x85, 0x58, 0x85, 0x4c, 0xcd, 0xe3, 0x9a, 0x56, 0x84, 0xe7, 0xa5, 0x6d, 0xa2, 0x7d,
+];
+
+/// The account of the `//Bob` development key.
+pub const BOB: AccountId = [
+    0x8e, 0xaf, 0x04, 0x15, 0x16, 0x87, 0x73, 0x63, 0x26, 0xc9, 0xfe, 0xa1, 0x7e, 0x25, 0xfc, 0x52,
+    0x87, 0x61, 0x36, 0x93, 0xc9, 0x12, 0x90, 0x9c, 0xb2, 0x26, 0xaa, 0x47, 0x94, 0xf2, 0x6a, 0x48,
+];
+
+/// The account of the `//Charlie` development key.
+pub const CHARLIE: AccountId = [
+    0x90, 0xb5, 0xab, 0x20, 0x5c, 0x69, 0x74, 0xc9, 0xea, 0x84, 0x1b, 0xe6, 0x88, 0x86, 0x46, 0x33,
+    0xdc, 0x9c, 0xa8, 0xa3, 0x57, 0x84, 0x3e, 0xea, 0xcf, 0x23, 0x14, 0x64, 0x99, 0x65, 0xfe, 0x22,
+];
+
+/// The account of the `//Dave` development key.
+pub const DAVE: AccountId = [
+    0x30, 0x67, 0x21, 0x21, 0x1d, 0x54, 0x04, 0xbd, 0x9d, 0xa8, 0x8e, 0x02, 0x04, 0x36, 0x0a, 0x1a,
+    0x9a, 0xb8, 0xb8, 0x7c, 0x66, 0xc1, 0xbc, 0x2f, 0xcd, 0xd3, 0x7f, 0x3c, 0x22, 0x22, 0xcc, 0x20,
+];
+
+/// The account of the `//Eve` development key.
+pub const EVE: AccountId = [
+    0xe6, 0x59, 0xa7, 0xa1, 0x62, 0x8c, 0xdd, 0x93, 0xfe, 0xbc, 0x04, 0xa4, 0xe0, 0x64, 0x6e, 0xa2,
+    0x0e, 0x9f, 0x5f, 0x0c, 0xe0, 0x97, 0xd9, 0xa0, 0x52, 0x90, 0xd4, 0xa9, 0xe0, 0x54, 0xdf, 0x4e,
+];
+
+/// The gas limit of a call or instantiation, unless another one is given.
+pub const DEFAULT_GAS_LIMIT: u64 = 1_000_000_000;
+
+/// The number of milliseconds between blocks.
+pub const BLOCK_TIME: u64 = 6_000;
+
+/// Set in the flags of [`ExecReturnValue`] if the contract reverted its changes.
+pub const REVERT_FLAG: u32 = 1;
+
+/// The reasons for which code cannot be uploaded or a contract could not be executed.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Error {
+    /// The code is no valid Wasm of a contract.
+    InvalidCode(String),
+    /// No code with this hash was uploaded.
+    CodeNotFound(Hash),
+    /// There is no contract at this account.
+    ContractNotFound(AccountId),
+    /// A contract already exists at the account of the instantiation.
+    DuplicateContract(AccountId),
+    /// The balance of the sender is too low for the transferred value.
+    TransferFailed,
+    /// The execution consumed all of the gas limit.
+    OutOfGas,
+    /// The contract trapped, e.g. because it panicked.
+    Trapped(String),
+}
+
+impl fmt::Display for Error {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Error::InvalidCode(reason) => write!(f, "Invalid contract code: {}", reason),
+            Error::CodeNotFound(hash) => write!(f, "No code with the hash {} found", hex(hash)),
+            Error::ContractNotFound(account) => {
+                write!(f, "No contract found at {}", hex(account))
+            }
+            Error::DuplicateContract(account) => {
+                write!(f, "A contract already exists at {}", hex(account))
+            }
+            Error::TransferFailed => write!(f, "The balance is too low for the transfer"),
+            Error::OutOfGas => write!(f, "The execution ran out of gas"),
+            Error::Trapped(reason) => write!(f, "The contract trapped: {}", reason),
+        }
+    }
+}
+
+impl std::error::Error for Error {}
+
+/// The data a contract returned with `seal_return`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ExecReturnValue {
+    pub flags: u32,
+    pub data: Vec<u8>,
+}
+
+impl ExecReturnValue {
+    /// Returns `true` if the contract reverted its changes.
+    pub fn did_revert(&self) -> bool {
+        self.flags & REVERT_FLAG != 0
+    }
+}
+
+/// The data returned by a constructor and the account of the instantiated contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InstantiateReturnValue {
+    pub result: ExecReturnValue,
+    pub account_id: AccountId,
+}
+
+/// An event deposited by a contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    /// The contract which deposited the event.
+    pub emitter: AccountId,
+    pub topics: Vec<Hash>,
+    pub data: Vec<u8>,
+}
+
+/// The outcome of a call or instantiation, like the `ContractResult` of the contracts pallet.
+///
+/// The events are the ones of the changes which were kept, the debug messages are collected
+/// regardless of the outcome.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractResult<R> {
+    pub result: Result<R, Error>,
+    pub gas_consumed: u64,
+    pub events: Vec<Event>,
+    pub debug_messages: Vec<String>,
+}
+
+/// An instantiated contract.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Encode, Decode)]
+pub(crate) struct ContractInfo {
+    pub code_hash: Hash,
+    pub storage: BTreeMap<[u8; 32], Vec<u8>>,
+}
+
+/// The state of the chain, which is persisted with [`Sandbox::encode_state`].
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+pub(crate) struct State {
+    /// The code instrumented for gas metering, by the hash of the uploaded code.
+    pub codes: BTreeMap<Hash, Vec<u8>>,
+    pub contracts: BTreeMap<AccountId, ContractInfo>,
+    pub balances: BTreeMap<AccountId, Balance>,
+    pub block_number: u32,
+    /// The timestamp of the current block, in milliseconds.
+    pub timestamp: u64,
+    pub minimum_balance: Balance,
+}
+
+impl Default for State {
+    fn default() -> Self {
+        Self {
+            codes: BTreeMap::new(),
+            contracts: BTreeMap::new(),
+            balances: BTreeMap::new(),
+            block_number: 1,
+            timestamp: BLOCK_TIME,
+            minimum_balance: 1,
+        }
+    }
+}
+
+/// An in-memory chain executing contracts.
+///
+/// Clones share the compiled code, so that cloning a sandbox to run several executions from
+/// the same state only copies the state.
+#[derive(Clone, Default)]
+pub struct Sandbox {
+    state: State,
+    /// The compiled code, by its hash.
+    modules: BTreeMap<Hash, Rc<wasmi::Module>>,
+}
+
+impl Sandbox {
+    /// Creates a sandbox without any accounts, code or contracts.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Restores a sandbox from a state returned by [`Sandbox::encode_state`].
+    pub fn decode_state(mut encoded: &[u8]) -> Result<Self, codec::Error> {
+        Ok(Self {
+            state: State::decode(&mut encoded)?,
+            modules: BTreeMap::new(),
+        })
+    }
+
+    /// Returns the SCALE encoded state of the sandbox, e.g. to persist it.
+    pub fn encode_state(&self) -> Vec<u8> {
+        self.state.encode()
+    }
+
+    /// Returns the balance of `account`.
+    pub fn balance(&self, account: &AccountId) -> Balance {
+        self.state
+            .balances
+            .get(account)
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// Sets the balance of `account`.
+    pub fn set_balance(&mut self, account: AccountId, balance: Balance) {
+        self.state.balances.insert(account, balance);
+    }
+
+    /// Returns the current block number.
+    pub fn block_number(&self) -> u32 {
+        self.state.block_number
+    }
+
+    /// Advances to the next block, `BLOCK_TIME` later.
+    pub fn advance_block(&mut self) {
+        self.state.block_number += 1;
+        self.state.timestamp += BLOCK_TIME;
+    }
+
+    /// Returns the hash of the code of the contract at `account`.
+    pub fn code_hash(&self, account: &AccountId) -> Option<Hash> {
+        self.state
+            .contracts
+            .get(account)
+            .map(|contract| contract.code_hash)
+    }
+
+    /// Returns the accounts of the instantiated contracts.
+    pub fn contracts(&self) -> impl Iterator<Item = &AccountId> {
+        self.state.contracts.keys()
+    }
+
+    /// Returns the value stored under `key` by the contract at `account`.
+    pub fn get_storage(&self, account: &AccountId, key: &[u8; 32]) -> Option<&[u8]> {
+        self.state
+            .contracts
+            .get(account)?
+            .storage
+            .get(key)
+            .map(Vec::as_slice)
+    }
+
+    /// Returns the keys and values stored by the contract at `account`, ordered by their keys.
+    pub fn storage(&self, account: &AccountId) -> impl Iterator<Item = (&[u8; 32], &[u8])> {
+        self.state
+            .contracts
+            .get(account)
+            .into_iter()
+            .flat_map(|contract| contract.storage.iter())
+            .map(|(key, value)| (key, value.as_slice()))
+    }
+
+    /// Returns the number of bytes of the keys and values stored by the contract at `account`.
+    pub fn storage_size(&self, account: &AccountId) -> usize {
+        self.state.contracts.get(account).map_or(0, |contract| {
+            contract
+                .storage
+                .values()
+                .map(|value| 32 + value.len())
+                .sum()
+        })
+    }
+
+    /// Uploads `wasm`, returning the hash it is stored under.
+    ///
+    /// Uploading the same code twice is no error.
+    pub fn upload_code(&mut self, wasm: &[u8]) -> Result<Hash, Error> {
+        let code_hash = blake2_256(wasm);
+        if self.state.codes.contains_key(&code_hash) {
+            return Ok(code_hash);
+        }
+        let instrumented = runtime::instrument(wasm)?;
+        let module = runtime::compile(&instrumented)?;
+        self.state.codes.insert(code_hash, instrumented);
+        self.modules.insert(code_hash, module);
+        Ok(code_hash)
+    }
+
+    /// Instantiates the code `code_hash` with the constructor call `data`, transferring `value`
+    /// from `origin` to the new contract.
+    ///
+    /// The account of the contract is derived from `origin`, the code hash, `data` and `salt`,
+    /// see [`contract_address`].
+    pub fn instantiate(
+        &mut self,
+        origin: AccountId,
+        code_hash: Hash,
+        value: Balance,
+        data: Vec<u8>,
+        salt: Vec<u8>,
+        gas_limit: u64,
+    ) -> ContractResult<InstantiateReturnValue> {
+        let account_id = contract_address(&origin, &code_hash, &data, &salt);
+        let mut ext = Ext::new(&mut self.state, &mut self.modules, gas_limit);
+        let result = ext
+            .instantiate(origin, code_hash, account_id, value, data)
+            .map(|result| InstantiateReturnValue { result, account_id });
+        ext.into_result(result)
+    }
+
+    /// Uploads `wasm` and instantiates it, see [`Sandbox::instantiate`].
+    pub fn instantiate_with_code(
+        &mut self,
+        origin: AccountId,
+        wasm: &[u8],
+        value: Balance,
+        data: Vec<u8>,
+        salt: Vec<u8>,
+        gas_limit: u64,
+    ) -> ContractResult<InstantiateReturnValue> {
+        match self.upload_code(wasm) {
+            Ok(code_hash) => self.instantiate(origin, code_hash, value, data, salt, gas_limit),
+            Err(err) => ContractResult {
+                result: Err(err),
+                gas_consumed: 0,
+                events: Vec::new(),
+                debug_messages: Vec::new(),
+            },
+        }
+    }
+
+    /// Calls the contract at `dest` with `data`, transferring `value` from `origin` to it.
+    pub fn call(
+        &mut self,
+        origin: AccountId,
+        dest: AccountId,
+        value: Balance,
+        data: Vec<u8>,
+        gas_limit: u64,
+    ) -> ContractResult<ExecReturnValue> {
+        let mut ext = Ext::new(&mut self.state, &mut self.modules, gas_limit);
+        let result = ext.call(origin, dest, value, data);
+        ext.into_result(result)
+    }
+}
+
+/// Returns the account of the contract instantiated by `deployer` from `code_hash`, with the
+/// constructor call `data` and `salt`, as derived by the contracts pallet.
+pub fn contract_address(
+    deployer: &AccountId,
+    code_hash: &Hash,
+    data: &[u8],
+    salt: &[u8],
+) -> AccountId {
+    blake2_256(&(b"contract_addr_v1", deployer, code_hash, data, salt).encode())
+}
+
+/// Returns the blake2 hash of `data` with a length of 256 bits.
+pub(crate) fn blake2_256(data: &[u8]) -> [u8; 32] {
+    let mut hash = [0u8; 32];
+    blake2_into(data, &mut hash);
+    hash
+}
+
+/// Writes the blake2 hash of `data` with the length of `out` to `out`.
+pub(crate) fn blake2_into(data: &[u8], out: &mut [u8]) {
+    use blake2::digest::{Update as _, VariableOutput as _};
+
+    let mut blake2 = blake2::VarBlake2b::new_keyed(&[], out.len());
+    blake2.update(data);
+    blake2.finalize_variable(|result| out.copy_from_slice(result));
+}
+
+fn hex(bytes: &[u8]) -> String {
+    let hex: String = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+    format!("0x{}", hex)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A contract storing a byte at the key `0x00..`, set by the constructor to its input and
+    /// returned by calls. Calls with the input `0xff` trap after storing it.
+    const STORE: &str = r#"
+        (module
+            (import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+            (import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+            (import "seal0" "seal_set_storage" (func $seal_set_storage (param i32 i32 i32)))
+            (import "seal0" "seal_get_storage" (func $seal_get_storage (param i32 i32 i32) (result i32)))
+            (import "seal0" "seal_deposit_event" (func $seal_deposit_event (param i32 i32 i32 i32)))
+            (import "env" "memory" (memory 1 1))
+
+            ;; [0, 32) the storage key, [32, 36) the buffer length, [36, 37) the buffer
+            (data (i32.const 32) "\01")
+
+            (func (export "deploy")
+                (call $seal_input (i32.const 36) (i32.const 32))
+                (call $seal_set_storage (i32.const 0) (i32.const 36) (i32.const 1))
+                (call $seal_deposit_event (i32.const 0) (i32.const 0) (i32.const 36) (i32.const 1))
+            )
+
+            (func (export "call")
+                (call $seal_input (i32.const 36) (i32.const 32))
+                (if (i32.eq (i32.load8_u (i32.const 36)) (i32.const 0xff))
+                    (then
+                        (call $seal_set_storage (i32.const 0) (i32.const 36) (i32.const 1))
+                        (unreachable)
+                    )
+                )
+                (i32.store (i32.const 32) (i32.const 1))
+                (drop (call $seal_get_storage (i32.const 0) (i32.const 36) (i32.const 32)))
+                (call $seal_return (i32.const 0) (i32.const 36) (i32.const 1))
+            )
+        )
+    "#;
+
+    fn instantiate(sandbox: &mut Sandbox, input: u8) -> AccountId {
+        let wasm = wabt::wat2wasm(STORE).unwrap();
+        let deploy =
+            sandbox.instantiate_with_code(ALICE, &wasm, 0, vec![input], vec![], DEFAULT_GAS_LIMIT);
+        assert_eq!(deploy.events.len(), 1);
+        deploy.result.unwrap().account_id
+    }
+
+    #[test]
+    fn derives_contract_addresses_like_the_pallet() {
+        let address = contract_address(&[1; 32], &[2; 32], &[3, 4], &[5]);
+
+        let mut entropy = b"contract_addr_v1".to_vec();
+        entropy.extend(&[1; 32]);
+        entropy.extend(&[2; 32]);
+        // the data and the salt are prefixed with their length
+        entropy.extend(&[8, 3, 4, 4, 5]);
+        assert_eq!(address, blake2_256(&entropy));
+        assert_ne!(address, contract_address(&[1; 32], &[2; 32], &[3], &[5]));
+    }
+
+    #[test]
+    fn instantiates_and_calls_contracts() {
+        let mut sandbox = Sandbox::new();
+        let contract = instantiate(&mut sandbox, 42);
+
+        let call = sandbox.call(ALICE, contract, 0, vec![0], DEFAULT_GAS_LIMIT);
+
+        assert_eq!(call.result.unwrap().data, vec![42]);
+        assert!(call.gas_consumed > 0);
+        assert_eq!(sandbox.get_storage(&contract, &[0; 32]), Some(&[42][..]));
+    }
+
+    #[test]
+    fn reverts_the_changes_of_trapped_calls() {
+        let mut sandbox = Sandbox::new();
+        let contract = instantiate(&mut sandbox, 42);
+
+        let call = sandbox.call(ALICE, contract, 0, vec![0xff], DEFAULT_GAS_LIMIT);
+
+        assert!(matches!(call.result, Err(Error::Trapped(_))));
+        assert_eq!(sandbox.get_storage(&contract, &[0; 32]), Some(&[42][..]));
+    }
+
+    #[test]
+    fn transfers_values_and_rejects_overdrafts() {
+        let mut sandbox = Sandbox::new();
+        sandbox.set_balance(ALICE, 100);
+        let contract = instantiate(&mut sandbox, 42);
+
+        let call = sandbox.call(ALICE, contract, 60, vec![0], DEFAULT_GAS_LIMIT);
+        assert!(call.result.is_ok());
+        let call = sandbox.call(ALICE, contract, 60, vec![0], DEFAULT_GAS_LIMIT);
+        assert_eq!(call.result, Err(Error::TransferFailed));
+
+        assert_eq!(sandbox.balance(&ALICE), 40);
+        assert_eq!(sandbox.balance(&contract), 60);
+    }
+
+    #[test]
+    fn stops_executions_running_out_of_gas() {
+        let mut sandbox = Sandbox::new();
+        let contract = instantiate(&mut sandbox, 42);
+
+        let call = sandbox.call(ALICE, contract, 0, vec![0], 10);
+
+        assert_eq!(call.result, Err(Error::OutOfGas));
+    }
+
+    #[test]
+    fn persists_the_state() {
+        let mut sandbox = Sandbox::new();
+        let contract = instantiate(&mut sandbox, 42);
+
+        let mut restored = Sandbox::decode_state(&sandbox.encode_state()).unwrap();
+        let call = restored.call(ALICE, contract, 0, vec![0], DEFAULT_GAS_LIMIT);
+
+        assert_eq!(call.result.unwrap().data, vec![42]);
+    }
+}
@@ -0,0 +1,949 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The execution of contracts: their instrumentation for gas metering and the host functions
+//! they import.
+
+use std::{cell::RefCell, collections::BTreeMap, fmt, rc::Rc};
+
+use codec::{Decode, Encode};
+use sha2::Digest as _;
+use wasmi::{
+    memory_units::Pages, Externals, FuncInstance, FuncRef, GlobalDescriptor, GlobalRef,
+    ImportResolver, MemoryDescriptor, MemoryInstance, MemoryRef, ModuleInstance, RuntimeArgs,
+    RuntimeValue, Signature, TableDescriptor, TableRef, Trap, TrapKind, ValueType,
+};
+
+use crate::{
+    blake2_256, blake2_into, AccountId, Balance, ContractInfo, ContractResult, Error, Event,
+    ExecReturnValue, Hash, State,
+};
+
+/// The number of nested calls and instantiations after which further ones trap.
+const MAX_CALL_DEPTH: usize = 32;
+
+/// The maximum number of memory pages of a contract, like the default `memory_pages` limit of
+/// the pallet, which is also the maximum of memories not declaring one.
+const MAX_MEMORY_PAGES: usize = 16;
+
+/// The gas charged for calling a host function, on top of the gas per byte it reads.
+const HOST_CALL_GAS: u64 = 100;
+
+/// The value written to an output pointer to skip the output.
+const SENTINEL: u32 = u32::MAX;
+
+/// The return codes of the host functions, like the `ReturnCode` of the contracts pallet.
+mod return_code {
+    pub const SUCCESS: u32 = 0;
+    pub const CALLEE_TRAPPED: u32 = 1;
+    pub const CALLEE_REVERTED: u32 = 2;
+    pub const KEY_NOT_FOUND: u32 = 3;
+    pub const TRANSFER_FAILED: u32 = 5;
+    pub const CODE_NOT_FOUND: u32 = 7;
+    pub const NOT_CALLABLE: u32 = 8;
+}
+
+/// Returns `wasm` instrumented to charge gas with the `gas` function of the `env` module.
+pub(crate) fn instrument(wasm: &[u8]) -> Result<Vec<u8>, Error> {
+    let module = parity_wasm::deserialize_buffer::<parity_wasm::elements::Module>(wasm)
+        .map_err(|err| Error::InvalidCode(err.to_string()))?;
+    for export in &["call", "deploy"] {
+        let exported = module.export_section().is_some_and(|exports| {
+            exports
+                .entries()
+                .iter()
+                .any(|entry| entry.field() == *export)
+        });
+        if !exported {
+            return Err(Error::InvalidCode(format!(
+                "The code does not export `{}`",
+                export
+            )));
+        }
+    }
+    let memories = module.import_section().into_iter().flat_map(|imports| {
+        imports
+            .entries()
+            .iter()
+            .filter_map(|entry| match entry.external() {
+                parity_wasm::elements::External::Memory(memory) => Some(memory.limits()),
+                _ => None,
+            })
+    });
+    for limits in memories {
+        memory_pages(limits.initial(), limits.maximum()).map_err(Error::InvalidCode)?;
+    }
+    let module = pwasm_utils::inject_gas_counter(module, &pwasm_utils::rules::Set::default())
+        .map_err(|_| Error::InvalidCode("The code cannot be metered".to_string()))?;
+    parity_wasm::serialize(module).map_err(|err| Error::InvalidCode(err.to_string()))
+}
+
+/// Returns the maximum number of pages of the imported memory with `initial` and `maximum`
+/// pages, which is at most `MAX_MEMORY_PAGES`.
+fn memory_pages(initial: u32, maximum: Option<u32>) -> Result<usize, String> {
+    let maximum = maximum.map_or(MAX_MEMORY_PAGES, |maximum| maximum as usize);
+    if maximum > MAX_MEMORY_PAGES {
+        return Err(format!(
+            "The memory of contracts has at most {} pages, not {}",
+            MAX_MEMORY_PAGES, maximum
+        ));
+    }
+    if initial as usize > maximum {
+        return Err(format!(
+            "The memory has {} initial pages, more than its maximum of {}",
+            initial, maximum
+        ));
+    }
+    Ok(maximum)
+}
+
+/// Compiles instrumented code.
+pub(crate) fn compile(code: &[u8]) -> Result<Rc<wasmi::Module>, Error> {
+    let module =
+        wasmi::Module::from_buffer(code).map_err(|err| Error::InvalidCode(err.to_string()))?;
+    module
+        .deny_floating_point()
+        .map_err(|_| Error::InvalidCode("The code uses floating point instructions".to_string()))?;
+    Ok(Rc::new(module))
+}
+
+/// The exported functions a contract is executed with.
+#[derive(Debug, Clone, Copy)]
+enum Entry {
+    Deploy,
+    Call,
+}
+
+/// The execution of a call or instantiation with its nested calls and instantiations.
+pub(crate) struct Ext<'a> {
+    state: &'a mut State,
+    modules: &'a mut BTreeMap<Hash, Rc<wasmi::Module>>,
+    gas_limit: u64,
+    gas_consumed: u64,
+    events: Vec<Event>,
+    debug_messages: Vec<String>,
+    depth: usize,
+}
+
+impl<'a> Ext<'a> {
+    pub fn new(
+        state: &'a mut State,
+        modules: &'a mut BTreeMap<Hash, Rc<wasmi::Module>>,
+        gas_limit: u64,
+    ) -> Self {
+        Self {
+            state,
+            modules,
+            gas_limit,
+            gas_consumed: 0,
+            events: Vec::new(),
+            debug_messages: Vec::new(),
+            depth: 0,
+        }
+    }
+
+    /// Returns the outcome of the execution with `result`.
+    pub fn into_result<R>(self, result: Result<R, Error>) -> ContractResult<R> {
+        ContractResult {
+            result,
+            gas_consumed: self.gas_consumed.min(self.gas_limit),
+            events: self.events,
+            debug_messages: self.debug_messages,
+        }
+    }
+
+    /// Instantiates a contract of `code_hash` at `account_id` and executes its constructor.
+    pub fn instantiate(
+        &mut self,
+        caller: AccountId,
+        code_hash: Hash,
+        account_id: AccountId,
+        value: Balance,
+        input: Vec<u8>,
+    ) -> Result<ExecReturnValue, Error> {
+        self.transaction(|ext| {
+            if ext.state.contracts.contains_key(&account_id) {
+                return Err(Error::DuplicateContract(account_id));
+            }
+            if !ext.state.codes.contains_key(&code_hash) {
+                return Err(Error::CodeNotFound(code_hash));
+            }
+            ext.state.contracts.insert(
+                account_id,
+                ContractInfo {
+                    code_hash,
+                    storage: BTreeMap::new(),
+                },
+            );
+            ext.transfer(&caller, &account_id, value)?;
+            ext.execute(Entry::Deploy, caller, account_id, value, input)
+        })
+    }
+
+    /// Calls the contract at `dest`.
+    pub fn call(
+        &mut self,
+        caller: AccountId,
+        dest: AccountId,
+        value: Balance,
+        input: Vec<u8>,
+    ) -> Result<ExecReturnValue, Error> {
+        self.transaction(|ext| {
+            if !ext.state.contracts.contains_key(&dest) {
+                return Err(Error::ContractNotFound(dest));
+            }
+            ext.transfer(&caller, &dest, value)?;
+            ext.execute(Entry::Call, caller, dest, value, input)
+        })
+    }
+
+    /// Runs `f`, restoring the state and events if it fails or reverts.
+    fn transaction(
+        &mut self,
+        f: impl FnOnce(&mut Self) -> Result<ExecReturnValue, Error>,
+    ) -> Result<ExecReturnValue, Error> {
+        let state = self.state.clone();
+        let events = self.events.len();
+        let result = f(self);
+        if !matches!(&result, Ok(ret) if !ret.did_revert()) {
+            *self.state = state;
+            self.events.truncate(events);
+        }
+        result
+    }
+
+    fn transfer(&mut self, from: &AccountId, to: &AccountId, value: Balance) -> Result<(), Error> {
+        if value == 0 || from == to {
+            return Ok(());
+        }
+        let balance = self.state.balances.get(from).copied().unwrap_or_default();
+        if balance < value {
+            return Err(Error::TransferFailed);
+        }
+        self.state.balances.insert(*from, balance - value);
+        *self.state.balances.entry(*to).or_default() += value;
+        Ok(())
+    }
+
+    fn module(&mut self, code_hash: &Hash) -> Result<Rc<wasmi::Module>, Error> {
+        if let Some(module) = self.modules.get(code_hash) {
+            return Ok(module.clone());
+        }
+        let code = self
+            .state
+            .codes
+            .get(code_hash)
+            .ok_or(Error::CodeNotFound(*code_hash))?;
+        let module = compile(code)?;
+        self.modules.insert(*code_hash, module.clone());
+        Ok(module)
+    }
+
+    fn execute(
+        &mut self,
+        entry: Entry,
+        caller: AccountId,
+        address: AccountId,
+        value: Balance,
+        input: Vec<u8>,
+    ) -> Result<ExecReturnValue, Error> {
+        if self.depth >= MAX_CALL_DEPTH {
+            return Err(Error::Trapped(
+                "The maximum call depth is reached".to_string(),
+            ));
+        }
+        let code_hash = self.state.contracts[&address].code_hash;
+        let module = self.module(&code_hash)?;
+        let resolver = Resolver::default();
+        let instance = ModuleInstance::new(&module, &resolver)
+            .map_err(|err| Error::Trapped(err.to_string()))?;
+        let memory = match resolver.memory.borrow().clone() {
+            Some(memory) => memory,
+            None => instance
+                .not_started_instance()
+                .export_by_name("memory")
+                .and_then(|export| export.as_memory().cloned())
+                .ok_or_else(|| Error::InvalidCode("The code has no memory".to_string()))?,
+        };
+
+        self.depth += 1;
+        let mut runtime = Runtime {
+            ext: self,
+            memory,
+            caller,
+            address,
+            value,
+            input,
+            unsupported: resolver.unsupported.into_inner(),
+        };
+        let export = match entry {
+            Entry::Deploy => "deploy",
+            Entry::Call => "call",
+        };
+        let outcome = instance
+            .run_start(&mut runtime)
+            .map_err(wasmi::Error::Trap)
+            .and_then(|instance| instance.invoke_export(export, &[], &mut runtime));
+        self.depth -= 1;
+        exec_result(outcome)
+    }
+}
+
+/// Returns the result of executing a contract from the `outcome` of its exported function.
+fn exec_result(
+    outcome: Result<Option<RuntimeValue>, wasmi::Error>,
+) -> Result<ExecReturnValue, Error> {
+    let trap = match outcome {
+        Ok(_) => return Ok(ExecReturnValue::default()),
+        Err(wasmi::Error::Trap(trap)) => trap,
+        Err(err) => return Err(Error::Trapped(err.to_string())),
+    };
+    match trap.kind() {
+        TrapKind::Host(err) => match err.downcast_ref::<Halt>() {
+            Some(Halt::Return(ret)) => Ok(ret.clone()),
+            Some(Halt::Terminated) => Ok(ExecReturnValue::default()),
+            Some(Halt::OutOfGas) => Err(Error::OutOfGas),
+            Some(Halt::Trap(reason)) => Err(Error::Trapped(reason.clone())),
+            None => Err(Error::Trapped(err.to_string())),
+        },
+        kind => Err(Error::Trapped(format!("{:?}", kind))),
+    }
+}
+
+/// Stops the execution of a contract from a host function.
+#[derive(Debug)]
+enum Halt {
+    /// The contract returned with `seal_return`.
+    Return(ExecReturnValue),
+    /// The contract removed itself with `seal_terminate`.
+    Terminated,
+    OutOfGas,
+    Trap(String),
+}
+
+impl fmt::Display for Halt {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Halt::Return(_) => write!(f, "The contract returned"),
+            Halt::Terminated => write!(f, "The contract terminated"),
+            Halt::OutOfGas => write!(f, "The execution ran out of gas"),
+            Halt::Trap(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+impl wasmi::HostError for Halt {}
+
+fn trap(reason: impl Into<String>) -> Trap {
+    Halt::Trap(reason.into()).into()
+}
+
+/// Resolves the imports of a contract, allocating its memory.
+///
+/// Functions which the sandbox does not support are resolved as well and trap once they are
+/// called, so that contracts importing them can still be executed.
+#[derive(Default)]
+struct Resolver {
+    memory: RefCell<Option<MemoryRef>>,
+    unsupported: RefCell<Vec<String>>,
+}
+
+impl ImportResolver for Resolver {
+    fn resolve_func(
+        &self,
+        module_name: &str,
+        field_name: &str,
+        signature: &Signature,
+    ) -> Result<FuncRef, wasmi::Error> {
+        let index = HOST_FUNCTIONS
+            .iter()
+            .position(|func| func.module == module_name && func.name == field_name);
+        if let Some(index) = index {
+            let func = &HOST_FUNCTIONS[index];
+            if signature.params() != func.params || signature.return_type() != func.result {
+                return Err(wasmi::Error::Instantiation(format!(
+                    "The host function {}::{} is imported with the wrong signature",
+                    module_name, field_name
+                )));
+            }
+            return Ok(FuncInstance::alloc_host(signature.clone(), index));
+        }
+        let mut unsupported = self.unsupported.borrow_mut();
+        unsupported.push(format!("{}::{}", module_name, field_name));
+        Ok(FuncInstance::alloc_host(
+            signature.clone(),
+            HOST_FUNCTIONS.len() + unsupported.len() - 1,
+        ))
+    }
+
+    fn resolve_global(
+        &self,
+        module_name: &str,
+        field_name: &str,
+        _descriptor: &GlobalDescriptor,
+    ) -> Result<GlobalRef, wasmi::Error> {
+        Err(wasmi::Error::Instantiation(format!(
+            "Contracts cannot import the global {}::{}",
+            module_name, field_name
+        )))
+    }
+
+    fn resolve_memory(
+        &self,
+        module_name: &str,
+        field_name: &str,
+        descriptor: &MemoryDescriptor,
+    ) -> Result<MemoryRef, wasmi::Error> {
+        if (module_name, field_name) != ("env", "memory") {
+            return Err(wasmi::Error::Instantiation(format!(
+                "Contracts cannot import the memory {}::{}",
+                module_name, field_name
+            )));
+        }
+        let maximum = memory_pages(descriptor.initial(), descriptor.maximum())
+            .map_err(wasmi::Error::Instantiation)?;
+        let memory =
+            MemoryInstance::alloc(Pages(descriptor.initial() as usize), Some(Pages(maximum)))?;
+        *self.memory.borrow_mut() = Some(memory.clone());
+        Ok(memory)
+    }
+
+    fn resolve_table(
+        &self,
+        module_name: &str,
+        field_name: &str,
+        _descriptor: &TableDescriptor,
+    ) -> Result<TableRef, wasmi::Error> {
+        Err(wasmi::Error::Instantiation(format!(
+            "Contracts cannot import the table {}::{}",
+            module_name, field_name
+        )))
+    }
+}
+
+/// The execution of a contract in a call or instantiation.
+struct Runtime<'e, 'a> {
+    ext: &'e mut Ext<'a>,
+    memory: MemoryRef,
+    caller: AccountId,
+    address: AccountId,
+    value: Balance,
+    input: Vec<u8>,
+    /// The imported functions which the sandbox does not support.
+    unsupported: Vec<String>,
+}
+
+impl Externals for Runtime<'_, '_> {
+    fn invoke_index(
+        &mut self,
+        index: usize,
+        args: RuntimeArgs,
+    ) -> Result<Option<RuntimeValue>, Trap> {
+        match HOST_FUNCTIONS.get(index) {
+            Some(func) => {
+                if func.module == "seal0" {
+                    self.charge(HOST_CALL_GAS)?;
+                }
+                (func.call)(self, args)
+            }
+            None => Err(trap(format!(
+                "The sandbox does not support the host function {}",
+                self.unsupported[index - HOST_FUNCTIONS.len()]
+            ))),
+        }
+    }
+}
+
+impl Runtime<'_, '_> {
+    fn charge(&mut self, gas: u64) -> Result<(), Trap> {
+        self.ext.gas_consumed = self.ext.gas_consumed.saturating_add(gas);
+        if self.ext.gas_consumed > self.ext.gas_limit {
+            return Err(Halt::OutOfGas.into());
+        }
+        Ok(())
+    }
+
+    fn read(&mut self, ptr: u32, len: u32) -> Result<Vec<u8>, Trap> {
+        self.charge(len as u64)?;
+        self.memory
+            .get(ptr, len as usize)
+            .map_err(|_| TrapKind::MemoryAccessOutOfBounds.into())
+    }
+
+    fn read_key(&mut self, ptr: u32) -> Result<[u8; 32], Trap> {
+        let mut key = [0u8; 32];
+        self.memory
+            .get_into(ptr, &mut key)
+            .map_err(|_| Trap::from(TrapKind::MemoryAccessOutOfBounds))?;
+        Ok(key)
+    }
+
+    fn decode<T: Decode>(&mut self, ptr: u32, len: u32) -> Result<T, Trap> {
+        let data = self.read(ptr, len)?;
+        T::decode(&mut &data[..]).map_err(|_| trap("Failed to decode the input of a host function"))
+    }
+
+    fn write(&mut self, ptr: u32, data: &[u8]) -> Result<(), Trap> {
+        self.charge(data.len() as u64)?;
+        self.memory
+            .set(ptr, data)
+            .map_err(|_| TrapKind::MemoryAccessOutOfBounds.into())
+    }
+
+    /// Writes `data` to the buffer at `out_ptr`, whose length is read from and then written to
+    /// `out_len_ptr`.
+    fn write_output(&mut self, out_ptr: u32, out_len_ptr: u32, data: &[u8]) -> Result<(), Trap> {
+        if out_ptr == SENTINEL {
+            return Ok(());
+        }
+        let len: u32 = self
+            .memory
+            .get_value(out_len_ptr)
+            .map_err(|_| Trap::from(TrapKind::MemoryAccessOutOfBounds))?;
+        if (len as usize) < data.len() {
+            return Err(trap("The output buffer is too small"));
+        }
+        self.write(out_ptr, data)?;
+        self.memory
+            .set_value(out_len_ptr, data.len() as u32)
+            .map_err(|_| TrapKind::MemoryAccessOutOfBounds.into())
+    }
+
+    fn contract(&mut self) -> Result<&mut ContractInfo, Trap> {
+        self.ext
+            .state
+            .contracts
+            .get_mut(&self.address)
+            .ok_or_else(|| trap("The contract was terminated"))
+    }
+}
+
+type HostResult = Result<Option<RuntimeValue>, Trap>;
+
+/// A function a contract imports from the host.
+struct HostFunction {
+    module: &'static str,
+    name: &'static str,
+    params: &'static [ValueType],
+    result: Option<ValueType>,
+    call: fn(&mut Runtime, RuntimeArgs) -> HostResult,
+}
+
+const I32: ValueType = ValueType::I32;
+const I64: ValueType = ValueType::I64;
+
+macro_rules! host_functions {
+    ($($module:literal $name:ident ($($param:ident),*) $(-> $result:ident)?;)*) => {
+        &[$(HostFunction {
+            module: $module,
+            name: stringify!($name),
+            params: &[$($param),*],
+            result: host_functions!(@result $($result)?),
+            call: $name,
+        }),*]
+    };
+    (@result) => { None };
+    (@result $result:ident) => { Some($result) };
+}
+
+/// The host functions of the sandbox, the `seal0` functions of the contracts pallet and the
+/// `gas` function of the instrumentation.
+const HOST_FUNCTIONS: &[HostFunction] = host_functions! {
+    "env" gas(I32);
+    "seal0" seal_input(I32, I32);
+    "seal0" seal_return(I32, I32, I32);
+    "seal0" seal_set_storage(I32, I32, I32);
+    "seal0" seal_clear_storage(I32);
+    "seal0" seal_get_storage(I32, I32, I32) -> I32;
+    "seal0" seal_contains_storage(I32) -> I32;
+    "seal0" seal_transfer(I32, I32, I32, I32) -> I32;
+    "seal0" seal_call(I32, I32, I64, I32, I32, I32, I32, I32, I32) -> I32;
+    "seal0" seal_instantiate(I32, I32, I64, I32, I32, I32, I32, I32, I32, I32, I32, I32, I32) -> I32;
+    "seal0" seal_terminate(I32, I32);
+    "seal0" seal_caller(I32, I32);
+    "seal0" seal_is_contract(I32) -> I32;
+    "seal0" seal_address(I32, I32);
+    "seal0" seal_weight_to_fee(I64, I32, I32);
+    "seal0" seal_gas_left(I32, I32);
+    "seal0" seal_balance(I32, I32);
+    "seal0" seal_value_transferred(I32, I32);
+    "seal0" seal_random(I32, I32, I32, I32);
+    "seal0" seal_now(I32, I32);
+    "seal0" seal_minimum_balance(I32, I32);
+    "seal0" seal_tombstone_deposit(I32, I32);
+    "seal0" seal_rent_allowance(I32, I32);
+    "seal0" seal_set_rent_allowance(I32, I32);
+    "seal0" seal_block_number(I32, I32);
+    "seal0" seal_deposit_event(I32, I32, I32, I32);
+    "seal0" seal_debug_message(I32, I32) -> I32;
+    "seal0" seal_println(I32, I32);
+    "seal0" seal_hash_sha2_256(I32, I32, I32);
+    "seal0" seal_hash_keccak_256(I32, I32, I32);
+    "seal0" seal_hash_blake2_256(I32, I32, I32);
+    "seal0" seal_hash_blake2_128(I32, I32, I32);
+};
+
+fn ok(value: Option<u32>) -> HostResult {
+    Ok(value.map(|value| RuntimeValue::I32(value as i32)))
+}
+
+fn gas(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let amount: u32 = args.nth_checked(0)?;
+    rt.charge(amount as u64)?;
+    ok(None)
+}
+
+fn seal_input(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let input = rt.input.clone();
+    rt.write_output(args.nth_checked(0)?, args.nth_checked(1)?, &input)?;
+    ok(None)
+}
+
+fn seal_return(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let flags = args.nth_checked(0)?;
+    let data = rt.read(args.nth_checked(1)?, args.nth_checked(2)?)?;
+    Err(Halt::Return(ExecReturnValue { flags, data }).into())
+}
+
+fn seal_set_storage(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let key = rt.read_key(args.nth_checked(0)?)?;
+    let value = rt.read(args.nth_checked(1)?, args.nth_checked(2)?)?;
+    rt.contract()?.storage.insert(key, value);
+    ok(None)
+}
+
+fn seal_clear_storage(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let key = rt.read_key(args.nth_checked(0)?)?;
+    rt.contract()?.storage.remove(&key);
+    ok(None)
+}
+
+fn seal_get_storage(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let key = rt.read_key(args.nth_checked(0)?)?;
+    match rt.contract()?.storage.get(&key).cloned() {
+        Some(value) => {
+            rt.write_output(args.nth_checked(1)?, args.nth_checked(2)?, &value)?;
+            ok(Some(return_code::SUCCESS))
+        }
+        None => ok(Some(return_code::KEY_NOT_FOUND)),
+    }
+}
+
+fn seal_contains_storage(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let key = rt.read_key(args.nth_checked(0)?)?;
+    let size = rt.contract()?.storage.get(&key).map(Vec::len);
+    ok(Some(size.map_or(SENTINEL, |size| size as u32)))
+}
+
+fn seal_transfer(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let to: AccountId = rt.decode(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    let value: Balance = rt.decode(args.nth_checked(2)?, args.nth_checked(3)?)?;
+    let from = rt.address;
+    match rt.ext.transfer(&from, &to, value) {
+        Ok(()) => ok(Some(return_code::SUCCESS)),
+        Err(_) => ok(Some(return_code::TRANSFER_FAILED)),
+    }
+}
+
+/// Returns the return code of the result of a nested call or instantiation, halting if it ran
+/// out of gas.
+fn nested_return_code(result: &Result<ExecReturnValue, Error>) -> Result<u32, Trap> {
+    match result {
+        Ok(ret) if ret.did_revert() => Ok(return_code::CALLEE_REVERTED),
+        Ok(_) => Ok(return_code::SUCCESS),
+        Err(Error::OutOfGas) => Err(Halt::OutOfGas.into()),
+        Err(Error::TransferFailed) => Ok(return_code::TRANSFER_FAILED),
+        Err(Error::CodeNotFound(_)) => Ok(return_code::CODE_NOT_FOUND),
+        Err(_) => Ok(return_code::CALLEE_TRAPPED),
+    }
+}
+
+fn seal_call(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let callee: AccountId = rt.decode(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    let value: Balance = rt.decode(args.nth_checked(3)?, args.nth_checked(4)?)?;
+    let input = rt.read(args.nth_checked(5)?, args.nth_checked(6)?)?;
+    if !rt.ext.state.contracts.contains_key(&callee) {
+        return ok(Some(return_code::NOT_CALLABLE));
+    }
+    let caller = rt.address;
+    let result = rt.ext.call(caller, callee, value, input);
+    let code = nested_return_code(&result)?;
+    if let Ok(ret) = result {
+        rt.write_output(args.nth_checked(7)?, args.nth_checked(8)?, &ret.data)?;
+    }
+    ok(Some(code))
+}
+
+fn seal_instantiate(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let code_hash: Hash = rt.decode(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    let value: Balance = rt.decode(args.nth_checked(3)?, args.nth_checked(4)?)?;
+    let input = rt.read(args.nth_checked(5)?, args.nth_checked(6)?)?;
+    let salt = rt.read(args.nth_checked(11)?, args.nth_checked(12)?)?;
+    let deployer = rt.address;
+    let account_id = crate::contract_address(&deployer, &code_hash, &input, &salt);
+    let result = rt
+        .ext
+        .instantiate(deployer, code_hash, account_id, value, input);
+    let code = nested_return_code(&result)?;
+    if let Ok(ret) = result {
+        if !ret.did_revert() {
+            rt.write_output(args.nth_checked(7)?, args.nth_checked(8)?, &account_id)?;
+        }
+        rt.write_output(args.nth_checked(9)?, args.nth_checked(10)?, &ret.data)?;
+    }
+    ok(Some(code))
+}
+
+fn seal_terminate(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let beneficiary: AccountId = rt.decode(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    let address = rt.address;
+    let balance = rt
+        .ext
+        .state
+        .balances
+        .get(&address)
+        .copied()
+        .unwrap_or_default();
+    rt.ext
+        .transfer(&address, &beneficiary, balance)
+        .map_err(|err| trap(err.to_string()))?;
+    rt.ext.state.contracts.remove(&address);
+    rt.ext.state.balances.remove(&address);
+    Err(Halt::Terminated.into())
+}
+
+/// Writes the SCALE encoded `value` to the output buffer of the arguments `out_ptr` and
+/// `out_len_ptr`.
+fn output(
+    rt: &mut Runtime,
+    args: &RuntimeArgs,
+    first_arg: usize,
+    value: impl Encode,
+) -> HostResult {
+    rt.write_output(
+        args.nth_checked(first_arg)?,
+        args.nth_checked(first_arg + 1)?,
+        &value.encode(),
+    )?;
+    ok(None)
+}
+
+fn seal_caller(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let caller = rt.caller;
+    output(rt, &args, 0, caller)
+}
+
+fn seal_is_contract(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let account = rt.read_key(args.nth_checked(0)?)?;
+    ok(Some(rt.ext.state.contracts.contains_key(&account) as u32))
+}
+
+fn seal_address(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let address = rt.address;
+    output(rt, &args, 0, address)
+}
+
+fn seal_weight_to_fee(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let gas: u64 = args.nth_checked(0)?;
+    output(rt, &args, 1, gas as Balance)
+}
+
+fn seal_gas_left(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let gas_left = rt.ext.gas_limit.saturating_sub(rt.ext.gas_consumed);
+    output(rt, &args, 0, gas_left)
+}
+
+fn seal_balance(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let balance = rt
+        .ext
+        .state
+        .balances
+        .get(&rt.address)
+        .copied()
+        .unwrap_or_default();
+    output(rt, &args, 0, balance)
+}
+
+fn seal_value_transferred(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let value = rt.value;
+    output(rt, &args, 0, value)
+}
+
+fn seal_random(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let mut subject = rt.read(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    subject.extend(rt.ext.state.block_number.encode());
+    output(rt, &args, 2, blake2_256(&subject))
+}
+
+fn seal_now(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let now = rt.ext.state.timestamp;
+    output(rt, &args, 0, now)
+}
+
+fn seal_minimum_balance(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let minimum_balance = rt.ext.state.minimum_balance;
+    output(rt, &args, 0, minimum_balance)
+}
+
+fn seal_tombstone_deposit(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    output(rt, &args, 0, 0 as Balance)
+}
+
+fn seal_rent_allowance(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    output(rt, &args, 0, Balance::MAX)
+}
+
+fn seal_set_rent_allowance(_rt: &mut Runtime, _args: RuntimeArgs) -> HostResult {
+    ok(None)
+}
+
+fn seal_block_number(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let block_number = rt.ext.state.block_number;
+    output(rt, &args, 0, block_number)
+}
+
+fn seal_deposit_event(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let topics_len: u32 = args.nth_checked(1)?;
+    let topics: Vec<Hash> = if topics_len == 0 {
+        Vec::new()
+    } else {
+        rt.decode(args.nth_checked(0)?, topics_len)?
+    };
+    let data = rt.read(args.nth_checked(2)?, args.nth_checked(3)?)?;
+    rt.ext.events.push(Event {
+        emitter: rt.address,
+        topics,
+        data,
+    });
+    ok(None)
+}
+
+fn seal_debug_message(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    let message = rt.read(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    rt.ext
+        .debug_messages
+        .push(String::from_utf8_lossy(&message).into_owned());
+    ok(Some(return_code::SUCCESS))
+}
+
+fn seal_println(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    seal_debug_message(rt, args)?;
+    ok(None)
+}
+
+fn hash(rt: &mut Runtime, args: RuntimeArgs, hasher: fn(&[u8]) -> Vec<u8>) -> HostResult {
+    let input = rt.read(args.nth_checked(0)?, args.nth_checked(1)?)?;
+    rt.write(args.nth_checked(2)?, &hasher(&input))?;
+    ok(None)
+}
+
+fn seal_hash_sha2_256(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    hash(rt, args, |input| sha2::Sha256::digest(input).to_vec())
+}
+
+fn seal_hash_keccak_256(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    hash(rt, args, |input| {
+        use tiny_keccak::Hasher as _;
+        let mut keccak = tiny_keccak::Keccak::v256();
+        let mut out = [0u8; 32];
+        keccak.update(input);
+        keccak.finalize(&mut out);
+        out.to_vec()
+    })
+}
+
+fn seal_hash_blake2_256(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    hash(rt, args, |input| blake2_256(input).to_vec())
+}
+
+fn seal_hash_blake2_128(rt: &mut Runtime, args: RuntimeArgs) -> HostResult {
+    hash(rt, args, |input| {
+        let mut out = [0u8; 16];
+        blake2_into(input, &mut out);
+        out.to_vec()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn contract(memory: &str) -> Vec<u8> {
+        wabt::wat2wasm(format!(
+            r#"(module
+                (import "env" "memory" (memory {}))
+                (func (export "deploy"))
+                (func (export "call"))
+            )"#,
+            memory
+        ))
+        .unwrap()
+    }
+
+    #[test]
+    fn limits_the_memory_of_contracts() {
+        assert_eq!(memory_pages(1, Some(2)), Ok(2));
+        assert_eq!(memory_pages(1, None), Ok(MAX_MEMORY_PAGES));
+        assert!(memory_pages(1, Some(MAX_MEMORY_PAGES as u32 + 1)).is_err());
+        assert!(memory_pages(3, Some(2)).is_err());
+
+        assert!(instrument(&contract("1 16")).is_ok());
+        assert!(instrument(&contract("1")).is_ok());
+        assert!(matches!(
+            instrument(&contract("1 17")),
+            Err(Error::InvalidCode(_))
+        ));
+        assert!(matches!(
+            instrument(&contract("17")),
+            Err(Error::InvalidCode(_))
+        ));
+    }
+
+    #[test]
+    fn rejects_code_the_pallet_rejects() {
+        let no_call = wabt::wat2wasm(r#"(module (func (export "deploy")))"#).unwrap();
+        assert!(matches!(instrument(&no_call), Err(Error::InvalidCode(_))));
+
+        let float = wabt::wat2wasm(
+            r#"(module
+                (func (export "deploy"))
+                (func (export "call") (drop (f32.const 1)))
+            )"#,
+        )
+        .unwrap();
+        let instrumented = instrument(&float).unwrap();
+        assert!(matches!(compile(&instrumented), Err(Error::InvalidCode(_))));
+    }
+
+    #[test]
+    fn resolves_the_host_functions_of_the_pallet() {
+        let resolver = Resolver::default();
+        let input = Signature::new(&[ValueType::I32, ValueType::I32][..], None);
+        assert!(resolver.resolve_func("seal0", "seal_input", &input).is_ok());
+        assert!(resolver
+            .resolve_func("seal0", "seal_input", &Signature::new(&[][..], None))
+            .is_err());
+
+        // unknown functions only trap when they are called
+        assert!(resolver
+            .resolve_func("seal0", "seal_unknown", &input)
+            .is_ok());
+        assert_eq!(
+            resolver.unsupported.borrow().as_slice(),
+            ["seal0::seal_unknown".to_string()]
+        );
+    }
+}
@@ -0,0 +1,571 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decodes SCALE encoded values with the types of the [`TypeRegistry`].
+
+use super::{encode_hex, ContractAbi, Field, Primitive, TypeDef, TypeRegistry};
+use anyhow::Result;
+use codec::{Compact, Decode};
+use serde_json::{Map, Value};
+use std::fmt;
+
+/// A value decoded with the types of the metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DecodedValue {
+    Bool(bool),
+    Char(char),
+    Str(String),
+    /// An integer in its decimal representation, or in hex for 256 bit integers.
+    Int(String),
+    /// A `Vec<u8>` or a `[u8; N]`.
+    Bytes(Vec<u8>),
+    Seq(Vec<DecodedValue>),
+    Tuple(Vec<DecodedValue>),
+    /// A struct with its name and fields, whose names are `None` for tuple structs.
+    Composite(String, Vec<(Option<String>, DecodedValue)>),
+    /// An enum variant with its name and fields.
+    Variant(String, Vec<(Option<String>, DecodedValue)>),
+}
+
+impl DecodedValue {
+    /// Returns the value as JSON: integers which do not fit a JSON number and bytes are
+    /// strings, unit variants their name and other variants an object with their name as the
+    /// single key.
+    pub fn to_json(&self) -> Value {
+        match self {
+            Self::Bool(value) => Value::Bool(*value),
+            Self::Char(value) => Value::String(value.to_string()),
+            Self::Str(value) => Value::String(value.clone()),
+            Self::Int(value) => value
+                .parse::<u64>()
+                .map(Value::from)
+                .or_else(|_| value.parse::<i64>().map(Value::from))
+                .unwrap_or_else(|_| Value::String(value.clone())),
+            Self::Bytes(bytes) => Value::String(encode_hex(bytes)),
+            Self::Seq(values) | Self::Tuple(values) => {
+                Value::Array(values.iter().map(Self::to_json).collect())
+            }
+            Self::Composite(_, fields) => fields_to_json(fields),
+            Self::Variant(name, fields) if fields.is_empty() => Value::String(name.clone()),
+            Self::Variant(name, fields) => {
+                let mut variant = Map::new();
+                variant.insert(name.clone(), fields_to_json(fields));
+                Value::Object(variant)
+            }
+        }
+    }
+}
+
+/// The width up to which [`DecodedValue::to_pretty_string`] keeps values on a single line.
+const LINE_WIDTH: usize = 80;
+
+impl DecodedValue {
+    /// Displays the value like [`fmt::Display`], but breaks the values which do not fit on a
+    /// line into a line per field or element, like `{:#?}` does.
+    pub fn to_pretty_string(&self) -> String {
+        let mut out = String::new();
+        self.write_pretty(&mut out, 0);
+        out
+    }
+
+    fn write_pretty(&self, out: &mut String, indent: usize) {
+        let inline = self.to_string();
+        let column = out.len() - out.rfind('\n').map_or(0, |newline| newline + 1);
+        let (open, close, items) = match self {
+            _ if column + inline.len() <= LINE_WIDTH => {
+                out.push_str(&inline);
+                return;
+            }
+            Self::Seq(values) if !values.is_empty() => (
+                "[".to_string(),
+                "]",
+                values.iter().map(|value| (None, value)).collect::<Vec<_>>(),
+            ),
+            Self::Tuple(values) if !values.is_empty() => (
+                "(".to_string(),
+                ")",
+                values.iter().map(|value| (None, value)).collect(),
+            ),
+            Self::Composite(name, fields) | Self::Variant(name, fields)
+                if !fields.is_empty() && fields.iter().all(|(name, _)| name.is_some()) =>
+            {
+                (
+                    format!("{} {{", name),
+                    "}",
+                    fields
+                        .iter()
+                        .map(|(name, value)| (name.as_deref(), value))
+                        .collect(),
+                )
+            }
+            Self::Composite(name, fields) | Self::Variant(name, fields) if !fields.is_empty() => (
+                format!("{}(", name),
+                ")",
+                fields.iter().map(|(_, value)| (None, value)).collect(),
+            ),
+            _ => {
+                out.push_str(&inline);
+                return;
+            }
+        };
+        out.push_str(&open);
+        out.push('\n');
+        for (name, value) in items {
+            out.push_str(&" ".repeat(indent + 4));
+            if let Some(name) = name {
+                out.push_str(name);
+                out.push_str(": ");
+            }
+            value.write_pretty(out, indent + 4);
+            out.push_str(",\n");
+        }
+        out.push_str(&" ".repeat(indent));
+        out.push_str(close);
+    }
+}
+
+fn fields_to_json(fields: &[(Option<String>, DecodedValue)]) -> Value {
+    match fields {
+        [] => Value::Null,
+        [(None, value)] => value.to_json(),
+        fields if fields.iter().all(|(name, _)| name.is_some()) => Value::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone().unwrap_or_default(), value.to_json()))
+                .collect(),
+        ),
+        fields => Value::Array(fields.iter().map(|(_, value)| value.to_json()).collect()),
+    }
+}
+
+/// Displays the value like a Rust value, e.g. `Err(LimitExceeded { limit: 100 })`.
+impl fmt::Display for DecodedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let list = |f: &mut fmt::Formatter<'_>, values: &[DecodedValue]| {
+            for (i, value) in values.iter().enumerate() {
+                if i > 0 {
+                    write!(f, ", ")?;
+                }
+                write!(f, "{}", value)?;
+            }
+            Ok(())
+        };
+        match self {
+            Self::Bool(value) => write!(f, "{}", value),
+            Self::Char(value) => write!(f, "{:?}", value),
+            Self::Str(value) => write!(f, "{:?}", value),
+            Self::Int(value) => write!(f, "{}", value),
+            Self::Bytes(bytes) => write!(f, "{}", encode_hex(bytes)),
+            Self::Seq(values) => {
+                write!(f, "[")?;
+                list(f, values)?;
+                write!(f, "]")
+            }
+            Self::Tuple(values) => {
+                write!(f, "(")?;
+                list(f, values)?;
+                write!(f, ")")
+            }
+            Self::Composite(name, fields) | Self::Variant(name, fields) => {
+                write!(f, "{}", name)?;
+                if fields.is_empty() {
+                    return Ok(());
+                }
+                if fields.iter().all(|(name, _)| name.is_some()) {
+                    write!(f, " {{ ")?;
+                    for (i, (name, value)) in fields.iter().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}: {}", name.as_deref().unwrap_or_default(), value)?;
+                    }
+                    write!(f, " }}")
+                } else {
+                    write!(f, "(")?;
+                    let values = fields
+                        .iter()
+                        .map(|(_, value)| value.clone())
+                        .collect::<Vec<_>>();
+                    list(f, &values)?;
+                    write!(f, ")")
+                }
+            }
+        }
+    }
+}
+
+/// An event of the contract, decoded with its metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DecodedEvent {
+    pub label: String,
+    pub fields: Vec<(String, DecodedValue)>,
+}
+
+#[cfg(feature = "extrinsics")]
+impl DecodedEvent {
+    /// Returns the fields of the event as a JSON object.
+    pub fn fields_json(&self) -> Value {
+        Value::Object(
+            self.fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_json()))
+                .collect(),
+        )
+    }
+}
+
+/// Displays the event like a struct, e.g. `Transfer { from: .., value: 100 }`.
+impl fmt::Display for DecodedEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let fields = self
+            .fields
+            .iter()
+            .map(|(name, value)| (Some(name.clone()), value.clone()))
+            .collect();
+        write!(f, "{}", DecodedValue::Variant(self.label.clone(), fields))
+    }
+}
+
+impl ContractAbi {
+    /// Decodes the data of an event emitted by the contract.
+    ///
+    /// ink! 3 encodes the events of a contract as the variants of an enum, whose index is the
+    /// position of the event in the metadata.
+    pub fn decode_event(&self, data: &[u8]) -> Result<DecodedEvent> {
+        let input = &mut &data[..];
+        let index = u8::decode(input)?;
+        let event = self
+            .events
+            .get(index as usize)
+            .ok_or_else(|| anyhow::anyhow!("The contract has no event with index {}", index))?;
+        let fields = event
+            .args
+            .iter()
+            .map(|arg| Ok((arg.label.clone(), self.types.decode(arg.ty.id, input)?)))
+            .collect::<Result<_>>()?;
+        if !input.is_empty() {
+            anyhow::bail!(
+                "{} bytes are left after decoding the event `{}`",
+                input.len(),
+                event.label
+            )
+        }
+        Ok(DecodedEvent {
+            label: event.label.clone(),
+            fields,
+        })
+    }
+}
+
+/// The value returned by a message, decoded with its return type.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ReturnValue {
+    /// The value the message returned.
+    Value(DecodedValue),
+    /// The `LangError` of a message which ink! could not dispatch, e.g. because its selector
+    /// is not known to the contract.
+    LangError(DecodedValue),
+}
+
+impl TypeRegistry {
+    /// Decodes `data` returned by a message with the return type `id`.
+    ///
+    /// Since ink! 4, the value of a message is wrapped in a `Result` whose error is a
+    /// `LangError`, which is unwrapped.
+    pub fn decode_return_value(&self, id: u32, data: &[u8]) -> Result<ReturnValue> {
+        let input = &mut &data[..];
+        let value = self.decode(id, input)?;
+        if !input.is_empty() {
+            anyhow::bail!(
+                "{} bytes are left after decoding the returned `{}`",
+                input.len(),
+                self.type_name(id)
+            )
+        }
+        if !self.is_lang_result(id)? {
+            return Ok(ReturnValue::Value(value));
+        }
+        Ok(match value {
+            DecodedValue::Variant(name, mut fields) if fields.len() == 1 => {
+                let (_, value) = fields.remove(0);
+                if name == "Ok" {
+                    ReturnValue::Value(value)
+                } else {
+                    ReturnValue::LangError(value)
+                }
+            }
+            value => ReturnValue::Value(value),
+        })
+    }
+
+    /// Returns `true` if the type `id` is a `Result` with a `LangError` as its error.
+    fn is_lang_result(&self, id: u32) -> Result<bool> {
+        let ty = self.resolve(id)?;
+        let variants = match &ty.def {
+            TypeDef::Variant(variants) if ty.path.last().map(String::as_str) == Some("Result") => {
+                variants
+            }
+            _ => return Ok(false),
+        };
+        let err = variants
+            .iter()
+            .find(|variant| variant.name == "Err")
+            .and_then(|variant| variant.fields.first());
+        Ok(match err {
+            Some(err) => self.resolve(err.ty)?.path.last().map(String::as_str) == Some("LangError"),
+            None => false,
+        })
+    }
+}
+
+impl TypeRegistry {
+    /// Decodes a value of the type `id` from the start of `input`, advancing it past the value.
+    pub fn decode(&self, id: u32, input: &mut &[u8]) -> Result<DecodedValue> {
+        let ty = self.resolve(id)?;
+        let is_byte = |id: u32| {
+            matches!(
+                self.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(Primitive::U8))
+            )
+        };
+        Ok(match &ty.def {
+            TypeDef::Primitive(primitive) => decode_primitive(*primitive, input)?,
+            TypeDef::Compact(_) => DecodedValue::Int(Compact::<u128>::decode(input)?.0.to_string()),
+            TypeDef::Sequence(ty) if is_byte(*ty) => DecodedValue::Bytes(Vec::<u8>::decode(input)?),
+            TypeDef::Sequence(ty) => {
+                let len = Compact::<u32>::decode(input)?.0;
+                DecodedValue::Seq(
+                    (0..len)
+                        .map(|_| self.decode(*ty, input))
+                        .collect::<Result<_>>()?,
+                )
+            }
+            TypeDef::Array { len, ty } if is_byte(*ty) => {
+                DecodedValue::Bytes(take(input, *len as usize)?.to_vec())
+            }
+            TypeDef::Array { len, ty } => DecodedValue::Seq(
+                (0..*len)
+                    .map(|_| self.decode(*ty, input))
+                    .collect::<Result<_>>()?,
+            ),
+            TypeDef::Tuple(ids) => DecodedValue::Tuple(
+                ids.iter()
+                    .map(|id| self.decode(*id, input))
+                    .collect::<Result<_>>()?,
+            ),
+            TypeDef::Composite(fields) => DecodedValue::Composite(
+                ty.path.last().cloned().unwrap_or_default(),
+                self.decode_fields(fields, input)?,
+            ),
+            TypeDef::Variant(variants) => {
+                let index = u8::decode(input)?;
+                let variant = variants
+                    .iter()
+                    .find(|variant| variant.index == index)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "`{}` has no variant with index {}",
+                            self.type_name(id),
+                            index
+                        )
+                    })?;
+                DecodedValue::Variant(
+                    variant.name.clone(),
+                    self.decode_fields(&variant.fields, input)?,
+                )
+            }
+            TypeDef::BitSequence => anyhow::bail!("Decoding bit sequences is not supported"),
+        })
+    }
+
+    fn decode_fields(
+        &self,
+        fields: &[Field],
+        input: &mut &[u8],
+    ) -> Result<Vec<(Option<String>, DecodedValue)>> {
+        fields
+            .iter()
+            .map(|field| Ok((field.name.clone(), self.decode(field.ty, input)?)))
+            .collect()
+    }
+}
+
+fn decode_primitive(primitive: Primitive, input: &mut &[u8]) -> Result<DecodedValue> {
+    let int = |value: &dyn ToString| DecodedValue::Int(value.to_string());
+    Ok(match primitive {
+        Primitive::Bool => DecodedValue::Bool(bool::decode(input)?),
+        Primitive::Char => {
+            let code = u32::decode(input)?;
+            DecodedValue::Char(
+                std::char::from_u32(code)
+                    .ok_or_else(|| anyhow::anyhow!("Invalid char {}", code))?,
+            )
+        }
+        Primitive::Str => DecodedValue::Str(String::decode(input)?),
+        Primitive::U8 => int(&u8::decode(input)?),
+        Primitive::U16 => int(&u16::decode(input)?),
+        Primitive::U32 => int(&u32::decode(input)?),
+        Primitive::U64 => int(&u64::decode(input)?),
+        Primitive::U128 => int(&u128::decode(input)?),
+        Primitive::I8 => int(&i8::decode(input)?),
+        Primitive::I16 => int(&i16::decode(input)?),
+        Primitive::I32 => int(&i32::decode(input)?),
+        Primitive::I64 => int(&i64::decode(input)?),
+        Primitive::I128 => int(&i128::decode(input)?),
+        Primitive::U256 | Primitive::I256 => {
+            // little endian, displayed as big endian hex
+            let mut bytes = take(input, 32)?.to_vec();
+            bytes.reverse();
+            DecodedValue::Int(encode_hex(&bytes))
+        }
+    })
+}
+
+/// Takes the next `len` bytes of `input`.
+fn take<'a>(input: &mut &'a [u8], len: usize) -> Result<&'a [u8]> {
+    if input.len() < len {
+        anyhow::bail!("Not enough data to decode {} bytes", len)
+    }
+    let (bytes, rest) = input.split_at(len);
+    *input = rest;
+    Ok(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{tests::metadata_v1, ContractAbi};
+    use codec::Encode;
+
+    #[test]
+    fn decodes_values_of_the_registry() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let types = &abi.types;
+
+        // `Result<(), Error>` with `Error::LimitExceeded { limit: 100 }`
+        let mut encoded = vec![1, 1];
+        encoded.extend(100u128.encode());
+        let input = &mut &encoded[..];
+        let value = types.decode(5, input).unwrap();
+        assert!(input.is_empty());
+        assert_eq!(value.to_string(), "Err(LimitExceeded { limit: 100 })");
+        assert_eq!(
+            value.to_json(),
+            serde_json::json!({ "Err": { "LimitExceeded": { "limit": 100 } } })
+        );
+
+        let value = types.decode(3, &mut &[1; 33][..]).unwrap();
+        assert_eq!(
+            value.to_string(),
+            format!("Some(AccountId(0x{}))", "01".repeat(32))
+        );
+        assert_eq!(
+            value.to_json(),
+            serde_json::json!({ "Some": format!("0x{}", "01".repeat(32)) })
+        );
+
+        assert!(types.decode(5, &mut &[2][..]).is_err());
+        assert!(types.decode(0, &mut &[0; 15][..]).is_err());
+    }
+
+    #[test]
+    fn unwraps_the_lang_error_of_return_values() {
+        let mut metadata = metadata_v1();
+        let types = metadata["V1"]["types"].as_array_mut().unwrap();
+        types.push(serde_json::json!({ "id": 8, "type": {
+            "def": { "variant": { "variants": [{ "index": 1, "name": "CouldNotReadInput" }] } },
+            "path": ["ink_primitives", "LangError"]
+        } }));
+        types.push(serde_json::json!({ "id": 9, "type": {
+            "def": { "variant": { "variants": [
+                { "fields": [{ "type": 5 }], "index": 0, "name": "Ok" },
+                { "fields": [{ "type": 8 }], "index": 1, "name": "Err" }
+            ] } },
+            "path": ["Result"]
+        } }));
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+
+        let mut data = vec![0, 1, 1];
+        data.extend(100u128.encode());
+        assert_eq!(
+            abi.types.decode_return_value(9, &data).unwrap(),
+            ReturnValue::Value(abi.types.decode(5, &mut &data[1..]).unwrap())
+        );
+        assert_eq!(
+            abi.types.decode_return_value(9, &[1, 1]).unwrap(),
+            ReturnValue::LangError(DecodedValue::Variant(
+                "CouldNotReadInput".to_string(),
+                Vec::new()
+            ))
+        );
+        // the returned value of contracts without `LangError` is kept
+        assert!(matches!(
+            abi.types.decode_return_value(5, &data[1..]).unwrap(),
+            ReturnValue::Value(DecodedValue::Variant(name, _)) if name == "Err"
+        ));
+        assert!(abi.types.decode_return_value(9, &[1, 1, 0]).is_err());
+    }
+
+    #[test]
+    fn pretty_prints_long_values() {
+        let int = |value: &str| DecodedValue::Int(value.to_string());
+        let short = DecodedValue::Variant("Ok".to_string(), vec![(None, int("5"))]);
+        assert_eq!(short.to_pretty_string(), "Ok(5)");
+
+        let balances = ["alice", "bob", "charlie"]
+            .iter()
+            .map(|name| (Some(name.to_string()), int("1000000000000000000")))
+            .collect();
+        let value = DecodedValue::Variant(
+            "Ok".to_string(),
+            vec![(
+                None,
+                DecodedValue::Composite("Balances".to_string(), balances),
+            )],
+        );
+        assert_eq!(
+            value.to_pretty_string(),
+            "\
+Ok(
+    Balances {
+        alice: 1000000000000000000,
+        bob: 1000000000000000000,
+        charlie: 1000000000000000000,
+    },
+)"
+        );
+    }
+
+    #[test]
+    fn decodes_events() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        // `Deposited { from: None, value: 5 }`
+        let mut data = vec![0, 0];
+        data.extend(5u128.encode());
+
+        let event = abi.decode_event(&data).unwrap();
+        assert_eq!(event.to_string(), "Deposited { from: None, value: 5 }");
+        #[cfg(feature = "extrinsics")]
+        assert_eq!(
+            event.fields_json(),
+            serde_json::json!({ "from": "None", "value": 5 })
+        );
+
+        data.push(0);
+        assert!(abi.decode_event(&data).is_err());
+        assert!(abi.decode_event(&[1]).is_err());
+    }
+}
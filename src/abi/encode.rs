@@ -0,0 +1,812 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encodes values given as JSON with the types of the [`TypeRegistry`].
+//!
+//! The JSON of a value is the same as returned by [`super::DecodedValue::to_json`]: integers
+//! are numbers or strings, bytes are `0x` prefixed hex strings, structs are objects or arrays
+//! and enum variants are their name or an object with their name as the single key.
+
+use super::{ContractAbi, Field, MessageSpec, Primitive, TypeDef, TypeRegistry};
+use anyhow::Result;
+use codec::{Compact, Encode};
+use serde_json::Value;
+use std::convert::{TryFrom, TryInto};
+
+impl ContractAbi {
+    /// Returns the constructor labeled `label`.
+    pub fn constructor(&self, label: &str) -> Result<&MessageSpec> {
+        find_spec("constructor", &self.constructors, label)
+    }
+
+    /// Returns the message labeled `label`, or `Trait::label` for trait messages.
+    pub fn message(&self, label: &str) -> Result<&MessageSpec> {
+        find_spec("message", &self.messages, label)
+    }
+
+    /// Encodes the data calling `spec` with `args`: its selector followed by the arguments.
+    ///
+    /// The errors name the invalid argument and show the layout of its type and an example.
+    pub fn encode_call(&self, spec: &MessageSpec, args: &[Value]) -> Result<Vec<u8>> {
+        if args.len() != spec.args.len() {
+            let params = spec
+                .args
+                .iter()
+                .map(|arg| format!("{}: {}", arg.label, self.types.type_name(arg.ty.id)))
+                .collect::<Vec<_>>();
+            let example = spec
+                .args
+                .iter()
+                .map(|arg| arg_literal(&self.types.example(arg.ty.id)))
+                .collect::<Vec<_>>();
+            anyhow::bail!(
+                "`{}` takes {} arguments `({})`, got {}, e.g. `--args {}`",
+                spec.label,
+                spec.args.len(),
+                params.join(", "),
+                args.len(),
+                example.join(" ")
+            )
+        }
+        let mut data = spec.selector.0.to_vec();
+        for (arg, value) in spec.args.iter().zip(args) {
+            self.types
+                .encode_to(arg.ty.id, value, &mut data)
+                .map_err(|err| {
+                    err.context(format!(
+                        "Invalid argument `{}` of `{}`\n  expected: {}\n  example:  {}",
+                        arg.label,
+                        spec.label,
+                        self.types.layout(arg.ty.id),
+                        arg_literal(&self.types.example(arg.ty.id))
+                    ))
+                })?;
+        }
+        Ok(data)
+    }
+}
+
+/// Returns `value` as given to `--args`, where strings need no quotes.
+fn arg_literal(value: &Value) -> String {
+    match value {
+        Value::String(string) => string.clone(),
+        value => value.to_string(),
+    }
+}
+
+impl ContractAbi {
+    /// Returns the arguments of `spec` in order from an object keyed by their labels, which
+    /// has to hold every argument and nothing else.
+    pub fn args_by_label(&self, spec: &MessageSpec, args: &Value) -> Result<Vec<Value>> {
+        let args = args.as_object().ok_or_else(|| {
+            anyhow::anyhow!(
+                "Expected the arguments of `{}` as an object keyed by their names",
+                spec.label
+            )
+        })?;
+        if let Some(unknown) = args
+            .keys()
+            .find(|label| !spec.args.iter().any(|arg| &arg.label == *label))
+        {
+            anyhow::bail!("`{}` has no argument `{}`", spec.label, unknown)
+        }
+        spec.args
+            .iter()
+            .map(|arg| {
+                args.get(&arg.label).cloned().ok_or_else(|| {
+                    anyhow::anyhow!(
+                        "Missing the argument `{}: {}` of `{}`",
+                        arg.label,
+                        self.types.type_name(arg.ty.id),
+                        spec.label
+                    )
+                })
+            })
+            .collect()
+    }
+}
+
+fn find_spec<'a>(kind: &str, specs: &'a [MessageSpec], label: &str) -> Result<&'a MessageSpec> {
+    let unprefixed = |spec: &&MessageSpec| spec.label.rsplit("::").next() == Some(label);
+    let matches = specs
+        .iter()
+        .filter(|spec| spec.label == label)
+        .collect::<Vec<_>>();
+    let matches = if matches.is_empty() {
+        specs.iter().filter(unprefixed).collect()
+    } else {
+        matches
+    };
+    match matches[..] {
+        [spec] => Ok(spec),
+        [] => {
+            let labels = specs
+                .iter()
+                .map(|spec| spec.label.as_str())
+                .collect::<Vec<_>>();
+            anyhow::bail!(
+                "No {} `{}`, expected one of {}",
+                kind,
+                label,
+                labels.join(", ")
+            )
+        }
+        _ => anyhow::bail!(
+            "The {} `{}` is ambiguous, prefix it with its trait",
+            kind,
+            label
+        ),
+    }
+}
+
+/// Parses an argument given on the command line: JSON, or a string if it is not valid JSON,
+/// so that e.g. `100`, `true`, `5Grw..` and `{"Some": 1}` need no quoting.
+pub(crate) fn parse_arg_value(arg: &str) -> Value {
+    serde_json::from_str(arg).unwrap_or_else(|_| Value::String(arg.to_string()))
+}
+
+/// The account of the examples of account ids.
+const EXAMPLE_ACCOUNT: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+impl TypeRegistry {
+    /// Returns the layout of the type `id`: the types of the fields of structs, e.g.
+    /// `Order { amount: u128, to: AccountId }`, and the variants of enums, e.g.
+    /// `Side = Buy | Sell`. Other types are only named.
+    pub fn layout(&self, id: u32) -> String {
+        let fields = |fields: &[Field]| {
+            if fields.iter().all(|field| field.name.is_some()) {
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{}: {}",
+                            field.name.as_deref().unwrap_or_default(),
+                            self.type_name(field.ty)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!(" {{ {} }}", fields.join(", "))
+            } else {
+                let fields = fields
+                    .iter()
+                    .map(|field| self.type_name(field.ty))
+                    .collect::<Vec<_>>();
+                format!("({})", fields.join(", "))
+            }
+        };
+        let name = self.type_name(id);
+        match self.resolve(id).map(|ty| (&ty.path, &ty.def)) {
+            Ok((_, TypeDef::Composite(composite))) if !composite.is_empty() => {
+                format!("{}{}", name, fields(composite))
+            }
+            Ok((path, TypeDef::Variant(variants))) if !path.is_empty() && path != &["Option"] => {
+                let variants = variants
+                    .iter()
+                    .map(|variant| match &variant.fields[..] {
+                        [] => variant.name.clone(),
+                        variant_fields => format!("{}{}", variant.name, fields(variant_fields)),
+                    })
+                    .collect::<Vec<_>>();
+                format!("{} = {}", name, variants.join(" | "))
+            }
+            _ => name,
+        }
+    }
+
+    /// Returns an example value of the type `id`, as JSON.
+    pub fn example(&self, id: u32) -> Value {
+        self.example_at(id, 0)
+    }
+
+    fn example_at(&self, id: u32, depth: usize) -> Value {
+        let ty = match self.resolve(id) {
+            // recursive types end with a `null`
+            Ok(ty) if depth < 8 => ty,
+            _ => return Value::Null,
+        };
+        let is_byte = |id: u32| {
+            matches!(
+                self.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(Primitive::U8))
+            )
+        };
+        match &ty.def {
+            TypeDef::Primitive(Primitive::Bool) => Value::Bool(false),
+            TypeDef::Primitive(Primitive::Char) => Value::String("a".to_string()),
+            TypeDef::Primitive(Primitive::Str) => Value::String("text".to_string()),
+            TypeDef::Primitive(Primitive::U256) | TypeDef::Primitive(Primitive::I256) => {
+                Value::String("0x01".to_string())
+            }
+            TypeDef::Primitive(_) | TypeDef::Compact(_) => Value::from(1),
+            TypeDef::Sequence(ty) if is_byte(*ty) => Value::String("0x0102".to_string()),
+            TypeDef::Sequence(ty) => Value::Array(vec![self.example_at(*ty, depth + 1)]),
+            // byte arrays of 32 bytes are usually account ids
+            TypeDef::Array { len: 32, ty } if is_byte(*ty) => {
+                Value::String(EXAMPLE_ACCOUNT.to_string())
+            }
+            TypeDef::Array { len, ty } if is_byte(*ty) => {
+                Value::String(format!("0x{}", "00".repeat(*len as usize)))
+            }
+            TypeDef::Array { len, ty } => {
+                Value::Array(vec![self.example_at(*ty, depth + 1); *len as usize])
+            }
+            TypeDef::Tuple(ids) if ids.is_empty() => Value::Null,
+            TypeDef::Tuple(ids) => Value::Array(
+                ids.iter()
+                    .map(|id| self.example_at(*id, depth + 1))
+                    .collect(),
+            ),
+            TypeDef::Composite(fields) => self.fields_example(fields, depth),
+            TypeDef::Variant(variants) => {
+                let variant = match variants.first() {
+                    Some(variant) => variant,
+                    None => return Value::Null,
+                };
+                // any value of an `Option` is its `Some` value
+                if ty.path == ["Option"] {
+                    return match variants.iter().find(|variant| variant.name == "Some") {
+                        Some(some) => self.fields_example(&some.fields, depth),
+                        None => Value::Null,
+                    };
+                }
+                match self.fields_example(&variant.fields, depth) {
+                    Value::Null => Value::String(variant.name.clone()),
+                    fields => {
+                        let mut object = serde_json::Map::new();
+                        object.insert(variant.name.clone(), fields);
+                        Value::Object(object)
+                    }
+                }
+            }
+            TypeDef::BitSequence => Value::Null,
+        }
+    }
+
+    /// Returns an example of the fields of a struct or variant, as [`Self::encode_fields`]
+    /// expects them.
+    fn fields_example(&self, fields: &[Field], depth: usize) -> Value {
+        match fields {
+            [] => Value::Null,
+            [field] if field.name.is_none() => self.example_at(field.ty, depth + 1),
+            fields if fields.iter().all(|field| field.name.is_some()) => Value::Object(
+                fields
+                    .iter()
+                    .map(|field| {
+                        (
+                            field.name.clone().unwrap_or_default(),
+                            self.example_at(field.ty, depth + 1),
+                        )
+                    })
+                    .collect(),
+            ),
+            fields => Value::Array(
+                fields
+                    .iter()
+                    .map(|field| self.example_at(field.ty, depth + 1))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Encodes `value` as a value of the type `id`.
+    #[cfg(any(test, feature = "extrinsics"))]
+    pub fn encode(&self, id: u32, value: &Value) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        self.encode_to(id, value, &mut output)?;
+        Ok(output)
+    }
+
+    fn encode_to(&self, id: u32, value: &Value, output: &mut Vec<u8>) -> Result<()> {
+        let ty = self.resolve(id)?;
+        let is_byte = |id: u32| {
+            matches!(
+                self.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(Primitive::U8))
+            )
+        };
+        let expected = || {
+            anyhow::anyhow!(
+                "Expected a value of type `{}`, got `{}`",
+                self.type_name(id),
+                value
+            )
+        };
+        match &ty.def {
+            TypeDef::Primitive(primitive) => encode_primitive(*primitive, value, output)
+                .map_err(|err| err.context(expected()))?,
+            TypeDef::Compact(_) => {
+                let int = parse_int(value).ok_or_else(expected)?;
+                let int = u128::try_from(int).map_err(|_| expected())?;
+                Compact(int).encode_to(output);
+            }
+            TypeDef::Sequence(ty) if is_byte(*ty) && value.is_string() => {
+                let bytes = parse_bytes(value).ok_or_else(expected)?;
+                bytes.encode_to(output);
+            }
+            TypeDef::Sequence(ty) => {
+                let values = value.as_array().ok_or_else(expected)?;
+                Compact(values.len() as u32).encode_to(output);
+                for value in values {
+                    self.encode_to(*ty, value, output)?;
+                }
+            }
+            TypeDef::Array { len, ty } if is_byte(*ty) && value.is_string() => {
+                let bytes = match parse_bytes(value) {
+                    Some(bytes) => bytes,
+                    // accounts are usually given as SS58 addresses
+                    None if *len == 32 => value
+                        .as_str()
+                        .and_then(decode_ss58)
+                        .ok_or_else(expected)?
+                        .to_vec(),
+                    None => return Err(expected()),
+                };
+                if bytes.len() != *len as usize {
+                    return Err(expected().context(format!("Expected {} bytes", len)));
+                }
+                output.extend(bytes);
+            }
+            TypeDef::Array { len, ty } => {
+                let values = value
+                    .as_array()
+                    .filter(|values| values.len() == *len as usize)
+                    .ok_or_else(expected)?;
+                for value in values {
+                    self.encode_to(*ty, value, output)?;
+                }
+            }
+            TypeDef::Tuple(ids) if ids.is_empty() && value.is_null() => (),
+            TypeDef::Tuple(ids) => {
+                let values = value
+                    .as_array()
+                    .filter(|values| values.len() == ids.len())
+                    .ok_or_else(expected)?;
+                for (id, value) in ids.iter().zip(values) {
+                    self.encode_to(*id, value, output)?;
+                }
+            }
+            TypeDef::Composite(fields) => {
+                let ids = fields.iter().map(|field| field.ty).collect::<Vec<_>>();
+                let names = fields
+                    .iter()
+                    .map(|field| field.name.as_deref())
+                    .collect::<Vec<_>>();
+                self.encode_fields(&ids, &names, value, output)
+                    .map_err(|err| err.context(expected()))?;
+            }
+            TypeDef::Variant(variants) => {
+                let variant = match value {
+                    Value::String(name) => Some((name.as_str(), &Value::Null)),
+                    Value::Object(object) if object.len() == 1 => object
+                        .iter()
+                        .next()
+                        .map(|(name, fields)| (name.as_str(), fields)),
+                    _ => None,
+                };
+                let (name, fields) = match variant {
+                    Some((name, fields)) if ty.path != ["Option"] => (name, fields),
+                    Some(("None", Value::Null)) => ("None", &Value::Null),
+                    Some(("Some", fields)) => ("Some", fields),
+                    // any other value of an `Option` is `Some` value
+                    _ if ty.path == ["Option"] => match value {
+                        Value::Null => ("None", &Value::Null),
+                        value => ("Some", value),
+                    },
+                    _ => return Err(expected()),
+                };
+                let variant = variants
+                    .iter()
+                    .find(|variant| variant.name == name)
+                    .ok_or_else(|| {
+                        let names = variants
+                            .iter()
+                            .map(|variant| variant.name.as_str())
+                            .collect::<Vec<_>>();
+                        anyhow::anyhow!(
+                            "`{}` has no variant `{}`, expected one of {}",
+                            self.type_name(id),
+                            name,
+                            names.join(", ")
+                        )
+                    })?;
+                output.push(variant.index);
+                let ids = variant
+                    .fields
+                    .iter()
+                    .map(|field| field.ty)
+                    .collect::<Vec<_>>();
+                let names = variant
+                    .fields
+                    .iter()
+                    .map(|field| field.name.as_deref())
+                    .collect::<Vec<_>>();
+                self.encode_fields(&ids, &names, fields, output)?;
+            }
+            TypeDef::BitSequence => anyhow::bail!("Encoding bit sequences is not supported"),
+        }
+        Ok(())
+    }
+
+    /// Encodes the fields of a struct or variant: an object with the names of named fields,
+    /// an array, or the value of the single field.
+    fn encode_fields(
+        &self,
+        ids: &[u32],
+        names: &[Option<&str>],
+        value: &Value,
+        output: &mut Vec<u8>,
+    ) -> Result<()> {
+        match (ids, value) {
+            ([], Value::Null) => Ok(()),
+            ([], Value::Array(values)) if values.is_empty() => Ok(()),
+            (ids, Value::Object(object)) if names.iter().all(Option::is_some) => {
+                if let Some(unknown) = object
+                    .keys()
+                    .find(|key| !names.contains(&Some(key.as_str())))
+                {
+                    anyhow::bail!("Unknown field `{}`", unknown)
+                }
+                for (id, name) in ids.iter().zip(names) {
+                    let name = name.unwrap_or_default();
+                    let value = object
+                        .get(name)
+                        .ok_or_else(|| anyhow::anyhow!("Missing field `{}`", name))?;
+                    self.encode_to(*id, value, output)
+                        .map_err(|err| err.context(format!("Invalid field `{}`", name)))?;
+                }
+                Ok(())
+            }
+            ([id], value) => self.encode_to(*id, value, output),
+            (ids, Value::Array(values)) if values.len() == ids.len() => {
+                for (id, value) in ids.iter().zip(values) {
+                    self.encode_to(*id, value, output)?;
+                }
+                Ok(())
+            }
+            (ids, _) => anyhow::bail!("Expected {} fields", ids.len()),
+        }
+    }
+}
+
+fn encode_primitive(primitive: Primitive, value: &Value, output: &mut Vec<u8>) -> Result<()> {
+    let int = || parse_int(value).ok_or_else(|| anyhow::anyhow!("Not an integer"));
+    macro_rules! int {
+        ($ty:ty) => {
+            <$ty>::try_from(int()?)
+                .map_err(|_| {
+                    anyhow::anyhow!(
+                        "{} is out of the range of `{}`, {} to {}",
+                        value,
+                        stringify!($ty),
+                        <$ty>::MIN,
+                        <$ty>::MAX
+                    )
+                })?
+                .encode_to(output)
+        };
+    }
+    match primitive {
+        Primitive::Bool => match value {
+            Value::Bool(value) => value.encode_to(output),
+            Value::String(value) if value == "true" || value == "false" => {
+                (value == "true").encode_to(output)
+            }
+            _ => anyhow::bail!("Not a bool"),
+        },
+        Primitive::Char => {
+            let mut chars = value.as_str().unwrap_or_default().chars();
+            match (chars.next(), chars.next()) {
+                (Some(char), None) => (char as u32).encode_to(output),
+                _ => anyhow::bail!("Not a single character"),
+            }
+        }
+        Primitive::Str => value
+            .as_str()
+            .ok_or_else(|| anyhow::anyhow!("Not a string"))?
+            .encode_to(output),
+        Primitive::U8 => int!(u8),
+        Primitive::U16 => int!(u16),
+        Primitive::U32 => int!(u32),
+        Primitive::U64 => int!(u64),
+        Primitive::U128 => int!(u128),
+        Primitive::I8 => int!(i8),
+        Primitive::I16 => int!(i16),
+        Primitive::I32 => int!(i32),
+        Primitive::I64 => int!(i64),
+        Primitive::I128 => int!(i128),
+        Primitive::U256 | Primitive::I256 => {
+            // given as big endian hex, as decoded
+            let mut bytes = parse_bytes(value)
+                .filter(|bytes| bytes.len() <= 32)
+                .ok_or_else(|| anyhow::anyhow!("Expected up to 32 bytes of hex"))?;
+            bytes.reverse();
+            bytes.resize(32, 0);
+            output.extend(bytes);
+        }
+    }
+    Ok(())
+}
+
+/// Parses an integer given as a number or as a decimal or `0x` prefixed hex string.
+///
+/// The value is returned as `i128` if it fits, larger values only fit a `u128`.
+fn parse_int(value: &Value) -> Option<Int> {
+    match value {
+        Value::Number(number) => number
+            .as_i64()
+            .map(|int| Int::Signed(int.into()))
+            .or_else(|| number.as_u64().map(|int| Int::Unsigned(int.into()))),
+        Value::String(int) => {
+            let int = int.replace('_', "");
+            match int.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16).ok().map(Int::Unsigned),
+                None => int
+                    .parse::<i128>()
+                    .map(Int::Signed)
+                    .or_else(|_| int.parse::<u128>().map(Int::Unsigned))
+                    .ok(),
+            }
+        }
+        _ => None,
+    }
+}
+
+/// An integer of any width.
+#[derive(Clone, Copy)]
+enum Int {
+    Signed(i128),
+    Unsigned(u128),
+}
+
+macro_rules! try_from_int {
+    ($($ty:ty),*) => {$(
+        impl TryFrom<Int> for $ty {
+            type Error = ();
+
+            fn try_from(int: Int) -> Result<Self, ()> {
+                match int {
+                    Int::Signed(int) => <$ty>::try_from(int).map_err(|_| ()),
+                    Int::Unsigned(int) => <$ty>::try_from(int).map_err(|_| ()),
+                }
+            }
+        }
+    )*};
+}
+
+try_from_int!(u8, u16, u32, u64, u128, i8, i16, i32, i64, i128);
+
+/// Parses bytes given as a `0x` prefixed hex string.
+fn parse_bytes(value: &Value) -> Option<Vec<u8>> {
+    let hex = value.as_str()?;
+    if !hex.starts_with("0x") {
+        return None;
+    }
+    super::decode_hex(hex).ok()
+}
+
+/// Returns the public key of an SS58 address, e.g. `5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY`.
+pub(crate) fn decode_ss58(address: &str) -> Option<[u8; 32]> {
+    use blake2::{Blake2b, Digest};
+
+    let data = decode_base58(address)?;
+    // the network prefix takes one byte below 64, two bytes otherwise
+    let prefix_len = match data.first()? {
+        0..=63 => 1,
+        64..=127 => 2,
+        _ => return None,
+    };
+    if data.len() != prefix_len + 32 + 2 {
+        return None;
+    }
+    let (payload, checksum) = data.split_at(prefix_len + 32);
+    let hash = Blake2b::new().chain(b"SS58PRE").chain(payload).finalize();
+    if checksum != &hash[..2] {
+        return None;
+    }
+    payload[prefix_len..].try_into().ok()
+}
+
+/// Returns the SS58 address of `public` with the generic substrate prefix 42.
+pub(crate) fn encode_ss58(public: &[u8; 32]) -> String {
+    use blake2::{Blake2b, Digest};
+
+    let mut data = vec![42];
+    data.extend_from_slice(public);
+    let hash = Blake2b::new().chain(b"SS58PRE").chain(&data).finalize();
+    data.extend_from_slice(&hash[..2]);
+    encode_base58(&data)
+}
+
+fn decode_base58(input: &str) -> Option<Vec<u8>> {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // big endian digits in base 256
+    let mut bytes: Vec<u8> = Vec::new();
+    for char in input.bytes() {
+        let mut carry = ALPHABET.iter().position(|digit| *digit == char)? as u32;
+        for byte in bytes.iter_mut().rev() {
+            carry += *byte as u32 * 58;
+            *byte = carry as u8;
+            carry >>= 8;
+        }
+        while carry > 0 {
+            bytes.insert(0, carry as u8);
+            carry >>= 8;
+        }
+    }
+    // leading `1`s are leading zero bytes
+    let zeros = input.bytes().take_while(|char| *char == b'1').count();
+    let mut decoded = vec![0; zeros];
+    decoded.extend(bytes);
+    Some(decoded)
+}
+
+fn encode_base58(input: &[u8]) -> String {
+    const ALPHABET: &[u8] = b"123456789ABCDEFGHJKLMNPQRSTUVWXYZabcdefghijkmnopqrstuvwxyz";
+    // big endian digits in base 58
+    let mut digits: Vec<u8> = Vec::new();
+    for byte in input {
+        let mut carry = *byte as u32;
+        for digit in digits.iter_mut().rev() {
+            carry += *digit as u32 * 256;
+            *digit = (carry % 58) as u8;
+            carry /= 58;
+        }
+        while carry > 0 {
+            digits.insert(0, (carry % 58) as u8);
+            carry /= 58;
+        }
+    }
+    // leading zero bytes are leading `1`s
+    let zeros = input.iter().take_while(|byte| **byte == 0).count();
+    std::iter::repeat_n('1', zeros)
+        .chain(digits.iter().map(|digit| ALPHABET[*digit as usize] as char))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{tests::metadata_v1, ContractAbi};
+    use serde_json::json;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+    #[test]
+    fn encodes_json_values() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let types = &abi.types;
+
+        assert_eq!(types.encode(0, &json!(100)).unwrap(), 100u128.encode());
+        assert_eq!(types.encode(0, &json!("1_000")).unwrap(), 1000u128.encode());
+        assert!(types.encode(0, &json!(-1)).is_err());
+
+        // an account as SS58 address and as hex
+        let alice = crate::abi::decode_hex(ALICE_HEX).unwrap();
+        assert_eq!(types.encode(1, &json!(ALICE)).unwrap(), alice);
+        assert_eq!(types.encode(1, &json!(ALICE_HEX)).unwrap(), alice);
+        assert!(types
+            .encode(
+                1,
+                &json!("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQX")
+            )
+            .is_err());
+
+        // `Option<AccountId>`
+        assert_eq!(types.encode(3, &json!(null)).unwrap(), vec![0]);
+        assert_eq!(types.encode(3, &json!("None")).unwrap(), vec![0]);
+        let mut some = vec![1];
+        some.extend(&alice);
+        assert_eq!(types.encode(3, &json!(ALICE)).unwrap(), some);
+        assert_eq!(types.encode(3, &json!({ "Some": ALICE })).unwrap(), some);
+
+        // `Result<(), Error>`, the inverse of decoding
+        let err = json!({ "Err": { "LimitExceeded": { "limit": 100 } } });
+        let encoded = types.encode(5, &err).unwrap();
+        assert_eq!(types.decode(5, &mut &encoded[..]).unwrap().to_json(), err);
+        assert!(types
+            .encode(5, &json!({ "Err": { "LimitExceeded": { "max": 100 } } }))
+            .is_err());
+        assert!(types.encode(7, &json!("Unknown")).is_err());
+    }
+
+    #[test]
+    fn encodes_calls_by_label() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+
+        let balance_of = abi.message("balance_of").unwrap();
+        assert_eq!(balance_of.label, "Ledger::balance_of");
+        let mut data = balance_of.selector.0.to_vec();
+        data.extend(crate::abi::decode_hex(ALICE_HEX).unwrap());
+        assert_eq!(
+            abi.encode_call(balance_of, &[parse_arg_value(ALICE)])
+                .unwrap(),
+            data
+        );
+
+        let new = abi.constructor("new").unwrap();
+        assert_eq!(
+            abi.encode_call(new, &[parse_arg_value("1000")]).unwrap()[4..],
+            1000u128.encode()[..]
+        );
+        assert_eq!(
+            abi.encode_call(new, &[]).unwrap_err().to_string(),
+            "`new` takes 1 arguments `(limit: u128)`, got 0, e.g. `--args 1`"
+        );
+        assert!(abi.message("transfer").is_err());
+    }
+
+    #[test]
+    fn explains_invalid_arguments() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let withdraw = abi.message("withdraw").unwrap();
+
+        let err = abi
+            .encode_call(withdraw, &[parse_arg_value("-5")])
+            .unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid argument `value` of `withdraw`\n  expected: u128\n  example:  1"
+        );
+        assert_eq!(
+            err.root_cause().to_string(),
+            "-5 is out of the range of `u128`, 0 to 340282366920938463463374607431768211455"
+        );
+
+        assert_eq!(
+            abi.types.layout(7),
+            "Error = InsufficientBalance | LimitExceeded { limit: u128 }"
+        );
+        assert_eq!(abi.types.example(7), json!("InsufficientBalance"));
+        assert_eq!(abi.types.example(3), json!(ALICE));
+        assert_eq!(abi.types.example(5), json!("Ok"));
+    }
+
+    #[test]
+    fn orders_args_by_label() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let new = abi.constructor("new").unwrap();
+
+        assert_eq!(
+            abi.args_by_label(new, &json!({ "limit": 1000 })).unwrap(),
+            vec![json!(1000)]
+        );
+        assert_eq!(
+            abi.args_by_label(new, &json!({})).unwrap_err().to_string(),
+            "Missing the argument `limit: u128` of `new`"
+        );
+        assert_eq!(
+            abi.args_by_label(new, &json!({ "limit": 1, "max": 2 }))
+                .unwrap_err()
+                .to_string(),
+            "`new` has no argument `max`"
+        );
+    }
+
+    #[test]
+    fn decodes_ss58_addresses() {
+        assert_eq!(
+            decode_ss58(ALICE).map(|key| key.to_vec()),
+            crate::abi::decode_hex(ALICE_HEX).ok()
+        );
+        assert_eq!(decode_ss58("not an address"), None);
+    }
+
+    #[test]
+    fn encodes_ss58_addresses() {
+        assert_eq!(encode_ss58(&decode_ss58(ALICE).unwrap()), ALICE);
+    }
+}
@@ -0,0 +1,391 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reads the storage layout of a contract from its metadata, and derives the keys of the
+//! child trie its values are stored under.
+
+use super::{decode_hex, type_id, MetadataVersion, TypeDef, TypeRegistry};
+use anyhow::{Context, Result};
+#[cfg(feature = "extrinsics")]
+use blake2::digest::{Update as _, VariableOutput as _};
+use serde_json::Value;
+
+/// How the keys of the storage layout map to the keys of the child trie of the contract.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum KeyScheme {
+    /// ink! 3 stores values under 32 byte keys, which the contracts pallet hashes with
+    /// `blake2_256`.
+    #[default]
+    Hashed,
+    /// ink! 4 stores values under keys of any length, which the contracts pallet prefixes
+    /// with their `blake2_128` hash.
+    Concat,
+}
+
+/// The values stored by a contract and the keys they are stored under.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct StorageLayout {
+    pub scheme: KeyScheme,
+    pub cells: Vec<StorageCell>,
+    pub mappings: Vec<StorageMapping>,
+    /// The paths of the values whose layout is not read, e.g. of the ink! 3 `HashMap`.
+    pub unsupported: Vec<String>,
+}
+
+/// A value stored under a single key.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageCell {
+    /// The path of the value in the storage struct, e.g. `owner` or `config.limit`, empty for
+    /// the storage struct itself.
+    pub path: String,
+    /// The key the contract stores the value under.
+    pub key: Vec<u8>,
+    /// The fields packed into the value in their order, with their paths and types.
+    pub fields: Vec<(String, u32)>,
+    /// `false` if fields whose layout is not read follow the `fields`.
+    pub complete: bool,
+}
+
+/// A `Mapping`, whose entries are stored under keys derived from their mapping keys.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StorageMapping {
+    pub path: String,
+    /// The key the keys of the entries are derived from.
+    pub prefix: Vec<u8>,
+    /// The type of the mapping keys, `None` if it was erased.
+    pub key_ty: Option<u32>,
+    pub value_ty: u32,
+}
+
+#[cfg(feature = "extrinsics")]
+impl StorageLayout {
+    /// Returns the key of the child trie the contract stores the value of `key` under.
+    pub fn trie_key(&self, key: &[u8]) -> Vec<u8> {
+        match self.scheme {
+            KeyScheme::Hashed => blake2(32, key),
+            KeyScheme::Concat => {
+                let mut trie_key = blake2(16, key);
+                trie_key.extend(key);
+                trie_key
+            }
+        }
+    }
+
+    /// Returns the key a value is stored under from the key of the child trie, if the key
+    /// is not hashed away.
+    pub fn storage_key(&self, trie_key: &[u8]) -> Option<Vec<u8>> {
+        match self.scheme {
+            KeyScheme::Hashed => None,
+            KeyScheme::Concat if trie_key.len() > 16 => {
+                let (hash, key) = trie_key.split_at(16);
+                Some(key.to_vec()).filter(|key| blake2(16, key) == hash)
+            }
+            KeyScheme::Concat => None,
+        }
+    }
+
+    /// Returns the key the entry of the SCALE encoded `mapping_key` of `mapping` is stored
+    /// under.
+    pub fn entry_key(&self, mapping: &StorageMapping, mapping_key: &[u8]) -> Vec<u8> {
+        let mut key = mapping.prefix.clone();
+        key.extend(mapping_key);
+        match self.scheme {
+            KeyScheme::Hashed => blake2(32, &key),
+            KeyScheme::Concat => key,
+        }
+    }
+
+    /// Returns the cell of the value at `path`, or of the value `path` is packed into.
+    pub fn cell(&self, path: &str) -> Option<&StorageCell> {
+        self.cells
+            .iter()
+            .find(|cell| cell.path == path)
+            .or_else(|| {
+                self.cells
+                    .iter()
+                    .find(|cell| cell.fields.iter().any(|(field, _)| is_within(field, path)))
+            })
+    }
+
+    /// Returns the mapping at `path`.
+    pub fn mapping(&self, path: &str) -> Option<&StorageMapping> {
+        self.mappings.iter().find(|mapping| mapping.path == path)
+    }
+}
+
+/// Returns `true` if `field` is the value at `path` or one of its fields.
+#[cfg(feature = "extrinsics")]
+pub fn is_within(field: &str, path: &str) -> bool {
+    path.is_empty()
+        || field == path
+        || (field.starts_with(path) && field[path.len()..].starts_with('.'))
+}
+
+/// Reads the storage layout from the `storage` section of the metadata.
+///
+/// Covers the `cell` and `struct` layouts of ink! 3 and the `root`, `leaf` and `struct`
+/// layouts of ink! 4. The values of other layouts are listed as unsupported.
+pub(super) fn parse(
+    storage: &Value,
+    version: MetadataVersion,
+    types: &TypeRegistry,
+) -> Result<StorageLayout> {
+    let mut parser = Parser {
+        version,
+        types,
+        layout: StorageLayout::default(),
+        root: None,
+    };
+    parser.walk("", storage)?;
+    Ok(parser.layout)
+}
+
+struct Parser<'a> {
+    version: MetadataVersion,
+    types: &'a TypeRegistry,
+    layout: StorageLayout,
+    /// The index of the cell the leaves of ink! 4 are packed into.
+    root: Option<usize>,
+}
+
+impl Parser<'_> {
+    fn walk(&mut self, path: &str, layout: &Value) -> Result<()> {
+        let (kind, inner) = match layout.as_object().and_then(|layout| layout.iter().next()) {
+            Some((kind, inner)) => (kind.as_str(), inner),
+            None => anyhow::bail!("Invalid storage layout {}", layout),
+        };
+        let key = |field: &str| -> Result<Vec<u8>> {
+            let key = inner
+                .get(field)
+                .and_then(Value::as_str)
+                .with_context(|| format!("`{}` of `{}` is missing", field, path))?;
+            decode_hex(key)
+        };
+        let ty = |value: Option<&Value>| -> Result<u32> {
+            let value = value.with_context(|| format!("`ty` of `{}` is missing", path))?;
+            type_id(value, self.version)
+        };
+        match kind {
+            "cell" => {
+                let key = key("key")?;
+                let ty = ty(inner.get("ty"))?;
+                if !self.push_mapping(path, &key, ty) {
+                    self.layout.cells.push(StorageCell {
+                        path: path.to_string(),
+                        key,
+                        fields: vec![(path.to_string(), ty)],
+                        complete: true,
+                    });
+                }
+            }
+            "struct" => {
+                let fields = inner
+                    .get("fields")
+                    .and_then(Value::as_array)
+                    .cloned()
+                    .unwrap_or_default();
+                for (i, field) in fields.iter().enumerate() {
+                    let name = field
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .map(ToString::to_string)
+                        .unwrap_or_else(|| i.to_string());
+                    let field_path = if path.is_empty() {
+                        name
+                    } else {
+                        format!("{}.{}", path, name)
+                    };
+                    self.walk(&field_path, field.get("layout").unwrap_or(&Value::Null))?;
+                }
+            }
+            "root" => {
+                // the layouts of ink! 4 are the ones with roots
+                self.layout.scheme = KeyScheme::Concat;
+                let key = key("root_key")?;
+                // since ink! 4.2 the type of the root is known, which tells mappings apart
+                if let Some(ty) = inner.get("ty").map(|ty| type_id(ty, self.version)) {
+                    if self.push_mapping(path, &key, ty?) {
+                        return Ok(());
+                    }
+                }
+                self.layout.cells.push(StorageCell {
+                    path: path.to_string(),
+                    key,
+                    fields: Vec::new(),
+                    complete: true,
+                });
+                let parent = self.root.replace(self.layout.cells.len() - 1);
+                self.walk(path, inner.get("layout").unwrap_or(&Value::Null))?;
+                self.root = parent;
+            }
+            "leaf" => {
+                let ty = ty(inner.get("ty"))?;
+                match self.root {
+                    Some(root) => {
+                        let cell = &mut self.layout.cells[root];
+                        // the leaves after an unsupported layout can't be found in the value
+                        if cell.complete {
+                            cell.fields.push((path.to_string(), ty));
+                        }
+                    }
+                    None => self.layout.cells.push(StorageCell {
+                        path: path.to_string(),
+                        key: key("key")?,
+                        fields: vec![(path.to_string(), ty)],
+                        complete: true,
+                    }),
+                }
+            }
+            _ => {
+                self.layout.unsupported.push(path.to_string());
+                if let Some(root) = self.root {
+                    self.layout.cells[root].complete = false;
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Adds the mapping at `path` if `ty` is a `Mapping`, returns `false` if it is not.
+    fn push_mapping(&mut self, path: &str, prefix: &[u8], ty: u32) -> bool {
+        let ty = match self.types.resolve(ty) {
+            Ok(ty) => ty,
+            Err(_) => return false,
+        };
+        let is_mapping = ty.path.last().map(String::as_str) == Some("Mapping")
+            && matches!(ty.def, TypeDef::Composite(_));
+        let value_ty = ty.params.get(1).and_then(|(_, ty)| *ty);
+        match value_ty {
+            Some(value_ty) if is_mapping => {
+                self.layout.mappings.push(StorageMapping {
+                    path: path.to_string(),
+                    prefix: prefix.to_vec(),
+                    key_ty: ty.params.first().and_then(|(_, ty)| *ty),
+                    value_ty,
+                });
+                true
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Returns the `blake2b` hash of `data` with a length of `len` bytes.
+#[cfg(feature = "extrinsics")]
+fn blake2(len: usize, data: &[u8]) -> Vec<u8> {
+    let mut hasher = blake2::VarBlake2b::new(len).expect("the length is valid");
+    hasher.update(data);
+    hasher.finalize_boxed().to_vec()
+}
+
+#[cfg(all(test, feature = "extrinsics"))]
+mod tests {
+    use super::*;
+    use crate::abi::{tests::metadata_v1, ContractAbi};
+    use serde_json::json;
+
+    fn mapping_type(id: u32) -> Value {
+        json!({ "id": id, "type": {
+            "def": { "composite": { "fields": [{ "name": "offset_key", "type": 2 }] } },
+            "params": [{ "name": "K", "type": 1 }, { "name": "V", "type": 0 }],
+            "path": ["ink_storage", "lazy", "mapping", "Mapping"]
+        } })
+    }
+
+    #[test]
+    fn derives_the_keys_of_ink_3_storage() {
+        let mut metadata = metadata_v1();
+        let key = |last: u8| format!("0x{}{:02x}", "00".repeat(31), last);
+        metadata["V1"]["storage"] = json!({
+            "struct": { "fields": [
+                { "name": "total", "layout": { "cell": { "key": key(0), "ty": 0 } } },
+                { "name": "balances", "layout": { "cell": { "key": key(1), "ty": 8 } } },
+                { "name": "owner", "layout": { "struct": { "fields": [
+                    { "layout": { "cell": { "key": key(2), "ty": 1 } } }
+                ] } } },
+                { "name": "accounts", "layout": { "hash": { "offset": key(3) } } }
+            ] }
+        });
+        metadata["V1"]["types"]
+            .as_array_mut()
+            .unwrap()
+            .push(mapping_type(8));
+        let storage = ContractAbi::from_json(&metadata).unwrap().storage;
+
+        assert_eq!(storage.scheme, KeyScheme::Hashed);
+        let owner = storage.cell("owner").unwrap();
+        assert_eq!(owner.path, "owner.0");
+        assert_eq!(owner.fields, vec![("owner.0".to_string(), 1)]);
+        assert_eq!(
+            storage.trie_key(&storage.cell("total").unwrap().key),
+            decode_hex("0x89eb0d6a8a691dae2cd15ed0369931ce0a949ecafa5c3f93f8121833646e15c3")
+                .unwrap()
+        );
+
+        let balances = storage.mapping("balances").unwrap();
+        assert_eq!((balances.key_ty, balances.value_ty), (Some(1), 0));
+        assert_eq!(
+            storage.entry_key(balances, &[7; 32]),
+            decode_hex("0x65856a272e2b132fe1261aea42572fec8ed3358f20f97f0f87075b9b078d713e")
+                .unwrap()
+        );
+        assert_eq!(storage.unsupported, vec!["accounts"]);
+    }
+
+    #[test]
+    fn packs_the_leaves_of_ink_4_roots() {
+        let mut metadata = metadata_v1()["V1"].clone();
+        metadata["version"] = json!("4");
+        metadata["storage"] = json!({ "root": {
+            "root_key": "0x00000000",
+            "layout": { "struct": { "name": "Bank", "fields": [
+                { "name": "total", "layout": { "leaf": { "key": "0x00000000", "ty": 0 } } },
+                { "name": "balances", "layout": { "root": {
+                    "root_key": "0x2a000000",
+                    "layout": { "leaf": { "key": "0x2a000000", "ty": 0 } },
+                    "ty": 8
+                } } },
+                { "name": "owner", "layout": { "leaf": { "key": "0x00000000", "ty": 1 } } },
+                { "name": "state", "layout": { "enum": { "name": "State", "variants": {} } } },
+                { "name": "limit", "layout": { "leaf": { "key": "0x00000000", "ty": 0 } } }
+            ] } }
+        } });
+        metadata["types"]
+            .as_array_mut()
+            .unwrap()
+            .push(mapping_type(8));
+        let storage = ContractAbi::from_json(&metadata).unwrap().storage;
+
+        assert_eq!(storage.scheme, KeyScheme::Concat);
+        let root = storage.cell("owner").unwrap();
+        assert_eq!(root.path, "");
+        assert_eq!(
+            root.fields,
+            vec![("total".to_string(), 0), ("owner".to_string(), 1)]
+        );
+        assert!(!root.complete);
+        assert_eq!(
+            storage.trie_key(&root.key),
+            decode_hex("0x11d2df4e979aa105cf552e9544ebd2b500000000").unwrap()
+        );
+
+        let balances = storage.mapping("balances").unwrap();
+        assert_eq!(
+            storage.entry_key(balances, &[1, 2]),
+            vec![42, 0, 0, 0, 1, 2]
+        );
+    }
+}
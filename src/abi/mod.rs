@@ -0,0 +1,931 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reads the ABI of a contract from the JSON of its ink! metadata.
+//!
+//! The metadata is read from the raw JSON instead of the `ink_metadata` types, so the
+//! metadata of contracts written with different ink! versions can be read alike.
+
+mod decode;
+mod encode;
+mod layout;
+
+#[cfg(feature = "extrinsics")]
+pub use self::decode::DecodedEvent;
+pub use self::decode::{DecodedValue, ReturnValue};
+pub(crate) use self::encode::{decode_ss58, encode_ss58, parse_arg_value};
+pub use self::layout::StorageLayout;
+#[cfg(feature = "extrinsics")]
+pub use self::layout::{is_within, KeyScheme, StorageCell, StorageMapping};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::{collections::BTreeMap, convert::TryInto, fmt, fs, path::Path};
+
+/// The version of the ink! metadata format.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum MetadataVersion {
+    /// Written by ink! before `3.0.0-rc3`, with `scale-info` type ids starting at 1.
+    V0,
+    /// Written by ink! `3.0.0-rc3` up to `3.0.0-rc6`.
+    V1,
+    /// Written by ink! `3.0.0-rc7` and `3.0.0-rc8`.
+    V2,
+    /// Written by ink! 3.
+    V3,
+    /// Written by ink! 4.
+    V4,
+}
+
+impl fmt::Display for MetadataVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:?}", self)
+    }
+}
+
+impl std::str::FromStr for MetadataVersion {
+    type Err = String;
+    fn from_str(version: &str) -> std::result::Result<Self, Self::Err> {
+        match version.to_ascii_uppercase().as_str() {
+            "V0" => Ok(MetadataVersion::V0),
+            "V1" => Ok(MetadataVersion::V1),
+            "V2" => Ok(MetadataVersion::V2),
+            "V3" => Ok(MetadataVersion::V3),
+            "V4" => Ok(MetadataVersion::V4),
+            _ => Err(format!("Unknown metadata version `{}`", version)),
+        }
+    }
+}
+
+/// The selector of a message or constructor.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Selector(pub [u8; 4]);
+
+impl Selector {
+    /// Parses a selector from its `0x` prefixed hex representation.
+    pub fn from_hex(hex: &str) -> Result<Self> {
+        let bytes = decode_hex(hex)?;
+        let bytes: [u8; 4] = bytes
+            .as_slice()
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("A selector must have 4 bytes, got `{}`", hex))?;
+        Ok(Self(bytes))
+    }
+}
+
+impl fmt::Display for Selector {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "0x")?;
+        for byte in &self.0 {
+            write!(f, "{:02x}", byte)?;
+        }
+        Ok(())
+    }
+}
+
+/// Returns the `0x` prefixed hex representation of `bytes`.
+pub fn encode_hex(bytes: &[u8]) -> String {
+    let digits = bytes
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("0x{}", digits)
+}
+
+/// Decodes a hex string, with or without `0x` prefix.
+pub fn decode_hex(input: &str) -> Result<Vec<u8>> {
+    input
+        .trim_start_matches("0x")
+        .as_bytes()
+        .chunks(2)
+        .map(|digits| {
+            std::str::from_utf8(digits)
+                .ok()
+                .filter(|digits| digits.len() == 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or_else(|| anyhow::anyhow!("Invalid hex string `{}`", input))
+        })
+        .collect()
+}
+
+/// A reference to a type of the [`TypeRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TypeRef {
+    /// The id of the type in the registry.
+    pub id: u32,
+    /// The path of the type as written in the contract source, e.g. `["Balance"]`.
+    pub display_name: Vec<String>,
+}
+
+/// An argument of a message, constructor or event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ArgSpec {
+    pub label: String,
+    pub ty: TypeRef,
+    /// `true` for event arguments which are topics.
+    pub indexed: bool,
+    pub docs: Vec<String>,
+}
+
+/// A message or constructor of the contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MessageSpec {
+    /// The label, e.g. `transfer` or `PSP22::transfer` for trait messages.
+    pub label: String,
+    pub selector: Selector,
+    pub args: Vec<ArgSpec>,
+    /// The return type, always `None` for constructors.
+    pub return_type: Option<TypeRef>,
+    /// `true` if the message may change the contract storage.
+    pub mutates: bool,
+    /// `true` if the message or constructor accepts a transferred value.
+    pub payable: bool,
+    pub docs: Vec<String>,
+}
+
+/// An event the contract may emit.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EventSpec {
+    pub label: String,
+    pub args: Vec<ArgSpec>,
+    pub docs: Vec<String>,
+}
+
+/// A primitive type of the [`TypeRegistry`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Primitive {
+    Bool,
+    Char,
+    Str,
+    U8,
+    U16,
+    U32,
+    U64,
+    U128,
+    U256,
+    I8,
+    I16,
+    I32,
+    I64,
+    I128,
+    I256,
+}
+
+impl Primitive {
+    fn parse(name: &str) -> Result<Self> {
+        Ok(match name {
+            "bool" => Self::Bool,
+            "char" => Self::Char,
+            "str" => Self::Str,
+            "u8" => Self::U8,
+            "u16" => Self::U16,
+            "u32" => Self::U32,
+            "u64" => Self::U64,
+            "u128" => Self::U128,
+            "u256" => Self::U256,
+            "i8" => Self::I8,
+            "i16" => Self::I16,
+            "i32" => Self::I32,
+            "i64" => Self::I64,
+            "i128" => Self::I128,
+            "i256" => Self::I256,
+            _ => anyhow::bail!("Unknown primitive type `{}`", name),
+        })
+    }
+
+    /// The name of the primitive in Rust.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Bool => "bool",
+            Self::Char => "char",
+            Self::Str => "String",
+            Self::U8 => "u8",
+            Self::U16 => "u16",
+            Self::U32 => "u32",
+            Self::U64 => "u64",
+            Self::U128 => "u128",
+            Self::U256 => "u256",
+            Self::I8 => "i8",
+            Self::I16 => "i16",
+            Self::I32 => "i32",
+            Self::I64 => "i64",
+            Self::I128 => "i128",
+            Self::I256 => "i256",
+        }
+    }
+}
+
+/// A field of a struct or enum variant.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    /// The name of the field, `None` for tuple structs and variants.
+    pub name: Option<String>,
+    pub ty: u32,
+    /// The name of the type as written in the contract, e.g. `Balance`.
+    pub type_name: Option<String>,
+    pub docs: Vec<String>,
+}
+
+/// A variant of an enum.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Variant {
+    pub name: String,
+    pub index: u8,
+    pub fields: Vec<Field>,
+    pub docs: Vec<String>,
+}
+
+/// The definition of a type of the [`TypeRegistry`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TypeDef {
+    Composite(Vec<Field>),
+    Variant(Vec<Variant>),
+    Sequence(u32),
+    Array { len: u32, ty: u32 },
+    Tuple(Vec<u32>),
+    Primitive(Primitive),
+    Compact(u32),
+    BitSequence,
+}
+
+/// A type used by the contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Type {
+    /// The path of the type, e.g. `["ink_env", "types", "AccountId"]`, empty for built-in types.
+    pub path: Vec<String>,
+    /// The generic parameters of the type, `None` if they were erased.
+    pub params: Vec<(String, Option<u32>)>,
+    pub def: TypeDef,
+    pub docs: Vec<String>,
+}
+
+/// The types used by the contract, indexed by their id.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TypeRegistry {
+    types: BTreeMap<u32, Type>,
+}
+
+impl TypeRegistry {
+    /// Returns the type with the given `id`.
+    pub fn resolve(&self, id: u32) -> Result<&Type> {
+        self.types
+            .get(&id)
+            .ok_or_else(|| anyhow::anyhow!("Type {} is missing in the metadata", id))
+    }
+
+    /// Returns a Rust like name of the type, e.g. `Option<AccountId>`.
+    pub fn type_name(&self, id: u32) -> String {
+        let ty = match self.resolve(id) {
+            Ok(ty) => ty,
+            Err(_) => return format!("<unknown type {}>", id),
+        };
+        match &ty.def {
+            TypeDef::Primitive(primitive) => primitive.name().to_string(),
+            TypeDef::Sequence(ty) => format!("Vec<{}>", self.type_name(*ty)),
+            TypeDef::Array { len, ty } => format!("[{}; {}]", self.type_name(*ty), len),
+            TypeDef::Tuple(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|ty| self.type_name(*ty))
+                    .collect::<Vec<_>>();
+                format!("({})", fields.join(", "))
+            }
+            TypeDef::Compact(ty) => format!("Compact<{}>", self.type_name(*ty)),
+            TypeDef::BitSequence => "BitVec".to_string(),
+            TypeDef::Composite(_) | TypeDef::Variant(_) => {
+                let name = ty.path.last().cloned().unwrap_or_default();
+                let params = ty
+                    .params
+                    .iter()
+                    .filter_map(|(_, ty)| ty.map(|ty| self.type_name(ty)))
+                    .collect::<Vec<_>>();
+                if params.is_empty() {
+                    name
+                } else {
+                    format!("{}<{}>", name, params.join(", "))
+                }
+            }
+        }
+    }
+
+    /// Returns the id of the type given by its id, its path or the end of its path, e.g.
+    /// `bank::Error` or `Error`, or by its name, e.g. `Option<AccountId>`.
+    pub fn lookup(&self, name: &str) -> Result<u32> {
+        if let Ok(id) = name.parse::<u32>() {
+            self.resolve(id)?;
+            return Ok(id);
+        }
+        let segments = name.split("::").collect::<Vec<_>>();
+        let ends_with_name = |path: &[String]| {
+            path.len() >= segments.len()
+                && path
+                    .iter()
+                    .rev()
+                    .zip(segments.iter().rev())
+                    .all(|(a, b)| a == b)
+        };
+        let matches = self
+            .types
+            .iter()
+            .filter(|(id, ty)| ends_with_name(&ty.path) || self.type_name(**id) == name)
+            .map(|(id, _)| *id)
+            .collect::<Vec<_>>();
+        match matches[..] {
+            [id] => Ok(id),
+            [] => anyhow::bail!("No type `{}` in the metadata", name),
+            _ => {
+                let candidates = matches
+                    .iter()
+                    .map(|id| match &self.types[id].path {
+                        path if path.is_empty() => format!("`{}` ({})", self.type_name(*id), id),
+                        path => format!("`{}` ({})", path.join("::"), id),
+                    })
+                    .collect::<Vec<_>>();
+                anyhow::bail!(
+                    "The type `{}` is ambiguous, give one of {}",
+                    name,
+                    candidates.join(", ")
+                )
+            }
+        }
+    }
+
+    /// Returns how values of the type `id` are SCALE encoded, regardless of the names of
+    /// the type and its fields.
+    ///
+    /// Types whose values are encoded the same have the same encoding, e.g. a struct with
+    /// a single field and the type of that field.
+    pub fn encoding(&self, id: u32) -> String {
+        self.encoding_at(id, 0)
+    }
+
+    fn encoding_at(&self, id: u32, depth: usize) -> String {
+        // guards against recursive types
+        if depth > 32 {
+            return "..".to_string();
+        }
+        let ty = match self.resolve(id) {
+            Ok(ty) => ty,
+            Err(_) => return "?".to_string(),
+        };
+        let fields = |ids: &mut dyn Iterator<Item = u32>| {
+            let fields = ids
+                .map(|id| self.encoding_at(id, depth + 1))
+                .collect::<Vec<_>>();
+            match fields.as_slice() {
+                [field] => field.clone(),
+                fields => format!("({})", fields.join(", ")),
+            }
+        };
+        match &ty.def {
+            TypeDef::Primitive(primitive) => primitive.name().to_string(),
+            TypeDef::Sequence(ty) => format!("Vec<{}>", self.encoding_at(*ty, depth + 1)),
+            TypeDef::Array { len, ty } => {
+                format!("[{}; {}]", self.encoding_at(*ty, depth + 1), len)
+            }
+            TypeDef::Compact(ty) => format!("Compact<{}>", self.encoding_at(*ty, depth + 1)),
+            TypeDef::BitSequence => "BitVec".to_string(),
+            TypeDef::Tuple(ids) => fields(&mut ids.iter().copied()),
+            TypeDef::Composite(composite) => fields(&mut composite.iter().map(|field| field.ty)),
+            TypeDef::Variant(variants) => {
+                let variants = variants
+                    .iter()
+                    .map(|variant| {
+                        format!(
+                            "{}: {}",
+                            variant.index,
+                            fields(&mut variant.fields.iter().map(|field| field.ty))
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("enum {{ {} }}", variants.join(", "))
+            }
+        }
+    }
+}
+
+/// The ABI of a contract, read from its ink! metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractAbi {
+    /// The name of the contract, if the metadata contains the `contract` section.
+    pub name: Option<String>,
+    pub constructors: Vec<MessageSpec>,
+    pub messages: Vec<MessageSpec>,
+    pub events: Vec<EventSpec>,
+    pub docs: Vec<String>,
+    pub types: TypeRegistry,
+    pub storage: StorageLayout,
+}
+
+impl ContractAbi {
+    /// Reads the ABI from a `metadata.json` or `.contract` file.
+    pub fn load(path: &Path) -> Result<Self> {
+        let file = fs::File::open(path)
+            .with_context(|| format!("Failed to open metadata file {}", path.display()))?;
+        let json: Value = serde_json::from_reader(std::io::BufReader::new(file))
+            .with_context(|| format!("Failed to parse metadata file {}", path.display()))?;
+        Self::from_json(&json)
+    }
+
+    /// Reads the ABI from the JSON of the metadata.
+    pub fn from_json(json: &Value) -> Result<Self> {
+        if !json.is_object() {
+            anyhow::bail!("The metadata must be a JSON object")
+        }
+        let (version, project) = versioned_project(json)?;
+
+        let name = json
+            .get("contract")
+            .and_then(|contract| contract.get("name"))
+            .and_then(Value::as_str)
+            .map(ToString::to_string);
+        let types = parse_types(field(project, "types")?, version)?;
+        let spec = field(project, "spec")?;
+        let constructors = array(spec, "constructors")?
+            .iter()
+            .map(|constructor| parse_message(constructor, version, true))
+            .collect::<Result<_>>()?;
+        let messages = array(spec, "messages")?
+            .iter()
+            .map(|message| parse_message(message, version, false))
+            .collect::<Result<_>>()?;
+        let events = match spec.get("events") {
+            Some(events) => events
+                .as_array()
+                .ok_or_else(|| anyhow::anyhow!("`events` must be an array"))?
+                .iter()
+                .map(|event| parse_event(event, version))
+                .collect::<Result<_>>()?,
+            None => Vec::new(),
+        };
+        // the storage layout is not needed to interact with the contract, so a layout which
+        // can't be read does not fail reading the ABI
+        let storage = project
+            .get("storage")
+            .and_then(|storage| layout::parse(storage, version, &types).ok())
+            .unwrap_or_default();
+
+        Ok(Self {
+            name,
+            constructors,
+            messages,
+            events,
+            docs: docs(spec),
+            types,
+            storage,
+        })
+    }
+}
+
+/// Returns the version of the metadata and the object holding `spec`, `storage` and `types`.
+pub fn versioned_project(root: &Value) -> Result<(MetadataVersion, &Value)> {
+    for (key, version) in [
+        ("V3", MetadataVersion::V3),
+        ("V2", MetadataVersion::V2),
+        ("V1", MetadataVersion::V1),
+    ] {
+        if let Some(project) = root.get(key) {
+            return Ok((version, project));
+        }
+    }
+    if root.get("spec").is_none() {
+        anyhow::bail!("No contract spec found in the metadata")
+    }
+    // since ink! 4 the version is a field next to the spec, before ink! 3 there was none
+    let version = match root.get("version") {
+        Some(Value::String(version)) if version == "4" => MetadataVersion::V4,
+        Some(Value::Number(version)) if version.as_u64() == Some(4) => MetadataVersion::V4,
+        Some(version) => anyhow::bail!("Unsupported metadata version {}", version),
+        None => MetadataVersion::V0,
+    };
+    Ok((version, root))
+}
+
+/// Returns the field `key` of the JSON object `value`.
+fn field<'a>(value: &'a Value, key: &str) -> Result<&'a Value> {
+    value
+        .get(key)
+        .ok_or_else(|| anyhow::anyhow!("`{}` is missing in the metadata", key))
+}
+
+/// Returns the array `key` of the JSON object `value`.
+fn array<'a>(value: &'a Value, key: &str) -> Result<&'a Vec<Value>> {
+    field(value, key)?
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("`{}` must be an array", key))
+}
+
+/// Returns the `docs` of the JSON object `value`.
+fn docs(value: &Value) -> Vec<String> {
+    value
+        .get("docs")
+        .and_then(Value::as_array)
+        .map(|docs| {
+            docs.iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// Returns the label of a spec: the `label` of ink! 3 and later, or the `name` before.
+///
+/// Before ink! 3 the `name` of messages was a path, e.g. `["PSP22", "transfer"]`.
+fn label(value: &Value) -> Result<String> {
+    match value.get("label").or_else(|| value.get("name")) {
+        Some(Value::String(label)) => Ok(label.clone()),
+        Some(Value::Array(segments)) => Ok(segments
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("::")),
+        _ => anyhow::bail!("No `label` found in {}", value),
+    }
+}
+
+/// Returns the type id, which starts at 1 for [`MetadataVersion::V0`] and at 0 afterwards.
+fn type_id(value: &Value, version: MetadataVersion) -> Result<u32> {
+    let id = value
+        .as_u64()
+        .ok_or_else(|| anyhow::anyhow!("Invalid type id {}", value))? as u32;
+    if version == MetadataVersion::V0 {
+        id.checked_sub(1)
+            .ok_or_else(|| anyhow::anyhow!("Invalid type id {}", value))
+    } else {
+        Ok(id)
+    }
+}
+
+fn parse_type_ref(value: &Value, version: MetadataVersion) -> Result<TypeRef> {
+    let display_name = value
+        .get("displayName")
+        .and_then(Value::as_array)
+        .map(|segments| {
+            segments
+                .iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(TypeRef {
+        id: type_id(field(value, "type")?, version)?,
+        display_name,
+    })
+}
+
+fn parse_arg(value: &Value, version: MetadataVersion) -> Result<ArgSpec> {
+    Ok(ArgSpec {
+        label: label(value)?,
+        ty: parse_type_ref(field(value, "type")?, version)?,
+        indexed: value
+            .get("indexed")
+            .and_then(Value::as_bool)
+            .unwrap_or(false),
+        docs: docs(value),
+    })
+}
+
+fn parse_message(
+    value: &Value,
+    version: MetadataVersion,
+    constructor: bool,
+) -> Result<MessageSpec> {
+    let selector = field(value, "selector")?
+        .as_str()
+        .ok_or_else(|| anyhow::anyhow!("`selector` must be a string"))?;
+    let return_type = match value.get("returnType") {
+        Some(Value::Null) | None => None,
+        Some(return_type) => Some(parse_type_ref(return_type, version)?),
+    };
+    Ok(MessageSpec {
+        label: label(value)?,
+        selector: Selector::from_hex(selector)?,
+        args: array(value, "args")?
+            .iter()
+            .map(|arg| parse_arg(arg, version))
+            .collect::<Result<_>>()?,
+        return_type: if constructor { None } else { return_type },
+        mutates: value
+            .get("mutates")
+            .and_then(Value::as_bool)
+            .unwrap_or(constructor),
+        // constructors are always payable before ink! 3
+        payable: value
+            .get("payable")
+            .and_then(Value::as_bool)
+            .unwrap_or(constructor),
+        docs: docs(value),
+    })
+}
+
+fn parse_event(value: &Value, version: MetadataVersion) -> Result<EventSpec> {
+    Ok(EventSpec {
+        label: label(value)?,
+        args: array(value, "args")?
+            .iter()
+            .map(|arg| parse_arg(arg, version))
+            .collect::<Result<_>>()?,
+        docs: docs(value),
+    })
+}
+
+/// Parses the type registry, a list of `{ "id": .., "type": .. }` since `scale-info` 1.0
+/// and a plain list of types before.
+fn parse_types(value: &Value, version: MetadataVersion) -> Result<TypeRegistry> {
+    let entries = value
+        .as_array()
+        .ok_or_else(|| anyhow::anyhow!("`types` must be an array"))?;
+    let mut types = BTreeMap::new();
+    for (index, entry) in entries.iter().enumerate() {
+        let (id, ty) = match (entry.get("id"), entry.get("type")) {
+            (Some(id), Some(ty)) => (type_id(id, MetadataVersion::V1)?, ty),
+            _ => (index as u32, entry),
+        };
+        types.insert(id, parse_type(ty, version)?);
+    }
+    Ok(TypeRegistry { types })
+}
+
+fn parse_type(value: &Value, version: MetadataVersion) -> Result<Type> {
+    let path = value
+        .get("path")
+        .and_then(Value::as_array)
+        .map(|path| {
+            path.iter()
+                .filter_map(Value::as_str)
+                .map(ToString::to_string)
+                .collect()
+        })
+        .unwrap_or_default();
+    let params = match value.get("params").and_then(Value::as_array) {
+        Some(params) => params
+            .iter()
+            .enumerate()
+            .map(|(i, param)| match param {
+                // `scale-info` 1.0 and later: `{ "name": "T", "type": 1 }`
+                Value::Object(_) => {
+                    let name = param
+                        .get("name")
+                        .and_then(Value::as_str)
+                        .unwrap_or_default()
+                        .to_string();
+                    let ty = match param.get("type") {
+                        Some(Value::Null) | None => None,
+                        Some(ty) => Some(type_id(ty, version)?),
+                    };
+                    Ok((name, ty))
+                }
+                // before: the plain type id
+                ty => Ok((format!("T{}", i), Some(type_id(ty, version)?))),
+            })
+            .collect::<Result<_>>()?,
+        None => Vec::new(),
+    };
+    let def = field(value, "def")?
+        .as_object()
+        .and_then(|def| def.iter().next())
+        .ok_or_else(|| anyhow::anyhow!("Invalid type definition {}", value))?;
+    let def = match (def.0.as_str(), def.1) {
+        ("composite", composite) => TypeDef::Composite(parse_fields(composite, version)?),
+        ("variant", variant) => {
+            let variants = match variant.get("variants") {
+                Some(Value::Array(variants)) => variants.as_slice(),
+                _ => &[],
+            };
+            TypeDef::Variant(
+                variants
+                    .iter()
+                    .enumerate()
+                    .map(|(i, variant)| {
+                        let index = variant
+                            .get("index")
+                            .or_else(|| variant.get("discriminant"))
+                            .and_then(Value::as_u64)
+                            .unwrap_or(i as u64) as u8;
+                        Ok(Variant {
+                            name: label(variant)?,
+                            index,
+                            fields: parse_fields(variant, version)?,
+                            docs: docs(variant),
+                        })
+                    })
+                    .collect::<Result<_>>()?,
+            )
+        }
+        ("sequence", sequence) => TypeDef::Sequence(type_id(field(sequence, "type")?, version)?),
+        ("array", array) => TypeDef::Array {
+            len: field(array, "len")?
+                .as_u64()
+                .ok_or_else(|| anyhow::anyhow!("Invalid array length"))? as u32,
+            ty: type_id(field(array, "type")?, version)?,
+        },
+        ("tuple", Value::Array(fields)) => TypeDef::Tuple(
+            fields
+                .iter()
+                .map(|ty| type_id(ty, version))
+                .collect::<Result<_>>()?,
+        ),
+        ("primitive", Value::String(primitive)) => TypeDef::Primitive(Primitive::parse(primitive)?),
+        ("compact", compact) => TypeDef::Compact(type_id(field(compact, "type")?, version)?),
+        ("bitsequence", _) => TypeDef::BitSequence,
+        (kind, _) => anyhow::bail!("Unknown type definition `{}`", kind),
+    };
+    Ok(Type {
+        path,
+        params,
+        def,
+        docs: docs(value),
+    })
+}
+
+fn parse_fields(value: &Value, version: MetadataVersion) -> Result<Vec<Field>> {
+    let fields = match value.get("fields") {
+        Some(Value::Array(fields)) => fields.as_slice(),
+        _ => &[],
+    };
+    fields
+        .iter()
+        .map(|field_value| {
+            Ok(Field {
+                name: field_value
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string),
+                ty: type_id(field(field_value, "type")?, version)?,
+                type_name: field_value
+                    .get("typeName")
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string),
+                docs: docs(field_value),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// The metadata of an ink! 3.0.0-rc6 contract with a balance, an account id, an
+    /// `Option`, a `Result` with a custom error, a trait message and an event.
+    pub(crate) fn metadata_v1() -> Value {
+        json!({
+            "metadataVersion": "0.1.0",
+            "source": { "hash": "0x00", "language": "ink! 3.0.0-rc6", "compiler": "rustc 1.57.0" },
+            "contract": { "name": "bank", "version": "0.1.0", "authors": ["Jane"] },
+            "V1": {
+                "spec": {
+                    "constructors": [{
+                        "args": [{ "name": "limit", "type": { "displayName": ["Balance"], "type": 0 } }],
+                        "docs": ["Creates a bank."],
+                        "name": ["new"],
+                        "selector": "0x9bae9d5e"
+                    }],
+                    "docs": [],
+                    "events": [{
+                        "args": [
+                            { "docs": [], "indexed": true, "name": "from", "type": { "displayName": ["Option"], "type": 3 } },
+                            { "docs": [], "indexed": false, "name": "value", "type": { "displayName": ["Balance"], "type": 0 } }
+                        ],
+                        "docs": [],
+                        "name": "Deposited"
+                    }],
+                    "messages": [
+                        {
+                            "args": [],
+                            "docs": [" Deposits the transferred value."],
+                            "mutates": true,
+                            "name": ["deposit"],
+                            "payable": true,
+                            "returnType": null,
+                            "selector": "0x2d10c9bd"
+                        },
+                        {
+                            "args": [{ "name": "owner", "type": { "displayName": ["AccountId"], "type": 1 } }],
+                            "docs": [],
+                            "mutates": false,
+                            "name": ["Ledger", "balance_of"],
+                            "payable": false,
+                            "returnType": { "displayName": ["Balance"], "type": 0 },
+                            "selector": "0x0f755a56"
+                        },
+                        {
+                            "args": [{ "name": "value", "type": { "displayName": ["Balance"], "type": 0 } }],
+                            "docs": [],
+                            "mutates": true,
+                            "name": ["withdraw"],
+                            "payable": false,
+                            "returnType": { "displayName": ["Result"], "type": 5 },
+                            "selector": "0x410fcc9d"
+                        }
+                    ]
+                },
+                "storage": { "struct": { "fields": [] } },
+                "types": [
+                    { "id": 0, "type": { "def": { "primitive": "u128" } } },
+                    { "id": 1, "type": {
+                        "def": { "composite": { "fields": [{ "type": 2, "typeName": "[u8; 32]" }] } },
+                        "path": ["ink_env", "types", "AccountId"]
+                    } },
+                    { "id": 2, "type": { "def": { "array": { "len": 32, "type": 4 } } } },
+                    { "id": 3, "type": {
+                        "def": { "variant": { "variants": [
+                            { "index": 0, "name": "None" },
+                            { "fields": [{ "type": 1 }], "index": 1, "name": "Some" }
+                        ] } },
+                        "params": [{ "name": "T", "type": 1 }],
+                        "path": ["Option"]
+                    } },
+                    { "id": 4, "type": { "def": { "primitive": "u8" } } },
+                    { "id": 5, "type": {
+                        "def": { "variant": { "variants": [
+                            { "fields": [{ "type": 6 }], "index": 0, "name": "Ok" },
+                            { "fields": [{ "type": 7 }], "index": 1, "name": "Err" }
+                        ] } },
+                        "params": [{ "name": "T", "type": 6 }, { "name": "E", "type": 7 }],
+                        "path": ["Result"]
+                    } },
+                    { "id": 6, "type": { "def": { "tuple": [] } } },
+                    { "id": 7, "type": {
+                        "def": { "variant": { "variants": [
+                            { "index": 0, "name": "InsufficientBalance" },
+                            { "fields": [{ "name": "limit", "type": 0 }], "index": 1, "name": "LimitExceeded" }
+                        ] } },
+                        "path": ["bank", "bank", "Error"]
+                    } }
+                ]
+            }
+        })
+    }
+
+    #[test]
+    fn parses_v1_metadata() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        assert_eq!(abi.name.as_deref(), Some("bank"));
+
+        let constructor = &abi.constructors[0];
+        assert!(constructor.payable);
+        assert_eq!(constructor.selector.to_string(), "0x9bae9d5e");
+
+        let balance_of = &abi.messages[1];
+        assert_eq!(balance_of.label, "Ledger::balance_of");
+        assert!(!balance_of.mutates);
+        assert_eq!(balance_of.args[0].ty.display_name, vec!["AccountId"]);
+        assert!(abi.messages[0].return_type.is_none());
+
+        assert!(abi.events[0].args[0].indexed);
+        assert!(matches!(
+            abi.types.resolve(2).unwrap().def,
+            TypeDef::Array { len: 32, ty: 4 }
+        ));
+        let error = abi.types.resolve(7).unwrap();
+        assert_eq!(abi.types.lookup("bank::Error").unwrap(), 7);
+        assert_eq!(abi.types.lookup("Option<AccountId>").unwrap(), 3);
+        assert_eq!(abi.types.lookup("4").unwrap(), 4);
+        assert!(abi.types.lookup("8").is_err());
+        assert!(abi.types.lookup("Vault").is_err());
+        match &error.def {
+            TypeDef::Variant(variants) => {
+                assert_eq!(variants[1].name, "LimitExceeded");
+                assert_eq!(variants[1].fields[0].name.as_deref(), Some("limit"));
+            }
+            def => panic!("Expected a variant, got {:?}", def),
+        }
+    }
+
+    #[test]
+    fn parses_v3_metadata() {
+        let mut metadata = metadata_v1();
+        let project = metadata["V1"].take();
+        metadata["V3"] = project;
+        metadata["V3"]["spec"]["messages"][1]["label"] = json!("Ledger::balance_of");
+
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+        assert_eq!(abi.messages[1].label, "Ledger::balance_of");
+        assert_eq!(abi.messages[1].args.len(), 1);
+    }
+
+    #[test]
+    fn rejects_metadata_without_spec() {
+        let result = ContractAbi::from_json(&json!({ "contract": { "name": "bank" } }));
+        assert_eq!(
+            result.unwrap_err().to_string(),
+            "No contract spec found in the metadata"
+        );
+    }
+}
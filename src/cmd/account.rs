@@ -0,0 +1,285 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Named accounts whose secret URIs are stored encrypted with a password, to sign extrinsics
+//! with `--account <name>` instead of passing the secret with `--suri`.
+
+use std::{convert::TryFrom, env, fs, io::Write as _, path::PathBuf};
+
+use anyhow::{Context, Result};
+use crypto_secretbox::{
+    aead::{Aead, KeyInit},
+    XSalsa20Poly1305,
+};
+use serde::{Deserialize, Serialize};
+use sp_core::{crypto::Ss58Codec, sr25519, Pair};
+use structopt::StructOpt;
+
+/// The environment variable with the password of the accounts, for non-interactive use.
+const PASSWORD_VAR: &str = "CARGO_CONTRACT_ACCOUNT_PASSWORD";
+
+/// The scrypt cost of deriving the key from the password, `N = 2^15` as in the polkadot-js
+/// keystore.
+const SCRYPT_LOG_N: u8 = 15;
+
+/// Manages the named accounts of the keystore.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "account")]
+pub struct AccountCommand {
+    #[structopt(subcommand)]
+    cmd: AccountSubcommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum AccountSubcommand {
+    /// Generate a new sr25519 keypair and store its secret phrase as the account `name`
+    #[structopt(name = "generate")]
+    Generate { name: String },
+    /// Store the secret URI of an existing keypair as the account `name`.
+    ///
+    /// The secret URI is read from a prompt, so that it is not part of the shell history.
+    #[structopt(name = "import")]
+    Import { name: String },
+    /// List the stored accounts with their addresses
+    #[structopt(name = "list")]
+    List,
+}
+
+impl AccountCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match &self.cmd {
+            AccountSubcommand::Generate { name } => {
+                let (pair, phrase, _) = sr25519::Pair::generate_with_phrase(None);
+                let address = pair.public().to_ss58check();
+                store_account(name, &address, &phrase)?;
+                println!(
+                    "Generated the account `{}` with the address {}\n\
+                    Write down its secret phrase, it is the only way to restore the account:\n\n\
+                    \t{}\n",
+                    name, address, phrase
+                );
+                Ok(None)
+            }
+            AccountSubcommand::Import { name } => {
+                let suri = rpassword::prompt_password("Secret URI: ")?;
+                let pair = sr25519::Pair::from_string(&suri, None)
+                    .map_err(|_| anyhow::anyhow!("Secret string error"))?;
+                let address = pair.public().to_ss58check();
+                store_account(name, &address, &suri)?;
+                Ok(Some(format!(
+                    "Imported the account `{}` with the address {}",
+                    name, address
+                )))
+            }
+            AccountSubcommand::List => {
+                let dir = keystore_dir()?;
+                let mut accounts = Vec::new();
+                if dir.exists() {
+                    for entry in fs::read_dir(&dir)? {
+                        let path = entry?.path();
+                        if path.extension().and_then(|ext| ext.to_str()) != Some("json") {
+                            continue;
+                        }
+                        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+                        let account = StoredAccount::load(&path)?;
+                        accounts.push(format!("{}\t{}", name, account.address));
+                    }
+                }
+                if accounts.is_empty() {
+                    return Ok(Some(format!("No accounts in {}", dir.display())));
+                }
+                accounts.sort();
+                println!("{}", accounts.join("\n"));
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// An account of the keystore.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct StoredAccount {
+    /// The SS58 address, to list accounts without decrypting them.
+    address: String,
+    /// The scrypt parameters deriving the key from the password.
+    scrypt: ScryptParams,
+    /// The XSalsa20-Poly1305 nonce and the encrypted secret URI, as hex.
+    nonce: String,
+    ciphertext: String,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+struct ScryptParams {
+    salt: String,
+    log_n: u8,
+    r: u32,
+    p: u32,
+}
+
+impl StoredAccount {
+    /// Encrypts `secret` with a key derived from `password`.
+    fn encrypt(address: &str, secret: &str, password: &str, log_n: u8) -> Result<Self> {
+        let scrypt = ScryptParams {
+            salt: hex::encode(rand::random::<[u8; 32]>()),
+            log_n,
+            r: 8,
+            p: 1,
+        };
+        let nonce = rand::random::<[u8; 24]>();
+        let ciphertext = scrypt
+            .cipher(password)?
+            .encrypt(&nonce.into(), secret.as_bytes())
+            .map_err(|_| anyhow::anyhow!("Failed to encrypt the secret"))?;
+        Ok(Self {
+            address: address.to_string(),
+            scrypt,
+            nonce: hex::encode(nonce),
+            ciphertext: hex::encode(ciphertext),
+        })
+    }
+
+    /// Decrypts the secret URI, failing if `password` is wrong.
+    fn decrypt(&self, password: &str) -> Result<String> {
+        let nonce = <[u8; 24]>::try_from(hex::decode(&self.nonce)?.as_slice())
+            .map_err(|_| anyhow::anyhow!("Invalid nonce"))?;
+        let secret = self
+            .scrypt
+            .cipher(password)?
+            .decrypt(&nonce.into(), hex::decode(&self.ciphertext)?.as_slice())
+            .map_err(|_| anyhow::anyhow!("Wrong password"))?;
+        Ok(String::from_utf8(secret)?)
+    }
+
+    fn load(path: &std::path::Path) -> Result<Self> {
+        let json = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&json).context(format!("Failed to parse {}", path.display()))
+    }
+}
+
+impl ScryptParams {
+    /// Returns the cipher with the key derived from `password`.
+    fn cipher(&self, password: &str) -> Result<XSalsa20Poly1305> {
+        let params = scrypt::Params::new(self.log_n, self.r, self.p, 32)
+            .map_err(|err| anyhow::anyhow!("Invalid scrypt parameters: {}", err))?;
+        let mut key = [0u8; 32];
+        scrypt::scrypt(
+            password.as_bytes(),
+            &hex::decode(&self.salt)?,
+            &params,
+            &mut key,
+        )
+        .map_err(|err| anyhow::anyhow!("Failed to derive the key: {}", err))?;
+        Ok(XSalsa20Poly1305::new(&key.into()))
+    }
+}
+
+/// Returns the directory holding the accounts, `accounts` of the config directory.
+fn keystore_dir() -> Result<PathBuf> {
+    Ok(crate::util::config_dir()?.join("accounts"))
+}
+
+fn account_path(name: &str) -> Result<PathBuf> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!(
+            "Invalid account name `{}`, use letters, digits, `-` and `_`",
+            name
+        )
+    }
+    Ok(keystore_dir()?.join(format!("{}.json", name)))
+}
+
+/// Returns the password of the accounts, from the environment or a prompt.
+fn password(confirm: bool) -> Result<String> {
+    if let Ok(password) = env::var(PASSWORD_VAR) {
+        return Ok(password);
+    }
+    let password = rpassword::prompt_password("Password: ")?;
+    if confirm && rpassword::prompt_password("Repeat password: ")? != password {
+        anyhow::bail!("The passwords do not match")
+    }
+    Ok(password)
+}
+
+/// Encrypts `secret` with a password and stores it as the account `name`.
+fn store_account(name: &str, address: &str, secret: &str) -> Result<()> {
+    let path = account_path(name)?;
+    if path.exists() {
+        anyhow::bail!("The account `{}` already exists", name)
+    }
+    let account = StoredAccount::encrypt(address, secret, &password(true)?, SCRYPT_LOG_N)?;
+    fs::create_dir_all(path.parent().expect("accounts are in the keystore"))?;
+
+    let mut options = fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    // only the user may read the encrypted secret
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options
+        .open(&path)
+        .context(format!("Failed to create {}", path.display()))?;
+    file.write_all(serde_json::to_string_pretty(&account)?.as_bytes())?;
+    Ok(())
+}
+
+/// Returns the secret URI of the account `name`, decrypted with the password.
+pub(crate) fn decrypt_account(name: &str) -> Result<String> {
+    let path = account_path(name)?;
+    if !path.exists() {
+        anyhow::bail!(
+            "No account `{}`, add it with `cargo contract account generate` or `import`",
+            name
+        )
+    }
+    StoredAccount::load(&path)?
+        .decrypt(&password(false)?)
+        .context(format!("Failed to decrypt the account `{}`", name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decrypts_with_the_password_only() {
+        let account = StoredAccount::encrypt(
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+            "//Alice",
+            "secret",
+            4,
+        )
+        .unwrap();
+        assert!(!account.ciphertext.contains(&hex::encode("//Alice")));
+
+        let json = serde_json::to_string(&account).unwrap();
+        let account: StoredAccount = serde_json::from_str(&json).unwrap();
+        assert_eq!(account.decrypt("secret").unwrap(), "//Alice");
+        assert_eq!(
+            account.decrypt("guess").unwrap_err().to_string(),
+            "Wrong password"
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        assert!(account_path("alice-dev").is_ok());
+        assert!(account_path("../alice").is_err());
+        assert!(account_path("").is_err());
+    }
+}
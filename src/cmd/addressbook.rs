@@ -0,0 +1,355 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Named contract addresses, given instead of the address wherever a contract is expected,
+//! e.g. `--contract my-dex`. The entries are stored in the `addressbook.toml` of the config
+//! directory, or of the project to share them, e.g.
+//!
+//! ```toml
+//! [contracts]
+//! my-dex = "5F3sa2TJAWMqDhXG6jhV4N8ko9SxwGy8TpaNS1repo5EYjQX"
+//!
+//! [networks.astar]
+//! my-dex = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+//! ```
+//!
+//! The entries of the network of `--network` take precedence over the entries of no network.
+//! Names which are not in the address book are looked up in the registry of the contracts
+//! instantiated on the network, see [`super::deployments`].
+
+use std::{
+    collections::BTreeMap,
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use structopt::StructOpt;
+
+use super::{chain::parse_account_id, deployments};
+
+/// The name of the address book files.
+const ADDRESS_BOOK: &str = "addressbook.toml";
+
+/// Manages the named contract addresses of the address book.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "addressbook")]
+pub struct AddressBookCommand {
+    #[structopt(subcommand)]
+    cmd: AddressBookSubcommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum AddressBookSubcommand {
+    /// Add the contract `address` as `name`, replacing an existing entry
+    #[structopt(name = "add")]
+    Add {
+        name: String,
+        #[structopt(parse(try_from_str = parse_account_id))]
+        address: AccountId32,
+        #[structopt(flatten)]
+        scope: Scope,
+    },
+    /// Remove the entry `name`
+    #[structopt(name = "remove")]
+    Remove {
+        name: String,
+        #[structopt(flatten)]
+        scope: Scope,
+    },
+    /// List the entries of the project and the config directory
+    #[structopt(name = "list")]
+    List {
+        /// List the entries of this network and of no network only
+        #[structopt(long)]
+        network: Option<String>,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct Scope {
+    /// The network the entry is used with, any network if not given
+    #[structopt(long)]
+    network: Option<String>,
+    /// Store the entry in the `addressbook.toml` of the current directory, to share it with
+    /// the project, instead of the config directory
+    #[structopt(long)]
+    project: bool,
+}
+
+impl Scope {
+    fn path(&self) -> Result<PathBuf> {
+        if self.project {
+            Ok(PathBuf::from(ADDRESS_BOOK))
+        } else {
+            Ok(crate::util::config_dir()?.join(ADDRESS_BOOK))
+        }
+    }
+}
+
+impl AddressBookCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match &self.cmd {
+            AddressBookSubcommand::Add {
+                name,
+                address,
+                scope,
+            } => {
+                if parse_account_id(name).is_ok() {
+                    anyhow::bail!("The name `{}` is an address itself", name)
+                }
+                let path = scope.path()?;
+                let mut book = AddressBook::load(&path)?;
+                book.entries_mut(scope.network.as_deref())
+                    .insert(name.clone(), address.to_ss58check());
+                book.store(&path)?;
+                Ok(Some(format!(
+                    "Added `{}` for {} to {}",
+                    name,
+                    address.to_ss58check(),
+                    path.display()
+                )))
+            }
+            AddressBookSubcommand::Remove { name, scope } => {
+                let path = scope.path()?;
+                let mut book = AddressBook::load(&path)?;
+                if book
+                    .entries_mut(scope.network.as_deref())
+                    .remove(name)
+                    .is_none()
+                {
+                    anyhow::bail!("There is no entry `{}` in {}", name, path.display())
+                }
+                book.store(&path)?;
+                Ok(Some(format!("Removed `{}` from {}", name, path.display())))
+            }
+            AddressBookSubcommand::List { network } => {
+                let book = AddressBook::load_all()?;
+                let mut entries = Vec::new();
+                for (name, address) in &book.contracts {
+                    entries.push(format!("{}\t{}", name, address));
+                }
+                for (entry_network, contracts) in &book.networks {
+                    if network.is_some() && network.as_ref() != Some(entry_network) {
+                        continue;
+                    }
+                    for (name, address) in contracts {
+                        entries.push(format!("{}\t{}\t{}", name, address, entry_network));
+                    }
+                }
+                if entries.is_empty() {
+                    return Ok(Some("The address book is empty".to_string()));
+                }
+                println!("{}", entries.join("\n"));
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// The entries of an address book file.
+#[derive(Debug, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct AddressBook {
+    /// The entries used with any network.
+    #[serde(default)]
+    contracts: BTreeMap<String, String>,
+    /// The entries used with `--network`, by the name of the network.
+    #[serde(default)]
+    networks: BTreeMap<String, BTreeMap<String, String>>,
+}
+
+impl AddressBook {
+    fn load(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(AddressBook::default());
+        }
+        let book = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&book).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Loads the address book of the config directory, extended by the one of the project.
+    fn load_all() -> Result<Self> {
+        let mut book = Self::load(&crate::util::config_dir()?.join(ADDRESS_BOOK))?;
+        let project = Self::load(Path::new(ADDRESS_BOOK))?;
+        book.contracts.extend(project.contracts);
+        for (network, contracts) in project.networks {
+            book.networks.entry(network).or_default().extend(contracts);
+        }
+        Ok(book)
+    }
+
+    fn store(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn entries_mut(&mut self, network: Option<&str>) -> &mut BTreeMap<String, String> {
+        match network {
+            Some(network) => self.networks.entry(network.to_string()).or_default(),
+            None => &mut self.contracts,
+        }
+    }
+
+    /// Returns the address of the entry `name` of `network`, or of no network.
+    fn lookup(&self, name: &str, network: Option<&str>) -> Option<&str> {
+        network
+            .and_then(|network| self.networks.get(network))
+            .and_then(|contracts| contracts.get(name))
+            .or_else(|| self.contracts.get(name))
+            .map(String::as_str)
+    }
+}
+
+/// Returns the account id `contract`, or the address of its entry in the address book.
+pub(crate) fn resolve_contract(contract: &str, network: Option<&str>) -> Result<AccountId32> {
+    if let Ok(account_id) = parse_account_id(contract) {
+        return Ok(account_id);
+    }
+    match AddressBook::load_all()?.lookup(contract, network) {
+        Some(address) => parse_account_id(address)
+            .with_context(|| format!("Invalid address of `{}` in the address book", contract)),
+        None => anyhow::bail!(
+            "`{}` is neither an address nor an entry of the address book",
+            contract
+        ),
+    }
+}
+
+/// Returns `args` with the names of address book entries or of instantiated contracts given to
+/// `--contract` replaced by their addresses.
+pub(crate) fn expand_args(args: Vec<OsString>) -> Result<Vec<OsString>> {
+    if !args
+        .iter()
+        .any(|arg| arg == "--contract" || arg.to_string_lossy().starts_with("--contract="))
+    {
+        return Ok(args);
+    }
+    expand(args, &AddressBook::load_all()?)
+}
+
+fn expand(args: Vec<OsString>, book: &AddressBook) -> Result<Vec<OsString>> {
+    let network = args
+        .iter()
+        .position(|arg| arg == "--network")
+        .and_then(|position| args.get(position + 1))
+        .map(|network| network.to_string_lossy().into_owned());
+    let resolve = |name: &str| -> Result<Option<String>> {
+        if parse_account_id(name).is_ok() {
+            return Ok(None);
+        }
+        match book.lookup(name, network.as_deref()) {
+            Some(address) => Ok(Some(address.to_string())),
+            None => deployments::lookup(&args, name),
+        }
+    };
+
+    let mut expanded = Vec::with_capacity(args.len());
+    let mut args = args.clone().into_iter();
+    while let Some(arg) = args.next() {
+        if arg == "--" {
+            expanded.push(arg);
+            expanded.extend(args);
+            break;
+        }
+        let lossy = arg.to_string_lossy().into_owned();
+        if lossy == "--contract" {
+            expanded.push(arg);
+            if let Some(value) = args.next() {
+                match resolve(&value.to_string_lossy())? {
+                    Some(address) => expanded.push(address.into()),
+                    None => expanded.push(value),
+                }
+            }
+        } else if let Some(value) = lossy.strip_prefix("--contract=") {
+            match resolve(value)? {
+                Some(address) => expanded.push(format!("--contract={}", address).into()),
+                None => expanded.push(arg),
+            }
+        } else {
+            expanded.push(arg);
+        }
+    }
+    Ok(expanded)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DEX: &str = "5F3sa2TJAWMqDhXG6jhV4N8ko9SxwGy8TpaNS1repo5EYjQX";
+    const ASTAR_DEX: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn replaces_entries_of_the_network() {
+        let book: AddressBook = toml::from_str(&format!(
+            r#"
+            [contracts]
+            my-dex = "{}"
+
+            [networks.astar]
+            my-dex = "{}"
+            "#,
+            DEX, ASTAR_DEX
+        ))
+        .unwrap();
+
+        let expanded = expand(
+            args(&["cargo-contract", "contract", "call", "--contract", "my-dex"]),
+            &book,
+        )
+        .unwrap();
+        assert_eq!(expanded[4], OsString::from(DEX));
+
+        let expanded = expand(
+            args(&[
+                "cargo-contract",
+                "contract",
+                "call",
+                "--network",
+                "astar",
+                "--contract=my-dex",
+            ]),
+            &book,
+        )
+        .unwrap();
+        assert_eq!(
+            expanded[5],
+            OsString::from(format!("--contract={}", ASTAR_DEX))
+        );
+
+        // addresses and unknown names are kept
+        let kept = args(&[
+            "cargo-contract",
+            "--contract",
+            ASTAR_DEX,
+            "--contract",
+            "other",
+        ]);
+        assert_eq!(expand(kept.clone(), &book).unwrap(), kept);
+    }
+}
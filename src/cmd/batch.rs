@@ -0,0 +1,484 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Submits the uploads, instantiations and calls of a batch file in a single extrinsic with
+//! `Utility::batch_all`, so that they are executed atomically.
+
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Deserializer};
+use serde_json::Value;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::blake2_256,
+    H256,
+};
+use structopt::StructOpt;
+use subxt::{
+    contracts::{InstantiateCall, PutCodeCall},
+    sp_runtime::codec::{Compact, Encode},
+    Call, Client, DefaultNodeRuntime, Encoded,
+};
+
+use super::{
+    addressbook::resolve_contract,
+    call::encode_call,
+    confirm::Summary,
+    dry_run::{self, Weight},
+    extrinsic_output::ExtrinsicOutput,
+    gas::DEFAULT_GAS_LIMIT,
+    instantiate::InstantiateWithLimitCall,
+    offline,
+    submit::{self, Submitted},
+};
+use crate::{
+    abi::{decode_hex, ContractAbi, MessageSpec},
+    ExtrinsicOpts,
+};
+
+/// Submits the operations of a batch file atomically, in a single extrinsic.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "batch")]
+pub struct BatchCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// The JSON file with the list of upload, instantiate and call operations
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+    /// Print the events and block of the extrinsic as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// An operation of a batch file, whose paths are relative to the batch file.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Operation {
+    /// Uploads the contract code `wasm`.
+    Upload { wasm: PathBuf },
+    /// Instantiates the code `codeHash`, or the code `wasm` uploaded earlier in the batch.
+    #[serde(rename_all = "camelCase")]
+    Instantiate {
+        code_hash: Option<H256>,
+        wasm: Option<PathBuf>,
+        constructor: Option<String>,
+        #[serde(flatten)]
+        input: Input,
+    },
+    /// Calls a message of `contract`.
+    #[serde(rename_all = "camelCase")]
+    Call {
+        contract: String,
+        message: Option<String>,
+        #[serde(flatten)]
+        input: Input,
+    },
+}
+
+/// The input of an instantiation or a call: the hex encoded `data`, or the arguments of the
+/// constructor or message encoded with the `metadata` of the contract.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Input {
+    data: Option<String>,
+    /// The arguments, as an array or an object keyed by their names.
+    args: Option<Value>,
+    metadata: Option<PathBuf>,
+    #[serde(default, deserialize_with = "dry_run::balance")]
+    value: u128,
+    #[serde(default = "default_gas_limit")]
+    gas_limit: u64,
+    /// The proof size of the gas limit, on chains metering it.
+    #[serde(default)]
+    gas_proof_size: Option<u64>,
+    #[serde(default, deserialize_with = "optional_balance")]
+    storage_deposit_limit: Option<u128>,
+}
+
+fn default_gas_limit() -> u64 {
+    DEFAULT_GAS_LIMIT
+}
+
+fn optional_balance<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<u128>, D::Error> {
+    dry_run::balance(deserializer).map(Some)
+}
+
+/// An operation of a batch file with its code and input data loaded.
+#[derive(Debug, PartialEq, Eq)]
+enum LoadedOperation {
+    Upload {
+        code: Vec<u8>,
+    },
+    Instantiate {
+        code_hash: H256,
+        data: Vec<u8>,
+        value: u128,
+        gas_limit: Weight,
+        storage_deposit_limit: Option<u128>,
+    },
+    Call {
+        contract: AccountId32,
+        data: Vec<u8>,
+        value: u128,
+        gas_limit: Weight,
+        storage_deposit_limit: Option<u128>,
+    },
+}
+
+impl BatchCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let operations = load(&self.file, self.extrinsic_opts.node.network.as_deref())?;
+        if self.extrinsic_opts.offline.is_some() {
+            return offline::write_unsigned(&self.extrinsic_opts, |cli| {
+                cli.encode(batch_all(cli, &operations)?)
+            })
+            .map(Some);
+        }
+
+        operations
+            .iter()
+            .fold(Summary::new("Submit the batch"), |summary, operation| {
+                operation.summarize(summary)
+            })
+            .confirm(&self.extrinsic_opts, |cli| {
+                cli.encode(batch_all(cli, &operations)?)
+            })?;
+        let submitted = async_std::task::block_on(async {
+            let cli = submit::client(&self.extrinsic_opts).await?;
+            let call = batch_all(&cli, &operations)?;
+            submit::watch(&cli, &self.extrinsic_opts, call).await
+        })?;
+        let output = match submitted {
+            Submitted::Executed(events) => ExtrinsicOutput::new(&events, None),
+            Submitted::Pending(pending) => return Ok(Some(pending.to_string())),
+        };
+        if self.output_json {
+            return Ok(Some(output.to_json()?));
+        }
+        let mut message = format!(
+            "Executed {} operations in block {:?}{}",
+            operations.len(),
+            output.block_hash,
+            output.display_explorer_links(&self.extrinsic_opts)?
+        );
+        for event in output
+            .events
+            .iter()
+            .filter(|event| event.pallet == "Contracts")
+        {
+            let data = decode_hex(&event.data)?;
+            match event.name.as_str() {
+                "CodeStored" => message.push_str(&format!("\n\tCode hash: {}", event.data)),
+                // the deployer and the contract
+                "Instantiated" if data.len() >= 64 => {
+                    let mut contract = [0u8; 32];
+                    contract.copy_from_slice(&data[32..64]);
+                    message.push_str(&format!(
+                        "\n\tContract account: {}",
+                        AccountId32::from(contract).to_ss58check()
+                    ));
+                }
+                _ => (),
+            }
+        }
+        Ok(Some(message))
+    }
+}
+
+/// Loads the operations of the batch file at `path`, looking up contract names in the address
+/// book of `network`.
+fn load(path: &Path, network: Option<&str>) -> Result<Vec<LoadedOperation>> {
+    let file = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    let operations: Vec<Operation> = serde_json::from_slice(&file)
+        .with_context(|| format!("Failed to parse {}", path.display()))?;
+    if operations.is_empty() {
+        anyhow::bail!("The batch file {} has no operations", path.display())
+    }
+    let dir = path.parent().unwrap_or_else(|| Path::new(""));
+    operations
+        .into_iter()
+        .enumerate()
+        .map(|(i, operation)| {
+            operation
+                .load(dir, network)
+                .with_context(|| format!("Invalid operation {} of {}", i + 1, path.display()))
+        })
+        .collect()
+}
+
+impl Operation {
+    fn load(self, dir: &Path, network: Option<&str>) -> Result<LoadedOperation> {
+        let read_wasm = |wasm: &Path| {
+            let path = dir.join(wasm);
+            fs::read(&path).with_context(|| format!("Failed to read {}", path.display()))
+        };
+        Ok(match self {
+            Operation::Upload { wasm } => LoadedOperation::Upload {
+                code: read_wasm(&wasm)?,
+            },
+            Operation::Instantiate {
+                code_hash,
+                wasm,
+                constructor,
+                input,
+            } => {
+                let code_hash = match (code_hash, wasm) {
+                    (Some(code_hash), None) => code_hash,
+                    (None, Some(wasm)) => H256(blake2_256(&read_wasm(&wasm)?)),
+                    _ => anyhow::bail!("Either `codeHash` or `wasm` is required"),
+                };
+                LoadedOperation::Instantiate {
+                    code_hash,
+                    data: input.data(dir, constructor.as_deref(), ContractAbi::constructor)?,
+                    value: input.value,
+                    gas_limit: input.gas_limit(),
+                    storage_deposit_limit: input.storage_deposit_limit,
+                }
+            }
+            Operation::Call {
+                contract,
+                message,
+                input,
+            } => LoadedOperation::Call {
+                contract: resolve_contract(&contract, network)?,
+                data: input.data(dir, message.as_deref(), ContractAbi::message)?,
+                value: input.value,
+                gas_limit: input.gas_limit(),
+                storage_deposit_limit: input.storage_deposit_limit,
+            },
+        })
+    }
+}
+
+impl LoadedOperation {
+    /// Adds the operation to the summary confirmed before submitting the batch.
+    fn summarize(&self, summary: Summary) -> Summary {
+        match self {
+            LoadedOperation::Upload { code } => summary.line(
+                "Upload",
+                format!("{:?} ({} bytes)", H256(blake2_256(code)), code.len()),
+            ),
+            LoadedOperation::Instantiate {
+                code_hash, value, ..
+            } => summary
+                .line("Instantiate", format!("{:?}", code_hash))
+                .balance("Value", *value),
+            LoadedOperation::Call {
+                contract,
+                data,
+                value,
+                ..
+            } => summary
+                .line("Call", contract.to_ss58check())
+                .line("Data", dry_run::hex(data))
+                .balance("Value", *value),
+        }
+    }
+}
+
+impl Input {
+    /// Returns the gas limit of `gasLimit` and `gasProofSize`.
+    fn gas_limit(&self) -> Weight {
+        Weight {
+            ref_time: self.gas_limit,
+            proof_size: self.gas_proof_size,
+        }
+    }
+
+    /// Returns the `data`, or the encoded arguments of the constructor or message `label`.
+    fn data(
+        &self,
+        dir: &Path,
+        label: Option<&str>,
+        spec: for<'a> fn(&'a ContractAbi, &str) -> Result<&'a MessageSpec>,
+    ) -> Result<Vec<u8>> {
+        let label = match (&self.data, label) {
+            (Some(data), None) => return decode_hex(data),
+            (None, Some(label)) => label,
+            _ => anyhow::bail!(
+                "Either `data` or the label of the constructor or message is required"
+            ),
+        };
+        let metadata = self
+            .metadata
+            .as_ref()
+            .context("Encoding the arguments requires the `metadata` of the contract")?;
+        let abi = ContractAbi::load(&dir.join(metadata))?;
+        let spec = spec(&abi, label)?;
+        let args = match &self.args {
+            None => Vec::new(),
+            Some(Value::Array(args)) => args.clone(),
+            Some(args) => abi.args_by_label(spec, args)?,
+        };
+        abi.encode_call(spec, &args)
+    }
+}
+
+/// Returns the `Utility::batch_all` Call of the `operations`.
+fn batch_all(
+    cli: &Client<DefaultNodeRuntime>,
+    operations: &[LoadedOperation],
+) -> Result<BatchAllCall, subxt::Error> {
+    let calls = operations
+        .iter()
+        .map(|operation| match operation {
+            LoadedOperation::Upload { code } => cli.encode(PutCodeCall {
+                _runtime: Default::default(),
+                code,
+            }),
+            LoadedOperation::Instantiate {
+                code_hash,
+                data,
+                value,
+                gas_limit:
+                    Weight {
+                        ref_time,
+                        proof_size: None,
+                    },
+                storage_deposit_limit: None,
+            } => cli.encode(InstantiateCall {
+                endowment: *value,
+                gas_limit: *ref_time,
+                code_hash,
+                data,
+            }),
+            LoadedOperation::Instantiate {
+                code_hash,
+                data,
+                value,
+                gas_limit,
+                storage_deposit_limit,
+            } => cli.encode(InstantiateWithLimitCall {
+                value: *value,
+                gas_limit: *gas_limit,
+                storage_deposit_limit: *storage_deposit_limit,
+                code_hash,
+                data,
+                salt: &[],
+            }),
+            LoadedOperation::Call {
+                contract,
+                data,
+                value,
+                gas_limit,
+                storage_deposit_limit,
+            } => encode_call(
+                cli,
+                &contract.clone().into(),
+                *value,
+                *gas_limit,
+                *storage_deposit_limit,
+                data,
+            ),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(BatchAllCall { calls })
+}
+
+/// The `Utility::batch_all` Call, which reverts all of its `calls` if one of them fails.
+struct BatchAllCall {
+    calls: Vec<Encoded>,
+}
+
+impl Encode for BatchAllCall {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Compact(self.calls.len() as u32).encode();
+        for call in &self.calls {
+            encoded.extend(&call.0);
+        }
+        encoded
+    }
+}
+
+impl Call<DefaultNodeRuntime> for BatchAllCall {
+    const MODULE: &'static str = "Utility";
+    const FUNCTION: &'static str = "batch_all";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{abi::tests::metadata_v1, util::tests::with_tmp_dir};
+    use serde_json::json;
+
+    #[test]
+    fn loads_operations_relative_to_the_batch_file() {
+        with_tmp_dir(|path| {
+            fs::write(path.join("bank.wasm"), [0, 97, 115, 109])?;
+            fs::write(path.join("bank.json"), metadata_v1().to_string())?;
+            let batch = json!([
+                { "upload": { "wasm": "bank.wasm" } },
+                {
+                    "instantiate": {
+                        "wasm": "bank.wasm",
+                        "constructor": "new",
+                        "args": { "limit": 100 },
+                        "metadata": "bank.json",
+                        "value": "1000000000000000000000",
+                    }
+                },
+                {
+                    "call": {
+                        "contract": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+                        "data": "0x2d10c9bd",
+                        "gasLimit": 1000,
+                        "gasProofSize": 64,
+                        "storageDepositLimit": 10,
+                    }
+                },
+            ]);
+            fs::write(path.join("batch.json"), batch.to_string())?;
+
+            let operations = load(&path.join("batch.json"), None)?;
+            assert_eq!(operations.len(), 3);
+            assert_eq!(
+                operations[1],
+                LoadedOperation::Instantiate {
+                    code_hash: H256(blake2_256(&[0, 97, 115, 109])),
+                    data: vec![
+                        0x9b, 0xae, 0x9d, 0x5e, 100, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0
+                    ],
+                    value: 1_000_000_000_000_000_000_000,
+                    gas_limit: Weight {
+                        ref_time: DEFAULT_GAS_LIMIT,
+                        proof_size: None,
+                    },
+                    storage_deposit_limit: None,
+                }
+            );
+            assert!(matches!(
+                &operations[2],
+                LoadedOperation::Call {
+                    data,
+                    gas_limit: Weight { ref_time: 1000, proof_size: Some(64) },
+                    storage_deposit_limit: Some(10),
+                    ..
+                } if data == &[0x2d, 0x10, 0xc9, 0xbd]
+            ));
+
+            fs::write(
+                path.join("batch.json"),
+                json!([{ "upload": {} }]).to_string(),
+            )?;
+            assert!(load(&path.join("batch.json"), None).is_err());
+            Ok(())
+        })
+    }
+}
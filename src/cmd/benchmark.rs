@@ -0,0 +1,648 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Measures the gas of each message of a contract with `cargo contract benchmark`.
+//!
+//! Each message is dry-run on the same state, with the arguments of a fixtures file or with
+//! example arguments of their types, and its costs are compared with the ones of a baseline
+//! saved by an earlier run with `--save-baseline` on the same backend. The sandbox measures
+//! other costs than a node: executed Wasm instructions and stored bytes instead of weights and
+//! storage deposits.
+
+use std::{
+    convert::TryFrom,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use contract_sandbox::{Sandbox, ALICE};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use structopt::StructOpt;
+
+use super::{
+    metadata::{built_metadata_path, read_json},
+    sandbox::{contract_wasm, ENDOWMENT},
+};
+use crate::{
+    abi::{ContractAbi, MessageSpec},
+    crate_metadata::CrateMetadata,
+    workspace::ManifestPath,
+};
+
+/// The file of the baseline, in the `target/ink` directory of the contract.
+const BASELINE_FILE: &str = "benchmark.json";
+
+/// Dry-runs each message of a contract, reporting its costs compared with a saved baseline.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "benchmark")]
+pub struct BenchmarkCommand {
+    /// Path to the Cargo.toml of the contract
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The `.contract` bundle or metadata file to read instead of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The Wasm code of the contract, if `--metadata` is no `.contract` bundle. Defaults to
+    /// the code of the built contract
+    #[structopt(long, parse(from_os_str))]
+    wasm: Option<PathBuf>,
+    /// The label of the constructor instantiating the contract in the sandbox
+    #[structopt(long, default_value = "new")]
+    constructor: String,
+    /// A JSON file with the arguments of the constructors and messages, keyed by their labels
+    /// under `constructors` and `messages`. Other messages are called with example arguments
+    #[structopt(long, parse(from_os_str))]
+    fixtures: Option<PathBuf>,
+    /// The gas limit of each call in the sandbox, in executed Wasm instructions
+    #[structopt(long, default_value = "1000000000")]
+    gas_limit: u64,
+    /// The file of the baseline, `target/ink/benchmark.json` of the contract by default
+    #[structopt(long, parse(from_os_str))]
+    baseline: Option<PathBuf>,
+    /// Save the costs of this run as the baseline of the next runs
+    #[structopt(long)]
+    save_baseline: bool,
+    /// Print the report as JSON
+    #[structopt(long)]
+    output_json: bool,
+    /// The account of a contract on the node of `--url` to dry-run the messages of, which
+    /// measures the weights and storage deposits the node charges, instead of instantiating
+    /// the contract in the sandbox, which measures neither
+    #[cfg(feature = "extrinsics")]
+    #[structopt(long, parse(try_from_str = super::chain::parse_account))]
+    contract: Option<super::chain::AccountId>,
+    #[cfg(feature = "extrinsics")]
+    #[structopt(flatten)]
+    extrinsic_opts: crate::ExtrinsicOpts,
+}
+
+impl BenchmarkCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let metadata_path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&metadata_path)?;
+        let fixtures = Fixtures::load(&abi, self.fixtures.as_deref())?;
+
+        let benchmark = self.run(&abi, &fixtures)?;
+        let baseline_path = self.baseline_path()?;
+        let baseline = if baseline_path.exists() {
+            Some(Benchmark::load(&baseline_path)?)
+        } else {
+            None
+        };
+        let baseline = match baseline {
+            // the baseline is replaced by this run
+            Some(baseline) if baseline.backend != benchmark.backend && self.save_baseline => None,
+            Some(baseline) if baseline.backend != benchmark.backend => anyhow::bail!(
+                "The baseline {} was measured on {}, its costs cannot be compared with the ones \
+                measured on {}. Pass another --baseline, or replace it with --save-baseline",
+                baseline_path.display(),
+                baseline.backend,
+                benchmark.backend
+            ),
+            baseline => baseline,
+        };
+        if self.output_json {
+            let report = JsonReport {
+                backend: &benchmark.backend,
+                messages: benchmark
+                    .messages
+                    .iter()
+                    .map(|measurement| JsonMeasurement {
+                        measurement,
+                        baseline: baseline
+                            .as_ref()
+                            .and_then(|baseline| baseline.get(&measurement.message)),
+                    })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string_pretty(&report)?);
+        } else {
+            report(&benchmark, baseline.as_ref(), &baseline_path);
+        }
+        if self.save_baseline {
+            benchmark.save(&baseline_path)?;
+            if !self.output_json {
+                println!("Saved the baseline to {}", baseline_path.display());
+            }
+        }
+        Ok(None)
+    }
+
+    /// Dry-runs the messages on the node if `--contract` is given, or in the sandbox.
+    fn run(&self, abi: &ContractAbi, fixtures: &Fixtures) -> Result<Benchmark> {
+        #[cfg(feature = "extrinsics")]
+        if let Some(contract) = &self.contract {
+            return benchmark_on_node(abi, fixtures, &self.extrinsic_opts, contract);
+        }
+        let wasm = contract_wasm(
+            self.manifest_path.as_ref(),
+            self.metadata.as_deref(),
+            self.wasm.as_deref(),
+        )?;
+        benchmark_in_sandbox(abi, fixtures, &wasm, &self.constructor, self.gas_limit)
+    }
+
+    fn baseline_path(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.baseline {
+            return Ok(path.clone());
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())
+            .context("Pass --baseline to keep the baseline outside of a contract")?;
+        Ok(CrateMetadata::collect(&manifest_path)?
+            .target_directory
+            .join(BASELINE_FILE))
+    }
+}
+
+/// The arguments of the constructors and messages, keyed by their labels.
+#[derive(Debug, Default)]
+struct Fixtures {
+    constructors: Map<String, Value>,
+    messages: Map<String, Value>,
+}
+
+impl Fixtures {
+    /// Reads the fixtures at `path`, keying them by the full labels of the metadata.
+    fn load(abi: &ContractAbi, path: Option<&Path>) -> Result<Self> {
+        let path = match path {
+            Some(path) => path,
+            None => return Ok(Self::default()),
+        };
+        let json = read_json(path)?;
+        let section = |name: &str| match &json[name] {
+            Value::Null => Ok(Map::new()),
+            Value::Object(fixtures) => Ok(fixtures.clone()),
+            _ => Err(anyhow::anyhow!(
+                "Expected `{}` of {} to be an object keyed by labels",
+                name,
+                path.display()
+            )),
+        };
+        let mut fixtures = Self::default();
+        for (label, args) in section("constructors")? {
+            let spec = abi.constructor(&label)?;
+            fixtures.constructors.insert(spec.label.clone(), args);
+        }
+        for (label, args) in section("messages")? {
+            let spec = abi.message(&label)?;
+            fixtures.messages.insert(spec.label.clone(), args);
+        }
+        Ok(fixtures)
+    }
+
+    /// Encodes the call of `spec` with the arguments of the fixtures, or with example
+    /// arguments of their types.
+    fn encode(
+        abi: &ContractAbi,
+        fixtures: &Map<String, Value>,
+        spec: &MessageSpec,
+    ) -> Result<Vec<u8>> {
+        let args = match fixtures.get(&spec.label) {
+            Some(args) => abi.args_by_label(spec, args)?,
+            None => spec
+                .args
+                .iter()
+                .map(|arg| abi.types.example(arg.ty.id))
+                .collect(),
+        };
+        abi.encode_call(spec, &args)
+            .with_context(|| format!("Failed to encode the arguments of `{}`", spec.label))
+    }
+}
+
+/// The costs of the messages of a contract, measured on the same backend.
+#[derive(Debug, Deserialize, Serialize)]
+struct Benchmark {
+    /// `sandbox`, or the url of the node.
+    backend: String,
+    messages: Vec<Measurement>,
+}
+
+impl Benchmark {
+    fn load(path: &Path) -> Result<Self> {
+        let json = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&json).with_context(|| {
+            format!(
+                "Failed to parse the baseline {}, save it again with --save-baseline",
+                path.display()
+            )
+        })
+    }
+
+    fn save(&self, path: &Path) -> Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        fs::write(path, serde_json::to_string_pretty(self)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    fn get(&self, message: &str) -> Option<&Measurement> {
+        self.messages
+            .iter()
+            .find(|measurement| measurement.message == message)
+    }
+}
+
+/// The costs of the dry run of a message.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+struct Measurement {
+    message: String,
+    costs: Costs,
+    #[serde(default)]
+    reverted: bool,
+    /// The error of a failed execution.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    error: Option<String>,
+}
+
+/// The costs measured by a backend, which are not comparable with the ones of the other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "snake_case")]
+enum Costs {
+    Sandbox {
+        /// The number of executed Wasm instructions, the gas of the sandbox.
+        instructions: u64,
+        /// The number of bytes of the keys and values added to the storage, negative if they
+        /// were removed. The sandbox charges no storage deposits.
+        storage_bytes: i64,
+    },
+    Node {
+        /// The reference time of the consumed weight.
+        ref_time: u64,
+        /// The size of the storage proof, if the node meters it.
+        proof_size: Option<u64>,
+        /// The storage deposit charged, negative if it was refunded.
+        storage_deposit: i128,
+    },
+}
+
+impl Costs {
+    /// Returns the costs labeled with their names, in the order of the report.
+    fn labeled(&self) -> Vec<(&'static str, Option<i128>)> {
+        match *self {
+            Costs::Sandbox {
+                instructions,
+                storage_bytes,
+            } => vec![
+                ("instructions", Some(instructions.into())),
+                ("storage bytes", Some(storage_bytes.into())),
+            ],
+            Costs::Node {
+                ref_time,
+                proof_size,
+                storage_deposit,
+            } => vec![
+                ("ref_time", Some(ref_time.into())),
+                ("proof_size", proof_size.map(i128::from)),
+                ("storage deposit", Some(storage_deposit)),
+            ],
+        }
+    }
+
+    /// Returns the cost named `label`, `None` if it is not measured.
+    fn get(&self, label: &str) -> Option<i128> {
+        self.labeled()
+            .into_iter()
+            .find(|(name, _)| *name == label)
+            .and_then(|(_, value)| value)
+    }
+}
+
+impl Measurement {
+    fn status(&self) -> Option<String> {
+        match &self.error {
+            Some(error) => Some(format!("failed: {}", error)),
+            None if self.reverted => Some("reverted".to_string()),
+            None => None,
+        }
+    }
+}
+
+/// Instantiates the contract in a new sandbox and dry-runs each message on the state after
+/// the instantiation.
+fn benchmark_in_sandbox(
+    abi: &ContractAbi,
+    fixtures: &Fixtures,
+    wasm: &[u8],
+    constructor: &str,
+    gas_limit: u64,
+) -> Result<Benchmark> {
+    let spec = abi.constructor(constructor)?;
+    let data = Fixtures::encode(abi, &fixtures.constructors, spec)?;
+    let mut sandbox = Sandbox::new();
+    sandbox.set_balance(ALICE, ENDOWMENT);
+    let instantiated = sandbox.instantiate_with_code(ALICE, wasm, 0, data, Vec::new(), gas_limit);
+    let contract = match instantiated.result {
+        Ok(value) if !value.result.did_revert() => value.account_id,
+        Ok(_) => anyhow::bail!("The constructor `{}` reverted", spec.label),
+        Err(err) => anyhow::bail!("The constructor `{}` failed: {}", spec.label, err),
+    };
+
+    let size = |sandbox: &Sandbox| sandbox.storage_size(&contract) as i64;
+    let messages = abi
+        .messages
+        .iter()
+        .map(|spec| {
+            let data = Fixtures::encode(abi, &fixtures.messages, spec)?;
+            let mut dry_run = sandbox.clone();
+            let result = dry_run.call(ALICE, contract, 0, data, gas_limit);
+            let (reverted, error) = match &result.result {
+                Ok(value) => (value.did_revert(), None),
+                Err(err) => (false, Some(err.to_string())),
+            };
+            Ok(Measurement {
+                message: spec.label.clone(),
+                costs: Costs::Sandbox {
+                    instructions: result.gas_consumed,
+                    storage_bytes: size(&dry_run) - size(&sandbox),
+                },
+                reverted,
+                error,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Benchmark {
+        backend: "sandbox".to_string(),
+        messages,
+    })
+}
+
+/// Dry-runs each message of the contract `contract` on the state of the node.
+#[cfg(feature = "extrinsics")]
+fn benchmark_on_node(
+    abi: &ContractAbi,
+    fixtures: &Fixtures,
+    extrinsic_opts: &crate::ExtrinsicOpts,
+    contract: &super::chain::AccountId,
+) -> Result<Benchmark> {
+    use super::dry_run::{self, StorageDeposit};
+
+    let messages = abi
+        .messages
+        .iter()
+        .map(|spec| {
+            let data = Fixtures::encode(abi, &fixtures.messages, spec)?;
+            let result = dry_run::call(extrinsic_opts, contract, 0, None, None, &data, None)?;
+            let storage_deposit = match result.storage_deposit {
+                Some(StorageDeposit::Charge(deposit)) => deposit as i128,
+                Some(StorageDeposit::Refund(deposit)) => -(deposit as i128),
+                None => 0,
+            };
+            let (reverted, error) = match &result.result {
+                Ok(value) => (value.reverted(), None),
+                Err(err) => (false, Some(result.display_error(err))),
+            };
+            Ok(Measurement {
+                message: spec.label.clone(),
+                costs: Costs::Node {
+                    ref_time: result.gas_consumed.ref_time,
+                    proof_size: result.gas_consumed.proof_size,
+                    storage_deposit,
+                },
+                reverted,
+                error,
+            })
+        })
+        .collect::<Result<_>>()?;
+    Ok(Benchmark {
+        backend: extrinsic_opts.node.url()?.to_string(),
+        messages,
+    })
+}
+
+/// The report printed with `--output-json`.
+#[derive(Serialize)]
+struct JsonReport<'a> {
+    backend: &'a str,
+    messages: Vec<JsonMeasurement<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonMeasurement<'a> {
+    #[serde(flatten)]
+    measurement: &'a Measurement,
+    baseline: Option<&'a Measurement>,
+}
+
+/// Returns the change from `baseline` to `value`, e.g. `+120 (+1.5%)`.
+fn change(value: i128, baseline: i128) -> Option<String> {
+    let delta = value - baseline;
+    if delta == 0 {
+        return None;
+    }
+    let change = match baseline {
+        0 => format!("{:+}", delta),
+        _ => format!(
+            "{:+} ({:+.1}%)",
+            delta,
+            delta as f64 * 100.0 / baseline.abs() as f64
+        ),
+    };
+    if delta > 0 {
+        Some(change.bright_red().to_string())
+    } else {
+        Some(change.green().to_string())
+    }
+}
+
+/// Renders a cost followed by its change from the baseline, if any.
+fn cell(value: Option<i128>, baseline: Option<Option<i128>>) -> String {
+    let value = match value {
+        Some(value) => value,
+        None => return "-".to_string(),
+    };
+    match baseline {
+        Some(Some(baseline)) => match change(value, baseline) {
+            Some(change) => format!("{} {}", value, change),
+            None => value.to_string(),
+        },
+        _ => value.to_string(),
+    }
+}
+
+/// Prints the costs of each message and their changes from the baseline.
+fn report(benchmark: &Benchmark, baseline: Option<&Benchmark>, baseline_path: &Path) {
+    let in_sandbox = benchmark.backend == "sandbox";
+    println!(
+        "{} {} messages {}",
+        "Benchmarked".green().bold(),
+        benchmark.messages.len(),
+        if in_sandbox {
+            "in the sandbox".to_string()
+        } else {
+            format!("on {}", benchmark.backend)
+        }
+    );
+    match baseline {
+        Some(_) => println!("Compared with the baseline {}", baseline_path.display()),
+        None => println!(
+            "No baseline at {}, save one with --save-baseline",
+            baseline_path.display()
+        ),
+    }
+    for measurement in &benchmark.messages {
+        let previous = baseline.map(|baseline| baseline.get(&measurement.message));
+        println!("\n{}", measurement.message.bold());
+        for (label, value) in measurement.costs.labeled() {
+            let previous =
+                previous.map(|previous| previous.and_then(|previous| previous.costs.get(label)));
+            println!("  {:<17}{}", format!("{}:", label), cell(value, previous));
+        }
+        if let Some(status) = measurement.status() {
+            println!("  {}", status.yellow());
+        }
+        if let Some(None) = previous {
+            println!("  {}", "new since the baseline".dimmed());
+        }
+    }
+    if let Some(baseline) = baseline {
+        for removed in baseline
+            .messages
+            .iter()
+            .filter(|previous| benchmark.get(&previous.message).is_none())
+        {
+            println!("\n{} removed since the baseline", removed.message.bold());
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        cmd::fuzz::tests::{counter_abi, counter_metadata, COUNTER},
+        util::tests::with_tmp_dir,
+    };
+    use contract_sandbox::DEFAULT_GAS_LIMIT;
+    use serde_json::json;
+
+    #[test]
+    fn measures_each_message_with_fixtures_or_examples() {
+        let abi = counter_abi();
+        let wasm = wabt::wat2wasm(COUNTER).unwrap();
+        let fixtures = Fixtures {
+            messages: json!({ "add": { "by": 7 } }).as_object().unwrap().clone(),
+            ..Fixtures::default()
+        };
+
+        let benchmark =
+            benchmark_in_sandbox(&abi, &fixtures, &wasm, "new", DEFAULT_GAS_LIMIT).unwrap();
+
+        assert_eq!(benchmark.backend, "sandbox");
+        let labels = benchmark
+            .messages
+            .iter()
+            .map(|measurement| measurement.message.as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(labels, ["add", "get", "small"]);
+        assert!(benchmark.messages.iter().all(|measurement| {
+            measurement.costs.get("instructions") > Some(0) && measurement.status().is_none()
+        }));
+        // the counter overwrites its storage with a value of the same size
+        assert_eq!(benchmark.messages[0].costs.get("storage bytes"), Some(0));
+        assert_eq!(benchmark.messages[0].costs.get("ref_time"), None);
+    }
+
+    #[test]
+    fn baselines_roundtrip_through_json() {
+        let benchmark = Benchmark {
+            backend: "ws://localhost:9944".to_string(),
+            messages: vec![
+                Measurement {
+                    message: "Ledger::transfer".to_string(),
+                    costs: Costs::Node {
+                        ref_time: 1_200,
+                        proof_size: Some(3_400),
+                        storage_deposit: -68,
+                    },
+                    reverted: true,
+                    error: None,
+                },
+                Measurement {
+                    message: "Ledger::balance_of".to_string(),
+                    costs: Costs::Sandbox {
+                        instructions: 900,
+                        storage_bytes: 0,
+                    },
+                    reverted: false,
+                    error: None,
+                },
+            ],
+        };
+        let json = serde_json::to_string(&benchmark).unwrap();
+        assert!(json.contains(r#""costs":{"sandbox":{"instructions":900,"storage_bytes":0}}"#));
+        let decoded: Benchmark = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded.messages, benchmark.messages);
+        assert_eq!(
+            decoded.get("Ledger::transfer").unwrap().status().unwrap(),
+            "reverted"
+        );
+    }
+
+    #[test]
+    fn refuses_baselines_of_another_backend() {
+        with_tmp_dir(|path| {
+            let metadata = path.join("metadata.json");
+            fs::write(&metadata, counter_metadata().to_string())?;
+            let wasm = path.join("counter.wasm");
+            fs::write(&wasm, wabt::wat2wasm(COUNTER)?)?;
+            let baseline = path.join("benchmark.json");
+            Benchmark {
+                backend: "ws://localhost:9944".to_string(),
+                messages: Vec::new(),
+            }
+            .save(&baseline)?;
+            let mut cmd = BenchmarkCommand::from_iter(&[
+                "benchmark",
+                "--metadata",
+                metadata.to_str().unwrap(),
+                "--wasm",
+                wasm.to_str().unwrap(),
+                "--baseline",
+                baseline.to_str().unwrap(),
+                "--output-json",
+            ]);
+
+            let err = cmd.exec().unwrap_err();
+            assert!(err
+                .to_string()
+                .contains("measured on ws://localhost:9944, its costs cannot be compared"));
+
+            cmd.save_baseline = true;
+            cmd.exec()?;
+            assert_eq!(Benchmark::load(&baseline)?.backend, "sandbox");
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn renders_changes_from_the_baseline() {
+        colored::control::set_override(false);
+        assert_eq!(cell(Some(1_100), Some(Some(1_000))), "1100 +100 (+10.0%)");
+        assert_eq!(cell(Some(900), Some(Some(1_000))), "900 -100 (-10.0%)");
+        assert_eq!(cell(Some(68), Some(Some(0))), "68 +68");
+        assert_eq!(cell(Some(1_000), Some(Some(1_000))), "1000");
+        assert_eq!(cell(Some(1_000), None), "1000");
+        assert_eq!(cell(None, Some(Some(1_000))), "-");
+    }
+}
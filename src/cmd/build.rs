@@ -14,6 +14,7 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+use super::metadata::{BuildInfo, MetadataOverrides, UserField, WasmOptSettings};
 use crate::{
     crate_metadata::CrateMetadata,
     maybe_println, util, validate_wasm,
@@ -35,10 +36,14 @@ use std::{
     str,
 };
 use structopt::StructOpt;
+use url::Url;
 
 /// This is the maximum number of pages available for a contract to allocate.
 const MAX_MEMORY_PAGES: u32 = 16;
 
+/// The target contracts are compiled for.
+const WASM_TARGET: &str = "wasm32-unknown-unknown";
+
 /// Arguments to use when executing `build` or `check` commands.
 #[derive(Default)]
 pub(crate) struct ExecuteArgs {
@@ -50,7 +55,13 @@ pub(crate) struct ExecuteArgs {
     unstable_flags: UnstableFlags,
     optimization_passes: OptimizationPasses,
     keep_debug_symbols: bool,
+    strip_docs: bool,
+    pub(crate) metadata_overrides: MetadataOverrides,
     output_type: OutputType,
+    /// Cargo features of the contract to enable.
+    pub(crate) features: Vec<String>,
+    /// Overrides the directory the artifacts are placed in.
+    pub(crate) target_directory: Option<PathBuf>,
 }
 
 /// Executes build of the smart-contract which produces a wasm binary that is ready for deploying.
@@ -116,6 +127,32 @@ pub struct BuildCommand {
     /// This is useful if one wants to analyze or debug the optimized binary.
     #[structopt(long)]
     keep_debug_symbols: bool,
+    /// Remove the doc strings from the generated metadata.
+    ///
+    /// This shrinks the `<name>.contract` bundle, e.g. for uploading it to a registry or
+    /// embedding it into a frontend. It is also enabled by `compact-metadata = true` in the
+    /// `[package.metadata.contract]` of your `Cargo.toml`.
+    #[structopt(long)]
+    strip_docs: bool,
+    /// Link the source code of the contract in the `source.url` of the metadata.
+    ///
+    /// Takes precedence over `source-url` in the `[package.metadata.contract]` of your
+    /// `Cargo.toml`.
+    #[structopt(long, value_name = "url")]
+    source_url: Option<Url>,
+    /// Link a homepage in the `contract.homepage` of the metadata.
+    ///
+    /// Takes precedence over `homepage` in the `[package.metadata.contract]` or `[package]`
+    /// of your `Cargo.toml`.
+    #[structopt(long, value_name = "url")]
+    homepage: Option<Url>,
+    /// Add a field to the `user` section of the metadata, e.g. `--user audit=https://...`.
+    ///
+    /// The value is parsed as JSON if possible, e.g. `--user 'ids=[1, 2]'`, and is a string
+    /// otherwise. The fields are added to the ones of `[package.metadata.contract.user]` in
+    /// your `Cargo.toml`, replacing fields with the same key.
+    #[structopt(long, value_name = "key=value", number_of_values = 1)]
+    user: Vec<UserField>,
 
     /// Export the build output in JSON format.
     #[structopt(long, conflicts_with = "verbose")]
@@ -144,6 +181,11 @@ impl BuildCommand {
             }
         };
 
+        let strip_docs = self.strip_docs
+            || Manifest::new(manifest_path.clone())?
+                .get_profile_compact_metadata()
+                .unwrap_or(false);
+
         let build_mode = match self.build_release {
             true => BuildMode::Release,
             false => BuildMode::Debug,
@@ -167,7 +209,15 @@ impl BuildCommand {
             unstable_flags,
             optimization_passes,
             keep_debug_symbols: self.keep_debug_symbols,
+            strip_docs,
+            metadata_overrides: MetadataOverrides {
+                source_url: self.source_url.clone(),
+                homepage: self.homepage.clone(),
+                user: self.user.clone(),
+            },
             output_type,
+            features: Vec::new(),
+            target_directory: None,
         };
 
         execute(args)
@@ -201,13 +251,44 @@ impl CheckCommand {
             unstable_flags,
             optimization_passes: OptimizationPasses::Zero,
             keep_debug_symbols: false,
+            strip_docs: false,
+            metadata_overrides: MetadataOverrides::default(),
             output_type: OutputType::default(),
+            features: Vec::new(),
+            target_directory: None,
         };
 
         execute(args)
     }
 }
 
+/// Builds the Wasm of the contract of `manifest_path` as it is built for deploying it: in release
+/// mode and with the optimization passes of its `Cargo.toml`.
+#[cfg(feature = "extrinsics")]
+pub(crate) fn build_release_code(
+    manifest_path: ManifestPath,
+    verbosity: Verbosity,
+) -> Result<BuildResult> {
+    let optimization_passes = Manifest::new(manifest_path.clone())?
+        .get_profile_optimization_passes()
+        .unwrap_or_default();
+    let args = ExecuteArgs {
+        manifest_path,
+        verbosity,
+        build_mode: BuildMode::Release,
+        build_artifact: BuildArtifacts::CodeOnly,
+        unstable_flags: UnstableFlags::default(),
+        optimization_passes,
+        keep_debug_symbols: false,
+        strip_docs: false,
+        metadata_overrides: MetadataOverrides::default(),
+        output_type: OutputType::default(),
+        features: Vec::new(),
+        target_directory: None,
+    };
+    execute(args)
+}
+
 /// Executes the supplied cargo command on the project in the specified directory, defaults to the
 /// current directory.
 ///
@@ -230,6 +311,7 @@ fn exec_cargo_for_wasm_target(
     build_mode: BuildMode,
     verbosity: Verbosity,
     unstable_flags: &UnstableFlags,
+    features: &[String],
 ) -> Result<()> {
     util::assert_channel()?;
 
@@ -243,18 +325,27 @@ fn exec_cargo_for_wasm_target(
     let cargo_build = |manifest_path: &ManifestPath| {
         let target_dir = &crate_metadata.target_directory;
         let target_dir = format!("--target-dir={}", target_dir.to_string_lossy());
+        let target_arg = format!("--target={}", WASM_TARGET);
         let mut args = vec![
-            "--target=wasm32-unknown-unknown",
+            &target_arg,
             "-Zbuild-std",
             "--no-default-features",
             "--release",
             &target_dir,
         ];
-        if build_mode == BuildMode::Debug {
+        if build_mode == BuildMode::Debug && crate_metadata.ink_version.major >= 4 {
+            // ink! 4 contracts depend on `ink_env` through the `ink` crate only
+            args.push("--features=ink/ink-debug");
+        } else if build_mode == BuildMode::Debug {
             args.push("--features=ink_env/ink-debug");
         } else {
             args.push("-Zbuild-std-features=panic_immediate_abort");
         }
+        let features = features
+            .iter()
+            .map(|feature| format!("--features={}", feature))
+            .collect::<Vec<_>>();
+        args.extend(features.iter().map(String::as_str));
         util::invoke_cargo(command, &args, manifest_path.directory(), verbosity)?;
 
         Ok(())
@@ -493,6 +584,39 @@ fn do_optimization(
     Ok(())
 }
 
+/// Returns the settings of the build, which are recorded in the metadata.
+fn build_info(
+    build_mode: BuildMode,
+    optimization_passes: OptimizationPasses,
+    keep_debug_symbols: bool,
+) -> Result<BuildInfo> {
+    let rustc = rustc_version::version_meta()?;
+    // e.g. `wasm-opt version 99 (version_99-79-gc12cc3f50)`
+    let wasm_opt_version = which::which("wasm-opt")
+        .ok()
+        .and_then(|path| Command::new(path).arg("--version").output().ok())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|version| {
+            version
+                .trim()
+                .trim_start_matches("wasm-opt version ")
+                .to_string()
+        });
+    Ok(BuildInfo {
+        rustc_version: rustc.semver.to_string(),
+        rustc_commit_hash: rustc.commit_hash,
+        rustc_commit_date: rustc.commit_date,
+        cargo_contract_version: env!("CARGO_CONTRACT_CLI_IMPL_VERSION").to_string(),
+        build_mode: build_mode.to_string(),
+        target: WASM_TARGET.to_string(),
+        wasm_opt_settings: WasmOptSettings {
+            version: wasm_opt_version,
+            optimization_passes: optimization_passes.to_string(),
+            keep_debug_symbols,
+        },
+    })
+}
+
 /// Checks if the wasm-opt binary under `wasm_opt_path` returns a version
 /// compatible with `cargo-contract`.
 ///
@@ -629,10 +753,18 @@ pub(crate) fn execute(args: ExecuteArgs) -> Result<BuildResult> {
         unstable_flags,
         optimization_passes,
         keep_debug_symbols,
+        strip_docs,
+        metadata_overrides,
         output_type,
+        features,
+        target_directory,
     } = args;
 
-    let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+    let mut crate_metadata = CrateMetadata::collect(&manifest_path)?;
+    if let Some(target_directory) = target_directory {
+        crate_metadata = crate_metadata.with_target_directory(target_directory);
+    }
+    metadata_overrides.apply(&mut crate_metadata);
 
     assert_compatible_ink_dependencies(&manifest_path, verbosity)?;
     if build_mode == BuildMode::Debug {
@@ -652,6 +784,7 @@ pub(crate) fn execute(args: ExecuteArgs) -> Result<BuildResult> {
             build_mode,
             verbosity,
             &unstable_flags,
+            &features,
         )?;
 
         maybe_println!(
@@ -682,6 +815,7 @@ pub(crate) fn execute(args: ExecuteArgs) -> Result<BuildResult> {
                 BuildMode::Release,
                 verbosity,
                 &unstable_flags,
+                &features,
             )?;
             (None, None)
         }
@@ -695,9 +829,12 @@ pub(crate) fn execute(args: ExecuteArgs) -> Result<BuildResult> {
             let metadata_result = super::metadata::execute(
                 &crate_metadata,
                 optimization_result.dest_wasm.as_path(),
+                &build_info(build_mode, optimization_passes, keep_debug_symbols)?,
                 verbosity,
                 build_artifact.steps(),
                 &unstable_flags,
+                strip_docs,
+                &features,
             )?;
             (Some(optimization_result), Some(metadata_result))
         }
@@ -864,6 +1001,10 @@ mod tests_ci_only {
                 // we choose zero optimization passes as the "cli" parameter
                 optimization_passes: Some(OptimizationPasses::Zero),
                 keep_debug_symbols: false,
+                strip_docs: false,
+                source_url: None,
+                homepage: None,
+                user: Vec::new(),
                 output_json: false,
             };
 
@@ -904,6 +1045,10 @@ mod tests_ci_only {
                 // we choose no optimization passes as the "cli" parameter
                 optimization_passes: None,
                 keep_debug_symbols: false,
+                strip_docs: false,
+                source_url: None,
+                homepage: None,
+                user: Vec::new(),
                 output_json: false,
             };
 
@@ -1069,6 +1214,10 @@ mod tests_ci_only {
                 unstable_options: UnstableOptions::default(),
                 optimization_passes: None,
                 keep_debug_symbols: false,
+                strip_docs: false,
+                source_url: None,
+                homepage: None,
+                user: Vec::new(),
                 output_json: false,
             };
             let res = cmd.exec().expect("build failed");
@@ -1237,4 +1386,51 @@ mod tests_ci_only {
             Ok(())
         })
     }
+
+    /// Creates a new ink! 4 contract project into `tmp_dir` and builds it with its metadata.
+    fn build_ink_4_project(tmp_dir: &Path, new_args: crate::cmd::new::ExecuteArgs) {
+        crate::cmd::new::execute("ink4_project", Some(tmp_dir), &new_args)
+            .expect("new project creation failed");
+        let manifest_path = ManifestPath::new(tmp_dir.join("ink4_project/Cargo.toml")).unwrap();
+        let args = crate::cmd::build::ExecuteArgs {
+            manifest_path,
+            build_artifact: BuildArtifacts::All,
+            ..Default::default()
+        };
+
+        let res = super::execute(args).expect("build failed");
+
+        let metadata = res.metadata_result.expect("metadata must be generated");
+        let metadata: serde_json::Value =
+            serde_json::from_slice(&std::fs::read(&metadata.dest_metadata).unwrap()).unwrap();
+        assert_eq!(metadata["version"], "4");
+    }
+
+    #[test]
+    fn builds_ink_4_project() {
+        with_tmp_dir(|tmp_dir| {
+            build_ink_4_project(
+                tmp_dir,
+                crate::cmd::new::ExecuteArgs {
+                    ink_version: Some("4.0.0".into()),
+                    ..Default::default()
+                },
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn builds_project_with_e2e_tests() {
+        with_tmp_dir(|tmp_dir| {
+            build_ink_4_project(
+                tmp_dir,
+                crate::cmd::new::ExecuteArgs {
+                    e2e: true,
+                    ..Default::default()
+                },
+            );
+            Ok(())
+        })
+    }
 }
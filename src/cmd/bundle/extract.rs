@@ -0,0 +1,159 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Splits a `.contract` bundle into its Wasm code and metadata.
+
+use crate::{
+    abi::decode_hex,
+    cmd::metadata::{blake2_hash, read_json},
+};
+use anyhow::{Context, Result};
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Extracts the Wasm code and the metadata of a `.contract` bundle, after checking that
+/// the code matches the hash recorded in the bundle.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "extract")]
+pub struct ExtractCommand {
+    /// The `.contract` bundle to extract
+    #[structopt(parse(from_os_str))]
+    bundle: PathBuf,
+    /// Extract the Wasm code, to `<contract>.wasm`
+    #[structopt(long)]
+    wasm: bool,
+    /// Extract the metadata, to `metadata.json`
+    #[structopt(long)]
+    metadata: bool,
+    /// The directory to extract to, the directory of the bundle if not given
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl ExtractCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let mut bundle = read_json(&self.bundle)?;
+        let name = bundle
+            .pointer("/contract/name")
+            .and_then(Value::as_str)
+            .unwrap_or("contract")
+            .to_string();
+        let source = bundle
+            .get_mut("source")
+            .and_then(Value::as_object_mut)
+            .ok_or_else(|| anyhow::anyhow!("The bundle has no `source` section"))?;
+        let wasm = match source.remove("wasm") {
+            Some(Value::String(wasm)) => {
+                decode_hex(&wasm).context("Failed to decode the Wasm code")?
+            }
+            _ => anyhow::bail!(
+                "{} contains no Wasm code, is it a `.contract` file?",
+                self.bundle.display()
+            ),
+        };
+        let hash = serde_json::to_value(blake2_hash(&wasm))?;
+        match source.get("hash") {
+            Some(expected) if expected == &hash => (),
+            Some(expected) => anyhow::bail!(
+                "The hash {} of the Wasm code does not match the hash {} of the bundle",
+                hash,
+                expected
+            ),
+            None => anyhow::bail!("The bundle contains no hash of the Wasm code"),
+        }
+
+        let dir = match &self.output {
+            Some(dir) => dir.clone(),
+            None => self.bundle.parent().map(PathBuf::from).unwrap_or_default(),
+        };
+        fs::create_dir_all(&dir)?;
+        // without flags, both components are extracted
+        let both = !self.wasm && !self.metadata;
+        let mut written = Vec::new();
+        if self.wasm || both {
+            let path = dir.join(format!("{}.wasm", name));
+            fs::write(&path, wasm)?;
+            written.push(path);
+        }
+        if self.metadata || both {
+            let path = dir.join("metadata.json");
+            fs::write(&path, serde_json::to_string_pretty(&bundle)?)?;
+            written.push(path);
+        }
+
+        let written = written
+            .iter()
+            .map(|path| path.display().to_string())
+            .collect::<Vec<_>>();
+        Ok(Some(format!("Extracted {}", written.join(" and "))))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    const WASM: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+    fn write_bundle(path: &std::path::Path, hash: Value) -> Result<PathBuf> {
+        let mut bundle = crate::abi::tests::metadata_v1();
+        bundle["source"]["wasm"] = Value::String("0x0061736d01000000".to_string());
+        bundle["source"]["hash"] = hash;
+        let bundle_path = path.join("bank.contract");
+        fs::write(&bundle_path, bundle.to_string())?;
+        Ok(bundle_path)
+    }
+
+    #[test]
+    fn extracts_wasm_and_metadata() {
+        with_tmp_dir(|path| {
+            let hash = serde_json::to_value(blake2_hash(&WASM))?;
+            let bundle = write_bundle(path, hash)?;
+            let cmd = ExtractCommand {
+                bundle,
+                wasm: false,
+                metadata: false,
+                output: Some(path.join("out")),
+            };
+            cmd.exec()?;
+
+            assert_eq!(fs::read(path.join("out").join("bank.wasm"))?, WASM);
+            let metadata = read_json(&path.join("out").join("metadata.json"))?;
+            assert!(metadata["source"].get("wasm").is_none());
+            assert_eq!(metadata["V1"], crate::abi::tests::metadata_v1()["V1"]);
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rejects_mismatching_hash() {
+        with_tmp_dir(|path| {
+            let bundle = write_bundle(path, Value::String(format!("0x{}", "00".repeat(32))))?;
+            let cmd = ExtractCommand {
+                bundle,
+                wasm: true,
+                metadata: false,
+                output: None,
+            };
+            let err = cmd.exec().unwrap_err();
+            assert!(err.to_string().contains("does not match the hash"));
+            assert!(!path.join("bank.wasm").exists());
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,39 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Works with `.contract` bundles with `cargo contract bundle`.
+
+mod extract;
+
+use self::extract::ExtractCommand;
+use anyhow::Result;
+use structopt::StructOpt;
+
+/// Works with `.contract` bundles, which contain the metadata and Wasm code of a contract.
+#[derive(Debug, StructOpt)]
+pub enum BundleCommand {
+    /// Extract the Wasm code and the metadata of a bundle
+    #[structopt(name = "extract")]
+    Extract(ExtractCommand),
+}
+
+impl BundleCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match self {
+            BundleCommand::Extract(extract) => extract.exec(),
+        }
+    }
+}
@@ -0,0 +1,445 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use sp_core::H256;
+use structopt::StructOpt;
+use subxt::{
+    contracts::*,
+    sp_runtime::codec::{Compact, Encode},
+    Call, Client, DefaultNodeRuntime, Encoded,
+};
+
+use super::{
+    chain::{parse_account, parse_block_hash, AccountId},
+    confirm::Summary,
+    dry_run::{self, Costs, Weight},
+    encode::encode_args,
+    extrinsic_output::{load_abi, ExtrinsicOutput},
+    gas::{AutoRetryOpts, GasOpts, Limits},
+    offline,
+    submit::{self, SubmitError, Submitted},
+    token::{self, Balance},
+};
+use crate::{abi::ContractAbi, ExtrinsicOpts, HexData};
+
+/// Calls a message of an instantiated contract.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "call")]
+pub struct CallCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// The account of the contract to call, an Ethereum address on chains with 20 byte
+    /// accounts
+    #[structopt(long, parse(try_from_str = parse_account))]
+    contract: AccountId,
+    /// Hex encoded data to call a contract message, starting with its selector
+    #[structopt(long, required_unless = "message", conflicts_with = "message")]
+    data: Option<HexData>,
+    /// The label of the message to call, whose arguments are encoded with the metadata
+    #[structopt(long)]
+    message: Option<String>,
+    /// The arguments of the message, as JSON or as plain strings
+    #[structopt(long, requires = "message")]
+    args: Vec<String>,
+    /// A JSON file with the arguments of the message, keyed by their names
+    #[structopt(
+        long,
+        parse(from_os_str),
+        requires = "message",
+        conflicts_with = "args"
+    )]
+    args_file: Option<PathBuf>,
+    /// Transfers this value to the contract with the call: plancks, or an amount of the token
+    /// of the chain, e.g. `1.5DOT`
+    #[structopt(long)]
+    value: Option<Balance>,
+    /// Transfers this value in plancks instead of `--value`
+    #[structopt(long, conflicts_with = "value")]
+    value_raw: Option<u128>,
+    #[structopt(flatten)]
+    gas: GasOpts,
+    /// Maximum balance to be charged for the storage the call adds, unbounded if not given:
+    /// plancks, or an amount of the token of the chain, e.g. `0.1DOT`
+    #[structopt(long)]
+    storage_deposit_limit: Option<Balance>,
+    #[structopt(flatten)]
+    auto_retry: AutoRetryOpts,
+    /// Execute the call on the state of the node without submitting it, and report its outcome
+    #[structopt(long)]
+    dry_run: bool,
+    /// The hash of the block whose state to execute the dry run on, instead of the best block
+    #[structopt(long, parse(try_from_str = parse_block_hash), requires = "dry-run")]
+    at: Option<H256>,
+    /// Path to the Cargo.toml of the contract whose metadata encodes the arguments and decodes
+    /// the returned value and the events
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the events and block of the extrinsic as JSON
+    #[structopt(long, conflicts_with = "dry-run")]
+    output_json: bool,
+}
+
+/// The `Contracts::call` Call of versions of the contracts pallet with a storage deposit
+/// limit, and of the ones metering the proof size, which `subxt` does not provide. It is also
+/// the Call of chains with Ethereum addresses, whose `dest` is no `MultiAddress`.
+pub(super) struct CallWithLimitCall<'a> {
+    pub dest: &'a AccountId,
+    pub value: u128,
+    pub gas_limit: Weight,
+    pub storage_deposit_limit: Option<u128>,
+    pub data: &'a [u8],
+}
+
+impl Encode for CallWithLimitCall<'_> {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Vec::new();
+        self.dest.encode_address_to(&mut encoded);
+        Compact(self.value).encode_to(&mut encoded);
+        self.gas_limit.encode_to(&mut encoded);
+        self.storage_deposit_limit
+            .map(Compact)
+            .encode_to(&mut encoded);
+        self.data.encode_to(&mut encoded);
+        encoded
+    }
+}
+
+impl Call<DefaultNodeRuntime> for CallWithLimitCall<'_> {
+    const MODULE: &'static str = dry_run::CONTRACTS;
+    const FUNCTION: &'static str = "call";
+}
+
+impl CallCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?;
+        let value = match (self.value_raw, &self.value) {
+            (Some(value), _) => value,
+            (None, Some(value)) => value.plancks(&self.extrinsic_opts.node.url()?)?,
+            (None, None) => 0,
+        };
+        let storage_deposit_limit = match &self.storage_deposit_limit {
+            Some(limit) => Some(limit.plancks(&self.extrinsic_opts.node.url()?)?),
+            None => None,
+        };
+        let data = match (&self.data, &self.message) {
+            (Some(data), _) => data.0.clone(),
+            (None, label) => {
+                let abi = abi
+                    .as_ref()
+                    .context("Encoding the arguments requires the metadata of the contract")?;
+                let label = label.as_deref().context("Missing `--message`")?;
+                encode_args(
+                    abi,
+                    abi.message(label)?,
+                    &self.args,
+                    self.args_file.as_ref(),
+                )?
+            }
+        };
+        let return_type = abi.as_ref().and_then(|abi| {
+            let message = abi
+                .messages
+                .iter()
+                .find(|message| data.starts_with(&message.selector.0))?;
+            Some((&abi.types, message.return_type.as_ref()?.id))
+        });
+        // the storage deposit and the required gas are only reported by a dry run, which also
+        // explains why the call fails, and is the outcome of `--dry-run`
+        let dry_run = dry_run::call(
+            &self.extrinsic_opts,
+            &self.contract,
+            value,
+            self.gas.dry_run_limit(),
+            storage_deposit_limit,
+            &data,
+            self.at,
+        );
+        if self.dry_run {
+            let result = dry_run?;
+            let limits = Limits {
+                gas: self.gas.limit(Some(result.required_gas())),
+                storage_deposit: storage_deposit_limit,
+            };
+            let costs = Costs::query(&self.extrinsic_opts, |cli| {
+                self.encode_call(cli, value, &data, limits)
+            })?;
+            print!("{}", dry_run::render_call(&result, return_type, &costs));
+            return match &result.result {
+                Ok(value) if !value.reverted() => Ok(None),
+                _ => Err(SubmitError::DryRunFailed.into()),
+            };
+        }
+
+        dry_run::warn_unbounded_deposit(storage_deposit_limit);
+        let (storage_deposit, required_gas, failure) = match dry_run {
+            Ok(result) => {
+                let failure = match &result.result {
+                    Ok(value) if !value.reverted() => None,
+                    _ => Some(dry_run::render_call(
+                        &result,
+                        return_type,
+                        &Costs::default(),
+                    )),
+                };
+                (result.storage_deposit, Some(result.required_gas()), failure)
+            }
+            Err(err) => {
+                log::debug!("Dry run before the call failed: {:?}", err);
+                (None, None, None)
+            }
+        };
+        if let Some(storage_deposit) = &storage_deposit {
+            storage_deposit.check_limit(storage_deposit_limit)?;
+        }
+        let limits = Limits {
+            gas: self.gas.limit(required_gas),
+            storage_deposit: storage_deposit_limit,
+        };
+        if self.extrinsic_opts.offline.is_some() {
+            return offline::write_unsigned(&self.extrinsic_opts, |cli| {
+                self.encode_call(cli, value, &data, limits)
+            })
+            .map(Some);
+        }
+
+        Summary::new("Call the contract")
+            .line("Contract", self.contract.to_string())
+            .input(
+                "Message",
+                abi.as_ref().map(|abi| (&abi.types, &abi.messages[..])),
+                &data,
+            )
+            .balance("Value", value)
+            .line("Gas limit", limits.gas)
+            .storage_deposit(storage_deposit)
+            .confirm(&self.extrinsic_opts, |cli| {
+                self.encode_call(cli, value, &data, limits)
+            })?;
+        let (submitted, limits) = self
+            .auto_retry
+            .submit(
+                limits,
+                || {
+                    dry_run::call(
+                        &self.extrinsic_opts,
+                        &self.contract,
+                        value,
+                        self.gas.dry_run_limit(),
+                        None,
+                        &data,
+                        None,
+                    )
+                    .ok()
+                    .map(|result| result.required_limits())
+                },
+                |limits| {
+                    submit_call(
+                        &self.extrinsic_opts,
+                        &self.contract,
+                        value,
+                        limits.gas,
+                        limits.storage_deposit,
+                        &data,
+                        abi.as_ref(),
+                    )
+                },
+            )
+            .map_err(|err| submit::explain_failure(err, failure.as_deref()))?;
+        let output = match submitted {
+            Submitted::Executed(output) => ExtrinsicOutput {
+                storage_deposit,
+                gas_limit: Some(limits.gas),
+                ..output
+            },
+            Submitted::Pending(pending) => return Ok(Some(pending.to_string())),
+        };
+        if self.output_json {
+            return Ok(Some(output.to_json()?));
+        }
+        // the token is only needed to show the storage deposit
+        let token = match output.storage_deposit {
+            Some(_) => {
+                async_std::task::block_on(token::fetch_or_none(&self.extrinsic_opts.node.url()?))
+            }
+            None => None,
+        };
+        Ok(Some(format!(
+            "Called the contract in block {:?}{}{}{}{}",
+            output.block_hash,
+            output.display_explorer_links(&self.extrinsic_opts)?,
+            output.display_gas(),
+            output.display_storage_deposit(token.as_ref()),
+            output.display_contract_events()
+        )))
+    }
+
+    /// Encodes the Call calling the contract with `value`, `data` and `limits`.
+    fn encode_call(
+        &self,
+        cli: &Client<DefaultNodeRuntime>,
+        value: u128,
+        data: &[u8],
+        limits: Limits,
+    ) -> Result<Encoded, subxt::Error> {
+        encode_call(
+            cli,
+            &self.contract,
+            value,
+            limits.gas,
+            limits.storage_deposit,
+            data,
+        )
+    }
+}
+
+/// Encodes the Call calling `contract` with `data`: the Call of the versions of the contracts
+/// pallet which take them if there is a storage deposit limit or the proof size is metered, or
+/// if `contract` is an Ethereum address.
+pub(super) fn encode_call(
+    cli: &Client<DefaultNodeRuntime>,
+    contract: &AccountId,
+    value: u128,
+    gas_limit: Weight,
+    storage_deposit_limit: Option<u128>,
+    data: &[u8],
+) -> Result<Encoded, subxt::Error> {
+    match (contract, storage_deposit_limit, gas_limit.proof_size) {
+        (AccountId::Substrate(contract), None, None) => cli.encode(CallCall {
+            dest: &contract.clone().into(),
+            value,
+            gas_limit: gas_limit.ref_time,
+            data,
+        }),
+        (_, storage_deposit_limit, _) => cli.encode(CallWithLimitCall {
+            dest: contract,
+            value,
+            gas_limit,
+            storage_deposit_limit,
+            data,
+        }),
+    }
+}
+
+/// Submits a call of `contract` with `data`, returning the outcome with the events of the
+/// contract decoded with `abi`.
+pub(super) fn submit_call(
+    extrinsic_opts: &ExtrinsicOpts,
+    contract: &AccountId,
+    value: u128,
+    gas_limit: Weight,
+    storage_deposit_limit: Option<u128>,
+    data: &[u8],
+    abi: Option<&ContractAbi>,
+) -> Result<Submitted<ExtrinsicOutput>> {
+    async_std::task::block_on(async {
+        let cli = submit::client(extrinsic_opts).await?;
+
+        let submitted = match (contract, storage_deposit_limit, gas_limit.proof_size) {
+            (AccountId::Substrate(contract), None, None) => {
+                let call = CallCall {
+                    dest: &contract.clone().into(),
+                    value,
+                    gas_limit: gas_limit.ref_time,
+                    data,
+                };
+                submit::watch(&cli, extrinsic_opts, call).await?
+            }
+            (_, storage_deposit_limit, _) => {
+                let call = CallWithLimitCall {
+                    dest: contract,
+                    value,
+                    gas_limit,
+                    storage_deposit_limit,
+                    data,
+                };
+                submit::watch(&cli, extrinsic_opts, call).await?
+            }
+        };
+        Ok(submitted.map(|events| ExtrinsicOutput {
+            contract: Some(contract.to_string()),
+            ..ExtrinsicOutput::new(&events, abi.map(|abi| (contract, abi)))
+        }))
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::crypto::AccountId32;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    fn parse(args: &[&str]) -> Result<CallCommand, structopt::clap::Error> {
+        let common = [
+            "call",
+            "--contract",
+            ALICE,
+            "--data",
+            "00",
+            "--suri",
+            "//Alice",
+        ];
+        CallCommand::from_iter_safe(common.iter().chain(args))
+    }
+
+    #[test]
+    fn takes_balances_in_units_of_the_token() {
+        let units = |amount: &str| Balance::Units {
+            amount: amount.to_string(),
+            symbol: "DOT".to_string(),
+        };
+
+        let command = parse(&["--value", "1.5DOT", "--storage-deposit-limit", "0.1 DOT"]).unwrap();
+        assert_eq!(command.value, Some(units("1.5")));
+        assert_eq!(command.storage_deposit_limit, Some(units("0.1")));
+
+        let command = parse(&["--value-raw", "7", "--storage-deposit-limit", "100"]).unwrap();
+        assert_eq!(command.value_raw, Some(7));
+        assert_eq!(command.storage_deposit_limit, Some(Balance::Plancks(100)));
+
+        assert!(parse(&["--value", "1DOT", "--value-raw", "7"]).is_err());
+        assert!(parse(&["--storage-deposit-limit", "1.5"]).is_err());
+    }
+
+    #[test]
+    fn encodes_the_storage_deposit_limit_of_calls() {
+        let dest = AccountId32::from([1; 32]).into();
+        let encode = |storage_deposit_limit| {
+            CallWithLimitCall {
+                dest: &dest,
+                value: 0,
+                gas_limit: Weight {
+                    ref_time: 0,
+                    proof_size: Some(0),
+                },
+                storage_deposit_limit,
+                data: &[0xab],
+            }
+            .encode()
+        };
+
+        // the optional compact limit comes right before the data
+        assert!(encode(Some(4)).ends_with(&[1, 4 << 2, 1 << 2, 0xab]));
+        assert!(encode(None).ends_with(&[0, 1 << 2, 0xab]));
+        assert_eq!(encode(Some(4)).len(), encode(None).len() + 1);
+    }
+}
@@ -0,0 +1,469 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! What the extrinsic commands assume about the runtime of a chain: the signed extensions its
+//! extrinsics are signed with, read from its metadata or configured with `--signed-extensions`,
+//! and the types of its account ids and balances, which are detected from the accounts stored
+//! on chain.
+
+use std::{convert::TryFrom, fmt, str::FromStr};
+
+use anyhow::{Context, Result};
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::twox_128,
+    H256,
+};
+use subxt::sp_runtime::{
+    codec::{Compact, Encode, Output},
+    generic::Era,
+};
+
+use super::{convert_address::checksummed, dry_run::hex, events, rpc};
+use crate::abi::decode_hex;
+
+/// The signed extensions of the runtimes of `substrate` chains, in the order of their
+/// runtimes, which unsigned extrinsics written without them are signed with.
+pub(crate) const DEFAULT_SIGNED_EXTENSIONS: &[&str] = &[
+    "CheckSpecVersion",
+    "CheckTxVersion",
+    "CheckGenesis",
+    "CheckMortality",
+    "CheckNonce",
+    "CheckWeight",
+    "ChargeTransactionPayment",
+];
+
+/// The data of the signed extensions of an extrinsic.
+pub(crate) struct ExtensionData {
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    pub genesis_hash: H256,
+    pub nonce: u32,
+    pub tip: u128,
+    /// The id of the asset the fees are paid in, the native token if not given.
+    pub fee_asset: Option<u32>,
+    /// The era and the hash of the block it starts at, immortal if not given.
+    pub era: Option<(Era, H256)>,
+}
+
+/// Fetches the identifiers of the signed extensions of the runtime of the chain at `url`, in
+/// the order of the runtime.
+pub(crate) async fn fetch_signed_extensions(url: &url::Url) -> Result<Vec<String>> {
+    let metadata: String = rpc::request(url, "state_getMetadata", vec![]).await?;
+    let metadata = events::runtime_metadata(&decode_hex(&metadata)?)?;
+    Ok(metadata
+        .extrinsic
+        .signed_extensions
+        .into_iter()
+        .map(events::decoded)
+        .collect())
+}
+
+/// Returns the data of the signed extensions `names` which is part of the extrinsic, and the
+/// data which is only signed.
+///
+/// Fails for extensions whose data is not known, naming them, as the extrinsic would be
+/// invalid without it.
+pub(crate) fn encode_signed_extensions(
+    names: &[String],
+    data: &ExtensionData,
+) -> Result<(Vec<u8>, Vec<u8>)> {
+    let mut extra = Vec::new();
+    let mut additional = Vec::new();
+    let mut unknown = Vec::new();
+    for name in names {
+        match name.as_str() {
+            "CheckSpecVersion" => data.spec_version.encode_to(&mut additional),
+            "CheckTxVersion" => data.transaction_version.encode_to(&mut additional),
+            "CheckGenesis" => data.genesis_hash.encode_to(&mut additional),
+            "CheckMortality" | "CheckEra" => match &data.era {
+                Some((era, block_hash)) => {
+                    era.encode_to(&mut extra);
+                    block_hash.encode_to(&mut additional);
+                }
+                None => {
+                    Era::Immortal.encode_to(&mut extra);
+                    data.genesis_hash.encode_to(&mut additional);
+                }
+            },
+            "CheckNonce" => Compact(data.nonce).encode_to(&mut extra),
+            "ChargeTransactionPayment" => Compact(data.tip).encode_to(&mut extra),
+            "ChargeAssetTxPayment" => {
+                Compact(data.tip).encode_to(&mut extra);
+                data.fee_asset.encode_to(&mut extra);
+            }
+            "CheckMetadataHash" => {
+                // the metadata hash is not checked
+                0u8.encode_to(&mut extra);
+                None::<H256>.encode_to(&mut additional);
+            }
+            "CheckNonZeroSender" | "CheckWeight" | "PrevalidateAttests" => (),
+            name => unknown.push(name),
+        }
+    }
+    if !unknown.is_empty() {
+        anyhow::bail!(
+            "The data of the signed extensions {} is not known, they cannot be encoded",
+            unknown.join(", ")
+        )
+    }
+    Ok((extra, additional))
+}
+
+/// Parses an account id given as an SS58 address or as its hex encoded 32 bytes.
+///
+/// Ethereum addresses of chains with 20 byte account ids are recognized, but refused, as only
+/// the extrinsic commands and `watch` support them.
+pub(crate) fn parse_account_id(input: &str) -> Result<AccountId32> {
+    match parse_account(input)? {
+        AccountId::Substrate(account_id) => Ok(account_id),
+        AccountId::Ethereum(_) => anyhow::bail!(
+            "{} is an Ethereum address, but only 32 byte account ids are supported here",
+            input
+        ),
+    }
+}
+
+/// Parses an account id given as an SS58 address, as its hex encoded 32 bytes, or as the
+/// `0x` prefixed 20 bytes of an Ethereum address.
+pub(crate) fn parse_account(input: &str) -> Result<AccountId> {
+    if let Ok(account_id) = AccountId32::from_ss58check(input) {
+        return Ok(AccountId::Substrate(account_id));
+    }
+    if !input.starts_with("0x") {
+        anyhow::bail!("Invalid SS58 address {}", input)
+    }
+    let bytes = decode_hex(input)?;
+    AccountId::from_bytes(&bytes).with_context(|| {
+        format!(
+            "{} is neither a 32 byte account id nor a 20 byte Ethereum address",
+            input
+        )
+    })
+}
+
+/// The account id of a chain: the 32 bytes of `substrate` chains, or the 20 byte Ethereum
+/// address of chains with `AccountId20`.
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub(crate) enum AccountId {
+    Substrate(AccountId32),
+    Ethereum([u8; 20]),
+}
+
+impl AccountId {
+    /// Returns the account id of the 32 or 20 `bytes`.
+    pub fn from_bytes(bytes: &[u8]) -> Option<Self> {
+        if let Ok(bytes) = <[u8; 32]>::try_from(bytes) {
+            return Some(AccountId::Substrate(bytes.into()));
+        }
+        <[u8; 20]>::try_from(bytes).ok().map(AccountId::Ethereum)
+    }
+
+    pub fn as_bytes(&self) -> &[u8] {
+        match self {
+            AccountId::Substrate(account_id) => account_id.as_ref(),
+            AccountId::Ethereum(address) => address,
+        }
+    }
+
+    /// Returns the 32 byte account id, failing for Ethereum addresses, which `feature` does
+    /// not support.
+    pub fn substrate(&self, feature: &str) -> Result<&AccountId32> {
+        match self {
+            AccountId::Substrate(account_id) => Ok(account_id),
+            AccountId::Ethereum(_) => anyhow::bail!(
+                "{} requires 32 byte account ids, but {} is an Ethereum address",
+                feature,
+                self
+            ),
+        }
+    }
+
+    /// Encodes the account as the address of Calls: the `MultiAddress` of 32 byte account ids,
+    /// or the 20 bytes of an Ethereum address, which chains with `AccountId20` look up as is.
+    pub fn encode_address_to<T: Output>(&self, dest: &mut T) {
+        if let AccountId::Substrate(_) = self {
+            // the `Id` variant of `MultiAddress`
+            dest.push_byte(0);
+        }
+        dest.write(self.as_bytes());
+    }
+}
+
+impl From<AccountId32> for AccountId {
+    fn from(account_id: AccountId32) -> Self {
+        AccountId::Substrate(account_id)
+    }
+}
+
+impl FromStr for AccountId {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        parse_account(input)
+    }
+}
+
+impl fmt::Display for AccountId {
+    /// Formats the SS58 address of 32 byte account ids, and the checksummed Ethereum address of
+    /// 20 byte ones.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AccountId::Substrate(account_id) => write!(f, "{}", account_id.to_ss58check()),
+            AccountId::Ethereum(address) => write!(f, "{}", checksummed(address)),
+        }
+    }
+}
+
+/// Encodes the account id as its bytes, as the runtime does.
+impl Encode for AccountId {
+    fn encode(&self) -> Vec<u8> {
+        self.as_bytes().to_vec()
+    }
+}
+
+/// Parses a `0x` prefixed block hash.
+pub(crate) fn parse_block_hash(input: &str) -> Result<H256> {
+    let bytes = decode_hex(input)?;
+    if bytes.len() != 32 {
+        anyhow::bail!("Block hash should be 32 bytes in length")
+    }
+    Ok(H256::from_slice(&bytes))
+}
+
+/// The sizes of the account ids and balances of a chain.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ChainTypes {
+    pub account_id_bytes: usize,
+    /// Not known for the accounts of runtimes with unknown account data.
+    pub balance_bytes: Option<usize>,
+}
+
+impl ChainTypes {
+    /// Detects the types of the chain at `url` from the first account stored in
+    /// `System.Account`, if there is any.
+    pub async fn detect(url: &url::Url) -> Result<Option<Self>> {
+        let mut prefix = twox_128(b"System").to_vec();
+        prefix.extend(&twox_128(b"Account"));
+        let keys: Vec<String> = rpc::request(
+            url,
+            "state_getKeysPaged",
+            vec![hex(&prefix).into(), 1.into()],
+        )
+        .await?;
+        let key = match keys.first() {
+            Some(key) => decode_hex(key)?,
+            None => return Ok(None),
+        };
+        let info: Option<String> =
+            rpc::request(url, "state_getStorage", vec![hex(&key).into()]).await?;
+        let info = decode_hex(&info.unwrap_or_default())?;
+        Ok(Some(Self::from_account(&key[prefix.len()..], &info)))
+    }
+
+    /// Returns the types of the account stored under the `key` hashed with
+    /// `Blake2_128Concat`, whose account info is `info`.
+    fn from_account(key: &[u8], info: &[u8]) -> Self {
+        ChainTypes {
+            // the account id follows its 16 byte hash
+            account_id_bytes: key.len().saturating_sub(16),
+            balance_bytes: balance_bytes(info.len()),
+        }
+    }
+
+    /// Fails if the extrinsic commands do not support the types: 32 byte account ids, or the
+    /// 20 byte accounts of chains with Ethereum-style `AccountId20`, and 16 or 8 byte
+    /// balances.
+    pub fn check_supported(&self) -> Result<()> {
+        if self.account_id_bytes != 32 && self.account_id_bytes != 20 {
+            anyhow::bail!(
+                "The chain has {} byte account ids, but only chains with 32 or 20 byte account \
+                 ids are supported",
+                self.account_id_bytes
+            )
+        }
+        if let Some(balance_bytes) = self.balance_bytes.filter(|bytes| ![16, 8].contains(bytes)) {
+            anyhow::bail!(
+                "The chain has {} bit balances, but only chains with 128 or 64 bit balances are \
+                 supported",
+                balance_bytes * 8
+            )
+        }
+        Ok(())
+    }
+
+    /// Fails if `account` is no account of the chain, e.g. an sr25519 key signing for a chain
+    /// with Ethereum accounts.
+    pub fn check_account(&self, account: &AccountId) -> Result<()> {
+        match (self.account_id_bytes, account) {
+            (20, AccountId::Substrate(_)) => anyhow::bail!(
+                "The chain has Ethereum-style 20 byte account ids, sign with the private key of \
+                 an Ethereum account and `--scheme ethereum` instead of {}",
+                account
+            ),
+            (32, AccountId::Ethereum(_)) => anyhow::bail!(
+                "The chain has 32 byte account ids, but {} is an Ethereum address",
+                account
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Returns the size of the balances of the account info of `len` bytes stored in
+/// `System.Account`, if its layout is known.
+pub(crate) fn balance_bytes(len: usize) -> Option<usize> {
+    // the nonce and 1 to 3 reference counters are followed by 4 balances
+    match len {
+        72 | 76 | 80 => Some(16),
+        40 | 44 | 48 => Some(8),
+        _ => None,
+    }
+}
+
+impl Default for ChainTypes {
+    /// The types of chains with 32 byte account ids and 128 bit balances.
+    fn default() -> Self {
+        ChainTypes {
+            account_id_bytes: 32,
+            balance_bytes: Some(16),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_the_default_signed_extensions() {
+        let names = DEFAULT_SIGNED_EXTENSIONS
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let data = ExtensionData {
+            spec_version: 1,
+            transaction_version: 2,
+            genesis_hash: H256::repeat_byte(1),
+            nonce: 7,
+            tip: 0,
+            fee_asset: None,
+            era: None,
+        };
+        let (extra, additional) = encode_signed_extensions(&names, &data).unwrap();
+        // the immortal era, the nonce and no tip
+        assert_eq!(extra, vec![0, 28, 0]);
+        let expected = (1u32, 2u32, H256::repeat_byte(1), H256::repeat_byte(1)).encode();
+        assert_eq!(additional, expected);
+
+        let mut names = names;
+        names.insert(0, "CheckNonZeroSender".to_string());
+        names.push("ChargeAssetTxPayment".to_string());
+        let (extra, _) = encode_signed_extensions(&names, &data).unwrap();
+        assert_eq!(extra, vec![0, 28, 0, 0, 0]);
+
+        let data = ExtensionData {
+            fee_asset: Some(1984),
+            ..data
+        };
+        let (extra, _) = encode_signed_extensions(&names[..1], &data).unwrap();
+        assert!(extra.is_empty());
+        let (extra, _) = encode_signed_extensions(&names[names.len() - 1..], &data).unwrap();
+        assert_eq!(extra, [&[0, 1][..], &1984u32.to_le_bytes()].concat());
+    }
+
+    #[test]
+    fn fails_on_signed_extensions_with_unknown_data() {
+        let names = ["CheckNonce", "CheckVesting", "CheckWeight", "SetEvmOrigin"]
+            .iter()
+            .map(|name| name.to_string())
+            .collect::<Vec<_>>();
+        let data = ExtensionData {
+            spec_version: 1,
+            transaction_version: 2,
+            genesis_hash: H256::zero(),
+            nonce: 0,
+            tip: 0,
+            fee_asset: None,
+            era: None,
+        };
+        let err = encode_signed_extensions(&names, &data).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "The data of the signed extensions CheckVesting, SetEvmOrigin is not known, they \
+             cannot be encoded"
+        );
+    }
+
+    #[test]
+    fn detects_the_types_of_stored_accounts() {
+        let key = [vec![0; 16], vec![1; 32]].concat();
+        let types = ChainTypes::from_account(&key, &[0; 80]);
+        assert_eq!(types.account_id_bytes, 32);
+        assert_eq!(types.balance_bytes, Some(16));
+        assert!(types.check_supported().is_ok());
+
+        let key = [vec![0; 16], vec![1; 20]].concat();
+        let types = ChainTypes::from_account(&key, &[0; 80]);
+        assert_eq!(types.account_id_bytes, 20);
+        assert!(types.check_supported().is_ok());
+        assert!(types.check_account(&AccountId::Ethereum([1; 20])).is_ok());
+        let alice = AccountId::Substrate([1; 32].into());
+        assert!(types.check_account(&alice).is_err());
+
+        let types = ChainTypes::from_account(&key, &[0; 48]);
+        assert_eq!(types.balance_bytes, Some(8));
+        assert!(types.check_supported().is_ok());
+
+        let types = ChainTypes {
+            account_id_bytes: 32,
+            balance_bytes: Some(4),
+        };
+        assert!(types.check_supported().is_err());
+    }
+
+    #[test]
+    fn parses_account_ids() {
+        let alice = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+        let alice_hex = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+        assert_eq!(
+            parse_account_id(alice_hex).unwrap(),
+            parse_account_id(alice).unwrap()
+        );
+        let err = parse_account_id("0x6be02d1d3665660d22ff9624b7be0551ee1ac91b").unwrap_err();
+        assert!(err.to_string().contains("Ethereum address"));
+        assert!(parse_account_id("0x0102").is_err());
+    }
+
+    #[test]
+    fn parses_ethereum_addresses() {
+        let address = "0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac";
+        let account = parse_account(&address.to_lowercase()).unwrap();
+        assert!(matches!(account, AccountId::Ethereum(_)));
+        // printed with the checksum of EIP-55
+        assert_eq!(account.to_string(), address);
+        assert!(account.substrate("`--multisig`").is_err());
+
+        let mut encoded = Vec::new();
+        account.encode_address_to(&mut encoded);
+        assert_eq!(encoded, account.as_bytes());
+        let alice = parse_account("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap();
+        let mut encoded = Vec::new();
+        alice.encode_address_to(&mut encoded);
+        assert_eq!(encoded, [&[0][..], alice.as_bytes()].concat());
+    }
+}
@@ -0,0 +1,248 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks that the contracts pallet of a chain supports contract code before uploading it, so
+//! known incompatibilities are reported instead of failing the upload on chain.
+
+use anyhow::Result;
+use colored::Colorize;
+use parity_wasm::elements::{External, Module};
+use serde::Deserialize;
+use sp_core::hashing::{blake2_64, twox_128};
+
+use super::{dry_run::hex, rpc};
+use crate::abi::decode_hex;
+
+/// The modules of host functions which only newer versions of the contracts pallet provide,
+/// with the storage version of the contracts pallet which introduced them.
+const HOST_FUNCTION_MODULES: &[(&str, u16)] = &[("seal0", 0), ("seal1", 5), ("seal2", 9)];
+
+/// The module of the host functions which are only provided by chains enabling the unstable
+/// interfaces of the contracts pallet.
+const UNSTABLE_MODULE: &str = "__unstable__";
+
+/// The storage version of the first contracts pallet which ink! 4 contracts can run on.
+const INK_4_STORAGE_VERSION: u16 = 9;
+
+/// The version of the contracts pallet of a chain.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Pallet {
+    /// The storage version of the pallet, which is increased by every migration.
+    pub storage_version: u16,
+    /// The version of the `ContractsApi` runtime API, `None` if the chain has no contracts
+    /// pallet.
+    pub api_version: Option<u32>,
+}
+
+impl Pallet {
+    /// Queries the version of the contracts pallet of the chain at `url`.
+    pub(crate) async fn query(url: &url::Url) -> Result<Self> {
+        let mut key = twox_128(b"Contracts").to_vec();
+        key.extend(&twox_128(b":__STORAGE_VERSION__:"));
+        let version: Option<String> =
+            rpc::request(url, "state_getStorage", vec![hex(&key).into()]).await?;
+        // the storage version is a little endian `u16`, before its introduction there was none
+        let storage_version = match version {
+            Some(version) => decode_hex(&version)?
+                .iter()
+                .take(2)
+                .rev()
+                .fold(0, |version, byte| version << 8 | u16::from(*byte)),
+            None => 0,
+        };
+
+        let runtime: RuntimeVersion = rpc::request(url, "state_getRuntimeVersion", vec![]).await?;
+        let api_id = hex(&blake2_64(b"ContractsApi"));
+        let api_version = runtime
+            .apis
+            .iter()
+            .find(|(id, _)| *id == api_id)
+            .map(|(_, version)| *version);
+        Ok(Pallet {
+            storage_version,
+            api_version,
+        })
+    }
+}
+
+#[derive(Deserialize)]
+struct RuntimeVersion {
+    apis: Vec<(String, u32)>,
+}
+
+/// Returns the host functions the Wasm `code` imports, as their module and name.
+pub(crate) fn imports(code: &[u8]) -> Result<Vec<(String, String)>> {
+    let module: Module = parity_wasm::deserialize_buffer(code)?;
+    Ok(module
+        .import_section()
+        .map(|section| {
+            section
+                .entries()
+                .iter()
+                .filter(|entry| matches!(entry.external(), External::Function(_)))
+                .map(|entry| (entry.module().to_string(), entry.field().to_string()))
+                .collect()
+        })
+        .unwrap_or_default())
+}
+
+/// Returns the known incompatibilities of code which imports the host functions `imports` and
+/// was written in `language`, e.g. `ink! 4.0.0`, with the contracts `pallet`.
+pub(crate) fn incompatibilities(
+    pallet: &Pallet,
+    imports: &[(String, String)],
+    language: Option<&str>,
+) -> Vec<String> {
+    if pallet.api_version.is_none() {
+        return vec!["The chain does not provide the contracts pallet".to_string()];
+    }
+    let mut incompatibilities = Vec::new();
+    for (module, name) in imports {
+        if module == UNSTABLE_MODULE {
+            incompatibilities.push(format!(
+                "The code imports the unstable host function `{}`, which is only provided by \
+                chains enabling the unstable interfaces of the contracts pallet",
+                name
+            ));
+            continue;
+        }
+        match HOST_FUNCTION_MODULES
+            .iter()
+            .find(|(known, _)| known == module)
+        {
+            Some((_, version)) if pallet.storage_version < *version => {
+                incompatibilities.push(format!(
+                    "The code imports the host function `{}::{}`, which requires the contracts \
+                    pallet with storage version {} or newer, the chain has version {}",
+                    module, name, version, pallet.storage_version
+                ))
+            }
+            Some(_) => (),
+            None => incompatibilities.push(format!(
+                "The code imports `{}::{}` from a module of host functions the contracts \
+                pallet does not know",
+                module, name
+            )),
+        }
+    }
+
+    let ink_major = language
+        .and_then(|language| language.strip_prefix("ink! "))
+        .and_then(|version| version.split('.').next())
+        .and_then(|major| major.parse::<u32>().ok());
+    if ink_major >= Some(4) && pallet.storage_version < INK_4_STORAGE_VERSION {
+        incompatibilities.push(format!(
+            "The code is written in {}, which requires the contracts pallet with storage \
+            version {} or newer, the chain has version {}",
+            language.unwrap_or_default(),
+            INK_4_STORAGE_VERSION,
+            pallet.storage_version
+        ));
+    }
+    incompatibilities
+}
+
+/// Warns about the known incompatibilities of the Wasm `code` written in `language` with the
+/// contracts pallet of the chain at `url`.
+///
+/// The check is best effort: if the chain can't be queried, nothing is reported.
+pub(crate) async fn warn_incompatibilities(url: &url::Url, code: &[u8], language: Option<&str>) {
+    let pallet = match Pallet::query(url).await {
+        Ok(pallet) => pallet,
+        Err(err) => {
+            log::debug!(
+                "Failed to query the version of the contracts pallet: {:?}",
+                err
+            );
+            return;
+        }
+    };
+    let imports = match imports(code) {
+        Ok(imports) => imports,
+        Err(err) => {
+            log::debug!("Failed to read the imports of the code: {:?}", err);
+            return;
+        }
+    };
+    for incompatibility in incompatibilities(&pallet, &imports, language) {
+        eprintln!("{} {}", "warning:".yellow().bold(), incompatibility);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Returns a Wasm module importing the functions `imports` and the memory `env::memory`.
+    fn module(imports: &[(&str, &str)]) -> Vec<u8> {
+        let name = |name: &str| {
+            let mut bytes = vec![name.len() as u8];
+            bytes.extend(name.as_bytes());
+            bytes
+        };
+        let mut entries = vec![imports.len() as u8 + 1];
+        for (module, field) in imports {
+            entries.extend(name(module));
+            entries.extend(name(field));
+            entries.extend(&[0x00, 0x00]);
+        }
+        entries.extend(name("env"));
+        entries.extend(name("memory"));
+        entries.extend(&[0x02, 0x00, 0x01]);
+
+        let mut code = vec![0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+        // a single type of functions without parameters and results
+        code.extend(&[0x01, 0x04, 0x01, 0x60, 0x00, 0x00]);
+        code.push(0x02);
+        code.push(entries.len() as u8);
+        code.extend(entries);
+        code
+    }
+
+    #[test]
+    fn reports_unsupported_host_functions() {
+        let code = module(&[
+            ("seal0", "seal_input"),
+            ("seal2", "set_storage"),
+            ("__unstable__", "take_storage"),
+        ]);
+        let imports = imports(&code).unwrap();
+        assert_eq!(imports.len(), 3);
+        assert_eq!(imports[1], ("seal2".to_string(), "set_storage".to_string()));
+
+        let pallet = Pallet {
+            storage_version: 8,
+            api_version: Some(1),
+        };
+        let reported = incompatibilities(&pallet, &imports, Some("ink! 4.0.0"));
+        assert_eq!(reported.len(), 3);
+        assert!(reported[0].contains("`seal2::set_storage`"));
+        assert!(reported[1].contains("`take_storage`"));
+        assert!(reported[2].contains("ink! 4.0.0"));
+
+        let pallet = Pallet {
+            storage_version: 9,
+            api_version: Some(2),
+        };
+        let imports = &imports[..2];
+        assert!(incompatibilities(&pallet, imports, Some("ink! 4.0.0")).is_empty());
+        let pallet = Pallet {
+            storage_version: 9,
+            api_version: None,
+        };
+        assert_eq!(incompatibilities(&pallet, imports, None).len(), 1);
+    }
+}
@@ -0,0 +1,249 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The summary of an extrinsic shown before submitting it, which is submitted once it is
+//! confirmed, or right away with `--skip-confirm`.
+
+use std::{
+    fmt::{Display, Write as _},
+    io::{self, BufRead, Write},
+};
+
+use anyhow::Result;
+use colored::Colorize;
+use subxt::{Client, DefaultNodeRuntime, Encoded};
+
+use super::{
+    dry_run::{self, StorageDeposit},
+    submit::{self, SubmitError},
+    token::{self, format_balance, Token},
+};
+use crate::{
+    abi::{MessageSpec, TypeRegistry},
+    ExtrinsicOpts,
+};
+
+/// The summary of an extrinsic about to be submitted.
+pub(crate) struct Summary {
+    /// What the extrinsic does, e.g. `Call the contract`.
+    action: &'static str,
+    lines: Vec<(&'static str, Line)>,
+}
+
+/// A line of the summary.
+enum Line {
+    Text(String),
+    /// An amount shown in units of the token of the chain.
+    Balance(u128),
+    StorageDeposit(StorageDeposit),
+}
+
+impl Summary {
+    pub fn new(action: &'static str) -> Self {
+        Summary {
+            action,
+            lines: Vec::new(),
+        }
+    }
+
+    pub fn line(mut self, label: &'static str, value: impl Display) -> Self {
+        self.lines.push((label, Line::Text(value.to_string())));
+        self
+    }
+
+    pub fn balance(mut self, label: &'static str, amount: u128) -> Self {
+        self.lines.push((label, Line::Balance(amount)));
+        self
+    }
+
+    /// Adds the storage deposit reported by a dry run, if there was one.
+    pub fn storage_deposit(mut self, storage_deposit: Option<StorageDeposit>) -> Self {
+        if let Some(storage_deposit) = storage_deposit {
+            let line = Line::StorageDeposit(storage_deposit);
+            self.lines.push(("Storage deposit", line));
+        }
+        self
+    }
+
+    /// Adds the constructor or message of `specs` which `data` calls, labeled with `label`,
+    /// and its arguments decoded with `types`. Without the metadata `data` is shown as hex.
+    pub fn input(
+        self,
+        label: &'static str,
+        abi: Option<(&TypeRegistry, &[MessageSpec])>,
+        data: &[u8],
+    ) -> Self {
+        match abi.and_then(|(types, specs)| decode_input(types, specs, data)) {
+            Some((spec, args)) => self.line(label, spec).line("Args", args),
+            None => self.line("Data", dry_run::hex(data)),
+        }
+    }
+
+    /// Shows the summary with the fee of the extrinsic with the Call encoded by `encode`, and
+    /// asks to confirm submitting it, unless `--skip-confirm` is given.
+    pub fn confirm<F>(self, extrinsic_opts: &ExtrinsicOpts, encode: F) -> Result<()>
+    where
+        F: FnOnce(&Client<DefaultNodeRuntime>) -> Result<Encoded, subxt::Error>,
+    {
+        if extrinsic_opts.skip_confirm {
+            return Ok(());
+        }
+        let fee = async_std::task::block_on(dry_run::query_fee(extrinsic_opts, encode))
+            .map_err(|err| log::debug!("Querying the fee of the extrinsic failed: {:?}", err))
+            .ok();
+        self.ask(extrinsic_opts, fee)
+    }
+
+    /// Shows the summary and asks to confirm submitting it, unless `--skip-confirm` is given,
+    /// for extrinsics whose fee is not known before submitting an earlier one.
+    pub fn confirm_without_fee(self, extrinsic_opts: &ExtrinsicOpts) -> Result<()> {
+        if extrinsic_opts.skip_confirm {
+            return Ok(());
+        }
+        self.ask(extrinsic_opts, None)
+    }
+
+    fn ask(self, extrinsic_opts: &ExtrinsicOpts, fee: Option<u128>) -> Result<()> {
+        let token = async_std::task::block_on(token::fetch_or_none(&extrinsic_opts.node.url()?));
+        let url = extrinsic_opts.node.url()?;
+        let network = match (&extrinsic_opts.node.network, &extrinsic_opts.fork_from) {
+            (_, Some(fork_from)) => format!("fork of {} ({})", fork_from, url),
+            (Some(network), None) => format!("{} ({})", network, url),
+            (None, None) => url.to_string(),
+        };
+        let account = submit::origin(extrinsic_opts)?.to_string();
+        eprint!("{}", self.render(&network, &account, fee, token.as_ref()));
+        eprint!("{} ", "Submit the extrinsic? [y/N]".bold());
+        io::stderr().flush()?;
+
+        let mut answer = String::new();
+        if io::stdin().lock().read_line(&mut answer)? == 0 {
+            return Err(SubmitError::Aborted(
+                "Submitting requires a confirmation, pass --skip-confirm to submit without one",
+            )
+            .into());
+        }
+        if !matches!(answer.trim().to_lowercase().as_str(), "y" | "yes") {
+            return Err(SubmitError::Aborted("The extrinsic was not submitted").into());
+        }
+        Ok(())
+    }
+
+    fn render(
+        &self,
+        network: &str,
+        account: &str,
+        fee: Option<u128>,
+        token: Option<&Token>,
+    ) -> String {
+        let mut out = String::new();
+        // writing to a `String` never fails
+        let _ = writeln!(out, "{}", self.action.bold());
+        let mut line = |label: &str, value: &str| {
+            let _ = writeln!(out, "  {} {}", format!("{}:", label).bold(), value);
+        };
+        line("Network", network);
+        line("Account", account);
+        for (label, value) in &self.lines {
+            let value = match value {
+                Line::Text(text) => text.clone(),
+                Line::Balance(amount) => format_balance(*amount, token),
+                Line::StorageDeposit(storage_deposit) => storage_deposit.format(token),
+            };
+            line(label, &value);
+        }
+        if let Some(fee) = fee {
+            line("Estimated fee", &format_balance(fee, token));
+        }
+        out
+    }
+}
+
+/// Returns the label of the constructor or message of `specs` which `data` calls and its
+/// decoded arguments, or `None` if `data` does not match any of them.
+fn decode_input(
+    types: &TypeRegistry,
+    specs: &[MessageSpec],
+    data: &[u8],
+) -> Option<(String, String)> {
+    let spec = specs
+        .iter()
+        .find(|spec| data.starts_with(&spec.selector.0))?;
+    let input = &mut &data[spec.selector.0.len()..];
+    let args = spec
+        .args
+        .iter()
+        .map(|arg| {
+            Ok(format!(
+                "{}: {}",
+                arg.label,
+                types.decode(arg.ty.id, input)?
+            ))
+        })
+        .collect::<Result<Vec<_>>>()
+        .ok()?;
+    Some((spec.label.clone(), args.join(", ")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{tests::metadata_v1, ContractAbi};
+
+    #[test]
+    fn renders_summary() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let withdraw = abi.message("withdraw").unwrap();
+        let mut data = withdraw.selector.0.to_vec();
+        data.extend(&5u128.to_le_bytes());
+
+        let summary = Summary::new("Call the contract")
+            .line(
+                "Contract",
+                "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+            )
+            .input("Message", Some((&abi.types, &abi.messages[..])), &data)
+            .balance("Value", 2_000_000_000_000)
+            .storage_deposit(Some(StorageDeposit::Charge(100)))
+            .input("Message", None, &[1, 2]);
+        let token = Token {
+            symbol: "ROC".to_string(),
+            decimals: 12,
+        };
+
+        colored::control::set_override(false);
+        assert_eq!(
+            summary.render(
+                "ws://localhost:9944/",
+                "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty",
+                Some(125_000_000_000),
+                Some(&token)
+            ),
+            "\
+Call the contract
+  Network: ws://localhost:9944/
+  Account: 5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty
+  Contract: 5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY
+  Message: withdraw
+  Args: value: 5
+  Value: 2000000000000 (2 ROC)
+  Storage deposit: charges 100 (0.0000000001 ROC)
+  Data: 0x0102
+  Estimated fee: 125000000000 (0.125 ROC)
+"
+        );
+    }
+}
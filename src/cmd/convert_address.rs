@@ -0,0 +1,215 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Converts addresses between their representations with `cargo contract convert-address`.
+
+use std::convert::TryFrom;
+
+use anyhow::Result;
+use serde::Serialize;
+use sp_core::hashing::blake2_256;
+use structopt::StructOpt;
+use tiny_keccak::{Hasher, Keccak};
+
+use super::key::{from_ss58, network_name, parse_prefix, to_ss58, NETWORKS};
+
+/// Converts an address between SS58 prefixes, hex account ids and Ethereum addresses.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "convert-address")]
+pub struct ConvertAddressCommand {
+    /// An SS58 address, a hex encoded account id, a hex encoded compressed ECDSA public key
+    /// or an Ethereum address
+    address: String,
+    /// The SS58 prefix of the network to print the address for, instead of the prefixes of
+    /// Polkadot, Kusama, Astar and Substrate. May be given several times
+    #[structopt(long = "prefix", number_of_values = 1, parse(try_from_str = parse_prefix))]
+    prefixes: Vec<u16>,
+    /// Print the representations of the address as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The representations of an address.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Converted {
+    /// The compressed ECDSA public key the account id is derived from.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub public_key: Option<String>,
+    pub account_id: String,
+    pub ss58: Vec<Ss58Address>,
+    /// The checksummed Ethereum address of an ECDSA public key or of the input.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub ethereum: Option<String>,
+}
+
+/// The SS58 address of an account id on a network.
+#[derive(Debug, PartialEq, Eq, Serialize)]
+pub struct Ss58Address {
+    pub network: String,
+    pub address: String,
+}
+
+impl ConvertAddressCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let prefixes = if self.prefixes.is_empty() {
+            NETWORKS.iter().map(|(prefix, _)| *prefix).collect()
+        } else {
+            self.prefixes.clone()
+        };
+        let converted = convert(&self.address, &prefixes)?;
+        if self.output_json {
+            return Ok(Some(serde_json::to_string_pretty(&converted)?));
+        }
+        if let Some(public_key) = &converted.public_key {
+            println!("Public key (hex):  {}", public_key);
+        }
+        println!("Account ID:        {}", converted.account_id);
+        for Ss58Address { network, address } in &converted.ss58 {
+            println!("{:<18}{}", format!("SS58 ({}):", network), address);
+        }
+        if let Some(ethereum) = &converted.ethereum {
+            println!("Ethereum address:  {}", ethereum);
+        }
+        Ok(None)
+    }
+}
+
+/// Converts `input` into the representations of its account id, with the SS58 addresses
+/// of `prefixes`.
+///
+/// The account id of an ECDSA public key is its blake2 hash, like for the ECDSA keys of
+/// Substrate, and the account id of an Ethereum address is the one `pallet-evm` maps it to.
+pub fn convert(input: &str, prefixes: &[u16]) -> Result<Converted> {
+    let (account_id, public_key, ethereum) = if let Ok((_, account_id)) = from_ss58(input) {
+        let account_id: &[u8] = account_id.as_ref();
+        (<[u8; 32]>::try_from(account_id)?, None, None)
+    } else {
+        let bytes = input
+            .strip_prefix("0x")
+            .and_then(|hex| hex::decode(hex).ok())
+            .ok_or_else(|| anyhow::anyhow!("`{}` is neither SS58 nor 0x prefixed hex", input))?;
+        match bytes.len() {
+            32 => (<[u8; 32]>::try_from(bytes.as_slice())?, None, None),
+            33 => {
+                let public_key = <[u8; 33]>::try_from(bytes.as_slice())?;
+                let ethereum = ethereum_address(&public_key)?;
+                (blake2_256(&public_key), Some(public_key), Some(ethereum))
+            }
+            20 => {
+                let ethereum = <[u8; 20]>::try_from(bytes.as_slice())?;
+                let mapped = blake2_256(&[&b"evm:"[..], &ethereum].concat());
+                (mapped, None, Some(ethereum))
+            }
+            len => anyhow::bail!(
+                "Expected an account id of 32 bytes, a compressed public key of 33 bytes or an \
+                 Ethereum address of 20 bytes, got {} bytes",
+                len
+            ),
+        }
+    };
+    Ok(Converted {
+        public_key: public_key.map(|key| format!("0x{}", hex::encode(key))),
+        account_id: format!("0x{}", hex::encode(account_id)),
+        ss58: prefixes
+            .iter()
+            .map(|prefix| Ss58Address {
+                network: network_name(*prefix),
+                address: to_ss58(*prefix, &account_id),
+            })
+            .collect(),
+        ethereum: ethereum.map(|address| checksummed(&address)),
+    })
+}
+
+/// Returns the Ethereum address of a compressed ECDSA public key: the last 20 bytes of the
+/// keccak hash of the uncompressed key.
+pub(crate) fn ethereum_address(public_key: &[u8; 33]) -> Result<[u8; 20]> {
+    let public_key = secp256k1::PublicKey::parse_compressed(public_key)
+        .map_err(|err| anyhow::anyhow!("Invalid ECDSA public key: {:?}", err))?;
+    // the uncompressed key is prefixed with its `0x04` tag
+    let hash = keccak(&public_key.serialize()[1..]);
+    Ok(<[u8; 20]>::try_from(&hash[12..])?)
+}
+
+/// Returns the address with the EIP-55 checksum in the case of its letters.
+pub(crate) fn checksummed(address: &[u8; 20]) -> String {
+    let hex = hex::encode(address);
+    let hash = keccak(hex.as_bytes());
+    let digits = hex
+        .chars()
+        .enumerate()
+        .map(|(i, c)| {
+            let nibble = (hash[i / 2] >> (if i % 2 == 0 { 4 } else { 0 })) & 0x0f;
+            if nibble >= 8 {
+                c.to_ascii_uppercase()
+            } else {
+                c
+            }
+        })
+        .collect::<String>();
+    format!("0x{}", digits)
+}
+
+fn keccak(data: &[u8]) -> [u8; 32] {
+    let mut hasher = Keccak::v256();
+    hasher.update(data);
+    let mut hash = [0; 32];
+    hasher.finalize(&mut hash);
+    hash
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const ALICE_HEX: &str = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+
+    #[test]
+    fn converts_between_representations() {
+        let converted = convert(ALICE, &[42]).unwrap();
+        assert_eq!(converted.account_id, ALICE_HEX);
+        assert_eq!(
+            converted.ss58,
+            vec![Ss58Address {
+                network: "substrate".to_string(),
+                address: ALICE.to_string(),
+            }]
+        );
+        assert_eq!(convert(ALICE_HEX, &[42]).unwrap(), converted);
+
+        // the public key of the secret key `1`
+        let converted = convert(
+            "0x0279be667ef9dcbbac55a06295ce870b07029bfcdb2dce28d959f2815b16f81798",
+            &[],
+        )
+        .unwrap();
+        assert_eq!(
+            converted.ethereum.as_deref(),
+            Some("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf")
+        );
+
+        let converted = convert("0x7e5f4552091a69125d5dfcb7b8c2659029395bdf", &[]).unwrap();
+        assert_eq!(
+            converted.ethereum.as_deref(),
+            Some("0x7E5F4552091A69125d5DfCb7b8C2659029395Bdf")
+        );
+        assert!(converted.public_key.is_none());
+
+        assert!(convert("0x1234", &[]).is_err());
+        assert!(convert("alice", &[]).is_err());
+    }
+}
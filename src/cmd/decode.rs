@@ -0,0 +1,316 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decodes the input of constructors and messages and the data of events with
+//! `cargo contract decode`.
+
+use crate::{
+    abi::{decode_hex, ContractAbi, DecodedValue, MessageSpec, TypeRegistry},
+    cmd::metadata::built_metadata_path,
+};
+use anyhow::Result;
+use serde_json::Value;
+use std::{fmt, path::PathBuf};
+use structopt::StructOpt;
+
+/// Decodes SCALE encoded contract data, e.g. copied from an explorer or a log.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "decode")]
+pub struct DecodeCommand {
+    /// The `0x` prefixed hex encoded data to decode
+    data: String,
+    /// Path to the Cargo.toml of the contract whose built metadata to read
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// What the data is, detected by the selector of the constructors and messages or else by
+    /// the index of the events if not given
+    #[structopt(long, value_name = "constructor | message | event")]
+    kind: Option<DataKind>,
+    /// Export the decoded data in JSON format
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// Decodes SCALE encoded bytes as a value of any type of the metadata, e.g. raw storage.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "decode-type")]
+pub struct DecodeTypeCommand {
+    /// The id of the type, its path or the end of its path, e.g. `bank::Error`, or its name,
+    /// e.g. `Option<AccountId>`
+    #[structopt(long = "type")]
+    ty: String,
+    /// The `0x` prefixed hex encoded bytes to decode
+    data: String,
+    /// Path to the Cargo.toml of the contract whose built metadata to read
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Export the decoded value in JSON format
+    #[structopt(long)]
+    output_json: bool,
+}
+
+impl DecodeTypeCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        let value = decode_type(&abi.types, &self.ty, &decode_hex(&self.data)?)?;
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&value.to_json())?);
+        } else {
+            println!("{}", value.to_pretty_string());
+        }
+        Ok(None)
+    }
+}
+
+/// Decodes all of `data` as a value of the type `ty` of `types`.
+pub fn decode_type(types: &TypeRegistry, ty: &str, data: &[u8]) -> Result<DecodedValue> {
+    let id = types.lookup(ty)?;
+    let input = &mut &data[..];
+    let value = types.decode(id, input)?;
+    if !input.is_empty() {
+        anyhow::bail!(
+            "{} bytes are left after decoding a `{}`",
+            input.len(),
+            types.type_name(id)
+        )
+    }
+    Ok(value)
+}
+
+/// The kinds of contract data `cargo contract decode` decodes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DataKind {
+    /// The input of a constructor, starting with its selector.
+    Constructor,
+    /// The input of a message, starting with its selector.
+    Message,
+    /// The data of an event, starting with its index.
+    Event,
+}
+
+impl std::str::FromStr for DataKind {
+    type Err = String;
+    fn from_str(kind: &str) -> Result<Self, Self::Err> {
+        match kind {
+            "constructor" => Ok(DataKind::Constructor),
+            "message" => Ok(DataKind::Message),
+            "event" => Ok(DataKind::Event),
+            _ => Err("Could not parse data kind".to_string()),
+        }
+    }
+}
+
+impl DataKind {
+    fn as_str(self) -> &'static str {
+        match self {
+            DataKind::Constructor => "constructor",
+            DataKind::Message => "message",
+            DataKind::Event => "event",
+        }
+    }
+}
+
+impl DecodeCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        let decoded = decode(&abi, self.kind, &decode_hex(&self.data)?)?;
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&decoded.to_json())?);
+        } else {
+            println!(
+                "{} {}",
+                decoded.kind.as_str(),
+                decoded.to_value().to_pretty_string()
+            );
+        }
+        Ok(None)
+    }
+}
+
+/// Contract data decoded with the metadata.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Decoded {
+    pub kind: DataKind,
+    /// The label of the constructor, message or event.
+    pub label: String,
+    pub args: Vec<(String, DecodedValue)>,
+}
+
+impl Decoded {
+    /// Returns the decoded data as a JSON object with the kind, label and arguments.
+    pub fn to_json(&self) -> Value {
+        serde_json::json!({
+            "kind": self.kind.as_str(),
+            "label": self.label,
+            "args": self
+                .args
+                .iter()
+                .map(|(name, value)| (name.clone(), value.to_json()))
+                .collect::<serde_json::Map<_, _>>(),
+        })
+    }
+
+    /// Returns the data as a variant named after the label, with the arguments as fields.
+    fn to_value(&self) -> DecodedValue {
+        let args = self
+            .args
+            .iter()
+            .map(|(name, value)| (Some(name.clone()), value.clone()))
+            .collect();
+        DecodedValue::Variant(self.label.clone(), args)
+    }
+}
+
+/// Displays the data like a struct, e.g. `withdraw { value: 5 }`.
+impl fmt::Display for Decoded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.to_value())
+    }
+}
+
+/// Decodes `data` as `kind`, or as the first kind it decodes as if `kind` is `None`.
+pub fn decode(abi: &ContractAbi, kind: Option<DataKind>, data: &[u8]) -> Result<Decoded> {
+    match kind {
+        Some(DataKind::Constructor) => decode_input(abi, DataKind::Constructor, data),
+        Some(DataKind::Message) => decode_input(abi, DataKind::Message, data),
+        Some(DataKind::Event) => {
+            let event = abi.decode_event(data)?;
+            Ok(Decoded {
+                kind: DataKind::Event,
+                label: event.label,
+                args: event.fields,
+            })
+        }
+        None => [DataKind::Constructor, DataKind::Message, DataKind::Event]
+            .iter()
+            .find_map(|kind| decode(abi, Some(*kind), data).ok())
+            .ok_or_else(|| {
+                anyhow::anyhow!(
+                    "The data is neither the input of a constructor or message nor an event of \
+                     the contract"
+                )
+            }),
+    }
+}
+
+/// Decodes the input of the constructor or message whose selector `data` starts with.
+fn decode_input(abi: &ContractAbi, kind: DataKind, data: &[u8]) -> Result<Decoded> {
+    let specs: &[MessageSpec] = match kind {
+        DataKind::Constructor => &abi.constructors,
+        _ => &abi.messages,
+    };
+    let spec = specs
+        .iter()
+        .find(|spec| data.starts_with(&spec.selector.0))
+        .ok_or_else(|| {
+            anyhow::anyhow!(
+                "The data does not start with the selector of a {}",
+                kind.as_str()
+            )
+        })?;
+    let input = &mut &data[spec.selector.0.len()..];
+    let args = spec
+        .args
+        .iter()
+        .map(|arg| Ok((arg.label.clone(), abi.types.decode(arg.ty.id, input)?)))
+        .collect::<Result<_>>()?;
+    if !input.is_empty() {
+        anyhow::bail!(
+            "{} bytes are left after decoding the input of `{}`",
+            input.len(),
+            spec.label
+        )
+    }
+    Ok(Decoded {
+        kind,
+        label: spec.label.clone(),
+        args,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use codec::Encode;
+
+    #[test]
+    fn detects_the_kind_of_data() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+
+        let mut withdraw = vec![0x41, 0x0f, 0xcc, 0x9d];
+        withdraw.extend(5u128.encode());
+        let decoded = decode(&abi, None, &withdraw).unwrap();
+        assert_eq!(decoded.to_string(), "withdraw { value: 5 }");
+        assert_eq!(
+            decoded.to_json(),
+            serde_json::json!({ "kind": "message", "label": "withdraw", "args": { "value": 5 } })
+        );
+        assert!(decode(&abi, Some(DataKind::Constructor), &withdraw).is_err());
+
+        let mut new = vec![0x9b, 0xae, 0x9d, 0x5e];
+        new.extend(1000u128.encode());
+        let decoded = decode(&abi, None, &new).unwrap();
+        assert_eq!(decoded.kind, DataKind::Constructor);
+        assert_eq!(decoded.to_string(), "new { limit: 1000 }");
+
+        let mut deposited = vec![0, 0];
+        deposited.extend(5u128.encode());
+        let decoded = decode(&abi, None, &deposited).unwrap();
+        assert_eq!(decoded.kind, DataKind::Event);
+        assert_eq!(decoded.to_string(), "Deposited { from: None, value: 5 }");
+
+        withdraw.push(0);
+        assert!(decode(&abi, None, &withdraw).is_err());
+    }
+
+    #[test]
+    fn decodes_values_of_any_type() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+
+        let mut error = vec![1];
+        error.extend(100u128.encode());
+        let value = decode_type(&abi.types, "bank::Error", &error).unwrap();
+        assert_eq!(value.to_string(), "LimitExceeded { limit: 100 }");
+        assert_eq!(
+            decode_type(&abi.types, "7", &error).unwrap(),
+            decode_type(&abi.types, "Error", &error).unwrap()
+        );
+        assert_eq!(
+            decode_type(&abi.types, "Option<AccountId>", &[0])
+                .unwrap()
+                .to_string(),
+            "None"
+        );
+
+        error.push(0);
+        assert!(decode_type(&abi.types, "Error", &error).is_err());
+    }
+}
@@ -17,22 +17,24 @@
 use std::{fs, io::Read, path::PathBuf};
 
 use anyhow::{Context, Result};
-use sp_core::H256;
-use subxt::{contracts::*, ClientBuilder, DefaultNodeRuntime};
-
+use colored::Colorize;
+use sp_core::{hashing::blake2_256, H256};
+use subxt::contracts::*;
+
+use super::{
+    compat,
+    confirm::Summary,
+    extrinsic_output::ExtrinsicOutput,
+    remove::OwnerInfoOfStore,
+    submit::{self, Submitted},
+};
 use crate::{crate_metadata, ExtrinsicOpts};
 
 /// Load the wasm blob from the specified path.
 ///
 /// Defaults to the target contract wasm in the current project, inferred via the crate metadata.
-fn load_contract_code(path: Option<&PathBuf>) -> Result<Vec<u8>> {
-    let contract_wasm_path = match path {
-        Some(path) => path.clone(),
-        None => {
-            let metadata = crate_metadata::CrateMetadata::collect(&Default::default())?;
-            metadata.dest_wasm
-        }
-    };
+pub(crate) fn load_contract_code(path: Option<&PathBuf>) -> Result<Vec<u8>> {
+    let contract_wasm_path = contract_wasm_path(path)?;
     log::info!("Contract code path: {}", contract_wasm_path.display());
     let mut data = Vec::new();
     let mut file = fs::File::open(&contract_wasm_path)
@@ -42,6 +44,29 @@ fn load_contract_code(path: Option<&PathBuf>) -> Result<Vec<u8>> {
     Ok(data)
 }
 
+/// Returns the path of the contract wasm, defaulting to the target contract wasm in the current
+/// project.
+fn contract_wasm_path(path: Option<&PathBuf>) -> Result<PathBuf> {
+    match path {
+        Some(path) => Ok(path.clone()),
+        None => {
+            let metadata = crate_metadata::CrateMetadata::collect(&Default::default())?;
+            Ok(metadata.dest_wasm)
+        }
+    }
+}
+
+/// Returns the language of the contract wasm, e.g. `ink! 3.0.0`, from the `metadata.json` next
+/// to it, `None` if there is no metadata.
+pub(crate) fn contract_language(path: Option<&PathBuf>) -> Result<Option<String>> {
+    let metadata = contract_wasm_path(path)?.with_file_name("metadata.json");
+    if !metadata.exists() {
+        return Ok(None);
+    }
+    let metadata = super::metadata::read_json(&metadata)?;
+    Ok(metadata["source"]["language"].as_str().map(String::from))
+}
+
 /// Put contract code to a smart contract enabled substrate chain.
 /// Returns the code hash of the deployed contract if successful.
 ///
@@ -53,25 +78,76 @@ fn load_contract_code(path: Option<&PathBuf>) -> Result<Vec<u8>> {
 pub(crate) fn execute_deploy(
     extrinsic_opts: &ExtrinsicOpts,
     contract_wasm_path: Option<&PathBuf>,
-) -> Result<H256> {
+) -> Result<Submitted<H256>> {
     let code = load_contract_code(contract_wasm_path)?;
+    let language = contract_language(contract_wasm_path)?;
+    Ok(put_code(extrinsic_opts, code, language.as_deref())?.map(|(code_hash, _)| code_hash))
+}
 
+/// Puts the contract `code` written in `language` to the chain, returning its code hash and the
+/// outcome of the extrinsic.
+///
+/// Code which is already stored on chain is not uploaded again, so that scripts deploying the
+/// same code repeatedly neither pay for the upload nor fail with `CodeAlreadyExists`; there is
+/// no outcome then. Known incompatibilities of the code with the contracts pallet of the chain
+/// are warned about before the upload is confirmed.
+pub(crate) fn put_code(
+    extrinsic_opts: &ExtrinsicOpts,
+    code: Vec<u8>,
+    language: Option<&str>,
+) -> Result<Submitted<(H256, Option<ExtrinsicOutput>)>> {
+    let code_hash = H256(blake2_256(&code));
+    if async_std::task::block_on(code_stored(extrinsic_opts, code_hash))? {
+        eprintln!(
+            "{} the code {:?} is already stored on chain, it is not uploaded again",
+            "Skipped:".cyan().bold(),
+            code_hash
+        );
+        return Ok(Submitted::Executed((code_hash, None)));
+    }
+    async_std::task::block_on(compat::warn_incompatibilities(
+        &extrinsic_opts.node.url()?,
+        &code,
+        language,
+    ));
+    Summary::new("Upload the code")
+        .line("Code hash", format!("{:?}", code_hash))
+        .line("Code size", format!("{} bytes", code.len()))
+        .confirm(extrinsic_opts, |cli| {
+            cli.encode(PutCodeCall {
+                _runtime: Default::default(),
+                code: &code,
+            })
+        })?;
     async_std::task::block_on(async move {
-        let cli = ClientBuilder::<DefaultNodeRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
-        let signer = extrinsic_opts.signer()?;
-
-        let events = cli.put_code_and_watch(&signer, &code).await?;
+        let cli = submit::client(extrinsic_opts).await?;
+        let call = PutCodeCall {
+            _runtime: Default::default(),
+            code: &code,
+        };
+        let events = match submit::watch(&cli, extrinsic_opts, call).await? {
+            Submitted::Executed(events) => events,
+            Submitted::Pending(pending) => return Ok(Submitted::Pending(pending)),
+        };
         let code_stored = events
             .code_stored()?
             .context("Failed to find CodeStored event")?;
 
-        Ok(code_stored.code_hash)
+        let output = ExtrinsicOutput {
+            code_hash: Some(code_stored.code_hash),
+            ..ExtrinsicOutput::new(&events, None)
+        };
+        Ok(Submitted::Executed((code_stored.code_hash, Some(output))))
     })
 }
 
+/// Returns whether code is stored under `code_hash` on the chain.
+pub(crate) async fn code_stored(extrinsic_opts: &ExtrinsicOpts, code_hash: H256) -> Result<bool> {
+    let cli = submit::builder(&extrinsic_opts.node.url()?).build().await?;
+    let owner = cli.fetch(&OwnerInfoOfStore { code_hash }, None).await?;
+    Ok(owner.is_some())
+}
+
 #[cfg(test)]
 mod tests {
     use std::{fs, io::Write};
@@ -98,9 +174,41 @@ mod tests {
 
             let url = url::Url::parse("ws://localhost:9944").unwrap();
             let extrinsic_opts = ExtrinsicOpts {
-                url,
-                suri: "//Alice".into(),
+                node: crate::NodeOpts {
+                    url: Some(url),
+                    network: None,
+                    timeout: None,
+                },
+                suri: Some("//Alice".into()),
+                account: None,
                 password: None,
+                scheme: crate::Scheme::Sr25519,
+                signer_kind: None,
+                signer_cmd: None,
+                offline: None,
+                from: None,
+                nonce: None,
+                track_nonce: false,
+                tip: 0,
+                fee_asset: None,
+                era: None,
+                immortal: false,
+                wait_for: crate::WaitFor::Finalized,
+                at_block: None,
+                after: None,
+                skip_confirm: true,
+                fork_from: None,
+                install_chopsticks: false,
+                explorer: None,
+                retries: 3,
+                retry_backoff: 2,
+                signed_extensions: Vec::new(),
+                proxy: None,
+                multisig: Vec::new(),
+                threshold: None,
+                multisig_max_weight: 10_000_000_000,
+                decrypted_suri: Default::default(),
+                scheduled: Default::default(),
             };
             let result = execute_deploy(&extrinsic_opts, Some(&wasm_path));
 
@@ -0,0 +1,509 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Deployment manifests describing the full contract set of a dapp, created with
+//! `cargo contract deployment` and deployed with `cargo contract deploy --deployment`.
+
+use crate::abi::{decode_hex, ContractAbi};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{collections::BTreeMap, fs, path::PathBuf};
+use structopt::StructOpt;
+use url::Url;
+
+/// The gas limit of instantiations without a configured one, as for `cargo contract instantiate`.
+const DEFAULT_GAS_LIMIT: u64 = 500_000_000;
+
+/// Merges `.contract` bundles and per-network configuration into a deployment manifest.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "deployment")]
+pub struct DeploymentCommand {
+    /// The `.contract` bundles of the contracts, in the order to deploy them in
+    #[structopt(parse(from_os_str), required = true)]
+    contracts: Vec<PathBuf>,
+    /// The TOML file configuring the networks to deploy to and how to instantiate the
+    /// contracts on each of them
+    #[structopt(long, parse(from_os_str))]
+    networks: PathBuf,
+    /// Where to write the deployment manifest
+    #[structopt(short, long, parse(from_os_str), default_value = "deployment.json")]
+    output: PathBuf,
+}
+
+impl DeploymentCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let config = fs::read_to_string(&self.networks)
+            .context(format!("Failed to read {}", self.networks.display()))?;
+        let config: NetworksConfig = toml::from_str(&config)
+            .context(format!("Failed to parse {}", self.networks.display()))?;
+        let bundles = self
+            .contracts
+            .iter()
+            .map(|path| {
+                let json = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+                serde_json::from_slice(&json).context(format!("Failed to parse {}", path.display()))
+            })
+            .collect::<Result<Vec<Value>>>()?;
+
+        let deployment = Deployment::new(bundles, config)?;
+        fs::write(&self.output, serde_json::to_string_pretty(&deployment)?)?;
+        Ok(Some(format!(
+            "Written the deployment of {} contracts to {}",
+            deployment.contracts.len(),
+            self.output.display()
+        )))
+    }
+}
+
+/// The configuration of the networks to deploy to, e.g.
+///
+/// ```toml
+/// [networks.local]
+/// url = "ws://localhost:9944"
+///
+/// [[networks.local.instantiate]]
+/// contract = "erc20"
+/// constructor = "new"
+/// args = "0x00e40b54020000000000000000000000"
+/// endowment = 1000000000000
+/// gas-limit = 500000000
+/// ```
+#[derive(Debug, Deserialize)]
+pub(crate) struct NetworksConfig {
+    networks: BTreeMap<String, NetworkConfig>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct NetworkConfig {
+    url: Url,
+    /// The instantiations in the order to execute them in, by default every contract is
+    /// instantiated with its first constructor.
+    instantiate: Option<Vec<InstantiateConfig>>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+struct InstantiateConfig {
+    contract: String,
+    /// The label of the constructor, the first constructor if not given.
+    constructor: Option<String>,
+    /// The SCALE encoded arguments of the constructor as hex.
+    #[serde(default)]
+    args: String,
+    endowment: Option<Amount>,
+    gas_limit: Option<u64>,
+    /// The proof size of the gas limit, on chains metering it.
+    gas_proof_size: Option<u64>,
+}
+
+/// An amount of balance: an integer, or a string for amounts beyond the 64 bit integers of TOML.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum Amount {
+    Integer(u64),
+    String(String),
+}
+
+impl Amount {
+    fn value(&self) -> Result<u128> {
+        match self {
+            Amount::Integer(amount) => Ok(*amount as u128),
+            Amount::String(amount) => amount
+                .parse()
+                .map_err(|_| anyhow::anyhow!("Invalid amount `{}`", amount)),
+        }
+    }
+}
+
+/// A deployment manifest, holding the contracts of a dapp and how to instantiate them on
+/// each network.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Deployment {
+    /// The contracts in the order to upload them in.
+    pub contracts: Vec<DeploymentContract>,
+    pub networks: BTreeMap<String, DeploymentNetwork>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DeploymentContract {
+    pub name: String,
+    pub code_hash: String,
+    /// The Wasm code as hex.
+    pub wasm: String,
+    /// The metadata of the contract, without the Wasm code.
+    pub metadata: Value,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct DeploymentNetwork {
+    pub url: Url,
+    /// The instantiations in the order to execute them in.
+    pub instantiate: Vec<Instantiation>,
+}
+
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(crate) struct Instantiation {
+    pub contract: String,
+    pub constructor: String,
+    /// The hex encoded input data of the instantiation: the selector of the constructor
+    /// followed by its arguments.
+    pub data: String,
+    pub endowment: u128,
+    pub gas_limit: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gas_proof_size: Option<u64>,
+}
+
+impl Deployment {
+    /// Merges the `.contract` `bundles` and the network `config`.
+    pub(crate) fn new(bundles: Vec<Value>, config: NetworksConfig) -> Result<Self> {
+        let mut contracts = Vec::new();
+        let mut abis = BTreeMap::new();
+        for mut bundle in bundles {
+            let abi = ContractAbi::from_json(&bundle)?;
+            let name = abi
+                .name
+                .clone()
+                .ok_or_else(|| anyhow::anyhow!("A bundle has no contract name"))?;
+            let source = bundle
+                .get_mut("source")
+                .and_then(Value::as_object_mut)
+                .ok_or_else(|| anyhow::anyhow!("The bundle of `{}` has no source", name))?;
+            let wasm = match source.remove("wasm") {
+                Some(Value::String(wasm)) => wasm,
+                _ => anyhow::bail!(
+                    "The bundle of `{}` contains no Wasm code, is it a `.contract` file?",
+                    name
+                ),
+            };
+            let code_hash = source
+                .get("hash")
+                .and_then(Value::as_str)
+                .unwrap_or_default()
+                .to_string();
+            if abis.insert(name.clone(), abi).is_some() {
+                anyhow::bail!("The contract `{}` is part of the deployment twice", name)
+            }
+            contracts.push(DeploymentContract {
+                name,
+                code_hash,
+                wasm,
+                metadata: bundle,
+            });
+        }
+
+        let mut networks = BTreeMap::new();
+        for (network, config) in config.networks {
+            let instantiate = match config.instantiate {
+                Some(instantiate) => instantiate,
+                None => contracts
+                    .iter()
+                    .map(|contract| InstantiateConfig {
+                        contract: contract.name.clone(),
+                        constructor: None,
+                        args: String::new(),
+                        endowment: None,
+                        gas_limit: None,
+                        gas_proof_size: None,
+                    })
+                    .collect(),
+            };
+            let instantiate = instantiate
+                .into_iter()
+                .map(|instantiation| {
+                    let abi = abis.get(&instantiation.contract).ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "The network `{}` instantiates `{}`, which is no contract of the deployment",
+                            network,
+                            instantiation.contract
+                        )
+                    })?;
+                    let constructor = match &instantiation.constructor {
+                        Some(label) => abi.constructors.iter().find(|c| &c.label == label),
+                        None => abi.constructors.first(),
+                    }
+                    .ok_or_else(|| {
+                        anyhow::anyhow!(
+                            "The contract `{}` has no constructor `{}`",
+                            instantiation.contract,
+                            instantiation.constructor.as_deref().unwrap_or_default()
+                        )
+                    })?;
+                    decode_hex(&instantiation.args).context(format!(
+                        "Invalid arguments of the instantiation of `{}` on `{}`",
+                        instantiation.contract, network
+                    ))?;
+                    Ok(Instantiation {
+                        contract: instantiation.contract,
+                        constructor: constructor.label.clone(),
+                        data: format!(
+                            "{}{}",
+                            constructor.selector,
+                            instantiation.args.trim_start_matches("0x")
+                        ),
+                        endowment: match &instantiation.endowment {
+                            Some(endowment) => endowment.value()?,
+                            None => 0,
+                        },
+                        gas_limit: instantiation.gas_limit.unwrap_or(DEFAULT_GAS_LIMIT),
+                        gas_proof_size: instantiation.gas_proof_size,
+                    })
+                })
+                .collect::<Result<_>>()?;
+            networks.insert(
+                network,
+                DeploymentNetwork {
+                    url: config.url,
+                    instantiate,
+                },
+            );
+        }
+        Ok(Self {
+            contracts,
+            networks,
+        })
+    }
+
+    /// Reads the deployment manifest at `path`.
+    #[cfg(feature = "extrinsics")]
+    pub(crate) fn load(path: &std::path::Path) -> Result<Self> {
+        let json = fs::read(path).context(format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&json).context(format!("Failed to parse {}", path.display()))
+    }
+}
+
+/// Uploads the contracts of the deployment manifest at `path` and instantiates them on
+/// `network`, in the order of the manifest.
+///
+/// Returns the accounts of the instantiated contracts.
+#[cfg(feature = "extrinsics")]
+pub(crate) fn execute_deployment(
+    extrinsic_opts: &crate::ExtrinsicOpts,
+    path: &std::path::Path,
+    network: &str,
+) -> Result<Vec<(String, String)>> {
+    if extrinsic_opts.offline.is_some()
+        || !extrinsic_opts.multisig.is_empty()
+        || extrinsic_opts.wait_for == crate::WaitFor::Broadcast
+    {
+        anyhow::bail!(
+            "A deployment instantiates the code it uploads and cannot be signed offline or by a \
+             multisig, nor be only broadcast"
+        )
+    }
+    let deployment = Deployment::load(path)?;
+    let config = deployment.networks.get(network).ok_or_else(|| {
+        anyhow::anyhow!("The network `{}` is not part of the deployment", network)
+    })?;
+    let mut extrinsic_opts = crate::ExtrinsicOpts {
+        node: crate::NodeOpts {
+            // the fork of `--fork-from` replaces the url of the network
+            url: match extrinsic_opts.fork_from {
+                Some(_) => Some(extrinsic_opts.node.url()?),
+                None => Some(config.url.clone()),
+            },
+            network: extrinsic_opts.node.network.clone(),
+            timeout: extrinsic_opts.node.timeout,
+        },
+        suri: extrinsic_opts.suri.clone(),
+        account: extrinsic_opts.account.clone(),
+        password: extrinsic_opts.password.clone(),
+        scheme: extrinsic_opts.scheme,
+        signer_kind: extrinsic_opts.signer_kind,
+        signer_cmd: extrinsic_opts.signer_cmd.clone(),
+        offline: None,
+        from: None,
+        nonce: None,
+        track_nonce: extrinsic_opts.track_nonce,
+        tip: extrinsic_opts.tip,
+        fee_asset: extrinsic_opts.fee_asset,
+        era: extrinsic_opts.era,
+        immortal: extrinsic_opts.immortal,
+        wait_for: extrinsic_opts.wait_for,
+        at_block: extrinsic_opts.at_block,
+        after: extrinsic_opts.after,
+        skip_confirm: extrinsic_opts.skip_confirm,
+        fork_from: extrinsic_opts.fork_from.clone(),
+        install_chopsticks: extrinsic_opts.install_chopsticks,
+        explorer: extrinsic_opts.explorer.clone(),
+        retries: extrinsic_opts.retries,
+        retry_backoff: extrinsic_opts.retry_backoff,
+        signed_extensions: extrinsic_opts.signed_extensions.clone(),
+        proxy: extrinsic_opts.proxy.clone(),
+        multisig: Vec::new(),
+        threshold: None,
+        multisig_max_weight: extrinsic_opts.multisig_max_weight,
+        decrypted_suri: extrinsic_opts.decrypted_suri.clone(),
+        scheduled: Default::default(),
+    };
+    // the whole deployment is confirmed once, not each of its extrinsics
+    config
+        .instantiate
+        .iter()
+        .fold(
+            super::confirm::Summary::new("Deploy the contracts"),
+            |summary, instantiation| {
+                summary
+                    .line("Instantiate", &instantiation.contract)
+                    .balance("Value", instantiation.endowment)
+            },
+        )
+        .confirm_without_fee(&extrinsic_opts)?;
+    extrinsic_opts.skip_confirm = true;
+
+    let mut code_hashes = BTreeMap::new();
+    let mut accounts = Vec::new();
+    for instantiation in &config.instantiate {
+        let code_hash = match code_hashes.get(&instantiation.contract) {
+            Some(code_hash) => *code_hash,
+            None => {
+                let contract = deployment
+                    .contracts
+                    .iter()
+                    .find(|contract| contract.name == instantiation.contract)
+                    .ok_or_else(|| {
+                        anyhow::anyhow!("The contract `{}` is missing", instantiation.contract)
+                    })?;
+                let (code_hash, _) = super::deploy::put_code(
+                    &extrinsic_opts,
+                    decode_hex(&contract.wasm)?,
+                    contract.metadata["source"]["language"].as_str(),
+                )?
+                .executed()?;
+                code_hashes.insert(instantiation.contract.clone(), code_hash);
+                code_hash
+            }
+        };
+        let (account, output) = super::execute_instantiate(
+            &extrinsic_opts,
+            instantiation.endowment,
+            super::dry_run::Weight {
+                ref_time: instantiation.gas_limit,
+                proof_size: instantiation.gas_proof_size,
+            },
+            None,
+            code_hash,
+            crate::HexData(decode_hex(&instantiation.data)?),
+            None,
+            None,
+        )?
+        .executed()?;
+        let deployed = super::deployments::Deployed {
+            name: instantiation.contract.clone(),
+            address: account.to_string(),
+            code_hash,
+            constructor: Some(instantiation.constructor.clone()),
+            args: Vec::new(),
+            data: instantiation.data.clone(),
+            block: output.block_hash,
+            deployer: super::submit::origin(&extrinsic_opts)?.to_string(),
+        };
+        super::deployments::record(&extrinsic_opts, deployed)?;
+        accounts.push((instantiation.contract.clone(), format!("{:?}", account)));
+    }
+    Ok(accounts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn merges_bundles_and_networks() {
+        let mut bundle = metadata_v1();
+        bundle["source"]["wasm"] = Value::String("0x0061736d".to_string());
+        let config = toml::from_str(
+            r#"
+            [networks.local]
+            url = "ws://localhost:9944"
+
+            [networks.testnet]
+            url = "wss://testnet.example.com"
+
+            [[networks.testnet.instantiate]]
+            contract = "bank"
+            constructor = "new"
+            args = "0x0a000000000000000000000000000000"
+            endowment = "100000000000000000000"
+            gas-limit = 1000000
+            gas-proof-size = 65536
+            "#,
+        )
+        .unwrap();
+
+        let deployment = Deployment::new(vec![bundle], config).unwrap();
+        assert_eq!(deployment.contracts[0].name, "bank");
+        assert_eq!(deployment.contracts[0].wasm, "0x0061736d");
+        assert!(deployment.contracts[0].metadata["source"]
+            .get("wasm")
+            .is_none());
+        assert_eq!(
+            deployment.networks["local"].instantiate,
+            vec![Instantiation {
+                contract: "bank".to_string(),
+                constructor: "new".to_string(),
+                data: "0x9bae9d5e".to_string(),
+                endowment: 0,
+                gas_limit: DEFAULT_GAS_LIMIT,
+                gas_proof_size: None,
+            }]
+        );
+        assert_eq!(
+            deployment.networks["testnet"].instantiate[0].data,
+            "0x9bae9d5e0a000000000000000000000000000000"
+        );
+        assert_eq!(
+            deployment.networks["testnet"].instantiate[0].gas_limit,
+            1_000_000
+        );
+        assert_eq!(
+            deployment.networks["testnet"].instantiate[0].gas_proof_size,
+            Some(65_536)
+        );
+
+        // the manifest can be read back
+        let json = serde_json::to_string(&deployment).unwrap();
+        assert_eq!(
+            serde_json::from_str::<Deployment>(&json).unwrap(),
+            deployment
+        );
+    }
+
+    #[test]
+    fn rejects_unknown_contracts() {
+        let mut bundle = metadata_v1();
+        bundle["source"]["wasm"] = Value::String("0x0061736d".to_string());
+        let config = toml::from_str(
+            r#"
+            [networks.local]
+            url = "ws://localhost:9944"
+
+            [[networks.local.instantiate]]
+            contract = "erc20"
+            "#,
+        )
+        .unwrap();
+
+        let error = Deployment::new(vec![bundle], config).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "The network `local` instantiates `erc20`, which is no contract of the deployment"
+        );
+    }
+}
@@ -0,0 +1,207 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The registry of the contracts instantiated on each network, which every instantiation
+//! appends to `deployments/<network>.json` of the current directory, e.g.
+//!
+//! ```json
+//! [
+//!   {
+//!     "name": "my-token",
+//!     "address": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+//!     "codeHash": "0x...",
+//!     "constructor": "new",
+//!     "args": ["1000"],
+//!     "data": "0x9bae9d5e...",
+//!     "block": "0x...",
+//!     "deployer": "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty"
+//!   }
+//! ]
+//! ```
+//!
+//! The network is the one of `--network`, or the host and port of `--url`. Contracts are
+//! given by their name wherever a contract is expected, e.g. `--contract my-token`, which
+//! resolves to the last contract instantiated under that name.
+
+use std::{
+    ffi::OsString,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp_core::H256;
+use url::Url;
+
+use crate::ExtrinsicOpts;
+
+/// The directory of the registries, relative to the current directory.
+const DEPLOYMENTS_DIR: &str = "deployments";
+
+/// The url of the node if `--url` is not given.
+const DEFAULT_URL: &str = "ws://localhost:9944";
+
+/// A contract instantiated on a network.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Deployed {
+    pub name: String,
+    /// The SS58 address of the contract.
+    pub address: String,
+    pub code_hash: H256,
+    /// The label of the constructor, if it was called by its label.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub constructor: Option<String>,
+    /// The arguments of the constructor as given on the command line.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    /// The hex encoded input of the constructor.
+    pub data: String,
+    /// The hash of the block the contract was instantiated in.
+    pub block: H256,
+    /// The SS58 address of the account which instantiated the contract.
+    pub deployer: String,
+}
+
+/// Returns the name of the registry of the network of `--network`, or of the node at `url`,
+/// e.g. `localhost-9944`.
+fn network_name(network: Option<&str>, url: &Url) -> String {
+    if let Some(network) = network {
+        return network.to_string();
+    }
+    let host = url.host_str().unwrap_or("localhost");
+    match url.port() {
+        Some(port) => format!("{}-{}", host, port),
+        None => host.to_string(),
+    }
+}
+
+/// Returns the path of the registry of `network` in `dir`.
+fn path(dir: &Path, network: &str) -> PathBuf {
+    dir.join(DEPLOYMENTS_DIR).join(format!("{}.json", network))
+}
+
+fn load(path: &Path) -> Result<Vec<Deployed>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let json = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    serde_json::from_slice(&json).with_context(|| format!("Failed to parse {}", path.display()))
+}
+
+/// Appends `deployed` to the registry of `network` in `dir`, returning the path of the registry.
+fn append(dir: &Path, network: &str, deployed: Deployed) -> Result<PathBuf> {
+    let path = path(dir, network);
+    let mut registry = load(&path)?;
+    registry.push(deployed);
+    fs::create_dir_all(dir.join(DEPLOYMENTS_DIR))?;
+    fs::write(&path, serde_json::to_string_pretty(&registry)?)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    Ok(path)
+}
+
+/// Returns the address of the last contract instantiated as `name` on `network` in `dir`.
+fn lookup_in(dir: &Path, network: &str, name: &str) -> Result<Option<String>> {
+    Ok(load(&path(dir, network))?
+        .into_iter()
+        .rev()
+        .find(|deployed| deployed.name == name)
+        .map(|deployed| deployed.address))
+}
+
+/// Records `deployed` in the registry of the network of `extrinsic_opts`, returning the path of
+/// the registry. Contracts instantiated on a fork of `--fork-from` are not recorded.
+pub(crate) fn record(
+    extrinsic_opts: &ExtrinsicOpts,
+    deployed: Deployed,
+) -> Result<Option<PathBuf>> {
+    if extrinsic_opts.fork_from.is_some() {
+        return Ok(None);
+    }
+    let network = network_name(
+        extrinsic_opts.node.network.as_deref(),
+        &extrinsic_opts.node.url()?,
+    );
+    append(Path::new(""), &network, deployed).map(Some)
+}
+
+/// Returns the address of the last contract instantiated as `name` on the network of `args`.
+pub(crate) fn lookup(args: &[OsString], name: &str) -> Result<Option<String>> {
+    let value = |flag: &str| {
+        let prefix = format!("{}=", flag);
+        let args = args
+            .iter()
+            .map(|arg| arg.to_string_lossy())
+            .take_while(|arg| arg != "--")
+            .collect::<Vec<_>>();
+        args.iter().enumerate().find_map(|(i, arg)| {
+            if arg == flag {
+                args.get(i + 1).map(|value| value.to_string())
+            } else {
+                arg.strip_prefix(&prefix).map(str::to_string)
+            }
+        })
+    };
+    let url = value("--url").unwrap_or_else(|| DEFAULT_URL.to_string());
+    // an invalid url is reported when the arguments are parsed
+    let url = match Url::parse(&url) {
+        Ok(url) => url,
+        Err(_) => return Ok(None),
+    };
+    let network = network_name(value("--network").as_deref(), &url);
+    lookup_in(Path::new(""), &network, name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    fn deployed(name: &str, address: &str) -> Deployed {
+        Deployed {
+            name: name.to_string(),
+            address: address.to_string(),
+            code_hash: H256::repeat_byte(1),
+            constructor: Some("new".to_string()),
+            args: vec!["1000".to_string()],
+            data: "0x9bae9d5e".to_string(),
+            block: H256::repeat_byte(2),
+            deployer: "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty".to_string(),
+        }
+    }
+
+    #[test]
+    fn resolves_the_last_contract_of_a_name() {
+        with_tmp_dir(|dir| {
+            let url = Url::parse("ws://localhost:9944").unwrap();
+            let local = network_name(None, &url);
+            assert_eq!(local, "localhost-9944");
+            assert_eq!(network_name(Some("astar"), &url), "astar");
+
+            append(dir, &local, deployed("my-token", "5Grw"))?;
+            append(dir, &local, deployed("my-dex", "5F3s"))?;
+            let path = append(dir, &local, deployed("my-token", "5GNJ"))?;
+            assert_eq!(path, dir.join("deployments").join("localhost-9944.json"));
+            assert_eq!(load(&path)?.len(), 3);
+
+            assert_eq!(lookup_in(dir, &local, "my-token")?.as_deref(), Some("5GNJ"));
+            assert_eq!(lookup_in(dir, &local, "other")?, None);
+            assert_eq!(lookup_in(dir, "astar", "my-token")?, None);
+            Ok(())
+        })
+    }
+}
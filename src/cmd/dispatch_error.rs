@@ -0,0 +1,284 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Explains the dispatch errors of failed extrinsics and dry runs, which nodes report as the
+//! index of a pallet and of one of its errors, e.g. `Module { index: 8, error: 11 }`.
+
+use std::convert::TryFrom;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use structopt::StructOpt;
+use url::Url;
+
+use super::submit;
+use crate::{abi::decode_hex, NodeOpts};
+
+/// Names and explains a dispatch error, resolving module errors with the metadata of a chain.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "decode-error")]
+pub struct DecodeErrorCommand {
+    /// The error: a module error as `<pallet index>:<error index>`, as JSON like
+    /// `{"Module":{"index":8,"error":"0x0b000000"}}`, or as hex of a SCALE encoded
+    /// `DispatchError` or of the pallet index followed by the error bytes
+    error: String,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl DecodeErrorCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let (pallet_index, error_index) = match parse(&self.error)? {
+            DispatchError::Module(pallet, error) => (pallet, error),
+            DispatchError::Named(name) => return Ok(Some(name)),
+        };
+        let (pallet, error) = self.node_opts.block_on(async {
+            let cli = submit::builder(&self.node_opts.url()?).build().await?;
+            let pallet = cli
+                .metadata()
+                .module_with_errors(pallet_index)
+                .map_err(|_| anyhow::anyhow!("The runtime has no pallet {}", pallet_index))?;
+            let error = pallet.error(error_index).map_err(|_| {
+                anyhow::anyhow!("The pallet {} has no error {}", pallet.name(), error_index)
+            })?;
+            Ok::<_, anyhow::Error>((pallet.name().to_string(), error.to_string()))
+        })?;
+        Ok(Some(format!(
+            "{}\n\tPallet {}, error {}",
+            describe(&pallet, &error),
+            pallet_index,
+            error_index
+        )))
+    }
+}
+
+/// A dispatch error given to `cargo contract decode-error`.
+#[derive(Debug, PartialEq, Eq)]
+enum DispatchError {
+    /// The index of a pallet and of one of its errors.
+    Module(u8, u8),
+    /// Another error, by its name.
+    Named(String),
+}
+
+/// The variants of `DispatchError` without fields, by their index.
+const DISPATCH_ERRORS: &[&str] = &[
+    "Other",
+    "CannotLookup",
+    "BadOrigin",
+    "Module",
+    "ConsumerRemaining",
+    "NoProviders",
+    "TooManyConsumers",
+    "Token",
+    "Arithmetic",
+    "Transactional",
+    "Exhausted",
+    "Corruption",
+    "Unavailable",
+    "RootNotAllowed",
+];
+
+/// Parses a dispatch error given as `<pallet>:<error>`, as JSON or as hex.
+///
+/// Hex of two or five bytes is a pallet index followed by the error, other hex a SCALE
+/// encoded `DispatchError`.
+fn parse(input: &str) -> Result<DispatchError> {
+    let input = input.trim();
+    let indices = input.split_once(':').and_then(|(pallet, error)| {
+        Some((pallet.trim().parse().ok()?, error.trim().parse().ok()?))
+    });
+    if let Some((pallet, error)) = indices {
+        return Ok(DispatchError::Module(pallet, error));
+    }
+    if input.starts_with("0x") {
+        let bytes = decode_hex(input)?;
+        return match bytes[..] {
+            [pallet, error] | [pallet, error, _, _, _] => Ok(DispatchError::Module(pallet, error)),
+            [3, pallet, error] | [3, pallet, error, _, _, _] => {
+                Ok(DispatchError::Module(pallet, error))
+            }
+            [variant, ..] => {
+                let name = DISPATCH_ERRORS
+                    .get(variant as usize)
+                    .with_context(|| format!("Unknown `DispatchError` variant {}", variant))?;
+                Ok(DispatchError::Named(name.to_string()))
+            }
+            [] => anyhow::bail!("Expected the bytes of the error"),
+        };
+    }
+    let json: Value = serde_json::from_str(input)
+        .with_context(|| format!("`{}` is neither a module error, JSON nor hex", input))?;
+    if let Some((pallet, error)) = module_error(&json) {
+        return Ok(DispatchError::Module(pallet, error));
+    }
+    match json {
+        Value::String(name) => Ok(DispatchError::Named(name)),
+        Value::Object(error) if error.len() == 1 => {
+            let (name, value) = error.into_iter().next().expect("the object has one key");
+            match value {
+                Value::String(reason) => Ok(DispatchError::Named(format!("{}::{}", name, reason))),
+                _ => Ok(DispatchError::Named(name)),
+            }
+        }
+        json => anyhow::bail!("Unknown dispatch error {}", json),
+    }
+}
+
+/// Returns the pallet and the name of the module error `err` of a dry run, resolved with the
+/// metadata of the runtime of `url`, followed by an explanation of common errors.
+///
+/// Returns `None` for other dispatch errors, or if the runtime does not know the error.
+pub(crate) async fn resolve(url: &Url, err: &Value) -> Option<String> {
+    let (pallet_index, error_index) = module_error(err)?;
+    let cli = submit::builder(url)
+        .build()
+        .await
+        .map_err(|err| log::debug!("Connecting to resolve the module error failed: {:?}", err))
+        .ok()?;
+    let pallet = cli.metadata().module_with_errors(pallet_index).ok()?;
+    let error = pallet.error(error_index).ok()?;
+    Some(describe(pallet.name(), error))
+}
+
+/// Returns the module error `pallet::error`, followed by an explanation of common errors.
+pub(crate) fn describe(pallet: &str, error: &str) -> String {
+    match explain(pallet, error) {
+        Some(explanation) => format!("{}::{}: {}", pallet, error, explanation),
+        None => format!("{}::{}", pallet, error),
+    }
+}
+
+/// Returns the index of the pallet and of the error of a module error, as reported by the
+/// contracts RPCs: `{ "Module": { "index": 8, "error": 11 } }`, where newer nodes report the
+/// error as four bytes, whose first is the index.
+fn module_error(err: &Value) -> Option<(u8, u8)> {
+    let module = err.get("Module").or_else(|| err.get("module"))?;
+    let index = module.get("index")?.as_u64()?;
+    let error = match module.get("error")? {
+        Value::Number(error) => error.as_u64()?,
+        Value::Array(bytes) => bytes.first()?.as_u64()?,
+        Value::String(bytes) => *decode_hex(bytes).ok()?.first()? as u64,
+        _ => return None,
+    };
+    Some((u8::try_from(index).ok()?, u8::try_from(error).ok()?))
+}
+
+/// Returns an explanation of the common errors of the contracts and balances pallets.
+fn explain(pallet: &str, error: &str) -> Option<&'static str> {
+    Some(match (pallet, error) {
+        ("Contracts", "OutOfGas") => {
+            "the gas limit was used up, raise it with `--gas-ref-time` and `--gas-proof-size` \
+            or leave it to the dry run"
+        }
+        ("Contracts", "ContractTrapped") => {
+            "the contract trapped, e.g. it panicked or an arithmetic operation overflowed; \
+            `--dry-run` shows its debug message"
+        }
+        ("Contracts", "ContractReverted") => {
+            "the contract reverted, `--dry-run` shows the error it returned"
+        }
+        ("Contracts", "StorageDepositLimitExhausted") => {
+            "the storage deposit exceeds `--storage-deposit-limit`"
+        }
+        ("Contracts", "StorageDepositNotEnoughFunds") | ("Contracts", "StorageExhausted") => {
+            "the account cannot pay the storage deposit"
+        }
+        ("Contracts", "TransferFailed") => {
+            "the value could not be transferred: the balance of the sender is too low, or the \
+            receiver would be left below the existential deposit"
+        }
+        ("Contracts", "NewContractNotFunded") | ("Contracts", "BelowSubsistenceThreshold") => {
+            "the value transferred to the new contract is below the existential deposit"
+        }
+        ("Contracts", "CodeNotFound") => "no code is uploaded under the code hash",
+        ("Contracts", "ContractNotFound") => "no contract is instantiated at the address",
+        ("Contracts", "DuplicateContract") => {
+            "a contract is already instantiated at the address, instantiate with another \
+            `--salt`"
+        }
+        ("Contracts", "CodeTooLarge") => "the code exceeds the maximum code size of the chain",
+        ("Contracts", "CodeRejected") => {
+            "the code was rejected, it uses Wasm features or host functions the chain does \
+            not support"
+        }
+        ("Contracts", "CodeInUse") => "contracts are still instantiated from the code",
+        ("Contracts", "MaxCallDepthReached") => "contracts called each other too deeply",
+        ("Contracts", "ReentranceDenied") => {
+            "the contract was called again while executing, which it does not allow"
+        }
+        ("Contracts", "DecodingFailed") => "the input of a host function could not be decoded",
+        ("Balances", "InsufficientBalance") => {
+            "the account cannot pay the transferred value and the fees"
+        }
+        ("Balances", "KeepAlive") | ("Balances", "ExistentialDeposit") => {
+            "the account would be left below the existential deposit"
+        }
+        _ => return None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn explains_module_errors() {
+        assert_eq!(
+            module_error(&json!({ "Module": { "index": 8, "error": 11 } })),
+            Some((8, 11))
+        );
+        assert_eq!(
+            module_error(&json!({ "Module": { "index": 70, "error": "0x0b000000" } })),
+            Some((70, 11))
+        );
+        assert_eq!(
+            module_error(&json!({ "module": { "index": 70, "error": [11, 0, 0, 0] } })),
+            Some((70, 11))
+        );
+        assert_eq!(module_error(&json!("BadOrigin")), None);
+
+        assert_eq!(
+            describe("Contracts", "CodeNotFound"),
+            "Contracts::CodeNotFound: no code is uploaded under the code hash"
+        );
+        assert_eq!(describe("Assets", "Frozen"), "Assets::Frozen");
+    }
+
+    #[test]
+    fn parses_dispatch_errors() {
+        let module = DispatchError::Module(8, 11);
+        assert_eq!(parse("8:11").unwrap(), module);
+        assert_eq!(parse("0x080b").unwrap(), module);
+        assert_eq!(parse("0x080b000000").unwrap(), module);
+        assert_eq!(parse("0x03080b000000").unwrap(), module);
+        assert_eq!(
+            parse(r#"{"Module":{"index":8,"error":"0x0b000000"}}"#).unwrap(),
+            module
+        );
+        assert_eq!(
+            parse("0x02").unwrap(),
+            DispatchError::Named("BadOrigin".to_string())
+        );
+        assert_eq!(
+            parse(r#"{"Arithmetic":"Overflow"}"#).unwrap(),
+            DispatchError::Named("Arithmetic::Overflow".to_string())
+        );
+        assert!(parse("0x").is_err());
+        assert!(parse("oops").is_err());
+    }
+}
@@ -0,0 +1,366 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Renders the interface of a contract as Markdown or HTML with `cargo contract doc`.
+
+use crate::{
+    abi::{ContractAbi, MessageSpec, TypeRegistry},
+    cmd::metadata::built_metadata_path,
+};
+use anyhow::Result;
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Renders the documentation of the constructors, messages and events of a contract.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "doc")]
+pub struct DocCommand {
+    /// Path to the Cargo.toml of the contract whose built metadata to document
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to document instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The format of the documentation.
+    ///
+    /// - `markdown`: A Markdown document, e.g. for the README of the contract.
+    ///
+    /// - `html`: A standalone HTML page.
+    #[structopt(
+        long,
+        default_value = "markdown",
+        value_name = "markdown | html",
+        verbatim_doc_comment
+    )]
+    format: DocFormat,
+    /// Write the documentation to this file instead of printing it
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// The formats `cargo contract doc` renders the documentation in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DocFormat {
+    Markdown,
+    Html,
+}
+
+impl std::str::FromStr for DocFormat {
+    type Err = String;
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "markdown" | "md" => Ok(DocFormat::Markdown),
+            "html" => Ok(DocFormat::Html),
+            _ => Err("Could not parse documentation format".to_string()),
+        }
+    }
+}
+
+impl DocCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        let doc = match self.format {
+            DocFormat::Markdown => render(&abi, Markdown::default()),
+            DocFormat::Html => render(&abi, Html::default()),
+        };
+
+        match &self.output {
+            Some(output_path) => {
+                fs::write(output_path, doc)?;
+                Ok(Some(format!(
+                    "Written the documentation to {}",
+                    output_path.display()
+                )))
+            }
+            None => {
+                print!("{}", doc);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// The building blocks of the documentation, written in the syntax of a format.
+trait Writer {
+    fn heading(&mut self, level: usize, text: &str, code: bool);
+    /// Doc comments, which are written in Markdown.
+    fn docs(&mut self, docs: &[String]);
+    fn paragraph(&mut self, text: &str);
+    fn code_block(&mut self, code: &str);
+    /// A list of items starting with code, e.g. the fields of an event.
+    fn list(&mut self, items: &[(String, String)]);
+    fn finish(self, title: &str) -> String;
+}
+
+/// Renders the documentation of `abi` with `writer`.
+fn render(abi: &ContractAbi, mut writer: impl Writer) -> String {
+    let title = abi.name.clone().unwrap_or_else(|| "Contract".to_string());
+    writer.heading(1, &title, false);
+    writer.docs(&abi.docs);
+
+    writer.heading(2, "Constructors", false);
+    for constructor in &abi.constructors {
+        message(&mut writer, constructor, &abi.types, true);
+    }
+    writer.heading(2, "Messages", false);
+    if abi.messages.is_empty() {
+        writer.paragraph("The contract has no messages.");
+    }
+    for message_spec in &abi.messages {
+        message(&mut writer, message_spec, &abi.types, false);
+    }
+
+    if !abi.events.is_empty() {
+        writer.heading(2, "Events", false);
+    }
+    for event in &abi.events {
+        writer.heading(3, &event.label, true);
+        writer.docs(&event.docs);
+        let fields = event
+            .args
+            .iter()
+            .map(|arg| {
+                let mut text = if arg.indexed {
+                    "Topic. ".to_string()
+                } else {
+                    String::new()
+                };
+                text.push_str(&doc_line(&arg.docs));
+                (
+                    format!("{}: {}", arg.label, abi.types.type_name(arg.ty.id)),
+                    text.trim_end().to_string(),
+                )
+            })
+            .collect::<Vec<_>>();
+        if !fields.is_empty() {
+            writer.list(&fields);
+        }
+    }
+    writer.finish(&title)
+}
+
+fn message(writer: &mut impl Writer, spec: &MessageSpec, types: &TypeRegistry, constructor: bool) {
+    writer.heading(3, &spec.label, true);
+
+    let args = spec
+        .args
+        .iter()
+        .map(|arg| format!("{}: {}", arg.label, types.type_name(arg.ty.id)))
+        .collect::<Vec<_>>();
+    let name = spec.label.rsplit("::").next().unwrap_or(&spec.label);
+    let mut signature = format!("fn {}({})", name, args.join(", "));
+    if let Some(return_type) = &spec.return_type {
+        signature.push_str(&format!(" -> {}", types.type_name(return_type.id)));
+    } else if constructor {
+        signature.push_str(" -> Self");
+    }
+    writer.code_block(&signature);
+
+    let mut properties = vec![format!("Selector {}", spec.selector)];
+    if !constructor {
+        properties.push(
+            if spec.mutates {
+                "mutates the storage"
+            } else {
+                "read-only"
+            }
+            .to_string(),
+        );
+    }
+    if spec.payable {
+        properties.push("payable".to_string());
+    }
+    writer.paragraph(&format!("{}.", properties.join(", ")));
+    writer.docs(&spec.docs);
+}
+
+/// Joins doc comment lines, which start with a space as written after `///`.
+fn join_docs(docs: &[String]) -> String {
+    docs.iter()
+        .map(|line| line.strip_prefix(' ').unwrap_or(line))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .trim()
+        .to_string()
+}
+
+/// Joins doc comment lines into a single line.
+fn doc_line(docs: &[String]) -> String {
+    docs.iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[derive(Default)]
+struct Markdown {
+    out: String,
+}
+
+impl Writer for Markdown {
+    fn heading(&mut self, level: usize, text: &str, code: bool) {
+        let text = if code {
+            format!("`{}`", text)
+        } else {
+            text.to_string()
+        };
+        self.out
+            .push_str(&format!("{} {}\n\n", "#".repeat(level), text));
+    }
+
+    fn docs(&mut self, docs: &[String]) {
+        let docs = join_docs(docs);
+        if !docs.is_empty() {
+            self.out.push_str(&format!("{}\n\n", docs));
+        }
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        self.out.push_str(&format!("{}\n\n", text));
+    }
+
+    fn code_block(&mut self, code: &str) {
+        self.out.push_str(&format!("```rust\n{}\n```\n\n", code));
+    }
+
+    fn list(&mut self, items: &[(String, String)]) {
+        for (code, text) in items {
+            if text.is_empty() {
+                self.out.push_str(&format!("- `{}`\n", code));
+            } else {
+                self.out.push_str(&format!("- `{}`: {}\n", code, text));
+            }
+        }
+        self.out.push('\n');
+    }
+
+    fn finish(self, _title: &str) -> String {
+        format!("{}\n", self.out.trim_end())
+    }
+}
+
+#[derive(Default)]
+struct Html {
+    body: String,
+}
+
+impl Writer for Html {
+    fn heading(&mut self, level: usize, text: &str, code: bool) {
+        let text = if code {
+            format!("<code>{}</code>", escape(text))
+        } else {
+            escape(text)
+        };
+        self.body
+            .push_str(&format!("<h{0}>{1}</h{0}>\n", level, text));
+    }
+
+    fn docs(&mut self, docs: &[String]) {
+        let docs = join_docs(docs);
+        for paragraph in docs.split("\n\n").filter(|p| !p.trim().is_empty()) {
+            self.body
+                .push_str(&format!("<p>{}</p>\n", escape(paragraph.trim())));
+        }
+    }
+
+    fn paragraph(&mut self, text: &str) {
+        self.body.push_str(&format!("<p>{}</p>\n", escape(text)));
+    }
+
+    fn code_block(&mut self, code: &str) {
+        self.body
+            .push_str(&format!("<pre><code>{}</code></pre>\n", escape(code)));
+    }
+
+    fn list(&mut self, items: &[(String, String)]) {
+        self.body.push_str("<ul>\n");
+        for (code, text) in items {
+            self.body
+                .push_str(&format!("<li><code>{}</code>", escape(code)));
+            if !text.is_empty() {
+                self.body.push_str(&format!(": {}", escape(text)));
+            }
+            self.body.push_str("</li>\n");
+        }
+        self.body.push_str("</ul>\n");
+    }
+
+    fn finish(self, title: &str) -> String {
+        format!(
+            "<!DOCTYPE html>\n\
+            <html>\n\
+            <head>\n\
+            <meta charset=\"utf-8\">\n\
+            <title>{}</title>\n\
+            <style>body {{ font-family: sans-serif; max-width: 50em; margin: auto; }}</style>\n\
+            </head>\n\
+            <body>\n\
+            {}\
+            </body>\n\
+            </html>\n",
+            escape(title),
+            self.body
+        )
+    }
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn renders_markdown() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let doc = render(&abi, Markdown::default());
+
+        assert!(doc.starts_with("# bank\n\n"), "{}", doc);
+        assert!(doc.contains(
+            "### `new`\n\n```rust\nfn new(limit: u128) -> Self\n```\n\n\
+            Selector 0x9bae9d5e, payable.\n\nCreates a bank.\n\n"
+        ));
+        assert!(doc.contains(
+            "### `Ledger::balance_of`\n\n```rust\nfn balance_of(owner: AccountId) -> u128\n```\n\n\
+            Selector 0x0f755a56, read-only.\n\n"
+        ));
+        assert!(doc
+            .contains("### `Deposited`\n\n- `from: Option<AccountId>`: Topic.\n- `value: u128`\n"));
+    }
+
+    #[test]
+    fn renders_html() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let doc = render(&abi, Html::default());
+
+        assert!(doc.contains("<title>bank</title>"));
+        assert!(doc.contains(
+            "<pre><code>fn withdraw(value: u128) -&gt; Result&lt;(), Error&gt;</code></pre>"
+        ));
+        assert!(doc.contains("<li><code>from: Option&lt;AccountId&gt;</code>: Topic.</li>"));
+    }
+}
@@ -0,0 +1,253 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Downloads the code of an instantiated contract with `cargo contract download`, bundled with
+//! its metadata if a registry serves it.
+
+use std::{fs, path::PathBuf, process::Command};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    storage::StorageKey,
+    H256,
+};
+use structopt::StructOpt;
+use subxt::{sp_runtime::codec::Encode, DefaultNodeRuntime, Metadata, MetadataError, Store};
+use url::Url;
+
+use super::{
+    chain::parse_account_id,
+    dry_run::{hex, CONTRACTS},
+    info::contract::ContractInfoOfStore,
+    metadata::blake2_hash,
+    network, submit,
+};
+use crate::NodeOpts;
+
+/// Downloads the Wasm of an instantiated contract as it was uploaded, and writes it as a
+/// `.contract` bundle with the metadata of the code from a registry.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "download")]
+pub struct DownloadCommand {
+    /// The account of the contract
+    #[structopt(long, parse(try_from_str = parse_account_id))]
+    contract: AccountId32,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// The verification or registry service serving the metadata of the code at
+    /// `<registry>/<code hash>/metadata.json`. Defaults to the `registry` of the `contract.toml`
+    /// or of the `config.toml` of the config directory
+    #[structopt(long, parse(try_from_str))]
+    registry: Option<Url>,
+    /// The file to write, `<contract>.contract`, or `<code hash>.wasm` without metadata, if not
+    /// given
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// The `Contracts::PristineCode` storage of uploaded code, the Wasm as it was uploaded.
+struct PristineCodeStore {
+    code_hash: H256,
+}
+
+impl Encode for PristineCodeStore {
+    fn encode(&self) -> Vec<u8> {
+        self.code_hash.encode()
+    }
+}
+
+impl Store<DefaultNodeRuntime> for PristineCodeStore {
+    const MODULE: &'static str = CONTRACTS;
+    const FIELD: &'static str = "PristineCode";
+    type Returns = Vec<u8>;
+
+    fn prefix(metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .prefix())
+    }
+
+    fn key(&self, metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .map()?
+            .key(&self.code_hash))
+    }
+}
+
+impl DownloadCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let (code_hash, wasm) = self.node_opts.block_on(async {
+            let cli = submit::builder(&self.node_opts.url()?).build().await?;
+            let contract = cli
+                .fetch(
+                    &ContractInfoOfStore {
+                        contract: &self.contract,
+                    },
+                    None,
+                )
+                .await?
+                .with_context(|| {
+                    format!(
+                        "No contract is instantiated at {}",
+                        self.contract.to_ss58check()
+                    )
+                })?;
+            let code_hash = contract.code_hash;
+            let wasm = cli
+                .fetch(&PristineCodeStore { code_hash }, None)
+                .await?
+                .with_context(|| {
+                    format!("The code {:?} of the contract is not on chain", code_hash)
+                })?;
+            Ok::<_, anyhow::Error>((code_hash, wasm))
+        })?;
+
+        let registry = match &self.registry {
+            Some(registry) => Some(registry.clone()),
+            None => network::registry()?,
+        };
+        let metadata = match &registry {
+            Some(registry) => {
+                let metadata = fetch_metadata(registry, &code_hash)?;
+                if metadata.is_none() {
+                    eprintln!(
+                        "{} {} has no metadata of the code {:?}, only the code is downloaded",
+                        "warning:".yellow().bold(),
+                        registry,
+                        code_hash
+                    );
+                }
+                metadata
+            }
+            None => {
+                eprintln!(
+                    "{} No registry is configured, only the code is downloaded",
+                    "warning:".yellow().bold()
+                );
+                None
+            }
+        };
+
+        let (path, contents) = match metadata {
+            Some(metadata) => {
+                let bundle = bundle(metadata, &wasm)?;
+                let name = bundle
+                    .pointer("/contract/name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("contract");
+                let path = self
+                    .output
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(format!("{}.contract", name)));
+                (path, serde_json::to_vec_pretty(&bundle)?)
+            }
+            None => {
+                let path = self
+                    .output
+                    .clone()
+                    .unwrap_or_else(|| PathBuf::from(format!("{:x}.wasm", code_hash)));
+                (path, wasm)
+            }
+        };
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+        Ok(Some(format!(
+            "Written the code {:?} of the contract to {}",
+            code_hash,
+            path.display()
+        )))
+    }
+}
+
+/// Fetches the metadata of the code `code_hash` from `<registry>/<code hash>/metadata.json`
+/// with `curl`, `None` if the registry does not know the code.
+fn fetch_metadata(registry: &Url, code_hash: &H256) -> Result<Option<Value>> {
+    let url = format!(
+        "{}/{:?}/metadata.json",
+        registry.as_str().trim_end_matches('/'),
+        code_hash
+    );
+    log::info!("Fetching the metadata from {}", url);
+    let output = Command::new("curl")
+        .args(["--silent", "--show-error", "--location"])
+        .args(["--write-out", "\n%{http_code}"])
+        .arg(&url)
+        .output()
+        .context("Failed to run `curl`, which fetches the metadata from the registry")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to fetch {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    let output = String::from_utf8(output.stdout)?;
+    let (body, status) = output.rsplit_once('\n').unwrap_or(("", &output));
+    match status {
+        "200" => Ok(Some(
+            serde_json::from_str(body).with_context(|| format!("{} is no JSON", url))?,
+        )),
+        "404" => Ok(None),
+        status => anyhow::bail!("Failed to fetch {}: HTTP status {}", url, status),
+    }
+}
+
+/// Returns the `.contract` bundle of the `metadata` and the `wasm`, after checking that the
+/// metadata is the one of the code.
+fn bundle(mut metadata: Value, wasm: &[u8]) -> Result<Value> {
+    let hash = serde_json::to_value(blake2_hash(wasm))?;
+    let source = metadata
+        .get_mut("source")
+        .and_then(Value::as_object_mut)
+        .context("The metadata of the registry has no `source` section")?;
+    match source.get("hash") {
+        Some(expected) if *expected != hash => anyhow::bail!(
+            "The metadata of the registry is the one of the code {}, not of the code {} of the \
+            contract",
+            expected,
+            hash
+        ),
+        _ => (),
+    }
+    source.insert("hash".to_string(), hash);
+    source.insert("wasm".to_string(), Value::String(hex(wasm)));
+    Ok(metadata)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const WASM: [u8; 8] = [0x00, 0x61, 0x73, 0x6d, 0x01, 0x00, 0x00, 0x00];
+
+    #[test]
+    fn bundles_the_metadata_of_the_code() {
+        let mut metadata = crate::abi::tests::metadata_v1();
+        metadata["source"]["hash"] = serde_json::to_value(blake2_hash(&WASM)).unwrap();
+        let bundled = bundle(metadata.clone(), &WASM).unwrap();
+        assert_eq!(bundled["source"]["wasm"], "0x0061736d01000000");
+        assert_eq!(bundled["V1"], metadata["V1"]);
+
+        metadata["source"]["hash"] = serde_json::to_value(blake2_hash(&WASM[..4])).unwrap();
+        assert!(bundle(metadata, &WASM).is_err());
+    }
+}
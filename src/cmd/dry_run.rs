@@ -0,0 +1,664 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dry runs of calls and instantiations, which are executed on the state of the node with the
+//! `contracts_call` and `contracts_instantiate` RPCs without submitting an extrinsic.
+
+use std::fmt::Write as _;
+
+use anyhow::Result;
+use colored::Colorize;
+use serde::{de, Deserialize, Deserializer, Serialize};
+use serde_json::{json, Value};
+use sp_core::{ecdsa, sr25519, H256};
+use subxt::{
+    sp_runtime::{
+        codec::{Compact, Encode},
+        MultiSignature,
+    },
+    Client, DefaultNodeRuntime, Encoded,
+};
+
+use super::{
+    chain::AccountId,
+    dispatch_error,
+    gas::Limits,
+    nonce, rpc,
+    signing::UnsignedExtrinsic,
+    submit,
+    token::{self, format_balance, Token},
+};
+use crate::{
+    abi::{decode_hex, ReturnValue, TypeRegistry},
+    ExtrinsicOpts,
+};
+
+/// The name of the contracts pallet, which `subxt` keeps private.
+pub(crate) const CONTRACTS: &str = "Contracts";
+
+/// The outcome of a dry run, as reported by the contracts RPCs.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ContractResult<R> {
+    pub gas_consumed: Weight,
+    /// The gas limit required for the execution, which may exceed the gas consumed.
+    #[serde(default)]
+    pub gas_required: Option<Weight>,
+    #[serde(default)]
+    pub storage_deposit: Option<StorageDeposit>,
+    /// The output of `ink_env::debug_println!`, if the node allows debug output.
+    #[serde(default, deserialize_with = "bytes")]
+    pub debug_message: Vec<u8>,
+    /// The returned value, or the `DispatchError` of a failed execution.
+    pub result: Result<R, Value>,
+    /// The module error of a failed execution, resolved with the metadata of the runtime.
+    #[serde(skip)]
+    pub error: Option<String>,
+}
+
+impl<R> ContractResult<R> {
+    /// Returns the gas limit required for the execution, the gas consumed by nodes which do
+    /// not report it.
+    pub fn required_gas(&self) -> Weight {
+        self.gas_required.unwrap_or(self.gas_consumed)
+    }
+
+    /// Returns the gas limit and the storage deposit limit required for the execution.
+    pub fn required_limits(&self) -> Limits {
+        Limits {
+            gas: self.required_gas(),
+            storage_deposit: match self.storage_deposit {
+                Some(StorageDeposit::Charge(deposit)) => Some(deposit),
+                _ => None,
+            },
+        }
+    }
+
+    /// Resolves the pallet and the name of the module error of a failed execution.
+    async fn resolve_error(mut self, url: &url::Url) -> Self {
+        if let Err(err) = &self.result {
+            self.error = dispatch_error::resolve(url, err).await;
+        }
+        self
+    }
+
+    /// Returns the error of a failed execution, by its name if it is a module error.
+    pub fn display_error(&self, err: &Value) -> String {
+        self.error.clone().unwrap_or_else(|| err.to_string())
+    }
+}
+
+/// An amount of gas: a number, or the computation time and proof size of nodes which meter
+/// both.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Weight {
+    pub ref_time: u64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub proof_size: Option<u64>,
+}
+
+impl<'de> Deserialize<'de> for Weight {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let weight = Value::deserialize(deserializer)?;
+        let field = |name| weight.get(name).and_then(Value::as_u64);
+        match weight.as_u64() {
+            Some(ref_time) => Ok(Weight {
+                ref_time,
+                proof_size: None,
+            }),
+            None => Ok(Weight {
+                ref_time: field("refTime")
+                    .or_else(|| field("ref_time"))
+                    .ok_or_else(|| de::Error::custom(format!("Invalid weight {}", weight)))?,
+                proof_size: field("proofSize").or_else(|| field("proof_size")),
+            }),
+        }
+    }
+}
+
+impl Weight {
+    /// Returns the weight as expected by the RPCs, a number if the proof size is not metered.
+    fn to_value(self) -> Value {
+        match self.proof_size {
+            Some(proof_size) => json!({ "refTime": self.ref_time, "proofSize": proof_size }),
+            None => self.ref_time.into(),
+        }
+    }
+}
+
+/// Encodes the weight as the gas limit of the Calls of the contracts pallet: the computation
+/// time, followed by the proof size on versions of the pallet which meter it.
+impl Encode for Weight {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Compact(self.ref_time).encode();
+        if let Some(proof_size) = self.proof_size {
+            Compact(proof_size).encode_to(&mut encoded);
+        }
+        encoded
+    }
+}
+
+impl std::fmt::Display for Weight {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self.proof_size {
+            Some(proof_size) => write!(f, "ref_time {}, proof_size {}", self.ref_time, proof_size),
+            None => write!(f, "{}", self.ref_time),
+        }
+    }
+}
+
+/// The value returned by a message or constructor.
+#[derive(Debug, Deserialize)]
+pub(crate) struct ExecReturnValue {
+    #[serde(deserialize_with = "flags")]
+    pub flags: u32,
+    #[serde(deserialize_with = "bytes")]
+    pub data: Vec<u8>,
+}
+
+impl ExecReturnValue {
+    /// `true` if the contract reverted the changes of the execution.
+    pub fn reverted(&self) -> bool {
+        self.flags & 1 != 0
+    }
+}
+
+/// The value returned by a constructor and the account of the instantiated contract.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct InstantiateReturnValue {
+    pub result: ExecReturnValue,
+    pub account_id: String,
+}
+
+/// The storage deposit charged for or refunded by an execution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum StorageDeposit {
+    #[serde(alias = "Refund")]
+    Refund(#[serde(deserialize_with = "balance")] u128),
+    #[serde(alias = "Charge")]
+    Charge(#[serde(deserialize_with = "balance")] u128),
+}
+
+impl StorageDeposit {
+    /// Fails if the deposit charged exceeds `limit`.
+    pub fn check_limit(&self, limit: Option<u128>) -> Result<()> {
+        match (self, limit) {
+            (StorageDeposit::Charge(deposit), Some(limit)) if *deposit > limit => anyhow::bail!(
+                "The storage deposit of {} exceeds the --storage-deposit-limit {}",
+                deposit,
+                limit
+            ),
+            _ => Ok(()),
+        }
+    }
+}
+
+impl StorageDeposit {
+    /// Formats the deposit, followed by its value in units of `token` if it is known.
+    pub fn format(&self, token: Option<&Token>) -> String {
+        match self {
+            StorageDeposit::Charge(deposit) => {
+                format!("charges {}", format_balance(*deposit, token))
+            }
+            StorageDeposit::Refund(deposit) => {
+                format!("refunds {}", format_balance(*deposit, token))
+            }
+        }
+    }
+}
+
+impl std::fmt::Display for StorageDeposit {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.format(None))
+    }
+}
+
+/// The fee of submitting the extrinsic of a dry run and the token of the chain, to show the
+/// costs of the dry run in units of the token.
+#[derive(Debug, Default)]
+pub(crate) struct Costs {
+    pub fee: Option<u128>,
+    pub token: Option<Token>,
+}
+
+impl Costs {
+    /// Queries the fee of the extrinsic with the call encoded by `encode` and the token of the
+    /// chain. Either is left out of the summary of the dry run if querying it fails.
+    pub fn query<F>(extrinsic_opts: &ExtrinsicOpts, encode: F) -> Result<Costs>
+    where
+        F: FnOnce(&Client<DefaultNodeRuntime>) -> Result<Encoded, subxt::Error>,
+    {
+        let url = extrinsic_opts.node.url()?;
+        Ok(async_std::task::block_on(async {
+            let fee = extrinsic_opts
+                .node
+                .timeout(query_fee(extrinsic_opts, encode))
+                .await
+                .map_err(|err| log::debug!("Querying the fee of the extrinsic failed: {:?}", err))
+                .ok();
+            Costs {
+                fee,
+                token: token::fetch_or_none(&url).await,
+            }
+        }))
+    }
+}
+
+/// The fee of an extrinsic, as reported by the `payment_queryInfo` RPC.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FeeInfo {
+    #[serde(deserialize_with = "balance")]
+    partial_fee: u128,
+}
+
+/// Returns the fee of the extrinsic with the call encoded by `encode`, wrapped as it would be
+/// when submitted.
+///
+/// The fee does not depend on the signature, so the extrinsic is not signed but carries a
+/// placeholder signature. It is never submitted.
+pub(crate) async fn query_fee<F>(extrinsic_opts: &ExtrinsicOpts, encode: F) -> Result<u128>
+where
+    F: FnOnce(&Client<DefaultNodeRuntime>) -> Result<Encoded, subxt::Error>,
+{
+    let cli = submit::client(extrinsic_opts).await?;
+    let call = submit::wrap(&cli, extrinsic_opts, encode(&cli)?).await?;
+    let account = extrinsic_opts.account_id()?;
+    let nonce = nonce::fetch(&extrinsic_opts.node.url()?, &account).await?;
+    let unsigned = UnsignedExtrinsic::new(&cli, extrinsic_opts, &account, nonce, call).await?;
+    let placeholder: MultiSignature = match account {
+        AccountId::Substrate(_) => sr25519::Signature::from_raw([0; 64]).into(),
+        AccountId::Ethereum(_) => ecdsa::Signature::from_raw([0; 65]).into(),
+    };
+    let extrinsic = unsigned.with_signature(&account, &placeholder)?;
+    let info: FeeInfo = rpc::request(
+        &extrinsic_opts.node.url()?,
+        "payment_queryInfo",
+        vec![hex(&extrinsic).into()],
+    )
+    .await?;
+    Ok(info.partial_fee)
+}
+
+/// Warns that an extrinsic submitted without a storage deposit limit may charge any deposit
+/// the signer can pay.
+pub(crate) fn warn_unbounded_deposit(limit: Option<u128>) {
+    if limit.is_none() {
+        eprintln!(
+            "{} No --storage-deposit-limit is set, the storage deposit charged is unbounded",
+            "warning:".yellow().bold()
+        );
+    }
+}
+
+/// Returns the gas limit as expected by the RPCs, `null` for the maximum the node allows.
+fn gas_limit(limit: Option<Weight>) -> Value {
+    limit.map_or(Value::Null, Weight::to_value)
+}
+
+/// Returns the storage deposit limit as expected by the RPCs, `null` if it is unbounded.
+fn deposit_limit(limit: Option<u128>) -> Value {
+    limit.map_or(Value::Null, |limit| format!("0x{:x}", limit).into())
+}
+
+/// Deserializes `0x` prefixed hex bytes.
+fn bytes<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<u8>, D::Error> {
+    let hex = String::deserialize(deserializer)?;
+    decode_hex(&hex).map_err(de::Error::custom)
+}
+
+/// Deserializes the return flags, a number or `{ "bits": .. }`.
+fn flags<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u32, D::Error> {
+    let flags = Value::deserialize(deserializer)?;
+    flags
+        .as_u64()
+        .or_else(|| flags.get("bits").and_then(Value::as_u64))
+        .map(|flags| flags as u32)
+        .ok_or_else(|| de::Error::custom(format!("Invalid return flags {}", flags)))
+}
+
+/// Deserializes a balance, a number or a decimal or `0x` prefixed hex string.
+pub(super) fn balance<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u128, D::Error> {
+    struct BalanceVisitor;
+
+    impl<'de> de::Visitor<'de> for BalanceVisitor {
+        type Value = u128;
+
+        fn expecting(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+            write!(f, "a balance")
+        }
+
+        fn visit_u64<E: de::Error>(self, value: u64) -> Result<u128, E> {
+            Ok(value.into())
+        }
+
+        fn visit_u128<E: de::Error>(self, value: u128) -> Result<u128, E> {
+            Ok(value)
+        }
+
+        fn visit_str<E: de::Error>(self, value: &str) -> Result<u128, E> {
+            match value.strip_prefix("0x") {
+                Some(hex) => u128::from_str_radix(hex, 16),
+                None => value.parse(),
+            }
+            .map_err(E::custom)
+        }
+    }
+
+    deserializer.deserialize_any(BalanceVisitor)
+}
+
+/// Serializes a balance as a string, which keeps its precision in JSON.
+pub(super) fn balance_string<S: serde::Serializer>(
+    value: &u128,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    serializer.collect_str(value)
+}
+
+/// Returns the `0x` prefixed hex representation of `bytes`, as expected by the RPCs.
+pub(super) fn hex(bytes: &[u8]) -> String {
+    format!("0x{}", hex::encode(bytes))
+}
+
+/// Executes a call of the contract `dest` with `data` on the state of the node, at the block
+/// `at` or at the best block. Without a `gas_limit` the node allows the maximum gas.
+pub(crate) fn call(
+    extrinsic_opts: &ExtrinsicOpts,
+    dest: &AccountId,
+    value: u128,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<u128>,
+    data: &[u8],
+    at: Option<H256>,
+) -> Result<ContractResult<ExecReturnValue>> {
+    let request = json!({
+        "origin": submit::origin(extrinsic_opts)?.to_string(),
+        "dest": dest.to_string(),
+        "value": format!("0x{:x}", value),
+        "gasLimit": self::gas_limit(gas_limit),
+        "storageDepositLimit": deposit_limit(storage_deposit_limit),
+        "inputData": hex(data),
+    });
+    let mut params = vec![request];
+    params.extend(at.map(|at| Value::from(hex(at.as_bytes()))));
+    extrinsic_opts.node.block_on(async {
+        let result: ContractResult<_> =
+            rpc::request(&extrinsic_opts.node.url()?, "contracts_call", params).await?;
+        Ok(result.resolve_error(&extrinsic_opts.node.url()?).await)
+    })
+}
+
+/// Executes an instantiation of the code `code_hash` with `data` and `salt` on the state of the
+/// node. Without a `gas_limit` the node allows the maximum gas.
+pub(crate) fn instantiate(
+    extrinsic_opts: &ExtrinsicOpts,
+    value: u128,
+    gas_limit: Option<Weight>,
+    storage_deposit_limit: Option<u128>,
+    code_hash: H256,
+    data: &[u8],
+    salt: &[u8],
+) -> Result<ContractResult<InstantiateReturnValue>> {
+    let request = json!({
+        "origin": submit::origin(extrinsic_opts)?.to_string(),
+        "value": format!("0x{:x}", value),
+        "gasLimit": self::gas_limit(gas_limit),
+        "storageDepositLimit": deposit_limit(storage_deposit_limit),
+        "code": { "existing": hex(code_hash.as_bytes()) },
+        "data": hex(data),
+        "salt": hex(salt),
+    });
+    extrinsic_opts.node.block_on(async {
+        let result: ContractResult<_> = rpc::request(
+            &extrinsic_opts.node.url()?,
+            "contracts_instantiate",
+            vec![request],
+        )
+        .await?;
+        Ok(result.resolve_error(&extrinsic_opts.node.url()?).await)
+    })
+}
+
+/// Renders the outcome of a call, decoding the returned value as the type `return_type` of
+/// the registry, if given.
+pub(crate) fn render_call(
+    result: &ContractResult<ExecReturnValue>,
+    return_type: Option<(&TypeRegistry, u32)>,
+    costs: &Costs,
+) -> String {
+    let outcome = match &result.result {
+        Ok(value) => render_return_value(value, return_type),
+        Err(err) => format!(
+            "{} {}",
+            "Failed:".bright_red().bold(),
+            result.display_error(err)
+        ),
+    };
+    render(result, outcome, costs)
+}
+
+/// Renders the outcome of an instantiation.
+pub(crate) fn render_instantiate(
+    result: &ContractResult<InstantiateReturnValue>,
+    costs: &Costs,
+) -> String {
+    let outcome = match &result.result {
+        Ok(value) if value.result.reverted() => render_return_value(&value.result, None),
+        Ok(value) => format!("{} {}", "Contract account:".bold(), value.account_id),
+        Err(err) => format!(
+            "{} {}",
+            "Failed:".bright_red().bold(),
+            result.display_error(err)
+        ),
+    };
+    render(result, outcome, costs)
+}
+
+fn render_return_value(
+    value: &ExecReturnValue,
+    return_type: Option<(&TypeRegistry, u32)>,
+) -> String {
+    let decoded = match return_type {
+        Some((types, id)) => match types.decode_return_value(id, &value.data) {
+            Ok(ReturnValue::Value(decoded)) => decoded.to_pretty_string(),
+            Ok(ReturnValue::LangError(err)) => {
+                return format!(
+                    "{} The contract could not dispatch the message: {}",
+                    "Failed:".bright_red().bold(),
+                    err
+                )
+            }
+            Err(err) => {
+                eprintln!(
+                    "{} The returned value is not a `{}`: {}",
+                    "warning:".yellow().bold(),
+                    types.type_name(id),
+                    err
+                );
+                hex(&value.data)
+            }
+        },
+        None => hex(&value.data),
+    };
+    if value.reverted() {
+        format!("{} {}", "Reverted:".bright_red().bold(), decoded)
+    } else {
+        format!("{} {}", "Returned:".bold(), decoded)
+    }
+}
+
+fn render<R>(result: &ContractResult<R>, outcome: String, costs: &Costs) -> String {
+    let mut out = String::new();
+    // writing to a `String` never fails
+    let _ = writeln!(out, "{}", outcome);
+    let _ = write!(out, "{} {}", "Gas consumed:".bold(), result.gas_consumed);
+    if let Some(gas_required) = &result.gas_required {
+        let _ = write!(out, ", {} {}", "required:".bold(), gas_required);
+    }
+    let _ = writeln!(out);
+    if let Some(storage_deposit) = &result.storage_deposit {
+        let _ = writeln!(
+            out,
+            "{} {}",
+            "Storage deposit:".bold(),
+            storage_deposit.format(costs.token.as_ref())
+        );
+    }
+    if let Some(fee) = costs.fee {
+        let _ = writeln!(
+            out,
+            "{} {}",
+            "Estimated fee:".bold(),
+            format_balance(fee, costs.token.as_ref())
+        );
+    }
+    if !result.debug_message.is_empty() {
+        let _ = writeln!(out, "{}", "Debug message:".bold());
+        for line in String::from_utf8_lossy(&result.debug_message).lines() {
+            let _ = writeln!(out, "  {}", line);
+        }
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{tests::metadata_v1, ContractAbi};
+
+    #[test]
+    fn renders_reverted_call() {
+        let result: ContractResult<ExecReturnValue> = serde_json::from_value(json!({
+            "gasConsumed": 1200,
+            "gasRequired": 1500,
+            "storageDeposit": { "charge": "0x64" },
+            // "withdraw failed\n"
+            "debugMessage": "0x7769746864726177206661696c65640a",
+            "result": { "Ok": { "flags": { "bits": 1 }, "data": "0x0101e8030000000000000000000000000000" } }
+        }))
+        .unwrap();
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+
+        colored::control::set_override(false);
+        assert_eq!(
+            render_call(&result, Some((&abi.types, 5)), &Costs::default()),
+            "\
+Reverted: Err(LimitExceeded { limit: 1000 })
+Gas consumed: 1200, required: 1500
+Storage deposit: charges 100
+Debug message:
+  withdraw failed
+"
+        );
+    }
+
+    #[test]
+    fn renders_failed_instantiation() {
+        let result: ContractResult<InstantiateReturnValue> = serde_json::from_value(json!({
+            "gasConsumed": 500,
+            "result": { "Err": { "Module": { "index": 8, "error": 3 } } }
+        }))
+        .unwrap();
+
+        colored::control::set_override(false);
+        assert_eq!(
+            render_instantiate(&result, &Costs::default()),
+            "Failed: {\"Module\":{\"error\":3,\"index\":8}}\nGas consumed: 500\n"
+        );
+
+        let result = ContractResult {
+            error: Some(dispatch_error::describe("Contracts", "CodeNotFound")),
+            ..result
+        };
+        assert_eq!(
+            render_instantiate(&result, &Costs::default()),
+            "Failed: Contracts::CodeNotFound: no code is uploaded under the code hash\n\
+            Gas consumed: 500\n"
+        );
+    }
+
+    #[test]
+    fn renders_costs_in_token_units() {
+        let result: ContractResult<InstantiateReturnValue> = serde_json::from_value(json!({
+            "gasConsumed": 500,
+            "storageDeposit": { "Charge": "2000000000000" },
+            "result": { "Ok": {
+                "result": { "flags": 0, "data": "0x" },
+                "accountId": "5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM"
+            } }
+        }))
+        .unwrap();
+        let costs = Costs {
+            fee: Some(1_234_500_000),
+            token: Some(Token {
+                symbol: "ROC".to_string(),
+                decimals: 12,
+            }),
+        };
+
+        colored::control::set_override(false);
+        assert_eq!(
+            render_instantiate(&result, &costs),
+            "\
+Contract account: 5C4hrfjw9DjXZTzV3MwzrrAr9P1MJhSrvWGWqi1eSuyUpnhM
+Gas consumed: 500
+Storage deposit: charges 2000000000000 (2 ROC)
+Estimated fee: 1234500000 (0.0012345 ROC)
+"
+        );
+    }
+
+    #[test]
+    fn checks_storage_deposit_limit() {
+        assert!(StorageDeposit::Charge(100).check_limit(Some(100)).is_ok());
+        assert!(StorageDeposit::Charge(100).check_limit(None).is_ok());
+        assert!(StorageDeposit::Refund(500).check_limit(Some(100)).is_ok());
+        assert_eq!(
+            StorageDeposit::Charge(101)
+                .check_limit(Some(100))
+                .unwrap_err()
+                .to_string(),
+            "The storage deposit of 101 exceeds the --storage-deposit-limit 100"
+        );
+    }
+    #[test]
+    fn encodes_both_dimensions_of_the_gas_limit() {
+        let v1 = Weight {
+            ref_time: 500_000_000,
+            proof_size: None,
+        };
+        assert_eq!(v1.encode(), Compact(500_000_000u64).encode());
+        assert_eq!(v1.to_value(), json!(500_000_000));
+        let v2 = Weight {
+            ref_time: 500_000_000,
+            proof_size: Some(65_536),
+        };
+        assert_eq!(v2.encode()[..v1.encode().len()], v1.encode()[..]);
+        assert_eq!(
+            v2.encode()[v1.encode().len()..],
+            Compact(65_536u64).encode()[..]
+        );
+        assert_eq!(
+            v2.to_value(),
+            json!({ "refTime": 500_000_000, "proofSize": 65_536 })
+        );
+        assert_eq!(v2.to_string(), "ref_time 500000000, proof_size 65536");
+    }
+}
@@ -0,0 +1,115 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Encodes the data calling constructors and messages with `cargo contract encode`.
+
+use crate::{
+    abi::{encode_hex, parse_arg_value, ContractAbi, MessageSpec},
+    cmd::metadata::built_metadata_path,
+};
+use anyhow::{Context, Result};
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Encodes the data calling a message or constructor, its selector followed by the arguments.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "encode")]
+pub struct EncodeCommand {
+    /// The label of the message, or of the constructor with `--constructor`
+    label: String,
+    /// The arguments of the message, as JSON or as plain strings
+    #[structopt(long)]
+    args: Vec<String>,
+    /// A JSON file with the arguments of the message, keyed by their names
+    #[structopt(long, parse(from_os_str), conflicts_with = "args")]
+    args_file: Option<PathBuf>,
+    /// Encode the call of the constructor labeled `label` instead of a message
+    #[structopt(long)]
+    constructor: bool,
+    /// Path to the Cargo.toml of the contract whose built metadata to read
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+}
+
+impl EncodeCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        let spec = if self.constructor {
+            abi.constructor(&self.label)?
+        } else {
+            abi.message(&self.label)?
+        };
+        let data = encode_args(&abi, spec, &self.args, self.args_file.as_ref())?;
+        println!("{}", encode_hex(&data));
+        Ok(None)
+    }
+}
+
+/// Encodes the data calling `spec` with the `args` given on the command line, or with the
+/// arguments of the JSON `args_file` keyed by their names.
+pub(crate) fn encode_args(
+    abi: &ContractAbi,
+    spec: &MessageSpec,
+    args: &[String],
+    args_file: Option<&PathBuf>,
+) -> Result<Vec<u8>> {
+    let args = match args_file {
+        Some(path) => {
+            let file =
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+            let args = serde_json::from_slice(&file)
+                .with_context(|| format!("Failed to parse {}", path.display()))?;
+            abi.args_by_label(spec, &args)
+                .with_context(|| format!("Invalid arguments in {}", path.display()))?
+        }
+        None => args.iter().map(|arg| parse_arg_value(arg)).collect(),
+    };
+    abi.encode_call(spec, &args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{abi::tests::metadata_v1, util::tests::with_tmp_dir};
+
+    #[test]
+    fn encodes_args_given_on_the_command_line_or_in_a_file() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let withdraw = abi.message("withdraw").unwrap();
+        let data = encode_args(&abi, withdraw, &["5".to_string()], None).unwrap();
+        assert_eq!(
+            encode_hex(&data),
+            "0x410fcc9d05000000000000000000000000000000"
+        );
+
+        with_tmp_dir(|path| {
+            let args_file = path.join("args.json");
+            fs::write(&args_file, r#"{ "value": 5 }"#)?;
+            assert_eq!(encode_args(&abi, withdraw, &[], Some(&args_file))?, data);
+
+            fs::write(&args_file, r#"{ "amount": 5 }"#)?;
+            assert!(encode_args(&abi, withdraw, &[], Some(&args_file)).is_err());
+            Ok(())
+        })
+    }
+}
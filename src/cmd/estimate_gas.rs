@@ -0,0 +1,311 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Estimates the gas and fee of calling a message or constructor with a dry run, without
+//! submitting an extrinsic.
+
+use std::{fmt::Write as _, path::PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use sp_core::H256;
+use structopt::StructOpt;
+use subxt::contracts::InstantiateCall;
+
+use super::{
+    call,
+    chain::{parse_account, AccountId},
+    dry_run::{self, Costs, StorageDeposit, Weight},
+    encode::encode_args,
+    extrinsic_output::load_abi,
+    gas::GasOpts,
+    instantiate::InstantiateWithLimitCall,
+    submit::SubmitError,
+    token::{self, format_balance, Balance, Token},
+};
+use crate::{parse_code_hash, ExtrinsicOpts};
+
+/// Estimates the gas required by a message or constructor and the fee of submitting it.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "estimate-gas")]
+pub struct EstimateGasCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// The account of the contract whose message to call
+    #[structopt(long, parse(try_from_str = parse_account), required_unless = "code-hash")]
+    contract: Option<AccountId>,
+    /// The hash of the uploaded code whose constructor to call
+    #[structopt(long, parse(try_from_str = parse_code_hash), conflicts_with = "contract")]
+    code_hash: Option<H256>,
+    /// The label of the message to call
+    #[structopt(long, requires = "contract")]
+    message: Option<String>,
+    /// The label of the constructor to call
+    #[structopt(long, requires = "code-hash", conflicts_with = "message")]
+    constructor: Option<String>,
+    /// The arguments of the message or constructor, as JSON or as plain strings
+    #[structopt(long)]
+    args: Vec<String>,
+    /// A JSON file with the arguments of the message or constructor, keyed by their names
+    #[structopt(long, parse(from_os_str), conflicts_with = "args")]
+    args_file: Option<PathBuf>,
+    /// The value transferred with the call: plancks, or an amount of the token of the chain,
+    /// e.g. `1.5DOT`
+    #[structopt(long)]
+    value: Option<Balance>,
+    /// The value transferred with the call in plancks instead of `--value`
+    #[structopt(long, conflicts_with = "value")]
+    value_raw: Option<u128>,
+    #[structopt(flatten)]
+    gas: GasOpts,
+    /// Path to the Cargo.toml of the contract whose metadata encodes the arguments
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file encoding the arguments instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the estimate as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The gas and fee estimated for a call or instantiation.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GasEstimate {
+    gas_consumed: Weight,
+    gas_required: Weight,
+    /// The gas limit to submit the extrinsic with: the required gas plus the margin, unless
+    /// given.
+    gas_limit: Weight,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    storage_deposit: Option<StorageDeposit>,
+    /// The fee of the extrinsic with the gas limit, as a string to keep its precision.
+    #[serde(serialize_with = "dry_run::balance_string")]
+    estimated_fee: u128,
+}
+
+impl EstimateGasCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Estimating gas requires the metadata of the contract")?;
+        let value = match (self.value_raw, &self.value) {
+            (Some(value), _) => value,
+            (None, Some(value)) => value.plancks(&self.extrinsic_opts.node.url()?)?,
+            (None, None) => 0,
+        };
+
+        let (outcome, estimate) = match (&self.contract, self.code_hash) {
+            (Some(contract), _) => {
+                let label = self.message.as_deref().context("Missing `--message`")?;
+                let data = encode_args(
+                    &abi,
+                    abi.message(label)?,
+                    &self.args,
+                    self.args_file.as_ref(),
+                )?;
+                let result = dry_run::call(
+                    &self.extrinsic_opts,
+                    contract,
+                    value,
+                    self.gas.dry_run_limit(),
+                    None,
+                    &data,
+                    None,
+                )?;
+                let failure = match &result.result {
+                    Ok(value) if !value.reverted() => None,
+                    _ => Some(dry_run::render_call(&result, None, &Costs::default())),
+                };
+                (
+                    failure,
+                    (
+                        result.gas_consumed,
+                        result.gas_required,
+                        result.storage_deposit,
+                        data,
+                    ),
+                )
+            }
+            (None, Some(code_hash)) => {
+                let label = self
+                    .constructor
+                    .as_deref()
+                    .context("Missing `--constructor`")?;
+                let data = encode_args(
+                    &abi,
+                    abi.constructor(label)?,
+                    &self.args,
+                    self.args_file.as_ref(),
+                )?;
+                let result = dry_run::instantiate(
+                    &self.extrinsic_opts,
+                    value,
+                    self.gas.dry_run_limit(),
+                    None,
+                    code_hash,
+                    &data,
+                    &[],
+                )?;
+                let failure = match &result.result {
+                    Ok(value) if !value.result.reverted() => None,
+                    _ => Some(dry_run::render_instantiate(&result, &Costs::default())),
+                };
+                (
+                    failure,
+                    (
+                        result.gas_consumed,
+                        result.gas_required,
+                        result.storage_deposit,
+                        data,
+                    ),
+                )
+            }
+            (None, None) => anyhow::bail!("Either `--contract` or `--code-hash` is required"),
+        };
+        if let Some(outcome) = outcome {
+            return Err(
+                anyhow::Error::from(SubmitError::DryRunFailed).context(format!(
+                    "The dry run failed, no gas is estimated:\n{}",
+                    outcome
+                )),
+            );
+        }
+        let (gas_consumed, gas_required, storage_deposit, data) = estimate;
+        let gas_required = gas_required.unwrap_or(gas_consumed);
+        let gas_limit = self.gas.limit(Some(gas_required));
+        let estimate = GasEstimate {
+            gas_consumed,
+            gas_required,
+            gas_limit,
+            storage_deposit,
+            estimated_fee: self.query_fee(value, gas_limit, &data)?,
+        };
+
+        if self.output_json {
+            return Ok(Some(serde_json::to_string_pretty(&estimate)?));
+        }
+        let token =
+            async_std::task::block_on(token::fetch_or_none(&self.extrinsic_opts.node.url()?));
+        print!("{}", render(&estimate, token.as_ref()));
+        Ok(None)
+    }
+
+    /// Returns the fee of the extrinsic calling with `value`, `data` and the gas limit
+    /// `gas_limit`.
+    fn query_fee(&self, value: u128, gas_limit: Weight, data: &[u8]) -> Result<u128> {
+        self.extrinsic_opts
+            .node
+            .block_on(dry_run::query_fee(&self.extrinsic_opts, |cli| {
+                match (&self.contract, &self.code_hash, gas_limit.proof_size) {
+                    (Some(contract), _, _) => {
+                        call::encode_call(cli, contract, value, gas_limit, None, data)
+                    }
+                    (None, Some(code_hash), None) => cli.encode(InstantiateCall {
+                        endowment: value,
+                        gas_limit: gas_limit.ref_time,
+                        code_hash,
+                        data,
+                    }),
+                    (None, Some(code_hash), Some(_)) => cli.encode(InstantiateWithLimitCall {
+                        value,
+                        gas_limit,
+                        storage_deposit_limit: None,
+                        code_hash,
+                        data,
+                        salt: &[],
+                    }),
+                    (None, None, _) => unreachable!("checked before the dry run"),
+                }
+            }))
+    }
+}
+
+fn render(estimate: &GasEstimate, token: Option<&Token>) -> String {
+    let mut out = String::new();
+    // writing to a `String` never fails
+    let _ = writeln!(out, "{} {}", "Gas required:".bold(), estimate.gas_required);
+    let _ = writeln!(out, "{} {}", "Gas consumed:".bold(), estimate.gas_consumed);
+    let _ = writeln!(out, "{} {}", "Gas limit:".bold(), estimate.gas_limit);
+    if let Some(storage_deposit) = &estimate.storage_deposit {
+        let _ = writeln!(
+            out,
+            "{} {}",
+            "Storage deposit:".bold(),
+            storage_deposit.format(token)
+        );
+    }
+    let _ = writeln!(
+        out,
+        "{} {}",
+        "Estimated fee:".bold(),
+        format_balance(estimate.estimated_fee, token)
+    );
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn renders_estimate() {
+        let estimate = GasEstimate {
+            gas_consumed: serde_json::from_value(json!({ "refTime": 1200, "proofSize": 30 }))
+                .unwrap(),
+            gas_required: serde_json::from_value(json!({ "refTime": 1500, "proofSize": 40 }))
+                .unwrap(),
+            gas_limit: Weight {
+                ref_time: 1650,
+                proof_size: Some(44),
+            },
+            storage_deposit: Some(StorageDeposit::Charge(100)),
+            estimated_fee: 125_000_000_000,
+        };
+
+        colored::control::set_override(false);
+        assert_eq!(
+            render(&estimate, None),
+            "\
+Gas required: ref_time 1500, proof_size 40
+Gas consumed: ref_time 1200, proof_size 30
+Gas limit: ref_time 1650, proof_size 44
+Storage deposit: charges 100
+Estimated fee: 125000000000
+"
+        );
+        let token = Token {
+            symbol: "ROC".to_string(),
+            decimals: 12,
+        };
+        assert!(render(&estimate, Some(&token))
+            .ends_with("Storage deposit: charges 100 (0.0000000001 ROC)\nEstimated fee: 125000000000 (0.125 ROC)\n"));
+        assert_eq!(
+            // `to_value` does not support the u128 balances of the deposit
+            serde_json::from_str::<serde_json::Value>(&serde_json::to_string(&estimate).unwrap())
+                .unwrap(),
+            json!({
+                "gasConsumed": { "refTime": 1200, "proofSize": 30 },
+                "gasRequired": { "refTime": 1500, "proofSize": 40 },
+                "gasLimit": { "refTime": 1650, "proofSize": 44 },
+                "storageDeposit": { "charge": 100 },
+                "estimatedFee": "125000000000",
+            })
+        );
+    }
+}
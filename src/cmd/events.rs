@@ -0,0 +1,423 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Decodes the `System::Events` stored for a block with the metadata of its runtime.
+//!
+//! `subxt` only decodes the events of the extrinsics it watches itself, the events of blocks
+//! which are looked up afterwards are decoded here, into the same [`RawEvent`]s.
+
+use anyhow::{Context, Result};
+use frame_metadata::{
+    DecodeDifferent, RuntimeMetadata, RuntimeMetadataPrefixed, RuntimeMetadataV12, META_RESERVED,
+};
+use sp_core::H256;
+use subxt::{
+    sp_runtime::{
+        codec::{Compact, Decode, Encode, Input},
+        DispatchError,
+    },
+    system::Phase,
+    RawEvent,
+};
+
+use super::{chain::ChainTypes, dispatch_error, dry_run::hex, rpc, submit::SubmitError};
+use crate::abi::decode_hex;
+
+/// The events and errors of the pallets of a runtime, by the index of the pallet.
+pub(crate) struct RuntimeEvents {
+    pallets: Vec<Pallet>,
+    /// The sizes of the account ids and balances of the chain, whose events hold them.
+    types: ChainTypes,
+}
+
+struct Pallet {
+    index: u8,
+    name: String,
+    /// The name and the argument types of each event.
+    events: Vec<(String, Vec<String>)>,
+    errors: Vec<String>,
+}
+
+/// A decoded event record, or the error of a failed dispatch reported by an event.
+#[derive(Debug)]
+pub(crate) enum Raw {
+    Event(RawEvent),
+    Error(String),
+}
+
+impl RuntimeEvents {
+    /// Fetches the metadata of the runtime of the block `block`, and the types of the chain.
+    pub(crate) async fn fetch(url: &url::Url, block: &H256) -> Result<Self> {
+        let metadata: String =
+            rpc::request(url, "state_getMetadata", vec![hex(&block.0).into()]).await?;
+        let runtime = Self::from_metadata(&decode_hex(&metadata)?)?;
+        Ok(Self {
+            types: ChainTypes::detect(url).await?.unwrap_or_default(),
+            ..runtime
+        })
+    }
+
+    /// Reads the events and errors of the pallets from the SCALE encoded `metadata`, for a
+    /// chain with the default types.
+    pub(crate) fn from_metadata(metadata: &[u8]) -> Result<Self> {
+        let mut pallets = Vec::new();
+        for module in decoded(runtime_metadata(metadata)?.modules) {
+            let events = match module.event {
+                Some(events) => decoded(events)
+                    .into_iter()
+                    .map(|event| (decoded(event.name), decoded(event.arguments)))
+                    .collect(),
+                None => Vec::new(),
+            };
+            let errors = decoded(module.errors)
+                .into_iter()
+                .map(|error| decoded(error.name))
+                .collect();
+            pallets.push(Pallet {
+                index: module.index,
+                name: decoded(module.name),
+                events,
+                errors,
+            });
+        }
+        Ok(Self {
+            pallets,
+            types: ChainTypes::default(),
+        })
+    }
+
+    /// Decodes the SCALE encoded `System::Events` of a block.
+    ///
+    /// As with `subxt`, the `DispatchResult` and `DispatchError` arguments of an event are
+    /// not part of its data, an error is recorded in its place.
+    pub(crate) fn decode(&self, mut events: &[u8]) -> Result<Vec<(Phase, Raw)>> {
+        let input = &mut events;
+        let len = <Compact<u32>>::decode(input)?.0;
+        let mut records = Vec::new();
+        for _ in 0..len {
+            let phase = Phase::decode(input)?;
+            let pallet_index = input.read_byte()?;
+            let pallet = self.pallet(pallet_index)?;
+            let event_index = input.read_byte()?;
+            let (name, arguments) = pallet.events.get(event_index as usize).with_context(|| {
+                format!("The pallet {} has no event {}", pallet.name, event_index)
+            })?;
+            let mut data = Vec::new();
+            let mut errors = Vec::new();
+            for argument in arguments {
+                self.decode_argument(argument, input, &mut data, &mut errors)
+                    .with_context(|| {
+                        format!("Failed to decode the event {}::{}", pallet.name, name)
+                    })?;
+            }
+            // the topics of the event
+            let _ = Vec::<H256>::decode(input)?;
+            if errors.is_empty() {
+                let event = RawEvent {
+                    module: pallet.name.clone(),
+                    variant: name.clone(),
+                    data,
+                };
+                records.push((phase.clone(), Raw::Event(event)));
+            }
+            for error in errors {
+                records.push((phase.clone(), Raw::Error(error)));
+            }
+        }
+        Ok(records)
+    }
+
+    /// Returns the events emitted by the extrinsic at `index` of the block whose
+    /// `System::Events` are `events`, or the error it failed with.
+    pub(crate) fn of_extrinsic(&self, events: &[u8], index: u32) -> Result<Vec<RawEvent>> {
+        let mut emitted = Vec::new();
+        for (phase, raw) in self.decode(events)? {
+            match (phase, raw) {
+                (Phase::ApplyExtrinsic(i), Raw::Event(event)) if i == index => emitted.push(event),
+                (Phase::ApplyExtrinsic(i), Raw::Error(err)) if i == index => {
+                    return Err(SubmitError::Failed(err).into())
+                }
+                _ => (),
+            }
+        }
+        Ok(emitted)
+    }
+
+    fn pallet(&self, index: u8) -> Result<&Pallet> {
+        self.pallets
+            .iter()
+            .find(|pallet| pallet.index == index)
+            .with_context(|| format!("The runtime has no pallet {}", index))
+    }
+
+    /// Copies the SCALE encoded argument of type `ty` from `input` to `output`.
+    fn decode_argument(
+        &self,
+        ty: &str,
+        input: &mut &[u8],
+        output: &mut Vec<u8>,
+        errors: &mut Vec<String>,
+    ) -> Result<()> {
+        let ty = ty.trim();
+        if let Some(item) = generic(ty, "Vec<") {
+            let len = <Compact<u32>>::decode(input)?;
+            output.extend(len.encode());
+            for _ in 0..len.0 {
+                self.decode_argument(item, input, output, errors)?;
+            }
+        } else if let Some(item) = generic(ty, "Option<") {
+            match input.read_byte()? {
+                0 => output.push(0),
+                1 => {
+                    output.push(1);
+                    self.decode_argument(item, input, output, errors)?;
+                }
+                byte => anyhow::bail!("Invalid first byte {} of an `Option`", byte),
+            }
+        } else if ty.starts_with('(') && ty.ends_with(')') {
+            for item in ty[1..ty.len() - 1].split(',') {
+                self.decode_argument(item, input, output, errors)?;
+            }
+        } else if ty == "DispatchResult" {
+            if let Err(err) = Result::<(), DispatchError>::decode(input)? {
+                errors.push(self.describe(err)?);
+            }
+        } else if ty == "DispatchError" {
+            let err = DispatchError::decode(input)?;
+            errors.push(self.describe(err)?);
+        } else {
+            let size = type_size(ty, &self.types)
+                .with_context(|| format!("The size of the type {} is unknown", ty))?;
+            let mut bytes = vec![0; size];
+            input.read(&mut bytes)?;
+            output.extend(bytes);
+        }
+        Ok(())
+    }
+
+    /// Returns the message of the dispatch error `err`, naming module errors by their pallet
+    /// and explaining common ones.
+    fn describe(&self, err: DispatchError) -> Result<String> {
+        Ok(match err {
+            DispatchError::Module { index, error, .. } => {
+                let pallet = self.pallet(index)?;
+                let name = pallet.errors.get(error as usize).with_context(|| {
+                    format!("The pallet {} has no error {}", pallet.name, error)
+                })?;
+                dispatch_error::describe(&pallet.name, name)
+            }
+            DispatchError::BadOrigin => {
+                "Bad origin: throw by ensure_signed, ensure_root or ensure_none.".to_string()
+            }
+            DispatchError::CannotLookup => {
+                "Cannot lookup some information required to validate the transaction.".to_string()
+            }
+            DispatchError::Other(err) => format!("Other error: {}", err),
+        })
+    }
+}
+
+/// Decodes the SCALE encoded `metadata` of a runtime.
+pub(crate) fn runtime_metadata(metadata: &[u8]) -> Result<RuntimeMetadataV12> {
+    let prefixed = RuntimeMetadataPrefixed::decode(&mut &metadata[..])
+        .context("Failed to decode the metadata of the runtime")?;
+    match prefixed {
+        RuntimeMetadataPrefixed(META_RESERVED, RuntimeMetadata::V12(metadata)) => Ok(metadata),
+        RuntimeMetadataPrefixed(META_RESERVED, _) => {
+            anyhow::bail!("Only the metadata V12 of runtimes is supported")
+        }
+        _ => anyhow::bail!("The metadata of the runtime lacks its magic number"),
+    }
+}
+
+/// Returns the item type of the generic type `ty` starting with `prefix`, e.g. `Vec<`.
+fn generic<'a>(ty: &'a str, prefix: &str) -> Option<&'a str> {
+    if ty.starts_with(prefix) && ty.ends_with('>') {
+        Some(&ty[prefix.len()..ty.len() - 1])
+    } else {
+        None
+    }
+}
+
+/// Returns the encoded size of the fixed size types of event arguments, as registered by
+/// `subxt` for its runtimes and by the commands for the types of the pallets they use. Account
+/// ids and balances have the sizes of the ones of the chain with `types`.
+fn type_size(ty: &str, types: &ChainTypes) -> Option<usize> {
+    Some(match ty {
+        "PhantomData" => 0,
+        "bool" | "u8" | "VoteThreshold" | "ProxyType" | "Status" => 1,
+        "u16" => 2,
+        "u32" | "ReferendumIndex" | "AccountIndex" | "SessionIndex" | "PropIndex"
+        | "ProposalIndex" | "AuthorityIndex" | "MemberCount" | "BlockNumber" | "Index" => 4,
+        "u64" | "AuthorityWeight" | "TaskAddress<BlockNumber>" | "Timepoint<BlockNumber>" => 8,
+        // the weight, class and fee payment of `DispatchInfo`
+        "DispatchInfo" => 10,
+        "Balance" => types.balance_bytes.unwrap_or(16),
+        "u128" | "Kind" => 16,
+        "AccountId" => types.account_id_bytes,
+        "Hash" | "AuthorityId" | "CodeHash<T>" | "CallHash" => 32,
+        _ => return None,
+    })
+}
+
+/// Returns the value of metadata decoded from its SCALE encoding.
+pub(crate) fn decoded<B, O>(value: DecodeDifferent<B, O>) -> O {
+    match value {
+        DecodeDifferent::Decoded(value) => value,
+        DecodeDifferent::Encode(_) => unreachable!("decoded metadata holds decoded values"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use frame_metadata::{
+        ErrorMetadata, EventMetadata, ExtrinsicMetadata, ModuleMetadata, RuntimeMetadataV12,
+    };
+
+    fn metadata() -> Vec<u8> {
+        let string = |s: &str| DecodeDifferent::Decoded(s.to_string());
+        let event = |name: &str, arguments: &[&str]| EventMetadata {
+            name: string(name),
+            arguments: DecodeDifferent::Decoded(arguments.iter().map(|a| a.to_string()).collect()),
+            documentation: DecodeDifferent::Decoded(Vec::new()),
+        };
+        let module = |index, name: &str, events, errors: &[&str]| ModuleMetadata {
+            name: string(name),
+            storage: None,
+            calls: None,
+            event: Some(DecodeDifferent::Decoded(events)),
+            constants: DecodeDifferent::Decoded(Vec::new()),
+            errors: DecodeDifferent::Decoded(
+                errors
+                    .iter()
+                    .map(|name| ErrorMetadata {
+                        name: string(name),
+                        documentation: DecodeDifferent::Decoded(Vec::new()),
+                    })
+                    .collect(),
+            ),
+            index,
+        };
+        let modules = vec![
+            module(
+                0,
+                "System",
+                vec![
+                    event("ExtrinsicSuccess", &["DispatchInfo"]),
+                    event("ExtrinsicFailed", &["DispatchError", "DispatchInfo"]),
+                ],
+                &[],
+            ),
+            module(
+                18,
+                "Contracts",
+                vec![event("ContractExecution", &["AccountId", "Vec<u8>"])],
+                &["OutOfGas", "ContractTrapped"],
+            ),
+        ];
+        RuntimeMetadataPrefixed(
+            META_RESERVED,
+            RuntimeMetadata::V12(RuntimeMetadataV12 {
+                modules: DecodeDifferent::Decoded(modules),
+                extrinsic: ExtrinsicMetadata {
+                    version: 4,
+                    signed_extensions: Vec::new(),
+                },
+            }),
+        )
+        .encode()
+    }
+
+    /// Returns the SCALE encoded `Phase::ApplyExtrinsic(index)`, which `subxt` only decodes.
+    fn apply_extrinsic(index: u32) -> Vec<u8> {
+        (0u8, index).encode()
+    }
+
+    #[test]
+    fn decodes_the_events_of_extrinsics() {
+        let runtime = RuntimeEvents::from_metadata(&metadata()).unwrap();
+        let info = [7u8; 10];
+        let mut events = Compact(3u32).encode();
+        // the contract of the first extrinsic emits an event and the extrinsic succeeds
+        events.extend(apply_extrinsic(0));
+        events.extend(&[18, 0]);
+        events.extend(&[1; 32]);
+        events.extend(vec![5u8, 6].encode());
+        events.extend(Vec::<H256>::new().encode());
+        events.extend(apply_extrinsic(0));
+        events.extend(&[0, 0]);
+        events.extend(&info);
+        events.extend(Vec::<H256>::new().encode());
+        // the second extrinsic runs out of gas
+        events.extend(apply_extrinsic(1));
+        events.extend(&[0, 1]);
+        events.extend(&[3, 18, 1]);
+        events.extend(&info);
+        events.extend(vec![H256::repeat_byte(2)].encode());
+
+        let emitted = runtime.of_extrinsic(&events, 0).unwrap();
+        assert_eq!(emitted.len(), 2);
+        assert_eq!(emitted[0].module, "Contracts");
+        assert_eq!(emitted[0].variant, "ContractExecution");
+        let mut data = vec![1; 32];
+        data.extend(vec![5u8, 6].encode());
+        assert_eq!(emitted[0].data, data);
+        assert_eq!(emitted[1].variant, "ExtrinsicSuccess");
+        assert_eq!(emitted[1].data, info);
+
+        let err = runtime.of_extrinsic(&events, 1).unwrap_err();
+        assert_eq!(
+            err.downcast_ref::<SubmitError>().unwrap().to_string(),
+            "The extrinsic failed: Contracts::ContractTrapped: the contract trapped, e.g. it \
+            panicked or an arithmetic operation overflowed; `--dry-run` shows its debug message"
+        );
+        assert!(runtime.of_extrinsic(&events, 2).unwrap().is_empty());
+    }
+
+    #[test]
+    fn decodes_the_accounts_of_the_chain() {
+        let runtime = RuntimeEvents {
+            types: ChainTypes {
+                account_id_bytes: 20,
+                balance_bytes: Some(16),
+            },
+            ..RuntimeEvents::from_metadata(&metadata()).unwrap()
+        };
+        let mut events = Compact(1u32).encode();
+        events.extend(apply_extrinsic(0));
+        events.extend(&[18, 0]);
+        events.extend(&[1; 20]);
+        events.extend(vec![5u8, 6].encode());
+        events.extend(Vec::<H256>::new().encode());
+
+        let emitted = runtime.of_extrinsic(&events, 0).unwrap();
+        let mut data = vec![1; 20];
+        data.extend(vec![5u8, 6].encode());
+        assert_eq!(emitted[0].data, data);
+    }
+
+    #[test]
+    fn fails_on_unknown_events() {
+        let runtime = RuntimeEvents::from_metadata(&metadata()).unwrap();
+        let mut events = Compact(1u32).encode();
+        // the phase `Finalization`
+        events.push(1);
+        events.extend(&[18, 1]);
+        let err = runtime.decode(&events).unwrap_err();
+        assert_eq!(err.to_string(), "The pallet Contracts has no event 1");
+    }
+}
@@ -0,0 +1,123 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Links to submitted extrinsics and instantiated contracts on a block explorer, configured with
+//! `--explorer` or the `explorer` of the network.
+
+use std::str::FromStr;
+
+use sp_core::H256;
+use url::Url;
+
+/// The polkadot.js apps, which connect to the node of the extrinsic.
+const POLKADOT_JS_APPS: &str = "https://polkadot.js.org/apps/";
+
+/// A block explorer to link the outcome of extrinsics on.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Explorer {
+    /// The polkadot.js apps, which link to the block of an extrinsic only.
+    PolkadotJs,
+    /// A Subscan explorer of the chain, e.g. `https://astar.subscan.io`.
+    Subscan(Url),
+}
+
+impl FromStr for Explorer {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input {
+            "polkadot-js" => Ok(Explorer::PolkadotJs),
+            url => Url::parse(url).map(Explorer::Subscan).map_err(|_| {
+                format!(
+                    "Expected `polkadot-js` or the url of a Subscan explorer, got `{}`",
+                    url
+                )
+            }),
+        }
+    }
+}
+
+impl Explorer {
+    /// Returns the link to the extrinsic `extrinsic_hash` included in `block_hash`, submitted
+    /// to the node `url`.
+    pub fn extrinsic(&self, url: &Url, block_hash: H256, extrinsic_hash: H256) -> String {
+        match self {
+            Explorer::PolkadotJs => {
+                let mut link = Url::parse(POLKADOT_JS_APPS).expect("the url is valid");
+                link.query_pairs_mut().append_pair("rpc", url.as_str());
+                link.set_fragment(Some(&format!("/explorer/query/{:?}", block_hash)));
+                link.to_string()
+            }
+            Explorer::Subscan(base) => format!(
+                "{}/extrinsic/{:?}",
+                base.as_str().trim_end_matches('/'),
+                extrinsic_hash
+            ),
+        }
+    }
+
+    /// Returns the link to the account of the contract with the SS58 address `contract`, if the
+    /// explorer has pages for accounts.
+    pub fn account(&self, contract: &str) -> Option<String> {
+        match self {
+            Explorer::PolkadotJs => None,
+            Explorer::Subscan(base) => Some(format!(
+                "{}/account/{}",
+                base.as_str().trim_end_matches('/'),
+                contract
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn links_extrinsics_and_contracts() {
+        let url = Url::parse("wss://rpc.astar.network").unwrap();
+        let block_hash = H256::repeat_byte(1);
+        let extrinsic_hash = H256::repeat_byte(2);
+
+        let subscan: Explorer = "https://astar.subscan.io/".parse().unwrap();
+        assert_eq!(
+            subscan.extrinsic(&url, block_hash, extrinsic_hash),
+            format!("https://astar.subscan.io/extrinsic/0x{}", "02".repeat(32))
+        );
+        assert_eq!(
+            subscan.account("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"),
+            Some(
+                "https://astar.subscan.io/account/5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+                    .to_string()
+            )
+        );
+
+        let apps: Explorer = "polkadot-js".parse().unwrap();
+        assert_eq!(
+            apps.extrinsic(&url, block_hash, extrinsic_hash),
+            format!(
+                "https://polkadot.js.org/apps/?rpc=wss%3A%2F%2Frpc.astar.network%2F#/explorer/query/0x{}",
+                "01".repeat(32)
+            )
+        );
+        assert_eq!(
+            apps.account("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"),
+            None
+        );
+        assert!("subscan".parse::<Explorer>().is_err());
+    }
+}
@@ -0,0 +1,260 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The outcome of submitted extrinsics, which is printed as JSON with `--output-json`.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use codec::{Compact, Decode};
+use serde::Serializer;
+use sp_core::H256;
+use subxt::{DefaultNodeRuntime, ExtrinsicSuccess, RawEvent};
+
+use super::{
+    chain::AccountId,
+    dry_run::{StorageDeposit, Weight},
+    metadata::built_metadata_path,
+    token::Token,
+};
+use crate::{
+    abi::{ContractAbi, DecodedEvent},
+    ExtrinsicOpts,
+};
+
+/// The outcome of an extrinsic included in a block.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ExtrinsicOutput {
+    /// The hash of the block the extrinsic was included in.
+    pub block_hash: H256,
+    pub extrinsic_hash: H256,
+    /// The SS58 address of the instantiated or called contract.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub contract: Option<String>,
+    /// The hash of the uploaded or instantiated code.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub code_hash: Option<H256>,
+    /// The gas limit the call or instantiation was submitted with.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub gas_limit: Option<Weight>,
+    /// The actual weight of the extrinsic, which includes the gas consumed by the contract.
+    pub gas_consumed: Option<Weight>,
+    /// The storage deposit charged or refunded, as reported by a dry run before submitting.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub storage_deposit: Option<StorageDeposit>,
+    /// The events the extrinsic emitted.
+    pub events: Vec<EventOutput>,
+    /// The events emitted by the contract, decoded with its metadata.
+    pub contract_events: Vec<ContractEventOutput>,
+}
+
+/// An event emitted by an extrinsic.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct EventOutput {
+    pub pallet: String,
+    pub name: String,
+    /// The SCALE encoded fields of the event.
+    pub data: String,
+}
+
+/// An event emitted by a contract.
+#[derive(Debug, serde::Serialize)]
+pub(crate) struct ContractEventOutput {
+    /// The SS58 address of the contract.
+    pub contract: String,
+    #[serde(rename = "event", serialize_with = "serialize_event")]
+    pub decoded: DecodedEvent,
+}
+
+fn serialize_event<S: Serializer>(event: &DecodedEvent, serializer: S) -> Result<S::Ok, S::Error> {
+    serde::Serialize::serialize(
+        &serde_json::json!({ "name": event.label, "fields": event.fields_json() }),
+        serializer,
+    )
+}
+
+impl ExtrinsicOutput {
+    /// Returns the outcome of the extrinsic, without a contract, code hash or storage deposit.
+    ///
+    /// The events emitted by the `contract` are decoded with its metadata `abi`, events of
+    /// other contracts are only part of the raw events.
+    pub fn new(
+        success: &ExtrinsicSuccess<DefaultNodeRuntime>,
+        contract: Option<(&AccountId, &ContractAbi)>,
+    ) -> Self {
+        let gas_consumed = success
+            .find_event_raw("System", "ExtrinsicSuccess")
+            .and_then(|event| dispatch_weight(&event.data));
+        Self {
+            block_hash: success.block,
+            extrinsic_hash: success.extrinsic,
+            contract: None,
+            code_hash: None,
+            gas_limit: None,
+            gas_consumed,
+            storage_deposit: None,
+            events: success.events.iter().map(EventOutput::new).collect(),
+            contract_events: match contract {
+                Some((contract, abi)) => success
+                    .events
+                    .iter()
+                    .filter_map(|event| decode_contract_event(event, contract, abi))
+                    .collect(),
+                None => Vec::new(),
+            },
+        }
+    }
+
+    /// Returns a line with the storage deposit, if it is known, in units of `token` too if it
+    /// is known.
+    pub fn display_storage_deposit(&self, token: Option<&Token>) -> String {
+        match &self.storage_deposit {
+            Some(storage_deposit) => {
+                format!("\n\tStorage deposit: {}", storage_deposit.format(token))
+            }
+            None => String::new(),
+        }
+    }
+
+    /// Returns the line of the gas limit and the gas consumed, if they are known.
+    pub fn display_gas(&self) -> String {
+        match (&self.gas_limit, &self.gas_consumed) {
+            (Some(limit), Some(consumed)) => {
+                format!("\n\tGas limit: {}, consumed: {}", limit, consumed)
+            }
+            (Some(limit), None) => format!("\n\tGas limit: {}", limit),
+            (None, Some(consumed)) => format!("\n\tGas consumed: {}", consumed),
+            (None, None) => String::new(),
+        }
+    }
+
+    /// Returns the lines linking the extrinsic and the contract on the `--explorer`, or the one
+    /// of the `--network`, if one is given.
+    pub fn display_explorer_links(&self, extrinsic_opts: &ExtrinsicOpts) -> Result<String> {
+        // the extrinsics simulated on a fork are not on the chain of the explorer
+        let explorer = match extrinsic_opts.explorer()? {
+            Some(explorer) if extrinsic_opts.fork_from.is_none() => explorer,
+            _ => return Ok(String::new()),
+        };
+        let mut links = format!(
+            "\n\tExtrinsic: {}",
+            explorer.extrinsic(
+                &extrinsic_opts.node.url()?,
+                self.block_hash,
+                self.extrinsic_hash
+            )
+        );
+        if let Some(link) = self
+            .contract
+            .as_deref()
+            .and_then(|contract| explorer.account(contract))
+        {
+            links.push_str(&format!("\n\tContract: {}", link));
+        }
+        Ok(links)
+    }
+
+    /// Returns a line per decoded contract event, e.g. to follow the result of a call.
+    pub fn display_contract_events(&self) -> String {
+        self.contract_events
+            .iter()
+            .map(|event| format!("\n\tEvent {}", event.decoded))
+            .collect()
+    }
+
+    /// Returns the outcome as pretty printed JSON.
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+}
+
+/// Decodes the weight the `DispatchInfo` of `System::ExtrinsicSuccess` starts with, which is
+/// followed by the class of the extrinsic and whether it paid fees: a `u64`, or the compact
+/// computation time and proof size of runtimes which meter both.
+fn dispatch_weight(data: &[u8]) -> Option<Weight> {
+    if data.len() == 10 {
+        return Some(Weight {
+            ref_time: u64::decode(&mut &data[..8]).ok()?,
+            proof_size: None,
+        });
+    }
+    let input = &mut &data[..];
+    let ref_time = Compact::<u64>::decode(input).ok()?.0;
+    let proof_size = Compact::<u64>::decode(input).ok()?.0;
+    Some(Weight {
+        ref_time,
+        proof_size: Some(proof_size),
+    })
+}
+
+/// Decodes the event if it was emitted by `contract`.
+///
+/// Contracts emit events with `Contracts::ContractEmitted`, which was named
+/// `Contracts::ContractExecution` in earlier versions of the contracts pallet.
+pub(crate) fn decode_contract_event(
+    event: &RawEvent,
+    contract: &AccountId,
+    abi: &ContractAbi,
+) -> Option<ContractEventOutput> {
+    if event.module != "Contracts"
+        || !matches!(
+            event.variant.as_str(),
+            "ContractEmitted" | "ContractExecution"
+        )
+    {
+        return None;
+    }
+    // the emitter has the size of the accounts of the chain, as `contract` does
+    let data = event.data.strip_prefix(contract.as_bytes())?;
+    let data = Vec::<u8>::decode(&mut &data[..]).ok()?;
+    match abi.decode_event(&data) {
+        Ok(decoded) => Some(ContractEventOutput {
+            contract: contract.to_string(),
+            decoded,
+        }),
+        Err(err) => {
+            log::warn!("Failed to decode an event of the contract: {}", err);
+            None
+        }
+    }
+}
+
+/// Returns the metadata of the contract given with `--metadata`, or of the built contract.
+///
+/// Outside of a contract project there may be no metadata, then `None` is returned.
+pub(crate) fn load_abi(
+    manifest_path: Option<&PathBuf>,
+    metadata: Option<&PathBuf>,
+) -> Result<Option<ContractAbi>> {
+    if let Some(path) = metadata {
+        return Ok(Some(ContractAbi::load(path)?));
+    }
+    match built_metadata_path(manifest_path) {
+        Ok(path) => Ok(Some(ContractAbi::load(&path)?)),
+        Err(_) if manifest_path.is_none() => Ok(None),
+        Err(err) => Err(err),
+    }
+}
+
+impl EventOutput {
+    fn new(event: &RawEvent) -> Self {
+        Self {
+            pallet: event.module.clone(),
+            name: event.variant.clone(),
+            data: format!("0x{}", hex::encode(&event.data)),
+        }
+    }
+}
@@ -0,0 +1,292 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Simulates extrinsics on a fork of a live chain with `--fork-from <url>`, served locally by
+//! [chopsticks](https://github.com/AcalaNetwork/chopsticks), so that deployments and
+//! migrations can be rehearsed against the real state without spending funds.
+//!
+//! The fork is started before the arguments are parsed, which then point `--url` at it, and
+//! is stopped once the command finished.
+
+use std::{
+    env,
+    ffi::OsString,
+    io::{BufRead, BufReader},
+    process::{Child, Command, Stdio},
+    sync::mpsc,
+    thread,
+    time::Duration,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use regex::Regex;
+
+#[cfg(unix)]
+use std::sync::atomic::{AtomicI32, Ordering};
+
+/// The environment variable with the command running chopsticks, e.g. the path of an installed
+/// `chopsticks`.
+const CHOPSTICKS_VAR: &str = "CARGO_CONTRACT_CHOPSTICKS";
+
+/// The release of chopsticks run with `npx` if `$CARGO_CONTRACT_CHOPSTICKS` is not set.
+const CHOPSTICKS_PACKAGE: &str = "@acala-network/chopsticks@1.0.1";
+
+/// The flag allowing `npx` to install chopsticks if it is not cached yet.
+const INSTALL_FLAG: &str = "--install-chopsticks";
+
+/// How long to wait for the fork to serve its RPC, which includes fetching the runtime.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(180);
+
+/// A fork of a live chain served by chopsticks, which is stopped when dropped.
+pub(crate) struct Fork {
+    child: Child,
+}
+
+impl Drop for Fork {
+    fn drop(&mut self) {
+        // `npx` runs chopsticks as a child of its own, so the whole process group is stopped
+        #[cfg(unix)]
+        FORK_GROUP.store(0, Ordering::SeqCst);
+        #[cfg(unix)]
+        unsafe {
+            libc::kill(-(self.child.id() as libc::pid_t), libc::SIGTERM);
+        }
+        #[cfg(not(unix))]
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// The process group of the running fork, which is not reached by the signals sent to the
+/// group of the terminal, e.g. by Ctrl-C.
+#[cfg(unix)]
+static FORK_GROUP: AtomicI32 = AtomicI32::new(0);
+
+/// Stops the fork when the command is interrupted or terminated, as the destructor of the fork
+/// is not run then.
+#[cfg(unix)]
+fn stop_fork_on_signals(group: u32) {
+    extern "C" fn stop_fork(signal: libc::c_int) {
+        let group = FORK_GROUP.swap(0, Ordering::SeqCst);
+        unsafe {
+            if group != 0 {
+                libc::kill(-group, libc::SIGTERM);
+            }
+            libc::signal(signal, libc::SIG_DFL);
+            libc::raise(signal);
+        }
+    }
+    FORK_GROUP.store(group as i32, Ordering::SeqCst);
+    for signal in [libc::SIGINT, libc::SIGTERM, libc::SIGHUP] {
+        unsafe {
+            let handler: extern "C" fn(libc::c_int) = stop_fork;
+            libc::signal(signal, handler as libc::sighandler_t);
+        }
+    }
+}
+
+/// Returns `args` with `--url` pointing at a fork of the chain of `--fork-from`, and the fork,
+/// which has to be kept until the command finished. `args` are returned as they are without
+/// `--fork-from`.
+pub(crate) fn expand_args(args: Vec<OsString>) -> Result<(Vec<OsString>, Option<Fork>)> {
+    let (position, endpoint) = match fork_from(&args)? {
+        Some(fork_from) => fork_from,
+        None => return Ok((args, None)),
+    };
+    let command = match env::var(CHOPSTICKS_VAR) {
+        Ok(command) => command,
+        Err(_) => {
+            // `npx` installs missing packages without asking if the standard input is no
+            // terminal, so it is told whether it may
+            let install = args
+                .iter()
+                .take_while(|arg| *arg != "--")
+                .any(|arg| arg == INSTALL_FLAG);
+            let install = if install { "--yes" } else { "--no" };
+            format!("npx {} {}", install, CHOPSTICKS_PACKAGE)
+        }
+    };
+    let mut words = command.split_whitespace();
+    let program = words
+        .next()
+        .with_context(|| format!("${} is empty", CHOPSTICKS_VAR))?;
+    eprintln!(
+        "{} Forking {} with `{}`, nothing is submitted to the chain",
+        "Simulating:".cyan().bold(),
+        endpoint,
+        command
+    );
+    let mut chopsticks = Command::new(program);
+    chopsticks
+        .args(words)
+        .arg(format!("--endpoint={}", endpoint))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped());
+    #[cfg(unix)]
+    std::os::unix::process::CommandExt::process_group(&mut chopsticks, 0);
+    let child = chopsticks.spawn().with_context(|| {
+        format!(
+            "Failed to run `{}`, install Node.js or set ${} to the chopsticks command",
+            command, CHOPSTICKS_VAR
+        )
+    })?;
+    #[cfg(unix)]
+    stop_fork_on_signals(child.id());
+    let mut fork = Fork { child };
+    let port = wait_for_port(&mut fork)?;
+
+    let mut args = args;
+    let url = format!("ws://127.0.0.1:{}", port);
+    args.splice(position..position, vec!["--url".into(), url.into()]);
+    Ok((args, Some(fork)))
+}
+
+/// Returns the url of `--fork-from` in `args`, which replaces `--url`, and the position after
+/// it.
+fn fork_from(args: &[OsString]) -> Result<Option<(usize, String)>> {
+    let args = args
+        .iter()
+        .map(|arg| arg.to_string_lossy())
+        .take_while(|arg| arg != "--")
+        .collect::<Vec<_>>();
+    let mut endpoint = None;
+    for (i, arg) in args.iter().enumerate() {
+        if arg == "--fork-from" {
+            endpoint = args.get(i + 1).map(|url| (i + 2, url.to_string()));
+        } else if let Some(url) = arg.strip_prefix("--fork-from=") {
+            endpoint = Some((i + 1, url.to_string()));
+        }
+    }
+    if endpoint.is_some()
+        && args
+            .iter()
+            .any(|arg| arg == "--url" || arg.starts_with("--url="))
+    {
+        anyhow::bail!("`--fork-from` cannot be combined with `--url`, the fork replaces it")
+    }
+    Ok(endpoint)
+}
+
+/// Waits until the fork logs the port it serves its RPC on, and returns the port.
+fn wait_for_port(fork: &mut Fork) -> Result<u16> {
+    let stdout = fork
+        .child
+        .stdout
+        .take()
+        .expect("the stdout of chopsticks is piped");
+    let (sender, receiver) = mpsc::channel();
+    // the output is read until chopsticks exits, so that it never blocks on a full pipe
+    thread::spawn(move || {
+        for line in BufReader::new(stdout).lines().map_while(|line| line.ok()) {
+            log::debug!("chopsticks: {}", line);
+            if let Some(port) = listening_port(&line) {
+                let _ = sender.send(port);
+            }
+        }
+    });
+    match receiver.recv_timeout(STARTUP_TIMEOUT) {
+        Ok(port) => Ok(port),
+        Err(mpsc::RecvTimeoutError::Disconnected) => {
+            let status = fork.child.wait()?;
+            anyhow::bail!(
+                "chopsticks exited with {} before serving the fork. If `npx` did not find it, \
+                 pass {} to install it",
+                status,
+                INSTALL_FLAG
+            )
+        }
+        Err(mpsc::RecvTimeoutError::Timeout) => anyhow::bail!(
+            "The fork was not served after {} seconds",
+            STARTUP_TIMEOUT.as_secs()
+        ),
+    }
+}
+
+/// Returns the port of the line chopsticks logs once it serves the fork, e.g. `Astar RPC
+/// listening on port 8000`, or `listening on http://[::]:8000` of later releases.
+fn listening_port(line: &str) -> Option<u16> {
+    let pattern = Regex::new(r"listening on (?:port |\S*:)(\d+)").expect("the regex is valid");
+    pattern.captures(line)?[1].parse().ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<OsString> {
+        args.iter().map(OsString::from).collect()
+    }
+
+    #[test]
+    fn finds_the_chain_to_fork() {
+        let endpoint = fork_from(&args(&[
+            "cargo-contract",
+            "contract",
+            "call",
+            "--fork-from",
+            "wss://rpc.astar.network",
+        ]))
+        .unwrap();
+        assert_eq!(endpoint, Some((5, "wss://rpc.astar.network".to_string())));
+
+        let endpoint = fork_from(&args(&[
+            "cargo-contract",
+            "contract",
+            "call",
+            "--fork-from=wss://a",
+        ]))
+        .unwrap();
+        assert_eq!(endpoint, Some((4, "wss://a".to_string())));
+
+        let endpoint = fork_from(&args(&[
+            "cargo-contract",
+            "contract",
+            "call",
+            "--",
+            "--fork-from",
+        ]))
+        .unwrap();
+        assert_eq!(endpoint, None);
+
+        assert!(fork_from(&args(&[
+            "cargo-contract",
+            "contract",
+            "call",
+            "--url",
+            "ws://localhost:9944",
+            "--fork-from",
+            "wss://a",
+        ]))
+        .is_err());
+    }
+
+    #[test]
+    fn reads_the_port_of_the_fork() {
+        assert_eq!(
+            listening_port("[10:21:42.512] INFO: Astar RPC listening on port 8000"),
+            Some(8000)
+        );
+        assert_eq!(
+            listening_port("[10:21:42.512] INFO (xcm): Astar RPC listening on http://[::]:8001 and ws://[::]:8001"),
+            Some(8001)
+        );
+        assert_eq!(
+            listening_port("[10:21:40.107] INFO: Loading config file"),
+            None
+        );
+    }
+}
@@ -0,0 +1,501 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates random values of the types of the metadata, and simpler values to shrink them.
+//!
+//! The values are JSON, as [`ContractAbi::encode_call`](crate::abi::ContractAbi::encode_call)
+//! expects them, so that they can be written to reproduction scripts as they are.
+
+use std::convert::TryFrom;
+
+use contract_sandbox::AccountId;
+use rand::{rngs::StdRng, Rng};
+use serde_json::{Map, Value};
+
+use crate::abi::{decode_ss58, encode_ss58, Field, Primitive, TypeDef, TypeRegistry};
+
+/// Below this depth sequences are empty and enums take their variants without fields, so that
+/// the values of recursive types end.
+const MAX_DEPTH: usize = 4;
+
+/// The maximum length of generated strings, byte sequences and vectors.
+const MAX_LEN: usize = 8;
+
+/// The characters of generated strings.
+const CHARS: &[u8] = b"abcdefghijklmnopqrstuvwxyzABCDEFGHIJKLMNOPQRSTUVWXYZ0123456789 _-";
+
+/// Generates and shrinks values of the types of a [`TypeRegistry`].
+pub struct Generator<'a> {
+    types: &'a TypeRegistry,
+    /// The accounts which values of 32 bytes, usually account ids, are mostly taken from.
+    accounts: Vec<AccountId>,
+}
+
+impl<'a> Generator<'a> {
+    pub fn new(types: &'a TypeRegistry, accounts: Vec<AccountId>) -> Self {
+        Self { types, accounts }
+    }
+
+    /// Returns a random value of the type `id`.
+    ///
+    /// Integers are often the bounds of their type, which uncovers overflows.
+    pub fn value(&self, id: u32, rng: &mut StdRng) -> Value {
+        self.value_at(id, rng, 0)
+    }
+
+    fn value_at(&self, id: u32, rng: &mut StdRng, depth: usize) -> Value {
+        let ty = match self.types.resolve(id) {
+            Ok(ty) if depth <= 4 * MAX_DEPTH => ty,
+            _ => return Value::Null,
+        };
+        let len = |rng: &mut StdRng| {
+            if depth < MAX_DEPTH {
+                rng.gen_range(0..=MAX_LEN)
+            } else {
+                0
+            }
+        };
+        match &ty.def {
+            TypeDef::Primitive(primitive) => primitive_value(*primitive, rng),
+            TypeDef::Compact(_) => unsigned(u128::MAX >> (rng.gen_range(0..4) * 32), rng),
+            TypeDef::Sequence(ty) if self.is_byte(*ty) => {
+                let bytes = (0..len(rng)).map(|_| rng.gen()).collect::<Vec<u8>>();
+                hex(&bytes)
+            }
+            TypeDef::Sequence(ty) => Value::Array(
+                (0..len(rng))
+                    .map(|_| self.value_at(*ty, rng, depth + 1))
+                    .collect(),
+            ),
+            TypeDef::Array { len: 32, ty } if self.is_byte(*ty) => {
+                if !self.accounts.is_empty() && rng.gen_ratio(3, 4) {
+                    let account = &self.accounts[rng.gen_range(0..self.accounts.len())];
+                    Value::String(encode_ss58(account))
+                } else {
+                    hex(&rng.gen::<[u8; 32]>())
+                }
+            }
+            TypeDef::Array { len, ty } if self.is_byte(*ty) => {
+                let bytes = (0..*len).map(|_| rng.gen()).collect::<Vec<u8>>();
+                hex(&bytes)
+            }
+            TypeDef::Array { len, ty } => Value::Array(
+                (0..*len)
+                    .map(|_| self.value_at(*ty, rng, depth + 1))
+                    .collect(),
+            ),
+            TypeDef::Tuple(ids) if ids.is_empty() => Value::Null,
+            TypeDef::Tuple(ids) => Value::Array(
+                ids.iter()
+                    .map(|id| self.value_at(*id, rng, depth + 1))
+                    .collect(),
+            ),
+            TypeDef::Composite(fields) => self.fields_value(fields, rng, depth),
+            TypeDef::Variant(variants) => {
+                let candidates = if depth < MAX_DEPTH {
+                    variants.iter().collect::<Vec<_>>()
+                } else {
+                    variants
+                        .iter()
+                        .filter(|variant| variant.fields.is_empty())
+                        .collect()
+                };
+                let variant = match candidates.len() {
+                    0 if variants.is_empty() => return Value::Null,
+                    0 => &variants[0],
+                    len => candidates[rng.gen_range(0..len)],
+                };
+                let fields = self.fields_value(&variant.fields, rng, depth);
+                variant_value(&variant.name, fields)
+            }
+            // not supported by the encoding of the arguments
+            TypeDef::BitSequence => Value::Null,
+        }
+    }
+
+    /// Returns a random value of the fields of a struct or variant, in the layout of
+    /// [`crate::abi::TypeRegistry::example`].
+    fn fields_value(&self, fields: &[Field], rng: &mut StdRng, depth: usize) -> Value {
+        match fields {
+            [] => Value::Null,
+            [field] if field.name.is_none() => self.value_at(field.ty, rng, depth + 1),
+            fields if fields.iter().all(|field| field.name.is_some()) => Value::Object(
+                fields
+                    .iter()
+                    .map(|field| {
+                        (
+                            field.name.clone().unwrap_or_default(),
+                            self.value_at(field.ty, rng, depth + 1),
+                        )
+                    })
+                    .collect(),
+            ),
+            fields => Value::Array(
+                fields
+                    .iter()
+                    .map(|field| self.value_at(field.ty, rng, depth + 1))
+                    .collect(),
+            ),
+        }
+    }
+
+    /// Returns simpler values of the type `id` than `value`, the simplest first.
+    ///
+    /// Integers shrink towards zero, strings, bytes and vectors towards empty ones, enums
+    /// towards their earlier variants without fields, and accounts towards the first account.
+    pub fn shrink(&self, id: u32, value: &Value) -> Vec<Value> {
+        let ty = match self.types.resolve(id) {
+            Ok(ty) => ty,
+            Err(_) => return Vec::new(),
+        };
+        match (&ty.def, value) {
+            (TypeDef::Primitive(Primitive::Bool), Value::Bool(true)) => vec![Value::Bool(false)],
+            (TypeDef::Primitive(Primitive::Char), Value::String(char)) if char != "a" => {
+                vec![Value::String("a".to_string())]
+            }
+            (TypeDef::Primitive(Primitive::Str), Value::String(string)) => {
+                let chars = string.chars().collect::<Vec<_>>();
+                shrink_len(chars.len())
+                    .into_iter()
+                    .map(|len| Value::String(chars[..len].iter().collect()))
+                    .collect()
+            }
+            (TypeDef::Primitive(Primitive::U256), Value::String(bytes))
+            | (TypeDef::Primitive(Primitive::I256), Value::String(bytes))
+                if bytes != "0x" =>
+            {
+                vec![Value::String("0x".to_string())]
+            }
+            (TypeDef::Primitive(_), value) | (TypeDef::Compact(_), value) => shrink_int(value),
+            (TypeDef::Sequence(ty), Value::String(_)) if self.is_byte(*ty) => {
+                let bytes = bytes(value);
+                shrink_len(bytes.len())
+                    .into_iter()
+                    .map(|len| hex(&bytes[..len]))
+                    .collect()
+            }
+            (TypeDef::Sequence(ty), Value::Array(values)) => {
+                let mut shrunk = shrink_len(values.len())
+                    .into_iter()
+                    .map(|len| Value::Array(values[..len].to_vec()))
+                    .collect::<Vec<_>>();
+                if values.len() > 1 {
+                    shrunk.extend((0..values.len()).map(|index| {
+                        let mut values = values.clone();
+                        values.remove(index);
+                        Value::Array(values)
+                    }));
+                }
+                let ids = vec![*ty; values.len()];
+                shrunk.extend(self.shrink_each(&ids, values).map(Value::Array));
+                shrunk
+            }
+            (TypeDef::Array { len: 32, ty }, Value::String(account)) if self.is_byte(*ty) => {
+                let first = match self.accounts.first() {
+                    Some(first) => first,
+                    None => return Vec::new(),
+                };
+                match decode_ss58(account) {
+                    Some(account) if &account == first => Vec::new(),
+                    _ => vec![Value::String(encode_ss58(first))],
+                }
+            }
+            (TypeDef::Array { len, ty }, Value::String(_)) if self.is_byte(*ty) => {
+                let zero = vec![0; *len as usize];
+                if bytes(value) == zero {
+                    Vec::new()
+                } else {
+                    vec![hex(&zero)]
+                }
+            }
+            (TypeDef::Array { ty, .. }, Value::Array(values)) => {
+                let ids = vec![*ty; values.len()];
+                self.shrink_each(&ids, values).map(Value::Array).collect()
+            }
+            (TypeDef::Tuple(ids), Value::Array(values)) => {
+                self.shrink_each(ids, values).map(Value::Array).collect()
+            }
+            (TypeDef::Composite(fields), value) => self.shrink_fields(fields, value),
+            (TypeDef::Variant(variants), value) => {
+                let (name, fields) = match value {
+                    Value::String(name) => (name.as_str(), &Value::Null),
+                    Value::Object(object) if object.len() == 1 => {
+                        let (name, fields) = object.iter().next().expect("one variant");
+                        (name.as_str(), fields)
+                    }
+                    _ => return Vec::new(),
+                };
+                let position = match variants.iter().position(|variant| variant.name == name) {
+                    Some(position) => position,
+                    None => return Vec::new(),
+                };
+                let mut shrunk = variants[..position]
+                    .iter()
+                    .filter(|variant| variant.fields.is_empty())
+                    .map(|variant| variant_value(&variant.name, Value::Null))
+                    .collect::<Vec<_>>();
+                shrunk.extend(
+                    self.shrink_fields(&variants[position].fields, fields)
+                        .into_iter()
+                        .map(|fields| variant_value(name, fields)),
+                );
+                shrunk
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns simpler values of the fields of a struct or variant.
+    fn shrink_fields(&self, fields: &[Field], value: &Value) -> Vec<Value> {
+        let ids = fields.iter().map(|field| field.ty).collect::<Vec<_>>();
+        match (fields, value) {
+            ([field], value) if field.name.is_none() => self.shrink(field.ty, value),
+            (fields, Value::Object(object)) => {
+                let names = fields
+                    .iter()
+                    .map(|field| field.name.clone().unwrap_or_default())
+                    .collect::<Vec<_>>();
+                let values = names
+                    .iter()
+                    .map(|name| object.get(name).cloned().unwrap_or(Value::Null))
+                    .collect::<Vec<_>>();
+                self.shrink_each(&ids, &values)
+                    .map(|values| {
+                        Value::Object(names.iter().cloned().zip(values).collect::<Map<_, _>>())
+                    })
+                    .collect()
+            }
+            (_, Value::Array(values)) if values.len() == ids.len() => {
+                self.shrink_each(&ids, values).map(Value::Array).collect()
+            }
+            _ => Vec::new(),
+        }
+    }
+
+    /// Returns the `values` of the types `ids` with one of them shrunk, for each way to shrink
+    /// each of them.
+    fn shrink_each<'b>(
+        &'b self,
+        ids: &'b [u32],
+        values: &'b [Value],
+    ) -> impl Iterator<Item = Vec<Value>> + 'b {
+        ids.iter()
+            .zip(values)
+            .enumerate()
+            .flat_map(move |(index, (id, value))| {
+                self.shrink(*id, value).into_iter().map(move |shrunk| {
+                    let mut values = values.to_vec();
+                    values[index] = shrunk;
+                    values
+                })
+            })
+    }
+
+    fn is_byte(&self, id: u32) -> bool {
+        matches!(
+            self.types.resolve(id).map(|ty| &ty.def),
+            Ok(TypeDef::Primitive(Primitive::U8))
+        )
+    }
+}
+
+/// Returns a random value of `primitive`.
+fn primitive_value(primitive: Primitive, rng: &mut StdRng) -> Value {
+    match primitive {
+        Primitive::Bool => Value::Bool(rng.gen()),
+        Primitive::Char => {
+            Value::String((CHARS[rng.gen_range(0..CHARS.len())] as char).to_string())
+        }
+        Primitive::Str => {
+            let len = rng.gen_range(0..=MAX_LEN);
+            Value::String(
+                (0..len)
+                    .map(|_| CHARS[rng.gen_range(0..CHARS.len())] as char)
+                    .collect(),
+            )
+        }
+        Primitive::U8 => unsigned(u8::MAX.into(), rng),
+        Primitive::U16 => unsigned(u16::MAX.into(), rng),
+        Primitive::U32 => unsigned(u32::MAX.into(), rng),
+        Primitive::U64 => unsigned(u64::MAX.into(), rng),
+        Primitive::U128 => unsigned(u128::MAX, rng),
+        Primitive::I8 => signed(i8::MIN.into(), i8::MAX.into(), rng),
+        Primitive::I16 => signed(i16::MIN.into(), i16::MAX.into(), rng),
+        Primitive::I32 => signed(i32::MIN.into(), i32::MAX.into(), rng),
+        Primitive::I64 => signed(i64::MIN.into(), i64::MAX.into(), rng),
+        Primitive::I128 => signed(i128::MIN, i128::MAX, rng),
+        Primitive::U256 | Primitive::I256 => {
+            let len = rng.gen_range(0..=32);
+            let bytes = (0..len).map(|_| rng.gen()).collect::<Vec<u8>>();
+            hex(&bytes)
+        }
+    }
+}
+
+/// Returns a random integer up to `max`, often zero, one or `max`.
+fn unsigned(max: u128, rng: &mut StdRng) -> Value {
+    let int = match rng.gen_range(0..8) {
+        0 => 0,
+        1 => 1,
+        2 => max,
+        3 => max - 1,
+        4 | 5 => rng.gen_range(0..=max.min(100)),
+        _ => rng.gen_range(0..=max),
+    };
+    int_value(false, int)
+}
+
+/// Returns a random integer between `min` and `max`, often zero, one, minus one or a bound.
+fn signed(min: i128, max: i128, rng: &mut StdRng) -> Value {
+    let int = match rng.gen_range(0..8) {
+        0 => 0,
+        1 => 1,
+        2 => -1,
+        3 => min,
+        4 => max,
+        5 => rng.gen_range(-100..=100),
+        _ => rng.gen_range(min..=max),
+    };
+    int_value(int < 0, int.unsigned_abs())
+}
+
+/// Returns the integer with the sign `negative` and the absolute value `int` as a JSON number,
+/// or as a string if no number holds it.
+fn int_value(negative: bool, int: u128) -> Value {
+    if negative {
+        let int = (int as i128).wrapping_neg();
+        match i64::try_from(int) {
+            Ok(int) => Value::from(int),
+            Err(_) => Value::String(int.to_string()),
+        }
+    } else {
+        match u64::try_from(int) {
+            Ok(int) => Value::from(int),
+            Err(_) => Value::String(int.to_string()),
+        }
+    }
+}
+
+/// Returns integers closer to zero than `value`: zero, half of it and the next one.
+fn shrink_int(value: &Value) -> Vec<Value> {
+    let int = match value {
+        Value::Number(number) => number.to_string(),
+        Value::String(int) => int.clone(),
+        _ => return Vec::new(),
+    };
+    let (negative, int) = match int.strip_prefix('-') {
+        Some(int) => (true, int.parse::<u128>()),
+        None => (false, int.parse::<u128>()),
+    };
+    let int = match int {
+        Ok(int) if int > 0 => int,
+        _ => return Vec::new(),
+    };
+    let mut shrunk = vec![0, int / 2, int - 1];
+    shrunk.dedup();
+    let mut shrunk = shrunk
+        .into_iter()
+        .filter(|shrunk| *shrunk < int)
+        .map(|shrunk| int_value(negative && shrunk > 0, shrunk))
+        .collect::<Vec<_>>();
+    if negative {
+        shrunk.push(int_value(false, int));
+    }
+    shrunk
+}
+
+/// Returns shorter lengths than `len`: empty, half of it and one less.
+fn shrink_len(len: usize) -> Vec<usize> {
+    let mut lens = vec![0, len / 2, len.saturating_sub(1)];
+    lens.dedup();
+    lens.retain(|shrunk| *shrunk < len);
+    lens
+}
+
+fn variant_value(name: &str, fields: Value) -> Value {
+    match fields {
+        Value::Null => Value::String(name.to_string()),
+        fields => {
+            let mut object = Map::new();
+            object.insert(name.to_string(), fields);
+            Value::Object(object)
+        }
+    }
+}
+
+fn hex(bytes: &[u8]) -> Value {
+    Value::String(crate::abi::encode_hex(bytes))
+}
+
+fn bytes(value: &Value) -> Vec<u8> {
+    value
+        .as_str()
+        .and_then(|hex| crate::abi::decode_hex(hex).ok())
+        .unwrap_or_default()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::{tests::metadata_v1, ContractAbi};
+    use rand::SeedableRng;
+
+    #[test]
+    fn generates_values_of_the_argument_types() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let generator = Generator::new(&abi.types, vec![[1; 32]]);
+        let mut rng = StdRng::seed_from_u64(0);
+        for spec in abi.constructors.iter().chain(&abi.messages) {
+            for _ in 0..100 {
+                let args = spec
+                    .args
+                    .iter()
+                    .map(|arg| generator.value(arg.ty.id, &mut rng))
+                    .collect::<Vec<_>>();
+                abi.encode_call(spec, &args).unwrap();
+                for (arg, value) in spec.args.iter().zip(&args) {
+                    for shrunk in generator.shrink(arg.ty.id, value) {
+                        abi.types.encode(arg.ty.id, &shrunk).unwrap();
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shrinks_towards_simpler_values() {
+        assert_eq!(
+            shrink_int(&Value::from(10)),
+            vec![Value::from(0), Value::from(5), Value::from(9)]
+        );
+        assert_eq!(
+            shrink_int(&Value::from(-3)),
+            vec![
+                Value::from(0),
+                Value::from(-1),
+                Value::from(-2),
+                Value::from(3)
+            ]
+        );
+        assert!(shrink_int(&Value::from(0)).is_empty());
+        assert_eq!(
+            shrink_int(&Value::String(u128::MAX.to_string()))[1],
+            Value::String((u128::MAX / 2).to_string())
+        );
+        assert_eq!(shrink_len(1), vec![0]);
+        assert_eq!(shrink_len(8), vec![0, 4, 7]);
+    }
+}
@@ -0,0 +1,159 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Invariants of contracts, checked after each step of a fuzzing run.
+//!
+//! Invariants are messages of the contract returning `bool`, e.g.
+//!
+//! ```ignore
+//! #[cfg(feature = "fuzz")]
+//! #[ink(message)]
+//! pub fn invariant_supply(&self) -> bool {
+//!     self.total_supply == self.holders.iter().map(|holder| self.balance_of(*holder)).sum()
+//! }
+//! ```
+//!
+//! Messages whose label starts with `invariant_` are invariants, as are the ones given with
+//! `--invariant`. An invariant taking an account is checked for each development account and
+//! for the contract itself. Gated behind the `fuzz` feature of the contract, the invariants are
+//! only part of the build `cargo contract fuzz` makes, not of the ABI of the deployed contract.
+
+use anyhow::Result;
+use contract_sandbox::{AccountId, Sandbox, ALICE};
+use serde_json::Value;
+
+use crate::abi::{encode_ss58, ContractAbi, DecodedValue, MessageSpec, ReturnValue};
+
+/// The prefix of the labels of the messages which are invariants.
+const PREFIX: &str = "invariant_";
+
+/// A message of the contract which must return `true` after each step.
+pub struct Invariant<'a> {
+    pub spec: &'a MessageSpec,
+    /// `true` if the invariant takes an account.
+    per_account: bool,
+}
+
+impl<'a> Invariant<'a> {
+    /// Returns the messages of the contract labeled `invariant_..` and the ones labeled
+    /// `labels`.
+    pub fn collect(abi: &'a ContractAbi, labels: &[String]) -> Result<Vec<Self>> {
+        let mut specs = abi
+            .messages
+            .iter()
+            .filter(|spec| {
+                let name = spec.label.rsplit("::").next().unwrap_or_default();
+                name.starts_with(PREFIX)
+            })
+            .collect::<Vec<_>>();
+        for label in labels {
+            let spec = abi.message(label)?;
+            if specs.iter().all(|known| known.label != spec.label) {
+                specs.push(spec);
+            }
+        }
+        specs
+            .into_iter()
+            .map(|spec| {
+                let per_account = match &spec.args[..] {
+                    [] => false,
+                    [arg] if abi.types.encoding(arg.ty.id) == "[u8; 32]" => true,
+                    _ => anyhow::bail!(
+                        "The invariant `{}` must take no arguments or an account",
+                        spec.label
+                    ),
+                };
+                Ok(Self { spec, per_account })
+            })
+            .collect()
+    }
+
+    /// Calls the invariant on a copy of the sandbox, returning how it was broken, if it was.
+    pub fn check(
+        &self,
+        abi: &ContractAbi,
+        sandbox: &Sandbox,
+        contract: AccountId,
+        accounts: &[AccountId],
+        gas_limit: u64,
+    ) -> Result<Option<String>> {
+        let accounts = if self.per_account {
+            accounts
+                .iter()
+                .copied()
+                .chain(Some(contract))
+                .map(Some)
+                .collect()
+        } else {
+            vec![None]
+        };
+        for account in accounts {
+            let args = account
+                .iter()
+                .map(|account| Value::String(encode_ss58(account)))
+                .collect::<Vec<_>>();
+            let data = abi.encode_call(self.spec, &args)?;
+            let result = sandbox.clone().call(ALICE, contract, 0, data, gas_limit);
+            let broken = match result.result {
+                Ok(value) if value.did_revert() => Some("reverted".to_string()),
+                Ok(value) => match self
+                    .spec
+                    .return_type
+                    .as_ref()
+                    .map(|return_type| abi.types.decode_return_value(return_type.id, &value.data))
+                {
+                    Some(Ok(ReturnValue::Value(DecodedValue::Bool(true)))) => None,
+                    Some(Ok(ReturnValue::Value(DecodedValue::Bool(false)))) => {
+                        Some("returned false".to_string())
+                    }
+                    _ => anyhow::bail!("The invariant `{}` must return a bool", self.spec.label),
+                },
+                Err(err) => Some(err.to_string()),
+            };
+            if let Some(broken) = broken {
+                return Ok(Some(match account {
+                    Some(account) => format!("{} for {}", broken, encode_ss58(&account)),
+                    None => broken,
+                }));
+            }
+        }
+        Ok(None)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn collects_invariants_taking_nothing_or_an_account() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+
+        let invariants = Invariant::collect(&abi, &["balance_of".to_string()]).unwrap();
+        assert_eq!(invariants.len(), 1);
+        assert_eq!(invariants[0].spec.label, "Ledger::balance_of");
+        assert!(invariants[0].per_account);
+
+        let err = Invariant::collect(&abi, &["withdraw".to_string()])
+            .err()
+            .unwrap();
+        assert_eq!(
+            err.to_string(),
+            "The invariant `withdraw` must take no arguments or an account"
+        );
+    }
+}
@@ -0,0 +1,1033 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Fuzzes contracts with `cargo contract fuzz`.
+//!
+//! Each run instantiates the contract in a fresh sandbox and calls random messages with random
+//! arguments of the types of the metadata, from random development accounts. A run fails if the
+//! contract traps, runs out of gas or breaks the metadata, e.g. by returning a value of another
+//! type, or if it breaks one of its invariants. Failing runs are minimized, dropping calls and
+//! shrinking arguments as long as the failure reproduces, and written as scripts which
+//! `--replay` executes again.
+
+mod generate;
+mod invariant;
+
+use std::{
+    convert::TryFrom,
+    fmt, fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use contract_sandbox::{
+    AccountId, Balance, ContractResult, ExecReturnValue, Hash, Sandbox, ALICE, BOB, CHARLIE, DAVE,
+    EVE,
+};
+use rand::{rngs::StdRng, Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use structopt::StructOpt;
+
+use self::{generate::Generator, invariant::Invariant};
+use super::{
+    build::{self, ExecuteArgs},
+    metadata::built_metadata_path,
+    sandbox::{contract_wasm, render_return_value, ENDOWMENT},
+};
+use crate::{
+    abi::{ContractAbi, MessageSpec, ReturnValue},
+    crate_metadata::CrateMetadata,
+    workspace::{Manifest, ManifestPath},
+};
+
+/// The development accounts calling the contract, by their names in the scripts.
+const ACCOUNTS: [(&str, AccountId); 5] = [
+    ("alice", ALICE),
+    ("bob", BOB),
+    ("charlie", CHARLIE),
+    ("dave", DAVE),
+    ("eve", EVE),
+];
+
+/// The cargo feature of the contract enabling its invariants.
+const FUZZ_FEATURE: &str = "fuzz";
+
+/// The maximum value transferred to payable messages and constructors.
+const MAX_VALUE: Balance = 1_000_000_000_000;
+
+/// The maximum number of executions minimizing a failure.
+const MAX_SHRINK_RUNS: usize = 2_000;
+
+/// Executes random calls of the messages of a contract in the sandbox, reporting the calls
+/// which trap or break the metadata with minimized scripts reproducing them.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "fuzz")]
+pub struct FuzzCommand {
+    /// Path to the Cargo.toml of the contract
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The `.contract` bundle or metadata file to read instead of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The Wasm code of the contract, if `--metadata` is no `.contract` bundle. Defaults to
+    /// the code of the built contract
+    #[structopt(long, parse(from_os_str))]
+    wasm: Option<PathBuf>,
+    /// The label of the constructor instantiating the contract. Defaults to a random
+    /// constructor for each run
+    #[structopt(long)]
+    constructor: Option<String>,
+    /// The number of runs, each instantiating the contract and calling it
+    #[structopt(long, default_value = "256")]
+    runs: usize,
+    /// The maximum number of calls of a run
+    #[structopt(long, default_value = "16")]
+    length: usize,
+    /// A message returning `bool` which must return `true` after each step, besides the
+    /// messages labeled `invariant_..`
+    #[structopt(long)]
+    invariant: Vec<String>,
+    /// The seed of the random calls, to repeat the runs of an earlier campaign. Defaults to a
+    /// random seed
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// The gas limit of each call, in executed Wasm instructions
+    #[structopt(long, default_value = "1000000000")]
+    gas_limit: u64,
+    /// The directory the scripts reproducing failures are written to, `target/ink/fuzz` of
+    /// the contract by default
+    #[structopt(long, parse(from_os_str))]
+    output_dir: Option<PathBuf>,
+    /// Execute a script reproducing a failure instead of random calls, failing if the failure
+    /// still occurs
+    #[structopt(long, parse(from_os_str))]
+    replay: Option<PathBuf>,
+}
+
+impl FuzzCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let metadata = match &self.metadata {
+            Some(path) => Some(path.clone()),
+            None if self.wasm.is_none() => self.build_fuzz_feature()?,
+            None => None,
+        };
+        let metadata_path = match &metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&metadata_path)?;
+        let wasm = contract_wasm(
+            self.manifest_path.as_ref(),
+            metadata.as_deref(),
+            self.wasm.as_deref(),
+        )?;
+        let fuzzer = Fuzzer::new(&abi, &wasm, self.gas_limit, &self.invariant)?;
+        if let Some(path) = &self.replay {
+            return replay(&fuzzer, path);
+        }
+        let constructor = match &self.constructor {
+            Some(label) => Some(abi.constructor(label)?),
+            None => None,
+        };
+
+        let seed = self.seed.unwrap_or_else(rand::random);
+        println!(
+            "{} {} runs of up to {} calls with --seed {}",
+            "Fuzzing".green().bold(),
+            self.runs,
+            self.length,
+            seed
+        );
+        for invariant in &fuzzer.invariants {
+            println!("Checking the invariant `{}`", invariant.spec.label);
+        }
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut stats = Stats::default();
+        let mut failures = Vec::<(Script, Failure)>::new();
+        for _ in 0..self.runs {
+            let script = fuzzer.generate(&mut rng, constructor, self.length);
+            let execution = fuzzer.execute(&script, false)?;
+            stats.add(&execution);
+            let failure = match execution.failure {
+                Some(failure) => failure,
+                None => continue,
+            };
+            if failures.iter().any(|(_, known)| known.is_like(&failure)) {
+                continue;
+            }
+            let (script, failure) = fuzzer.minimize(script, failure)?;
+            failures.push((script, failure));
+        }
+        println!(
+            "Executed {} calls, {} of which reverted",
+            stats.calls, stats.reverted
+        );
+        if failures.is_empty() {
+            println!("{}", "No failures found".green().bold());
+            return Ok(None);
+        }
+
+        let output_dir = self.output_dir()?;
+        fs::create_dir_all(&output_dir)
+            .with_context(|| format!("Failed to create {}", output_dir.display()))?;
+        for (script, failure) in &failures {
+            let path = output_dir.join(failure.file_name());
+            script.write(&path, failure)?;
+            println!("\n{} {}", "Failure:".bright_red().bold(), failure);
+            for (index, step) in script.steps().enumerate() {
+                let kind = if index == 0 { "instantiate" } else { "call" };
+                println!("  {} {}", kind, step);
+            }
+            println!(
+                "Reproduce it with `cargo contract fuzz --replay {}`",
+                path.display()
+            );
+        }
+        anyhow::bail!("Found {} failures", failures.len())
+    }
+
+    /// Builds the contract with its `fuzz` feature into `target/ink/fuzz/build`, returning the
+    /// `.contract` bundle, or `None` if the contract has no such feature.
+    fn build_fuzz_feature(&self) -> Result<Option<PathBuf>> {
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        if !Manifest::new(manifest_path.clone())?.has_feature(FUZZ_FEATURE) {
+            return Ok(None);
+        }
+        let target_directory = CrateMetadata::collect(&manifest_path)?
+            .target_directory
+            .join("fuzz")
+            .join("build");
+        let mut args = ExecuteArgs::default();
+        args.manifest_path = manifest_path;
+        args.features = vec![FUZZ_FEATURE.to_string()];
+        args.target_directory = Some(target_directory);
+        let metadata_result = build::execute(args)?
+            .metadata_result
+            .expect("the metadata is built by default");
+        Ok(Some(metadata_result.dest_bundle))
+    }
+
+    fn output_dir(&self) -> Result<PathBuf> {
+        if let Some(dir) = &self.output_dir {
+            return Ok(dir.clone());
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())
+            .context("Pass --output-dir to write the scripts outside of a contract")?;
+        Ok(CrateMetadata::collect(&manifest_path)?
+            .target_directory
+            .join("fuzz"))
+    }
+}
+
+/// Executes the script at `path`, printing the outcome of each step.
+fn replay(fuzzer: &Fuzzer, path: &Path) -> Result<Option<String>> {
+    let script = Script::read(path)?;
+    match fuzzer.execute(&script, true)?.failure {
+        Some(failure) => anyhow::bail!("{}", failure),
+        None => {
+            println!("{}", "The failure no longer occurs".green().bold());
+            Ok(None)
+        }
+    }
+}
+
+/// A constructor or message call of a script.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Step {
+    /// The label of the constructor or message.
+    label: String,
+    args: Vec<Value>,
+    /// The name of the development account executing the call, e.g. `alice`.
+    origin: String,
+    /// The value transferred to payable constructors and messages.
+    #[serde(default, skip_serializing_if = "is_zero")]
+    value: Balance,
+}
+
+fn is_zero(value: &Balance) -> bool {
+    *value == 0
+}
+
+impl Step {
+    fn origin(&self) -> Result<AccountId> {
+        ACCOUNTS
+            .iter()
+            .find(|(name, _)| *name == self.origin)
+            .map(|(_, account)| *account)
+            .ok_or_else(|| {
+                let names = ACCOUNTS.iter().map(|(name, _)| *name).collect::<Vec<_>>();
+                anyhow::anyhow!(
+                    "Unknown origin `{}`, expected one of {}",
+                    self.origin,
+                    names.join(", ")
+                )
+            })
+    }
+}
+
+/// Displays the step as call, e.g. `transfer("5FHne..", 100) by alice`.
+impl fmt::Display for Step {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let args = self.args.iter().map(Value::to_string).collect::<Vec<_>>();
+        write!(f, "{}({}) by {}", self.label, args.join(", "), self.origin)?;
+        if self.value > 0 {
+            write!(f, " with value {}", self.value)?;
+        }
+        Ok(())
+    }
+}
+
+/// The instantiation of the contract and the calls following it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Script {
+    constructor: Step,
+    calls: Vec<Step>,
+}
+
+impl Script {
+    fn read(path: &Path) -> Result<Self> {
+        let json = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&json).with_context(|| format!("Invalid script {}", path.display()))
+    }
+
+    /// Writes the script to `path`, noting the failure it reproduces.
+    fn write(&self, path: &Path, failure: &Failure) -> Result<()> {
+        #[derive(Serialize)]
+        struct Reproduction<'a> {
+            failure: String,
+            constructor: &'a Step,
+            calls: &'a [Step],
+        }
+        let reproduction = Reproduction {
+            failure: failure.to_string(),
+            constructor: &self.constructor,
+            calls: &self.calls,
+        };
+        fs::write(path, serde_json::to_string_pretty(&reproduction)?)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Returns the constructor followed by the calls.
+    fn steps(&self) -> impl Iterator<Item = &Step> {
+        std::iter::once(&self.constructor).chain(&self.calls)
+    }
+
+    fn step_mut(&mut self, index: usize) -> &mut Step {
+        match index {
+            0 => &mut self.constructor,
+            index => &mut self.calls[index - 1],
+        }
+    }
+}
+
+/// The ways in which a contract fails.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Problem {
+    /// The contract trapped, e.g. because it panicked.
+    Trapped(String),
+    OutOfGas,
+    /// A message taking `&self` changed the storage of the contract.
+    StorageChanged,
+    /// The contract emitted an event which does not decode with the metadata.
+    InvalidEvent(String),
+    /// The message returned a value which does not decode with its return type.
+    InvalidReturnValue(String),
+    /// The contract could not dispatch a well-typed call, returning a `LangError`.
+    NotDispatched(String),
+    /// A message returning `bool` returned `false`, reverted or trapped after the step.
+    Invariant {
+        invariant: String,
+        broken: String,
+    },
+}
+
+/// A failing step of a script.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Failure {
+    /// The index of the step, `0` for the constructor.
+    step: usize,
+    label: String,
+    problem: Problem,
+}
+
+impl Failure {
+    /// Returns `true` if both are the same kind of failure of the same constructor or message,
+    /// e.g. traps of `transfer` regardless of the panic message.
+    fn is_like(&self, other: &Failure) -> bool {
+        let invariant = |problem: &Problem| match problem {
+            Problem::Invariant { invariant, .. } => Some(invariant.clone()),
+            _ => None,
+        };
+        self.label == other.label
+            && std::mem::discriminant(&self.problem) == std::mem::discriminant(&other.problem)
+            && invariant(&self.problem) == invariant(&other.problem)
+    }
+
+    fn file_name(&self) -> String {
+        let kind = match &self.problem {
+            Problem::Trapped(_) => "trapped",
+            Problem::OutOfGas => "out-of-gas",
+            Problem::StorageChanged => "storage-changed",
+            Problem::InvalidEvent(_) => "invalid-event",
+            Problem::InvalidReturnValue(_) => "invalid-return-value",
+            Problem::NotDispatched(_) => "not-dispatched",
+            Problem::Invariant { invariant, .. } => invariant.as_str(),
+        };
+        format!("{}-{}.json", self.label, kind).replace("::", "-")
+    }
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "`{}` ", self.label)?;
+        match &self.problem {
+            Problem::Trapped(reason) => write!(f, "trapped: {}", reason),
+            Problem::OutOfGas => write!(f, "ran out of gas"),
+            Problem::StorageChanged => {
+                write!(f, "changed the storage of the contract, but takes `&self`")
+            }
+            Problem::InvalidEvent(err) => {
+                write!(f, "emitted an event not matching the metadata: {}", err)
+            }
+            Problem::InvalidReturnValue(err) => {
+                write!(f, "returned a value not matching its return type: {}", err)
+            }
+            Problem::NotDispatched(err) => {
+                write!(f, "was not dispatched by the contract: {}", err)
+            }
+            Problem::Invariant { invariant, broken } => {
+                write!(f, "broke the invariant `{}`, which {}", invariant, broken)
+            }
+        }
+    }
+}
+
+/// The outcome of the execution of a script.
+#[derive(Debug, Default)]
+struct Execution {
+    calls: usize,
+    reverted: usize,
+    failure: Option<Failure>,
+}
+
+#[derive(Debug, Default)]
+struct Stats {
+    calls: usize,
+    reverted: usize,
+}
+
+impl Stats {
+    fn add(&mut self, execution: &Execution) {
+        self.calls += execution.calls;
+        self.reverted += execution.reverted;
+    }
+}
+
+/// Generates, executes and minimizes scripts.
+struct Fuzzer<'a> {
+    abi: &'a ContractAbi,
+    /// The messages called by the scripts, all but the invariants.
+    messages: Vec<&'a MessageSpec>,
+    invariants: Vec<Invariant<'a>>,
+    accounts: Vec<AccountId>,
+    generator: Generator<'a>,
+    /// The sandbox the scripts start from, with the code uploaded and the accounts endowed.
+    sandbox: Sandbox,
+    code_hash: Hash,
+    gas_limit: u64,
+}
+
+impl<'a> Fuzzer<'a> {
+    fn new(
+        abi: &'a ContractAbi,
+        wasm: &[u8],
+        gas_limit: u64,
+        invariants: &[String],
+    ) -> Result<Self> {
+        if abi.constructors.is_empty() {
+            anyhow::bail!("The contract has no constructors")
+        }
+        let mut sandbox = Sandbox::new();
+        for (_, account) in &ACCOUNTS {
+            sandbox.set_balance(*account, ENDOWMENT);
+        }
+        let code_hash = sandbox
+            .upload_code(wasm)
+            .map_err(|err| anyhow::anyhow!("{}", err))?;
+        let invariants = Invariant::collect(abi, invariants)?;
+        let messages = abi
+            .messages
+            .iter()
+            .filter(|spec| {
+                invariants
+                    .iter()
+                    .all(|invariant| invariant.spec.label != spec.label)
+            })
+            .collect();
+        let accounts = ACCOUNTS
+            .iter()
+            .map(|(_, account)| *account)
+            .collect::<Vec<_>>();
+        Ok(Self {
+            abi,
+            messages,
+            invariants,
+            generator: Generator::new(&abi.types, accounts.clone()),
+            accounts,
+            sandbox,
+            code_hash,
+            gas_limit,
+        })
+    }
+
+    /// Returns a script instantiating the contract with `constructor`, or a random one, and
+    /// calling up to `length` random messages.
+    fn generate(
+        &self,
+        rng: &mut StdRng,
+        constructor: Option<&MessageSpec>,
+        length: usize,
+    ) -> Script {
+        let constructors = &self.abi.constructors;
+        let constructor =
+            constructor.unwrap_or_else(|| &constructors[rng.gen_range(0..constructors.len())]);
+        let constructor = self.generate_step(rng, constructor);
+        let messages = &self.messages;
+        let calls = match messages.len() {
+            0 => Vec::new(),
+            len => (0..rng.gen_range(1..=length.max(1)))
+                .map(|_| {
+                    let message = &messages[rng.gen_range(0..len)];
+                    self.generate_step(rng, message)
+                })
+                .collect(),
+        };
+        Script { constructor, calls }
+    }
+
+    fn generate_step(&self, rng: &mut StdRng, spec: &MessageSpec) -> Step {
+        let args = spec
+            .args
+            .iter()
+            .map(|arg| self.generator.value(arg.ty.id, rng))
+            .collect();
+        let value = if spec.payable && rng.gen() {
+            rng.gen_range(1..=MAX_VALUE)
+        } else {
+            0
+        };
+        Step {
+            label: spec.label.clone(),
+            args,
+            origin: ACCOUNTS[rng.gen_range(0..ACCOUNTS.len())].0.to_string(),
+            value,
+        }
+    }
+
+    /// Executes the script in a fresh sandbox, up to the first failing step.
+    ///
+    /// The execution ends early if the constructor reverts or the contract is gone, e.g.
+    /// because it terminated. With `verbose` the outcome of each step is printed.
+    fn execute(&self, script: &Script, verbose: bool) -> Result<Execution> {
+        let mut sandbox = self.sandbox.clone();
+        let mut execution = Execution::default();
+
+        let step = &script.constructor;
+        let spec = self.abi.constructor(&step.label)?;
+        let data = self.abi.encode_call(spec, &step.args)?;
+        if verbose {
+            println!("{} {}", "Instantiate".bold(), step);
+        }
+        let result = sandbox.instantiate(
+            step.origin()?,
+            self.code_hash,
+            step.value,
+            data,
+            Vec::new(),
+            self.gas_limit,
+        );
+        let returned = result.result.as_ref().map(|value| &value.result);
+        execution.failure = self.check(0, spec, &result, returned, None, verbose);
+        let contract = match &result.result {
+            Ok(value) if execution.failure.is_none() && !value.result.did_revert() => {
+                value.account_id
+            }
+            _ => return Ok(execution),
+        };
+        execution.failure = self.check_invariants(0, spec, &sandbox, contract)?;
+        if execution.failure.is_some() {
+            return Ok(execution);
+        }
+
+        for (index, step) in script.calls.iter().enumerate() {
+            let spec = self.abi.message(&step.label)?;
+            let data = self.abi.encode_call(spec, &step.args)?;
+            if verbose {
+                println!("{} {}", "Call".bold(), step);
+            }
+            sandbox.advance_block();
+            let before = storage(&sandbox, &contract);
+            let result = sandbox.call(step.origin()?, contract, step.value, data, self.gas_limit);
+            execution.calls += 1;
+            let returned = result.result.as_ref();
+            if matches!(returned, Ok(value) if value.did_revert()) {
+                execution.reverted += 1;
+            }
+            let storage = (!spec.mutates).then(|| (before, storage(&sandbox, &contract)));
+            execution.failure = self.check(index + 1, spec, &result, returned, storage, verbose);
+            if execution.failure.is_some() || sandbox.code_hash(&contract).is_none() {
+                break;
+            }
+            if matches!(returned, Ok(value) if !value.did_revert()) {
+                execution.failure = self.check_invariants(index + 1, spec, &sandbox, contract)?;
+                if execution.failure.is_some() {
+                    break;
+                }
+            }
+        }
+        Ok(execution)
+    }
+
+    /// Returns the failure of the step `index` executing `spec`, if any.
+    ///
+    /// `storage` is the storage of the contract before and after calling a message taking
+    /// `&self`.
+    fn check<R>(
+        &self,
+        index: usize,
+        spec: &MessageSpec,
+        result: &ContractResult<R>,
+        returned: std::result::Result<&ExecReturnValue, &contract_sandbox::Error>,
+        storage: Option<(Vec<u8>, Vec<u8>)>,
+        verbose: bool,
+    ) -> Option<Failure> {
+        if verbose {
+            for message in &result.debug_messages {
+                println!("  {} {}", "Debug:".bold(), message.trim_end());
+            }
+        }
+        let failure = |problem| {
+            Some(Failure {
+                step: index,
+                label: spec.label.clone(),
+                problem,
+            })
+        };
+        let value = match returned {
+            Ok(value) => value,
+            Err(contract_sandbox::Error::Trapped(reason)) => {
+                let reason = panic_message(&result.debug_messages).unwrap_or(reason);
+                return failure(Problem::Trapped(reason.to_string()));
+            }
+            Err(contract_sandbox::Error::OutOfGas) => return failure(Problem::OutOfGas),
+            Err(err) => {
+                if verbose {
+                    println!("  {} {}", "Error:".bold(), err);
+                }
+                return None;
+            }
+        };
+        for event in &result.events {
+            match self.abi.decode_event(&event.data) {
+                Ok(event) if verbose => println!("  {} {}", "Event:".bold(), event),
+                Ok(_) => (),
+                Err(err) => return failure(Problem::InvalidEvent(err.to_string())),
+            }
+        }
+        // constructors return no value
+        let return_type = if index == 0 {
+            None
+        } else {
+            spec.return_type.as_ref()
+        };
+        if let Some(return_type) = return_type {
+            match self
+                .abi
+                .types
+                .decode_return_value(return_type.id, &value.data)
+            {
+                Ok(ReturnValue::Value(_)) => (),
+                Ok(ReturnValue::LangError(err)) => {
+                    return failure(Problem::NotDispatched(err.to_string()))
+                }
+                Err(err) => return failure(Problem::InvalidReturnValue(err.to_string())),
+            }
+        }
+        if verbose {
+            let (returned, _) = render_return_value(self.abi, return_type, value);
+            let outcome = if value.did_revert() {
+                "Reverted:".bright_red().bold()
+            } else {
+                "Returned:".bold()
+            };
+            println!("  {} {}", outcome, returned);
+        }
+        match storage {
+            Some((before, after)) if before != after && !value.did_revert() => {
+                failure(Problem::StorageChanged)
+            }
+            _ => None,
+        }
+    }
+
+    /// Returns the failure of the step `index` executing `spec` if it broke an invariant.
+    fn check_invariants(
+        &self,
+        index: usize,
+        spec: &MessageSpec,
+        sandbox: &Sandbox,
+        contract: AccountId,
+    ) -> Result<Option<Failure>> {
+        for invariant in &self.invariants {
+            let broken =
+                invariant.check(self.abi, sandbox, contract, &self.accounts, self.gas_limit)?;
+            if let Some(broken) = broken {
+                return Ok(Some(Failure {
+                    step: index,
+                    label: spec.label.clone(),
+                    problem: Problem::Invariant {
+                        invariant: invariant.spec.label.clone(),
+                        broken,
+                    },
+                }));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Minimizes the script of `failure`: drops calls and shrinks the arguments, values and
+    /// origins of the steps for as long as a failure like it occurs.
+    fn minimize(&self, mut script: Script, mut failure: Failure) -> Result<(Script, Failure)> {
+        let mut budget = MAX_SHRINK_RUNS;
+        script.calls.truncate(failure.step);
+        loop {
+            let mut shrunk = false;
+
+            let mut index = 0;
+            while index < script.calls.len() {
+                let mut candidate = script.clone();
+                candidate.calls.remove(index);
+                match self.reproduce(&candidate, &failure, &mut budget)? {
+                    Some(found) => {
+                        script = candidate;
+                        script.calls.truncate(found.step);
+                        failure = found;
+                        shrunk = true;
+                    }
+                    None => index += 1,
+                }
+            }
+
+            let mut index = 0;
+            while index <= script.calls.len() {
+                let mut found = None;
+                for step in self.simpler_steps(index, &script)? {
+                    let mut candidate = script.clone();
+                    *candidate.step_mut(index) = step;
+                    if let Some(reproduced) = self.reproduce(&candidate, &failure, &mut budget)? {
+                        found = Some((candidate, reproduced));
+                        break;
+                    }
+                }
+                match found {
+                    Some((candidate, reproduced)) => {
+                        script = candidate;
+                        script.calls.truncate(reproduced.step);
+                        failure = reproduced;
+                        shrunk = true;
+                    }
+                    None => index += 1,
+                }
+            }
+
+            if !shrunk || budget == 0 {
+                return Ok((script, failure));
+            }
+        }
+    }
+
+    /// Executes `script`, returning its failure if it is like `failure`.
+    fn reproduce(
+        &self,
+        script: &Script,
+        failure: &Failure,
+        budget: &mut usize,
+    ) -> Result<Option<Failure>> {
+        if *budget == 0 {
+            return Ok(None);
+        }
+        *budget -= 1;
+        Ok(self
+            .execute(script, false)?
+            .failure
+            .filter(|found| found.is_like(failure)))
+    }
+
+    /// Returns simpler variants of the step `index` of the script, the simplest first.
+    fn simpler_steps(&self, index: usize, script: &Script) -> Result<Vec<Step>> {
+        let step = match index {
+            0 => &script.constructor,
+            index => &script.calls[index - 1],
+        };
+        let spec = match index {
+            0 => self.abi.constructor(&step.label)?,
+            _ => self.abi.message(&step.label)?,
+        };
+        let mut steps = Vec::new();
+        if step.origin != ACCOUNTS[0].0 {
+            steps.push(Step {
+                origin: ACCOUNTS[0].0.to_string(),
+                ..step.clone()
+            });
+        }
+        if step.value > 0 {
+            steps.push(Step {
+                value: 0,
+                ..step.clone()
+            });
+            steps.push(Step {
+                value: step.value / 2,
+                ..step.clone()
+            });
+        }
+        for (position, (arg, value)) in spec.args.iter().zip(&step.args).enumerate() {
+            for shrunk in self.generator.shrink(arg.ty.id, value) {
+                let mut step = step.clone();
+                step.args[position] = shrunk;
+                steps.push(step);
+            }
+        }
+        Ok(steps)
+    }
+}
+
+/// Returns the storage of the contract, SCALE encoded to compare it.
+fn storage(sandbox: &Sandbox, contract: &AccountId) -> Vec<u8> {
+    use codec::Encode;
+    sandbox.storage(contract).collect::<Vec<_>>().encode()
+}
+
+/// Returns the message of a panic of the contract, which ink! prints as debug message if the
+/// contract was built with debug messages enabled.
+fn panic_message(debug_messages: &[String]) -> Option<&str> {
+    debug_messages
+        .iter()
+        .find(|message| message.contains("panicked at"))
+        .map(|message| message.trim_end())
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use contract_sandbox::DEFAULT_GAS_LIMIT;
+    use serde_json::json;
+
+    /// A counter whose `add` message traps when the counter overflows, and whose `small`
+    /// message returns whether the counter is below 1000.
+    pub(crate) const COUNTER: &str = r#"
+        (module
+            (import "seal0" "seal_input" (func $seal_input (param i32 i32)))
+            (import "seal0" "seal_return" (func $seal_return (param i32 i32 i32)))
+            (import "seal0" "seal_set_storage" (func $seal_set_storage (param i32 i32 i32)))
+            (import "seal0" "seal_get_storage" (func $seal_get_storage (param i32 i32 i32) (result i32)))
+            (import "env" "memory" (memory 1 1))
+
+            ;; [0, 32) the storage key, [32, 36) the buffer length, [36, 40) the counter,
+            ;; [40, 48) the input, [48, 49) the output of `small`
+            (func (export "deploy")
+                (call $seal_set_storage (i32.const 0) (i32.const 36) (i32.const 4))
+            )
+
+            (func (export "call")
+                (local $counter i32)
+                (i32.store (i32.const 32) (i32.const 8))
+                (call $seal_input (i32.const 40) (i32.const 32))
+                (i32.store (i32.const 32) (i32.const 4))
+                (drop (call $seal_get_storage (i32.const 0) (i32.const 36) (i32.const 32)))
+                ;; the selector of `add`
+                (if (i32.eq (i32.load (i32.const 40)) (i32.const 0x01000000))
+                    (then
+                        (local.set $counter
+                            (i32.add (i32.load (i32.const 36)) (i32.load (i32.const 44))))
+                        (if (i32.lt_u (local.get $counter) (i32.load (i32.const 36)))
+                            (then (unreachable)))
+                        (i32.store (i32.const 36) (local.get $counter))
+                        (call $seal_set_storage (i32.const 0) (i32.const 36) (i32.const 4))
+                        (call $seal_return (i32.const 0) (i32.const 0) (i32.const 0))
+                    )
+                )
+                ;; the selector of `small`
+                (if (i32.eq (i32.load (i32.const 40)) (i32.const 0x03000000))
+                    (then
+                        (i32.store8 (i32.const 48)
+                            (i32.lt_u (i32.load (i32.const 36)) (i32.const 1000)))
+                        (call $seal_return (i32.const 0) (i32.const 48) (i32.const 1))
+                    )
+                )
+                (call $seal_return (i32.const 0) (i32.const 36) (i32.const 4))
+            )
+        )
+    "#;
+
+    /// The metadata of the [`COUNTER`] contract.
+    pub(crate) fn counter_metadata() -> Value {
+        let message = |name: &str, selector: &str, args, mutates, return_type| {
+            json!({
+                "args": args, "docs": [], "mutates": mutates, "name": [name], "payable": false,
+                "returnType": return_type, "selector": selector
+            })
+        };
+        let by = json!([{ "name": "by", "type": { "displayName": ["u32"], "type": 0 } }]);
+        let count = json!({ "displayName": ["u32"], "type": 0 });
+        let small = json!({ "displayName": ["bool"], "type": 1 });
+        json!({
+            "metadataVersion": "0.1.0",
+            "source": { "hash": "0x00", "language": "ink! 3.0.0-rc6", "compiler": "rustc 1.57.0" },
+            "contract": { "name": "counter", "version": "0.1.0", "authors": [] },
+            "V1": {
+                "spec": {
+                    "constructors": [
+                        { "args": [], "docs": [], "name": ["new"], "selector": "0x9bae9d5e" }
+                    ],
+                    "docs": [],
+                    "events": [],
+                    "messages": [
+                        message("add", "0x00000001", by, true, Value::Null),
+                        message("get", "0x00000002", json!([]), false, count),
+                        message("small", "0x00000003", json!([]), false, small),
+                    ]
+                },
+                "storage": { "struct": { "fields": [] } },
+                "types": [
+                    { "id": 0, "type": { "def": { "primitive": "u32" } } },
+                    { "id": 1, "type": { "def": { "primitive": "bool" } } }
+                ]
+            }
+        })
+    }
+
+    pub(crate) fn counter_abi() -> ContractAbi {
+        ContractAbi::from_json(&counter_metadata()).unwrap()
+    }
+
+    #[test]
+    fn finds_and_minimizes_traps() {
+        let abi = counter_abi();
+        let wasm = wabt::wat2wasm(COUNTER).unwrap();
+        let fuzzer = Fuzzer::new(&abi, &wasm, DEFAULT_GAS_LIMIT, &[]).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let (script, failure) = (0..100)
+            .find_map(|_| {
+                let script = fuzzer.generate(&mut rng, None, 16);
+                let failure = fuzzer.execute(&script, false).unwrap().failure?;
+                Some((script, failure))
+            })
+            .expect("the overflow is found");
+        let (script, failure) = fuzzer.minimize(script, failure).unwrap();
+
+        assert_eq!(failure.label, "add");
+        assert!(matches!(failure.problem, Problem::Trapped(_)));
+        // two additions overflow the counter
+        assert_eq!(script.calls.len(), 2);
+        assert!(script.steps().all(|step| step.origin == "alice"));
+        let sum = script
+            .calls
+            .iter()
+            .map(|call| call.args[0].as_u64().unwrap())
+            .sum::<u64>();
+        assert!(sum > u64::from(u32::MAX));
+    }
+
+    #[test]
+    fn checks_invariants_after_each_step() {
+        let abi = counter_abi();
+        let wasm = wabt::wat2wasm(COUNTER).unwrap();
+        let invariants = ["small".to_string()];
+        let fuzzer = Fuzzer::new(&abi, &wasm, DEFAULT_GAS_LIMIT, &invariants).unwrap();
+        let mut rng = StdRng::seed_from_u64(0);
+
+        let (script, failure) = (0..100)
+            .find_map(|_| {
+                let script = fuzzer.generate(&mut rng, None, 16);
+                let failure = fuzzer.execute(&script, false).unwrap().failure?;
+                Some((script, failure))
+            })
+            .expect("the invariant is broken");
+        let (script, failure) = fuzzer.minimize(script, failure).unwrap();
+
+        assert_eq!(
+            failure.to_string(),
+            "`add` broke the invariant `small`, which returned false"
+        );
+        assert_eq!(script.calls.len(), 1);
+        assert_eq!(script.calls[0].args, vec![Value::from(1000)]);
+        assert!(fuzzer.messages.iter().all(|spec| spec.label != "small"));
+    }
+
+    #[test]
+    fn scripts_roundtrip_through_json() {
+        let step = |label: &str, args: Vec<Value>, value| Step {
+            label: label.to_string(),
+            args,
+            origin: "bob".to_string(),
+            value,
+        };
+        let script = Script {
+            constructor: step("new", vec![Value::from(1)], 0),
+            calls: vec![step(
+                "transfer",
+                vec![Value::from("0x01"), Value::from(2)],
+                3,
+            )],
+        };
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("script.json");
+        let failure = Failure {
+            step: 1,
+            label: "transfer".to_string(),
+            problem: Problem::OutOfGas,
+        };
+        script.write(&path, &failure).unwrap();
+        assert_eq!(Script::read(&path).unwrap(), script);
+        assert_eq!(script.calls[0].origin().unwrap(), BOB);
+        assert_eq!(
+            script.calls[0].to_string(),
+            "transfer(\"0x01\", 2) by bob with value 3"
+        );
+    }
+
+    #[test]
+    fn failures_are_alike_regardless_of_their_details() {
+        let failure = |step, label: &str, problem| Failure {
+            step,
+            label: label.to_string(),
+            problem,
+        };
+        let trap = failure(2, "transfer", Problem::Trapped("overflow".to_string()));
+        assert!(trap.is_like(&failure(
+            1,
+            "transfer",
+            Problem::Trapped("unreachable".into())
+        )));
+        assert!(!trap.is_like(&failure(2, "transfer", Problem::OutOfGas)));
+        assert!(!trap.is_like(&failure(2, "approve", Problem::Trapped("overflow".into()))));
+        assert_eq!(trap.file_name(), "transfer-trapped.json");
+    }
+}
@@ -0,0 +1,312 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The gas limits of calls and instantiations, given on the command line or derived from the
+//! gas their dry run required.
+
+use std::convert::TryFrom;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use structopt::StructOpt;
+
+use super::{dry_run::Weight, submit::SubmitError};
+
+/// The computation time of the gas limit when there is no dry run to derive it from.
+pub(crate) const DEFAULT_GAS_LIMIT: u64 = 500_000_000;
+
+/// The gas limit of an extrinsic: its computation time and, on chains metering it, the size of
+/// its storage proof. The dimensions which are not given are derived from a dry run.
+#[derive(Debug, StructOpt)]
+pub(crate) struct GasOpts {
+    /// The gas limit as `<ref time>` or `<ref time>,<proof size>`, combining `--gas-ref-time`
+    /// and `--gas-proof-size`
+    #[structopt(
+        long,
+        parse(try_from_str = parse_weight),
+        conflicts_with_all = &["gas-ref-time", "gas-proof-size"]
+    )]
+    gas: Option<Weight>,
+    /// The maximum computation time of the execution, in picoseconds. Defaults to the time
+    /// required by a dry run plus `--gas-margin`
+    #[structopt(long)]
+    gas_ref_time: Option<u64>,
+    /// The maximum size of the storage proof of the execution, in bytes, on chains metering
+    /// it. Defaults to the size required by a dry run plus `--gas-margin`
+    #[structopt(long)]
+    gas_proof_size: Option<u64>,
+    /// The percentage added to the gas required by the dry run, for the gas limits which are
+    /// not given
+    #[structopt(long, default_value = "10")]
+    gas_margin: u64,
+}
+
+impl GasOpts {
+    /// Returns the computation time and proof size given on the command line.
+    fn given(&self) -> (Option<u64>, Option<u64>) {
+        match self.gas {
+            Some(gas) => (Some(gas.ref_time), gas.proof_size),
+            None => (self.gas_ref_time, self.gas_proof_size),
+        }
+    }
+
+    /// Returns the gas limit of the dry run: the given one, or `None` for the maximum the node
+    /// allows if no computation time is given.
+    pub fn dry_run_limit(&self) -> Option<Weight> {
+        let (ref_time, proof_size) = self.given();
+        ref_time.map(|ref_time| Weight {
+            ref_time,
+            proof_size,
+        })
+    }
+
+    /// Returns the gas limit to submit the extrinsic with: the given one, whose missing
+    /// dimensions are the ones `required` by the dry run plus the margin.
+    ///
+    /// Without a dry run, the computation time defaults to [`DEFAULT_GAS_LIMIT`] and the proof
+    /// size is not metered.
+    pub fn limit(&self, required: Option<Weight>) -> Weight {
+        let (ref_time, proof_size) = self.given();
+        if ref_time.is_none() && required.is_none() {
+            eprintln!(
+                "{} No gas was estimated by a dry run, submitting with the gas limit {}",
+                "warning:".yellow().bold(),
+                DEFAULT_GAS_LIMIT
+            );
+        }
+        let margin = |gas: u64| gas.saturating_add(gas.saturating_mul(self.gas_margin) / 100);
+        Weight {
+            ref_time: ref_time
+                .or_else(|| required.map(|required| margin(required.ref_time)))
+                .unwrap_or(DEFAULT_GAS_LIMIT),
+            proof_size: proof_size.or_else(|| {
+                required
+                    .and_then(|required| required.proof_size)
+                    .map(margin)
+            }),
+        }
+    }
+}
+
+/// The limits an extrinsic executing a contract is submitted with.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Limits {
+    pub gas: Weight,
+    /// The storage deposit limit, unbounded if `None`.
+    pub storage_deposit: Option<u128>,
+}
+
+/// Submits extrinsics again with raised limits if they run out of gas or exceed their storage
+/// deposit limit.
+#[derive(Debug, StructOpt)]
+pub(crate) struct AutoRetryOpts {
+    /// Submit the extrinsic again, up to this many times, if it runs out of gas or exceeds the
+    /// storage deposit limit, with the limits required by a new dry run raised by
+    /// `--auto-retry-factor`
+    #[structopt(long, value_name = "retries", conflicts_with_all = &["nonce", "offline"])]
+    auto_retry: Option<u32>,
+    /// The percentage the limits are raised by on every automatic retry
+    #[structopt(long, default_value = "50", value_name = "percent")]
+    auto_retry_factor: u64,
+}
+
+impl AutoRetryOpts {
+    /// Submits the extrinsic with `limits`, and again with raised limits for as many retries
+    /// as allowed if it runs out of gas or exceeds its storage deposit limit.
+    ///
+    /// `dry_run` returns the limits required by a new dry run, `None` if it fails. Returns the
+    /// outcome of `submit` together with the limits it finally succeeded with.
+    pub fn submit<T>(
+        &self,
+        mut limits: Limits,
+        mut dry_run: impl FnMut() -> Option<Limits>,
+        mut submit: impl FnMut(Limits) -> Result<T>,
+    ) -> Result<(T, Limits)> {
+        let retries = self.auto_retry.unwrap_or_default();
+        let mut retry = 0;
+        loop {
+            match submit(limits) {
+                Err(err) if retry < retries && exceeded_limits(&err) => {
+                    retry += 1;
+                    limits = self.raise(limits, dry_run());
+                    eprintln!(
+                        "{} {}\n\tSubmitting again with the gas limit {} and the storage \
+                        deposit limit {} ({} of {})",
+                        "Retrying:".yellow().bold(),
+                        err,
+                        limits.gas,
+                        limits
+                            .storage_deposit
+                            .map_or_else(|| "unbounded".to_string(), |limit| limit.to_string()),
+                        retry,
+                        retries
+                    );
+                }
+                result => return result.map(|outcome| (outcome, limits)),
+            }
+        }
+    }
+
+    /// Returns `limits`, or the limits `required` by the dry run if they are higher, raised by
+    /// `--auto-retry-factor`. An unbounded storage deposit limit stays unbounded.
+    fn raise(&self, limits: Limits, required: Option<Limits>) -> Limits {
+        let factor = u128::from(self.auto_retry_factor);
+        let raise = |amount: u128| amount.saturating_add(amount.saturating_mul(factor) / 100);
+        let raise_gas = |gas: u64| u64::try_from(raise(u128::from(gas))).unwrap_or(u64::MAX);
+        let required = required.unwrap_or(limits);
+        Limits {
+            gas: Weight {
+                ref_time: raise_gas(limits.gas.ref_time.max(required.gas.ref_time)),
+                proof_size: limits
+                    .gas
+                    .proof_size
+                    .max(required.gas.proof_size)
+                    .map(raise_gas),
+            },
+            storage_deposit: limits
+                .storage_deposit
+                .map(|limit| raise(limit.max(required.storage_deposit.unwrap_or_default()))),
+        }
+    }
+}
+
+/// Returns whether the extrinsic failed by running out of gas or exceeding its storage
+/// deposit limit.
+fn exceeded_limits(err: &anyhow::Error) -> bool {
+    match err.downcast_ref::<SubmitError>() {
+        Some(SubmitError::Failed(failure)) => {
+            failure.starts_with("Contracts::OutOfGas")
+                || failure.starts_with("Contracts::StorageDepositLimitExhausted")
+        }
+        _ => false,
+    }
+}
+
+/// Parses a gas limit given as `<ref time>` or `<ref time>,<proof size>`.
+pub(crate) fn parse_weight(input: &str) -> Result<Weight> {
+    let (ref_time, proof_size) = match input.split_once(',') {
+        Some((ref_time, proof_size)) => (ref_time, Some(proof_size)),
+        None => (input, None),
+    };
+    let ref_time = ref_time
+        .trim()
+        .parse()
+        .with_context(|| format!("Invalid computation time of the gas limit {}", input))?;
+    let proof_size = proof_size
+        .map(|proof_size| proof_size.trim().parse())
+        .transpose()
+        .with_context(|| format!("Invalid proof size of the gas limit {}", input))?;
+    Ok(Weight {
+        ref_time,
+        proof_size,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gas_opts(args: &[&str]) -> GasOpts {
+        GasOpts::from_iter(std::iter::once("gas").chain(args.iter().copied()))
+    }
+
+    #[test]
+    fn derives_missing_limits_from_the_dry_run() {
+        let required = Weight {
+            ref_time: 1_000,
+            proof_size: Some(200),
+        };
+        assert_eq!(
+            gas_opts(&[]).limit(Some(required)),
+            Weight {
+                ref_time: 1_100,
+                proof_size: Some(220),
+            }
+        );
+        let opts = gas_opts(&["--gas-ref-time", "5000", "--gas-margin", "50"]);
+        assert_eq!(opts.dry_run_limit().unwrap().ref_time, 5_000);
+        assert_eq!(
+            opts.limit(Some(required)),
+            Weight {
+                ref_time: 5_000,
+                proof_size: Some(300),
+            }
+        );
+        let opts = gas_opts(&["--gas", "7000,64"]);
+        assert_eq!(opts.limit(None), opts.dry_run_limit().unwrap());
+        assert_eq!(opts.limit(None).proof_size, Some(64));
+        assert_eq!(gas_opts(&[]).limit(None).ref_time, DEFAULT_GAS_LIMIT);
+
+        assert!(parse_weight("100,").is_err());
+        assert_eq!(parse_weight("100").unwrap().proof_size, None);
+    }
+
+    #[test]
+    fn raises_the_limits_of_retries() {
+        let opts = AutoRetryOpts {
+            auto_retry: Some(2),
+            auto_retry_factor: 50,
+        };
+        let limits = Limits {
+            gas: Weight {
+                ref_time: 1_000,
+                proof_size: Some(100),
+            },
+            storage_deposit: Some(10),
+        };
+        let required = Limits {
+            gas: Weight {
+                ref_time: 2_000,
+                proof_size: Some(50),
+            },
+            storage_deposit: Some(40),
+        };
+        assert_eq!(
+            opts.raise(limits, Some(required)),
+            Limits {
+                gas: Weight {
+                    ref_time: 3_000,
+                    proof_size: Some(150),
+                },
+                storage_deposit: Some(60),
+            }
+        );
+        let unbounded = Limits {
+            storage_deposit: None,
+            ..limits
+        };
+        assert_eq!(opts.raise(unbounded, None).storage_deposit, None);
+        assert_eq!(opts.raise(unbounded, None).gas.ref_time, 1_500);
+
+        let mut attempts = Vec::new();
+        let (outcome, used) = opts
+            .submit(
+                limits,
+                || Some(required),
+                |limits| {
+                    attempts.push(limits);
+                    match attempts.len() {
+                        1 => Err(SubmitError::Failed("Contracts::OutOfGas".to_string()).into()),
+                        _ => Ok("executed"),
+                    }
+                },
+            )
+            .unwrap();
+        assert_eq!(outcome, "executed");
+        assert_eq!(attempts.len(), 2);
+        assert_eq!(used.gas.ref_time, 3_000);
+    }
+}
@@ -0,0 +1,168 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates a typed client of a contract with `cargo contract generate-client`.
+
+use crate::{
+    abi::ContractAbi,
+    cmd::metadata::built_metadata_path,
+    codegen::{python, rust},
+};
+use anyhow::Result;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
+use structopt::StructOpt;
+
+/// Generates a client crate with typed calls of the constructors and messages of a contract.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "generate-client", alias = "generate-types")]
+pub struct GenerateClientCommand {
+    /// Path to the Cargo.toml of the contract whose built metadata to generate the client of
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The language of the client.
+    ///
+    /// - `rust`: A crate with a function building a typed call for each constructor and
+    ///   message.
+    ///
+    /// - `python`: A package with dataclasses of the types and a method building a call for
+    ///   each constructor and message, submitted with py-substrate-interface.
+    #[structopt(long, value_name = "rust | python", verbatim_doc_comment)]
+    lang: ClientLanguage,
+    /// The directory of the client, `<contract>-client` for Rust and `<contract>_client`
+    /// for Python if not given
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+/// The languages `cargo contract generate-client` generates clients in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ClientLanguage {
+    Rust,
+    Python,
+}
+
+impl std::str::FromStr for ClientLanguage {
+    type Err = String;
+    fn from_str(lang: &str) -> Result<Self, Self::Err> {
+        match lang {
+            "rust" => Ok(ClientLanguage::Rust),
+            "python" => Ok(ClientLanguage::Python),
+            _ => Err("Could not parse client language".to_string()),
+        }
+    }
+}
+
+impl GenerateClientCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        // named `<contract>-client` for Rust and `<contract>_client` for Python
+        let dir = |separator: &str| match &self.output {
+            Some(dir) => dir.clone(),
+            None => {
+                let contract = abi.name.as_deref().unwrap_or("contract");
+                let contract = contract.replace(&['-', '_'][..], separator);
+                PathBuf::from(format!("{}{}client", contract, separator))
+            }
+        };
+
+        let dir = match self.lang {
+            ClientLanguage::Rust => {
+                let dir = dir("-");
+                let client = rust::generate_client(&abi)?;
+                let lib_rs = dir.join("src").join("lib.rs");
+                ensure_generated(&lib_rs, rust::GENERATED_HEADER)?;
+                fs::create_dir_all(dir.join("src"))?;
+                // the manifest is kept, it may have been extended since
+                let manifest_path = dir.join("Cargo.toml");
+                if !manifest_path.exists() {
+                    fs::write(&manifest_path, client.manifest)?;
+                }
+                fs::write(&lib_rs, client.lib)?;
+                dir
+            }
+            ClientLanguage::Python => {
+                let dir = dir("_");
+                let init_py = dir.join("__init__.py");
+                ensure_generated(&init_py, python::GENERATED_HEADER)?;
+                fs::create_dir_all(&dir)?;
+                fs::write(&init_py, python::generate(&abi)?)?;
+                dir
+            }
+        };
+        Ok(Some(format!("Generated the client in {}", dir.display())))
+    }
+}
+
+/// Fails if `path` exists but was not generated, i.e. does not start with `header`.
+fn ensure_generated(path: &Path, header: &str) -> Result<()> {
+    match fs::read_to_string(path) {
+        Ok(contents) if !contents.starts_with(header) => anyhow::bail!(
+            "{} was not generated by cargo-contract, refusing to overwrite it",
+            path.display()
+        ),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn generates_rust_client() {
+        with_tmp_dir(|path| {
+            let metadata_path = path.join("metadata.json");
+            fs::write(&metadata_path, crate::abi::tests::metadata_v1().to_string())?;
+            let dir = path.join("bank-client");
+            let cmd = GenerateClientCommand {
+                manifest_path: None,
+                metadata: Some(metadata_path),
+                lang: ClientLanguage::Rust,
+                output: Some(dir.clone()),
+            };
+            cmd.exec()?;
+            let lib = fs::read_to_string(dir.join("src").join("lib.rs"))?;
+            assert!(lib.contains("pub mod messages {"));
+            assert!(dir.join("Cargo.toml").exists());
+
+            // hand-written code is not overwritten
+            fs::write(dir.join("src").join("lib.rs"), "// my client")?;
+            assert!(cmd.exec().is_err());
+
+            let dir = path.join("bank_client");
+            let cmd = GenerateClientCommand {
+                lang: ClientLanguage::Python,
+                output: Some(dir.clone()),
+                ..cmd
+            };
+            cmd.exec()?;
+            let module = fs::read_to_string(dir.join("__init__.py"))?;
+            assert!(module.contains("class Messages:"));
+            Ok(())
+        })
+    }
+}
@@ -0,0 +1,201 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hashes data with the hashers of substrate chains with `cargo contract hash`.
+
+use crate::abi::{decode_hex, encode_hex};
+use anyhow::{Context, Result};
+use blake2::digest::{Update as _, VariableOutput as _};
+use sha2::Digest as _;
+use std::{fs, hash::Hasher as _, path::PathBuf};
+use structopt::StructOpt;
+use tiny_keccak::Hasher as _;
+
+/// Hashes bytes or a string, e.g. for the topics of events, storage keys and code hashes.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "hash")]
+pub struct HashCommand {
+    /// The `0x` prefixed hex of the bytes to hash, or a string whose UTF-8 bytes are hashed
+    #[structopt(required_unless = "file")]
+    data: Option<String>,
+    /// A file whose contents to hash instead, e.g. a Wasm blob for its code hash
+    #[structopt(long, parse(from_os_str), conflicts_with = "data")]
+    file: Option<PathBuf>,
+    /// The hasher: blake2_128, blake2_256, blake2_128_concat, keccak256, sha2_256, twox64,
+    /// twox128, twox256 or twox64_concat
+    #[structopt(long, default_value = "blake2_256")]
+    algo: Hasher,
+}
+
+/// The hashers of substrate chains, including the `_concat` hashers of storage maps which
+/// append the hashed data to the hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hasher {
+    Blake2_128,
+    Blake2_256,
+    Blake2_128Concat,
+    Keccak256,
+    Sha2_256,
+    Twox64,
+    Twox128,
+    Twox256,
+    Twox64Concat,
+}
+
+impl std::str::FromStr for Hasher {
+    type Err = String;
+    fn from_str(algo: &str) -> Result<Self, Self::Err> {
+        match algo {
+            "blake2_128" => Ok(Hasher::Blake2_128),
+            "blake2_256" => Ok(Hasher::Blake2_256),
+            "blake2_128_concat" => Ok(Hasher::Blake2_128Concat),
+            "keccak256" => Ok(Hasher::Keccak256),
+            "sha2_256" => Ok(Hasher::Sha2_256),
+            "twox64" => Ok(Hasher::Twox64),
+            "twox128" => Ok(Hasher::Twox128),
+            "twox256" => Ok(Hasher::Twox256),
+            "twox64_concat" => Ok(Hasher::Twox64Concat),
+            _ => Err(format!("Unknown hasher {}", algo)),
+        }
+    }
+}
+
+impl Hasher {
+    /// Returns the hash of `data`.
+    pub fn hash(self, data: &[u8]) -> Vec<u8> {
+        match self {
+            Hasher::Blake2_128 => blake2_128(data).to_vec(),
+            Hasher::Blake2_256 => blake2_256(data).to_vec(),
+            Hasher::Blake2_128Concat => [&blake2_128(data)[..], data].concat(),
+            Hasher::Keccak256 => keccak_256(data).to_vec(),
+            Hasher::Sha2_256 => sha2_256(data).to_vec(),
+            Hasher::Twox64 => twox_64(data).to_vec(),
+            Hasher::Twox128 => twox_128(data).to_vec(),
+            Hasher::Twox256 => twox_256(data).to_vec(),
+            Hasher::Twox64Concat => [&twox_64(data)[..], data].concat(),
+        }
+    }
+}
+
+/// Returns the unkeyed blake2b hash of `data` with `N` bytes.
+fn blake2<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut output = [0u8; N];
+    let mut blake2 =
+        blake2::VarBlake2b::new(N).expect("blake2b supports hashes of 16 and 32 bytes");
+    blake2.update(data);
+    blake2.finalize_variable(|result| output.copy_from_slice(result));
+    output
+}
+
+fn blake2_128(data: &[u8]) -> [u8; 16] {
+    blake2(data)
+}
+
+fn blake2_256(data: &[u8]) -> [u8; 32] {
+    blake2(data)
+}
+
+fn keccak_256(data: &[u8]) -> [u8; 32] {
+    let mut output = [0u8; 32];
+    let mut keccak = tiny_keccak::Keccak::v256();
+    keccak.update(data);
+    keccak.finalize(&mut output);
+    output
+}
+
+fn sha2_256(data: &[u8]) -> [u8; 32] {
+    sha2::Sha256::digest(data).into()
+}
+
+/// Returns the concatenated little endian xxHash64 hashes of `data` seeded with `0..N / 8`.
+fn twox<const N: usize>(data: &[u8]) -> [u8; N] {
+    let mut output = [0u8; N];
+    for (seed, chunk) in output.chunks_mut(8).enumerate() {
+        let mut hasher = twox_hash::XxHash64::with_seed(seed as u64);
+        hasher.write(data);
+        chunk.copy_from_slice(&hasher.finish().to_le_bytes());
+    }
+    output
+}
+
+fn twox_64(data: &[u8]) -> [u8; 8] {
+    twox(data)
+}
+
+fn twox_128(data: &[u8]) -> [u8; 16] {
+    twox(data)
+}
+
+fn twox_256(data: &[u8]) -> [u8; 32] {
+    twox(data)
+}
+
+impl HashCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let data = match (&self.data, &self.file) {
+            (_, Some(path)) => {
+                fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?
+            }
+            (Some(data), None) => parse_data(data)?,
+            (None, None) => anyhow::bail!("Give the data to hash or --file"),
+        };
+        Ok(Some(encode_hex(&self.algo.hash(&data))))
+    }
+}
+
+/// Returns the bytes of `0x` prefixed hex, or the UTF-8 bytes of any other string.
+fn parse_data(data: &str) -> Result<Vec<u8>> {
+    if data.starts_with("0x") {
+        decode_hex(data)
+    } else {
+        Ok(data.as_bytes().to_vec())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn hashes_like_substrate() {
+        let hash = |algo: &str, data: &str| {
+            let algo = algo.parse::<Hasher>().unwrap();
+            encode_hex(&algo.hash(&parse_data(data).unwrap()))
+        };
+        assert_eq!(
+            hash("twox128", "System"),
+            "0x26aa394eea5630e07c48ae0c9558cef7"
+        );
+        assert_eq!(
+            hash("keccak256", "0x"),
+            "0xc5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(
+            hash("blake2_128_concat", "0x00000000"),
+            "0x11d2df4e979aa105cf552e9544ebd2b500000000"
+        );
+        assert_eq!(
+            hash("blake2_256", "0x"),
+            "0x0e5751c026e543b2e8ab2eb06099daa1d1e5df47778f7787faab45cdf12fe3a8"
+        );
+        assert_eq!(
+            hash("sha2_256", "0x"),
+            "0xe3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855"
+        );
+        assert_eq!(hash("twox64_concat", "0x01").len(), 2 + 2 * 9);
+        assert!("md5".parse::<Hasher>().is_err());
+    }
+}
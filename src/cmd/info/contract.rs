@@ -0,0 +1,433 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prints the on-chain state of an instantiated contract with `cargo contract info contract`,
+//! as a quick sanity check after deployments.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    storage::StorageKey,
+    H256,
+};
+use structopt::StructOpt;
+use subxt::{
+    sp_runtime::codec::{Decode, Encode, Error as CodecError, Input},
+    DefaultNodeRuntime, Metadata, MetadataError, Store,
+};
+
+use crate::{
+    cmd::{
+        chain::{balance_bytes, parse_account_id},
+        dry_run::{balance_string, CONTRACTS},
+        metadata::{built_metadata_path, read_json},
+        remove::OwnerInfoOfStore,
+        submit,
+        token::{self, format_balance},
+    },
+    NodeOpts,
+};
+
+/// Prints the code hash, storage deposit and balance of an instantiated contract.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "contract")]
+pub struct ContractCommand {
+    /// The account of the contract
+    #[structopt(long, parse(try_from_str = parse_account_id))]
+    contract: AccountId32,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Path to the Cargo.toml of the contract project to compare the code on chain with
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to compare the code on chain with, instead of the
+    /// built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the information as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The `Contracts::ContractInfoOf` storage of an instantiated contract.
+pub(crate) struct ContractInfoOfStore<'a> {
+    pub contract: &'a AccountId32,
+}
+
+impl Encode for ContractInfoOfStore<'_> {
+    fn encode(&self) -> Vec<u8> {
+        self.contract.encode()
+    }
+}
+
+impl Store<DefaultNodeRuntime> for ContractInfoOfStore<'_> {
+    const MODULE: &'static str = CONTRACTS;
+    const FIELD: &'static str = "ContractInfoOf";
+    type Returns = ContractInfo;
+
+    fn prefix(metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .prefix())
+    }
+
+    fn key(&self, metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .map()?
+            .key(self.contract))
+    }
+}
+
+/// The child trie, code and storage deposit of an instantiated contract.
+#[derive(Debug, PartialEq, Eq)]
+pub(crate) struct ContractInfo {
+    /// The id of the child trie holding the storage of the contract.
+    pub trie_id: Vec<u8>,
+    pub code_hash: H256,
+    /// The deposit for the storage of the contract, unless its layout is not known.
+    pub storage_deposit: Option<u128>,
+}
+
+impl Decode for ContractInfo {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        let trie_id = Vec::<u8>::decode(input)?;
+        let code_hash = H256::decode(input)?;
+        // the single deposit of the contract was split up into the deposits of its bytes,
+        // items and the contract itself, which follow the number of bytes and items
+        let storage_deposit = match input.remaining_len()? {
+            Some(16) => Some(u128::decode(input)?),
+            Some(len) if len >= 56 => {
+                let _bytes_and_items = <[u8; 8]>::decode(input)?;
+                let deposits = <[u128; 3]>::decode(input)?;
+                Some(deposits.iter().sum())
+            }
+            _ => None,
+        };
+        Ok(ContractInfo {
+            trie_id,
+            code_hash,
+            storage_deposit,
+        })
+    }
+}
+
+/// The `System::Account` storage of an account.
+struct AccountStore<'a> {
+    account: &'a AccountId32,
+}
+
+impl Encode for AccountStore<'_> {
+    fn encode(&self) -> Vec<u8> {
+        self.account.encode()
+    }
+}
+
+impl Store<DefaultNodeRuntime> for AccountStore<'_> {
+    const MODULE: &'static str = "System";
+    const FIELD: &'static str = "Account";
+    type Returns = AccountBalance;
+
+    fn prefix(metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .prefix())
+    }
+
+    fn key(&self, metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .map()?
+            .key(self.account))
+    }
+}
+
+/// The free and reserved balance of an account.
+#[derive(Debug, Default, PartialEq, Eq)]
+struct AccountBalance {
+    free: u128,
+    reserved: u128,
+}
+
+impl Decode for AccountBalance {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        // the reference counters before the balances differ between runtime versions, the
+        // balances are the last four `u128`s, or `u64`s on some chains, and start with the free
+        // and reserved balance
+        let len = input
+            .remaining_len()?
+            .ok_or_else(|| CodecError::from("The length of the account is unknown"))?;
+        if balance_bytes(len) == Some(8) {
+            input.read(&mut vec![0; len - 32])?;
+            let [free, reserved, _, _] = <[u64; 4]>::decode(input)?;
+            return Ok(AccountBalance {
+                free: free.into(),
+                reserved: reserved.into(),
+            });
+        }
+        let skipped = len
+            .checked_sub(64)
+            .ok_or_else(|| CodecError::from("The account holds no balances"))?;
+        input.read(&mut vec![0; skipped])?;
+        let [free, reserved, _, _] = <[u128; 4]>::decode(input)?;
+        Ok(AccountBalance { free, reserved })
+    }
+}
+
+/// The information printed about a contract.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Info {
+    contract: String,
+    code_hash: H256,
+    /// The deposit for the storage of the contract, unless its layout is not known.
+    #[serde(
+        skip_serializing_if = "Option::is_none",
+        serialize_with = "optional_balance_string"
+    )]
+    storage_deposit: Option<u128>,
+    #[serde(serialize_with = "balance_string")]
+    free_balance: u128,
+    #[serde(serialize_with = "balance_string")]
+    reserved_balance: u128,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    code_owner: Option<CodeOwner>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    local_project: Option<LocalProject>,
+}
+
+/// The account which uploaded the code of the contract.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct CodeOwner {
+    owner: String,
+    #[serde(serialize_with = "balance_string")]
+    deposit: u128,
+    /// The number of contracts instantiated from the code.
+    refcount: u64,
+}
+
+/// The local contract project compared with the code on chain.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LocalProject {
+    name: String,
+    version: String,
+    code_hash: H256,
+    /// `true` if the code of the project is the code of the contract.
+    matches: bool,
+}
+
+fn optional_balance_string<S: serde::Serializer>(
+    value: &Option<u128>,
+    serializer: S,
+) -> Result<S::Ok, S::Error> {
+    match value {
+        Some(value) => balance_string(value, serializer),
+        None => serializer.serialize_none(),
+    }
+}
+
+impl ContractCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let local_project = self.local_project()?;
+        let (info, token) = self.node_opts.block_on(async {
+            let cli = submit::builder(&self.node_opts.url()?).build().await?;
+            let contract = cli
+                .fetch(
+                    &ContractInfoOfStore {
+                        contract: &self.contract,
+                    },
+                    None,
+                )
+                .await?
+                .with_context(|| {
+                    format!(
+                        "No contract is instantiated at {}",
+                        self.contract.to_ss58check()
+                    )
+                })?;
+            let balance = cli
+                .fetch(
+                    &AccountStore {
+                        account: &self.contract,
+                    },
+                    None,
+                )
+                .await?
+                .unwrap_or_default();
+            let code_owner = cli
+                .fetch(
+                    &OwnerInfoOfStore {
+                        code_hash: contract.code_hash,
+                    },
+                    None,
+                )
+                .await?;
+            let info = Info {
+                contract: self.contract.to_ss58check(),
+                code_hash: contract.code_hash,
+                storage_deposit: contract.storage_deposit,
+                free_balance: balance.free,
+                reserved_balance: balance.reserved,
+                code_owner: code_owner.map(|owner| CodeOwner {
+                    owner: owner.owner.to_ss58check(),
+                    deposit: owner.deposit,
+                    refcount: owner.refcount,
+                }),
+                local_project: local_project.map(|(name, version, code_hash)| LocalProject {
+                    name,
+                    version,
+                    code_hash,
+                    matches: code_hash == contract.code_hash,
+                }),
+            };
+            let token = token::fetch_or_none(&self.node_opts.url()?).await;
+            Ok::<_, anyhow::Error>((info, token))
+        })?;
+
+        if self.output_json {
+            return Ok(Some(serde_json::to_string_pretty(&info)?));
+        }
+        let balance = |amount| format_balance(amount, token.as_ref());
+        let mut out = format!(
+            "{} {}\n{} {:?}\n{} {}\n{} free {}, reserved {}",
+            "Contract:".bold(),
+            info.contract,
+            "Code hash:".bold(),
+            info.code_hash,
+            "Storage deposit:".bold(),
+            info.storage_deposit
+                .map_or_else(|| "unknown".to_string(), balance),
+            "Balance:".bold(),
+            balance(info.free_balance),
+            balance(info.reserved_balance),
+        );
+        if let Some(owner) = &info.code_owner {
+            out.push_str(&format!(
+                "\n{} {}, who deposited {} for the code used by {} contracts",
+                "Code owner:".bold(),
+                owner.owner,
+                balance(owner.deposit),
+                owner.refcount
+            ));
+        }
+        if let Some(project) = &info.local_project {
+            let comparison = if project.matches {
+                "matches the code on chain".green()
+            } else {
+                format!("differs from the code on chain: {:?}", project.code_hash).yellow()
+            };
+            out.push_str(&format!(
+                "\n{} {} {}, {}",
+                "Local project:".bold(),
+                project.name,
+                project.version,
+                comparison
+            ));
+        }
+        println!("{}", out);
+        Ok(None)
+    }
+
+    /// Returns the name, version and code hash of the contract of `--metadata`, or of the
+    /// built contract. Outside of a contract project there may be none.
+    fn local_project(&self) -> Result<Option<(String, String, H256)>> {
+        let path = match (
+            &self.metadata,
+            built_metadata_path(self.manifest_path.as_ref()),
+        ) {
+            (Some(path), _) => path.clone(),
+            (None, Ok(path)) if path.exists() => path,
+            (None, Err(err)) if self.manifest_path.is_some() => return Err(err),
+            (None, _) => return Ok(None),
+        };
+        let json = read_json(&path)?;
+        let field = |value: &Value| value.as_str().unwrap_or_default().to_string();
+        let code_hash = json["source"]["hash"]
+            .as_str()
+            .and_then(|hash| crate::abi::decode_hex(hash).ok())
+            .filter(|hash| hash.len() == 32)
+            .map(|hash| H256::from_slice(&hash))
+            .with_context(|| format!("{} has no valid `source.hash`", path.display()))?;
+        Ok(Some((
+            field(&json["contract"]["name"]),
+            field(&json["contract"]["version"]),
+            code_hash,
+        )))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_contract_infos_of_both_layouts() {
+        let code_hash = H256::repeat_byte(7);
+        let mut single = vec![1u8, 2].encode();
+        single.extend(code_hash.encode());
+        single.extend(1_000u128.encode());
+        assert_eq!(
+            ContractInfo::decode(&mut &single[..]).unwrap(),
+            ContractInfo {
+                trie_id: vec![1, 2],
+                code_hash,
+                storage_deposit: Some(1_000),
+            }
+        );
+
+        let mut split = vec![1u8, 2].encode();
+        split.extend(code_hash.encode());
+        split.extend((10u32, 2u32, 100u128, 20u128, 3u128).encode());
+        // fields added later are ignored
+        split.extend(vec![0u8; 3]);
+        assert_eq!(
+            ContractInfo::decode(&mut &split[..])
+                .unwrap()
+                .storage_deposit,
+            Some(123)
+        );
+
+        let mut account = (5u32, 0u32, 1u32, 0u32).encode();
+        account.extend((500u128, 20u128, 0u128, 0u128).encode());
+        assert_eq!(
+            AccountBalance::decode(&mut &account[..]).unwrap(),
+            AccountBalance {
+                free: 500,
+                reserved: 20
+            }
+        );
+        let mut account = (5u32, 0u32, 1u32).encode();
+        account.extend((500u64, 20u64, 0u64, 0u64).encode());
+        assert_eq!(
+            AccountBalance::decode(&mut &account[..]).unwrap(),
+            AccountBalance {
+                free: 500,
+                reserved: 20
+            }
+        );
+    }
+}
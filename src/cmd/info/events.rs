@@ -0,0 +1,285 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Lists the events of a contract with their topics with `cargo contract info events`.
+//!
+//! ink! publishes an event with the topics of its signature and of its `#[ink(topic)]`
+//! fields. The signature topic is derived from `<Contract>::<Event>`, where `<Contract>` is
+//! the name of the storage struct of the contract. The topic of a field is derived from the
+//! prefix `<Contract>::<Event>::<field>` and the value of the field, it is only known when
+//! the event is emitted.
+
+use super::render_table;
+use crate::{
+    abi::{ContractAbi, EventSpec},
+    cmd::metadata::{blake2_hash, built_metadata_path},
+};
+use anyhow::Result;
+use codec::Encode as _;
+use heck::CamelCase as _;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Lists the events of a contract with their topics and fields.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "events")]
+pub struct EventsCommand {
+    /// Path to the Cargo.toml of the contract whose built metadata to read
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The name of the storage struct of the contract, which prefixes the topics.
+    ///
+    /// The camel cased name of the contract if not given, e.g. `Erc20` for `erc20`.
+    #[structopt(long, value_name = "ident")]
+    contract_ident: Option<String>,
+    /// Export the events in JSON format
+    #[structopt(long)]
+    output_json: bool,
+    /// Export a compact decoder table in JSON format, for indexers decoding the events
+    /// without the metadata
+    #[structopt(long, conflicts_with = "output-json")]
+    decoder_table: bool,
+}
+
+impl EventsCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        let contract_ident = match (&self.contract_ident, &abi.name) {
+            (Some(ident), _) => ident.clone(),
+            (None, Some(name)) => name.to_camel_case(),
+            (None, None) => anyhow::bail!(
+                "The metadata does not name the contract, pass its storage struct with `--contract-ident`"
+            ),
+        };
+        let events = events(&abi, &contract_ident);
+
+        if self.decoder_table {
+            let table = DecoderTable {
+                contract: contract_ident,
+                events: events
+                    .iter()
+                    .map(|event| DecoderEntry {
+                        index: event.index,
+                        name: event.label.clone(),
+                        topic: event.signature_topic.clone(),
+                        fields: event
+                            .fields
+                            .iter()
+                            .map(|field| DecoderField {
+                                name: field.name.clone(),
+                                encoding: field.encoding.clone(),
+                                indexed: field.indexed,
+                            })
+                            .collect(),
+                    })
+                    .collect(),
+            };
+            println!("{}", serde_json::to_string(&table)?);
+        } else if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&events)?);
+        } else {
+            print!("{}", table(&events));
+        }
+        Ok(None)
+    }
+}
+
+/// An event with its topics and fields.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct EventInfo {
+    /// The index of the event, which is the first byte of its encoded data.
+    pub index: u8,
+    pub label: String,
+    /// The topic of the signature of the event, the first topic it is published with.
+    pub signature_topic: String,
+    pub fields: Vec<EventField>,
+}
+
+/// A field of an event.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct EventField {
+    pub name: String,
+    /// The Rust like name of the type, e.g. `Option<AccountId>`.
+    #[serde(rename = "type")]
+    pub ty: String,
+    /// How the value of the field is SCALE encoded.
+    pub encoding: String,
+    /// `true` for `#[ink(topic)]` fields.
+    pub indexed: bool,
+    /// The prefix of the topic of `#[ink(topic)]` fields, which is hashed with the value.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub topic_prefix: Option<String>,
+}
+
+/// The compact description of the events for decoding them.
+#[derive(serde::Serialize)]
+struct DecoderTable {
+    contract: String,
+    events: Vec<DecoderEntry>,
+}
+
+#[derive(serde::Serialize)]
+struct DecoderEntry {
+    index: u8,
+    name: String,
+    topic: String,
+    fields: Vec<DecoderField>,
+}
+
+#[derive(serde::Serialize)]
+struct DecoderField {
+    name: String,
+    encoding: String,
+    indexed: bool,
+}
+
+/// Returns the events of `abi` in the order of the metadata, which is the order of their
+/// indices.
+pub fn events(abi: &ContractAbi, contract_ident: &str) -> Vec<EventInfo> {
+    abi.events
+        .iter()
+        .enumerate()
+        .map(|(index, event)| EventInfo {
+            index: index as u8,
+            label: event.label.clone(),
+            signature_topic: signature_topic(contract_ident, event),
+            fields: event
+                .args
+                .iter()
+                .map(|arg| EventField {
+                    name: arg.label.clone(),
+                    ty: abi.types.type_name(arg.ty.id),
+                    encoding: abi.types.encoding(arg.ty.id),
+                    indexed: arg.indexed,
+                    topic_prefix: if arg.indexed {
+                        Some(format!(
+                            "{}::{}::{}",
+                            contract_ident, event.label, arg.label
+                        ))
+                    } else {
+                        None
+                    },
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Returns the topic of the signature of `event` as `0x` prefixed hex.
+///
+/// Like the topics of fields, it is the SCALE encoded prefix, which is empty, followed by the
+/// value `<Contract>::<Event>`. Values of up to 32 bytes are the topic themselves, padded
+/// with zeros, longer ones are hashed with BLAKE2b-256.
+fn signature_topic(contract_ident: &str, event: &EventSpec) -> String {
+    let signature = format!("{}::{}", contract_ident, event.label);
+    // the empty prefix
+    let mut encoded = Vec::<u8>::new().encode();
+    encoded.extend_from_slice(signature.as_bytes());
+    let topic = if encoded.len() <= 32 {
+        let mut topic = [0u8; 32];
+        topic[..encoded.len()].copy_from_slice(&encoded);
+        topic
+    } else {
+        blake2_hash(&encoded).0
+    };
+    let hex = topic
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect::<String>();
+    format!("0x{}", hex)
+}
+
+/// Renders the events as a table with a row per field.
+fn table(events: &[EventInfo]) -> String {
+    let mut rows = Vec::new();
+    for event in events {
+        let mut cells = vec![
+            event.index.to_string(),
+            event.label.clone(),
+            event.signature_topic.clone(),
+        ];
+        if event.fields.is_empty() {
+            rows.push(cells);
+            continue;
+        }
+        for field in &event.fields {
+            cells.push(format!("{}: {}", field.name, field.ty));
+            cells.push(field.topic_prefix.clone().unwrap_or_default());
+            rows.push(cells);
+            // the event is named in its first row only
+            cells = vec![String::new(); 3];
+        }
+    }
+    render_table(
+        &["INDEX", "EVENT", "SIGNATURE TOPIC", "FIELD", "TOPIC PREFIX"],
+        &rows,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn lists_events_with_topics() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let listed = events(&abi, "Bank");
+
+        // `Bank::Deposited` with its empty prefix fits into a topic
+        let mut expected = b"\0Bank::Deposited".to_vec();
+        expected.resize(32, 0);
+        assert_eq!(
+            listed[0].signature_topic,
+            format!(
+                "0x{}",
+                expected
+                    .iter()
+                    .map(|b| format!("{:02x}", b))
+                    .collect::<String>()
+            )
+        );
+        assert_eq!(
+            serde_json::to_value(&listed[0].fields).unwrap(),
+            serde_json::json!([
+                {
+                    "name": "from",
+                    "type": "Option<AccountId>",
+                    "encoding": "enum { 0: (), 1: [u8; 32] }",
+                    "indexed": true,
+                    "topic_prefix": "Bank::Deposited::from",
+                },
+                {
+                    "name": "value",
+                    "type": "u128",
+                    "encoding": "u128",
+                    "indexed": false,
+                },
+            ])
+        );
+
+        let long = events(&abi, "AVeryLongContractIdentifier");
+        assert_eq!(long[0].signature_topic.len(), 66);
+        assert_ne!(&long[0].signature_topic[2..], "0".repeat(64));
+    }
+}
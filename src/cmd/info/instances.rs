@@ -0,0 +1,183 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Lists the contracts instantiated from a code hash with `cargo contract info instances`, by
+//! iterating the `Contracts::ContractInfoOf` storage of the chain.
+
+use std::convert::TryFrom;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::twox_128,
+    H256,
+};
+use structopt::StructOpt;
+use subxt::sp_runtime::codec::Decode;
+
+use super::contract::ContractInfo;
+use crate::{
+    abi::decode_hex,
+    cmd::{dry_run::hex, rpc},
+    parse_code_hash, NodeOpts,
+};
+
+/// The number of storage keys fetched per request.
+const PAGE_SIZE: u32 = 1000;
+
+/// Lists the contracts instantiated from the code stored under a code hash.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "instances")]
+pub struct InstancesCommand {
+    /// The hash of the uploaded code whose contracts to list
+    #[structopt(long, parse(try_from_str = parse_code_hash))]
+    code_hash: H256,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Print the addresses of the contracts as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The storage values of keys at a block, as returned by `state_queryStorageAt`.
+#[derive(Deserialize)]
+struct StorageChangeSet {
+    changes: Vec<(String, Option<String>)>,
+}
+
+impl InstancesCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let (block, instances) = self.node_opts.block_on(self.instances())?;
+        let addresses = instances
+            .iter()
+            .map(Ss58Codec::to_ss58check)
+            .collect::<Vec<_>>();
+        if self.output_json {
+            let output = json!({ "block": block, "contracts": addresses });
+            return Ok(Some(serde_json::to_string_pretty(&output)?));
+        }
+        for address in &addresses {
+            println!("{}", address);
+        }
+        Ok(Some(format!(
+            "{} {} contracts are instantiated from {:?} at block {:?}",
+            "Found".green().bold(),
+            addresses.len(),
+            self.code_hash,
+            block
+        )))
+    }
+
+    /// Returns the best block and the contracts instantiated from the code at that block,
+    /// reading the storage page by page.
+    async fn instances(&self) -> Result<(H256, Vec<AccountId32>)> {
+        let url = &self.node_opts.url()?;
+        let block: H256 = rpc::request(url, "chain_getBlockHash", vec![]).await?;
+        let mut prefix = twox_128(b"Contracts").to_vec();
+        prefix.extend(&twox_128(b"ContractInfoOf"));
+
+        let mut instances = Vec::new();
+        let mut start = Value::Null;
+        loop {
+            let keys: Vec<String> = rpc::request(
+                url,
+                "state_getKeysPaged",
+                vec![
+                    hex(&prefix).into(),
+                    PAGE_SIZE.into(),
+                    start,
+                    hex(&block.0).into(),
+                ],
+            )
+            .await?;
+            if keys.is_empty() {
+                break;
+            }
+            let change_sets: Vec<StorageChangeSet> = rpc::request(
+                url,
+                "state_queryStorageAt",
+                vec![json!(keys), hex(&block.0).into()],
+            )
+            .await?;
+            for change_set in change_sets {
+                instances.extend(instances_of(&change_set.changes, &self.code_hash)?);
+            }
+            if keys.len() < PAGE_SIZE as usize {
+                break;
+            }
+            start = json!(keys.last());
+        }
+        Ok((block, instances))
+    }
+}
+
+/// Returns the accounts of the `ContractInfoOf` entries whose code hash is `code_hash`.
+///
+/// The accounts are the last 32 bytes of the keys, which are hashed with `Twox64Concat`.
+fn instances_of(
+    entries: &[(String, Option<String>)],
+    code_hash: &H256,
+) -> Result<Vec<AccountId32>> {
+    let mut instances = Vec::new();
+    for (key, value) in entries {
+        let value = match value {
+            Some(value) => decode_hex(value)?,
+            None => continue,
+        };
+        let info = ContractInfo::decode(&mut &value[..])
+            .with_context(|| format!("Failed to decode the contract info of the key {}", key))?;
+        if info.code_hash != *code_hash {
+            continue;
+        }
+        let key = decode_hex(key)?;
+        let account = key
+            .len()
+            .checked_sub(32)
+            .and_then(|start| <[u8; 32]>::try_from(&key[start..]).ok())
+            .with_context(|| format!("Invalid key {}", hex(&key)))?;
+        instances.push(AccountId32::from(account));
+    }
+    Ok(instances)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use subxt::sp_runtime::codec::Encode;
+
+    #[test]
+    fn filters_contracts_by_code_hash() {
+        let entry = |account: u8, code_hash: u8| {
+            let mut key = twox_128(b"Contracts").to_vec();
+            key.extend(&twox_128(b"ContractInfoOf"));
+            key.extend(&[0; 8]);
+            key.extend(&[account; 32]);
+            let mut value = vec![1u8].encode();
+            value.extend(H256::repeat_byte(code_hash).encode());
+            value.extend(100u128.encode());
+            (hex(&key), Some(hex(&value)))
+        };
+        let entries = vec![entry(1, 7), entry(2, 8), entry(3, 7), (hex(&[4; 64]), None)];
+
+        assert_eq!(
+            instances_of(&entries, &H256::repeat_byte(7)).unwrap(),
+            vec![AccountId32::from([1; 32]), AccountId32::from([3; 32])]
+        );
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prints information about the interface of a contract with `cargo contract info`.
+
+#[cfg(feature = "extrinsics")]
+pub(crate) mod contract;
+mod events;
+#[cfg(feature = "extrinsics")]
+mod instances;
+mod selectors;
+
+#[cfg(feature = "extrinsics")]
+use self::{contract::ContractCommand, instances::InstancesCommand};
+use self::{events::EventsCommand, selectors::SelectorsCommand};
+use anyhow::Result;
+use colored::Colorize;
+use structopt::StructOpt;
+
+/// Prints information about the interface of a contract.
+#[derive(Debug, StructOpt)]
+pub enum InfoCommand {
+    /// List the constructors and messages with their selectors
+    #[structopt(name = "selectors")]
+    Selectors(SelectorsCommand),
+    /// List the events with their topics and fields
+    #[structopt(name = "events")]
+    Events(EventsCommand),
+    /// Print the code hash, storage deposit and balance of an instantiated contract
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "contract")]
+    Contract(ContractCommand),
+    /// List the contracts instantiated from the code stored under a code hash
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "instances")]
+    Instances(InstancesCommand),
+}
+
+impl InfoCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match self {
+            InfoCommand::Selectors(selectors) => selectors.exec(),
+            InfoCommand::Events(events) => events.exec(),
+            #[cfg(feature = "extrinsics")]
+            InfoCommand::Contract(contract) => contract.exec(),
+            #[cfg(feature = "extrinsics")]
+            InfoCommand::Instances(instances) => instances.exec(),
+        }
+    }
+}
+
+/// Renders `rows` as a table with aligned columns below the bold `header`.
+fn render_table(header: &[&str], rows: &[Vec<String>]) -> String {
+    let mut widths = header.iter().map(|cell| cell.len()).collect::<Vec<_>>();
+    for row in rows {
+        for (width, cell) in widths.iter_mut().zip(row) {
+            *width = (*width).max(cell.len());
+        }
+    }
+    let line = |cells: &[String]| {
+        let cells = cells
+            .iter()
+            .zip(&widths)
+            .map(|(cell, width)| format!("{:width$}", cell, width = width))
+            .collect::<Vec<_>>();
+        format!("{}\n", cells.join("  ").trim_end())
+    };
+
+    let header = header
+        .iter()
+        .map(|cell| cell.to_string())
+        .collect::<Vec<_>>();
+    let mut table = line(&header).bold().to_string();
+    for row in rows {
+        table.push_str(&line(row));
+    }
+    table
+}
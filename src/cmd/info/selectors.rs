@@ -0,0 +1,170 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::render_table;
+use crate::{
+    abi::{ContractAbi, MessageSpec},
+    cmd::metadata::built_metadata_path,
+};
+use anyhow::Result;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Lists the constructors and messages of a contract with their selectors.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "selectors")]
+pub struct SelectorsCommand {
+    /// Path to the Cargo.toml of the contract whose built metadata to read
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Export the selectors in JSON format
+    #[structopt(long)]
+    output_json: bool,
+}
+
+impl SelectorsCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let abi = ContractAbi::load(&path)?;
+        let selectors = selectors(&abi);
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&selectors)?);
+        } else {
+            print!("{}", table(&selectors));
+        }
+        Ok(None)
+    }
+}
+
+/// A constructor or message with its selector.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SelectorInfo {
+    /// Either `constructor` or `message`.
+    pub kind: &'static str,
+    pub label: String,
+    pub selector: String,
+    pub mutates: bool,
+    pub payable: bool,
+    pub args: Vec<ArgInfo>,
+}
+
+/// An argument of a constructor or message.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct ArgInfo {
+    pub name: String,
+    /// The Rust like name of the type, e.g. `Option<AccountId>`.
+    #[serde(rename = "type")]
+    pub ty: String,
+}
+
+/// Returns the constructors and messages of `abi` in the order of the metadata.
+pub fn selectors(abi: &ContractAbi) -> Vec<SelectorInfo> {
+    let info = |kind, spec: &MessageSpec| SelectorInfo {
+        kind,
+        label: spec.label.clone(),
+        selector: spec.selector.to_string(),
+        mutates: spec.mutates,
+        payable: spec.payable,
+        args: spec
+            .args
+            .iter()
+            .map(|arg| ArgInfo {
+                name: arg.label.clone(),
+                ty: abi.types.type_name(arg.ty.id),
+            })
+            .collect(),
+    };
+    abi.constructors
+        .iter()
+        .map(|constructor| info("constructor", constructor))
+        .chain(abi.messages.iter().map(|message| info("message", message)))
+        .collect()
+}
+
+/// Renders the selectors as a table with aligned columns.
+fn table(selectors: &[SelectorInfo]) -> String {
+    let rows = selectors
+        .iter()
+        .map(|info| {
+            let args = info
+                .args
+                .iter()
+                .map(|arg| format!("{}: {}", arg.name, arg.ty))
+                .collect::<Vec<_>>();
+            vec![
+                info.kind.to_string(),
+                info.label.clone(),
+                info.selector.clone(),
+                if info.mutates { "mutates" } else { "read-only" }.to_string(),
+                if info.payable { "yes" } else { "no" }.to_string(),
+                args.join(", "),
+            ]
+        })
+        .collect::<Vec<_>>();
+    render_table(
+        &[
+            "KIND",
+            "LABEL",
+            "SELECTOR",
+            "MUTABILITY",
+            "PAYABLE",
+            "ARGUMENTS",
+        ],
+        &rows,
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn lists_selectors() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let selectors = selectors(&abi);
+
+        assert_eq!(
+            serde_json::to_value(&selectors[2]).unwrap(),
+            serde_json::json!({
+                "kind": "message",
+                "label": "Ledger::balance_of",
+                "selector": "0x0f755a56",
+                "mutates": false,
+                "payable": false,
+                "args": [{ "name": "owner", "type": "AccountId" }],
+            })
+        );
+
+        colored::control::set_override(false);
+        assert_eq!(
+            table(&selectors),
+            "\
+KIND         LABEL               SELECTOR    MUTABILITY  PAYABLE  ARGUMENTS
+constructor  new                 0x9bae9d5e  mutates     yes      limit: u128
+message      deposit             0x2d10c9bd  mutates     yes
+message      Ledger::balance_of  0x0f755a56  read-only   no       owner: AccountId
+message      withdraw            0x410fcc9d  mutates     no       value: u128
+"
+        );
+    }
+}
@@ -14,38 +14,469 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+use std::{path::PathBuf, str::FromStr};
+
 use anyhow::{Context, Result};
-use subxt::{balances::Balances, contracts::*, system::System, ClientBuilder, DefaultNodeRuntime};
+use sp_core::{crypto::AccountId32, hashing::blake2_256, H256};
+use structopt::StructOpt;
+use subxt::{
+    balances::Balances,
+    contracts::*,
+    sp_runtime::codec::{Compact, Encode},
+    system::System,
+    Call, Client, DefaultNodeRuntime, Encoded,
+};
+
+use super::{
+    chain::AccountId,
+    confirm::Summary,
+    deployments::{self, Deployed},
+    dry_run::{self, Costs, Weight},
+    encode::encode_args,
+    extrinsic_output::{load_abi, ExtrinsicOutput},
+    gas::{AutoRetryOpts, GasOpts, Limits},
+    offline,
+    submit::{self, SubmitError, Submitted},
+    token::{self, Balance},
+};
+use crate::{
+    abi::{decode_hex, ContractAbi},
+    parse_code_hash, ExtrinsicOpts, HexData,
+};
+
+/// Instantiates a contract from code already uploaded to the chain.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "instantiate")]
+pub struct InstantiateCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// Transfers an initial balance to the instantiated contract: plancks, or an amount of the
+    /// token of the chain, e.g. `1.5DOT`
+    #[structopt(name = "endowment", long)]
+    endowment: Option<Balance>,
+    /// Transfers this initial balance in plancks instead of `--endowment`
+    #[structopt(long, conflicts_with = "endowment")]
+    endowment_raw: Option<u128>,
+    #[structopt(flatten)]
+    gas: GasOpts,
+    /// Maximum balance to be charged for the storage of the instantiated contract, unbounded
+    /// if not given: plancks, or an amount of the token of the chain, e.g. `0.1DOT`
+    #[structopt(long)]
+    storage_deposit_limit: Option<Balance>,
+    #[structopt(flatten)]
+    auto_retry: AutoRetryOpts,
+    /// The hash of the smart contract code already uploaded to the chain
+    #[structopt(long, parse(try_from_str = parse_code_hash))]
+    code_hash: H256,
+    /// Hex encoded data to call a contract constructor
+    #[structopt(long, required_unless = "constructor", conflicts_with = "constructor")]
+    data: Option<HexData>,
+    /// The label of the constructor to call, whose arguments are encoded with the metadata
+    #[structopt(long)]
+    constructor: Option<String>,
+    /// The arguments of the constructor, as JSON or as plain strings
+    #[structopt(long, requires = "constructor")]
+    args: Vec<String>,
+    /// A JSON file with the arguments of the constructor, keyed by their names
+    #[structopt(
+        long,
+        parse(from_os_str),
+        requires = "constructor",
+        conflicts_with = "args"
+    )]
+    args_file: Option<PathBuf>,
+    /// The hex encoded salt of the contract address, or `random` for 32 random bytes, to
+    /// instantiate the same code with the same arguments more than once
+    #[structopt(long)]
+    salt: Option<Salt>,
+    /// Print the address the contract will be instantiated at, without submitting anything
+    #[structopt(long, conflicts_with_all = &["dry-run", "output-json"])]
+    predict_address: bool,
+    /// Execute the instantiation on the state of the node without submitting it, and report
+    /// its outcome
+    #[structopt(long)]
+    dry_run: bool,
+    /// Print the contract account, code hash, events and block of the extrinsic as JSON
+    #[structopt(long, conflicts_with = "dry-run")]
+    output_json: bool,
+    /// Path to the Cargo.toml of the contract whose metadata encodes the arguments and decodes
+    /// the events
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The name to record the contract under in `deployments/<network>.json`, the name of the
+    /// contract of the metadata by default
+    #[structopt(long)]
+    name: Option<String>,
+}
+
+impl InstantiateCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?;
+        let endowment = match (self.endowment_raw, &self.endowment) {
+            (Some(endowment), _) => endowment,
+            (None, Some(endowment)) => endowment.plancks(&self.extrinsic_opts.node.url()?)?,
+            (None, None) => 0,
+        };
+        let storage_deposit_limit = match &self.storage_deposit_limit {
+            Some(limit) => Some(limit.plancks(&self.extrinsic_opts.node.url()?)?),
+            None => None,
+        };
+        let data = match (&self.data, &self.constructor) {
+            (Some(data), _) => data.clone(),
+            (None, label) => {
+                let abi = abi
+                    .as_ref()
+                    .context("Encoding the arguments requires the metadata of the contract")?;
+                let label = label.as_deref().context("Missing `--constructor`")?;
+                let spec = abi.constructor(label)?;
+                HexData(encode_args(abi, spec, &self.args, self.args_file.as_ref())?)
+            }
+        };
+        let salt = self.salt.as_ref().map(|salt| &salt.bytes[..]);
+        if self.predict_address {
+            let deployer = submit::origin(&self.extrinsic_opts)?;
+            let address = contract_address(
+                &deployer,
+                &self.code_hash,
+                &data.0,
+                salt.unwrap_or_default(),
+            );
+            return Ok(Some(format!(
+                "Contract account: {}{}",
+                address,
+                self.display_random_salt()
+            )));
+        }
+        // the storage deposit and the required gas are only reported by a dry run, which also
+        // explains why the instantiation fails, and is the outcome of `--dry-run`
+        let dry_run = dry_run::instantiate(
+            &self.extrinsic_opts,
+            endowment,
+            self.gas.dry_run_limit(),
+            storage_deposit_limit,
+            self.code_hash,
+            &data.0,
+            salt.unwrap_or_default(),
+        );
+        if self.dry_run {
+            let result = dry_run?;
+            let limits = Limits {
+                gas: self.gas.limit(Some(result.required_gas())),
+                storage_deposit: storage_deposit_limit,
+            };
+            let costs = Costs::query(&self.extrinsic_opts, |cli| {
+                self.encode_call(cli, endowment, &data.0, salt, limits)
+            })?;
+            print!("{}", dry_run::render_instantiate(&result, &costs));
+            return match &result.result {
+                Ok(value) if !value.result.reverted() => Ok(None),
+                _ => Err(SubmitError::DryRunFailed.into()),
+            };
+        }
+
+        dry_run::warn_unbounded_deposit(storage_deposit_limit);
+        let (storage_deposit, required_gas, failure) = match dry_run {
+            Ok(result) => {
+                let failure = match &result.result {
+                    Ok(value) if !value.result.reverted() => None,
+                    _ => Some(dry_run::render_instantiate(&result, &Costs::default())),
+                };
+                (result.storage_deposit, Some(result.required_gas()), failure)
+            }
+            Err(err) => {
+                log::debug!("Dry run before the instantiation failed: {:?}", err);
+                (None, None, None)
+            }
+        };
+        if let Some(storage_deposit) = &storage_deposit {
+            storage_deposit.check_limit(storage_deposit_limit)?;
+        }
+        let limits = Limits {
+            gas: self.gas.limit(required_gas),
+            storage_deposit: storage_deposit_limit,
+        };
+        if self.extrinsic_opts.offline.is_some() {
+            return offline::write_unsigned(&self.extrinsic_opts, |cli| {
+                self.encode_call(cli, endowment, &data.0, salt, limits)
+            })
+            .map(Some);
+        }
+
+        Summary::new("Instantiate the code")
+            .line("Code hash", format!("{:?}", self.code_hash))
+            .input(
+                "Constructor",
+                abi.as_ref().map(|abi| (&abi.types, &abi.constructors[..])),
+                &data.0,
+            )
+            .balance("Value", endowment)
+            .line("Gas limit", limits.gas)
+            .storage_deposit(storage_deposit)
+            .confirm(&self.extrinsic_opts, |cli| {
+                self.encode_call(cli, endowment, &data.0, salt, limits)
+            })?;
+        let input = dry_run::hex(&data.0);
+        let (submitted, limits) = self
+            .auto_retry
+            .submit(
+                limits,
+                || {
+                    dry_run::instantiate(
+                        &self.extrinsic_opts,
+                        endowment,
+                        self.gas.dry_run_limit(),
+                        None,
+                        self.code_hash,
+                        &data.0,
+                        salt.unwrap_or_default(),
+                    )
+                    .ok()
+                    .map(|result| result.required_limits())
+                },
+                |limits| {
+                    execute_instantiate(
+                        &self.extrinsic_opts,
+                        endowment,
+                        limits.gas,
+                        limits.storage_deposit,
+                        self.code_hash,
+                        data.clone(),
+                        salt,
+                        abi.as_ref(),
+                    )
+                },
+            )
+            .map_err(|err| submit::explain_failure(err, failure.as_deref()))?;
+        let (contract_account, output) = match submitted {
+            Submitted::Executed(executed) => executed,
+            Submitted::Pending(pending) => return Ok(Some(pending.to_string())),
+        };
+        let output = ExtrinsicOutput {
+            storage_deposit,
+            gas_limit: Some(limits.gas),
+            ..output
+        };
+        let recorded = match self
+            .name
+            .clone()
+            .or_else(|| abi.as_ref().and_then(|abi| abi.name.clone()))
+        {
+            Some(name) => {
+                let deployed = Deployed {
+                    name: name.clone(),
+                    address: contract_account.to_string(),
+                    code_hash: self.code_hash,
+                    constructor: self.constructor.clone(),
+                    args: self.args.clone(),
+                    data: input,
+                    block: output.block_hash,
+                    deployer: submit::origin(&self.extrinsic_opts)?.to_string(),
+                };
+                deployments::record(&self.extrinsic_opts, deployed)?
+                    .map(|path| format!("\n\tRecorded as `{}` in {}", name, path.display()))
+            }
+            None => None,
+        };
+        if self.output_json {
+            return Ok(Some(output.to_json()?));
+        }
+        // the token is only needed to show the storage deposit
+        let token = match output.storage_deposit {
+            Some(_) => {
+                async_std::task::block_on(token::fetch_or_none(&self.extrinsic_opts.node.url()?))
+            }
+            None => None,
+        };
+        Ok(Some(format!(
+            "Contract account: {:?}{}{}{}{}{}{}",
+            contract_account,
+            self.display_random_salt(),
+            recorded.unwrap_or_default(),
+            output.display_explorer_links(&self.extrinsic_opts)?,
+            output.display_gas(),
+            output.display_storage_deposit(token.as_ref()),
+            output.display_contract_events()
+        )))
+    }
+
+    /// Displays the salt of `--salt random`, which is needed to instantiate the contract at the
+    /// same address again.
+    fn display_random_salt(&self) -> String {
+        match &self.salt {
+            Some(salt) if salt.random => format!("\n\tSalt: 0x{}", hex::encode(&salt.bytes)),
+            _ => String::new(),
+        }
+    }
+
+    /// Encodes the Call instantiating the contract with `endowment`, `data`, `salt` and
+    /// `limits`.
+    fn encode_call(
+        &self,
+        cli: &Client<DefaultNodeRuntime>,
+        endowment: u128,
+        data: &[u8],
+        salt: Option<&[u8]>,
+        limits: Limits,
+    ) -> Result<Encoded, subxt::Error> {
+        let gas_limit = limits.gas;
+        match (limits.storage_deposit, salt, gas_limit.proof_size) {
+            (None, None, None) => cli.encode(InstantiateCall {
+                endowment,
+                gas_limit: gas_limit.ref_time,
+                code_hash: &self.code_hash,
+                data,
+            }),
+            (storage_deposit_limit, salt, _) => cli.encode(InstantiateWithLimitCall {
+                value: endowment,
+                gas_limit,
+                storage_deposit_limit,
+                code_hash: &self.code_hash,
+                data,
+                salt: salt.unwrap_or_default(),
+            }),
+        }
+    }
+}
+
+/// The salt of the address of an instantiated contract.
+#[derive(Debug)]
+struct Salt {
+    bytes: Vec<u8>,
+    /// `true` if the salt was generated for `--salt random`.
+    random: bool,
+}
+
+impl FromStr for Salt {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        if input == "random" {
+            return Ok(Salt {
+                bytes: rand::random::<[u8; 32]>().to_vec(),
+                random: true,
+            });
+        }
+        Ok(Salt {
+            bytes: decode_hex(input).context("Expected a hex encoded salt or `random`")?,
+            random: false,
+        })
+    }
+}
+
+/// Returns the address of the contract instantiated by `deployer` from the code `code_hash`,
+/// with the constructor `data` and the `salt`, as derived by the contracts pallet: chains with
+/// Ethereum addresses take the first 20 bytes of the hash.
+pub(crate) fn contract_address(
+    deployer: &AccountId,
+    code_hash: &H256,
+    data: &[u8],
+    salt: &[u8],
+) -> AccountId {
+    let entropy = (b"contract_addr_v1", deployer, code_hash, data, salt).using_encoded(blake2_256);
+    match deployer {
+        AccountId::Substrate(_) => AccountId32::from(entropy).into(),
+        AccountId::Ethereum(_) => {
+            AccountId::from_bytes(&entropy[..20]).expect("20 bytes are an Ethereum address")
+        }
+    }
+}
+
+/// The `Contracts::instantiate` Call of versions of the contracts pallet with a storage
+/// deposit limit and a salt, and of the ones metering the proof size, which `subxt` does not
+/// provide.
+pub(super) struct InstantiateWithLimitCall<'a> {
+    pub value: u128,
+    pub gas_limit: Weight,
+    pub storage_deposit_limit: Option<u128>,
+    pub code_hash: &'a H256,
+    pub data: &'a [u8],
+    pub salt: &'a [u8],
+}
 
-use crate::{ExtrinsicOpts, HexData};
+impl Encode for InstantiateWithLimitCall<'_> {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = Compact(self.value).encode();
+        self.gas_limit.encode_to(&mut encoded);
+        self.storage_deposit_limit
+            .map(Compact)
+            .encode_to(&mut encoded);
+        self.code_hash.encode_to(&mut encoded);
+        self.data.encode_to(&mut encoded);
+        self.salt.encode_to(&mut encoded);
+        encoded
+    }
+}
+
+impl Call<DefaultNodeRuntime> for InstantiateWithLimitCall<'_> {
+    const MODULE: &'static str = dry_run::CONTRACTS;
+    const FUNCTION: &'static str = "instantiate";
+}
 
 /// Instantiate a contract stored at the supplied code hash.
-/// Returns the account id of the instantiated contract if successful.
+/// Returns the account id of the instantiated contract and the outcome of the extrinsic if
+/// successful.
 ///
 /// Creates an extrinsic with the `Contracts::instantiate` Call, submits via RPC, then waits for
-/// the `ContractsEvent::Instantiated` event.
+/// the `ContractsEvent::Instantiated` event. The events emitted by the constructor are decoded
+/// with `abi`, if given. With a `storage_deposit_limit`, a `salt` or the proof size in the
+/// `gas_limit` the Call of versions of the contracts pallet which take them is submitted. With
+/// `--multisig` the instantiation may await the approval of other signatories instead.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn execute_instantiate(
     extrinsic_opts: &ExtrinsicOpts,
     endowment: <DefaultNodeRuntime as Balances>::Balance,
-    gas_limit: u64,
+    gas_limit: Weight,
+    storage_deposit_limit: Option<u128>,
     code_hash: <DefaultNodeRuntime as System>::Hash,
     data: HexData,
-) -> Result<<DefaultNodeRuntime as System>::AccountId> {
+    salt: Option<&[u8]>,
+    abi: Option<&ContractAbi>,
+) -> Result<Submitted<(AccountId, ExtrinsicOutput)>> {
     async_std::task::block_on(async move {
-        let cli = ClientBuilder::<DefaultNodeRuntime>::new()
-            .set_url(&extrinsic_opts.url.to_string())
-            .build()
-            .await?;
-        let signer = extrinsic_opts.signer()?;
-
-        let events = cli
-            .instantiate_and_watch(&signer, endowment, gas_limit, &code_hash, &data.0)
-            .await?;
-        let instantiated = events
-            .instantiated()?
+        let cli = submit::client(extrinsic_opts).await?;
+        let submitted = match (storage_deposit_limit, salt, gas_limit.proof_size) {
+            (None, None, None) => {
+                let call = InstantiateCall {
+                    endowment,
+                    gas_limit: gas_limit.ref_time,
+                    code_hash: &code_hash,
+                    data: &data.0,
+                };
+                submit::watch(&cli, extrinsic_opts, call).await?
+            }
+            (storage_deposit_limit, salt, _) => {
+                let call = InstantiateWithLimitCall {
+                    value: endowment,
+                    gas_limit,
+                    storage_deposit_limit,
+                    code_hash: &code_hash,
+                    data: &data.0,
+                    salt: salt.unwrap_or_default(),
+                };
+                submit::watch(&cli, extrinsic_opts, call).await?
+            }
+        };
+        let events = match submitted {
+            Submitted::Executed(events) => events,
+            Submitted::Pending(pending) => return Ok(Submitted::Pending(pending)),
+        };
+        // the event holds the deployer and the contract, accounts of the size of the origin's,
+        // which `subxt` would decode as 32 byte account ids
+        let size = submit::origin(extrinsic_opts)?.as_bytes().len();
+        let contract = events
+            .find_event_raw(dry_run::CONTRACTS, "Instantiated")
+            .and_then(|event| AccountId::from_bytes(event.data.get(size..2 * size)?))
             .context("Failed to find Instantiated event")?;
 
-        Ok(instantiated.contract)
+        let output = ExtrinsicOutput {
+            contract: Some(contract.to_string()),
+            code_hash: Some(code_hash),
+            ..ExtrinsicOutput::new(&events, abi.map(|abi| (&contract, abi)))
+        };
+        Ok(Submitted::Executed((contract, output)))
     })
 }
 
@@ -53,8 +484,14 @@ pub(crate) fn execute_instantiate(
 mod tests {
     use std::{fs, io::Write};
 
-    use crate::{cmd::deploy::execute_deploy, util::tests::with_tmp_dir, ExtrinsicOpts, HexData};
+    use super::{contract_address, Weight};
+    use crate::{
+        cmd::{deploy::execute_deploy, AccountId},
+        util::tests::with_tmp_dir,
+        ExtrinsicOpts, HexData,
+    };
     use assert_matches::assert_matches;
+    use sp_core::{crypto::AccountId32, hashing::blake2_256, H256};
 
     const CONTRACT: &str = r#"
 (module
@@ -75,24 +512,112 @@ mod tests {
 
             let url = url::Url::parse("ws://localhost:9944").unwrap();
             let extrinsic_opts = ExtrinsicOpts {
-                url,
-                suri: "//Alice".into(),
+                node: crate::NodeOpts {
+                    url: Some(url),
+                    network: None,
+                    timeout: None,
+                },
+                suri: Some("//Alice".into()),
+                account: None,
                 password: None,
+                scheme: crate::Scheme::Sr25519,
+                signer_kind: None,
+                signer_cmd: None,
+                offline: None,
+                from: None,
+                nonce: None,
+                track_nonce: false,
+                tip: 0,
+                fee_asset: None,
+                era: None,
+                immortal: false,
+                wait_for: crate::WaitFor::Finalized,
+                at_block: None,
+                after: None,
+                skip_confirm: true,
+                fork_from: None,
+                install_chopsticks: false,
+                explorer: None,
+                retries: 3,
+                retry_backoff: 2,
+                signed_extensions: Vec::new(),
+                proxy: None,
+                multisig: Vec::new(),
+                threshold: None,
+                multisig_max_weight: 10_000_000_000,
+                decrypted_suri: Default::default(),
+                scheduled: Default::default(),
             };
-            let code_hash =
-                execute_deploy(&extrinsic_opts, Some(&wasm_path)).expect("Deploy should succeed");
+            let code_hash = execute_deploy(&extrinsic_opts, Some(&wasm_path))
+                .and_then(|deployed| deployed.executed())
+                .expect("Deploy should succeed");
 
-            let gas_limit = 500_000_000;
+            let gas_limit = Weight {
+                ref_time: 500_000_000,
+                proof_size: None,
+            };
             let result = super::execute_instantiate(
                 &extrinsic_opts,
                 100000000000000,
                 gas_limit,
+                None,
                 code_hash,
                 HexData::default(),
+                None,
+                None,
             );
 
             assert_matches!(result, Ok(_));
             Ok(())
         })
     }
+
+    #[test]
+    fn derives_contract_addresses() {
+        let deployer = AccountId32::from([1; 32]).into();
+        let code_hash = H256::repeat_byte(2);
+        let address = contract_address(&deployer, &code_hash, &[3, 4], &[5]);
+
+        let mut entropy = b"contract_addr_v1".to_vec();
+        entropy.extend(&[1; 32]);
+        entropy.extend(&[2; 32]);
+        // the data and the salt are prefixed with their length
+        entropy.extend(&[8, 3, 4, 4, 5]);
+        assert_eq!(address, AccountId32::from(blake2_256(&entropy)).into());
+        assert_ne!(
+            address,
+            contract_address(&deployer, &code_hash, &[3, 4], &[6])
+        );
+    }
+
+    #[test]
+    fn derives_the_contract_addresses_of_ethereum_deployers() {
+        let deployer = AccountId::Ethereum([1; 20]);
+        let code_hash = H256::repeat_byte(2);
+        let address = contract_address(&deployer, &code_hash, &[], &[]);
+
+        let mut entropy = b"contract_addr_v1".to_vec();
+        entropy.extend(&[1; 20]);
+        entropy.extend(&[2; 32]);
+        entropy.extend(&[0, 0]);
+        assert_eq!(address.as_bytes(), &blake2_256(&entropy)[..20]);
+    }
+
+    #[test]
+    fn derives_the_contract_addresses_of_the_sandbox() {
+        let deployer = AccountId32::from([1; 32]).into();
+        let code_hash = H256::repeat_byte(2);
+        for (data, salt) in &[
+            (&[][..], &[][..]),
+            (&[3, 4], &[5]),
+            (&[0x9b; 100], &[7; 32]),
+        ] {
+            let sandbox_address =
+                contract_sandbox::contract_address(&[1; 32], &[2; 32], data, salt);
+            assert_eq!(
+                contract_address(&deployer, &code_hash, data, salt),
+                AccountId32::from(sandbox_address).into()
+            );
+        }
+    }
 }
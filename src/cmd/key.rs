@@ -0,0 +1,390 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates, derives and inspects keys with `cargo contract key`, so that no separate
+//! `subkey` install is needed to manage the accounts of contract developers.
+
+use std::convert::TryFrom;
+
+use anyhow::Result;
+use blake2::{Blake2b, Digest};
+use sp_core::{
+    crypto::{AccountId32, Pair},
+    ecdsa, sr25519,
+};
+use structopt::StructOpt;
+use subxt::sp_runtime::{traits::IdentifyAccount, MultiSigner};
+
+use crate::Scheme;
+
+/// The SS58 prefixes addresses are printed with when no `--prefix` is given.
+pub(super) const NETWORKS: &[(u16, &str)] = &[
+    (0, "polkadot"),
+    (2, "kusama"),
+    (5, "astar"),
+    (42, "substrate"),
+];
+
+/// Generates, derives and inspects keys and their addresses.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "key")]
+pub struct KeyCommand {
+    #[structopt(subcommand)]
+    cmd: KeySubcommand,
+}
+
+#[derive(Debug, StructOpt)]
+enum KeySubcommand {
+    /// Generate a new keypair with a 12 word secret phrase
+    #[structopt(name = "generate")]
+    Generate {
+        #[structopt(flatten)]
+        key: KeyOpts,
+    },
+    /// Print the public key, account id and addresses of a secret URI, an SS58 address or
+    /// a hex encoded account id
+    #[structopt(name = "inspect")]
+    Inspect {
+        input: String,
+        #[structopt(flatten)]
+        key: KeyOpts,
+    },
+    /// Derive the keypair of a secret URI along a path of hard `//` and soft `/` junctions,
+    /// e.g. `//staking/0`
+    #[structopt(name = "derive")]
+    Derive {
+        suri: String,
+        path: String,
+        #[structopt(flatten)]
+        key: KeyOpts,
+    },
+    /// Generate keypairs until the address of one starts with `pattern` after the characters
+    /// all addresses of the network start with
+    #[structopt(name = "vanity")]
+    Vanity {
+        pattern: String,
+        /// The number of keypairs to generate before giving up
+        #[structopt(long, default_value = "1000000")]
+        attempts: u64,
+        #[structopt(flatten)]
+        key: KeyOpts,
+    },
+}
+
+#[derive(Debug, StructOpt)]
+struct KeyOpts {
+    /// The signature scheme of the keys
+    #[structopt(
+        long,
+        default_value = "sr25519",
+        value_name = "sr25519 | ecdsa",
+        possible_values = &["sr25519", "ecdsa"]
+    )]
+    scheme: Scheme,
+    /// The SS58 prefix of the network to print the addresses for, instead of the prefixes
+    /// of Polkadot, Kusama, Astar and Substrate. May be given several times; `vanity` uses
+    /// the first one
+    #[structopt(long = "prefix", number_of_values = 1, parse(try_from_str = parse_prefix))]
+    prefixes: Vec<u16>,
+}
+
+impl KeyOpts {
+    fn prefixes(&self) -> Vec<u16> {
+        if self.prefixes.is_empty() {
+            NETWORKS.iter().map(|(prefix, _)| *prefix).collect()
+        } else {
+            self.prefixes.clone()
+        }
+    }
+}
+
+impl KeyCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match &self.cmd {
+            KeySubcommand::Generate { key } => {
+                let (suri, public, account_id) = match key.scheme {
+                    Scheme::Sr25519 => generate::<sr25519::Pair>(),
+                    Scheme::Ecdsa | Scheme::Ethereum => generate::<ecdsa::Pair>(),
+                };
+                print_key(Some(("Secret phrase", &suri)), &public, &account_id, key);
+            }
+            KeySubcommand::Inspect { input, key } => {
+                if let Ok((prefix, account_id)) = from_ss58(input) {
+                    println!("Network:          {}", network_name(prefix));
+                    print_key(None, &[], &account_id, key);
+                } else if let Some(account_id) = parse_hex_account_id(input) {
+                    print_key(None, &[], &account_id, key);
+                } else {
+                    let (public, account_id) = inspect(input, key.scheme)?;
+                    print_key(Some(("Secret URI", input)), &public, &account_id, key);
+                }
+            }
+            KeySubcommand::Derive { suri, path, key } => {
+                if !path.starts_with('/') {
+                    anyhow::bail!(
+                        "The derivation path `{}` should start with `//` or `/`",
+                        path
+                    );
+                }
+                let derived = format!("{}{}", suri, path);
+                let (public, account_id) = inspect(&derived, key.scheme)?;
+                print_key(Some(("Secret URI", &derived)), &public, &account_id, key);
+            }
+            KeySubcommand::Vanity {
+                pattern,
+                attempts,
+                key,
+            } => {
+                if let Some(c) = pattern.chars().find(|c| !is_base58(*c)) {
+                    anyhow::bail!("`{}` never occurs in SS58 addresses", c);
+                }
+                let prefix = key.prefixes()[0];
+                let found = match key.scheme {
+                    Scheme::Sr25519 => vanity::<sr25519::Pair>(pattern, prefix, *attempts),
+                    Scheme::Ecdsa | Scheme::Ethereum => {
+                        vanity::<ecdsa::Pair>(pattern, prefix, *attempts)
+                    }
+                };
+                match found {
+                    Some((seed, public, account_id)) => {
+                        print_key(Some(("Secret seed", &seed)), &public, &account_id, key)
+                    }
+                    None => anyhow::bail!(
+                        "No address starting with `{}` in {} attempts",
+                        pattern,
+                        attempts
+                    ),
+                }
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Prints the secret, the public key, the account id and the addresses of a key.
+fn print_key(secret: Option<(&str, &str)>, public: &[u8], account_id: &AccountId32, key: &KeyOpts) {
+    if let Some((label, secret)) = secret {
+        println!("{:<18}{}", format!("{}:", label), secret);
+    }
+    if !public.is_empty() {
+        println!("Public key (hex):  0x{}", hex::encode(public));
+    }
+    println!("Account ID:        0x{}", hex::encode(account_id));
+    for prefix in key.prefixes() {
+        println!(
+            "{:<18}{}",
+            format!("SS58 ({}):", network_name(prefix)),
+            to_ss58(prefix, account_id.as_ref())
+        );
+    }
+}
+
+/// Generates a keypair with a secret phrase.
+fn generate<P: Pair>() -> (String, Vec<u8>, AccountId32)
+where
+    P::Public: Into<MultiSigner>,
+{
+    let (pair, phrase, _) = P::generate_with_phrase(None);
+    let (public, account_id) = public_and_account_id(&pair);
+    (phrase, public, account_id)
+}
+
+/// Returns the public key and the account id of the keypair of `suri`.
+fn inspect(suri: &str, scheme: Scheme) -> Result<(Vec<u8>, AccountId32)> {
+    fn from_suri<P: Pair>(suri: &str) -> Result<(Vec<u8>, AccountId32)>
+    where
+        P::Public: Into<MultiSigner>,
+    {
+        let pair =
+            P::from_string(suri, None).map_err(|_| anyhow::anyhow!("Secret string error"))?;
+        Ok(public_and_account_id(&pair))
+    }
+    match scheme {
+        Scheme::Sr25519 => from_suri::<sr25519::Pair>(suri),
+        Scheme::Ecdsa | Scheme::Ethereum => from_suri::<ecdsa::Pair>(suri),
+    }
+}
+
+/// Generates keypairs from random seeds until the address of one with the SS58 `prefix`
+/// starts with `pattern` after the characters all addresses of the network start with.
+///
+/// Returns the hex encoded seed, the public key and the account id of the keypair.
+fn vanity<P: Pair>(
+    pattern: &str,
+    prefix: u16,
+    attempts: u64,
+) -> Option<(String, Vec<u8>, AccountId32)>
+where
+    P::Public: Into<MultiSigner>,
+{
+    let network_chars = network_chars(prefix);
+    (0..attempts).find_map(|_| {
+        let (pair, seed) = P::generate();
+        let (public, account_id) = public_and_account_id(&pair);
+        let address = to_ss58(prefix, account_id.as_ref());
+        if address[network_chars..].starts_with(pattern) {
+            Some((
+                format!("0x{}", hex::encode(seed.as_ref())),
+                public,
+                account_id,
+            ))
+        } else {
+            None
+        }
+    })
+}
+
+fn public_and_account_id<P: Pair>(pair: &P) -> (Vec<u8>, AccountId32)
+where
+    P::Public: Into<MultiSigner>,
+{
+    let public = pair.public();
+    let raw = public.as_ref().to_vec();
+    let signer: MultiSigner = public.into();
+    (raw, signer.into_account())
+}
+
+pub(super) fn network_name(prefix: u16) -> String {
+    NETWORKS
+        .iter()
+        .find(|(known, _)| *known == prefix)
+        .map_or_else(|| prefix.to_string(), |(_, name)| name.to_string())
+}
+
+pub(super) fn parse_prefix(input: &str) -> Result<u16> {
+    let prefix = input.parse::<u16>()?;
+    if prefix > 16_383 {
+        anyhow::bail!("SS58 prefixes are at most 16383")
+    }
+    Ok(prefix)
+}
+
+fn parse_hex_account_id(input: &str) -> Option<AccountId32> {
+    let bytes = hex::decode(input.strip_prefix("0x")?).ok()?;
+    let bytes = <[u8; 32]>::try_from(bytes.as_slice()).ok()?;
+    Some(AccountId32::from(bytes))
+}
+
+fn is_base58(c: char) -> bool {
+    c.is_ascii_alphanumeric() && !matches!(c, '0' | 'O' | 'I' | 'l')
+}
+
+/// The SS58 checksum of `data`: the first two bytes of its blake2b-512 hash, prefixed with
+/// `SS58PRE`.
+fn checksum(data: &[u8]) -> [u8; 2] {
+    let hash = Blake2b::new().chain(b"SS58PRE").chain(data).finalize();
+    [hash[0], hash[1]]
+}
+
+/// Encodes the account id with the SS58 `prefix`, which takes one byte below 64 and two
+/// bytes up to 16383.
+pub(super) fn to_ss58(prefix: u16, account_id: &[u8]) -> String {
+    let mut data = prefix_bytes(prefix);
+    data.extend_from_slice(account_id);
+    let checksum = checksum(&data);
+    data.extend_from_slice(&checksum);
+    bs58::encode(data).into_string()
+}
+
+/// The bytes the SS58 `prefix` is encoded with.
+fn prefix_bytes(prefix: u16) -> Vec<u8> {
+    if prefix < 64 {
+        vec![prefix as u8]
+    } else {
+        vec![
+            ((prefix & 0b1111_1100) >> 2) as u8 | 0b0100_0000,
+            (prefix >> 8) as u8 | ((prefix & 0b11) << 6) as u8,
+        ]
+    }
+}
+
+/// The number of leading characters all addresses with the SS58 `prefix` share, e.g. the `5`
+/// of Substrate, but none of Kusama. They are those shared by the addresses of the lowest and
+/// the highest account id and checksum.
+fn network_chars(prefix: u16) -> usize {
+    let address = |byte| {
+        let mut data = prefix_bytes(prefix);
+        data.extend_from_slice(&[byte; 32 + 2]);
+        bs58::encode(data).into_string()
+    };
+    let (lowest, highest) = (address(0), address(0xff));
+    lowest
+        .chars()
+        .zip(highest.chars())
+        .take_while(|(low, high)| low == high)
+        .count()
+}
+
+/// Decodes an SS58 address into its prefix and account id.
+pub(super) fn from_ss58(address: &str) -> Result<(u16, AccountId32)> {
+    let data = bs58::decode(address).into_vec()?;
+    let (prefix, prefix_len) = match data.first() {
+        Some(first @ 0..=63) => (u16::from(*first), 1),
+        Some(first @ 64..=127) if data.len() > 1 => {
+            let lower = (first << 2) | (data[1] >> 6);
+            let upper = data[1] & 0b0011_1111;
+            (u16::from(lower) | (u16::from(upper) << 8), 2)
+        }
+        _ => anyhow::bail!("Invalid SS58 prefix"),
+    };
+    if data.len() != prefix_len + 32 + 2 {
+        anyhow::bail!("SS58 addresses of accounts encode 32 bytes")
+    }
+    let (body, checksum_bytes) = data.split_at(prefix_len + 32);
+    if checksum(body) != checksum_bytes {
+        anyhow::bail!("Invalid SS58 checksum")
+    }
+    let account_id = <[u8; 32]>::try_from(&body[prefix_len..])?;
+    Ok((prefix, AccountId32::from(account_id)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_addresses_with_network_prefixes() {
+        let alice = hex::decode("d43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d")
+            .unwrap();
+        assert_eq!(
+            to_ss58(42, &alice),
+            "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY"
+        );
+        assert_eq!(
+            to_ss58(0, &alice),
+            "15oF4uVJwmo4TdGW7VfQxNLavjCXviqxT9S1MgbjMNHr6Sp5"
+        );
+        for prefix in [2, 5, 42, 64, 1284, 16_383].iter() {
+            let (decoded, account_id) = from_ss58(&to_ss58(*prefix, &alice)).unwrap();
+            assert_eq!(decoded, *prefix);
+            assert_eq!(AsRef::<[u8]>::as_ref(&account_id), &alice[..]);
+        }
+        assert!(from_ss58("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQZ").is_err());
+    }
+
+    #[test]
+    fn counts_the_leading_characters_of_networks() {
+        // Polkadot addresses start with `1`, Kusama ones with one of `CDEFGHJ`
+        assert_eq!(network_chars(0), 1);
+        assert_eq!(network_chars(2), 0);
+        assert_eq!(network_chars(42), 1);
+        for prefix in [0, 2, 5, 42, 1284, 16_383].iter() {
+            let chars = network_chars(*prefix);
+            let address = to_ss58(*prefix, &[7; 32]);
+            assert_eq!(address[..chars], to_ss58(*prefix, &[0xee; 32])[..chars]);
+        }
+    }
+}
@@ -0,0 +1,162 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signs extrinsics with the Polkadot app of a Ledger hardware wallet, so that no secret key
+//! is needed on the deploying machine.
+
+use anyhow::Result;
+use ledger_transport::APDUCommand;
+use ledger_transport_hid::{hidapi::HidApi, TransportNativeHID};
+use sp_core::crypto::AccountId32;
+use subxt::sp_runtime::{codec::Decode, MultiSignature};
+
+use super::{chain::AccountId, signing::Signer};
+
+/// The instruction class of the Polkadot app.
+const CLA: u8 = 0x90;
+const INS_GET_ADDRESS: u8 = 0x01;
+const INS_SIGN: u8 = 0x02;
+/// The sr25519 signature scheme of the instructions.
+const SR25519: u8 = 0x01;
+/// The BIP44 coin type of Polkadot.
+const COIN_TYPE: u32 = 354;
+/// The maximum data of an APDU chunk sent to the app.
+const CHUNK_SIZE: usize = 250;
+
+/// Signs extrinsics with the sr25519 account `m/44'/354'/0'/0'/0'` of the Polkadot app.
+pub(crate) struct LedgerSigner {
+    transport: TransportNativeHID,
+    account_id: AccountId,
+}
+
+impl LedgerSigner {
+    /// Connects to the first Ledger found, which has to be unlocked with the Polkadot app
+    /// open, and reads the account to sign with.
+    pub fn connect() -> Result<Self> {
+        let api = HidApi::new().map_err(|err| anyhow::anyhow!("Failed to access USB: {}", err))?;
+        let transport = TransportNativeHID::new(&api)
+            .map_err(|err| anyhow::anyhow!("No Ledger found: {}", err))?;
+        let response = exchange(&transport, INS_GET_ADDRESS, 0, derivation_path(0))?;
+        // the public key is followed by the SS58 address
+        let public_key = response
+            .get(..32)
+            .ok_or_else(|| anyhow::anyhow!("The Ledger returned no public key"))?;
+        let mut account_id = [0u8; 32];
+        account_id.copy_from_slice(public_key);
+        Ok(Self {
+            transport,
+            account_id: AccountId32::from(account_id).into(),
+        })
+    }
+
+    /// Returns the signature of `payload`, after the user approved it on the Ledger.
+    fn sign_payload(&self, payload: &[u8]) -> Result<MultiSignature> {
+        let mut signature = Vec::new();
+        for (p1, chunk) in sign_chunks(derivation_path(0), payload) {
+            signature = exchange(&self.transport, INS_SIGN, p1, chunk)?;
+        }
+        // the signature starts with its scheme, as a `MultiSignature`
+        Ok(MultiSignature::decode(&mut &signature[..])?)
+    }
+}
+
+impl Signer for LedgerSigner {
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<MultiSignature> {
+        // the app parses the full payload, which is not hashed like payloads signed by keys
+        self.sign_payload(payload)
+    }
+}
+
+/// Sends the instruction `ins` to the Polkadot app and returns its response.
+fn exchange(transport: &TransportNativeHID, ins: u8, p1: u8, data: Vec<u8>) -> Result<Vec<u8>> {
+    let command = APDUCommand {
+        cla: CLA,
+        ins,
+        p1,
+        p2: SR25519,
+        data,
+    };
+    let answer = transport
+        .exchange(&command)
+        .map_err(|err| anyhow::anyhow!("Failed to communicate with the Ledger: {}", err))?;
+    match answer.retcode() {
+        0x9000 => Ok(answer.data().to_vec()),
+        0x6e01 | 0x6e00 => anyhow::bail!("Open the Polkadot app on the Ledger"),
+        0x6986 => anyhow::bail!("The transaction was rejected on the Ledger"),
+        code => anyhow::bail!(
+            "The Ledger failed with {:#06x}: {}",
+            code,
+            String::from_utf8_lossy(answer.data())
+        ),
+    }
+}
+
+/// Returns the hardened BIP44 path `m/44'/354'/account'/0'/0'`, as expected by the app.
+fn derivation_path(account: u32) -> Vec<u8> {
+    const HARDENED: u32 = 0x8000_0000;
+    [44, COIN_TYPE, account, 0, 0]
+        .iter()
+        .flat_map(|index| (HARDENED | index).to_le_bytes().to_vec())
+        .collect()
+}
+
+/// Splits signing `payload` into chunks: the first one holds the path, the last one is
+/// marked as such.
+fn sign_chunks(path: Vec<u8>, payload: &[u8]) -> Vec<(u8, Vec<u8>)> {
+    const INIT: u8 = 0x00;
+    const ADD: u8 = 0x01;
+    const LAST: u8 = 0x02;
+    let mut chunks = vec![(INIT, path)];
+    let payload_chunks = payload.chunks(CHUNK_SIZE).collect::<Vec<_>>();
+    for (i, chunk) in payload_chunks.iter().enumerate() {
+        let p1 = if i + 1 == payload_chunks.len() {
+            LAST
+        } else {
+            ADD
+        };
+        chunks.push((p1, chunk.to_vec()));
+    }
+    chunks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn derives_polkadot_path() {
+        assert_eq!(
+            derivation_path(1),
+            vec![
+                0x2c, 0, 0, 0x80, 0x62, 0x01, 0, 0x80, 1, 0, 0, 0x80, 0, 0, 0, 0x80, 0, 0, 0, 0x80
+            ]
+        );
+    }
+
+    #[test]
+    fn splits_payload_into_chunks() {
+        let payload = vec![7; CHUNK_SIZE + 10];
+        let chunks = sign_chunks(derivation_path(0), &payload);
+        let flags = chunks.iter().map(|(p1, _)| *p1).collect::<Vec<_>>();
+        assert_eq!(flags, vec![0, 1, 2]);
+        assert_eq!(chunks[1].1.len(), CHUNK_SIZE);
+        assert_eq!(chunks[2].1.len(), 10);
+    }
+}
@@ -0,0 +1,56 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Shrinks the generated metadata for `cargo contract build --strip-docs`.
+
+use serde_json::Value;
+
+/// Empties all `docs` of the metadata: of the contract, its constructors, messages, events,
+/// their arguments and of the types.
+///
+/// The `docs` are emptied rather than removed, as tools reading the metadata expect them.
+pub(crate) fn strip_docs(value: &mut Value) {
+    match value {
+        Value::Object(object) => {
+            for (key, value) in object.iter_mut() {
+                match value {
+                    Value::Array(docs) if key == "docs" => docs.clear(),
+                    value => strip_docs(value),
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(strip_docs),
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{abi::tests::metadata_v1, cmd::metadata::validate::validate};
+
+    #[test]
+    fn strips_all_docs() {
+        let mut metadata = metadata_v1();
+        strip_docs(&mut metadata);
+
+        let json = metadata.to_string();
+        assert!(!json.contains("Deposits"), "{}", json);
+        assert!(json.contains("\"docs\":[]"));
+        // the stripped metadata stays valid
+        assert_eq!(validate(&metadata).unwrap().1, Vec::<String>::new());
+    }
+}
@@ -0,0 +1,265 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Converts metadata between ink! metadata versions with `cargo contract metadata convert`.
+
+use super::read_json;
+use crate::abi::{versioned_project, MetadataVersion};
+use anyhow::Result;
+use colored::Colorize;
+use serde_json::{Map, Value};
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Converts a metadata or `.contract` file to another ink! metadata version.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "convert")]
+pub struct ConvertCommand {
+    /// The metadata version to convert to
+    #[structopt(long, value_name = "v1 | v2 | v3 | v4")]
+    to: MetadataVersion,
+    /// The metadata or `.contract` file to convert
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+    /// Write the converted metadata to this file instead of printing it
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+impl ConvertCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let json = read_json(&self.file)?;
+        let (converted, warnings) = convert(&json, self.to)?;
+        for warning in warnings {
+            eprintln!("{} {}", "warning:".yellow().bold(), warning);
+        }
+        let converted = serde_json::to_string_pretty(&converted)?;
+
+        match &self.output {
+            Some(output_path) => {
+                fs::write(output_path, converted)?;
+                Ok(Some(format!(
+                    "Written the {} metadata to {}",
+                    self.to,
+                    output_path.display()
+                )))
+            }
+            None => {
+                println!("{}", converted);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Converts `json` to the metadata version `to`, returning warnings about information which
+/// the version `to` cannot express.
+///
+/// The types and the encoding of values are the same in all versions, so return types are
+/// kept as they are: the `Result` ink! 4 wraps them in is part of what the contract returns.
+pub(crate) fn convert(json: &Value, to: MetadataVersion) -> Result<(Value, Vec<String>)> {
+    let (from, project) = versioned_project(json)?;
+    if from == MetadataVersion::V0 || to == MetadataVersion::V0 {
+        anyhow::bail!(
+            "Converting from or to V0 metadata is not supported, its type ids are numbered differently"
+        )
+    }
+    let mut warnings = Vec::new();
+
+    let mut spec = project
+        .get("spec")
+        .cloned()
+        .ok_or_else(|| anyhow::anyhow!("No contract spec found in the metadata"))?;
+    for (key, is_constructor) in [("constructors", true), ("messages", false)] {
+        for spec in items(&mut spec, key) {
+            convert_label(spec, to, true);
+            for arg in items(spec, "args") {
+                convert_label(arg, to, false);
+            }
+            let spec = match spec.as_object_mut() {
+                Some(spec) => spec,
+                None => continue,
+            };
+            if to < MetadataVersion::V4 {
+                spec.remove("default");
+            }
+            if !is_constructor {
+                continue;
+            }
+            // constructors are payable since ink! 3 and return a value since ink! 4
+            match (spec.get("payable").and_then(Value::as_bool), to) {
+                (None, MetadataVersion::V3) | (None, MetadataVersion::V4) => {
+                    spec.insert("payable".into(), Value::Bool(true));
+                }
+                (Some(payable), MetadataVersion::V1) | (Some(payable), MetadataVersion::V2) => {
+                    spec.remove("payable");
+                    if !payable {
+                        warnings.push(format!(
+                            "The constructor `{}` is not payable, which {} metadata cannot express",
+                            label(spec),
+                            to
+                        ));
+                    }
+                }
+                _ => (),
+            }
+            if to < MetadataVersion::V4 {
+                spec.remove("returnType");
+            } else {
+                spec.entry("returnType").or_insert(Value::Null);
+            }
+        }
+    }
+    for event in items(&mut spec, "events") {
+        convert_label(event, to, false);
+        for arg in items(event, "args") {
+            convert_label(arg, to, false);
+        }
+    }
+    if to < MetadataVersion::V4 {
+        if let Some(spec) = spec.as_object_mut() {
+            spec.remove("lang_error");
+            spec.remove("environment");
+        }
+    }
+
+    let storage = project.get("storage").cloned().unwrap_or_default();
+    if (from < MetadataVersion::V4) != (to < MetadataVersion::V4) {
+        warnings.push(
+            "The storage layout is kept as it is, though ink! 4 lays out the storage differently \
+            than ink! 3"
+                .to_string(),
+        );
+    }
+    let mut converted = Map::new();
+    converted.insert("spec".into(), spec);
+    converted.insert("storage".into(), storage);
+    converted.insert(
+        "types".into(),
+        project.get("types").cloned().unwrap_or_default(),
+    );
+
+    // the contract project is nested in the version before ink! 4 and next to it since
+    let mut root = json.as_object().cloned().unwrap_or_default();
+    for key in ["V1", "V2", "V3", "version", "spec", "storage", "types"] {
+        root.remove(key);
+    }
+    if to == MetadataVersion::V4 {
+        root.remove("metadataVersion");
+        root.insert("version".into(), Value::String("4".into()));
+        root.extend(converted);
+    } else {
+        root.entry("metadataVersion")
+            .or_insert_with(|| Value::String("0.1.0".into()));
+        root.insert(to.to_string(), Value::Object(converted));
+    }
+    Ok((Value::Object(root), warnings))
+}
+
+/// Returns the items of the array `key` of `value`.
+fn items<'a>(value: &'a mut Value, key: &str) -> impl Iterator<Item = &'a mut Value> {
+    value
+        .get_mut(key)
+        .and_then(Value::as_array_mut)
+        .into_iter()
+        .flatten()
+}
+
+/// Returns the label of a spec, joining the path of the `name` before ink! 3.
+fn label(spec: &Map<String, Value>) -> String {
+    match spec.get("label").or_else(|| spec.get("name")) {
+        Some(Value::String(label)) => label.clone(),
+        Some(Value::Array(segments)) => segments
+            .iter()
+            .filter_map(Value::as_str)
+            .collect::<Vec<_>>()
+            .join("::"),
+        _ => String::new(),
+    }
+}
+
+/// Converts between the `label` of ink! 3 and later and the `name` before, which is a path
+/// for constructors and messages.
+fn convert_label(value: &mut Value, to: MetadataVersion, is_path: bool) {
+    let spec = match value.as_object_mut() {
+        Some(spec) => spec,
+        None => return,
+    };
+    let label = label(spec);
+    spec.remove("label");
+    spec.remove("name");
+    if to >= MetadataVersion::V2 {
+        spec.insert("label".into(), Value::String(label));
+    } else if is_path {
+        let path = label.split("::").map(|s| Value::String(s.into())).collect();
+        spec.insert("name".into(), Value::Array(path));
+    } else {
+        spec.insert("name".into(), Value::String(label));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        abi::{tests::metadata_v1, ContractAbi},
+        cmd::metadata::validate::validate,
+    };
+
+    #[test]
+    fn converts_between_versions() {
+        let metadata = metadata_v1();
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+
+        for to in [
+            MetadataVersion::V2,
+            MetadataVersion::V3,
+            MetadataVersion::V4,
+        ] {
+            let (converted, warnings) = convert(&metadata, to).unwrap();
+            assert_eq!(validate(&converted).unwrap(), (to, Vec::new()));
+            assert_eq!(ContractAbi::from_json(&converted).unwrap(), abi);
+            assert_eq!(warnings.is_empty(), to != MetadataVersion::V4);
+        }
+
+        let (v4, _) = convert(&metadata, MetadataVersion::V4).unwrap();
+        assert_eq!(v4["version"], "4");
+        assert_eq!(v4["spec"]["messages"][1]["label"], "Ledger::balance_of");
+        assert_eq!(v4["spec"]["constructors"][0]["payable"], true);
+        let (v1, _) = convert(&v4, MetadataVersion::V1).unwrap();
+        assert_eq!(v1, metadata);
+    }
+
+    #[test]
+    fn warns_about_lost_information() {
+        let mut metadata = metadata_v1();
+        let (mut v3, _) = convert(&metadata, MetadataVersion::V3).unwrap();
+        v3["V3"]["spec"]["constructors"][0]["payable"] = Value::Bool(false);
+        let (_, warnings) = convert(&v3, MetadataVersion::V2).unwrap();
+        assert_eq!(
+            warnings,
+            vec!["The constructor `new` is not payable, which V2 metadata cannot express"]
+        );
+
+        // before ink! 3 the contract project was at the root
+        let project = metadata.as_object_mut().unwrap().remove("V1").unwrap();
+        for key in ["spec", "storage", "types"] {
+            metadata[key] = project[key].clone();
+        }
+        let error = convert(&metadata, MetadataVersion::V4).unwrap_err();
+        assert!(error.to_string().contains("V0 metadata is not supported"));
+    }
+}
@@ -0,0 +1,586 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compares two versions of the metadata of a contract with `cargo contract metadata diff`.
+
+use super::built_metadata_path;
+use crate::abi::{ArgSpec, ContractAbi, EventSpec, MessageSpec, TypeRef, TypeRegistry};
+use anyhow::Result;
+use colored::Colorize;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Reports the differences between two versions of the metadata, flagging the ones
+/// which break callers of the old version.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "diff")]
+pub struct DiffCommand {
+    /// The old metadata or `.contract` file
+    #[structopt(parse(from_os_str))]
+    old: PathBuf,
+    /// The new metadata or `.contract` file, the metadata of the built contract if not given
+    #[structopt(parse(from_os_str))]
+    new: Option<PathBuf>,
+    /// Path to the Cargo.toml of the contract whose built metadata is the new one
+    #[structopt(long, parse(from_os_str), conflicts_with = "new")]
+    manifest_path: Option<PathBuf>,
+    /// Export the differences in JSON format
+    #[structopt(long)]
+    output_json: bool,
+}
+
+impl DiffCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let new_path = match &self.new {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let old = ContractAbi::load(&self.old)?;
+        let new = ContractAbi::load(&new_path)?;
+        let changes = diff(&old, &new);
+
+        if self.output_json {
+            return Ok(Some(serde_json::to_string_pretty(&changes)?));
+        }
+        if changes.is_empty() {
+            return Ok(Some("The ABI did not change".to_string()));
+        }
+        println!("{}", report(&changes));
+        Ok(None)
+    }
+}
+
+/// Renders the changes, marking the breaking ones, followed by a count of them.
+pub(super) fn report(changes: &[Change]) -> String {
+    let mut report = String::new();
+    for change in changes {
+        let marker = if change.breaking {
+            "[breaking]  ".bright_red().bold()
+        } else {
+            "[compatible]".green().bold()
+        };
+        report.push_str(&format!(
+            "{} {}: {}\n",
+            marker, change.item, change.description
+        ));
+    }
+    let breaking = changes.iter().filter(|change| change.breaking).count();
+    report.push_str(&format!(
+        "\n{} changes, {} of them ABI-breaking",
+        changes.len(),
+        breaking
+    ));
+    report
+}
+
+/// A difference between two versions of the metadata.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct Change {
+    /// The changed constructor, message or event, e.g. ``message `transfer` ``.
+    pub item: String,
+    /// What changed.
+    pub description: String,
+    /// `true` if callers or indexers built for the old metadata break.
+    pub breaking: bool,
+}
+
+/// Returns the differences between the `old` and the `new` metadata.
+pub fn diff(old: &ContractAbi, new: &ContractAbi) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_specs(
+        &mut changes,
+        "constructor",
+        (&old.constructors, &old.types),
+        (&new.constructors, &new.types),
+    );
+    diff_specs(
+        &mut changes,
+        "message",
+        (&old.messages, &old.types),
+        (&new.messages, &new.types),
+    );
+    diff_events(
+        &mut changes,
+        (&old.events, &old.types),
+        (&new.events, &new.types),
+    );
+    changes.extend(diff_storage(old, new));
+    changes
+}
+
+/// Returns the differences between the storage layouts of the `old` and the `new` metadata,
+/// flagging the ones which break reading the storage written by the old version of the code
+/// with the new one, e.g. after upgrading the code of a contract.
+pub fn diff_storage(old: &ContractAbi, new: &ContractAbi) -> Vec<Change> {
+    if old.storage.scheme != new.storage.scheme {
+        return vec![Change {
+            item: "storage".to_string(),
+            description: "keys changed between the ink! 3 and ink! 4 layouts".to_string(),
+            breaking: true,
+        }];
+    }
+    let mut changes = Vec::new();
+    let mut change = |path: &str, description: String, breaking: bool| {
+        let item = if path.is_empty() {
+            "storage".to_string()
+        } else {
+            format!("storage `{}`", path)
+        };
+        changes.push(Change {
+            item,
+            description,
+            breaking,
+        })
+    };
+    let hex = |key: &[u8]| {
+        let digits = key
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect::<String>();
+        format!("0x{}", digits)
+    };
+
+    for old_cell in &old.storage.cells {
+        let path = &old_cell.path;
+        let new_cell = match new.storage.cells.iter().find(|cell| &cell.path == path) {
+            Some(cell) => cell,
+            None => {
+                change(
+                    path,
+                    "removed, its value is left in the storage".to_string(),
+                    false,
+                );
+                continue;
+            }
+        };
+        if new_cell.key != old_cell.key {
+            change(
+                path,
+                format!(
+                    "moved from key {} to {}",
+                    hex(&old_cell.key),
+                    hex(&new_cell.key)
+                ),
+                true,
+            );
+        }
+        let signature = |fields: &[(String, u32)], types: &TypeRegistry| match fields {
+            [(field, ty)] if field == path => types.type_name(*ty),
+            fields => {
+                let fields = fields
+                    .iter()
+                    .map(|(field, ty)| format!("{}: {}", field, types.type_name(*ty)))
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", fields.join(", "))
+            }
+        };
+        let encodings = |fields: &[(String, u32)], types: &TypeRegistry| {
+            fields
+                .iter()
+                .map(|(_, ty)| types.encoding(*ty))
+                .collect::<Vec<_>>()
+        };
+        // the fields of a cell are packed, so any change of their encodings breaks it
+        if encodings(&old_cell.fields, &old.types) != encodings(&new_cell.fields, &new.types) {
+            change(
+                path,
+                format!(
+                    "layout changed from `{}` to `{}`",
+                    signature(&old_cell.fields, &old.types),
+                    signature(&new_cell.fields, &new.types)
+                ),
+                true,
+            );
+        }
+    }
+    for new_cell in &new.storage.cells {
+        if !old
+            .storage
+            .cells
+            .iter()
+            .any(|cell| cell.path == new_cell.path)
+        {
+            change(&new_cell.path, "added".to_string(), false);
+        }
+    }
+
+    for old_mapping in &old.storage.mappings {
+        let path = &old_mapping.path;
+        let new_mapping = match new
+            .storage
+            .mappings
+            .iter()
+            .find(|mapping| &mapping.path == path)
+        {
+            Some(mapping) => mapping,
+            None => {
+                change(
+                    path,
+                    "removed, its entries are left in the storage".to_string(),
+                    false,
+                );
+                continue;
+            }
+        };
+        if new_mapping.prefix != old_mapping.prefix {
+            change(
+                path,
+                format!(
+                    "moved from key {} to {}",
+                    hex(&old_mapping.prefix),
+                    hex(&new_mapping.prefix)
+                ),
+                true,
+            );
+        }
+        let signature = |key_ty: Option<u32>, value_ty: u32, types: &TypeRegistry| {
+            let key = key_ty.map_or_else(|| "_".to_string(), |ty| types.type_name(ty));
+            format!("Mapping<{}, {}>", key, types.type_name(value_ty))
+        };
+        let encoding = |key_ty: Option<u32>, value_ty: u32, types: &TypeRegistry| {
+            (
+                key_ty.map(|ty| types.encoding(ty)),
+                types.encoding(value_ty),
+            )
+        };
+        if encoding(old_mapping.key_ty, old_mapping.value_ty, &old.types)
+            != encoding(new_mapping.key_ty, new_mapping.value_ty, &new.types)
+        {
+            change(
+                path,
+                format!(
+                    "type changed from `{}` to `{}`",
+                    signature(old_mapping.key_ty, old_mapping.value_ty, &old.types),
+                    signature(new_mapping.key_ty, new_mapping.value_ty, &new.types)
+                ),
+                true,
+            );
+        }
+    }
+    for new_mapping in &new.storage.mappings {
+        if !old
+            .storage
+            .mappings
+            .iter()
+            .any(|mapping| mapping.path == new_mapping.path)
+        {
+            change(&new_mapping.path, "added".to_string(), false);
+        }
+    }
+    changes
+}
+
+fn diff_specs(
+    changes: &mut Vec<Change>,
+    kind: &str,
+    (old_specs, old_types): (&[MessageSpec], &TypeRegistry),
+    (new_specs, new_types): (&[MessageSpec], &TypeRegistry),
+) {
+    let mut change = |label: &str, description: String, breaking: bool| {
+        changes.push(Change {
+            item: format!("{} `{}`", kind, label),
+            description,
+            breaking,
+        })
+    };
+
+    let mut matched = Vec::new();
+    for old in old_specs {
+        // a spec whose label changed is still found by its selector
+        let new = new_specs
+            .iter()
+            .find(|new| new.label == old.label)
+            .or_else(|| new_specs.iter().find(|new| new.selector == old.selector));
+        let new = match new {
+            Some(new) => new,
+            None => {
+                change(&old.label, "removed".to_string(), true);
+                continue;
+            }
+        };
+        matched.push(&new.label);
+
+        if new.label != old.label {
+            change(&old.label, format!("renamed to `{}`", new.label), false);
+        }
+        if new.selector != old.selector {
+            change(
+                &old.label,
+                format!("selector changed from {} to {}", old.selector, new.selector),
+                true,
+            );
+        }
+        diff_args(
+            &mut change,
+            &old.label,
+            (&old.args, old_types),
+            (&new.args, new_types),
+        );
+
+        let return_type = |ty: &Option<TypeRef>, types: &TypeRegistry| match ty {
+            Some(ty) => (types.type_name(ty.id), types.encoding(ty.id)),
+            None => ("()".to_string(), "()".to_string()),
+        };
+        let (old_name, old_encoding) = return_type(&old.return_type, old_types);
+        let (new_name, new_encoding) = return_type(&new.return_type, new_types);
+        if old_encoding != new_encoding {
+            change(
+                &old.label,
+                format!("return type changed from `{}` to `{}`", old_name, new_name),
+                true,
+            );
+        }
+
+        match (old.payable, new.payable) {
+            (true, false) => change(&old.label, "no longer payable".to_string(), true),
+            (false, true) => change(&old.label, "now payable".to_string(), false),
+            _ => (),
+        }
+        match (old.mutates, new.mutates) {
+            (true, false) => change(
+                &old.label,
+                "no longer mutates the storage".to_string(),
+                false,
+            ),
+            (false, true) => change(&old.label, "now mutates the storage".to_string(), false),
+            _ => (),
+        }
+    }
+
+    for new in new_specs {
+        if !matched.contains(&&new.label) {
+            change(
+                &new.label,
+                format!("added with selector {}", new.selector),
+                false,
+            );
+        }
+    }
+}
+
+fn diff_events(
+    changes: &mut Vec<Change>,
+    (old_events, old_types): (&[EventSpec], &TypeRegistry),
+    (new_events, new_types): (&[EventSpec], &TypeRegistry),
+) {
+    let mut change = |label: &str, description: String, breaking: bool| {
+        changes.push(Change {
+            item: format!("event `{}`", label),
+            description,
+            breaking,
+        })
+    };
+
+    for (old_index, old) in old_events.iter().enumerate() {
+        let (new_index, new) = match new_events
+            .iter()
+            .enumerate()
+            .find(|(_, new)| new.label == old.label)
+        {
+            Some(new) => new,
+            None => {
+                change(&old.label, "removed".to_string(), true);
+                continue;
+            }
+        };
+        // the events of a contract are encoded as the variants of a single enum
+        if new_index != old_index {
+            change(
+                &old.label,
+                format!(
+                    "moved from position {} to {}, which changes its encoding",
+                    old_index, new_index
+                ),
+                true,
+            );
+        }
+        diff_args(
+            &mut change,
+            &old.label,
+            (&old.args, old_types),
+            (&new.args, new_types),
+        );
+        let topics = |args: &[ArgSpec]| {
+            args.iter()
+                .filter(|arg| arg.indexed)
+                .map(|arg| arg.label.clone())
+                .collect::<Vec<_>>()
+        };
+        if topics(&old.args) != topics(&new.args) {
+            change(
+                &old.label,
+                format!(
+                    "topics changed from [{}] to [{}]",
+                    topics(&old.args).join(", "),
+                    topics(&new.args).join(", ")
+                ),
+                true,
+            );
+        }
+    }
+
+    for new in new_events {
+        if !old_events.iter().any(|old| old.label == new.label) {
+            change(&new.label, "added".to_string(), false);
+        }
+    }
+}
+
+/// Reports changed arguments, which break callers if they are encoded differently.
+fn diff_args(
+    change: &mut dyn FnMut(&str, String, bool),
+    label: &str,
+    (old_args, old_types): (&[ArgSpec], &TypeRegistry),
+    (new_args, new_types): (&[ArgSpec], &TypeRegistry),
+) {
+    let signature = |args: &[ArgSpec], types: &TypeRegistry| {
+        let args = args
+            .iter()
+            .map(|arg| format!("{}: {}", arg.label, types.type_name(arg.ty.id)))
+            .collect::<Vec<_>>();
+        format!("({})", args.join(", "))
+    };
+    let encodings = |args: &[ArgSpec], types: &TypeRegistry| {
+        args.iter()
+            .map(|arg| types.encoding(arg.ty.id))
+            .collect::<Vec<_>>()
+    };
+
+    let old_signature = signature(old_args, old_types);
+    let new_signature = signature(new_args, new_types);
+    if encodings(old_args, old_types) != encodings(new_args, new_types) {
+        change(
+            label,
+            format!(
+                "arguments changed from `{}` to `{}`",
+                old_signature, new_signature
+            ),
+            true,
+        );
+    } else if old_signature != new_signature {
+        change(
+            label,
+            format!(
+                "arguments renamed from `{}` to `{}`",
+                old_signature, new_signature
+            ),
+            false,
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+
+    #[test]
+    fn identical_metadata_has_no_changes() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        assert!(diff(&abi, &abi).is_empty());
+    }
+
+    #[test]
+    fn flags_breaking_changes() {
+        let old = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let mut metadata = metadata_v1();
+        let spec = &mut metadata["V1"]["spec"];
+        // `deposit` is renamed, keeping its selector
+        spec["messages"][0]["name"] = json!(["pay_in"]);
+        // `balance_of` takes a `u128` instead of an `AccountId`
+        spec["messages"][1]["args"][0]["type"]["type"] = json!(0);
+        spec["messages"][1]["args"][0]["name"] = json!("who");
+        // `withdraw` is removed, `close` is added
+        spec["messages"][2]["name"] = json!(["close"]);
+        spec["messages"][2]["selector"] = json!("0xdeadbeef");
+        // the argument of the constructor is renamed only
+        spec["constructors"][0]["args"][0]["name"] = json!("max");
+        spec["events"][0]["args"][0]["indexed"] = json!(false);
+        let new = ContractAbi::from_json(&metadata).unwrap();
+
+        let changes = diff(&old, &new)
+            .into_iter()
+            .map(|change| (change.item, change.description, change.breaking))
+            .collect::<Vec<_>>();
+        let change = |item: &str, description: &str, breaking| {
+            (item.to_string(), description.to_string(), breaking)
+        };
+        assert_eq!(
+            changes,
+            vec![
+                change(
+                    "constructor `new`",
+                    "arguments renamed from `(limit: u128)` to `(max: u128)`",
+                    false
+                ),
+                change("message `deposit`", "renamed to `pay_in`", false),
+                change(
+                    "message `Ledger::balance_of`",
+                    "arguments changed from `(owner: AccountId)` to `(who: u128)`",
+                    true
+                ),
+                change("message `withdraw`", "removed", true),
+                change("message `close`", "added with selector 0xdeadbeef", false),
+                change(
+                    "event `Deposited`",
+                    "topics changed from [from] to []",
+                    true
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn flags_breaking_storage_changes() {
+        let key = |last: u8| format!("0x{}{:02x}", "00".repeat(31), last);
+        let cell = |name: &str, last: u8, ty: u32| json!({ "name": name, "layout": { "cell": { "key": key(last), "ty": ty } } });
+        let mut metadata = metadata_v1();
+        metadata["V1"]["storage"] =
+            json!({ "struct": { "fields": [cell("total", 0, 0), cell("owner", 1, 1)] } });
+        let old = ContractAbi::from_json(&metadata).unwrap();
+        metadata["V1"]["storage"] = json!({ "struct": { "fields": [
+            cell("total", 0, 1),
+            cell("limit", 2, 0)
+        ] } });
+        let new = ContractAbi::from_json(&metadata).unwrap();
+
+        let changes = diff_storage(&old, &new)
+            .into_iter()
+            .map(|change| (change.item, change.description, change.breaking))
+            .collect::<Vec<_>>();
+        let change = |item: &str, description: &str, breaking| {
+            (item.to_string(), description.to_string(), breaking)
+        };
+        assert_eq!(
+            changes,
+            vec![
+                change(
+                    "storage `total`",
+                    "layout changed from `u128` to `AccountId`",
+                    true
+                ),
+                change(
+                    "storage `owner`",
+                    "removed, its value is left in the storage",
+                    false
+                ),
+                change("storage `limit`", "added", false),
+            ]
+        );
+        assert!(diff_storage(&old, &old).is_empty());
+    }
+}
@@ -0,0 +1,223 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Converts the metadata of built contracts with `cargo contract metadata`.
+
+use super::{
+    built_metadata_path, convert::ConvertCommand, diff::DiffCommand, hash::HashCommand, read_json,
+    show::ShowCommand, snapshot::SnapshotCommand, validate::ValidateCommand,
+};
+use crate::{
+    abi::ContractAbi,
+    codegen::{json_schema, solidity},
+};
+use anyhow::Result;
+use colored::Colorize;
+use serde_json::Value;
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Converts the metadata of a built contract into other formats.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "metadata")]
+pub struct MetadataCommand {
+    /// Path to the Cargo.toml of the contract whose metadata to read
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The format to print the metadata in.
+    ///
+    /// - `ink`: The ink! metadata, without the Wasm of `.contract` files.
+    ///
+    /// - `solidity-abi`: The Solidity JSON ABI of the constructors, messages and events
+    ///   which have a Solidity counterpart.
+    ///
+    /// - `json-schema`: A JSON Schema of the arguments of every constructor and message,
+    ///   for validating arguments given as JSON before encoding them.
+    #[structopt(
+        long,
+        default_value = "ink",
+        value_name = "ink | solidity-abi | json-schema",
+        verbatim_doc_comment
+    )]
+    format: MetadataFormat,
+    /// Write the converted metadata to this file instead of printing it
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+    #[structopt(subcommand)]
+    cmd: Option<MetadataSubcommand>,
+}
+
+#[derive(Debug, StructOpt)]
+enum MetadataSubcommand {
+    /// Print a readable summary of the metadata
+    #[structopt(name = "show")]
+    Show(ShowCommand),
+    /// Report the differences between two versions of the metadata
+    #[structopt(name = "diff")]
+    Diff(DiffCommand),
+    /// Check that metadata conforms to a known ink! metadata version
+    #[structopt(name = "validate")]
+    Validate(ValidateCommand),
+    /// Convert metadata to another ink! metadata version
+    #[structopt(name = "convert")]
+    Convert(ConvertCommand),
+    /// Print the hash of the canonical serialization of the metadata
+    #[structopt(name = "hash")]
+    Hash(HashCommand),
+    /// Write a snapshot of the interface, or check that the interface is compatible with it
+    #[structopt(name = "snapshot")]
+    Snapshot(SnapshotCommand),
+}
+
+/// The formats `cargo contract metadata` converts the metadata to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetadataFormat {
+    /// The ink! metadata.
+    Ink,
+    /// The JSON ABI of Solidity contracts.
+    SolidityAbi,
+    /// JSON Schemas of the arguments of the constructors and messages.
+    JsonSchema,
+}
+
+impl std::str::FromStr for MetadataFormat {
+    type Err = String;
+    fn from_str(format: &str) -> Result<Self, Self::Err> {
+        match format {
+            "ink" => Ok(MetadataFormat::Ink),
+            "solidity-abi" => Ok(MetadataFormat::SolidityAbi),
+            "json-schema" => Ok(MetadataFormat::JsonSchema),
+            _ => Err("Could not parse metadata format".to_string()),
+        }
+    }
+}
+
+impl MetadataCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match &self.cmd {
+            Some(MetadataSubcommand::Show(show)) => return show.exec(),
+            Some(MetadataSubcommand::Diff(diff)) => return diff.exec(),
+            Some(MetadataSubcommand::Validate(validate)) => return validate.exec(),
+            Some(MetadataSubcommand::Convert(convert)) => return convert.exec(),
+            Some(MetadataSubcommand::Hash(hash)) => return hash.exec(),
+            Some(MetadataSubcommand::Snapshot(snapshot)) => return snapshot.exec(),
+            None => (),
+        }
+        let path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let mut json = read_json(&path)?;
+
+        let output = match self.format {
+            MetadataFormat::Ink => {
+                if let Some(source) = json.get_mut("source").and_then(Value::as_object_mut) {
+                    source.remove("wasm");
+                }
+                serde_json::to_string_pretty(&json)?
+            }
+            MetadataFormat::SolidityAbi => {
+                let abi = ContractAbi::from_json(&json)?;
+                let solidity = solidity::generate(&abi);
+                for (label, reason) in &solidity.skipped {
+                    eprintln!(
+                        "{} `{}` is left out of the Solidity ABI: {}",
+                        "warning:".yellow().bold(),
+                        label,
+                        reason
+                    );
+                }
+                serde_json::to_string_pretty(&solidity.entries)?
+            }
+            MetadataFormat::JsonSchema => {
+                let abi = ContractAbi::from_json(&json)?;
+                serde_json::to_string_pretty(&json_schema::generate(&abi)?)?
+            }
+        };
+
+        match &self.output {
+            Some(output_path) => {
+                fs::write(output_path, output)?;
+                Ok(Some(format!(
+                    "Written the metadata to {}",
+                    output_path.display()
+                )))
+            }
+            None => {
+                println!("{}", output);
+                Ok(None)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn converts_metadata() {
+        with_tmp_dir(|path| {
+            let mut metadata = crate::abi::tests::metadata_v1();
+            metadata["source"]["wasm"] = Value::String("0x0061736d".to_string());
+            let bundle_path = path.join("bank.contract");
+            fs::write(&bundle_path, metadata.to_string())?;
+
+            let output = path.join("metadata.json");
+            let cmd = MetadataCommand {
+                manifest_path: None,
+                metadata: Some(bundle_path.clone()),
+                format: MetadataFormat::Ink,
+                output: Some(output.clone()),
+                cmd: None,
+            };
+            cmd.exec()?;
+            let ink: Value = serde_json::from_slice(&fs::read(&output)?)?;
+            assert!(ink["source"].get("wasm").is_none());
+            assert_eq!(ink["V1"], metadata["V1"]);
+
+            let output = path.join("abi.json");
+            let cmd = MetadataCommand {
+                manifest_path: None,
+                metadata: Some(bundle_path.clone()),
+                format: MetadataFormat::SolidityAbi,
+                output: Some(output.clone()),
+                cmd: None,
+            };
+            cmd.exec()?;
+            let abi: Value = serde_json::from_slice(&fs::read(&output)?)?;
+            assert_eq!(abi[0]["type"], "constructor");
+            assert_eq!(abi[2]["name"], "balance_of");
+
+            let output = path.join("schema.json");
+            let cmd = MetadataCommand {
+                manifest_path: None,
+                metadata: Some(bundle_path),
+                format: MetadataFormat::JsonSchema,
+                output: Some(output.clone()),
+                cmd: None,
+            };
+            cmd.exec()?;
+            let schemas: Value = serde_json::from_slice(&fs::read(&output)?)?;
+            assert_eq!(schemas["messages"]["withdraw"]["required"][0], "value");
+            Ok(())
+        })
+    }
+}
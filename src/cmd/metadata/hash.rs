@@ -0,0 +1,130 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Hashes the canonical serialization of the metadata with `cargo contract metadata hash`.
+//!
+//! The canonical serialization is the JSON of the metadata without whitespace and with the
+//! keys of every object sorted by their UTF-8 bytes. The `source` section is left out, as it
+//! describes a particular build rather than the interface: the code, its hash and the
+//! versions of the tools.
+
+use super::{blake2_hash, built_metadata_path, read_json};
+use anyhow::Result;
+use contract_metadata::CodeHash;
+use serde_json::Value;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Prints the hash of the canonical serialization of the metadata.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "hash")]
+pub struct HashCommand {
+    /// The metadata or `.contract` file to hash, the metadata of the built contract if not given
+    #[structopt(parse(from_os_str))]
+    file: Option<PathBuf>,
+    /// Path to the Cargo.toml of the contract whose built metadata to hash
+    #[structopt(long, parse(from_os_str), conflicts_with = "file")]
+    manifest_path: Option<PathBuf>,
+}
+
+impl HashCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.file {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        // the hash is written like the code hash in the metadata, as `0x` prefixed hex
+        match serde_json::to_value(metadata_hash(&read_json(&path)?))? {
+            Value::String(hash) => Ok(Some(hash)),
+            _ => unreachable!("hashes are serialized as strings"),
+        }
+    }
+}
+
+/// Returns the blake2 hash of the canonical serialization of `metadata`.
+pub(crate) fn metadata_hash(metadata: &Value) -> CodeHash {
+    let mut metadata = metadata.clone();
+    if let Some(metadata) = metadata.as_object_mut() {
+        metadata.remove("source");
+    }
+    blake2_hash(canonical_json(&metadata).as_bytes())
+}
+
+/// Serializes `json` without whitespace and with the keys of objects sorted.
+pub(crate) fn canonical_json(json: &Value) -> String {
+    let mut out = String::new();
+    write_canonical(&mut out, json);
+    out
+}
+
+fn write_canonical(out: &mut String, json: &Value) {
+    match json {
+        Value::Array(items) => {
+            out.push('[');
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                write_canonical(out, item);
+            }
+            out.push(']');
+        }
+        Value::Object(map) => {
+            let mut entries = map.iter().collect::<Vec<_>>();
+            entries.sort_by(|(a, _), (b, _)| a.as_bytes().cmp(b.as_bytes()));
+            out.push('{');
+            for (i, (key, value)) in entries.into_iter().enumerate() {
+                if i > 0 {
+                    out.push(',');
+                }
+                out.push_str(&Value::String(key.clone()).to_string());
+                out.push(':');
+                write_canonical(out, value);
+            }
+            out.push('}');
+        }
+        // scalars have a single compact serialization
+        scalar => out.push_str(&scalar.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn canonical_json_sorts_keys() {
+        let json: Value =
+            serde_json::from_str(r#"{ "b": [1, { "y": true, "x": null }], "a": "é\n" }"#).unwrap();
+        assert_eq!(
+            canonical_json(&json),
+            r#"{"a":"é\n","b":[1,{"x":null,"y":true}]}"#
+        );
+    }
+
+    #[test]
+    fn hash_ignores_formatting_and_source() {
+        let metadata = metadata_v1();
+        let mut rebuilt: Value =
+            serde_json::from_str(&serde_json::to_string_pretty(&metadata).unwrap()).unwrap();
+        rebuilt["source"]["wasm"] = Value::String("0x0061736d".to_string());
+        assert_eq!(metadata_hash(&metadata), metadata_hash(&rebuilt));
+
+        rebuilt["V1"]["spec"]["messages"][0]["payable"] = Value::Bool(false);
+        assert_ne!(metadata_hash(&metadata), metadata_hash(&rebuilt));
+    }
+}
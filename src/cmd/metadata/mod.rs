@@ -14,14 +14,28 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+mod compact;
+mod convert;
+pub(crate) mod diff;
+mod format;
+mod hash;
+mod selectors;
+mod show;
+mod snapshot;
+mod validate;
+
+pub use self::format::MetadataCommand;
+
 use crate::{
+    abi::ContractAbi,
+    codegen,
     crate_metadata::CrateMetadata,
     maybe_println, util,
     workspace::{ManifestPath, Workspace},
     UnstableFlags, Verbosity,
 };
 
-use anyhow::Result;
+use anyhow::{Context, Result};
 use blake2::digest::{Update as _, VariableOutput as _};
 use colored::Colorize;
 use contract_metadata::{
@@ -29,7 +43,9 @@ use contract_metadata::{
     SourceLanguage, SourceWasm, User,
 };
 use semver::Version;
+use serde_json::Value;
 use std::{
+    convert::TryFrom,
     fs,
     path::{Path, PathBuf},
 };
@@ -37,6 +53,75 @@ use url::Url;
 
 const METADATA_FILE: &str = "metadata.json";
 
+/// Returns the path of the metadata of the built contract at `manifest_path`.
+pub(crate) fn built_metadata_path(manifest_path: Option<&PathBuf>) -> Result<PathBuf> {
+    let manifest_path = ManifestPath::try_from(manifest_path)?;
+    let crate_metadata = CrateMetadata::collect(&manifest_path)?;
+    let path = crate_metadata.target_directory.join(METADATA_FILE);
+    if !path.exists() {
+        anyhow::bail!(
+            "No metadata found at {}, build the contract with `cargo contract build` first",
+            path.display()
+        )
+    }
+    Ok(path)
+}
+
+/// Reads the JSON of a metadata or `.contract` file.
+pub(crate) fn read_json(path: &Path) -> Result<Value> {
+    let contents = fs::read_to_string(path)
+        .with_context(|| format!("Failed to read metadata file {}", path.display()))?;
+    serde_json::from_str(&contents)
+        .with_context(|| format!("Failed to parse metadata file {}", path.display()))
+}
+
+/// Fields of the metadata set on the command line, which take precedence over the ones
+/// in the `[package.metadata.contract]` of the `Cargo.toml`.
+#[derive(Debug, Default, Clone)]
+pub(crate) struct MetadataOverrides {
+    pub source_url: Option<Url>,
+    pub homepage: Option<Url>,
+    pub user: Vec<UserField>,
+}
+
+impl MetadataOverrides {
+    /// Overrides the fields of `crate_metadata`.
+    pub(crate) fn apply(self, crate_metadata: &mut CrateMetadata) {
+        if let Some(source_url) = self.source_url {
+            crate_metadata.source_url = Some(source_url);
+        }
+        if let Some(homepage) = self.homepage {
+            crate_metadata.homepage = Some(homepage);
+        }
+        if !self.user.is_empty() {
+            let user = crate_metadata.user.get_or_insert_with(Default::default);
+            for UserField(key, value) in self.user {
+                user.insert(key, value);
+            }
+        }
+    }
+}
+
+/// A field of the `user` section of the metadata, given as `key=value`.
+///
+/// The value is parsed as JSON, falling back to a string if it is no valid JSON.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct UserField(pub String, pub Value);
+
+impl std::str::FromStr for UserField {
+    type Err = String;
+    fn from_str(field: &str) -> Result<Self, Self::Err> {
+        let (key, value) = field
+            .split_once('=')
+            .ok_or_else(|| format!("Expected `key=value`, got `{}`", field))?;
+        if key.is_empty() {
+            return Err(format!("The field `{}` has no key", field));
+        }
+        let value = serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.into()));
+        Ok(UserField(key.to_string(), value))
+    }
+}
+
 /// Metadata generation result.
 #[derive(serde::Serialize)]
 pub struct MetadataResult {
@@ -53,15 +138,45 @@ struct ExtendedMetadataResult {
     user: Option<User>,
 }
 
+/// The settings a contract was built with, recorded in the `source.build_info` of the
+/// metadata so that anyone can attempt to reproduce the exact build.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct BuildInfo {
+    /// The version of the Rust compiler, e.g. `1.57.0-nightly`.
+    pub rustc_version: String,
+    /// The commit of the Rust compiler.
+    pub rustc_commit_hash: Option<String>,
+    /// The date of the commit of the Rust compiler, which identifies a nightly toolchain.
+    pub rustc_commit_date: Option<String>,
+    pub cargo_contract_version: String,
+    /// Either `debug` or `release`.
+    pub build_mode: String,
+    pub target: String,
+    pub wasm_opt_settings: WasmOptSettings,
+}
+
+/// The settings of the optimization of the contract with `wasm-opt`.
+#[derive(Debug, Clone, serde::Serialize)]
+pub(crate) struct WasmOptSettings {
+    /// The version of `wasm-opt`, e.g. `99 (version_99-79-gc12cc3f50)`.
+    pub version: Option<String>,
+    pub optimization_passes: String,
+    pub keep_debug_symbols: bool,
+}
+
 /// Generates a file with metadata describing the ABI of the smart-contract.
 ///
 /// It does so by generating and invoking a temporary workspace member.
+#[allow(clippy::too_many_arguments)]
 pub(crate) fn execute(
     crate_metadata: &CrateMetadata,
     final_contract_wasm: &Path,
+    build_info: &BuildInfo,
     verbosity: Verbosity,
     total_steps: usize,
     unstable_options: &UnstableFlags,
+    strip_docs: bool,
+    features: &[String],
 ) -> Result<MetadataResult> {
     util::assert_channel()?;
 
@@ -76,7 +191,7 @@ pub(crate) fn execute(
         source,
         contract,
         user,
-    } = extended_metadata(crate_metadata, final_contract_wasm)?;
+    } = extended_metadata(crate_metadata, final_contract_wasm, build_info)?;
 
     let generate_metadata = |manifest_path: &ManifestPath| -> Result<()> {
         let mut current_progress = 4;
@@ -87,20 +202,35 @@ pub(crate) fn execute(
             "Generating metadata".bright_green().bold()
         );
         let target_dir_arg = format!("--target-dir={}", target_directory.to_string_lossy());
+        let mut args = vec![
+            "--package".to_string(),
+            "metadata-gen".to_string(),
+            manifest_path.cargo_arg(),
+            target_dir_arg,
+            "--release".to_string(),
+        ];
+        // the contract is a dependency of the `metadata-gen` package
+        args.extend(
+            features
+                .iter()
+                .map(|feature| format!("--features=contract/{}", feature)),
+        );
         let stdout = util::invoke_cargo(
             "run",
-            &[
-                "--package",
-                "metadata-gen",
-                &manifest_path.cargo_arg(),
-                &target_dir_arg,
-                "--release",
-            ],
+            args,
             crate_metadata.manifest_path.directory(),
             verbosity,
         )?;
 
-        let ink_meta: serde_json::Map<String, serde_json::Value> = serde_json::from_slice(&stdout)?;
+        let mut ink_meta: serde_json::Map<String, serde_json::Value> =
+            serde_json::from_slice(&stdout)?;
+        if strip_docs {
+            ink_meta.values_mut().for_each(compact::strip_docs);
+        }
+        // metadata versions unknown to the reader are left to ink! to check
+        if let Ok(abi) = ContractAbi::from_json(&Value::Object(ink_meta.clone())) {
+            selectors::check_selectors(&abi)?;
+        }
         let metadata = ContractMetadata::new(source, contract, user, ink_meta);
         {
             let mut metadata = metadata.clone();
@@ -136,6 +266,12 @@ pub(crate) fn execute(
             .using_temp(generate_metadata)?;
     }
 
+    codegen::typescript::update_client(
+        &crate_metadata.manifest_path.absolute_directory()?,
+        &out_path_metadata,
+        verbosity,
+    )?;
+
     Ok(MetadataResult {
         dest_metadata: out_path_metadata,
         dest_bundle: out_path_bundle,
@@ -146,6 +282,7 @@ pub(crate) fn execute(
 fn extended_metadata(
     crate_metadata: &CrateMetadata,
     final_contract_wasm: &Path,
+    build_info: &BuildInfo,
 ) -> Result<ExtendedMetadataResult> {
     let contract_package = &crate_metadata.root_package;
     let ink_version = &crate_metadata.ink_version;
@@ -168,7 +305,16 @@ fn extended_metadata(
         let compiler = SourceCompiler::new(Compiler::RustC, rust_version);
         let wasm = fs::read(final_contract_wasm)?;
         let hash = blake2_hash(wasm.as_slice());
-        Source::new(Some(SourceWasm::new(wasm)), hash, lang, compiler)
+        let build_info = match serde_json::to_value(build_info)? {
+            Value::Object(build_info) => build_info,
+            _ => unreachable!("the build info is a struct"),
+        };
+        let source = Source::new(Some(SourceWasm::new(wasm)), hash, lang, compiler)
+            .with_build_info(build_info);
+        match crate_metadata.source_url.clone() {
+            Some(url) => source.with_url(url),
+            None => source,
+        }
     };
 
     // Required contract fields
@@ -213,7 +359,7 @@ fn extended_metadata(
 }
 
 /// Returns the blake2 hash of the submitted slice.
-fn blake2_hash(code: &[u8]) -> CodeHash {
+pub(crate) fn blake2_hash(code: &[u8]) -> CodeHash {
     let mut output = [0u8; 32];
     let mut blake2 = blake2::VarBlake2b::new_keyed(&[], 32);
     blake2.update(code);
@@ -224,7 +370,7 @@ fn blake2_hash(code: &[u8]) -> CodeHash {
 #[cfg(feature = "test-ci-only")]
 #[cfg(test)]
 mod tests {
-    use crate::cmd::metadata::blake2_hash;
+    use crate::cmd::metadata::{blake2_hash, MetadataOverrides};
     use crate::{
         cmd, crate_metadata::CrateMetadata, util::tests::with_new_contract_project, ManifestPath,
     };
@@ -278,6 +424,25 @@ mod tests {
             Ok(())
         }
 
+        /// Add a key/value to the `[package.metadata.contract]` section
+        fn add_contract_metadata_value(
+            &mut self,
+            key: &'static str,
+            value: value::Value,
+        ) -> anyhow::Result<()> {
+            self.package_mut()?
+                .entry("metadata")
+                .or_insert(value::Value::Table(Default::default()))
+                .as_table_mut()
+                .context("metadata section should be a table")?
+                .entry("contract")
+                .or_insert(value::Value::Table(Default::default()))
+                .as_table_mut()
+                .context("metadata.contract section should be a table")?
+                .insert(key.into(), value);
+            Ok(())
+        }
+
         fn add_package_value(
             &mut self,
             key: &'static str,
@@ -409,6 +574,67 @@ mod tests {
             assert_eq!("Apache-2.0", license.as_str().unwrap());
             assert_eq!(&expected_user_metadata, user.as_object().unwrap());
 
+            let build_info = source
+                .get("build_info")
+                .expect("source.build_info not found");
+            assert_eq!(build_info["build_mode"], "debug");
+            assert_eq!(build_info["target"], "wasm32-unknown-unknown");
+            assert_eq!(build_info["wasm_opt_settings"]["optimization_passes"], "z");
+            assert!(build_info["rustc_version"].is_string());
+
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn metadata_overrides_take_precedence() {
+        with_new_contract_project(|manifest_path| {
+            let mut test_manifest = TestContractManifest::new(manifest_path)?;
+            test_manifest.add_package_value("homepage", "http://homepage.com".into())?;
+            test_manifest
+                .add_contract_metadata_value("homepage", "http://contract.homepage.com".into())?;
+            test_manifest
+                .add_contract_metadata_value("source-url", "http://source.com/v1".into())?;
+            test_manifest.add_user_metadata_value("audit", "pending".into())?;
+            test_manifest.add_user_metadata_value("team", "core".into())?;
+            test_manifest.write()?;
+
+            let crate_metadata = CrateMetadata::collect(&test_manifest.manifest_path)?;
+            let final_contract_wasm_path = &crate_metadata.dest_wasm;
+            fs::create_dir_all(final_contract_wasm_path.parent().unwrap()).unwrap();
+            fs::write(final_contract_wasm_path, "TEST FINAL WASM BLOB").unwrap();
+
+            let mut args = crate::cmd::build::ExecuteArgs::default();
+            args.manifest_path = test_manifest.manifest_path;
+            args.metadata_overrides = MetadataOverrides {
+                source_url: Some(url::Url::parse("http://source.com/v2")?),
+                homepage: None,
+                user: vec![
+                    "audit=https://audits.com/1".parse().unwrap(),
+                    "ids=[1, 2]".parse().unwrap(),
+                ],
+            };
+
+            let build_result = cmd::build::execute(args)?;
+            let dest_bundle = build_result
+                .metadata_result
+                .expect("Metadata should be generated")
+                .dest_bundle;
+            let metadata_json: Value = serde_json::from_slice(&fs::read(&dest_bundle)?)?;
+
+            assert_eq!(metadata_json["source"]["url"], "http://source.com/v2");
+            assert_eq!(
+                metadata_json["contract"]["homepage"],
+                "http://contract.homepage.com/"
+            );
+            assert_eq!(
+                metadata_json["user"],
+                serde_json::json!({
+                    "audit": "https://audits.com/1",
+                    "team": "core",
+                    "ids": [1, 2],
+                })
+            );
             Ok(())
         })
     }
@@ -0,0 +1,96 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Detects constructors and messages sharing a selector, which the contract cannot tell
+//! apart when dispatching a call.
+
+use crate::abi::{ContractAbi, MessageSpec, Selector};
+use std::collections::BTreeMap;
+
+/// Fails if constructors or messages of `abi` share a selector, naming all colliding items.
+///
+/// Constructors and messages are dispatched separately, so a constructor may have the
+/// selector of a message.
+pub(crate) fn check_selectors(abi: &ContractAbi) -> anyhow::Result<()> {
+    let collisions = collisions("constructor", &abi.constructors)
+        .into_iter()
+        .chain(collisions("message", &abi.messages))
+        .map(|(selector, items)| {
+            let (last, others) = items.split_last().expect("a collision has several items");
+            format!(
+                "  {} and {} share the selector {}",
+                others.join(", "),
+                last,
+                selector
+            )
+        })
+        .collect::<Vec<_>>();
+    if !collisions.is_empty() {
+        anyhow::bail!(
+            "Found colliding selectors, the contract can only dispatch calls to one of each:\n{}\n\
+            Give the colliding items distinct selectors with `#[ink(selector = ..)]`.",
+            collisions.join("\n")
+        )
+    }
+    Ok(())
+}
+
+/// Returns the selectors of `specs` which are shared by several of them.
+fn collisions(kind: &str, specs: &[MessageSpec]) -> Vec<(Selector, Vec<String>)> {
+    let mut by_selector = BTreeMap::<_, Vec<_>>::new();
+    for spec in specs {
+        by_selector
+            .entry(spec.selector)
+            .or_default()
+            .push(format!("{} `{}`", kind, spec.label));
+    }
+    by_selector
+        .into_iter()
+        .filter(|(_, items)| items.len() > 1)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+
+    #[test]
+    fn reports_colliding_selectors() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        assert!(check_selectors(&abi).is_ok());
+
+        let mut metadata = metadata_v1();
+        let spec = &mut metadata["V1"]["spec"];
+        // a constructor may share the selector of a message
+        spec["constructors"][0]["selector"] = json!("0x2d10c9bd");
+        spec["messages"][1]["selector"] = json!("0x2d10c9bd");
+        spec["messages"][2]["selector"] = json!("0x2d10c9bd");
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+
+        let error = check_selectors(&abi).unwrap_err().to_string();
+        assert!(
+            error.contains(
+                "message `deposit`, message `Ledger::balance_of` and message `withdraw` share \
+                the selector 0x2d10c9bd"
+            ),
+            "{}",
+            error
+        );
+        assert!(!error.contains("constructor"));
+    }
+}
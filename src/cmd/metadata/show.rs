@@ -0,0 +1,351 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prints a readable summary of the metadata with `cargo contract metadata show`.
+
+use super::{built_metadata_path, read_json};
+use crate::abi::{versioned_project, ArgSpec, ContractAbi, MessageSpec, TypeRef, TypeRegistry};
+use anyhow::Result;
+use colored::Colorize;
+use serde_json::Value;
+use std::{fmt::Write as _, path::PathBuf};
+use structopt::StructOpt;
+
+/// Prints the constructors, messages, events and storage layout of a contract.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "show")]
+pub struct ShowCommand {
+    /// The metadata or `.contract` file to show, the metadata of the built contract if not given
+    #[structopt(parse(from_os_str))]
+    file: Option<PathBuf>,
+    /// Path to the Cargo.toml of the contract whose built metadata to show
+    #[structopt(long, parse(from_os_str), conflicts_with = "file")]
+    manifest_path: Option<PathBuf>,
+}
+
+impl ShowCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let path = match &self.file {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        print!("{}", render(&read_json(&path)?)?);
+        Ok(None)
+    }
+}
+
+/// Renders the summary of the metadata `json`.
+fn render(json: &Value) -> Result<String> {
+    let abi = ContractAbi::from_json(json)?;
+    let (version, project) = versioned_project(json)?;
+    let types = &abi.types;
+    let mut out = String::new();
+
+    // writing to a `String` never fails
+    let name = abi.name.as_deref().unwrap_or("<unnamed contract>");
+    let mut details = vec![version.to_string()];
+    if let Some(version) = json.pointer("/contract/version").and_then(Value::as_str) {
+        details.insert(0, format!("version {}", version));
+    }
+    if let Some(language) = json.pointer("/source/language").and_then(Value::as_str) {
+        details.push(language.to_string());
+    }
+    let _ = writeln!(out, "{} ({})", name.bold(), details.join(", "));
+    write_docs(&mut out, &abi.docs, "  ");
+
+    heading(&mut out, "Constructors");
+    for constructor in &abi.constructors {
+        write_spec(&mut out, constructor, &constructor.label, types, "  ", true);
+    }
+
+    // inherent messages first, followed by the messages of each trait
+    let mut groups: Vec<(Option<&str>, Vec<&MessageSpec>)> = Vec::new();
+    for message in &abi.messages {
+        let group = message.label.rsplit_once("::").map(|(name, _)| name);
+        match groups.iter_mut().find(|(name, _)| *name == group) {
+            Some((_, messages)) => messages.push(message),
+            None => groups.push((group, vec![message])),
+        }
+    }
+    groups.sort_by_key(|(group, _)| group.is_some());
+    heading(&mut out, "Messages");
+    for (group, messages) in groups {
+        let indent = match group {
+            Some(name) => {
+                let _ = writeln!(out, "  {} {}", "impl".dimmed(), name.bold());
+                "    "
+            }
+            None => "  ",
+        };
+        for message in messages {
+            let name = message.label.rsplit("::").next().unwrap_or_default();
+            write_spec(&mut out, message, name, types, indent, false);
+        }
+    }
+
+    heading(&mut out, "Events");
+    for event in &abi.events {
+        let _ = writeln!(out, "  {}", event.label.bright_green());
+        write_docs(&mut out, &event.docs, "    ");
+        for arg in &event.args {
+            let topic = if arg.indexed { "#[topic] " } else { "" };
+            let _ = writeln!(
+                out,
+                "    {}{}: {}",
+                topic.dimmed(),
+                arg.label,
+                type_name(&arg.ty, types)
+            );
+        }
+    }
+
+    heading(&mut out, "Storage");
+    match project.get("storage") {
+        Some(storage) => write_layout(&mut out, None, storage, types, 1),
+        None => {
+            let _ = writeln!(out, "  {}", "<no storage layout>".dimmed());
+        }
+    }
+    Ok(out)
+}
+
+fn heading(out: &mut String, title: &str) {
+    let _ = writeln!(out, "\n{}", title.bold().underline());
+}
+
+fn write_docs(out: &mut String, docs: &[String], indent: &str) {
+    for line in docs {
+        let _ = writeln!(out, "{}{}", indent, line.trim().dimmed());
+    }
+}
+
+/// Writes the signature of a constructor or message with its selector and properties.
+///
+/// Constructors always write the storage, so they are not marked as mutating.
+fn write_spec(
+    out: &mut String,
+    spec: &MessageSpec,
+    name: &str,
+    types: &TypeRegistry,
+    indent: &str,
+    constructor: bool,
+) {
+    let args = spec
+        .args
+        .iter()
+        .map(|ArgSpec { label, ty, .. }| format!("{}: {}", label, type_name(ty, types)))
+        .collect::<Vec<_>>();
+    let returns = match &spec.return_type {
+        Some(ty) => format!(" -> {}", type_name(ty, types)),
+        None => String::new(),
+    };
+    let mut properties = vec![spec.selector.to_string()];
+    if spec.mutates && !constructor {
+        properties.push("mutates".to_string());
+    }
+    if spec.payable {
+        properties.push("payable".to_string());
+    }
+    let _ = writeln!(
+        out,
+        "{}{}({}){}  {}",
+        indent,
+        name.bright_green(),
+        args.join(", "),
+        returns,
+        properties.join(", ").dimmed()
+    );
+    write_docs(out, &spec.docs, &format!("{}  ", indent));
+}
+
+/// Returns the name the type was declared with, e.g. `Balance`, or the name of the type.
+fn type_name(ty: &TypeRef, types: &TypeRegistry) -> String {
+    match ty.display_name.as_slice() {
+        [name] if !types.type_name(ty.id).contains('<') => name.clone(),
+        _ => types.type_name(ty.id),
+    }
+}
+
+/// Writes the storage `layout` as a tree, a line per field.
+///
+/// Covers the layouts of ink! 3 (`cell`, `hash`, `array`, `struct` and `enum`) and the
+/// `root` and `leaf` layouts of ink! 4.
+fn write_layout(
+    out: &mut String,
+    name: Option<&str>,
+    layout: &Value,
+    types: &TypeRegistry,
+    depth: usize,
+) {
+    let indent = "  ".repeat(depth);
+    let label = |description: String| match name {
+        Some(name) => format!("{}{}: {}", indent, name, description),
+        None => format!("{}{}", indent, description),
+    };
+    let key = |layout: &Value, field: &str| {
+        layout
+            .get(field)
+            .and_then(Value::as_str)
+            .map(|key| format!("  {}", format!("at {}", key).dimmed()))
+            .unwrap_or_default()
+    };
+    let (kind, inner) = match layout.as_object().and_then(|layout| layout.iter().next()) {
+        Some((kind, inner)) => (kind.as_str(), inner),
+        None => {
+            let _ = writeln!(out, "{}", label("<unknown layout>".to_string()));
+            return;
+        }
+    };
+    match kind {
+        "cell" | "leaf" => {
+            let ty = inner
+                .get("ty")
+                .and_then(Value::as_u64)
+                .map(|id| types.type_name(id as u32))
+                .unwrap_or_else(|| "?".to_string());
+            let _ = writeln!(out, "{}{}", label(ty), key(inner, "key"));
+        }
+        "struct" => {
+            let fields = inner
+                .get("fields")
+                .and_then(Value::as_array)
+                .cloned()
+                .unwrap_or_default();
+            // the root struct is the storage itself
+            let depth = if name.is_some() || depth > 1 {
+                let struct_name = inner
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .unwrap_or("struct");
+                let _ = writeln!(out, "{}", label(struct_name.to_string()));
+                depth + 1
+            } else {
+                depth
+            };
+            if fields.is_empty() && depth == 1 {
+                let _ = writeln!(out, "{}{}", indent, "<empty>".dimmed());
+            }
+            for (i, field) in fields.iter().enumerate() {
+                let field_name = field
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .map(ToString::to_string)
+                    .unwrap_or_else(|| i.to_string());
+                let field_layout = field.get("layout").unwrap_or(&Value::Null);
+                write_layout(out, Some(&field_name), field_layout, types, depth);
+            }
+        }
+        "root" => {
+            if name.is_some() {
+                let _ = writeln!(
+                    out,
+                    "{}{}",
+                    label("lazily loaded".to_string()),
+                    key(inner, "root_key")
+                );
+                if let Some(layout) = inner.get("layout") {
+                    write_layout(out, None, layout, types, depth + 1);
+                }
+            } else if let Some(layout) = inner.get("layout") {
+                write_layout(out, None, layout, types, depth);
+            }
+        }
+        "hash" => {
+            let _ = writeln!(
+                out,
+                "{}{}",
+                label("hashed storage".to_string()),
+                key(inner, "offset")
+            );
+            if let Some(layout) = inner.get("layout") {
+                write_layout(out, None, layout, types, depth + 1);
+            }
+        }
+        "array" => {
+            let len = inner.get("len").and_then(Value::as_u64).unwrap_or_default();
+            let _ = writeln!(
+                out,
+                "{}{}",
+                label(format!("array of {}", len)),
+                key(inner, "offset")
+            );
+            if let Some(layout) = inner.get("layout") {
+                write_layout(out, None, layout, types, depth + 1);
+            }
+        }
+        "enum" => {
+            let name = inner.get("name").and_then(Value::as_str).unwrap_or("enum");
+            let _ = writeln!(out, "{}", label(name.to_string()));
+            if let Some(variants) = inner.get("variants").and_then(Value::as_object) {
+                for (index, variant) in variants {
+                    let variant = serde_json::json!({ "struct": variant });
+                    write_layout(out, Some(index), &variant, types, depth + 1);
+                }
+            }
+        }
+        _ => {
+            let _ = writeln!(out, "{}", label(format!("<unknown layout `{}`>", kind)));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+
+    #[test]
+    fn renders_summary() {
+        let mut metadata = metadata_v1();
+        metadata["V1"]["storage"] = json!({
+            "struct": { "fields": [
+                { "name": "total", "layout": { "cell": { "key": "0x00", "ty": 0 } } },
+                { "name": "owner", "layout": { "struct": { "fields": [
+                    { "layout": { "cell": { "key": "0x01", "ty": 1 } } }
+                ] } } }
+            ] }
+        });
+        colored::control::set_override(false);
+        assert_eq!(
+            render(&metadata).unwrap(),
+            "\
+bank (version 0.1.0, V1, ink! 3.0.0-rc6)
+
+Constructors
+  new(limit: Balance)  0x9bae9d5e, payable
+    Creates a bank.
+
+Messages
+  deposit()  0x2d10c9bd, mutates, payable
+    Deposits the transferred value.
+  withdraw(value: Balance) -> Result<(), Error>  0x410fcc9d, mutates
+  impl Ledger
+    balance_of(owner: AccountId) -> Balance  0x0f755a56
+
+Events
+  Deposited
+    #[topic] from: Option<AccountId>
+    value: Balance
+
+Storage
+  total: u128  at 0x00
+  owner: struct
+    0: AccountId  at 0x01
+"
+        );
+    }
+}
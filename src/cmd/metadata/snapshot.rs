@@ -0,0 +1,225 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Keeps a snapshot of the interface of a contract with `cargo contract metadata snapshot`.
+//!
+//! The snapshot is the metadata without what does not concern callers: the `source` of the
+//! build, the version and authors of the contract, the storage layout and the docs. Its keys are
+//! sorted, so that it only changes with the interface. Committed next to the code, `--check`
+//! fails once the interface changes incompatibly with the snapshot.
+
+use super::{
+    built_metadata_path,
+    diff::{self, diff},
+    hash::canonical_json,
+    read_json,
+};
+use crate::{abi::ContractAbi, workspace::ManifestPath};
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use std::{convert::TryFrom, fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// The file of the snapshot, next to the `Cargo.toml` of the contract.
+const SNAPSHOT_FILE: &str = "abi-snapshot.json";
+
+/// Writes a snapshot of the interface of the contract, or checks the interface against it.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "snapshot")]
+pub struct SnapshotCommand {
+    /// Path to the Cargo.toml of the contract whose built metadata to snapshot
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to read instead of the metadata of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The file of the snapshot, `abi-snapshot.json` next to the Cargo.toml of the contract by
+    /// default
+    #[structopt(long, parse(from_os_str))]
+    snapshot: Option<PathBuf>,
+    /// Compare the interface with the snapshot instead of writing it, failing if it changed
+    /// incompatibly
+    #[structopt(long)]
+    check: bool,
+}
+
+impl SnapshotCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let metadata_path = match &self.metadata {
+            Some(path) => path.clone(),
+            None => built_metadata_path(self.manifest_path.as_ref())?,
+        };
+        let interface = normalize(&read_json(&metadata_path)?)?;
+        let snapshot_path = self.snapshot_path()?;
+
+        if !self.check {
+            fs::write(
+                &snapshot_path,
+                serde_json::to_string_pretty(&interface)? + "\n",
+            )
+            .with_context(|| format!("Failed to write {}", snapshot_path.display()))?;
+            return Ok(Some(format!(
+                "Written the ABI snapshot to {}",
+                snapshot_path.display()
+            )));
+        }
+
+        if !snapshot_path.exists() {
+            anyhow::bail!(
+                "No ABI snapshot found at {}, write it with `cargo contract metadata snapshot`",
+                snapshot_path.display()
+            )
+        }
+        let snapshot = ContractAbi::from_json(&read_json(&snapshot_path)?).with_context(|| {
+            format!(
+                "Failed to read the ABI snapshot {}",
+                snapshot_path.display()
+            )
+        })?;
+        let changes = diff(&snapshot, &ContractAbi::from_json(&interface)?);
+        if changes.is_empty() {
+            return Ok(Some("The ABI matches the snapshot".to_string()));
+        }
+        println!("{}", diff::report(&changes));
+        let breaking = changes.iter().filter(|change| change.breaking).count();
+        if breaking > 0 {
+            anyhow::bail!(
+                "The ABI changed incompatibly with the snapshot {}. If the break is intended, \
+                update the snapshot with `cargo contract metadata snapshot`",
+                snapshot_path.display()
+            )
+        }
+        eprintln!(
+            "{} The ABI changed compatibly, update the snapshot with `cargo contract metadata \
+            snapshot`",
+            "warning:".yellow().bold(),
+        );
+        Ok(None)
+    }
+
+    fn snapshot_path(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.snapshot {
+            return Ok(path.clone());
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())
+            .context("Pass --snapshot to keep the snapshot outside of a contract")?;
+        Ok(manifest_path.absolute_directory()?.join(SNAPSHOT_FILE))
+    }
+}
+
+/// Returns the interface of the contract of `metadata`, with the keys of objects sorted.
+pub(crate) fn normalize(metadata: &Value) -> Result<Value> {
+    // fails early on metadata which is no ABI
+    ContractAbi::from_json(metadata)?;
+    let mut interface = metadata.clone();
+    if let Some(root) = interface.as_object_mut() {
+        root.remove("source");
+        if let Some(contract) = root.get_mut("contract").and_then(Value::as_object_mut) {
+            let name = contract.remove("name");
+            contract.clear();
+            contract.extend(name.map(|name| ("name".to_string(), name)));
+        }
+    }
+    // the spec, storage and types are nested in the version before ink! 4
+    let project = match ["V3", "V2", "V1"]
+        .iter()
+        .find(|key| interface.get(**key).is_some())
+    {
+        Some(key) => &mut interface[*key],
+        None => &mut interface,
+    };
+    if let Some(project) = project.as_object_mut() {
+        project.remove("storage");
+    }
+    remove_docs(&mut interface);
+    // parsing the canonical serialization keeps its order of keys with `preserve_order`
+    Ok(serde_json::from_str(&canonical_json(&interface))?)
+}
+
+fn remove_docs(json: &mut Value) {
+    match json {
+        Value::Array(items) => items.iter_mut().for_each(remove_docs),
+        Value::Object(map) => {
+            map.remove("docs");
+            map.values_mut().for_each(remove_docs);
+        }
+        _ => (),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{abi::tests::metadata_v1, util::tests::with_tmp_dir};
+    use serde_json::json;
+
+    #[test]
+    fn snapshots_only_the_interface() {
+        let metadata = metadata_v1();
+        let interface = normalize(&metadata).unwrap();
+        assert!(interface.get("source").is_none());
+        assert_eq!(interface["contract"], json!({ "name": "bank" }));
+        assert!(interface["V1"].get("storage").is_none());
+        assert!(interface["V1"]["spec"]["messages"][0].get("docs").is_none());
+
+        let mut rebuilt = metadata;
+        rebuilt["contract"]["version"] = json!("0.2.0");
+        rebuilt["V1"]["spec"]["messages"][0]["docs"] = json!(["Pays in."]);
+        assert_eq!(normalize(&rebuilt).unwrap(), interface);
+    }
+
+    #[test]
+    fn check_fails_on_breaking_changes_only() {
+        with_tmp_dir(|path| {
+            let snapshot = path.join(SNAPSHOT_FILE);
+            let interface = normalize(&metadata_v1())?;
+            fs::write(&snapshot, serde_json::to_string_pretty(&interface)?)?;
+            let check = |metadata: &Value| {
+                let metadata_path = path.join("metadata.json");
+                fs::write(&metadata_path, metadata.to_string())?;
+                let cmd = SnapshotCommand {
+                    manifest_path: None,
+                    metadata: Some(metadata_path),
+                    snapshot: Some(snapshot.clone()),
+                    check: true,
+                };
+                cmd.exec()
+            };
+
+            let mut metadata = metadata_v1();
+            assert_eq!(
+                check(&metadata)?,
+                Some("The ABI matches the snapshot".to_string())
+            );
+
+            // a message is added
+            let mut close = metadata["V1"]["spec"]["messages"][0].clone();
+            close["name"] = json!(["close"]);
+            close["selector"] = json!("0xdeadbeef");
+            metadata["V1"]["spec"]["messages"]
+                .as_array_mut()
+                .unwrap()
+                .push(close);
+            assert_eq!(check(&metadata)?, None);
+
+            metadata["V1"]["spec"]["messages"][1]["selector"] = json!("0x00000001");
+            let err = check(&metadata).unwrap_err();
+            assert!(err.to_string().starts_with("The ABI changed incompatibly"));
+            Ok(())
+        })
+    }
+}
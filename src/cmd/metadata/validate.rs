@@ -0,0 +1,399 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Checks metadata against the formats of the known ink! metadata versions with
+//! `cargo contract metadata validate`.
+
+use super::read_json;
+use crate::abi::{versioned_project, MetadataVersion, Selector};
+use anyhow::Result;
+use serde_json::Value;
+use std::path::PathBuf;
+use structopt::StructOpt;
+
+/// Checks that a metadata or `.contract` file conforms to a known ink! metadata version.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "validate")]
+pub struct ValidateCommand {
+    /// The metadata or `.contract` file to check
+    #[structopt(parse(from_os_str))]
+    file: PathBuf,
+}
+
+impl ValidateCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let json = read_json(&self.file)?;
+        let (version, errors) = validate(&json)?;
+        if !errors.is_empty() {
+            anyhow::bail!(
+                "{} does not conform to the {} ink! metadata format:\n  {}",
+                self.file.display(),
+                version,
+                errors.join("\n  ")
+            )
+        }
+        Ok(Some(format!(
+            "{} conforms to the {} ink! metadata format",
+            self.file.display(),
+            version
+        )))
+    }
+}
+
+/// Returns the version the metadata claims to be of and where it deviates from its format.
+///
+/// Fails if the metadata is of no known version at all.
+pub(crate) fn validate(json: &Value) -> Result<(MetadataVersion, Vec<String>)> {
+    if !json.is_object() {
+        anyhow::bail!("The metadata must be a JSON object")
+    }
+    let (version, project) = versioned_project(json)?;
+    let path = match version {
+        MetadataVersion::V1 | MetadataVersion::V2 | MetadataVersion::V3 => version.to_string(),
+        MetadataVersion::V0 | MetadataVersion::V4 => String::new(),
+    };
+    let mut validator = Validator {
+        version,
+        errors: Vec::new(),
+        type_count: 0,
+    };
+    if version == MetadataVersion::V0 {
+        validator.string(json, "", "metadataVersion");
+    }
+    validator.project(project, &path);
+    Ok((version, validator.errors))
+}
+
+struct Validator {
+    version: MetadataVersion,
+    errors: Vec<String>,
+    /// The number of types in the registry, for checking references to them.
+    type_count: u64,
+}
+
+impl Validator {
+    fn error(&mut self, path: &str, message: &str) {
+        let path = if path.is_empty() { "<root>" } else { path };
+        self.errors.push(format!("{}: {}", path, message))
+    }
+
+    /// Returns the field `key` of `value` at `path` and its path, reporting it if missing.
+    fn field<'a>(
+        &mut self,
+        value: &'a Value,
+        path: &str,
+        key: &str,
+    ) -> Option<(&'a Value, String)> {
+        let field_path = join(path, key);
+        match value.get(key) {
+            Some(field) => Some((field, field_path)),
+            None => {
+                self.error(&field_path, "is missing");
+                None
+            }
+        }
+    }
+
+    fn array<'a>(
+        &mut self,
+        value: &'a Value,
+        path: &str,
+        key: &str,
+    ) -> Option<(&'a [Value], String)> {
+        let (field, path) = self.field(value, path, key)?;
+        match field.as_array() {
+            Some(array) => Some((array.as_slice(), path)),
+            None => {
+                self.error(&path, "must be an array");
+                None
+            }
+        }
+    }
+
+    fn string(&mut self, value: &Value, path: &str, key: &str) {
+        if let Some((field, path)) = self.field(value, path, key) {
+            if !field.is_string() {
+                self.error(&path, "must be a string")
+            }
+        }
+    }
+
+    fn bool(&mut self, value: &Value, path: &str, key: &str) {
+        if let Some((field, path)) = self.field(value, path, key) {
+            if !field.is_boolean() {
+                self.error(&path, "must be a boolean")
+            }
+        }
+    }
+
+    fn docs(&mut self, value: &Value, path: &str) {
+        if let Some((docs, path)) = self.array(value, path, "docs") {
+            if !docs.iter().all(Value::is_string) {
+                self.error(&path, "must be an array of strings")
+            }
+        }
+    }
+
+    /// Checks the `spec`, `storage` and `types` of the contract.
+    fn project(&mut self, project: &Value, path: &str) {
+        if let Some((types, path)) = self.array(project, path, "types") {
+            self.type_count = types.len() as u64;
+            for (i, ty) in types.iter().enumerate() {
+                self.type_entry(ty, &format!("{}[{}]", path, i), i as u64);
+            }
+        }
+        if let Some((storage, path)) = self.field(project, path, "storage") {
+            if !storage.is_object() {
+                self.error(&path, "must be an object")
+            }
+        }
+        let (spec, path) = match self.field(project, path, "spec") {
+            Some(spec) => spec,
+            None => return,
+        };
+        if let Some((constructors, path)) = self.array(spec, &path, "constructors") {
+            if constructors.is_empty() {
+                self.error(&path, "must contain at least one constructor")
+            }
+            for (i, constructor) in constructors.iter().enumerate() {
+                self.message(constructor, &format!("{}[{}]", path, i), true);
+            }
+        }
+        if let Some((messages, path)) = self.array(spec, &path, "messages") {
+            for (i, message) in messages.iter().enumerate() {
+                self.message(message, &format!("{}[{}]", path, i), false);
+            }
+        }
+        if let Some((events, path)) = self.array(spec, &path, "events") {
+            for (i, event) in events.iter().enumerate() {
+                let path = format!("{}[{}]", path, i);
+                self.label(event, &path, false);
+                if let Some((args, path)) = self.array(event, &path, "args") {
+                    for (i, arg) in args.iter().enumerate() {
+                        let path = format!("{}[{}]", path, i);
+                        self.arg(arg, &path);
+                        self.bool(arg, &path, "indexed");
+                    }
+                }
+                self.docs(event, &path);
+            }
+        }
+        self.docs(spec, &path);
+    }
+
+    fn message(&mut self, message: &Value, path: &str, constructor: bool) {
+        self.label(message, path, true);
+        if let Some((selector, path)) = self.field(message, path, "selector") {
+            if !matches!(selector.as_str().map(Selector::from_hex), Some(Ok(_))) {
+                self.error(&path, "must be a 4 byte hex string, e.g. \"0x9bae9d5e\"")
+            }
+        }
+        if let Some((args, path)) = self.array(message, path, "args") {
+            for (i, arg) in args.iter().enumerate() {
+                self.arg(arg, &format!("{}[{}]", path, i));
+            }
+        }
+        if constructor {
+            // constructors are payable since ink! 3
+            if self.version >= MetadataVersion::V3 {
+                self.bool(message, path, "payable");
+            }
+        } else {
+            self.bool(message, path, "mutates");
+            self.bool(message, path, "payable");
+            if let Some((return_type, path)) = self.field(message, path, "returnType") {
+                if !return_type.is_null() {
+                    self.type_ref(return_type, &path);
+                }
+            }
+        }
+        self.docs(message, path);
+    }
+
+    fn arg(&mut self, arg: &Value, path: &str) {
+        // unlike the ones of messages, the names of arguments always were plain strings
+        let key = if self.version >= MetadataVersion::V2 {
+            "label"
+        } else {
+            "name"
+        };
+        self.string(arg, path, key);
+        if let Some((ty, path)) = self.field(arg, path, "type") {
+            self.type_ref(ty, &path);
+        }
+    }
+
+    /// Checks the `label` of ink! 3 and later, or the `name` before, which is a path for
+    /// constructors and messages.
+    fn label(&mut self, value: &Value, path: &str, is_path: bool) {
+        if self.version >= MetadataVersion::V2 {
+            return self.string(value, path, "label");
+        }
+        if !is_path {
+            return self.string(value, path, "name");
+        }
+        if let Some((name, path)) = self.field(value, path, "name") {
+            let is_path = matches!(
+                name.as_array(),
+                Some(segments) if !segments.is_empty() && segments.iter().all(Value::is_string)
+            );
+            if !is_path {
+                self.error(&path, "must be a non-empty array of strings")
+            }
+        }
+    }
+
+    fn type_ref(&mut self, value: &Value, path: &str) {
+        if let Some((id, path)) = self.field(value, path, "type") {
+            self.type_id(id, &path);
+        }
+        if let Some((display_name, path)) = self.field(value, path, "displayName") {
+            match display_name.as_array() {
+                Some(segments) if segments.iter().all(Value::is_string) => (),
+                _ => self.error(&path, "must be an array of strings"),
+            }
+        }
+    }
+
+    /// Checks that `id` refers to a type of the registry, whose ids start at 1 before ink! 3.
+    fn type_id(&mut self, id: &Value, path: &str) {
+        let first = if self.version == MetadataVersion::V0 {
+            1
+        } else {
+            0
+        };
+        match id.as_u64() {
+            Some(id) if id >= first && id - first < self.type_count => (),
+            Some(id) => self.error(path, &format!("refers to the unknown type {}", id)),
+            None => self.error(path, "must be a type id"),
+        }
+    }
+
+    /// Checks an entry of the type registry, a plain type before `scale-info` 1.0.
+    fn type_entry(&mut self, entry: &Value, path: &str, index: u64) {
+        if self.version == MetadataVersion::V0 {
+            return self.type_def(entry, path);
+        }
+        if let Some((id, path)) = self.field(entry, path, "id") {
+            if id.as_u64() != Some(index) {
+                self.error(&path, &format!("must be {}, the index of the type", index))
+            }
+        }
+        if let Some((ty, path)) = self.field(entry, path, "type") {
+            self.type_def(ty, &path)
+        }
+    }
+
+    fn type_def(&mut self, ty: &Value, path: &str) {
+        let (def, path) = match self.field(ty, path, "def") {
+            Some(def) => def,
+            None => return,
+        };
+        let kinds = [
+            "composite",
+            "variant",
+            "sequence",
+            "array",
+            "tuple",
+            "primitive",
+            "compact",
+            "bitSequence",
+            "phantom",
+        ];
+        match def.as_object().map(|def| def.iter().collect::<Vec<_>>()) {
+            Some(entries) if entries.len() == 1 && kinds.contains(&entries[0].0.as_str()) => {
+                let (kind, value) = entries[0];
+                let path = join(&path, kind);
+                match kind.as_str() {
+                    "sequence" | "array" | "compact" => {
+                        if let Some((id, path)) = self.field(value, &path, "type") {
+                            self.type_id(id, &path);
+                        }
+                    }
+                    "tuple" => match value.as_array() {
+                        Some(ids) => {
+                            for (i, id) in ids.iter().enumerate() {
+                                self.type_id(id, &format!("{}[{}]", path, i));
+                            }
+                        }
+                        None => self.error(&path, "must be an array of type ids"),
+                    },
+                    "primitive" if !value.is_string() => {
+                        self.error(&path, "must be the name of a primitive type")
+                    }
+                    _ => (),
+                }
+            }
+            _ => self.error(
+                &path,
+                &format!(
+                    "must be an object with one of the keys {}",
+                    kinds.join(", ")
+                ),
+            ),
+        }
+    }
+}
+
+fn join(path: &str, key: &str) -> String {
+    if path.is_empty() {
+        key.to_string()
+    } else {
+        format!("{}.{}", path, key)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+
+    #[test]
+    fn accepts_valid_metadata() {
+        let (version, errors) = validate(&metadata_v1()).unwrap();
+        assert_eq!(version, MetadataVersion::V1);
+        assert_eq!(errors, Vec::<String>::new());
+    }
+
+    #[test]
+    fn reports_deviations_from_the_format() {
+        let mut metadata = metadata_v1();
+        let spec = &mut metadata["V1"]["spec"];
+        spec["messages"][0]["selector"] = json!("0x2d10");
+        spec["messages"][1]["args"][0]["type"]["type"] = json!(42);
+        spec["events"][0]["args"][0]
+            .as_object_mut()
+            .unwrap()
+            .remove("indexed");
+        // before ink! 3 the names of messages are paths
+        spec["constructors"][0]["name"] = json!("new");
+
+        let (version, errors) = validate(&metadata).unwrap();
+        assert_eq!(version, MetadataVersion::V1);
+        assert_eq!(
+            errors,
+            vec![
+                "V1.spec.constructors[0].name: must be a non-empty array of strings",
+                "V1.spec.messages[0].selector: must be a 4 byte hex string, e.g. \"0x9bae9d5e\"",
+                "V1.spec.messages[1].args[0].type.type: refers to the unknown type 42",
+                "V1.spec.events[0].args[0].indexed: is missing",
+            ]
+        );
+
+        assert!(validate(&json!({ "version": "5", "spec": {} })).is_err());
+    }
+}
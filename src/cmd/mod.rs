@@ -14,18 +14,152 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+#[cfg(feature = "extrinsics")]
+pub(crate) mod account;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod addressbook;
+#[cfg(feature = "extrinsics")]
+mod batch;
+mod benchmark;
 pub mod build;
+pub mod bundle;
+#[cfg(feature = "extrinsics")]
+mod call;
+#[cfg(feature = "extrinsics")]
+mod chain;
+#[cfg(feature = "extrinsics")]
+mod compat;
+#[cfg(feature = "extrinsics")]
+mod confirm;
+#[cfg(feature = "extrinsics")]
+mod convert_address;
+pub mod decode;
 #[cfg(feature = "extrinsics")]
 mod deploy;
+pub mod deployment;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod deployments;
+#[cfg(feature = "extrinsics")]
+mod dispatch_error;
+pub mod doc;
+#[cfg(feature = "extrinsics")]
+mod download;
+#[cfg(feature = "extrinsics")]
+mod dry_run;
+pub mod encode;
+#[cfg(feature = "extrinsics")]
+mod estimate_gas;
+#[cfg(feature = "extrinsics")]
+mod events;
+#[cfg(feature = "extrinsics")]
+mod explorer;
+#[cfg(feature = "extrinsics")]
+mod extrinsic_output;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod fork;
+mod fuzz;
+#[cfg(feature = "extrinsics")]
+mod gas;
+pub mod generate_client;
+mod hash;
+pub mod info;
 #[cfg(feature = "extrinsics")]
 mod instantiate;
+#[cfg(feature = "extrinsics")]
+mod key;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod ledger;
 pub mod metadata;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod network;
 pub mod new;
+mod node;
+#[cfg(feature = "extrinsics")]
+mod nonce;
+#[cfg(feature = "extrinsics")]
+mod offline;
+#[cfg(feature = "extrinsics")]
+mod reconnect;
+#[cfg(feature = "extrinsics")]
+mod remove;
+#[cfg(feature = "extrinsics")]
+mod repl;
+#[cfg(feature = "extrinsics")]
+mod rpc;
+mod sandbox;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod schedule;
+pub mod selector;
+#[cfg(feature = "extrinsics")]
+pub(crate) mod signer_cmd;
+#[cfg(feature = "extrinsics")]
+mod signing;
+#[cfg(feature = "extrinsics")]
+mod state_call;
+#[cfg(feature = "extrinsics")]
+mod storage;
+#[cfg(feature = "extrinsics")]
+mod submit;
 pub mod test;
+#[cfg(feature = "extrinsics")]
+mod token;
+#[cfg(feature = "extrinsics")]
+mod upgrade;
+#[cfg(feature = "extrinsics")]
+mod upload;
+#[cfg(feature = "extrinsics")]
+mod verify;
+#[cfg(feature = "extrinsics")]
+mod watch;
+#[cfg(feature = "extrinsics")]
+mod xcm;
 
+#[cfg(feature = "extrinsics")]
 pub(crate) use self::{
+    account::AccountCommand,
+    addressbook::AddressBookCommand,
+    batch::BatchCommand,
+    call::CallCommand,
+    chain::{parse_account, parse_account_id, AccountId},
+    convert_address::ConvertAddressCommand,
+    deploy::execute_deploy,
+    deployment::execute_deployment,
+    dispatch_error::DecodeErrorCommand,
+    download::DownloadCommand,
+    estimate_gas::EstimateGasCommand,
+    explorer::Explorer,
+    instantiate::{execute_instantiate, InstantiateCommand},
+    key::KeyCommand,
+    offline::SubmitSignedCommand,
+    remove::{dry_run_remove, execute_remove},
+    repl::ReplCommand,
+    signing::{EthereumSigner, KeypairSigner, Signer},
+    state_call::RpcCommand,
+    storage::StorageCommand,
+    submit::{exit_code, SubmitError, Submitted},
+    token::ConvertBalanceCommand,
+    upgrade::UpgradeCommand,
+    upload::{dry_run_upload, execute_upload, offline_upload},
+    verify::VerifyCommand,
+    watch::WatchCommand,
+    xcm::XcmCallCommand,
+};
+pub(crate) use self::{
+    benchmark::BenchmarkCommand,
     build::{BuildCommand, CheckCommand},
+    bundle::BundleCommand,
+    decode::{DecodeCommand, DecodeTypeCommand},
+    deployment::DeploymentCommand,
+    doc::DocCommand,
+    encode::EncodeCommand,
+    fuzz::FuzzCommand,
+    generate_client::GenerateClientCommand,
+    hash::HashCommand,
+    info::InfoCommand,
+    metadata::MetadataCommand,
+    new::NewCommand,
+    node::NodeCommand,
+    sandbox::SandboxCommand,
+    selector::SelectorCommand,
     test::TestCommand,
 };
-#[cfg(feature = "extrinsics")]
-pub(crate) use self::{deploy::execute_deploy, instantiate::execute_instantiate};
@@ -0,0 +1,176 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Named networks selected with `--network`, configured in the `contract.toml` of the project
+//! and in the `config.toml` of the config directory, e.g.
+//!
+//! ```toml
+//! [networks.astar]
+//! url = "wss://rpc.astar.network"
+//! account = "deployer"
+//! explorer = "https://astar.subscan.io"
+//! ```
+//!
+//! The url, the account and the explorer of the network are used unless they are given
+//! explicitly. The `contract.toml` of the project is usually committed, so only the config
+//! directory may configure the secret key URI of a network as its `suri`.
+//!
+//! The same files configure the `registry` which `cargo contract download` fetches the metadata
+//! of contracts from, e.g. `registry = "https://verifier.example.com"`.
+
+use std::collections::BTreeMap;
+
+use anyhow::Result;
+use serde::{de::Error as _, Deserialize, Deserializer};
+use url::Url;
+
+use super::Explorer;
+use crate::util::{self, PROJECT_CONFIG};
+
+#[derive(Debug, Default, Deserialize)]
+struct Config {
+    #[serde(default)]
+    networks: BTreeMap<String, Network>,
+    /// The verification or registry service serving the metadata of contracts.
+    registry: Option<Url>,
+}
+
+/// A network configured for `--network`.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "kebab-case", deny_unknown_fields)]
+pub(crate) struct Network {
+    pub url: Option<Url>,
+    /// The secret key URI of the account signing the extrinsics.
+    pub suri: Option<String>,
+    /// The name of an account stored with `cargo contract account`.
+    pub account: Option<String>,
+    /// The block explorer of the network, `polkadot-js` or the url of a Subscan explorer.
+    #[serde(default, deserialize_with = "explorer")]
+    pub explorer: Option<Explorer>,
+}
+
+fn explorer<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Option<Explorer>, D::Error> {
+    let explorer = String::deserialize(deserializer)?;
+    explorer.parse().map(Some).map_err(D::Error::custom)
+}
+
+/// Returns the network `name`, the networks of the project overriding the global networks of
+/// the same name, or `None` if it is not configured.
+pub(crate) fn lookup(name: &str) -> Result<Option<Network>> {
+    let (project, global) = util::load_configs::<Config>()?;
+    find(
+        name,
+        project.unwrap_or_default(),
+        global.unwrap_or_default(),
+    )
+}
+
+/// Returns the configured registry of contract metadata, the one of the project overriding the
+/// global one.
+pub(crate) fn registry() -> Result<Option<Url>> {
+    let (project, global) = util::load_configs::<Config>()?;
+    Ok(project
+        .and_then(|config| config.registry)
+        .or_else(|| global.and_then(|config| config.registry)))
+}
+
+fn find(name: &str, mut project: Config, mut global: Config) -> Result<Option<Network>> {
+    if let Some(network) = project.networks.remove(name) {
+        if network.suri.is_some() {
+            anyhow::bail!(
+                "The network `{}` of {} configures a `suri`, which is only read from the config \
+                 directory as {} is usually committed. Configure an `account` instead",
+                name,
+                PROJECT_CONFIG,
+                PROJECT_CONFIG
+            )
+        }
+        return Ok(Some(network));
+    }
+    Ok(global.networks.remove(name))
+}
+
+/// Returns the error of the network `name` which is not configured.
+pub(crate) fn not_configured(name: &str) -> anyhow::Error {
+    anyhow::anyhow!(
+        "The network `{}` is neither configured in {} nor in the config directory",
+        name,
+        PROJECT_CONFIG
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn config(toml: &str) -> Config {
+        toml::from_str(toml).unwrap()
+    }
+
+    #[test]
+    fn prefers_the_networks_of_the_project() {
+        let global = r#"
+            [networks.astar]
+            url = "wss://rpc.astar.network"
+            suri = "//Alice"
+
+            [networks.shiden]
+            url = "wss://rpc.shiden.astar.network"
+            "#;
+        let project = config(
+            r#"
+            [networks.astar]
+            url = "wss://astar.api.onfinality.io/public-ws"
+            account = "deployer"
+            explorer = "https://astar.subscan.io"
+            "#,
+        );
+
+        let astar = find("astar", project, config(global)).unwrap();
+        assert_eq!(
+            astar,
+            Some(Network {
+                url: Some(Url::parse("wss://astar.api.onfinality.io/public-ws").unwrap()),
+                suri: None,
+                account: Some("deployer".to_string()),
+                explorer: Some(Explorer::Subscan(
+                    Url::parse("https://astar.subscan.io").unwrap()
+                )),
+            })
+        );
+        let shiden = find("shiden", Config::default(), config(global)).unwrap();
+        assert_eq!(
+            shiden.and_then(|shiden| shiden.url).unwrap().as_str(),
+            "wss://rpc.shiden.astar.network/"
+        );
+        assert_eq!(
+            find("rococo", Config::default(), config(global)).unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn refuses_secret_key_uris_in_the_project() {
+        let project = config(
+            r#"
+            [networks.local]
+            suri = "//Alice"
+            "#,
+        );
+        assert!(find("local", project, Config::default()).is_err());
+        assert!(toml::from_str::<Config>("[networks.local]\nflags = []").is_err());
+    }
+}
@@ -0,0 +1,219 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Renders a contract skeleton with the constructors, messages and events of existing
+//! contract metadata.
+
+use crate::{
+    abi::{ArgSpec, ContractAbi, MessageSpec},
+    codegen::{
+        fn_names,
+        rust::{write_docs, TypeWriter},
+        type_names,
+    },
+};
+use anyhow::Result;
+use std::fmt::Write as _;
+
+/// Renders the `lib.rs` of a contract with the ABI of `abi`.
+///
+/// The messages keep their selectors, but their bodies are left to be implemented. The
+/// result still contains the `{{name}}` and `{{camel_name}}` template placeholders.
+pub fn render(abi: &ContractAbi) -> Result<String> {
+    let names = type_names(abi);
+    let gen = TypeWriter::new(
+        abi,
+        &names,
+        "    ",
+        &[
+            "#[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]",
+            "#[cfg_attr(feature = \"std\", derive(scale_info::TypeInfo))]",
+        ],
+        true,
+    );
+
+    let mut body = String::new();
+    let w = &mut body;
+    // writing to a `String` never fails
+    for (id, name) in &names {
+        let ty = abi.types.resolve(*id)?;
+        // the types of the environment are provided by ink!
+        if ty.path.first().map(String::as_str) == Some("ink_env") {
+            continue;
+        }
+        gen.write_type(w, name, ty)?;
+    }
+
+    let _ = writeln!(w, "    /// Defines the storage of your contract.");
+    let _ = writeln!(w, "    ///");
+    let _ = writeln!(
+        w,
+        "    /// The metadata does not describe the storage in a way it can be restored from."
+    );
+    let _ = writeln!(w, "    #[ink(storage)]");
+    let _ = writeln!(w, "    pub struct {{{{camel_name}}}} {{}}\n");
+
+    for event in &abi.events {
+        write_docs(w, &event.docs, "    ");
+        let _ = writeln!(w, "    #[ink(event)]");
+        let _ = writeln!(w, "    pub struct {} {{", event.label);
+        for arg in &event.args {
+            if arg.indexed {
+                let _ = writeln!(w, "        #[ink(topic)]");
+            }
+            let _ = writeln!(w, "        {}: {},", arg.label, gen.arg_type(&arg.ty)?);
+        }
+        let _ = writeln!(w, "    }}\n");
+    }
+
+    let _ = writeln!(w, "    impl {{{{camel_name}}}} {{");
+    let constructors = fn_names(&abi.constructors);
+    let messages = fn_names(&abi.messages);
+    let specs = abi
+        .constructors
+        .iter()
+        .zip(constructors)
+        .map(|(spec, name)| (spec, name, true))
+        .chain(
+            abi.messages
+                .iter()
+                .zip(messages)
+                .map(|(spec, name)| (spec, name, false)),
+        );
+    for (i, (spec, name, constructor)) in specs.enumerate() {
+        if i > 0 {
+            let _ = writeln!(w);
+        }
+        write_fn(w, &gen, spec, &name, constructor)?;
+    }
+    let _ = writeln!(w, "    }}");
+    let _ = writeln!(w, "}}");
+
+    let mut out = String::new();
+    let _ = writeln!(out, "#![cfg_attr(not(feature = \"std\"), no_std)]\n");
+    let _ = writeln!(out, "use ink_lang as ink;\n");
+    write_docs(&mut out, &abi.docs, "");
+    let _ = writeln!(out, "#[ink::contract]");
+    let _ = writeln!(out, "mod {{{{name}}}} {{");
+    match (gen.uses_vec(), gen.uses_string()) {
+        (true, true) => {
+            let _ = writeln!(out, "    use ink_prelude::{{string::String, vec::Vec}};\n");
+        }
+        (true, false) => {
+            let _ = writeln!(out, "    use ink_prelude::vec::Vec;\n");
+        }
+        (false, true) => {
+            let _ = writeln!(out, "    use ink_prelude::string::String;\n");
+        }
+        (false, false) => (),
+    }
+    out.push_str(&body);
+    Ok(out)
+}
+
+/// Writes the constructor or message `spec` with an unimplemented body.
+fn write_fn(
+    w: &mut String,
+    gen: &TypeWriter,
+    spec: &MessageSpec,
+    name: &str,
+    constructor: bool,
+) -> Result<()> {
+    write_docs(w, &spec.docs, "        ");
+    let args = spec
+        .args
+        .iter()
+        .map(|ArgSpec { label, ty, .. }| Ok(format!("{}: {}", label, gen.arg_type(ty)?)))
+        .collect::<Result<Vec<_>>>()?;
+    if constructor {
+        let _ = writeln!(
+            w,
+            "        #[ink(constructor, selector = {})]",
+            spec.selector
+        );
+        let _ = writeln!(w, "        pub fn {}({}) -> Self {{", name, args.join(", "));
+        // the arguments are kept for the implementation
+        for arg in &spec.args {
+            let _ = writeln!(w, "            let _ = {};", arg.label);
+        }
+        let _ = writeln!(w, "            Self {{}}");
+    } else {
+        let payable = if spec.payable { "payable, " } else { "" };
+        let _ = writeln!(
+            w,
+            "        #[ink(message, {}selector = {})]",
+            payable, spec.selector
+        );
+        let receiver = if spec.mutates { "&mut self" } else { "&self" };
+        let args = std::iter::once(receiver.to_string())
+            .chain(args)
+            .collect::<Vec<_>>();
+        let returns = match &spec.return_type {
+            Some(ty) => format!(" -> {}", gen.arg_type(ty)?),
+            None => String::new(),
+        };
+        let _ = writeln!(
+            w,
+            "        pub fn {}({}){} {{",
+            name,
+            args.join(", "),
+            returns
+        );
+        let _ = writeln!(w, "            todo!()");
+    }
+    let _ = writeln!(w, "        }}");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn renders_messages_with_their_selectors() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let lib = render(&abi).unwrap();
+
+        assert!(lib.contains("    pub enum Error {\n        InsufficientBalance,\n        LimitExceeded {\n            limit: u128,\n        },\n    }"));
+        assert!(!lib.contains("pub struct AccountId"));
+        assert!(!lib.contains("ink_prelude"));
+        assert!(lib.contains(
+            "        #[ink(topic)]\n        from: Option<AccountId>,\n        value: Balance,"
+        ));
+        assert!(lib.contains(
+            "        #[ink(constructor, selector = 0x9bae9d5e)]\n        pub fn new(limit: Balance) -> Self {"
+        ));
+        assert!(lib.contains(
+            "        #[ink(message, payable, selector = 0x2d10c9bd)]\n        pub fn deposit(&mut self) {"
+        ));
+        assert!(lib.contains("        pub fn balance_of(&self, owner: AccountId) -> Balance {"));
+        assert!(lib
+            .contains("        pub fn withdraw(&mut self, value: Balance) -> Result<(), Error> {"));
+    }
+
+    #[test]
+    fn prefixes_ambiguous_trait_messages() {
+        let mut metadata = metadata_v1();
+        let messages = &mut metadata["V1"]["spec"]["messages"];
+        messages[0]["name"] = serde_json::json!(["Vault", "balance_of"]);
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+
+        let lib = render(&abi).unwrap();
+        assert!(lib.contains("pub fn vault_balance_of(&mut self)"));
+        assert!(lib.contains("pub fn ledger_balance_of(&self, owner: AccountId)"));
+    }
+}
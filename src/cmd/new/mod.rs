@@ -0,0 +1,957 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+mod from_metadata;
+mod remote;
+mod wizard;
+mod workspace;
+
+use std::{
+    env, fmt, fs,
+    io::{self, Cursor, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    str::FromStr,
+};
+
+use crate::abi::ContractAbi;
+use anyhow::Result;
+use heck::CamelCase as _;
+use structopt::StructOpt;
+
+/// The ink! version the generated contracts depend on, unless another one is requested.
+pub const DEFAULT_INK_VERSION: &str = "3.0.0-rc6";
+
+/// The ink! version of contracts created from the ink! 4 templates, unless another one is
+/// requested. It is the first version of `ink_e2e`.
+pub const INK_4_VERSION: &str = "4.0.0";
+
+/// The author of the generated crates, unless another one is requested.
+const DEFAULT_AUTHOR: &str = "[your_name] <[your_email]>";
+
+/// The placeholders which are always set by `cargo contract new` itself.
+const RESERVED_PLACEHOLDERS: &[&str] = &[
+    "name",
+    "camel_name",
+    "ink_version",
+    "author",
+    "license",
+    "e2e",
+];
+
+/// The contract templates bundled with `cargo contract new`.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Template {
+    /// A `bool` which gets flipped, about the simplest contract you can build.
+    #[default]
+    Flipper,
+    /// A fungible token implementing the PSP22 standard.
+    Psp22,
+    /// A non-fungible token implementing the PSP34 standard.
+    Psp34,
+    /// A wallet which transfers funds once enough of its owners confirmed.
+    Multisig,
+    /// A DAO whose members vote on spending the funds of its treasury.
+    Dao,
+    /// A library crate holding an `#[ink::trait_definition]` shared between contracts.
+    TraitDefinition,
+}
+
+impl Template {
+    /// The names of all bundled templates.
+    pub const NAMES: &'static [&'static str] = &[
+        "flipper",
+        "psp22",
+        "psp34",
+        "multisig",
+        "dao",
+        "trait-definition",
+    ];
+
+    /// The name of the template, which is also its directory in the bundled archive.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Flipper => "flipper",
+            Self::Psp22 => "psp22",
+            Self::Psp34 => "psp34",
+            Self::Multisig => "multisig",
+            Self::Dao => "dao",
+            Self::TraitDefinition => "trait-definition",
+        }
+    }
+}
+
+impl fmt::Display for Template {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+impl FromStr for Template {
+    type Err = String;
+
+    fn from_str(input: &str) -> Result<Self, Self::Err> {
+        match input.to_lowercase().as_str() {
+            "flipper" => Ok(Template::Flipper),
+            "psp22" => Ok(Template::Psp22),
+            "psp34" => Ok(Template::Psp34),
+            "multisig" => Ok(Template::Multisig),
+            "dao" => Ok(Template::Dao),
+            "trait-definition" => Ok(Template::TraitDefinition),
+            _ => Err(format!(
+                "Unknown template '{}', available templates: {}",
+                input,
+                Template::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+/// Setup and create a new smart contract project.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "new")]
+pub struct NewCommand {
+    /// The name of the newly created smart contract
+    name: String,
+    /// The optional target directory for the contract project
+    #[structopt(short, long, parse(from_os_str))]
+    target_dir: Option<PathBuf>,
+    /// The template to create the contract from.
+    ///
+    /// - `flipper`: a `bool` which gets flipped, about the simplest contract you can build.
+    ///
+    /// - `psp22`: a fungible token implementing the PSP22 standard.
+    ///
+    /// - `psp34`: a non-fungible token implementing the PSP34 standard.
+    ///
+    /// - `multisig`: a wallet which transfers funds once enough of its owners confirmed.
+    ///
+    /// - `dao`: a DAO whose members vote on spending the funds of its treasury.
+    ///
+    /// - `trait-definition`: a library crate holding an `#[ink::trait_definition]` shared
+    ///   between contracts.
+    #[structopt(
+        long,
+        default_value = "flipper",
+        possible_values = Template::NAMES,
+        verbatim_doc_comment
+    )]
+    template: Template,
+    /// Ask for the storage items, messages and events of the contract instead of
+    /// using a template
+    #[structopt(short, long, conflicts_with = "template")]
+    interactive: bool,
+    /// Create a cargo workspace `name` holding the `--contracts` and a crate with the
+    /// types shared between them. The first contract calls all others.
+    #[structopt(long, conflicts_with_all = &["template", "interactive"])]
+    workspace: bool,
+    /// The comma separated names of the contracts of the `--workspace`
+    #[structopt(long, use_delimiter = true, requires = "workspace")]
+    contracts: Vec<String>,
+    /// Add an `e2e_tests` module running the contract on a local node with `ink_e2e`.
+    /// Not available for the `trait-definition` template, the others then depend on
+    /// ink! 4. With `--interactive` it is the default answer of the wizard.
+    #[structopt(long, conflicts_with = "workspace")]
+    with_e2e: bool,
+    /// Add a `client/` TypeScript package using polkadot.js, whose types are
+    /// regenerated by every `cargo contract build`
+    #[structopt(long, conflicts_with = "workspace")]
+    with_frontend_client: bool,
+    /// Create a contract skeleton with the constructors, messages and events of the
+    /// metadata of an existing contract, e.g. `target/ink/metadata.json`
+    #[structopt(
+        long,
+        parse(from_os_str),
+        conflicts_with_all = &["template", "interactive", "workspace", "with-e2e"]
+    )]
+    from_metadata: Option<PathBuf>,
+    /// Create the contract from the template in a git repository. It is cached, later
+    /// uses of the same repository work offline.
+    #[structopt(
+        long,
+        value_name = "url",
+        conflicts_with_all = &["template", "interactive", "workspace", "with-e2e", "from-metadata"]
+    )]
+    template_repo: Option<String>,
+    /// Fetch the `--template-repo` again instead of using its cached copy
+    #[structopt(long, requires = "template-repo")]
+    refresh_templates: bool,
+    /// The author of the generated crates, e.g. "Jane Doe <jane@example.com>"
+    #[structopt(long)]
+    author: Option<String>,
+    /// The SPDX license expression of the generated crates, e.g. "Apache-2.0"
+    #[structopt(long)]
+    license: Option<String>,
+    /// The ink! version the generated crates depend on. The `trait-definition` template
+    /// is not available for ink! 4 and later
+    #[structopt(long, parse(try_from_str = semver::Version::parse))]
+    ink_version: Option<semver::Version>,
+    /// Replaces every `{{key}}` in the template files with `value`, can be repeated
+    #[structopt(
+        long = "var",
+        value_name = "key=value",
+        parse(try_from_str = parse_var),
+        number_of_values = 1
+    )]
+    vars: Vec<(String, String)>,
+}
+
+/// Parses a `key=value` template variable.
+fn parse_var(input: &str) -> Result<(String, String)> {
+    let (key, value) = input
+        .split_once('=')
+        .ok_or_else(|| anyhow::anyhow!("Expected `key=value`, got `{}`", input))?;
+    let key = key.trim();
+    if key.is_empty() || !key.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        anyhow::bail!(
+            "Template variable names can only contain alphanumeric characters and underscores"
+        )
+    }
+    if RESERVED_PLACEHOLDERS.contains(&key) {
+        anyhow::bail!(
+            "The template variable `{}` is set by cargo-contract, use its own option instead",
+            key
+        )
+    }
+    Ok((key.to_string(), value.to_string()))
+}
+
+impl NewCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let mut args = ExecuteArgs {
+            template: self.template,
+            lib_rs: None,
+            ink_version: self.ink_version.as_ref().map(ToString::to_string),
+            e2e: self.with_e2e,
+            client: self.with_frontend_client,
+            from_metadata: self.from_metadata.clone(),
+            template_repo: self.template_repo.clone(),
+            refresh_templates: self.refresh_templates,
+            author: self.author.clone(),
+            license: self.license.clone(),
+            vars: self.vars.clone(),
+        };
+        if self.workspace {
+            return workspace::execute(
+                &self.name,
+                self.target_dir.as_ref(),
+                &self.contracts,
+                &args,
+            );
+        }
+        if self.interactive {
+            let stdin = io::stdin();
+            let default_ink_version = args.ink_version.as_deref().unwrap_or(DEFAULT_INK_VERSION);
+            let spec = wizard::ask(
+                &mut stdin.lock(),
+                &mut io::stdout(),
+                default_ink_version,
+                args.e2e,
+            )?;
+            args.lib_rs = Some(wizard::render(&spec));
+            args.e2e = spec.e2e;
+            args.ink_version = Some(spec.ink_version);
+        }
+        execute(&self.name, self.target_dir.as_ref(), &args)
+    }
+}
+
+/// Options for the generated contract project.
+#[derive(Debug, Default)]
+pub(crate) struct ExecuteArgs {
+    /// The template providing the files of the project.
+    pub(crate) template: Template,
+    /// Replaces the `lib.rs` of the template, if set.
+    pub(crate) lib_rs: Option<String>,
+    /// The ink! version to depend on, [`DEFAULT_INK_VERSION`] if not set.
+    pub(crate) ink_version: Option<String>,
+    /// Adds end-to-end tests, which requires the project to depend on ink! 4.
+    pub(crate) e2e: bool,
+    /// Adds a TypeScript client package in `client/`.
+    pub(crate) client: bool,
+    /// The metadata of the contract whose interface the generated contract implements.
+    pub(crate) from_metadata: Option<PathBuf>,
+    /// The git repository holding the template, instead of a bundled one.
+    pub(crate) template_repo: Option<String>,
+    /// Fetches the `template_repo` even if it is cached.
+    pub(crate) refresh_templates: bool,
+    /// The author of the crate, [`DEFAULT_AUTHOR`] if not set.
+    pub(crate) author: Option<String>,
+    /// The license of the crate, left out of the manifest if not set.
+    pub(crate) license: Option<String>,
+    /// Further `{{key}}` placeholders and their values.
+    pub(crate) vars: Vec<(String, String)>,
+}
+
+impl ExecuteArgs {
+    /// Returns the values of the template placeholders for the crate `name`.
+    fn placeholders(&self, name: &str, default_ink_version: &str) -> Vec<(&str, String)> {
+        let mut placeholders = vec![
+            ("name", name.to_string()),
+            ("camel_name", name.to_camel_case()),
+            (
+                "ink_version",
+                self.ink_version
+                    .as_deref()
+                    .unwrap_or(default_ink_version)
+                    .to_string(),
+            ),
+            (
+                "author",
+                self.author.as_deref().unwrap_or(DEFAULT_AUTHOR).to_string(),
+            ),
+        ];
+        if let Some(license) = &self.license {
+            placeholders.push(("license", license.clone()));
+        }
+        if self.e2e {
+            placeholders.push(("e2e", "true".to_string()));
+        }
+        placeholders.extend(
+            self.vars
+                .iter()
+                .map(|(key, value)| (key.as_str(), value.clone())),
+        );
+        placeholders
+    }
+}
+
+pub(crate) fn execute<P>(name: &str, dir: Option<P>, args: &ExecuteArgs) -> Result<Option<String>>
+where
+    P: AsRef<Path>,
+{
+    validate_name(name)?;
+    if args.e2e && args.template == Template::TraitDefinition {
+        anyhow::bail!("A trait definition is no contract, it cannot have end-to-end tests");
+    }
+    if args.client && args.template == Template::TraitDefinition {
+        anyhow::bail!("A trait definition is no contract, it cannot have a frontend client");
+    }
+    let (template, default_ink_version) = bundled_template(args)?;
+    let lib_rs = match &args.from_metadata {
+        Some(path) => Some(from_metadata::render(&ContractAbi::load(path)?)?),
+        None => args.lib_rs.clone(),
+    };
+    let template_dir = match &args.template_repo {
+        Some(url) => Some(remote::fetch(
+            url,
+            &remote::cache_root()?,
+            args.refresh_templates,
+        )?),
+        None => None,
+    };
+
+    let out_dir = dir
+        .map_or(env::current_dir()?, |p| p.as_ref().to_path_buf())
+        .join(name);
+    if out_dir.join("Cargo.toml").exists() {
+        anyhow::bail!("A Cargo package already exists in {}", name);
+    }
+    if !out_dir.exists() {
+        fs::create_dir(&out_dir)?;
+    }
+
+    let placeholders = args.placeholders(name, default_ink_version);
+    match template_dir {
+        Some(template_dir) => remote::unpack_dir(&template_dir, &out_dir, &placeholders)?,
+        None => unpack(&template, &out_dir, &placeholders, lib_rs.as_deref())?,
+    }
+    if args.client {
+        unpack("client", &out_dir.join("client"), &placeholders, None)?;
+    }
+
+    Ok(Some(format!("Created contract {}", name)))
+}
+
+/// Returns an error if `name` cannot be used as the name of a contract.
+fn validate_name(name: &str) -> Result<()> {
+    if !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        anyhow::bail!("Contract names can only contain alphanumeric characters and underscores");
+    }
+
+    if !name
+        .chars()
+        .next()
+        .map(|c| c.is_alphabetic())
+        .unwrap_or(false)
+    {
+        anyhow::bail!("Contract names must begin with an alphabetic character");
+    }
+    Ok(())
+}
+
+/// Returns the directory of the bundled template for `args` and the ink! version it depends
+/// on by default.
+///
+/// The templates written for ink! 4 live in the `ink4` directory of the archive.
+fn bundled_template(args: &ExecuteArgs) -> Result<(String, &'static str)> {
+    let ink_4 = match &args.ink_version {
+        Some(version) => is_ink_4(version)?,
+        None => args.e2e,
+    };
+    if args.e2e && !ink_4 {
+        anyhow::bail!(
+            "End-to-end tests require ink! 4 or later, not {}",
+            args.ink_version.as_deref().unwrap_or(DEFAULT_INK_VERSION)
+        )
+    }
+    let template = if args.from_metadata.is_some() {
+        "from-metadata".to_string()
+    } else {
+        args.template.to_string()
+    };
+    if !ink_4 {
+        return Ok((template, DEFAULT_INK_VERSION));
+    }
+
+    let unsupported = if args.from_metadata.is_some() {
+        Some("Contracts generated from metadata are".to_string())
+    } else if args.template == Template::TraitDefinition {
+        Some(format!("The {} template is", args.template))
+    } else {
+        None
+    };
+    if let Some(unsupported) = unsupported {
+        anyhow::bail!(
+            "{} only available for ink! 3, not for ink! {}",
+            unsupported,
+            args.ink_version.as_deref().unwrap_or(INK_4_VERSION)
+        )
+    }
+    Ok((format!("ink4/{}", template), INK_4_VERSION))
+}
+
+/// Returns `true` if `version` is ink! 4 or later, whose crates differ from ink! 3.
+fn is_ink_4(version: &str) -> Result<bool> {
+    let version = semver::Version::parse(version)
+        .map_err(|err| anyhow::anyhow!("Invalid ink! version `{}`: {}", version, err))?;
+    Ok(version.major >= 4)
+}
+
+/// Writes the files of the `template` directory of the bundled archive into `out_dir`.
+///
+/// The files are rendered with [`render`]. If `lib_rs` is set it replaces the contents
+/// of the `lib.rs` of the template.
+fn unpack(
+    template: &str,
+    out_dir: &Path,
+    placeholders: &[(&str, String)],
+    lib_rs: Option<&str>,
+) -> Result<()> {
+    let templates = include_bytes!(concat!(env!("OUT_DIR"), "/template.zip"));
+    let mut cursor = Cursor::new(Vec::new());
+    cursor.write_all(templates)?;
+    cursor.seek(SeekFrom::Start(0))?;
+
+    let mut archive = zip::ZipArchive::new(cursor)?;
+    let template_prefix = format!("{}/", template);
+
+    for i in 0..archive.len() {
+        let mut file = archive.by_index(i)?;
+        // every template lives in its own directory of the bundled archive
+        let file_name = match file.name().strip_prefix(&template_prefix) {
+            Some(file_name) if !file_name.is_empty() => file_name.to_string(),
+            _ => continue,
+        };
+        let mut contents = String::new();
+        file.read_to_string(&mut contents)?;
+        if let (Some(lib_rs), "lib.rs") = (lib_rs, file_name.as_str()) {
+            contents = lib_rs.to_string();
+        }
+
+        let contents = render(&contents, placeholders);
+
+        let outpath = out_dir.join(&file_name);
+
+        if file_name.ends_with('/') {
+            fs::create_dir_all(&outpath)?;
+        } else {
+            if let Some(p) = outpath.parent() {
+                if !p.exists() {
+                    fs::create_dir_all(p)?;
+                }
+            }
+            let mut outfile = fs::OpenOptions::new()
+                .write(true)
+                .create_new(true)
+                .open(outpath.clone())
+                .map_err(|e| {
+                    if e.kind() == std::io::ErrorKind::AlreadyExists {
+                        anyhow::anyhow!("New contract file {} already exists", file_name)
+                    } else {
+                        anyhow::anyhow!(e)
+                    }
+                })?;
+
+            outfile.write_all(contents.as_bytes())?;
+        }
+
+        // Get and set permissions
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+
+            if let Some(mode) = file.unix_mode() {
+                fs::set_permissions(&outpath, fs::Permissions::from_mode(mode))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Replaces every `{{key}}` in `template` with the value of `key` in `placeholders`.
+///
+/// The lines between a `{{#key}}` and a `{{/key}}` line are only kept if `key` is one of
+/// the `placeholders`, e.g. the `license` of the manifest is left out if none was given.
+fn render(template: &str, placeholders: &[(&str, String)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    // the section which is currently left out, if any
+    let mut skipped: Option<&str> = None;
+    for line in template.split_inclusive('\n') {
+        let tag = line.trim_end();
+        if let Some(key) = tag.strip_prefix("{{#").and_then(|t| t.strip_suffix("}}")) {
+            if skipped.is_none() && !placeholders.iter().any(|(k, _)| *k == key) {
+                skipped = Some(key);
+            }
+            continue;
+        }
+        if let Some(key) = tag.strip_prefix("{{/").and_then(|t| t.strip_suffix("}}")) {
+            if skipped == Some(key) {
+                skipped = None;
+            }
+            continue;
+        }
+        if skipped.is_none() {
+            rendered.push_str(line);
+        }
+    }
+
+    for (key, value) in placeholders {
+        rendered = rendered.replace(&format!("{{{{{}}}}}", key), value);
+    }
+    rendered
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::{with_new_contract_project, with_tmp_dir};
+
+    #[test]
+    fn rejects_hyphenated_name() {
+        with_new_contract_project(|manifest_path| {
+            let result = execute(
+                "rejects-hyphenated-name",
+                Some(manifest_path),
+                &Default::default(),
+            );
+            assert!(result.is_err(), "Should fail");
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "Contract names can only contain alphanumeric characters and underscores"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rejects_name_with_period() {
+        with_new_contract_project(|manifest_path| {
+            let result = execute("../xxx", Some(manifest_path), &Default::default());
+            assert!(result.is_err(), "Should fail");
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "Contract names can only contain alphanumeric characters and underscores"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rejects_name_beginning_with_number() {
+        with_new_contract_project(|manifest_path| {
+            let result = execute("1xxx", Some(manifest_path), &Default::default());
+            assert!(result.is_err(), "Should fail");
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "Contract names must begin with an alphabetic character"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn contract_cargo_project_already_exists() {
+        with_tmp_dir(|path| {
+            let name = "test_contract_cargo_project_already_exists";
+            let _ = execute(name, Some(path), &Default::default());
+            let result = execute(name, Some(path), &Default::default());
+
+            assert!(result.is_err(), "Should fail");
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "A Cargo package already exists in test_contract_cargo_project_already_exists"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn dont_overwrite_existing_files_not_in_cargo_project() {
+        with_tmp_dir(|path| {
+            let name = "dont_overwrite_existing_files";
+            let dir = path.join(name);
+            fs::create_dir_all(&dir).unwrap();
+            fs::File::create(dir.join(".gitignore")).unwrap();
+            let result = execute(name, Some(path), &Default::default());
+
+            assert!(result.is_err(), "Should fail");
+            assert_eq!(
+                result.err().unwrap().to_string(),
+                "New contract file .gitignore already exists"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn creates_contract_from_every_template() {
+        with_tmp_dir(|path| {
+            for (i, template_name) in Template::NAMES.iter().enumerate() {
+                let template = template_name.parse::<Template>().expect("valid template");
+                let name = format!("{}_{}", template_name.replace('-', "_"), i);
+                let args = ExecuteArgs {
+                    template,
+                    ..Default::default()
+                };
+                execute(&name, Some(path), &args)?;
+
+                let lib = fs::read_to_string(path.join(&name).join("lib.rs"))?;
+                assert!(lib.contains(&name.to_camel_case()));
+                assert!(!lib.contains("{{name}}") && !lib.contains("{{camel_name}}"));
+                let manifest = fs::read_to_string(path.join(&name).join("Cargo.toml"))?;
+                assert!(manifest.contains(&format!("name = \"{}\"", name)));
+                assert!(manifest.contains(&format!("version = \"{}\"", DEFAULT_INK_VERSION)));
+            }
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn creates_contract_from_wizard_answers() {
+        with_tmp_dir(|path| {
+            let name = "wizard";
+            let spec = wizard::ContractSpec {
+                storage: vec![wizard::Field {
+                    name: "value".into(),
+                    ty: "u32".into(),
+                }],
+                messages: Vec::new(),
+                events: Vec::new(),
+                payable_constructor: false,
+                getters: true,
+                e2e: false,
+                ink_version: "3.0.0-rc5".into(),
+            };
+            let args = ExecuteArgs {
+                template: Template::Flipper,
+                lib_rs: Some(wizard::render(&spec)),
+                ink_version: Some(spec.ink_version.clone()),
+                ..Default::default()
+            };
+            execute(name, Some(path), &args)?;
+
+            let lib = fs::read_to_string(path.join(name).join("lib.rs"))?;
+            assert!(lib.contains("pub struct Wizard {\n        value: u32,\n    }"));
+            assert!(!lib.contains("flip"));
+            let manifest = fs::read_to_string(path.join(name).join("Cargo.toml"))?;
+            assert!(manifest.contains("version = \"3.0.0-rc5\""));
+            assert!(!manifest.contains("{{ink_version}}"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn creates_contract_with_e2e_tests_from_wizard_answers() {
+        with_tmp_dir(|path| {
+            let name = "wizard_e2e";
+            let spec = wizard::ContractSpec {
+                storage: Vec::new(),
+                messages: Vec::new(),
+                events: Vec::new(),
+                payable_constructor: false,
+                getters: true,
+                e2e: true,
+                ink_version: INK_4_VERSION.into(),
+            };
+            let args = ExecuteArgs {
+                lib_rs: Some(wizard::render(&spec)),
+                ink_version: Some(spec.ink_version.clone()),
+                e2e: spec.e2e,
+                ..Default::default()
+            };
+            execute(name, Some(path), &args)?;
+
+            let lib = fs::read_to_string(path.join(name).join("lib.rs"))?;
+            assert!(lib.contains("WizardE2eRef::new()"));
+            assert!(lib.contains("let _contract_account_id = client"));
+            assert!(lib.contains("Self::env().transferred_value()"));
+            let manifest = fs::read_to_string(path.join(name).join("Cargo.toml"))?;
+            assert!(manifest.contains(&format!("ink = {{ version = \"{}\"", INK_4_VERSION)));
+            assert!(manifest.contains(&format!("ink_e2e = \"{}\"", INK_4_VERSION)));
+            assert!(manifest.contains("e2e-tests = []"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn pins_requested_ink_version() {
+        with_tmp_dir(|path| {
+            let args = ExecuteArgs {
+                ink_version: Some("4.3.0".into()),
+                ..Default::default()
+            };
+            execute("ink_4", Some(path), &args)?;
+            let manifest = fs::read_to_string(path.join("ink_4").join("Cargo.toml"))?;
+            assert!(manifest.contains("ink = { version = \"4.3.0\""));
+            assert!(manifest.contains("tag = \"v4.3.0\""));
+            assert!(!manifest.contains("ink_e2e"));
+            let lib = fs::read_to_string(path.join("ink_4").join("lib.rs"))?;
+            assert!(!lib.contains("e2e_tests"));
+            assert!(lib.ends_with("        }\n    }\n}\n"));
+
+            let args = ExecuteArgs {
+                ink_version: Some("4.3.0".into()),
+                e2e: true,
+                ..Default::default()
+            };
+            execute("ink_4_e2e", Some(path), &args)?;
+            let manifest = fs::read_to_string(path.join("ink_4_e2e").join("Cargo.toml"))?;
+            assert!(manifest.contains("ink_e2e = \"4.3.0\""));
+
+            let args = ExecuteArgs {
+                template: Template::Psp22,
+                ink_version: Some("4.3.0".into()),
+                ..Default::default()
+            };
+            execute("psp22_ink_4", Some(path), &args)?;
+            let lib = fs::read_to_string(path.join("psp22_ink_4").join("lib.rs"))?;
+            assert!(lib.contains("use ink::storage::Mapping;"));
+            assert!(!lib.contains("e2e_tests"));
+
+            let args = ExecuteArgs {
+                template: Template::TraitDefinition,
+                ink_version: Some("4.3.0".into()),
+                ..Default::default()
+            };
+            let result = execute("trait_ink_4", Some(path), &args);
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "The trait-definition template is only available for ink! 3, not for ink! 4.3.0"
+            );
+            assert!(!path.join("trait_ink_4").exists());
+
+            let args = ExecuteArgs {
+                ink_version: Some("3.0.0".into()),
+                e2e: true,
+                ..Default::default()
+            };
+            let result = execute("e2e_ink_3", Some(path), &args);
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "End-to-end tests require ink! 4 or later, not 3.0.0"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn creates_contract_with_e2e_tests() {
+        with_tmp_dir(|path| {
+            let name = "with_e2e";
+            let args = ExecuteArgs {
+                e2e: true,
+                ..Default::default()
+            };
+            execute(name, Some(path), &args)?;
+
+            let lib = fs::read_to_string(path.join(name).join("lib.rs"))?;
+            assert!(lib.contains("#[cfg(all(test, feature = \"e2e-tests\"))]\n    mod e2e_tests {"));
+            assert!(lib.contains("let constructor = WithE2eRef::new(false);"));
+            let manifest = fs::read_to_string(path.join(name).join("Cargo.toml"))?;
+            assert!(manifest.contains("[dev-dependencies]\nink_e2e = \"4.0.0\""));
+            assert!(manifest.contains("e2e-tests = []"));
+
+            let args = ExecuteArgs {
+                template: Template::Dao,
+                e2e: true,
+                ..Default::default()
+            };
+            execute("dao_with_e2e", Some(path), &args)?;
+            let lib = fs::read_to_string(path.join("dao_with_e2e").join("lib.rs"))?;
+            assert!(lib.contains("    mod e2e_tests {"));
+            assert!(lib.contains("let constructor = DaoWithE2eRef::new(members, 50);"));
+
+            let args = ExecuteArgs {
+                template: Template::TraitDefinition,
+                e2e: true,
+                ..Default::default()
+            };
+            let result = execute("trait_with_e2e", Some(path), &args);
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "A trait definition is no contract, it cannot have end-to-end tests"
+            );
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn creates_contract_with_frontend_client() {
+        with_tmp_dir(|path| {
+            let name = "with_client";
+            let args = ExecuteArgs {
+                template: Template::Psp22,
+                client: true,
+                ..Default::default()
+            };
+            execute(name, Some(path), &args)?;
+
+            let client = path.join(name).join("client");
+            let package = fs::read_to_string(client.join("package.json"))?;
+            assert!(package.contains("\"name\": \"with_client-client\""));
+            assert!(package.contains("\"@polkadot/api-contract\""));
+            let contract_ts = fs::read_to_string(client.join("src").join("contract.ts"))?;
+            assert!(contract_ts.starts_with(crate::codegen::typescript::GENERATED_HEADER));
+            assert!(client.join("src").join("index.ts").exists());
+
+            let args = ExecuteArgs {
+                template: Template::TraitDefinition,
+                client: true,
+                ..Default::default()
+            };
+            assert!(execute("trait_with_client", Some(path), &args).is_err());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn creates_contract_from_metadata() {
+        with_tmp_dir(|path| {
+            let metadata_path = path.join("metadata.json");
+            fs::write(&metadata_path, crate::abi::tests::metadata_v1().to_string())?;
+            let args = ExecuteArgs {
+                from_metadata: Some(metadata_path),
+                ..Default::default()
+            };
+            execute("bank_mock", Some(path), &args)?;
+
+            let lib = fs::read_to_string(path.join("bank_mock").join("lib.rs"))?;
+            assert!(lib.contains("mod bank_mock {"));
+            assert!(lib.contains("    pub struct BankMock {}"));
+            assert!(lib.contains("#[ink(message, selector = 0x410fcc9d)]"));
+            let manifest = fs::read_to_string(path.join("bank_mock").join("Cargo.toml"))?;
+            assert!(manifest.contains("ink_prelude = { version = \"3.0.0-rc6\""));
+
+            let args = ExecuteArgs {
+                from_metadata: Some(path.join("missing.json")),
+                ..Default::default()
+            };
+            assert!(execute("missing", Some(path), &args).is_err());
+            assert!(!path.join("missing").exists());
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn trait_definition_is_a_library() {
+        with_tmp_dir(|path| {
+            let name = "shared_interface";
+            let args = ExecuteArgs {
+                template: Template::TraitDefinition,
+                ..Default::default()
+            };
+            execute(name, Some(path), &args)?;
+
+            let lib = fs::read_to_string(path.join(name).join("lib.rs"))?;
+            assert!(lib.contains("#[ink::trait_definition]\npub trait SharedInterface {"));
+            assert!(!lib.contains("#[ink(storage)]"));
+            let manifest = fs::read_to_string(path.join(name).join("Cargo.toml"))?;
+            assert!(!manifest.contains("cdylib"));
+            assert!(!manifest.contains("ink_storage"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn substitutes_template_variables() {
+        with_tmp_dir(|path| {
+            let name = "with_vars";
+            let args = ExecuteArgs {
+                author: Some("Jane Doe <jane@example.com>".into()),
+                license: Some("Apache-2.0".into()),
+                ink_version: Some("3.0.0-rc5".into()),
+                ..Default::default()
+            };
+            execute(name, Some(path), &args)?;
+
+            let manifest = fs::read_to_string(path.join(name).join("Cargo.toml"))?;
+            assert!(manifest.contains(
+                "authors = [\"Jane Doe <jane@example.com>\"]\nlicense = \"Apache-2.0\"\nedition"
+            ));
+            assert!(manifest.contains("ink_lang = { version = \"3.0.0-rc5\""));
+
+            let name = "without_license";
+            execute(name, Some(path), &ExecuteArgs::default())?;
+            let manifest = fs::read_to_string(path.join(name).join("Cargo.toml"))?;
+            assert!(manifest.contains("authors = [\"[your_name] <[your_email]>\"]\nedition"));
+            assert!(!manifest.contains("{{"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn renders_sections_and_custom_variables() {
+        let template = "a\n{{#description}}\ndescription = \"{{description}}\"\n{{/description}}\n{{#license}}\nlicense\n{{/license}}\nb\n";
+        let placeholders = [("description", "A token".to_string())];
+        assert_eq!(
+            render(template, &placeholders),
+            "a\ndescription = \"A token\"\nb\n"
+        );
+    }
+
+    #[test]
+    fn parses_template_variables() {
+        assert_eq!(
+            parse_var("description=A token = money").unwrap(),
+            ("description".to_string(), "A token = money".to_string())
+        );
+        assert_eq!(
+            parse_var("name=other").unwrap_err().to_string(),
+            "The template variable `name` is set by cargo-contract, use its own option instead"
+        );
+        assert!(parse_var("description").is_err());
+    }
+
+    #[test]
+    fn rejects_unknown_template() {
+        let result = "erc1155".parse::<Template>();
+        assert_eq!(
+            result.unwrap_err(),
+            "Unknown template 'erc1155', available templates: flipper, psp22, psp34, multisig, dao, trait-definition"
+        );
+    }
+}
@@ -0,0 +1,190 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Templates fetched from git repositories, cached locally so that `cargo contract new`
+//! keeps working without network access.
+
+use super::render;
+use anyhow::{Context, Result};
+use colored::Colorize;
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// Returns the directory holding the cached templates, `templates` in the cache directory.
+pub(crate) fn cache_root() -> Result<PathBuf> {
+    Ok(crate::util::cache_dir()?.join("templates"))
+}
+
+/// Returns the directory of the cached copy of the template repository `url`.
+///
+/// The repository is only cloned if there is no cached copy yet or if `refresh` is set.
+/// A failed refresh keeps the previously cached copy.
+pub(crate) fn fetch(url: &str, cache_root: &Path, refresh: bool) -> Result<PathBuf> {
+    let cached = cache_root.join(cache_key(url));
+    if cached.exists() && !refresh {
+        return Ok(cached);
+    }
+
+    fs::create_dir_all(cache_root)?;
+    let tmp_dir = tempfile::Builder::new()
+        .prefix(".fetching-")
+        .tempdir_in(cache_root)?;
+    let clone_dir = tmp_dir.path().join("template");
+    let output = Command::new("git")
+        .args(["clone", "--depth", "1", "--quiet", url])
+        .arg(&clone_dir)
+        .output()
+        .context("Failed to run `git`, is it installed?")?;
+    if !output.status.success() {
+        let error = String::from_utf8_lossy(&output.stderr);
+        if cached.exists() {
+            eprintln!(
+                "{} Failed to refresh the template {}, using the cached copy: {}",
+                "warning:".yellow().bold(),
+                url,
+                error.trim()
+            );
+            return Ok(cached);
+        }
+        anyhow::bail!("Failed to fetch the template {}: {}", url, error.trim())
+    }
+
+    fs::remove_dir_all(clone_dir.join(".git"))?;
+    if cached.exists() {
+        fs::remove_dir_all(&cached)?;
+    }
+    fs::rename(&clone_dir, &cached)?;
+    Ok(cached)
+}
+
+/// Returns the name of the cache directory of the repository `url`.
+fn cache_key(url: &str) -> String {
+    url.trim_end_matches('/')
+        .trim_end_matches(".git")
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '_' })
+        .collect()
+}
+
+/// Copies the template in `template_dir` to `out_dir`, replacing its placeholders.
+///
+/// As in the bundled templates, a `_Cargo.toml` is renamed to `Cargo.toml`.
+pub(crate) fn unpack_dir(
+    template_dir: &Path,
+    out_dir: &Path,
+    placeholders: &[(&str, String)],
+) -> Result<()> {
+    fs::create_dir_all(out_dir)?;
+    for entry in fs::read_dir(template_dir)? {
+        let entry = entry?;
+        let file_name = entry.file_name();
+        let path = entry.path();
+        if file_name == ".git" {
+            continue;
+        }
+        let target = if file_name == "_Cargo.toml" {
+            out_dir.join("Cargo.toml")
+        } else {
+            out_dir.join(&file_name)
+        };
+
+        if entry.file_type()?.is_dir() {
+            unpack_dir(&path, &target, placeholders)?;
+        } else if target.exists() {
+            anyhow::bail!("New contract file {} already exists", target.display())
+        } else {
+            match fs::read_to_string(&path) {
+                Ok(contents) => fs::write(&target, render(&contents, placeholders))?,
+                // binary files are copied as they are
+                Err(_) => {
+                    fs::copy(&path, &target)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    fn git(dir: &Path, args: &[&str]) {
+        let status = Command::new("git")
+            .args(args)
+            .current_dir(dir)
+            .status()
+            .expect("git must be installed");
+        assert!(status.success(), "git {:?} failed", args);
+    }
+
+    #[test]
+    fn caches_fetched_templates() {
+        with_tmp_dir(|path| {
+            let repo = path.join("repo");
+            fs::create_dir_all(repo.join("src"))?;
+            fs::write(repo.join("_Cargo.toml"), "[package]\nname = \"{{name}}\"\n")?;
+            fs::write(repo.join("src").join("lib.rs"), "// v1")?;
+            git(&repo, &["init", "--quiet"]);
+            git(&repo, &["add", "."]);
+            git(
+                &repo,
+                &[
+                    "-c",
+                    "user.name=test",
+                    "-c",
+                    "user.email=test@example.com",
+                    "commit",
+                    "--quiet",
+                    "-m",
+                    "v1",
+                ],
+            );
+            let url = repo.to_string_lossy().to_string();
+            let cache_root = path.join("cache");
+
+            let cached = fetch(&url, &cache_root, false)?;
+            assert!(!cached.join(".git").exists());
+            let out_dir = path.join("out");
+            unpack_dir(&cached, &out_dir, &[("name", "my_contract".to_string())])?;
+            assert_eq!(
+                fs::read_to_string(out_dir.join("Cargo.toml"))?,
+                "[package]\nname = \"my_contract\"\n"
+            );
+            assert!(out_dir.join("src").join("lib.rs").exists());
+
+            // without network access the cached copy is used
+            fs::remove_dir_all(&repo)?;
+            assert_eq!(fetch(&url, &cache_root, false)?, cached);
+            assert_eq!(fetch(&url, &cache_root, true)?, cached);
+            assert_eq!(
+                fs::read_to_string(cached.join("src").join("lib.rs"))?,
+                "// v1"
+            );
+
+            let result = fetch("/does/not/exist", &cache_root, false);
+            assert!(result
+                .unwrap_err()
+                .to_string()
+                .starts_with("Failed to fetch the template /does/not/exist"));
+            Ok(())
+        })
+    }
+}
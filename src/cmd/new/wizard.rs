@@ -0,0 +1,612 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::INK_4_VERSION;
+use anyhow::Result;
+use colored::Colorize;
+use std::{
+    fmt::Write as _,
+    io::{BufRead, Write},
+};
+
+/// A field of the contract storage or of an event.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Field {
+    pub name: String,
+    pub ty: String,
+}
+
+/// A message of the contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub name: String,
+    /// `true` if the message takes `&mut self`.
+    pub mutates: bool,
+    /// `true` if the message accepts a transferred value.
+    pub payable: bool,
+}
+
+/// An event of the contract.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Event {
+    pub name: String,
+    pub fields: Vec<Field>,
+}
+
+/// The contract skeleton described by the answers given to the wizard.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ContractSpec {
+    pub storage: Vec<Field>,
+    pub messages: Vec<Message>,
+    pub events: Vec<Event>,
+    /// If `false` the constructor rejects any transferred value.
+    pub payable_constructor: bool,
+    /// Generates a getter message for each storage item.
+    pub getters: bool,
+    /// Generates an `e2e_tests` module, which requires ink! 4.
+    pub e2e: bool,
+    pub ink_version: String,
+}
+
+/// Asks the user about the contract to generate, reading the answers from `input`.
+///
+/// `default_e2e` is the default answer to whether to generate end-to-end tests.
+pub fn ask<R, W>(
+    input: &mut R,
+    output: &mut W,
+    default_ink_version: &str,
+    default_e2e: bool,
+) -> Result<ContractSpec>
+where
+    R: BufRead,
+    W: Write,
+{
+    let mut prompt = Prompt { input, output };
+
+    let storage = prompt.ask_parsed(
+        "Storage items, e.g. `value: bool, owner: AccountId`",
+        "value: bool",
+        parse_fields,
+    )?;
+    let getters = prompt.confirm("Generate a getter message for each storage item?", true)?;
+
+    let message_names = prompt.ask_parsed(
+        "Additional messages, e.g. `flip, reset`",
+        "",
+        parse_identifiers,
+    )?;
+    let mut messages = Vec::new();
+    for name in message_names {
+        let mutates = prompt.confirm(&format!("Does `{}` mutate the storage?", name), true)?;
+        let payable = prompt.confirm(&format!("Is `{}` payable?", name), false)?;
+        messages.push(Message {
+            name,
+            mutates,
+            payable,
+        });
+    }
+
+    let events = prompt.ask_parsed(
+        "Events, e.g. `Flipped(new_value: bool), Reset`",
+        "",
+        parse_events,
+    )?;
+    let payable_constructor = prompt.confirm("Should the constructor be payable?", true)?;
+    let e2e = prompt.confirm("Include end-to-end tests? They require ink! 4", default_e2e)?;
+    let default_ink_version = if e2e && !is_ink_4(default_ink_version) {
+        INK_4_VERSION
+    } else {
+        default_ink_version
+    };
+    let ink_version = prompt.ask_parsed("ink! version", default_ink_version, |version| {
+        semver::Version::parse(version)
+            .map_err(|err| anyhow::anyhow!("Invalid ink! version '{}': {}", version, err))?;
+        if e2e && !is_ink_4(version) {
+            anyhow::bail!("End-to-end tests require ink! 4 or later, not {}", version)
+        }
+        Ok(version.to_string())
+    })?;
+
+    Ok(ContractSpec {
+        storage,
+        messages,
+        events,
+        payable_constructor,
+        getters,
+        e2e,
+        ink_version,
+    })
+}
+
+/// Returns `true` if `version` is ink! 4 or later, whose contracts use the `ink` crate only.
+fn is_ink_4(version: &str) -> bool {
+    semver::Version::parse(version).is_ok_and(|version| version.major >= 4)
+}
+
+/// Asks questions on `output` and reads the answers line by line from `input`.
+struct Prompt<'a, R, W> {
+    input: &'a mut R,
+    output: &'a mut W,
+}
+
+impl<'a, R, W> Prompt<'a, R, W>
+where
+    R: BufRead,
+    W: Write,
+{
+    /// Returns the trimmed answer to `question`, or `default` if the answer is empty.
+    fn ask(&mut self, question: &str, default: &str) -> Result<String> {
+        if default.is_empty() {
+            write!(self.output, "{} ", format!("{}:", question).bold())?;
+        } else {
+            write!(
+                self.output,
+                "{} [{}] ",
+                format!("{}:", question).bold(),
+                default
+            )?;
+        }
+        self.output.flush()?;
+
+        let mut answer = String::new();
+        if self.input.read_line(&mut answer)? == 0 {
+            anyhow::bail!("Unexpected end of input while waiting for an answer")
+        }
+        let answer = answer.trim();
+        if answer.is_empty() {
+            Ok(default.to_string())
+        } else {
+            Ok(answer.to_string())
+        }
+    }
+
+    /// Asks `question` until the answer can be parsed with `parse`.
+    fn ask_parsed<T, F>(&mut self, question: &str, default: &str, parse: F) -> Result<T>
+    where
+        F: Fn(&str) -> Result<T>,
+    {
+        loop {
+            let answer = self.ask(question, default)?;
+            match parse(&answer) {
+                Ok(parsed) => return Ok(parsed),
+                Err(err) => writeln!(self.output, "{} {}", "error:".bright_red().bold(), err)?,
+            }
+        }
+    }
+
+    /// Asks a yes/no `question`.
+    fn confirm(&mut self, question: &str, default: bool) -> Result<bool> {
+        let default = if default { "y" } else { "n" };
+        self.ask_parsed(question, default, |answer| {
+            match answer.to_lowercase().as_str() {
+                "y" | "yes" => Ok(true),
+                "n" | "no" => Ok(false),
+                _ => anyhow::bail!("Please answer with `y` or `n`"),
+            }
+        })
+    }
+}
+
+/// Splits `input` at every `separator` which is not nested in brackets.
+fn split_top_level(input: &str, separator: char) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut depth = 0i32;
+    let mut start = 0;
+    for (i, c) in input.char_indices() {
+        match c {
+            '<' | '(' | '[' => depth += 1,
+            '>' | ')' | ']' => depth -= 1,
+            c if c == separator && depth == 0 => {
+                parts.push(input[start..i].trim());
+                start = i + c.len_utf8();
+            }
+            _ => (),
+        }
+    }
+    parts.push(input[start..].trim());
+    parts.into_iter().filter(|part| !part.is_empty()).collect()
+}
+
+/// Returns an error if `ident` is not a valid Rust identifier.
+fn ensure_identifier(ident: &str) -> Result<()> {
+    let starts_alphabetic = ident
+        .chars()
+        .next()
+        .map(|c| c.is_alphabetic() || c == '_')
+        .unwrap_or(false);
+    if !starts_alphabetic || !ident.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        anyhow::bail!("`{}` is not a valid identifier", ident)
+    }
+    Ok(())
+}
+
+/// Parses a comma separated list of identifiers.
+fn parse_identifiers(input: &str) -> Result<Vec<String>> {
+    split_top_level(input, ',')
+        .into_iter()
+        .map(|ident| ensure_identifier(ident).map(|_| ident.to_string()))
+        .collect()
+}
+
+/// Parses a comma separated list of `name: Type` fields.
+fn parse_fields(input: &str) -> Result<Vec<Field>> {
+    split_top_level(input, ',')
+        .into_iter()
+        .map(|field| {
+            let (name, ty) = field
+                .split_once(':')
+                .ok_or_else(|| anyhow::anyhow!("Expected `name: Type`, got `{}`", field))?;
+            let (name, ty) = (name.trim(), ty.trim());
+            ensure_identifier(name)?;
+            if ty.is_empty() {
+                anyhow::bail!("Missing type for `{}`", name)
+            }
+            Ok(Field {
+                name: name.to_string(),
+                ty: ty.to_string(),
+            })
+        })
+        .collect()
+}
+
+/// Parses a comma separated list of events, e.g. `Flipped(new_value: bool), Reset`.
+fn parse_events(input: &str) -> Result<Vec<Event>> {
+    split_top_level(input, ',')
+        .into_iter()
+        .map(|event| {
+            let (name, fields) = match event.split_once('(') {
+                Some((name, fields)) => {
+                    let fields = fields
+                        .strip_suffix(')')
+                        .ok_or_else(|| anyhow::anyhow!("Missing `)` in `{}`", event))?;
+                    (name.trim(), parse_fields(fields)?)
+                }
+                None => (event, Vec::new()),
+            };
+            ensure_identifier(name)?;
+            Ok(Event {
+                name: name.to_string(),
+                fields,
+            })
+        })
+        .collect()
+}
+
+/// Renders the `lib.rs` of the contract described by `spec`.
+///
+/// The result still contains the `{{name}}` and `{{camel_name}}` template placeholders.
+/// Contracts for ink! 4 use the `ink` crate instead of `ink_lang`.
+pub fn render(spec: &ContractSpec) -> String {
+    let ink_4 = is_ink_4(&spec.ink_version);
+    let mut out = String::new();
+    let w = &mut out;
+    // writing to a `String` never fails
+    let _ = writeln!(w, "#![cfg_attr(not(feature = \"std\"), no_std)]\n");
+    if !ink_4 {
+        let _ = writeln!(w, "use ink_lang as ink;\n");
+    }
+    let _ = writeln!(w, "#[ink::contract]");
+    let _ = writeln!(w, "mod {{{{name}}}} {{");
+
+    let _ = writeln!(w, "    /// Defines the storage of your contract.");
+    let _ = writeln!(w, "    #[ink(storage)]");
+    let _ = writeln!(w, "    pub struct {{{{camel_name}}}} {{");
+    for field in &spec.storage {
+        let _ = writeln!(w, "        {}: {},", field.name, field.ty);
+    }
+    let _ = writeln!(w, "    }}\n");
+
+    for event in &spec.events {
+        let _ = writeln!(w, "    #[ink(event)]");
+        if event.fields.is_empty() {
+            let _ = writeln!(w, "    pub struct {} {{}}\n", event.name);
+            continue;
+        }
+        let _ = writeln!(w, "    pub struct {} {{", event.name);
+        for field in &event.fields {
+            let _ = writeln!(w, "        {}: {},", field.name, field.ty);
+        }
+        let _ = writeln!(w, "    }}\n");
+    }
+
+    let _ = writeln!(w, "    impl {{{{camel_name}}}} {{");
+    let args = spec
+        .storage
+        .iter()
+        .map(|field| format!("{}: {}", field.name, field.ty))
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(
+        w,
+        "        /// Constructor that initializes the storage with the given values."
+    );
+    let _ = writeln!(w, "        #[ink(constructor)]");
+    let _ = writeln!(w, "        pub fn new({}) -> Self {{", args);
+    if !spec.payable_constructor {
+        let transferred = if ink_4 {
+            "transferred_value"
+        } else {
+            "transferred_balance"
+        };
+        let _ = writeln!(
+            w,
+            "            assert_eq!(Self::env().{}(), 0, \"the constructor is not payable\");",
+            transferred
+        );
+    }
+    let field_names = spec
+        .storage
+        .iter()
+        .map(|field| field.name.as_str())
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(w, "            Self {{ {} }}", field_names);
+    let _ = writeln!(w, "        }}");
+
+    if spec.getters {
+        for field in &spec.storage {
+            let _ = writeln!(w);
+            let _ = writeln!(w, "        /// Returns the current `{}`.", field.name);
+            let _ = writeln!(w, "        #[ink(message)]");
+            let _ = writeln!(w, "        pub fn {}(&self) -> {} {{", field.name, field.ty);
+            let _ = writeln!(w, "            self.{}.clone()", field.name);
+            let _ = writeln!(w, "        }}");
+        }
+    }
+
+    for message in &spec.messages {
+        let _ = writeln!(w);
+        let _ = writeln!(
+            w,
+            "        /// TODO: describe what `{}` does.",
+            message.name
+        );
+        if message.payable {
+            let _ = writeln!(w, "        #[ink(message, payable)]");
+        } else {
+            let _ = writeln!(w, "        #[ink(message)]");
+        }
+        let receiver = if message.mutates {
+            "&mut self"
+        } else {
+            "&self"
+        };
+        let _ = writeln!(w, "        pub fn {}({}) {{", message.name, receiver);
+        let _ = writeln!(w, "            todo!()");
+        let _ = writeln!(w, "        }}");
+    }
+    let _ = writeln!(w, "    }}\n");
+
+    let defaults = spec
+        .storage
+        .iter()
+        .map(|_| "Default::default()")
+        .collect::<Vec<_>>()
+        .join(", ");
+    let _ = writeln!(
+        w,
+        "    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`"
+    );
+    let _ = writeln!(
+        w,
+        "    /// module and test functions are marked with a `#[test]` attribute."
+    );
+    let _ = writeln!(w, "    #[cfg(test)]");
+    let _ = writeln!(w, "    mod tests {{");
+    let _ = writeln!(w, "        use super::*;");
+    if !ink_4 {
+        let _ = writeln!(w, "        use ink_lang as ink;");
+    }
+    let _ = writeln!(w);
+    let _ = writeln!(w, "        #[ink::test]");
+    let _ = writeln!(w, "        fn new_works() {{");
+    let _ = writeln!(
+        w,
+        "            let _{{{{name}}}} = {{{{camel_name}}}}::new({});",
+        defaults
+    );
+    let _ = writeln!(w, "        }}");
+    let _ = writeln!(w, "    }}");
+    if spec.e2e {
+        render_e2e_tests(w, spec, &defaults);
+    }
+    let _ = writeln!(w, "}}");
+    out
+}
+
+/// Renders the `e2e_tests` module, which instantiates the contract and dry runs its getters.
+fn render_e2e_tests(w: &mut String, spec: &ContractSpec, defaults: &str) {
+    let getters = if spec.getters { &spec.storage[..] } else { &[] };
+    let _ = writeln!(w);
+    let _ = writeln!(
+        w,
+        "    /// End-to-end tests instantiate the contract on a running node and call it."
+    );
+    let _ = writeln!(w, "    ///");
+    let _ = writeln!(
+        w,
+        "    /// They are only compiled with the `e2e-tests` feature. Start a node, e.g."
+    );
+    let _ = writeln!(
+        w,
+        "    /// `substrate-contracts-node`, and run them with `cargo test --features e2e-tests`."
+    );
+    let _ = writeln!(w, "    #[cfg(all(test, feature = \"e2e-tests\"))]");
+    let _ = writeln!(w, "    mod e2e_tests {{");
+    let _ = writeln!(w, "        use super::*;");
+    if !getters.is_empty() {
+        let _ = writeln!(w, "        use ink_e2e::build_message;");
+    }
+    let _ = writeln!(w);
+    let _ = writeln!(
+        w,
+        "        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;\n"
+    );
+    let _ = writeln!(w, "        #[ink_e2e::test]");
+    let _ = writeln!(
+        w,
+        "        async fn new_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {{"
+    );
+    let _ = writeln!(
+        w,
+        "            let constructor = {{{{camel_name}}}}Ref::new({});",
+        defaults
+    );
+    let account_id = if getters.is_empty() {
+        "_contract_account_id"
+    } else {
+        "contract_account_id"
+    };
+    let _ = writeln!(w, "            let {} = client", account_id);
+    let _ = writeln!(
+        w,
+        "                .instantiate(\"{{{{name}}}}\", &ink_e2e::alice(), constructor, 0, None)"
+    );
+    let _ = writeln!(w, "                .await");
+    let _ = writeln!(w, "                .expect(\"instantiate failed\")");
+    let _ = writeln!(w, "                .account_id;");
+    for field in getters {
+        let _ = writeln!(w);
+        let _ = writeln!(
+            w,
+            "            let {} = build_message::<{{{{camel_name}}}}Ref>(contract_account_id.clone())",
+            field.name
+        );
+        let _ = writeln!(
+            w,
+            "                .call(|{{{{name}}}}| {{{{name}}}}.{}());",
+            field.name
+        );
+        let _ = writeln!(
+            w,
+            "            client.call_dry_run(&ink_e2e::alice(), &{}, 0, None).await;",
+            field.name
+        );
+    }
+    let _ = writeln!(w, "            Ok(())");
+    let _ = writeln!(w, "        }}");
+    let _ = writeln!(w, "    }}");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::new::DEFAULT_INK_VERSION;
+    use std::io::Cursor;
+
+    #[test]
+    fn splits_fields_with_generic_types() {
+        let fields = parse_fields("balances: HashMap<AccountId, Balance>, owner: AccountId")
+            .expect("fields must be parsed");
+        assert_eq!(
+            fields,
+            vec![
+                Field {
+                    name: "balances".into(),
+                    ty: "HashMap<AccountId, Balance>".into()
+                },
+                Field {
+                    name: "owner".into(),
+                    ty: "AccountId".into()
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn rejects_invalid_identifiers() {
+        assert_eq!(
+            parse_fields("1value: bool").unwrap_err().to_string(),
+            "`1value` is not a valid identifier"
+        );
+        assert_eq!(
+            parse_events("Flipped(value: bool").unwrap_err().to_string(),
+            "Missing `)` in `Flipped(value: bool`"
+        );
+    }
+
+    #[test]
+    fn asks_again_on_invalid_answers() {
+        let mut input = Cursor::new(
+            "value bool\nvalue: bool, owner: AccountId\n\nflip\nmaybe\ny\n\nFlipped(new_value: bool), Reset\nn\n\n\n",
+        );
+        let mut output = Vec::new();
+        let spec =
+            ask(&mut input, &mut output, DEFAULT_INK_VERSION, false).expect("wizard must succeed");
+
+        assert_eq!(spec.storage.len(), 2);
+        assert!(spec.getters);
+        assert_eq!(
+            spec.messages,
+            vec![Message {
+                name: "flip".into(),
+                mutates: true,
+                payable: false
+            }]
+        );
+        assert_eq!(spec.events.len(), 2);
+        assert_eq!(spec.events[1].fields, Vec::new());
+        assert!(!spec.payable_constructor);
+        assert!(!spec.e2e);
+        assert_eq!(spec.ink_version, DEFAULT_INK_VERSION);
+    }
+
+    #[test]
+    fn asks_for_ink_4_with_e2e_tests() {
+        let mut input = Cursor::new("\n\n\n\n\ny\n3.0.0-rc6\n\n");
+        let mut output = Vec::new();
+        let spec =
+            ask(&mut input, &mut output, DEFAULT_INK_VERSION, false).expect("wizard must succeed");
+
+        assert!(spec.e2e);
+        assert_eq!(spec.ink_version, INK_4_VERSION);
+        let output = String::from_utf8(output).unwrap();
+        assert!(output.contains("End-to-end tests require ink! 4 or later, not 3.0.0-rc6"));
+
+        let lib = render(&spec);
+        assert!(!lib.contains("ink_lang"));
+        assert!(lib.contains("    #[cfg(all(test, feature = \"e2e-tests\"))]\n    mod e2e_tests {"));
+        assert!(lib.contains("{{camel_name}}Ref::new(Default::default())"));
+        assert!(lib.contains(".call(|{{name}}| {{name}}.value());"));
+    }
+
+    #[test]
+    fn renders_contract_skeleton() {
+        let spec = ContractSpec {
+            storage: parse_fields("value: bool").unwrap(),
+            messages: vec![Message {
+                name: "flip".into(),
+                mutates: true,
+                payable: true,
+            }],
+            events: parse_events("Flipped(new_value: bool)").unwrap(),
+            payable_constructor: true,
+            getters: true,
+            e2e: false,
+            ink_version: DEFAULT_INK_VERSION.into(),
+        };
+        let lib = render(&spec);
+
+        assert!(lib.contains("mod {{name}} {"));
+        assert!(lib.contains("pub struct {{camel_name}} {\n        value: bool,\n    }"));
+        assert!(lib.contains("pub struct Flipped {\n        new_value: bool,\n    }"));
+        assert!(lib.contains("pub fn new(value: bool) -> Self {"));
+        assert!(lib.contains("pub fn value(&self) -> bool {"));
+        assert!(lib.contains("#[ink(message, payable)]\n        pub fn flip(&mut self) {"));
+        assert!(!lib.contains("transferred_balance"));
+        assert!(!lib.contains("e2e_tests"));
+    }
+}
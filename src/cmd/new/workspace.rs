@@ -0,0 +1,279 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use super::{is_ink_4, unpack, validate_name, ExecuteArgs, DEFAULT_INK_VERSION};
+use anyhow::Result;
+use heck::CamelCase as _;
+use std::{collections::HashSet, env, fmt::Write as _, fs, path::Path};
+
+/// Creates a cargo workspace `name` with a contract crate for each of `contracts` and a
+/// `{name}_types` crate with the types shared between them.
+///
+/// The first contract is the entry point of the workspace: it instantiates all other
+/// contracts from their code hashes and calls them.
+pub(crate) fn execute<P>(
+    name: &str,
+    dir: Option<P>,
+    contracts: &[String],
+    args: &ExecuteArgs,
+) -> Result<Option<String>>
+where
+    P: AsRef<Path>,
+{
+    validate_name(name)?;
+    if let Some(version) = &args.ink_version {
+        if is_ink_4(version)? {
+            anyhow::bail!(
+                "Workspaces are only available for ink! 3, not for ink! {}",
+                version
+            )
+        }
+    }
+    let types = format!("{}_types", name);
+    if contracts.is_empty() {
+        anyhow::bail!("A workspace needs at least one contract, add them with `--contracts`");
+    }
+    let mut unique = HashSet::new();
+    for contract in contracts {
+        validate_name(contract)?;
+        if contract == &types {
+            anyhow::bail!(
+                "The contract name {} is reserved for the shared types",
+                types
+            );
+        }
+        if !unique.insert(contract) {
+            anyhow::bail!("The contract {} is listed more than once", contract);
+        }
+    }
+
+    let out_dir = dir
+        .map_or(env::current_dir()?, |p| p.as_ref().to_path_buf())
+        .join(name);
+    if out_dir.join("Cargo.toml").exists() {
+        anyhow::bail!("A Cargo package already exists in {}", name);
+    }
+    if !out_dir.exists() {
+        fs::create_dir(&out_dir)?;
+    }
+
+    let members = contracts
+        .iter()
+        .map(|contract| format!("    \"contracts/{}\",\n", contract))
+        .collect::<String>();
+    unpack("workspace/root", &out_dir, &[("members", members)], None)?;
+
+    let placeholders = |crate_name| {
+        let mut placeholders = args.placeholders(crate_name, DEFAULT_INK_VERSION);
+        placeholders.push(("types", types.clone()));
+        placeholders
+    };
+    unpack(
+        "workspace/types",
+        &out_dir.join("types"),
+        &placeholders(&types),
+        None,
+    )?;
+
+    let (entry, callees) = contracts
+        .split_first()
+        .expect("checked to be non-empty above");
+    let contracts_dir = out_dir.join("contracts");
+    for callee in callees {
+        unpack(
+            "workspace/contract",
+            &contracts_dir.join(callee),
+            &placeholders(callee),
+            None,
+        )?;
+    }
+    if callees.is_empty() {
+        unpack(
+            "workspace/contract",
+            &contracts_dir.join(entry),
+            &placeholders(entry),
+            None,
+        )?;
+    } else {
+        let mut entry_placeholders = placeholders(entry);
+        entry_placeholders.extend(callee_placeholders(callees));
+        unpack(
+            "workspace/entry",
+            &contracts_dir.join(entry),
+            &entry_placeholders,
+            None,
+        )?;
+    }
+
+    Ok(Some(format!(
+        "Created workspace {} with the contracts {}",
+        name,
+        contracts.join(", ")
+    )))
+}
+
+/// Returns the placeholders wiring the entry contract to the `callees`.
+fn callee_placeholders(callees: &[String]) -> Vec<(&'static str, String)> {
+    let mut dependencies = String::new();
+    let mut features = String::new();
+    let mut imports = String::new();
+    let mut fields = String::new();
+    let mut params = String::new();
+    let mut instantiations = String::new();
+    let mut inits = String::new();
+    let mut calls = String::new();
+
+    // keep a share of the endowment for the entry contract itself
+    let shares = callees.len() + 1;
+    // writing to a `String` never fails
+    for callee in callees {
+        let camel_callee = callee.to_camel_case();
+        let _ = writeln!(
+            dependencies,
+            "{} = {{ path = \"../{}\", default-features = false, features = [\"ink-as-dependency\"] }}",
+            callee, callee
+        );
+        let _ = writeln!(features, "    \"{}/std\",", callee);
+        let _ = writeln!(imports, "    use {}::{};", callee, camel_callee);
+        let _ = writeln!(fields, "        {}: {},", callee, camel_callee);
+        let _ = write!(params, ", {}_code_hash: Hash", callee);
+        let _ = writeln!(
+            instantiations,
+            "            let {callee} = {camel}::new()\n                \
+             .endowment(total_balance / {shares})\n                \
+             .code_hash({callee}_code_hash)\n                \
+             .salt_bytes(salt)\n                \
+             .instantiate()\n                \
+             .expect(\"failed at instantiating the `{camel}` contract\");",
+            callee = callee,
+            camel = camel_callee,
+            shares = shares,
+        );
+        let _ = writeln!(inits, "                {},", callee);
+        let _ = writeln!(calls, "            self.{}.set(number);", callee);
+    }
+
+    vec![
+        ("callee_dependencies", dependencies),
+        ("callee_features", features),
+        ("callee_imports", imports),
+        ("callee_fields", fields),
+        ("callee_params", params),
+        ("callee_instantiations", instantiations),
+        ("callee_inits", inits),
+        ("callee_calls", calls),
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    fn contracts(names: &[&str]) -> Vec<String> {
+        names.iter().map(ToString::to_string).collect()
+    }
+
+    fn contract_manifest(workspace_dir: &Path, contract: &str) -> std::path::PathBuf {
+        workspace_dir
+            .join("contracts")
+            .join(contract)
+            .join("Cargo.toml")
+    }
+
+    #[test]
+    fn creates_workspace_with_wired_contracts() {
+        with_tmp_dir(|path| {
+            execute(
+                "dapp",
+                Some(path),
+                &contracts(&["router", "vault", "oracle"]),
+                &Default::default(),
+            )?;
+            let workspace_dir = path.join("dapp");
+
+            let root = fs::read_to_string(workspace_dir.join("Cargo.toml"))?;
+            assert!(root.contains("\"types\",\n    \"contracts/router\",\n    \"contracts/vault\",\n    \"contracts/oracle\",\n]"));
+
+            let types = fs::read_to_string(workspace_dir.join("types").join("Cargo.toml"))?;
+            assert!(types.contains("name = \"dapp_types\""));
+
+            let router = fs::read_to_string(contract_manifest(&workspace_dir, "router"))?;
+            assert!(router.contains("dapp_types = { path = \"../../types\""));
+            assert!(router.contains(
+                "vault = { path = \"../vault\", default-features = false, features = [\"ink-as-dependency\"] }"
+            ));
+            assert!(router.contains("    \"oracle/std\",\n]"));
+            let router = fs::read_to_string(workspace_dir.join("contracts/router/lib.rs"))?;
+            assert!(router.contains("use oracle::Oracle;"));
+            assert!(router.contains(
+                "pub fn new(version: u32, vault_code_hash: Hash, oracle_code_hash: Hash) -> Self {"
+            ));
+            assert!(router.contains(".endowment(total_balance / 3)"));
+            assert!(router.contains("self.vault.set(number);"));
+
+            let vault = fs::read_to_string(workspace_dir.join("contracts/vault/lib.rs"))?;
+            assert!(vault.contains("mod vault {\n    use dapp_types::Value;"));
+            assert!(!vault.contains("{{"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn single_contract_workspace_has_no_calls() {
+        with_tmp_dir(|path| {
+            execute(
+                "dapp",
+                Some(path),
+                &contracts(&["token"]),
+                &Default::default(),
+            )?;
+
+            let token = fs::read_to_string(path.join("dapp/contracts/token/lib.rs"))?;
+            assert!(token.contains("pub fn new() -> Self {"));
+            assert!(!token.contains("{{"));
+            Ok(())
+        })
+    }
+
+    #[test]
+    fn rejects_duplicate_contracts() {
+        with_tmp_dir(|path| {
+            let result = execute(
+                "dapp",
+                Some(path),
+                &contracts(&["token", "dapp_types"]),
+                &Default::default(),
+            );
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "The contract name dapp_types is reserved for the shared types"
+            );
+
+            let result = execute(
+                "dapp",
+                Some(path),
+                &contracts(&["token", "token"]),
+                &Default::default(),
+            );
+            assert_eq!(
+                result.unwrap_err().to_string(),
+                "The contract token is listed more than once"
+            );
+            Ok(())
+        })
+    }
+}
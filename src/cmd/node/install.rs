@@ -0,0 +1,432 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Installs released `substrate-contracts-node` binaries into the cache directory with
+//! `cargo contract node install`.
+//!
+//! The version to use is pinned in the `[node]` section of the `contract.toml` of the project
+//! or of the `config.toml` of the config directory, e.g.
+//!
+//! ```toml
+//! [node]
+//! version = "v0.24.0"
+//! sha256 = "<the sha256 of the release archive>"
+//! ```
+//!
+//! The archive of a version is checked against the pinned `sha256` when it is installed. The
+//! sha256 of the binary unpacked from it is recorded then, and the binary is hashed and checked
+//! against it, and the archive it came from against the pinned `sha256`, before the node is run.
+
+use std::{
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Deserialize;
+use sha2::{Digest as _, Sha256};
+use structopt::StructOpt;
+
+use super::DEFAULT_NODE;
+use crate::util::{self, PROJECT_CONFIG};
+
+/// The releases of `substrate-contracts-node`.
+const RELEASES: &str = "https://github.com/paritytech/substrate-contracts-node/releases/download";
+
+/// The file next to an installed binary with the sha256 of the archive it was installed from.
+const CHECKSUM_FILE: &str = "archive.sha256";
+
+/// The file next to an installed binary with its sha256.
+const BINARY_CHECKSUM_FILE: &str = "binary.sha256";
+
+/// Downloads a release of `substrate-contracts-node` into the cache directory.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "install")]
+pub struct InstallCommand {
+    /// The release to install, e.g. `v0.24.0`. Defaults to the `version` of the `[node]`
+    /// section of the `contract.toml` or of the `config.toml` of the config directory
+    #[structopt(long)]
+    version: Option<String>,
+    /// The sha256 the release archive must have. Defaults to the configured `sha256` when
+    /// installing the configured version
+    #[structopt(long)]
+    sha256: Option<String>,
+    /// Download the release again even if it is installed
+    #[structopt(long)]
+    force: bool,
+}
+
+impl InstallCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let config = NodeConfig::load()?;
+        let version = match (&self.version, &config.version) {
+            (Some(version), _) | (None, Some(version)) => version,
+            (None, None) => anyhow::bail!(
+                "Pass --version or configure the `version` of the `[node]` section in {} or the \
+                 config directory",
+                PROJECT_CONFIG
+            ),
+        };
+        let sha256 = match &self.sha256 {
+            Some(sha256) => Some(sha256),
+            None if config.version.as_ref() == Some(version) => config.sha256.as_ref(),
+            None => None,
+        };
+
+        let dir = version_dir(version)?;
+        let binary = dir.join(DEFAULT_NODE);
+        if binary.exists() && !self.force {
+            check_installed(&dir, sha256)?;
+            return Ok(Some(format!(
+                "{} {} is already installed at {}",
+                DEFAULT_NODE,
+                version,
+                binary.display()
+            )));
+        }
+
+        let url = format!("{}/{}/{}", RELEASES, version, asset()?);
+        println!("{} {}", "Downloading".green().bold(), url);
+        let archive = install(&url, &dir, sha256.map(String::as_str))?;
+        if sha256.is_none() {
+            println!(
+                "{} the archive is not verified, pin `sha256 = \"{}\"` in the `[node]` section \
+                 to verify it",
+                "warning:".yellow().bold(),
+                archive
+            );
+        }
+        Ok(Some(format!(
+            "Installed {} {} at {}",
+            DEFAULT_NODE,
+            version,
+            binary.display()
+        )))
+    }
+}
+
+/// Lists the releases of `substrate-contracts-node` installed in the cache directory.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "list")]
+pub struct ListCommand {}
+
+impl ListCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let root = nodes_dir()?;
+        if !root.exists() {
+            return Ok(Some("No node is installed".to_string()));
+        }
+        let configured = NodeConfig::load()?.version;
+        let mut versions = fs::read_dir(&root)?
+            .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+            .collect::<Result<Vec<_>>>()?;
+        versions.retain(|version| root.join(version).join(DEFAULT_NODE).exists());
+        versions.sort();
+        for version in &versions {
+            if configured.as_ref() == Some(version) {
+                println!("{} {}", version, "(configured)".green());
+            } else {
+                println!("{}", version);
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// The `[node]` section of the configuration.
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub(crate) struct NodeConfig {
+    /// The release of `substrate-contracts-node` to run the end-to-end tests against.
+    pub version: Option<String>,
+    /// The sha256 of the release archive.
+    pub sha256: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Config {
+    node: Option<NodeConfig>,
+}
+
+impl NodeConfig {
+    /// Returns the `[node]` section of the project, or else the one of the config directory.
+    pub fn load() -> Result<Self> {
+        let (project, global) = util::load_configs::<Config>()?;
+        Ok(project
+            .and_then(|config| config.node)
+            .or_else(|| global.and_then(|config| config.node))
+            .unwrap_or_default())
+    }
+}
+
+/// Returns the path of the binary of the configured release, checked against the configured
+/// sha256, or `None` if no release is configured.
+pub(crate) fn configured() -> Result<Option<PathBuf>> {
+    let config = NodeConfig::load()?;
+    let version = match config.version {
+        Some(version) => version,
+        None => return Ok(None),
+    };
+    let dir = version_dir(&version)?;
+    let binary = dir.join(DEFAULT_NODE);
+    if !binary.exists() {
+        anyhow::bail!(
+            "{} {} is configured but not installed, install it with `cargo contract node install`",
+            DEFAULT_NODE,
+            version
+        )
+    }
+    check_installed(&dir, config.sha256.as_ref())?;
+    Ok(Some(binary))
+}
+
+/// Returns the directory of the installed releases.
+fn nodes_dir() -> Result<PathBuf> {
+    Ok(crate::util::cache_dir()?.join("nodes"))
+}
+
+/// Returns the directory of the installed release `version`.
+fn version_dir(version: &str) -> Result<PathBuf> {
+    validate_version(version)?;
+    Ok(nodes_dir()?.join(version))
+}
+
+/// Checks that `version` is a single path component of `[0-9A-Za-z._-]`, other than `.` and
+/// `..`, so that its directory stays below the directory of the installed releases.
+fn validate_version(version: &str) -> Result<()> {
+    let valid_char = |c: char| c.is_ascii_alphanumeric() || matches!(c, '.' | '_' | '-');
+    if version.is_empty() || !version.chars().all(valid_char) || version == "." || version == ".." {
+        anyhow::bail!("Invalid node version `{}`", version)
+    }
+    Ok(())
+}
+
+/// Returns the name of the release archive for the current platform.
+fn asset() -> Result<&'static str> {
+    match (std::env::consts::OS, std::env::consts::ARCH) {
+        ("linux", "x86_64") => Ok("substrate-contracts-node-linux.tar.gz"),
+        ("macos", _) => Ok("substrate-contracts-node-mac-universal.tar.gz"),
+        (os, arch) => anyhow::bail!(
+            "There is no released {} for {}-{}, build it and set $CONTRACTS_NODE to its path",
+            DEFAULT_NODE,
+            os,
+            arch
+        ),
+    }
+}
+
+/// Checks that the binary installed in `dir` is the one unpacked on installation, and that it
+/// was installed from an archive with `sha256`.
+fn check_installed(dir: &Path, sha256: Option<&String>) -> Result<()> {
+    let unpacked = fs::read_to_string(dir.join(BINARY_CHECKSUM_FILE)).unwrap_or_default();
+    if sha256_file(&dir.join(DEFAULT_NODE))? != unpacked.trim() {
+        anyhow::bail!(
+            "The node in {} changed since it was installed, reinstall it with `cargo contract \
+             node install --force`",
+            dir.display()
+        )
+    }
+    let expected = match sha256 {
+        Some(sha256) => sha256,
+        None => return Ok(()),
+    };
+    let installed = fs::read_to_string(dir.join(CHECKSUM_FILE)).unwrap_or_default();
+    if !installed.trim().eq_ignore_ascii_case(expected.trim()) {
+        anyhow::bail!(
+            "The node in {} was not installed from an archive with the sha256 {}, reinstall it \
+             with `cargo contract node install --force`",
+            dir.display(),
+            expected
+        )
+    }
+    Ok(())
+}
+
+/// Downloads the archive at `url`, checks it against `sha256` and unpacks its binary into
+/// `dir`. Returns the sha256 of the archive.
+fn install(url: &str, dir: &Path, sha256: Option<&str>) -> Result<String> {
+    let root = nodes_dir()?;
+    fs::create_dir_all(&root)?;
+    let tmp_dir = tempfile::Builder::new()
+        .prefix(".installing-")
+        .tempdir_in(&root)?;
+    let archive = tmp_dir.path().join("node.tar.gz");
+    let output = Command::new("curl")
+        .args([
+            "--silent",
+            "--show-error",
+            "--location",
+            "--fail",
+            "--output",
+        ])
+        .arg(&archive)
+        .arg(url)
+        .output()
+        .context("Failed to run `curl`, which downloads the node")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to download {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+
+    let checksum = sha256_file(&archive)?;
+    if let Some(expected) = sha256 {
+        if !checksum.eq_ignore_ascii_case(expected.trim()) {
+            anyhow::bail!(
+                "The sha256 of {} is {}, but {} was expected",
+                url,
+                checksum,
+                expected
+            )
+        }
+    }
+
+    let unpacked = tmp_dir.path().join("unpacked");
+    fs::create_dir_all(&unpacked)?;
+    let output = Command::new("tar")
+        .arg("-xzf")
+        .arg(&archive)
+        .arg("-C")
+        .arg(&unpacked)
+        .output()
+        .context("Failed to run `tar`, which unpacks the node")?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "Failed to unpack {}: {}",
+            url,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )
+    }
+    let binary = find_binary(&unpacked)?
+        .with_context(|| format!("The archive {} contains no {}", url, DEFAULT_NODE))?;
+
+    let staged = tmp_dir.path().join("node");
+    fs::create_dir_all(&staged)?;
+    fs::rename(&binary, staged.join(DEFAULT_NODE))?;
+    fs::write(staged.join(CHECKSUM_FILE), &checksum)?;
+    fs::write(
+        staged.join(BINARY_CHECKSUM_FILE),
+        sha256_file(&staged.join(DEFAULT_NODE))?,
+    )?;
+    if dir.exists() {
+        fs::remove_dir_all(dir)?;
+    }
+    fs::rename(&staged, dir)?;
+    Ok(checksum)
+}
+
+/// Returns the path of the node binary somewhere below `dir`.
+fn find_binary(dir: &Path) -> Result<Option<PathBuf>> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            if let Some(binary) = find_binary(&path)? {
+                return Ok(Some(binary));
+            }
+        } else if path.file_name().is_some_and(|name| name == DEFAULT_NODE) {
+            return Ok(Some(path));
+        }
+    }
+    Ok(None)
+}
+
+/// Returns the hex of the sha256 of the file at `path`.
+fn sha256_file(path: &Path) -> Result<String> {
+    let mut file = fs::File::open(path)?;
+    let mut hasher = Sha256::new();
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let read = file.read(&mut buf)?;
+        if read == 0 {
+            break;
+        }
+        hasher.update(&buf[..read]);
+    }
+    Ok(hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_the_binary_in_the_unpacked_archive() {
+        let dir = tempfile::tempdir().unwrap();
+        let nested = dir
+            .path()
+            .join("artifacts")
+            .join("substrate-contracts-node-linux");
+        fs::create_dir_all(&nested).unwrap();
+        fs::write(nested.join("README"), "").unwrap();
+        fs::write(nested.join(DEFAULT_NODE), "").unwrap();
+
+        let binary = find_binary(dir.path()).unwrap();
+
+        assert_eq!(binary, Some(nested.join(DEFAULT_NODE)));
+    }
+
+    #[test]
+    fn hashes_files_with_sha256() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("data");
+        fs::write(&path, "abc").unwrap();
+
+        assert_eq!(
+            sha256_file(&path).unwrap(),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad"
+        );
+    }
+
+    #[test]
+    fn rejects_binaries_changed_since_they_were_installed() {
+        let dir = tempfile::tempdir().unwrap();
+        fs::write(dir.path().join(DEFAULT_NODE), "abc").unwrap();
+        fs::write(dir.path().join(CHECKSUM_FILE), "01ab").unwrap();
+        fs::write(
+            dir.path().join(BINARY_CHECKSUM_FILE),
+            "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+        )
+        .unwrap();
+        let pinned = "01AB".to_string();
+
+        assert!(check_installed(dir.path(), Some(&pinned)).is_ok());
+        assert!(check_installed(dir.path(), Some(&"02ab".to_string())).is_err());
+        fs::write(dir.path().join(DEFAULT_NODE), "abd").unwrap();
+        assert!(check_installed(dir.path(), None).is_err());
+    }
+
+    #[test]
+    fn rejects_versions_escaping_the_cache_directory() {
+        assert!(version_dir("../v1").is_err());
+        assert!(version_dir("..").is_err());
+        assert!(version_dir(".").is_err());
+        for version in &["", "v1/..", "a\\b", "v0 24", "~"] {
+            assert!(validate_version(version).is_err(), "{}", version);
+        }
+        for version in &["v0.24.0", "0.24.0-rc1", "latest_build"] {
+            assert!(validate_version(version).is_ok(), "{}", version);
+        }
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Runs a local `substrate-contracts-node` for the end-to-end tests of
+//! `cargo contract test --e2e`, and installs released nodes with `cargo contract node`.
+//!
+//! The node runs a development chain on free ports with a temporary base path, which is
+//! removed once the node is stopped.
+
+mod install;
+
+use self::install::{InstallCommand, ListCommand};
+use std::{
+    env, fs,
+    net::{Ipv4Addr, TcpListener, TcpStream},
+    path::{Path, PathBuf},
+    process::{Child, Command, Stdio},
+    thread,
+    time::{Duration, Instant},
+};
+
+use anyhow::{Context, Result};
+use structopt::StructOpt;
+use tempfile::TempDir;
+
+/// The environment variable with the path of the node binary, also read by the end-to-end
+/// testing framework of ink!.
+pub(crate) const NODE_VAR: &str = "CONTRACTS_NODE";
+
+/// The environment variable with the url of the running node, exported to the tests.
+pub(crate) const URL_VAR: &str = "CONTRACTS_NODE_URL";
+
+/// The name of the node binary looked up in the `PATH` if `$CONTRACTS_NODE` is not set.
+const DEFAULT_NODE: &str = "substrate-contracts-node";
+
+/// How long to wait for the node to serve its RPC.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Manages the `substrate-contracts-node` binaries the end-to-end tests run against.
+#[derive(Debug, StructOpt)]
+pub enum NodeCommand {
+    /// Download a release of the node into the cache directory
+    #[structopt(name = "install")]
+    Install(InstallCommand),
+    /// List the releases of the node installed in the cache directory
+    #[structopt(name = "list")]
+    List(ListCommand),
+}
+
+impl NodeCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match self {
+            NodeCommand::Install(install) => install.exec(),
+            NodeCommand::List(list) => list.exec(),
+        }
+    }
+}
+
+/// A running node, which is stopped when dropped.
+pub(crate) struct ContractsNode {
+    child: Child,
+    url: String,
+    /// The base path of the chain, removed once the node is stopped.
+    _base_path: TempDir,
+}
+
+impl ContractsNode {
+    /// The websockets url of the RPC of the node.
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+impl Drop for ContractsNode {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+/// Returns the path of the node binary, from `$CONTRACTS_NODE`, the release configured in the
+/// `[node]` section, or else the `PATH`.
+pub(crate) fn locate() -> Result<PathBuf> {
+    if let Some(path) = env::var_os(NODE_VAR) {
+        return Ok(path.into());
+    }
+    if let Some(path) = install::configured()? {
+        return Ok(path);
+    }
+    which::which(DEFAULT_NODE).map_err(|_| {
+        anyhow::anyhow!(
+            "`{}` was not found in the PATH, install it or set ${} to its path",
+            DEFAULT_NODE,
+            NODE_VAR
+        )
+    })
+}
+
+/// Starts the node binary at `program` on a development chain and waits until it serves its
+/// RPC.
+pub(crate) fn spawn(program: &Path) -> Result<ContractsNode> {
+    let base_path = tempfile::Builder::new()
+        .prefix("cargo-contract-node.")
+        .tempdir()?;
+    let rpc_port = free_port()?;
+    let log_path = base_path.path().join("node.log");
+    let log = fs::File::create(&log_path)?;
+    let child = Command::new(program)
+        .args(node_args(base_path.path(), rpc_port, free_port()?))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(log)
+        .spawn()
+        .with_context(|| format!("Failed to run `{}`", program.display()))?;
+    let mut node = ContractsNode {
+        child,
+        url: format!("ws://127.0.0.1:{}", rpc_port),
+        _base_path: base_path,
+    };
+    if let Err(err) = wait_for_port(&mut node, rpc_port) {
+        let log = fs::read_to_string(&log_path).unwrap_or_default();
+        let mut tail = log.lines().rev().take(10).collect::<Vec<_>>();
+        tail.reverse();
+        anyhow::bail!("{}, the last lines it logged:\n{}", err, tail.join("\n"))
+    }
+    log::debug!(
+        "Started {} at {}, logging to {}",
+        program.display(),
+        node.url,
+        log_path.display()
+    );
+    Ok(node)
+}
+
+/// Returns the arguments running a development chain under `base_path`, serving its RPC on
+/// `rpc_port` and listening for peers on `p2p_port`.
+fn node_args(base_path: &Path, rpc_port: u16, p2p_port: u16) -> Vec<String> {
+    vec![
+        "--dev".to_string(),
+        format!("--base-path={}", base_path.display()),
+        format!("--rpc-port={}", rpc_port),
+        format!("--port={}", p2p_port),
+        "--no-prometheus".to_string(),
+    ]
+}
+
+/// Returns a port which is free at the moment.
+fn free_port() -> Result<u16> {
+    Ok(TcpListener::bind((Ipv4Addr::LOCALHOST, 0))?
+        .local_addr()?
+        .port())
+}
+
+/// Waits until the node accepts connections on `port`.
+fn wait_for_port(node: &mut ContractsNode, port: u16) -> Result<()> {
+    let started = Instant::now();
+    while TcpStream::connect((Ipv4Addr::LOCALHOST, port)).is_err() {
+        if let Some(status) = node.child.try_wait()? {
+            anyhow::bail!("The node exited with {} before serving its RPC", status)
+        }
+        if started.elapsed() > STARTUP_TIMEOUT {
+            anyhow::bail!(
+                "The node did not serve its RPC after {} seconds",
+                STARTUP_TIMEOUT.as_secs()
+            )
+        }
+        thread::sleep(Duration::from_millis(200));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn runs_a_development_chain_in_the_base_path() {
+        let args = node_args(Path::new("/tmp/node"), 9955, 30555);
+        assert_eq!(
+            args,
+            vec![
+                "--dev",
+                "--base-path=/tmp/node",
+                "--rpc-port=9955",
+                "--port=30555",
+                "--no-prometheus"
+            ]
+        );
+    }
+}
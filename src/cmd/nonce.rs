@@ -0,0 +1,176 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The nonces extrinsics are signed with: the next nonce of the account by default, the nonce
+//! of `--nonce`, or the next nonce tracked in a file per account with `--track-nonce`, so that
+//! commands submitting extrinsics of the same account in quick succession use distinct nonces.
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{Read, Seek, SeekFrom, Write},
+    path::PathBuf,
+};
+
+use super::{chain::AccountId, rpc};
+use crate::ExtrinsicOpts;
+use anyhow::{Context, Result};
+use fs2::FileExt;
+use sp_core::H256;
+
+/// A nonce given with `--nonce` or reserved with `--track-nonce`.
+pub(crate) struct Nonce {
+    pub value: u32,
+    tracker: Option<NonceTracker>,
+}
+
+impl Nonce {
+    /// Returns the nonce of `--nonce`, or reserves the next nonce of `account` on the chain
+    /// `genesis_hash` with `--track-nonce`. Without either, the next nonce of the account is
+    /// left to be fetched when signing.
+    pub async fn reserve(
+        extrinsic_opts: &ExtrinsicOpts,
+        genesis_hash: &H256,
+        account: &AccountId,
+    ) -> Result<Option<Nonce>> {
+        if let Some(nonce) = extrinsic_opts.nonce {
+            return Ok(Some(Nonce {
+                value: nonce,
+                tracker: None,
+            }));
+        }
+        if !extrinsic_opts.track_nonce {
+            return Ok(None);
+        }
+        let tracker = NonceTracker::open(genesis_hash, account)?;
+        let next = fetch(&extrinsic_opts.node.url()?, account).await?;
+        Ok(Some(Nonce {
+            value: tracker.reserve(next)?,
+            tracker: Some(tracker),
+        }))
+    }
+
+    /// Gives a reserved nonce back, after its extrinsic could not be submitted.
+    ///
+    /// The nonce is reused only if no later nonce was reserved in the meantime. Had the
+    /// extrinsic been submitted anyway, the next nonce of the account on chain is used.
+    pub fn release(self) {
+        if let Some(tracker) = self.tracker {
+            if let Err(err) = tracker.release(self.value) {
+                log::warn!("Failed to release the nonce {}: {:?}", self.value, err);
+            }
+        }
+    }
+}
+
+/// Returns the next nonce of `account`, including its extrinsics in the transaction pool.
+pub(crate) async fn fetch(url: &url::Url, account: &AccountId) -> Result<u32> {
+    rpc::request(
+        url,
+        "system_accountNextIndex",
+        vec![account.to_string().into()],
+    )
+    .await
+}
+
+/// The file in which the next nonce of an account is tracked.
+struct NonceTracker {
+    path: PathBuf,
+}
+
+impl NonceTracker {
+    /// Opens the tracker of `account` on the chain `genesis_hash`, in `nonces` of the config
+    /// directory.
+    fn open(genesis_hash: &H256, account: &AccountId) -> Result<Self> {
+        let dir = crate::util::config_dir()?.join("nonces");
+        fs::create_dir_all(&dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+        Ok(NonceTracker {
+            path: dir.join(format!("{:x}-{}", genesis_hash, account)),
+        })
+    }
+
+    /// Reserves the greater of the next nonce on chain `next` and the next tracked nonce.
+    fn reserve(&self, next: u32) -> Result<u32> {
+        self.update(|tracked| {
+            let nonce = tracked.map_or(next, |tracked| tracked.max(next));
+            (nonce, nonce + 1)
+        })
+    }
+
+    /// Tracks `nonce` as the next nonce again, if it was the last one reserved.
+    fn release(&self, nonce: u32) -> Result<()> {
+        self.update(|tracked| match tracked {
+            Some(tracked) if tracked == nonce + 1 => ((), nonce),
+            Some(tracked) => ((), tracked),
+            None => ((), nonce),
+        })
+    }
+
+    /// Updates the tracked nonce with `f`, holding a lock on the file so that concurrent
+    /// commands wait for each other.
+    fn update<R>(&self, f: impl FnOnce(Option<u32>) -> (R, u32)) -> Result<R> {
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&self.path)
+            .with_context(|| format!("Failed to open {}", self.path.display()))?;
+        file.lock_exclusive()?;
+        let result = Self::update_locked(&mut file, f);
+        file.unlock()?;
+        result
+    }
+
+    fn update_locked<R>(file: &mut File, f: impl FnOnce(Option<u32>) -> (R, u32)) -> Result<R> {
+        let mut tracked = String::new();
+        file.read_to_string(&mut tracked)?;
+        let tracked = match tracked.trim() {
+            "" => None,
+            tracked => Some(tracked.parse().context("Invalid tracked nonce")?),
+        };
+        let (result, next) = f(tracked);
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        write!(file, "{}", next)?;
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+
+    #[test]
+    fn tracks_reserved_nonces() {
+        with_tmp_dir(|path| {
+            let tracker = NonceTracker {
+                path: path.join("nonce"),
+            };
+            assert_eq!(tracker.reserve(5)?, 5);
+            // the extrinsic with the nonce 5 is not in the pool yet
+            assert_eq!(tracker.reserve(5)?, 6);
+            assert_eq!(tracker.reserve(9)?, 9);
+
+            tracker.release(9)?;
+            assert_eq!(tracker.reserve(5)?, 9);
+            // a nonce reserved before the last one is not reused
+            tracker.release(7)?;
+            assert_eq!(tracker.reserve(5)?, 10);
+            Ok(())
+        })
+    }
+}
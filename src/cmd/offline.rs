@@ -0,0 +1,133 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing extrinsics on another, possibly air-gapped, machine: with `--offline` the unsigned
+//! extrinsic is written to a file instead of being submitted, and `cargo contract
+//! submit-signed` submits it with the signature produced elsewhere.
+
+use std::{fs, path::PathBuf};
+
+use anyhow::{Context, Result};
+use sp_core::{sr25519, H256};
+use structopt::StructOpt;
+use subxt::{
+    sp_runtime::{codec::Decode, MultiSignature},
+    Client, DefaultNodeRuntime, Encoded,
+};
+
+use super::{
+    dry_run::hex,
+    nonce::{self, Nonce},
+    rpc,
+    signing::UnsignedExtrinsic,
+    submit,
+};
+use crate::{abi::decode_hex, ExtrinsicOpts, NodeOpts};
+
+/// Writes the unsigned extrinsic of the call encoded by `encode` to the file of `--offline`,
+/// instead of submitting it. The call is wrapped as it would be when submitted.
+pub(crate) fn write_unsigned<F>(extrinsic_opts: &ExtrinsicOpts, encode: F) -> Result<String>
+where
+    F: FnOnce(&Client<DefaultNodeRuntime>) -> Result<Encoded, subxt::Error>,
+{
+    let path = extrinsic_opts
+        .offline
+        .as_ref()
+        .context("Writing an unsigned extrinsic requires `--offline`")?;
+    let signer = extrinsic_opts.account_id()?;
+    let unsigned = async_std::task::block_on(async {
+        let cli = submit::client(extrinsic_opts).await?;
+        let call = submit::wrap(&cli, extrinsic_opts, encode(&cli)?).await?;
+        let nonce = match Nonce::reserve(extrinsic_opts, cli.genesis(), &signer).await? {
+            Some(nonce) => nonce.value,
+            None => nonce::fetch(&extrinsic_opts.node.url()?, &signer).await?,
+        };
+        UnsignedExtrinsic::new(&cli, extrinsic_opts, &signer, nonce, call).await
+    })?;
+    fs::write(path, serde_json::to_string_pretty(&unsigned)?)?;
+    Ok(format!(
+        "Written the unsigned extrinsic to {}\n\tSign its signingPayload with the account {} \
+         and submit it with `cargo contract submit-signed`",
+        path.display(),
+        unsigned.signer
+    ))
+}
+
+/// Submits an extrinsic written with `--offline` and signed on another machine.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "submit-signed")]
+pub struct SubmitSignedCommand {
+    /// The unsigned extrinsic written with `--offline`
+    #[structopt(parse(from_os_str))]
+    unsigned: PathBuf,
+    /// The hex encoded signature of the signing payload: a 64 byte sr25519 signature, or a
+    /// `MultiSignature` starting with the byte of its scheme
+    #[structopt(long)]
+    signature: String,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl SubmitSignedCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let unsigned = UnsignedExtrinsic::load(&self.unsigned)?;
+        let signature = parse_signature(&self.signature)?;
+        let extrinsic = unsigned.into_signed(signature)?;
+        let hash: H256 = self.node_opts.block_on(rpc::request(
+            &self.node_opts.url()?,
+            "author_submitExtrinsic",
+            vec![hex(&extrinsic).into()],
+        ))?;
+        Ok(Some(format!("Submitted the extrinsic {:?}", hash)))
+    }
+}
+
+/// Parses a hex encoded sr25519 signature or `MultiSignature`.
+fn parse_signature(signature: &str) -> Result<MultiSignature> {
+    let bytes = decode_hex(signature).context("Invalid signature")?;
+    if bytes.len() == 64 {
+        let mut raw = [0u8; 64];
+        raw.copy_from_slice(&bytes);
+        return Ok(sr25519::Signature::from_raw(raw).into());
+    }
+    MultiSignature::decode(&mut &bytes[..]).map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid signature of {} bytes, expected a 64 byte sr25519 signature or a \
+             MultiSignature",
+            bytes.len()
+        )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::{crypto::AccountId32, Pair};
+    use subxt::sp_runtime::{codec::Encode, traits::Verify};
+
+    #[test]
+    fn parses_signatures() {
+        let pair = sr25519::Pair::from_string("//Alice", None).unwrap();
+        let signature = pair.sign(b"payload");
+        let expected = MultiSignature::from(signature.clone());
+
+        let raw = hex(signature.as_ref());
+        assert_eq!(parse_signature(&raw).unwrap(), expected);
+        assert_eq!(parse_signature(&hex(&expected.encode())).unwrap(), expected);
+        assert!(parse_signature("0x0102").is_err());
+        assert!(expected.verify(&b"payload"[..], &AccountId32::from(pair.public())));
+    }
+}
@@ -0,0 +1,241 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Watching submitted extrinsics over unstable connections: once the connection to the node
+//! drops while an extrinsic is submitted or watched, the node is reconnected to with a backoff
+//! and the extrinsic is looked up by its hash in the blocks since it was submitted, resubmitting
+//! it if it was lost in the meantime.
+
+use std::time::Duration;
+
+use anyhow::Result;
+use jsonrpsee::{client::RequestError, common::Params};
+use serde::{de::IgnoredAny, Deserialize};
+use sp_core::{hashing::blake2_256, H256};
+use subxt::{DefaultNodeRuntime, ExtrinsicSuccess};
+
+use super::{dry_run::hex, events::RuntimeEvents, rpc, submit::SubmitError};
+use crate::{ExtrinsicOpts, WaitFor};
+
+/// How long to wait for new blocks while looking for a resumed extrinsic.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Submits the `encoded` extrinsic and waits for it as long as `--wait-for` asks for, resuming
+/// to watch it up to `--retries` times after the connection to the node dropped.
+pub(crate) async fn submit_and_watch(
+    extrinsic_opts: &ExtrinsicOpts,
+    encoded: Vec<u8>,
+) -> Result<ExtrinsicSuccess<DefaultNodeRuntime>> {
+    let since = rpc::block_number(&extrinsic_opts.node.url()?, None).await?;
+    let watched = Watched {
+        extrinsic_opts,
+        hash: H256(blake2_256(&encoded)),
+        encoded,
+    };
+    let mut err = match watched.submit().await {
+        Ok(block) => return watched.success(block).await,
+        Err(err) if extrinsic_opts.retries > 0 && !is_rejection(&err) => err,
+        Err(err) => return Err(err),
+    };
+
+    let mut backoff = Duration::from_secs(extrinsic_opts.retry_backoff);
+    let mut next = since;
+    for retry in 1..=extrinsic_opts.retries {
+        log::warn!(
+            "Lost the connection to the node: {}, reconnecting in {:?} ({} of {})",
+            err,
+            backoff,
+            retry,
+            extrinsic_opts.retries
+        );
+        async_std::task::sleep(backoff).await;
+        backoff *= 2;
+        match watched.resume(&mut next).await {
+            Ok(block) => return watched.success(block).await,
+            Err(resumed) if is_rejection(&resumed) => return Err(resumed),
+            Err(resumed) => err = resumed,
+        }
+    }
+    Err(err.context(format!(
+        "Failed to watch the extrinsic {:?} after {} retries",
+        watched.hash, extrinsic_opts.retries
+    )))
+}
+
+/// Returns `true` if `err` is not worth retrying: the node answered with an error, or the
+/// extrinsic failed or was not included.
+fn is_rejection(err: &anyhow::Error) -> bool {
+    err.downcast_ref::<SubmitError>().is_some()
+        || err
+            .downcast_ref::<RequestError>()
+            .is_some_and(|err| !is_disconnect(err))
+}
+
+/// Returns `true` if `err` is caused by the connection to the node, rather than by the node
+/// answering with an error.
+pub(super) fn is_disconnect(err: &RequestError) -> bool {
+    !matches!(err, RequestError::Request(_))
+}
+
+/// The status of a watched extrinsic, as notified by `author_submitAndWatchExtrinsic`.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum TransactionStatus {
+    Future,
+    Ready,
+    Broadcast(IgnoredAny),
+    InBlock(H256),
+    Retracted(IgnoredAny),
+    FinalityTimeout(IgnoredAny),
+    Finalized(H256),
+    Usurped(IgnoredAny),
+    Dropped,
+    Invalid,
+}
+
+impl TransactionStatus {
+    /// Returns the error of the statuses of extrinsics which are not included in a block, as
+    /// `subxt` reports them.
+    fn not_included(&self) -> Option<SubmitError> {
+        let status = match self {
+            TransactionStatus::FinalityTimeout(_) => "FinalityTimeout",
+            TransactionStatus::Usurped(_) => "Usurped",
+            TransactionStatus::Dropped => "Dropped",
+            TransactionStatus::Invalid => "Invalid",
+            _ => return None,
+        };
+        Some(SubmitError::NotIncluded(format!("Extrinsic {}", status)))
+    }
+}
+
+/// A submitted extrinsic whose watching is resumed.
+struct Watched<'a> {
+    extrinsic_opts: &'a ExtrinsicOpts,
+    encoded: Vec<u8>,
+    hash: H256,
+}
+
+impl Watched<'_> {
+    /// Submits the extrinsic and returns the block it is included in, once the block is final
+    /// enough for `--wait-for`.
+    async fn submit(&self) -> Result<H256> {
+        let client = jsonrpsee::ws_client(self.extrinsic_opts.node.url()?.as_str()).await?;
+        let params = Params::Array(vec![hex(&self.encoded).into()]);
+        let mut statuses = client
+            .subscribe::<TransactionStatus>(
+                "author_submitAndWatchExtrinsic",
+                params,
+                "author_unwatchExtrinsic",
+            )
+            .await?;
+        log::info!("Submitted the extrinsic {:?}", self.hash);
+        loop {
+            let status = statuses.next().await;
+            log::debug!("The extrinsic {:?} is {:?}", self.hash, status);
+            if let Some(err) = status.not_included() {
+                return Err(err.into());
+            }
+            match (status, self.extrinsic_opts.wait_for) {
+                (TransactionStatus::InBlock(block), WaitFor::InBlock)
+                | (TransactionStatus::Finalized(block), _) => return Ok(block),
+                // a retracted extrinsic is included in another block again
+                _ => (),
+            }
+        }
+    }
+
+    /// Looks up the extrinsic in the blocks from `next` on, until it is found in a block
+    /// which is final enough for `--wait-for`. The extrinsic is submitted again if it is
+    /// neither in a block nor in the transaction pool.
+    async fn resume(&self, next: &mut u64) -> Result<H256> {
+        let url = &self.extrinsic_opts.node.url()?;
+        let encoded = hex(&self.encoded);
+        loop {
+            let head: H256 = match self.extrinsic_opts.wait_for {
+                WaitFor::InBlock => rpc::request(url, "chain_getBlockHash", vec![]).await?,
+                WaitFor::Finalized | WaitFor::Broadcast => {
+                    rpc::request(url, "chain_getFinalizedHead", vec![]).await?
+                }
+            };
+            let head = rpc::block_number(url, Some(&head)).await?;
+            while *next <= head {
+                let hash: H256 =
+                    rpc::request(url, "chain_getBlockHash", vec![(*next).into()]).await?;
+                let extrinsics = rpc::extrinsics(url, &hash).await?;
+                if extrinsics.contains(&encoded) {
+                    return Ok(hash);
+                }
+                *next += 1;
+            }
+
+            let pending: Vec<String> =
+                rpc::request(url, "author_pendingExtrinsics", vec![]).await?;
+            if !pending.contains(&encoded) {
+                log::info!("Submitting the extrinsic {:?} again", self.hash);
+                let _: H256 =
+                    rpc::request(url, "author_submitExtrinsic", vec![encoded.clone().into()])
+                        .await?;
+            }
+            async_std::task::sleep(POLL_INTERVAL).await;
+        }
+    }
+
+    /// Returns the events of the extrinsic in the block `block`, as `subxt` does for the
+    /// extrinsics it watches, or the error it failed with.
+    async fn success(&self, block: H256) -> Result<ExtrinsicSuccess<DefaultNodeRuntime>> {
+        let url = &self.extrinsic_opts.node.url()?;
+        let encoded = hex(&self.encoded);
+        let index = rpc::extrinsics(url, &block)
+            .await?
+            .iter()
+            .position(|extrinsic| *extrinsic == encoded)
+            .ok_or_else(|| {
+                anyhow::anyhow!("The block {:?} lacks the extrinsic {:?}", block, self.hash)
+            })?;
+        let runtime = RuntimeEvents::fetch(url, &block).await?;
+        let events = rpc::events(url, &block).await?;
+        Ok(ExtrinsicSuccess {
+            block,
+            extrinsic: self.hash,
+            events: runtime.of_extrinsic(&events, index as u32)?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn tells_outcomes_apart_from_disconnects() {
+        let dropped: TransactionStatus = serde_json::from_str(r#""dropped""#).unwrap();
+        let dropped = anyhow::Error::from(dropped.not_included().unwrap());
+        assert!(is_rejection(&dropped));
+        assert_eq!(
+            dropped
+                .downcast_ref::<SubmitError>()
+                .map(SubmitError::exit_code),
+            Some(4)
+        );
+
+        let in_block: TransactionStatus =
+            serde_json::from_str(&format!(r#"{{"inBlock":"0x{}"}}"#, "01".repeat(32))).unwrap();
+        assert!(in_block.not_included().is_none());
+
+        let closed = RequestError::TransportError("background task closed".into());
+        assert!(!is_rejection(&closed.into()));
+    }
+}
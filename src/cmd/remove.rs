@@ -0,0 +1,165 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use anyhow::{Context, Result};
+use sp_core::H256;
+use subxt::{
+    sp_core::storage::StorageKey,
+    sp_runtime::{
+        codec::{Compact, Decode, Encode, Error as CodecError, Input},
+        AccountId32,
+    },
+    Call, ClientBuilder, DefaultNodeRuntime, Metadata, MetadataError, Store,
+};
+
+use super::{
+    confirm::Summary,
+    dry_run::CONTRACTS,
+    submit::{self, Submitted},
+};
+use crate::ExtrinsicOpts;
+
+/// The `Contracts::remove_code` Call, which `subxt` does not provide.
+struct RemoveCodeCall {
+    code_hash: H256,
+}
+
+impl Encode for RemoveCodeCall {
+    fn encode(&self) -> Vec<u8> {
+        self.code_hash.encode()
+    }
+}
+
+impl Call<DefaultNodeRuntime> for RemoveCodeCall {
+    const MODULE: &'static str = CONTRACTS;
+    const FUNCTION: &'static str = "remove_code";
+}
+
+/// The `Contracts::OwnerInfoOf` storage of the uploaded code.
+pub(crate) struct OwnerInfoOfStore {
+    pub code_hash: H256,
+}
+
+impl Encode for OwnerInfoOfStore {
+    fn encode(&self) -> Vec<u8> {
+        self.code_hash.encode()
+    }
+}
+
+impl Store<DefaultNodeRuntime> for OwnerInfoOfStore {
+    const MODULE: &'static str = CONTRACTS;
+    const FIELD: &'static str = "OwnerInfoOf";
+    type Returns = OwnerInfo;
+
+    fn prefix(metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .prefix())
+    }
+
+    fn key(&self, metadata: &Metadata) -> Result<StorageKey, MetadataError> {
+        Ok(metadata
+            .module(Self::MODULE)?
+            .storage(Self::FIELD)?
+            .map()?
+            .key(&self.code_hash))
+    }
+}
+
+/// The owner of uploaded code, the deposit they paid for it and the number of contracts
+/// instantiated from it.
+#[derive(Debug)]
+pub(crate) struct OwnerInfo {
+    pub owner: AccountId32,
+    pub deposit: u128,
+    pub refcount: u64,
+}
+
+impl Decode for OwnerInfo {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(OwnerInfo {
+            owner: Decode::decode(input)?,
+            deposit: <Compact<u128>>::decode(input)?.0,
+            refcount: <Compact<u64>>::decode(input)?.0,
+        })
+    }
+}
+
+/// Removes the code stored under `code_hash`, refunding the deposit to its owner.
+///
+/// Creates an extrinsic with the `Contracts::remove_code` Call, submits via RPC, then waits for
+/// the `ContractsEvent::CodeRemoved` event. Only the owner of the code can remove it, and only
+/// if no live contract was instantiated from it.
+pub(crate) fn execute_remove(
+    extrinsic_opts: &ExtrinsicOpts,
+    code_hash: H256,
+) -> Result<Submitted<H256>> {
+    Summary::new("Remove the code")
+        .line("Code hash", format!("{:?}", code_hash))
+        .confirm(extrinsic_opts, |cli| {
+            cli.encode(RemoveCodeCall { code_hash })
+        })?;
+    async_std::task::block_on(async move {
+        let cli = submit::client(extrinsic_opts).await?;
+        let events = match submit::watch(&cli, extrinsic_opts, RemoveCodeCall { code_hash }).await?
+        {
+            Submitted::Executed(events) => events,
+            Submitted::Pending(pending) => return Ok(Submitted::Pending(pending)),
+        };
+        let code_removed = events
+            .find_event_raw(CONTRACTS, "CodeRemoved")
+            .context("Failed to find CodeRemoved event")?;
+        Ok(Submitted::Executed(H256::decode(
+            &mut &code_removed.data[..],
+        )?))
+    })
+}
+
+/// Returns the owner, deposit and number of contracts using the code stored under
+/// `code_hash`, or `None` if there is no such code, without removing it.
+///
+/// The code can only be removed if no contract uses it anymore.
+pub(crate) fn dry_run_remove(
+    extrinsic_opts: &ExtrinsicOpts,
+    code_hash: H256,
+) -> Result<Option<OwnerInfo>> {
+    async_std::task::block_on(async move {
+        let cli = ClientBuilder::<DefaultNodeRuntime>::new()
+            .set_url(extrinsic_opts.node.url()?.to_string())
+            .build()
+            .await?;
+        Ok(cli.fetch(&OwnerInfoOfStore { code_hash }, None).await?)
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_owner_info() {
+        let owner = AccountId32::from([1; 32]);
+        let mut encoded = owner.encode();
+        encoded.extend(Compact(1_000_000_000u128).encode());
+        encoded.extend(Compact(2u64).encode());
+
+        let info = OwnerInfo::decode(&mut &encoded[..]).unwrap();
+        assert_eq!(info.owner, owner);
+        assert_eq!(info.deposit, 1_000_000_000);
+        assert_eq!(info.refcount, 2);
+    }
+}
@@ -0,0 +1,621 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! An interactive session with a contract, `cargo contract repl`, which dry runs the messages
+//! typed and submits them only when asked to.
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use rustyline::{
+    completion::Completer, error::ReadlineError, highlight::Highlighter, hint::Hinter,
+    validate::Validator, Editor, Helper,
+};
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::{
+    call::{encode_call, submit_call},
+    chain::{parse_account, AccountId},
+    confirm::Summary,
+    dry_run::{self, Costs},
+    extrinsic_output::load_abi,
+    submit::Submitted,
+    token::Balance,
+};
+use crate::{
+    abi::{parse_arg_value, ContractAbi, MessageSpec, ReturnValue},
+    ExtrinsicOpts,
+};
+
+/// The file in the config directory the typed lines are appended to, and read from by the next
+/// session.
+const HISTORY: &str = "repl_history";
+
+const HELP: &str = "\
+<message> [args..]                 Dry run the message and print what it returns
+submit <message> [args..] [--value <balance>]
+                                   Submit a call of the message, after confirming it
+let <name> = <message> [args..]    Store the value the message returns as `$name`
+let <name> = <value>               Store a value as `$name`, e.g. `let bob = 5FHneW46..`
+messages                           List the messages with their arguments
+complete <prefix>                  List the messages and commands starting with `prefix`, which
+                                   the Tab key completes
+vars                               List the stored values
+history                            List the typed lines, `!<n>` repeats line `n`, `!!` the last
+help                               Print this help
+exit                               End the session
+
+Messages are given by their label or a prefix only they start with. Arguments are JSON or plain
+strings like for `cargo contract call --args`, and `$name` is replaced by the value `name`.";
+
+/// Starts an interactive session with a contract.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "repl")]
+pub struct ReplCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// The account of the contract to call
+    #[structopt(long, parse(try_from_str = parse_account))]
+    contract: AccountId,
+    /// Path to the Cargo.toml of the contract whose metadata encodes the arguments and decodes
+    /// the returned values
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+}
+
+/// Completes the word at the cursor with the Tab key, from the messages and the commands.
+struct ReplHelper {
+    words: Vec<String>,
+}
+
+impl Completer for ReplHelper {
+    type Candidate = String;
+
+    fn complete(
+        &self,
+        line: &str,
+        pos: usize,
+        _ctx: &rustyline::Context<'_>,
+    ) -> rustyline::Result<(usize, Vec<String>)> {
+        let start = line[..pos].rfind(char::is_whitespace).map_or(0, |i| i + 1);
+        let prefix = &line[start..pos];
+        let candidates = self
+            .words
+            .iter()
+            .filter(|word| word.starts_with(prefix))
+            .cloned()
+            .collect();
+        Ok((start, candidates))
+    }
+}
+
+impl Hinter for ReplHelper {
+    type Hint = String;
+}
+
+impl Highlighter for ReplHelper {}
+
+impl Validator for ReplHelper {}
+
+impl Helper for ReplHelper {}
+
+/// Whether the session goes on after a line.
+enum Flow {
+    Continue,
+    Exit,
+}
+
+/// The state of a session: its stored values and the lines typed so far.
+struct Session<'a> {
+    cmd: &'a ReplCommand,
+    abi: ContractAbi,
+    vars: BTreeMap<String, Value>,
+    history: Vec<String>,
+}
+
+impl ReplCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("The REPL requires the metadata of the contract")?;
+        let history_path = crate::util::config_dir()?.join(HISTORY);
+        let history = read_history(&history_path).unwrap_or_else(|err| {
+            log::debug!("Reading the history failed: {:?}", err);
+            Vec::new()
+        });
+        let mut editor = Editor::<ReplHelper>::new();
+        editor.set_helper(Some(ReplHelper {
+            words: completions(&abi, ""),
+        }));
+        for line in &history {
+            editor.add_history_entry(line.as_str());
+        }
+        let mut session = Session {
+            cmd: self,
+            abi,
+            vars: BTreeMap::new(),
+            history,
+        };
+        println!(
+            "Calling {} on {}, type `help` for the commands",
+            self.contract,
+            self.extrinsic_opts.node.url()?
+        );
+
+        let prompt = format!("{}> ", session.abi.name.as_deref().unwrap_or("contract"));
+        loop {
+            // the editor only reads stdin while editing, confirming a submitted call reads it too
+            let line = match editor.readline(&prompt) {
+                Ok(line) => line,
+                // Ctrl-C discards the line being edited
+                Err(ReadlineError::Interrupted) => continue,
+                Err(ReadlineError::Eof) => break,
+                Err(err) => return Err(err.into()),
+            };
+            let line = match expand_history(line.trim(), &session.history) {
+                Ok(line) if line.is_empty() => continue,
+                Ok(line) => line,
+                Err(err) => {
+                    eprintln!("{} {}", "error:".bright_red().bold(), err);
+                    continue;
+                }
+            };
+            session.history.push(line.clone());
+            editor.add_history_entry(line.as_str());
+            if let Err(err) = append_history(&history_path, &line) {
+                log::debug!("Writing the history failed: {:?}", err);
+            }
+            match session.eval(&line) {
+                Ok(Flow::Continue) => (),
+                Ok(Flow::Exit) => break,
+                Err(err) => eprintln!("{} {:#}", "error:".bright_red().bold(), err),
+            }
+        }
+        Ok(None)
+    }
+}
+
+impl Session<'_> {
+    /// Evaluates a line typed into the session.
+    fn eval(&mut self, line: &str) -> Result<Flow> {
+        let words = split_words(line)?;
+        let (first, rest) = match words.split_first() {
+            Some((first, rest)) => (first.as_str(), rest),
+            None => return Ok(Flow::Continue),
+        };
+        match first {
+            "exit" | "quit" => return Ok(Flow::Exit),
+            "help" => println!("{}", HELP),
+            "messages" => {
+                for spec in &self.abi.messages {
+                    println!("{}", self.signature(spec));
+                }
+            }
+            "complete" => {
+                let prefix = rest.first().map(String::as_str).unwrap_or_default();
+                for completion in completions(&self.abi, prefix) {
+                    println!("{}", completion);
+                }
+            }
+            "vars" => {
+                for (name, value) in &self.vars {
+                    println!("${} = {}", name, value);
+                }
+            }
+            "history" => {
+                for (i, line) in self.history.iter().enumerate() {
+                    println!("{:>4}  {}", i + 1, line);
+                }
+            }
+            "let" => self.assign(rest)?,
+            "submit" => self.submit(rest)?,
+            _ => self.query(&words)?,
+        }
+        Ok(Flow::Continue)
+    }
+
+    /// Returns the message with its arguments and return type, e.g.
+    /// `withdraw(value: u128) -> Result<(), Error>  [mutates]`.
+    fn signature(&self, spec: &MessageSpec) -> String {
+        let types = &self.abi.types;
+        let args = spec
+            .args
+            .iter()
+            .map(|arg| format!("{}: {}", arg.label, types.type_name(arg.ty.id)))
+            .collect::<Vec<_>>();
+        let returns = spec
+            .return_type
+            .as_ref()
+            .map(|ty| format!(" -> {}", types.type_name(ty.id)))
+            .unwrap_or_default();
+        let mutates = if spec.mutates { "  [mutates]" } else { "" };
+        format!("{}({}){}{}", spec.label, args.join(", "), returns, mutates)
+    }
+
+    /// Encodes the call of the message `words` starts with, with the arguments following it.
+    fn encode(&self, words: &[String]) -> Result<(&MessageSpec, Vec<u8>)> {
+        let (label, args) = words.split_first().context("Missing the message")?;
+        let spec = resolve_message(&self.abi, label)?;
+        let args = args
+            .iter()
+            .map(|arg| Ok(parse_arg_value(&substitute(arg, &self.vars)?)))
+            .collect::<Result<Vec<_>>>()?;
+        Ok((spec, self.abi.encode_call(spec, &args)?))
+    }
+
+    /// Dry runs the message of `words` and prints the outcome.
+    fn query(&self, words: &[String]) -> Result<()> {
+        let (spec, data) = self.encode(words)?;
+        let opts = &self.cmd.extrinsic_opts;
+        let result = dry_run::call(opts, &self.cmd.contract, 0, None, None, &data, None)?;
+        let return_type = spec.return_type.as_ref().map(|ty| (&self.abi.types, ty.id));
+        print!(
+            "{}",
+            dry_run::render_call(&result, return_type, &Costs::default())
+        );
+        if spec.mutates {
+            println!(
+                "{} `{}` mutates the contract, `submit` it to change its storage",
+                "Dry run:".cyan().bold(),
+                spec.label
+            );
+        }
+        Ok(())
+    }
+
+    /// Stores the value of `let <name> = ..`.
+    fn assign(&mut self, words: &[String]) -> Result<()> {
+        let (name, expr) = match words {
+            [name, eq, expr @ ..] if eq == "=" && !expr.is_empty() => (name, expr),
+            _ => anyhow::bail!(
+                "Expected `let <name> = <message> [args..]` or `let <name> = <value>`"
+            ),
+        };
+        if !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            anyhow::bail!("`{}` is not a valid name", name)
+        }
+        let value = match resolve_message(&self.abi, &expr[0]) {
+            Ok(_) => self.returned_value(expr)?,
+            Err(_) if expr.len() == 1 => parse_arg_value(&substitute(&expr[0], &self.vars)?),
+            Err(err) => return Err(err),
+        };
+        println!("${} = {}", name, value);
+        self.vars.insert(name.clone(), value);
+        Ok(())
+    }
+
+    /// Dry runs the message of `words` and returns the value it returns as JSON.
+    fn returned_value(&self, words: &[String]) -> Result<Value> {
+        let (spec, data) = self.encode(words)?;
+        let opts = &self.cmd.extrinsic_opts;
+        let result = dry_run::call(opts, &self.cmd.contract, 0, None, None, &data, None)?;
+        let value = match &result.result {
+            Ok(value) if !value.reverted() => value,
+            Ok(_) => anyhow::bail!("`{}` reverted", spec.label),
+            Err(err) => anyhow::bail!("`{}` failed: {}", spec.label, result.display_error(err)),
+        };
+        let ty = match &spec.return_type {
+            Some(ty) => ty,
+            None => return Ok(Value::Null),
+        };
+        match self.abi.types.decode_return_value(ty.id, &value.data)? {
+            ReturnValue::Value(decoded) => Ok(decoded.to_json()),
+            ReturnValue::LangError(err) => {
+                anyhow::bail!("The contract could not dispatch the message: {}", err)
+            }
+        }
+    }
+
+    /// Submits a call of the message of `words`, with the gas and the storage deposit a dry
+    /// run requires.
+    fn submit(&self, words: &[String]) -> Result<()> {
+        let (words, value) = match words {
+            [words @ .., flag, value] if flag == "--value" => {
+                let value = substitute(value, &self.vars)?.parse::<Balance>()?;
+                (words, value.plancks(&self.cmd.extrinsic_opts.node.url()?)?)
+            }
+            words => (words, 0),
+        };
+        let (spec, data) = self.encode(words)?;
+        let opts = &self.cmd.extrinsic_opts;
+        let contract = &self.cmd.contract;
+        let result = dry_run::call(opts, contract, value, None, None, &data, None)?;
+        match &result.result {
+            Ok(returned) if !returned.reverted() => (),
+            _ => {
+                let return_type = spec.return_type.as_ref().map(|ty| (&self.abi.types, ty.id));
+                print!(
+                    "{}",
+                    dry_run::render_call(&result, return_type, &Costs::default())
+                );
+                anyhow::bail!("The dry run of `{}` failed, not submitting it", spec.label)
+            }
+        }
+        let limits = result.required_limits();
+        Summary::new("Call the contract")
+            .line("Contract", contract.to_string())
+            .input(
+                "Message",
+                Some((&self.abi.types, &self.abi.messages[..])),
+                &data,
+            )
+            .balance("Value", value)
+            .line("Gas limit", limits.gas)
+            .storage_deposit(result.storage_deposit)
+            .confirm(opts, |cli| {
+                encode_call(
+                    cli,
+                    contract,
+                    value,
+                    limits.gas,
+                    limits.storage_deposit,
+                    &data,
+                )
+            })?;
+        let submitted = submit_call(
+            opts,
+            contract,
+            value,
+            limits.gas,
+            limits.storage_deposit,
+            &data,
+            Some(&self.abi),
+        )?;
+        match submitted {
+            Submitted::Executed(output) => println!(
+                "Called the contract in block {:?}{}",
+                output.block_hash,
+                output.display_contract_events()
+            ),
+            Submitted::Pending(pending) => println!("{}", pending),
+        }
+        Ok(())
+    }
+}
+
+/// Returns the message labeled `label`, or the only message whose label, or whose label
+/// without its trait, starts with `label`.
+fn resolve_message<'a>(abi: &'a ContractAbi, label: &str) -> Result<&'a MessageSpec> {
+    if let Ok(spec) = abi.message(label) {
+        return Ok(spec);
+    }
+    let matches = abi
+        .messages
+        .iter()
+        .filter(|spec| {
+            spec.label.starts_with(label)
+                || spec
+                    .label
+                    .rsplit("::")
+                    .next()
+                    .is_some_and(|name| name.starts_with(label))
+        })
+        .collect::<Vec<_>>();
+    match matches[..] {
+        [spec] => Ok(spec),
+        [] => abi.message(label),
+        _ => {
+            let labels = matches
+                .iter()
+                .map(|spec| spec.label.as_str())
+                .collect::<Vec<_>>();
+            anyhow::bail!("`{}` may be {}", label, labels.join(", "))
+        }
+    }
+}
+
+/// Returns the commands and the labels of the messages starting with `prefix`.
+fn completions(abi: &ContractAbi, prefix: &str) -> Vec<String> {
+    const COMMANDS: &[&str] = &[
+        "complete", "exit", "help", "history", "let", "messages", "submit", "vars",
+    ];
+    abi.messages
+        .iter()
+        .map(|spec| spec.label.as_str())
+        .chain(COMMANDS.iter().copied())
+        .filter(|word| word.starts_with(prefix))
+        .map(str::to_string)
+        .collect()
+}
+
+/// Splits a line into words at whitespace, keeping quoted strings and JSON objects and arrays
+/// together. The quotes of quoted strings are removed.
+fn split_words(line: &str) -> Result<Vec<String>> {
+    let mut words = Vec::new();
+    let mut word = String::new();
+    let mut quote = None;
+    let mut depth = 0usize;
+    let mut in_word = false;
+    for c in line.chars() {
+        match (quote, c) {
+            (Some(q), c) if c == q => {
+                quote = None;
+                if depth > 0 {
+                    word.push(c);
+                }
+            }
+            (Some(_), c) => word.push(c),
+            (None, '"') | (None, '\'') => {
+                quote = Some(c);
+                in_word = true;
+                if depth > 0 {
+                    word.push(c);
+                }
+            }
+            (None, '{') | (None, '[') => {
+                depth += 1;
+                in_word = true;
+                word.push(c);
+            }
+            (None, '}') | (None, ']') => {
+                depth = depth.saturating_sub(1);
+                word.push(c);
+            }
+            (None, c) if c.is_whitespace() && depth == 0 => {
+                if in_word {
+                    words.push(std::mem::take(&mut word));
+                    in_word = false;
+                }
+            }
+            (None, c) => {
+                in_word = true;
+                word.push(c);
+            }
+        }
+    }
+    if quote.is_some() {
+        anyhow::bail!("Unterminated quote in `{}`", line)
+    }
+    if in_word {
+        words.push(word);
+    }
+    Ok(words)
+}
+
+/// Replaces the words `$name` by the stored value `name`, strings without their quotes.
+fn substitute(word: &str, vars: &BTreeMap<String, Value>) -> Result<String> {
+    let name = match word.strip_prefix('$') {
+        Some(name) => name,
+        None => return Ok(word.to_string()),
+    };
+    match vars.get(name) {
+        Some(Value::String(value)) => Ok(value.clone()),
+        Some(value) => Ok(value.to_string()),
+        None => anyhow::bail!("No value `${}`, store it with `let {} = ..`", name, name),
+    }
+}
+
+/// Replaces `!!` by the last line of the history and `!<n>` by its line `n`.
+fn expand_history(line: &str, history: &[String]) -> Result<String> {
+    let index = match line {
+        "!!" => history.len().checked_sub(1),
+        line => match line.strip_prefix('!').map(str::parse::<usize>) {
+            Some(Ok(n)) => n.checked_sub(1).filter(|i| *i < history.len()),
+            _ => return Ok(line.to_string()),
+        },
+    };
+    index
+        .map(|i| {
+            println!("{}", history[i]);
+            history[i].clone()
+        })
+        .with_context(|| format!("`{}` is not in the history", line))
+}
+
+/// Returns the lines of the history of earlier sessions, none if there is no history yet.
+fn read_history(path: &Path) -> Result<Vec<String>> {
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let history = fs::read_to_string(path)?;
+    Ok(history.lines().map(str::to_string).collect())
+}
+
+fn append_history(path: &Path, line: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    writeln!(file, "{}", line)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn splits_words_keeping_quotes_and_json_together() {
+        assert_eq!(
+            split_words(r#"let x = transfer {"Some": "a b"} 'c d'  [1, 2]"#).unwrap(),
+            vec![
+                "let",
+                "x",
+                "=",
+                "transfer",
+                r#"{"Some": "a b"}"#,
+                "c d",
+                "[1, 2]"
+            ]
+        );
+        assert!(split_words("withdraw \"5").is_err());
+
+        let mut vars = BTreeMap::new();
+        vars.insert("alice".to_string(), Value::String("5Grw".to_string()));
+        vars.insert("limit".to_string(), serde_json::json!(100));
+        assert_eq!(substitute("$alice", &vars).unwrap(), "5Grw");
+        assert_eq!(substitute("$limit", &vars).unwrap(), "100");
+        assert_eq!(substitute("100", &vars).unwrap(), "100");
+        assert!(substitute("$bob", &vars).is_err());
+    }
+
+    #[test]
+    fn resolves_messages_by_their_prefix() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        assert_eq!(resolve_message(&abi, "with").unwrap().label, "withdraw");
+        assert_eq!(
+            resolve_message(&abi, "bal").unwrap().label,
+            "Ledger::balance_of"
+        );
+        assert!(resolve_message(&abi, "transfer").is_err());
+        assert_eq!(completions(&abi, "d"), vec!["deposit"]);
+        assert_eq!(completions(&abi, "h"), vec!["help", "history"]);
+
+        let history = vec!["deposit".to_string(), "withdraw 5".to_string()];
+        assert_eq!(expand_history("!!", &history).unwrap(), "withdraw 5");
+        assert_eq!(expand_history("!1", &history).unwrap(), "deposit");
+        assert!(expand_history("!3", &history).is_err());
+        assert_eq!(expand_history("vars", &history).unwrap(), "vars");
+    }
+
+    #[test]
+    fn completes_the_word_at_the_cursor() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let helper = ReplHelper {
+            words: completions(&abi, ""),
+        };
+        let history = rustyline::history::History::new();
+        let ctx = rustyline::Context::new(&history);
+        assert_eq!(
+            helper.complete("submit with", 11, &ctx).unwrap(),
+            (7, vec!["withdraw".to_string()])
+        );
+        assert_eq!(
+            helper.complete("he", 2, &ctx).unwrap(),
+            (0, vec!["help".to_string()])
+        );
+    }
+
+    #[test]
+    fn reads_the_history_of_earlier_sessions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join(HISTORY);
+        assert!(read_history(&path).unwrap().is_empty());
+        append_history(&path, "deposit").unwrap();
+        append_history(&path, "withdraw 5").unwrap();
+        assert_eq!(read_history(&path).unwrap(), vec!["deposit", "withdraw 5"]);
+    }
+}
@@ -0,0 +1,130 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Sends the RPC requests of the contracts pallet which `subxt` does not wrap.
+
+use anyhow::{Context, Result};
+use jsonrpsee::common::Params;
+use serde::{de::DeserializeOwned, Deserialize};
+use serde_json::Value;
+use sp_core::{hashing::twox_128, H256};
+
+use super::dry_run::hex;
+use crate::abi::decode_hex;
+
+/// Sends the request `method` with the positional `params` to the node at `url`.
+pub(crate) async fn request<T: DeserializeOwned>(
+    url: &url::Url,
+    method: &str,
+    params: Vec<Value>,
+) -> Result<T> {
+    let client = jsonrpsee::ws_client(url.as_str()).await?;
+    Ok(client.request(method, Params::Array(params)).await?)
+}
+
+/// Returns the number of the block `hash`, or of the best block.
+pub(crate) async fn block_number(url: &url::Url, hash: Option<&H256>) -> Result<u64> {
+    let params = hash.map(|hash| vec![hex(&hash.0).into()]);
+    let header: Header = request(url, "chain_getHeader", params.unwrap_or_default()).await?;
+    u64::from_str_radix(header.number.trim_start_matches("0x"), 16)
+        .with_context(|| format!("Invalid block number {}", header.number))
+}
+
+/// Returns the extrinsics of the block `block`, hex encoded.
+pub(crate) async fn extrinsics(url: &url::Url, block: &H256) -> Result<Vec<String>> {
+    let block: SignedBlock = request(url, "chain_getBlock", vec![hex(&block.0).into()]).await?;
+    Ok(block.block.extrinsics)
+}
+
+/// Returns the SCALE encoded `System::Events` of the block `block`.
+pub(crate) async fn events(url: &url::Url, block: &H256) -> Result<Vec<u8>> {
+    let mut key = twox_128(b"System").to_vec();
+    key.extend(&twox_128(b"Events"));
+    let events: Option<String> = request(
+        url,
+        "state_getStorage",
+        vec![hex(&key).into(), hex(&block.0).into()],
+    )
+    .await?;
+    // no events are stored for blocks without any
+    events.map_or(Ok(vec![0]), |events| decode_hex(&events))
+}
+
+/// Returns the value stored under `key` in the child trie `trie_id` of a contract.
+pub(crate) async fn child_storage(
+    url: &url::Url,
+    trie_id: &[u8],
+    key: &[u8],
+) -> Result<Option<Vec<u8>>> {
+    let value: Option<String> = request(
+        url,
+        "childstate_getStorage",
+        vec![hex(&child_storage_key(trie_id)).into(), hex(key).into()],
+    )
+    .await?;
+    value.map(|value| decode_hex(&value)).transpose()
+}
+
+/// Returns all keys and values of the child trie `trie_id` of a contract at the block `at`.
+pub(crate) async fn child_storage_entries(
+    url: &url::Url,
+    trie_id: &[u8],
+    at: &H256,
+) -> Result<Vec<(Vec<u8>, Vec<u8>)>> {
+    // a single connection for all requests, there is a request per entry
+    let client = jsonrpsee::ws_client(url.as_str()).await?;
+    let child_key = hex(&child_storage_key(trie_id));
+    let params = |key: &str| {
+        Params::Array(vec![
+            child_key.clone().into(),
+            key.into(),
+            hex(&at.0).into(),
+        ])
+    };
+    let keys: Vec<String> = client.request("childstate_getKeys", params("0x")).await?;
+    let mut entries = Vec::new();
+    for key in keys {
+        let value: Option<String> = client
+            .request("childstate_getStorage", params(&key))
+            .await?;
+        if let Some(value) = value {
+            entries.push((decode_hex(&key)?, decode_hex(&value)?));
+        }
+    }
+    Ok(entries)
+}
+
+/// Returns the key of the child trie `trie_id` in the storage of the node.
+fn child_storage_key(trie_id: &[u8]) -> Vec<u8> {
+    let mut key = b":child_storage:default:".to_vec();
+    key.extend(trie_id);
+    key
+}
+
+#[derive(Deserialize)]
+struct Header {
+    number: String,
+}
+
+#[derive(Deserialize)]
+struct SignedBlock {
+    block: Block,
+}
+
+#[derive(Deserialize)]
+struct Block {
+    extrinsics: Vec<String>,
+}
@@ -0,0 +1,408 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Executes contracts in-process with `cargo contract sandbox`, without a node.
+//!
+//! The state of the sandbox is kept in `target/ink/sandbox.state` between the commands, so
+//! that a contract instantiated by one command can be called by the next.
+
+use std::{
+    convert::{TryFrom, TryInto},
+    fs,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use contract_sandbox::{
+    AccountId, Balance, ContractResult, Event, ExecReturnValue, Sandbox, ALICE,
+};
+use serde_json::{json, Value};
+use structopt::StructOpt;
+
+use super::{
+    encode::encode_args,
+    metadata::{built_metadata_path, read_json},
+};
+use crate::{
+    abi::{decode_hex, decode_ss58, encode_hex, encode_ss58, ContractAbi, ReturnValue, TypeRef},
+    crate_metadata::CrateMetadata,
+    workspace::ManifestPath,
+};
+
+/// The balance of `//Alice` in a new sandbox.
+pub(super) const ENDOWMENT: Balance = 1_000_000_000_000_000_000_000;
+
+/// The file of the state of the sandbox, in the `target/ink` directory of the contract.
+const STATE_FILE: &str = "sandbox.state";
+
+/// Executes contracts in an in-process sandbox, in milliseconds and without a node.
+#[derive(Debug, StructOpt)]
+pub enum SandboxCommand {
+    /// Upload and instantiate the contract in the sandbox
+    #[structopt(name = "instantiate")]
+    Instantiate(InstantiateCommand),
+    /// Call a message of a contract instantiated in the sandbox
+    #[structopt(name = "call")]
+    Call(CallCommand),
+}
+
+impl SandboxCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match self {
+            SandboxCommand::Instantiate(instantiate) => instantiate.exec(),
+            SandboxCommand::Call(call) => call.exec(),
+        }
+    }
+}
+
+/// The options shared by the commands executing contracts in the sandbox.
+#[derive(Debug, StructOpt)]
+pub struct SandboxOpts {
+    /// Path to the Cargo.toml of the contract
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The `.contract` bundle or metadata file to read instead of the built contract
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// The file keeping the state of the sandbox, `target/ink/sandbox.state` of the contract
+    /// by default
+    #[structopt(long, parse(from_os_str))]
+    state: Option<PathBuf>,
+    /// The account executing the contract, as SS58 address or hex. Defaults to `//Alice`,
+    /// which a new sandbox endows
+    #[structopt(long, parse(try_from_str = parse_account))]
+    origin: Option<AccountId>,
+    /// The value to transfer to the contract, in the smallest unit of the token
+    #[structopt(long, default_value = "0")]
+    value: Balance,
+    /// The gas limit, in executed Wasm instructions
+    #[structopt(long, default_value = "1000000000")]
+    gas_limit: u64,
+    /// Print the outcome as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+impl SandboxOpts {
+    fn metadata_path(&self) -> Result<PathBuf> {
+        match &self.metadata {
+            Some(path) => Ok(path.clone()),
+            None => built_metadata_path(self.manifest_path.as_ref()),
+        }
+    }
+
+    fn state_path(&self) -> Result<PathBuf> {
+        if let Some(path) = &self.state {
+            return Ok(path.clone());
+        }
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())
+            .context("Pass --state to keep the state of the sandbox outside of a contract")?;
+        Ok(CrateMetadata::collect(&manifest_path)?
+            .target_directory
+            .join(STATE_FILE))
+    }
+
+    fn origin(&self) -> AccountId {
+        self.origin.unwrap_or(ALICE)
+    }
+}
+
+/// Uploads the contract to the sandbox and instantiates it with a constructor.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "instantiate")]
+pub struct InstantiateCommand {
+    /// The label of the constructor
+    #[structopt(long, default_value = "new")]
+    constructor: String,
+    /// The arguments of the constructor, as JSON or as plain strings
+    #[structopt(long)]
+    args: Vec<String>,
+    /// A JSON file with the arguments of the constructor, keyed by their names
+    #[structopt(long, parse(from_os_str), conflicts_with = "args")]
+    args_file: Option<PathBuf>,
+    /// The hex of the salt deriving the account of the contract
+    #[structopt(long, default_value = "0x")]
+    salt: String,
+    /// The Wasm code to instantiate, if `--metadata` is no `.contract` bundle. Defaults to
+    /// the code of the built contract
+    #[structopt(long, parse(from_os_str))]
+    wasm: Option<PathBuf>,
+    #[structopt(flatten)]
+    opts: SandboxOpts,
+}
+
+impl InstantiateCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = ContractAbi::load(&self.opts.metadata_path()?)?;
+        let spec = abi.constructor(&self.constructor)?;
+        let data = encode_args(&abi, spec, &self.args, self.args_file.as_ref())?;
+        let wasm = contract_wasm(
+            self.opts.manifest_path.as_ref(),
+            self.opts.metadata.as_deref(),
+            self.wasm.as_deref(),
+        )?;
+        let salt = decode_hex(&self.salt).context("Invalid --salt")?;
+
+        let state_path = self.opts.state_path()?;
+        let mut sandbox = load(&state_path)?;
+        let result = sandbox.instantiate_with_code(
+            self.opts.origin(),
+            &wasm,
+            self.opts.value,
+            data,
+            salt,
+            self.opts.gas_limit,
+        );
+        let outcome = result.result.as_ref().map(|value| {
+            let returned = render_return_value(&abi, None, &value.result);
+            (Some(value.account_id), returned, value.result.did_revert())
+        });
+        report(&abi, &result, outcome, self.opts.output_json)?;
+        save(&sandbox, &state_path)?;
+        Ok(None)
+    }
+}
+
+/// Calls a message of a contract instantiated in the sandbox.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "call")]
+pub struct CallCommand {
+    /// The account of the contract, as SS58 address or hex
+    #[structopt(long, parse(try_from_str = parse_account))]
+    contract: AccountId,
+    /// The label of the message
+    #[structopt(long)]
+    message: String,
+    /// The arguments of the message, as JSON or as plain strings
+    #[structopt(long)]
+    args: Vec<String>,
+    /// A JSON file with the arguments of the message, keyed by their names
+    #[structopt(long, parse(from_os_str), conflicts_with = "args")]
+    args_file: Option<PathBuf>,
+    /// Keep the state of the sandbox as it was before the call
+    #[structopt(long)]
+    dry_run: bool,
+    #[structopt(flatten)]
+    opts: SandboxOpts,
+}
+
+impl CallCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = ContractAbi::load(&self.opts.metadata_path()?)?;
+        let spec = abi.message(&self.message)?;
+        let data = encode_args(&abi, spec, &self.args, self.args_file.as_ref())?;
+
+        let state_path = self.opts.state_path()?;
+        let mut sandbox = load(&state_path)?;
+        let result = sandbox.call(
+            self.opts.origin(),
+            self.contract,
+            self.opts.value,
+            data,
+            self.opts.gas_limit,
+        );
+        let outcome = result.result.as_ref().map(|value| {
+            let returned = render_return_value(&abi, spec.return_type.as_ref(), value);
+            (None, returned, value.did_revert())
+        });
+        report(&abi, &result, outcome, self.opts.output_json)?;
+        if !self.dry_run {
+            save(&sandbox, &state_path)?;
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the code of the contract: of `wasm`, of the `.contract` bundle at `metadata`, or of
+/// the built contract.
+pub(super) fn contract_wasm(
+    manifest_path: Option<&PathBuf>,
+    metadata: Option<&Path>,
+    wasm: Option<&Path>,
+) -> Result<Vec<u8>> {
+    if let Some(path) = wasm {
+        return fs::read(path).with_context(|| format!("Failed to read {}", path.display()));
+    }
+    if let Some(path) = metadata {
+        return match read_json(path)?["source"]["wasm"].as_str() {
+            Some(wasm) => decode_hex(wasm).context("Failed to decode the Wasm code"),
+            None => anyhow::bail!(
+                "{} contains no Wasm code, pass the code with --wasm",
+                path.display()
+            ),
+        };
+    }
+    let manifest_path = ManifestPath::try_from(manifest_path)?;
+    let path = CrateMetadata::collect(&manifest_path)?.dest_wasm;
+    fs::read(&path).with_context(|| {
+        format!(
+            "Failed to read {}, build the contract with `cargo contract build` first",
+            path.display()
+        )
+    })
+}
+
+/// Parses an account given as SS58 address or as hex.
+pub(super) fn parse_account(input: &str) -> Result<AccountId> {
+    if let Some(account) = decode_ss58(input) {
+        return Ok(account);
+    }
+    decode_hex(input)
+        .ok()
+        .and_then(|bytes| bytes.try_into().ok())
+        .ok_or_else(|| {
+            anyhow::anyhow!("`{}` is neither an SS58 address nor 32 bytes of hex", input)
+        })
+}
+
+/// Returns the sandbox of the state at `path`, or a new one endowing `//Alice`.
+fn load(path: &Path) -> Result<Sandbox> {
+    if !path.exists() {
+        let mut sandbox = Sandbox::new();
+        sandbox.set_balance(ALICE, ENDOWMENT);
+        return Ok(sandbox);
+    }
+    let state = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+    Sandbox::decode_state(&state).map_err(|err| {
+        anyhow::anyhow!(
+            "Failed to decode the sandbox state {}, remove it to start afresh: {}",
+            path.display(),
+            err
+        )
+    })
+}
+
+fn save(sandbox: &Sandbox, path: &Path) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+    fs::write(path, sandbox.encode_state())
+        .with_context(|| format!("Failed to write {}", path.display()))
+}
+
+/// Returns the value returned by a message with `return_type` as text and as JSON.
+pub(super) fn render_return_value(
+    abi: &ContractAbi,
+    return_type: Option<&TypeRef>,
+    value: &ExecReturnValue,
+) -> (String, Value) {
+    let hex = encode_hex(&value.data);
+    let return_type = match return_type {
+        Some(return_type) => return_type,
+        None if value.data.is_empty() => return ("()".to_string(), Value::Null),
+        None => return (hex.clone(), Value::String(hex)),
+    };
+    match abi.types.decode_return_value(return_type.id, &value.data) {
+        Ok(ReturnValue::Value(decoded)) => (decoded.to_pretty_string(), decoded.to_json()),
+        Ok(ReturnValue::LangError(err)) => (
+            format!("the contract could not dispatch the message: {}", err),
+            json!({ "LangError": err.to_json() }),
+        ),
+        Err(_) => (hex.clone(), Value::String(hex)),
+    }
+}
+
+/// Returns an event decoded with the metadata as text and as JSON, or its data as hex if it
+/// was emitted by another contract.
+fn render_event(abi: &ContractAbi, event: &Event) -> (String, Value) {
+    match abi.decode_event(&event.data) {
+        Ok(decoded) => (decoded.to_string(), Value::String(decoded.to_string())),
+        Err(_) => {
+            let hex = encode_hex(&event.data);
+            (
+                format!("{} from {}", hex, encode_ss58(&event.emitter)),
+                json!({ "emitter": encode_ss58(&event.emitter), "data": hex }),
+            )
+        }
+    }
+}
+
+/// The account of an instantiated contract, the returned value and whether the contract
+/// reverted.
+type Outcome<'a> =
+    std::result::Result<(Option<AccountId>, (String, Value), bool), &'a contract_sandbox::Error>;
+
+/// Prints the outcome of an execution, failing if the contract trapped or reverted.
+fn report<R>(
+    abi: &ContractAbi,
+    result: &ContractResult<R>,
+    outcome: Outcome,
+    output_json: bool,
+) -> Result<()> {
+    let events = result
+        .events
+        .iter()
+        .map(|event| render_event(abi, event))
+        .collect::<Vec<_>>();
+    if output_json {
+        let mut output = json!({
+            "gas_consumed": result.gas_consumed,
+            "events": events.iter().map(|(_, json)| json.clone()).collect::<Vec<_>>(),
+            "debug_messages": result.debug_messages,
+        });
+        match &outcome {
+            Ok((account, (_, returned), reverted)) => {
+                if let Some(account) = account {
+                    output["contract"] = Value::String(encode_ss58(account));
+                }
+                output["returned"] = returned.clone();
+                output["reverted"] = Value::Bool(*reverted);
+            }
+            Err(err) => output["error"] = Value::String(err.to_string()),
+        }
+        println!("{}", serde_json::to_string_pretty(&output)?);
+    } else {
+        for message in &result.debug_messages {
+            println!("{} {}", "Debug:".bold(), message.trim_end());
+        }
+        for (event, _) in &events {
+            println!("{} {}", "Event:".bold(), event);
+        }
+        if let Ok((account, (returned, _), reverted)) = &outcome {
+            if let Some(account) = account.filter(|_| !reverted) {
+                println!("{} {}", "Contract:".bold(), encode_ss58(&account));
+            }
+            if *reverted {
+                println!("{} {}", "Reverted:".bright_red().bold(), returned);
+            } else {
+                println!("{} {}", "Returned:".bold(), returned);
+            }
+        }
+        println!("{} {}", "Gas consumed:".bold(), result.gas_consumed);
+    }
+    match outcome {
+        Ok((_, _, true)) => anyhow::bail!("The contract reverted"),
+        Ok(_) => Ok(()),
+        Err(err) => Err(anyhow::anyhow!("{}", err)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_accounts_as_ss58_or_hex() {
+        let alice = "0xd43593c715fdd31c61141abd04a99fd6822c8558854ccde39a5684e7a56da27d";
+        assert_eq!(parse_account(alice).unwrap(), ALICE);
+        assert_eq!(
+            parse_account("5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY").unwrap(),
+            ALICE
+        );
+        assert!(parse_account("0x00").is_err());
+    }
+}
@@ -0,0 +1,91 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Holds back the extrinsics of `--at-block` and `--after` until the block arrives or the
+//! duration passed, e.g. for coordinated launches and timelocked admin actions.
+
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+
+use super::rpc;
+use crate::ExtrinsicOpts;
+
+/// The interval of polling the best block of `--at-block`.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Parses the duration of `--after`, e.g. `90s`, `15m` or `1h 30m`.
+pub(crate) fn parse_duration(input: &str) -> Result<Duration> {
+    humantime::parse_duration(input).with_context(|| {
+        format!(
+            "Invalid duration `{}`, expected e.g. `90s` or `1h 30m`",
+            input
+        )
+    })
+}
+
+/// Waits for the block of `--at-block` and the duration of `--after` before the first extrinsic
+/// of the command is submitted.
+///
+/// The duration starts once the command is about to submit, after its confirmation.
+pub(crate) async fn wait(extrinsic_opts: &ExtrinsicOpts) -> Result<()> {
+    if extrinsic_opts.scheduled.replace(true) {
+        return Ok(());
+    }
+    if let Some(after) = extrinsic_opts.after {
+        eprintln!(
+            "{} Submitting in {}",
+            "Scheduled:".cyan().bold(),
+            humantime::format_duration(after)
+        );
+        async_std::task::sleep(after).await;
+    }
+    if let Some(at_block) = extrinsic_opts.at_block {
+        let started = Instant::now();
+        let mut best = rpc::block_number(&extrinsic_opts.node.url()?, None).await?;
+        if best < at_block {
+            eprintln!(
+                "{} Submitting at block {}, the best block is {}",
+                "Scheduled:".cyan().bold(),
+                at_block,
+                best
+            );
+        }
+        while best < at_block {
+            async_std::task::sleep(POLL_INTERVAL).await;
+            best = rpc::block_number(&extrinsic_opts.node.url()?, None).await?;
+        }
+        log::debug!(
+            "Block {} arrived after {} seconds",
+            best,
+            started.elapsed().as_secs()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_durations() {
+        assert_eq!(parse_duration("90s").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("1h 30m").unwrap(), Duration::from_secs(5400));
+        assert!(parse_duration("soon").is_err());
+    }
+}
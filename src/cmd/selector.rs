@@ -0,0 +1,104 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Computes the selectors of ink! messages and constructors with `cargo contract selector`.
+
+use super::metadata::blake2_hash;
+use crate::abi::{encode_hex, Selector};
+use anyhow::{Context, Result};
+use std::{fs, path::PathBuf};
+use structopt::StructOpt;
+
+/// Computes the selector of a message or constructor from its name.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "selector")]
+pub struct SelectorCommand {
+    /// The name to compute the selector of, e.g. `transfer`, or `PSP22::transfer` for the
+    /// messages of traits
+    #[structopt(required_unless = "file")]
+    name: Option<String>,
+    /// A file with a name per line to compute the selectors of instead; empty lines and lines
+    /// starting with `#` are skipped
+    #[structopt(long, parse(from_os_str), conflicts_with = "name")]
+    file: Option<PathBuf>,
+    /// Export the selectors and their preimages in JSON format
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// A selector with the preimage it is the hash of.
+#[derive(Debug, PartialEq, Eq, serde::Serialize)]
+pub struct SelectorInfo {
+    pub name: String,
+    /// The `0x` prefixed hex of the hashed bytes, the UTF-8 bytes of the name.
+    pub preimage: String,
+    pub selector: String,
+}
+
+impl SelectorCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let names = match (&self.name, &self.file) {
+            (_, Some(path)) => fs::read_to_string(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?
+                .lines()
+                .map(str::trim)
+                .filter(|line| !line.is_empty() && !line.starts_with('#'))
+                .map(str::to_string)
+                .collect(),
+            (Some(name), None) => vec![name.clone()],
+            (None, None) => anyhow::bail!("Give a name or --file"),
+        };
+        let selectors = names
+            .iter()
+            .map(|name| SelectorInfo {
+                name: name.clone(),
+                preimage: encode_hex(name.as_bytes()),
+                selector: selector(name).to_string(),
+            })
+            .collect::<Vec<_>>();
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&selectors)?);
+        } else {
+            for info in &selectors {
+                println!(
+                    "{}  {}  (preimage {})",
+                    info.selector, info.name, info.preimage
+                );
+            }
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the selector ink! derives from `name`: the first 4 bytes of its blake2 hash.
+///
+/// The name of a trait message is prefixed with the trait, e.g. `PSP22::transfer`.
+pub fn selector(name: &str) -> Selector {
+    let hash = blake2_hash(name.as_bytes()).0;
+    Selector([hash[0], hash[1], hash[2], hash[3]])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_selectors_like_ink() {
+        assert_eq!(selector("flip").to_string(), "0x633aa551");
+        assert_eq!(selector("new").to_string(), "0x9bae9d5e");
+        assert_eq!(selector("PSP22::transfer").to_string(), "0xdb20f9f5");
+    }
+}
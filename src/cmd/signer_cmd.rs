@@ -0,0 +1,220 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signs extrinsics with an external program given with `--signer-cmd`, e.g. a bridge to a KMS,
+//! an HSM or a wallet, so that they need not be linked into `cargo-contract`.
+//!
+//! The program is run once per request. It reads a single line of JSON from stdin and writes a
+//! single JSON object to stdout:
+//!
+//! - `{"version":1,"method":"account"}` is answered with the SS58 address or the `0x` prefixed
+//!   public key of the account signing the extrinsics, e.g. `{"account":"5Grw..."}`, or with
+//!   the Ethereum address of the account on chains with `AccountId20`.
+//! - `{"version":1,"method":"sign","account":"5Grw...","payload":"0x.."}` is answered with the
+//!   SCALE encoded `MultiSignature` of the payload, e.g. `{"signature":"0x01.."}` for an
+//!   sr25519 signature. The payload is hashed already if it is longer than 256 bytes. Ethereum
+//!   accounts answer with the `Ecdsa` variant holding the signature of its keccak hash.
+//!
+//! Failures are answered with `{"error":"<reason>"}` or a non-zero exit code.
+
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+};
+
+use anyhow::{Context, Result};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use subxt::sp_runtime::{codec::Decode, MultiSignature};
+
+use super::{
+    chain::{parse_account, AccountId},
+    dry_run::hex,
+    signing::{hashed, Signer},
+};
+use crate::abi::decode_hex;
+
+/// The version of the protocol, sent with every request.
+const VERSION: u32 = 1;
+
+/// Signs extrinsics with the account of an external program.
+pub(crate) struct CommandSigner {
+    command: String,
+    account_id: AccountId,
+}
+
+#[derive(Serialize)]
+#[serde(tag = "method", rename_all = "camelCase")]
+enum Method {
+    Account,
+    Sign { account: String, payload: String },
+}
+
+#[derive(Serialize)]
+struct Request {
+    version: u32,
+    #[serde(flatten)]
+    method: Method,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum Response<T> {
+    Error { error: String },
+    Ok(T),
+}
+
+#[derive(Deserialize)]
+struct AccountResponse {
+    account: String,
+}
+
+#[derive(Deserialize)]
+struct SignResponse {
+    signature: String,
+}
+
+impl CommandSigner {
+    /// Asks the program of `command` for the account to sign with. `command` is the path of the
+    /// program followed by its whitespace separated arguments.
+    pub fn connect(command: &str) -> Result<Self> {
+        let response: AccountResponse = request(command, Method::Account)?;
+        let account_id = parse_account(&response.account)
+            .with_context(|| format!("`{}` returned an invalid account", command))?;
+        Ok(CommandSigner {
+            command: command.to_string(),
+            account_id,
+        })
+    }
+}
+
+impl Signer for CommandSigner {
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<MultiSignature> {
+        let method = Method::Sign {
+            account: self.account_id.to_string(),
+            payload: hex(&hashed(payload)),
+        };
+        let response: SignResponse = request(&self.command, method)?;
+        let signature = decode_hex(&response.signature)?;
+        MultiSignature::decode(&mut &signature[..]).map_err(|err| {
+            anyhow::anyhow!(
+                "`{}` returned an invalid signature, expected a SCALE encoded \
+                 `MultiSignature`: {}",
+                self.command,
+                err
+            )
+        })
+    }
+}
+
+/// Runs `command` with the `method` request on stdin and returns its response.
+fn request<T: DeserializeOwned>(command: &str, method: Method) -> Result<T> {
+    let mut words = command.split_whitespace();
+    let program = words.next().context("The `--signer-cmd` is empty")?;
+    let mut child = Command::new(program)
+        .args(words)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+        .with_context(|| format!("Failed to run `{}`", command))?;
+    let mut request = serde_json::to_vec(&Request {
+        version: VERSION,
+        method,
+    })?;
+    request.push(b'\n');
+    // the stdin is closed once it is dropped, for programs reading to its end
+    child
+        .stdin
+        .take()
+        .context("The stdin of the signer is not piped")?
+        .write_all(&request)?;
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        anyhow::bail!("`{}` failed with {}", command, output.status)
+    }
+    let response = serde_json::from_slice(&output.stdout).with_context(|| {
+        format!(
+            "`{}` returned invalid JSON: {}",
+            command,
+            String::from_utf8_lossy(&output.stdout)
+        )
+    })?;
+    match response {
+        Response::Ok(response) => Ok(response),
+        Response::Error { error } => anyhow::bail!("`{}` failed: {}", command, error),
+    }
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use crate::util::tests::with_tmp_dir;
+    use std::{fs, os::unix::fs::PermissionsExt, path::Path};
+    use subxt::sp_runtime::{codec::Encode, traits::Verify};
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+
+    fn write_script(dir: &Path, name: &str, script: &str) -> Result<String> {
+        let path = dir.join(name);
+        fs::write(&path, script)?;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755))?;
+        Ok(path.display().to_string())
+    }
+
+    #[test]
+    fn signs_with_an_external_program() {
+        with_tmp_dir(|dir| {
+            // signs with the key of Alice, whose signatures are checked below
+            let pair =
+                <sp_core::sr25519::Pair as sp_core::Pair>::from_string("//Alice", None).unwrap();
+            let payload = b"payload".to_vec();
+            let signature: MultiSignature = sp_core::Pair::sign(&pair, &payload).into();
+            let script = format!(
+                "#!/bin/sh\n\
+                 read -r request\n\
+                 case \"$request\" in\n\
+                 *'\"method\":\"sign\"'*'\"payload\":\"{}\"'*) echo '{{\"signature\":\"{}\"}}' ;;\n\
+                 *'\"method\":\"sign\"'*) echo '{{\"error\":\"unexpected payload\"}}' ;;\n\
+                 *'\"version\":1'*) echo '{{\"account\":\"{}\"}}' ;;\n\
+                 esac\n",
+                hex(&payload),
+                hex(&signature.encode()),
+                ALICE
+            );
+            let command = write_script(dir, "signer.sh", &script)?;
+
+            let signer = CommandSigner::connect(&command)?;
+            assert_eq!(signer.account_id().to_string(), ALICE);
+            let signature = signer.sign(&payload)?;
+            let alice = signer.account_id().substrate("the test")?;
+            assert!(signature.verify(&payload[..], alice));
+            assert_eq!(
+                signer.sign(b"other").unwrap_err().to_string(),
+                format!("`{}` failed: unexpected payload", command)
+            );
+
+            let command = write_script(dir, "failing.sh", "#!/bin/sh\nread -r request\nexit 3\n")?;
+            assert_eq!(
+                CommandSigner::connect(&command).err().unwrap().to_string(),
+                format!("`{}` failed with exit status: 3", command)
+            );
+            Ok(())
+        })
+    }
+}
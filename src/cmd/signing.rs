@@ -0,0 +1,571 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Signing extrinsics with the signed extensions of the [`ExtrinsicOpts`]: the nonce, the tip
+//! of `--tip`, the asset of `--fee-asset` paying the fees and the validity window of `--era`,
+//! or no validity window with `--immortal`.
+//!
+//! Extrinsics of chains with 32 byte account ids are signed with a `MultiSignature`, the ones of
+//! chains with Ethereum-style `AccountId20` accounts with the ECDSA signature of the keccak hash
+//! of their payload, as Ethereum transactions are.
+
+use std::{fs, path::PathBuf, time::Duration};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use sp_core::{
+    crypto::Pair,
+    ecdsa,
+    hashing::{blake2_256, keccak_256},
+    H256,
+};
+use subxt::{
+    sp_runtime::{
+        codec::Encode,
+        generic::Era,
+        traits::{IdentifyAccount, Verify},
+        MultiSignature, MultiSigner,
+    },
+    Call, Client, DefaultNodeRuntime, Encoded, ExtrinsicSuccess,
+};
+
+use super::{
+    chain::{self, parse_account, AccountId, ExtensionData},
+    convert_address::ethereum_address,
+    dry_run::hex,
+    nonce, reconnect, rpc,
+    submit::{Pending, SubmitError, Submitted},
+};
+use crate::{abi::decode_hex, ExtrinsicOpts, WaitFor};
+
+/// The number of blocks extrinsics are valid for without `--era`.
+pub(crate) const DEFAULT_ERA: u64 = 64;
+
+/// Signs the payloads of extrinsics.
+pub(crate) trait Signer: Send + Sync {
+    /// The account signing the extrinsics.
+    fn account_id(&self) -> &AccountId;
+
+    /// Returns the signature of the signing `payload`, which is not hashed yet.
+    ///
+    /// Ethereum accounts sign with an ECDSA signature, which is returned as the `Ecdsa` variant
+    /// of the `MultiSignature`.
+    fn sign(&self, payload: &[u8]) -> Result<MultiSignature>;
+}
+
+/// Signs with an sr25519 or ECDSA keypair.
+pub(crate) struct KeypairSigner<P> {
+    pair: P,
+    account_id: AccountId,
+}
+
+impl<P: Pair> KeypairSigner<P>
+where
+    P::Public: Into<MultiSigner>,
+{
+    pub fn new(pair: P) -> Self {
+        let signer: MultiSigner = pair.public().into();
+        let account_id = signer.into_account().into();
+        KeypairSigner { pair, account_id }
+    }
+
+    /// Creates the keypair of the secret key URI `suri`, decrypted with `password`.
+    pub fn from_suri(suri: &str, password: Option<&str>) -> Result<Self> {
+        let pair =
+            P::from_string(suri, password).map_err(|_| anyhow::anyhow!("Secret string error"))?;
+        Ok(Self::new(pair))
+    }
+}
+
+impl<P: Pair> Signer for KeypairSigner<P>
+where
+    P::Signature: Into<MultiSignature>,
+{
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<MultiSignature> {
+        Ok(self.pair.sign(&hashed(payload)).into())
+    }
+}
+
+/// Signs with the secp256k1 key of an Ethereum account, for chains with `AccountId20`.
+pub(crate) struct EthereumSigner {
+    secret: secp256k1::SecretKey,
+    account_id: AccountId,
+}
+
+impl EthereumSigner {
+    /// Creates the signer of the secret key URI `suri`, e.g. the `0x` prefixed private key,
+    /// decrypted with `password`.
+    pub fn from_suri(suri: &str, password: Option<&str>) -> Result<Self> {
+        let pair = ecdsa::Pair::from_string(suri, password)
+            .map_err(|_| anyhow::anyhow!("Secret string error"))?;
+        let secret = secp256k1::SecretKey::parse(&pair.seed())
+            .map_err(|err| anyhow::anyhow!("Invalid secp256k1 secret key: {:?}", err))?;
+        let public = secp256k1::PublicKey::from_secret_key(&secret);
+        let address = ethereum_address(&public.serialize_compressed())?;
+        Ok(EthereumSigner {
+            secret,
+            account_id: AccountId::Ethereum(address),
+        })
+    }
+}
+
+impl Signer for EthereumSigner {
+    fn account_id(&self) -> &AccountId {
+        &self.account_id
+    }
+
+    fn sign(&self, payload: &[u8]) -> Result<MultiSignature> {
+        let message = secp256k1::Message::parse(&keccak_256(&hashed(payload)));
+        let (signature, recovery_id) = secp256k1::sign(&message, &self.secret);
+        let mut raw = [0; 65];
+        raw[..64].copy_from_slice(&signature.serialize());
+        raw[64] = recovery_id.serialize();
+        Ok(ecdsa::Signature::from_raw(raw).into())
+    }
+}
+
+/// Returns the Ethereum address which signed the keccak hash of `message` with the
+/// recoverable ECDSA `signature`, if it is valid.
+fn recover_ethereum_address(signature: &[u8; 65], message: &[u8]) -> Option<[u8; 20]> {
+    let message = secp256k1::Message::parse(&keccak_256(message));
+    let mut raw = [0; 64];
+    raw.copy_from_slice(&signature[..64]);
+    // Ethereum tools add 27 to the recovery id
+    let recovery_id = match signature[64] {
+        v @ 27..=28 => v - 27,
+        v => v,
+    };
+    let recovery_id = secp256k1::RecoveryId::parse(recovery_id).ok()?;
+    let public =
+        secp256k1::recover(&message, &secp256k1::Signature::parse(&raw), &recovery_id).ok()?;
+    ethereum_address(&public.serialize_compressed()).ok()
+}
+
+/// Returns the bytes of the signing `payload` which are signed: the payload, hashed if it is
+/// longer than 256 bytes.
+pub(super) fn hashed(payload: &[u8]) -> Vec<u8> {
+    if payload.len() > 256 {
+        blake2_256(payload).to_vec()
+    } else {
+        payload.to_vec()
+    }
+}
+
+/// An extrinsic with an encoded call, to be signed by `signer`.
+///
+/// Besides the call, the signature covers the nonce, the tip, the validity window, the runtime
+/// version and the genesis hash, which are fetched from the node when the extrinsic is created.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct UnsignedExtrinsic {
+    /// The SS58 address of the account which signs the extrinsic, or its Ethereum address on
+    /// chains with `AccountId20`.
+    pub signer: String,
+    /// The encoded call, starting with the indices of the pallet and the call.
+    pub call: String,
+    pub nonce: u32,
+    /// The tip for the block author, in the smallest unit of the chain.
+    #[serde(default)]
+    pub tip: u128,
+    /// The id of the asset the fees are paid in, the native token if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub fee_asset: Option<u32>,
+    /// The blocks the extrinsic is valid in, valid forever if not given.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub era: Option<Mortality>,
+    pub spec_version: u32,
+    pub transaction_version: u32,
+    pub genesis_hash: H256,
+    /// The identifiers of the signed extensions of the runtime, in its order.
+    #[serde(default = "default_signed_extensions")]
+    pub signed_extensions: Vec<String>,
+    /// The payload to sign, the call and the signed extensions, hashed if longer than 256
+    /// bytes. Ethereum accounts sign its keccak hash.
+    pub signing_payload: String,
+}
+
+fn default_signed_extensions() -> Vec<String> {
+    chain::DEFAULT_SIGNED_EXTENSIONS
+        .iter()
+        .map(|name| name.to_string())
+        .collect()
+}
+
+/// The validity window of a mortal extrinsic: `period` blocks from the block `block_number`,
+/// whose hash is signed along with the call.
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Mortality {
+    /// The number of blocks, rounded up to a power of two between 4 and 65536.
+    pub period: u64,
+    pub block_number: u64,
+    pub block_hash: H256,
+}
+
+impl Mortality {
+    /// Returns the window of `period` blocks from the last finalized block.
+    async fn from_finalized(url: &url::Url, period: u64) -> Result<Self> {
+        let finalized: H256 = rpc::request(url, "chain_getFinalizedHead", vec![]).await?;
+        let number = rpc::block_number(url, Some(&finalized)).await?;
+        let era = Era::mortal(period, number);
+        // long periods start at a block before the finalized one, as they are quantized
+        let block_number = era.birth(number);
+        let block_hash = if block_number == number {
+            finalized
+        } else {
+            rpc::request(url, "chain_getBlockHash", vec![block_number.into()]).await?
+        };
+        Ok(Mortality {
+            period: match era {
+                Era::Mortal(period, _) => period,
+                Era::Immortal => period,
+            },
+            block_number,
+            block_hash,
+        })
+    }
+
+    fn era(&self) -> Era {
+        Era::mortal(self.period, self.block_number)
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RuntimeVersion {
+    spec_version: u32,
+    transaction_version: u32,
+}
+
+impl UnsignedExtrinsic {
+    /// Creates the unsigned extrinsic of `call`, signed with the `nonce` of `signer` and the
+    /// tip and validity window of `extrinsic_opts`, fetching the runtime version and the
+    /// genesis hash from the node.
+    pub async fn new(
+        cli: &Client<DefaultNodeRuntime>,
+        extrinsic_opts: &ExtrinsicOpts,
+        signer: &AccountId,
+        nonce: u32,
+        call: Encoded,
+    ) -> Result<Self> {
+        let url = &extrinsic_opts.node.url()?;
+        if let Some(types) = chain::ChainTypes::detect(url).await? {
+            types.check_account(signer)?;
+        }
+        let version: RuntimeVersion = rpc::request(url, "state_getRuntimeVersion", vec![]).await?;
+        let era = if extrinsic_opts.immortal {
+            None
+        } else {
+            let period = extrinsic_opts.era.unwrap_or(DEFAULT_ERA);
+            Some(Mortality::from_finalized(url, period).await?)
+        };
+        let signed_extensions = if extrinsic_opts.signed_extensions.is_empty() {
+            chain::fetch_signed_extensions(url).await?
+        } else {
+            extrinsic_opts.signed_extensions.clone()
+        };
+        if extrinsic_opts.fee_asset.is_some()
+            && !signed_extensions
+                .iter()
+                .any(|name| name == "ChargeAssetTxPayment")
+        {
+            anyhow::bail!(
+                "Fees are paid in an asset with the `ChargeAssetTxPayment` signed extension, \
+                which the runtime or `--signed-extensions` lacks"
+            )
+        }
+        let mut unsigned = UnsignedExtrinsic {
+            signer: signer.to_string(),
+            call: hex(&call.0),
+            nonce,
+            tip: extrinsic_opts.tip,
+            fee_asset: extrinsic_opts.fee_asset,
+            era,
+            spec_version: version.spec_version,
+            transaction_version: version.transaction_version,
+            genesis_hash: *cli.genesis(),
+            signed_extensions,
+            signing_payload: String::new(),
+        };
+        unsigned.signing_payload = hex(&hashed(&unsigned.payload()?));
+        Ok(unsigned)
+    }
+
+    /// Reads an unsigned extrinsic written with `--offline`.
+    pub fn load(path: &PathBuf) -> Result<Self> {
+        let file = fs::read(path).with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_slice(&file).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn signer(&self) -> Result<AccountId> {
+        parse_account(&self.signer).with_context(|| format!("Invalid signer {}", self.signer))
+    }
+
+    /// Returns the data of the signed extensions which is part of the extrinsic, and the data
+    /// which is only signed.
+    fn signed_extensions(&self) -> Result<(Vec<u8>, Vec<u8>)> {
+        let data = ExtensionData {
+            spec_version: self.spec_version,
+            transaction_version: self.transaction_version,
+            genesis_hash: self.genesis_hash,
+            nonce: self.nonce,
+            tip: self.tip,
+            fee_asset: self.fee_asset,
+            era: self
+                .era
+                .as_ref()
+                .map(|mortality| (mortality.era(), mortality.block_hash)),
+        };
+        chain::encode_signed_extensions(&self.signed_extensions, &data)
+    }
+
+    /// Returns the signing payload: the call and the data of the signed extensions.
+    pub fn payload(&self) -> Result<Vec<u8>> {
+        let (extra, additional) = self.signed_extensions()?;
+        let mut payload = decode_hex(&self.call)?;
+        payload.extend(extra);
+        payload.extend(additional);
+        Ok(payload)
+    }
+
+    /// Returns the encoded extrinsic signed with `signature`, which is checked to be the
+    /// signature of the signing payload by the signer.
+    pub fn into_signed(self, signature: MultiSignature) -> Result<Vec<u8>> {
+        let signer = self.signer()?;
+        let payload = hashed(&self.payload()?);
+        let valid = match (&signer, &signature) {
+            (AccountId::Substrate(account_id), _) => signature.verify(&payload[..], account_id),
+            (AccountId::Ethereum(address), MultiSignature::Ecdsa(signature)) => {
+                recover_ethereum_address(signature.as_ref(), &payload) == Some(*address)
+            }
+            (AccountId::Ethereum(_), _) => false,
+        };
+        if !valid {
+            anyhow::bail!(
+                "The signature is not a signature of the signing payload by {}",
+                self.signer
+            )
+        }
+        self.with_signature(&signer, &signature)
+    }
+
+    /// Returns the encoded extrinsic signed with `signature`, without checking it.
+    pub fn with_signature(
+        &self,
+        signer: &AccountId,
+        signature: &MultiSignature,
+    ) -> Result<Vec<u8>> {
+        // a signed extrinsic of the fourth version
+        let mut extrinsic = vec![0b1000_0100];
+        signer.encode_address_to(&mut extrinsic);
+        match (signer, signature) {
+            // the signature of Ethereum accounts is no `MultiSignature`
+            (AccountId::Ethereum(_), MultiSignature::Ecdsa(signature)) => {
+                signature.encode_to(&mut extrinsic)
+            }
+            (AccountId::Ethereum(_), _) => {
+                anyhow::bail!("Ethereum accounts sign with ECDSA signatures")
+            }
+            (AccountId::Substrate(_), _) => signature.encode_to(&mut extrinsic),
+        }
+        extrinsic.extend(self.signed_extensions()?.0);
+        extrinsic.extend(decode_hex(&self.call)?);
+        // the extrinsic is prefixed with its length
+        Ok(extrinsic.encode())
+    }
+}
+
+/// Signs `call` with `signer` and the signed extensions of `extrinsic_opts`, submits it and
+/// waits for it as long as `--wait-for` and `--timeout` ask for, reconnecting up to `--retries`
+/// times. Without a `nonce`, the next nonce of the signer is used.
+pub(crate) async fn sign_and_watch<C: Call<DefaultNodeRuntime> + Send + Sync>(
+    cli: &Client<DefaultNodeRuntime>,
+    extrinsic_opts: &ExtrinsicOpts,
+    signer: &dyn Signer,
+    nonce: Option<u32>,
+    call: C,
+) -> Result<Submitted<ExtrinsicSuccess<DefaultNodeRuntime>>> {
+    let account = signer.account_id();
+    let nonce = match nonce {
+        Some(nonce) => nonce,
+        None => nonce::fetch(&extrinsic_opts.node.url()?, account).await?,
+    };
+    let call = cli.encode(call)?;
+    let unsigned = UnsignedExtrinsic::new(cli, extrinsic_opts, account, nonce, call).await?;
+    let signature = signer
+        .sign(&unsigned.payload()?)
+        .context("Failed to sign the extrinsic")?;
+    let extrinsic = unsigned.into_signed(signature)?;
+
+    if extrinsic_opts.wait_for == WaitFor::Broadcast {
+        let hash = rpc::request(
+            &extrinsic_opts.node.url()?,
+            "author_submitExtrinsic",
+            vec![hex(&extrinsic).into()],
+        )
+        .await?;
+        return Ok(Submitted::Pending(Pending::Broadcast(hash)));
+    }
+    let watched = reconnect::submit_and_watch(extrinsic_opts, extrinsic);
+    let events = match extrinsic_opts.node.timeout {
+        Some(timeout) => async_std::future::timeout(Duration::from_secs(timeout), watched)
+            .await
+            .map_err(|_| SubmitError::Timeout(Some(extrinsic_opts.wait_for), timeout))?,
+        None => watched.await,
+    }?;
+    Ok(Submitted::Executed(events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use sp_core::{ecdsa, sr25519};
+
+    #[test]
+    fn signs_the_tip_and_the_validity_window() {
+        let mut unsigned = UnsignedExtrinsic {
+            signer: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            call: "0x0803".to_string(),
+            nonce: 7,
+            tip: 0,
+            fee_asset: None,
+            era: None,
+            spec_version: 1,
+            transaction_version: 1,
+            genesis_hash: H256::repeat_byte(1),
+            signed_extensions: default_signed_extensions(),
+            signing_payload: String::new(),
+        };
+        let immortal = unsigned.payload().unwrap();
+        // the call, the immortal era, the nonce and no tip, then the signed data
+        assert_eq!(&immortal[..5], &[8, 3, 0, 28, 0]);
+
+        unsigned.tip = 1_000;
+        unsigned.era = Some(Mortality {
+            period: 64,
+            block_number: 100,
+            block_hash: H256::repeat_byte(2),
+        });
+        let mortal = unsigned.payload().unwrap();
+        let mut expected = vec![8, 3];
+        expected.extend(Era::mortal(64, 100).encode());
+        expected.push(28);
+        expected.extend(subxt::sp_runtime::codec::Compact(1_000u128).encode());
+        assert_eq!(&mortal[..expected.len()], &expected[..]);
+        // the hash of the first block of the window is signed instead of the genesis hash
+        assert_eq!(
+            &mortal[mortal.len() - 32..],
+            H256::repeat_byte(2).as_bytes()
+        );
+
+        let json = serde_json::to_string(&unsigned).unwrap();
+        assert_eq!(
+            serde_json::from_str::<UnsignedExtrinsic>(&json).unwrap(),
+            unsigned
+        );
+    }
+
+    #[test]
+    fn encodes_signed_extrinsics() {
+        let signer = KeypairSigner::new(sr25519::Pair::from_string("//Alice", None).unwrap());
+        let unsigned = UnsignedExtrinsic {
+            signer: signer.account_id().to_string(),
+            call: "0x0803".to_string(),
+            nonce: 0,
+            tip: 0,
+            fee_asset: None,
+            era: None,
+            spec_version: 1,
+            transaction_version: 1,
+            genesis_hash: H256::zero(),
+            signed_extensions: default_signed_extensions(),
+            signing_payload: String::new(),
+        };
+        let signature = signer.sign(&unsigned.payload().unwrap()).unwrap();
+        let extrinsic = unsigned.into_signed(signature).unwrap();
+        // the compact length, the version, the address, the signature, the extensions and
+        // the call
+        assert_eq!(extrinsic.len(), 2 + 1 + 33 + 65 + 3 + 2);
+        assert_eq!(&extrinsic[..4], &[0xa1, 0x01, 0x84, 0]);
+        assert_eq!(&extrinsic[extrinsic.len() - 5..], &[0, 0, 0, 8, 3]);
+    }
+
+    #[test]
+    fn signs_with_ecdsa_keys() {
+        let signer = KeypairSigner::<ecdsa::Pair>::from_suri("//Alice", None).unwrap();
+        let unsigned = UnsignedExtrinsic {
+            signer: signer.account_id().to_string(),
+            call: "0x0803".to_string(),
+            nonce: 0,
+            tip: 0,
+            fee_asset: None,
+            era: None,
+            spec_version: 1,
+            transaction_version: 1,
+            genesis_hash: H256::zero(),
+            signed_extensions: default_signed_extensions(),
+            signing_payload: String::new(),
+        };
+        let signature = signer.sign(&unsigned.payload().unwrap()).unwrap();
+        assert!(matches!(signature, MultiSignature::Ecdsa(_)));
+        let extrinsic = unsigned.into_signed(signature).unwrap();
+        // the signature is one byte longer than an sr25519 signature
+        assert_eq!(extrinsic.len(), 2 + 1 + 33 + 66 + 3 + 2);
+    }
+
+    #[test]
+    fn signs_for_ethereum_accounts() {
+        // the private key of the development account Alith of Ethereum-compatible chains
+        let signer = EthereumSigner::from_suri(
+            "0x5fb92d6e98884f76de468fa3f6278f8807c48bebc13595d45af5bdc4da702133",
+            None,
+        )
+        .unwrap();
+        assert_eq!(
+            signer.account_id().to_string(),
+            "0xf24FF3a9CF04c71Dbc94D0b566f7A27B94566cac"
+        );
+        let unsigned = || UnsignedExtrinsic {
+            signer: signer.account_id().to_string(),
+            call: "0x0803".to_string(),
+            nonce: 0,
+            tip: 0,
+            fee_asset: None,
+            era: None,
+            spec_version: 1,
+            transaction_version: 1,
+            genesis_hash: H256::zero(),
+            signed_extensions: default_signed_extensions(),
+            signing_payload: String::new(),
+        };
+        let payload = unsigned().payload().unwrap();
+        let extrinsic = unsigned()
+            .into_signed(signer.sign(&payload).unwrap())
+            .unwrap();
+        // the raw address and the raw recoverable signature, neither of them tagged
+        assert_eq!(extrinsic.len(), 2 + 1 + 20 + 65 + 3 + 2);
+        assert_eq!(&extrinsic[3..23], signer.account_id().as_bytes());
+
+        let other = KeypairSigner::<ecdsa::Pair>::from_suri("//Alice", None).unwrap();
+        assert!(unsigned()
+            .into_signed(other.sign(&payload).unwrap())
+            .is_err());
+    }
+}
@@ -0,0 +1,83 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Calls the `ContractsApi` runtime API with raw SCALE encoded input and output through the
+//! `state_call` RPC with `cargo contract rpc`, to debug the behavior of the node.
+
+use anyhow::Result;
+use sp_core::H256;
+use structopt::StructOpt;
+
+use super::{chain::parse_block_hash, dry_run::hex, rpc};
+use crate::{abi::decode_hex, NodeOpts};
+
+/// The runtime API of the contracts pallet.
+const CONTRACTS_API: &str = "ContractsApi";
+
+/// Calls a function of the `ContractsApi` runtime API with SCALE encoded arguments and prints
+/// its SCALE encoded result.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "rpc")]
+pub struct RpcCommand {
+    /// The function of the runtime API: `call`, `instantiate`, `upload_code` or `get_storage`,
+    /// or `<Api>_<function>` for the functions of other runtime APIs
+    method: String,
+    /// The hex encoded SCALE arguments of the function, concatenated in their order
+    #[structopt(default_value = "0x")]
+    args: String,
+    /// The hash of the block whose state to call the function on, instead of the best block
+    #[structopt(long, parse(try_from_str = parse_block_hash))]
+    at: Option<H256>,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+}
+
+impl RpcCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let args = decode_hex(&self.args)?;
+        let mut params = vec![runtime_function(&self.method).into(), hex(&args).into()];
+        if let Some(at) = &self.at {
+            params.push(hex(&at.0).into());
+        }
+        let result: String =
+            self.node_opts
+                .block_on(rpc::request(&self.node_opts.url()?, "state_call", params))?;
+        println!("{}", result);
+        Ok(None)
+    }
+}
+
+/// Returns the name of the runtime function `method`, a function of the `ContractsApi` unless
+/// it names its API.
+fn runtime_function(method: &str) -> String {
+    if method.contains('_') && method.starts_with(char::is_uppercase) {
+        method.to_string()
+    } else {
+        format!("{}_{}", CONTRACTS_API, method)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn names_runtime_functions() {
+        assert_eq!(runtime_function("call"), "ContractsApi_call");
+        assert_eq!(runtime_function("upload_code"), "ContractsApi_upload_code");
+        assert_eq!(runtime_function("Core_version"), "Core_version");
+    }
+}
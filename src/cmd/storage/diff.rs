@@ -0,0 +1,283 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Compares the storage of a contract at two blocks with `cargo contract storage diff`.
+
+use std::{collections::BTreeMap, path::PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+use sp_core::{crypto::AccountId32, H256};
+use structopt::StructOpt;
+
+use super::dump::{snapshot, Snapshot};
+use crate::{
+    cmd::{
+        chain::{parse_account_id, parse_block_hash},
+        extrinsic_output::load_abi,
+        rpc,
+    },
+    NodeOpts,
+};
+
+/// Shows which values in the storage of a contract changed between two blocks, decoded with
+/// the storage layout of its metadata.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "diff")]
+pub struct DiffCommand {
+    /// The account of the contract
+    #[structopt(long, parse(try_from_str = parse_account_id))]
+    contract: AccountId32,
+    /// The number or `0x` prefixed hash of the block to compare from
+    #[structopt(long)]
+    from: BlockRef,
+    /// The number or `0x` prefixed hash of the block to compare to, the best block if not
+    /// given
+    #[structopt(long)]
+    to: Option<BlockRef>,
+    /// The key of a `Mapping` entry to decode, as `<path>=<key>`, e.g. `balances=5GrwvaEF...`
+    #[structopt(long = "map-key", parse(try_from_str = super::dump::parse_map_key))]
+    map_keys: Vec<(String, String)>,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Path to the Cargo.toml of the contract whose metadata describes the storage
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the changes as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// A block given by its number or its hash.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BlockRef {
+    Number(u64),
+    Hash(H256),
+}
+
+impl std::str::FromStr for BlockRef {
+    type Err = anyhow::Error;
+    fn from_str(input: &str) -> Result<Self> {
+        if input.starts_with("0x") {
+            parse_block_hash(input).map(BlockRef::Hash)
+        } else {
+            Ok(BlockRef::Number(input.parse().with_context(|| {
+                format!("Expected a block number or hash, got `{}`", input)
+            })?))
+        }
+    }
+}
+
+impl BlockRef {
+    /// Returns the hash of the block.
+    async fn hash(&self, url: &url::Url) -> Result<H256> {
+        match self {
+            BlockRef::Hash(hash) => Ok(*hash),
+            BlockRef::Number(number) => {
+                let hash: Option<H256> =
+                    rpc::request(url, "chain_getBlockHash", vec![(*number).into()]).await?;
+                hash.with_context(|| format!("Block #{} is not known", number))
+            }
+        }
+    }
+}
+
+/// A value of the storage which changed between the blocks.
+#[derive(Debug, PartialEq, Serialize)]
+struct Change {
+    /// The path of the field, e.g. `owner` or `balances[5GrwvaEF...]`, or the key of a value
+    /// which is not decoded.
+    path: String,
+    /// The value at the `--from` block, `null` if it was not stored.
+    from: Option<Value>,
+    /// The value at the `--to` block, `null` if it was removed.
+    to: Option<Value>,
+}
+
+/// The changes printed with `--output-json`.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiffOutput<'a> {
+    from_block: H256,
+    to_block: H256,
+    changes: &'a [Change],
+}
+
+impl DiffCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Decoding the storage requires the metadata of the contract")?;
+        let map_keys = self
+            .map_keys
+            .iter()
+            .map(|(path, key)| super::map_key(&abi, path, key))
+            .collect::<Result<Vec<_>>>()?;
+        let (from, to) = self.node_opts.block_on(async {
+            let from = self.from.hash(&self.node_opts.url()?).await?;
+            let to = match &self.to {
+                Some(to) => Some(to.hash(&self.node_opts.url()?).await?),
+                None => None,
+            };
+            let from = snapshot(
+                &self.node_opts.url()?,
+                &self.contract,
+                &abi,
+                &map_keys,
+                Some(from),
+            )
+            .await?;
+            let to = snapshot(&self.node_opts.url()?, &self.contract, &abi, &map_keys, to).await?;
+            Ok::<_, anyhow::Error>((from, to))
+        })?;
+        let changes = diff(&from, &to);
+
+        if self.output_json {
+            let output = DiffOutput {
+                from_block: from.block,
+                to_block: to.block,
+                changes: &changes,
+            };
+            return Ok(Some(serde_json::to_string_pretty(&output)?));
+        }
+        if changes.is_empty() {
+            return Ok(Some(format!(
+                "The storage did not change between {:?} and {:?}",
+                from.block, to.block
+            )));
+        }
+        let value = |value: &Option<Value>| match value {
+            Some(value) => value.to_string(),
+            None => "<none>".dimmed().to_string(),
+        };
+        for change in &changes {
+            println!(
+                "{} {} -> {}",
+                format!("{}:", change.path).bold(),
+                value(&change.from),
+                value(&change.to)
+            );
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the values which differ between the snapshots `from` and `to`.
+fn diff(from: &Snapshot, to: &Snapshot) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_values(&mut changes, &from.fields, &to.fields);
+
+    let entries = |snapshot: &Snapshot| {
+        snapshot
+            .mappings
+            .iter()
+            .flat_map(|(path, entries)| {
+                entries
+                    .iter()
+                    .map(move |entry| (format!("{}[{}]", path, entry.key), entry.value.clone()))
+            })
+            .collect::<BTreeMap<_, _>>()
+    };
+    diff_values(&mut changes, &entries(from), &entries(to));
+
+    let raw = |snapshot: &Snapshot| {
+        snapshot
+            .raw
+            .iter()
+            .map(|(key, value)| (key.clone(), Value::String(value.clone())))
+            .collect::<BTreeMap<_, _>>()
+    };
+    diff_values(&mut changes, &raw(from), &raw(to));
+    changes
+}
+
+fn diff_values(
+    changes: &mut Vec<Change>,
+    from: &BTreeMap<String, Value>,
+    to: &BTreeMap<String, Value>,
+) {
+    let mut paths = from.keys().chain(to.keys()).collect::<Vec<_>>();
+    paths.sort();
+    paths.dedup();
+    for path in paths {
+        let (from, to) = (from.get(path), to.get(path));
+        if from != to {
+            changes.push(Change {
+                path: path.clone(),
+                from: from.cloned(),
+                to: to.cloned(),
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cmd::storage::dump::MappingEntry;
+    use serde_json::json;
+
+    #[test]
+    fn reports_changed_fields_and_entries() {
+        let snapshot = |total: u64, balances: Vec<(&str, u64)>, raw: &[(&str, &str)]| Snapshot {
+            fields: vec![
+                ("total".to_string(), json!(total)),
+                ("owner".to_string(), json!("0x01")),
+            ]
+            .into_iter()
+            .collect(),
+            mappings: vec![(
+                "balances".to_string(),
+                balances
+                    .into_iter()
+                    .map(|(key, value)| MappingEntry {
+                        key: json!(key),
+                        value: json!(value),
+                    })
+                    .collect(),
+            )]
+            .into_iter()
+            .collect(),
+            raw: raw
+                .iter()
+                .map(|(key, value)| (key.to_string(), value.to_string()))
+                .collect(),
+            ..Default::default()
+        };
+        let from = snapshot(100, vec![("0x02", 60), ("0x03", 40)], &[("0x09", "0x01")]);
+        let to = snapshot(160, vec![("0x02", 120), ("0x04", 40)], &[("0x09", "0x01")]);
+
+        let change = |path: &str, from: Option<u64>, to: Option<u64>| Change {
+            path: path.to_string(),
+            from: from.map(Value::from),
+            to: to.map(Value::from),
+        };
+        assert_eq!(
+            diff(&from, &to),
+            vec![
+                change("total", Some(100), Some(160)),
+                change("balances[\"0x02\"]", Some(60), Some(120)),
+                change("balances[\"0x03\"]", Some(40), None),
+                change("balances[\"0x04\"]", None, Some(40)),
+            ]
+        );
+        assert!(diff(&to, &to).is_empty());
+    }
+}
@@ -0,0 +1,303 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Dumps the storage of a contract into a JSON snapshot with `cargo contract storage dump`.
+
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs,
+    path::PathBuf,
+};
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    H256,
+};
+use structopt::StructOpt;
+
+use super::read::{decode_entry, Entry};
+use crate::{
+    abi::{ContractAbi, StorageCell, StorageMapping},
+    cmd::{chain::parse_account_id, dry_run::hex, extrinsic_output::load_abi, rpc},
+    NodeOpts,
+};
+
+/// Writes all storage entries of a contract into a JSON snapshot, decoded with the storage
+/// layout of its metadata where possible.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "dump")]
+pub struct DumpCommand {
+    /// The account of the contract
+    #[structopt(long, parse(try_from_str = parse_account_id))]
+    contract: AccountId32,
+    /// The key of a `Mapping` entry to decode, as `<path>=<key>`, e.g. `balances=5GrwvaEF...`.
+    ///
+    /// The entries of ink! 4 mappings are found without their keys, the keys of ink! 3
+    /// mappings are hashed away and have to be passed.
+    #[structopt(long = "map-key", parse(try_from_str = parse_map_key))]
+    map_keys: Vec<(String, String)>,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Path to the Cargo.toml of the contract whose metadata describes the storage
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Write the snapshot to this file instead of printing it
+    #[structopt(short, long, parse(from_os_str))]
+    output: Option<PathBuf>,
+}
+
+pub(super) fn parse_map_key(input: &str) -> Result<(String, String)> {
+    let (path, key) = input
+        .split_once('=')
+        .context("Expected the mapping key as `<path>=<key>`")?;
+    Ok((path.to_string(), key.to_string()))
+}
+
+/// The storage of a contract at a block.
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(super) struct Snapshot {
+    pub contract: String,
+    pub block: H256,
+    /// The values of the fields of the storage, by their paths.
+    pub fields: BTreeMap<String, Value>,
+    /// The entries of the mappings, by the paths of the mappings.
+    pub mappings: BTreeMap<String, Vec<MappingEntry>>,
+    /// The values which are not decoded, by their keys in the child trie of the contract.
+    pub raw: BTreeMap<String, String>,
+}
+
+/// An entry of a `Mapping`.
+#[derive(Debug, PartialEq, Serialize, Deserialize)]
+pub(super) struct MappingEntry {
+    pub key: Value,
+    pub value: Value,
+}
+
+impl DumpCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Decoding the storage requires the metadata of the contract")?;
+        let map_keys = self
+            .map_keys
+            .iter()
+            .map(|(path, key)| super::map_key(&abi, path, key))
+            .collect::<Result<Vec<_>>>()?;
+        let snapshot = self.node_opts.block_on(snapshot(
+            &self.node_opts.url()?,
+            &self.contract,
+            &abi,
+            &map_keys,
+            None,
+        ))?;
+
+        let json = serde_json::to_string_pretty(&snapshot)?;
+        match &self.output {
+            Some(path) => {
+                fs::write(path, json)?;
+                Ok(Some(format!(
+                    "Written the storage at block {:?} to {}",
+                    snapshot.block,
+                    path.display()
+                )))
+            }
+            None => {
+                println!("{}", json);
+                Ok(None)
+            }
+        }
+    }
+}
+
+/// Reads the storage of `contract` at the block `at`, or at the best block.
+///
+/// The entries of `mappings` are decoded with the passed keys.
+pub(super) async fn snapshot(
+    url: &url::Url,
+    contract: &AccountId32,
+    abi: &ContractAbi,
+    map_keys: &[(&StorageMapping, Vec<u8>)],
+    at: Option<H256>,
+) -> Result<Snapshot> {
+    let block = match at {
+        Some(at) => at,
+        None => rpc::request(url, "chain_getBlockHash", vec![]).await?,
+    };
+    let trie_id = super::trie_id(url, contract).await?;
+    let entries = rpc::child_storage_entries(url, &trie_id, &block).await?;
+    Ok(Snapshot {
+        contract: contract.to_ss58check(),
+        block,
+        ..decode(abi, entries, map_keys)
+    })
+}
+
+/// The storage entries whose keys are known from the storage layout.
+enum Known<'a> {
+    Cell(&'a StorageCell),
+    MappingEntry(&'a StorageMapping, &'a [u8]),
+}
+
+/// Decodes the `entries` of the child trie of a contract with its storage layout.
+fn decode(
+    abi: &ContractAbi,
+    entries: Vec<(Vec<u8>, Vec<u8>)>,
+    map_keys: &[(&StorageMapping, Vec<u8>)],
+) -> Snapshot {
+    let storage = &abi.storage;
+    let mut known = HashMap::new();
+    for cell in &storage.cells {
+        known.insert(storage.trie_key(&cell.key), Known::Cell(cell));
+    }
+    for (mapping, key) in map_keys {
+        let trie_key = storage.trie_key(&storage.entry_key(mapping, key));
+        known.insert(trie_key, Known::MappingEntry(mapping, key));
+    }
+
+    let mut snapshot = Snapshot::default();
+    for (trie_key, value) in entries {
+        let decoded = match known.get(&trie_key) {
+            Some(Known::Cell(cell)) => decode_entry(&abi.types, &Entry::Cell(cell, ""), &value)
+                .map(|fields| {
+                    for (path, value) in fields {
+                        snapshot.fields.insert(path, value.to_json());
+                    }
+                })
+                .is_ok(),
+            Some(Known::MappingEntry(mapping, key)) => {
+                decode_mapping_entry(abi, mapping, key, &value, &mut snapshot)
+            }
+            // the keys of ink! 4 mapping entries end with their mapping key
+            None => {
+                let entry = storage.storage_key(&trie_key).and_then(|key| {
+                    storage
+                        .mappings
+                        .iter()
+                        .find(|mapping| key.starts_with(&mapping.prefix))
+                        .map(|mapping| (mapping, key[mapping.prefix.len()..].to_vec()))
+                });
+                match entry {
+                    Some((mapping, key)) => {
+                        decode_mapping_entry(abi, mapping, &key, &value, &mut snapshot)
+                    }
+                    None => false,
+                }
+            }
+        };
+        if !decoded {
+            snapshot.raw.insert(hex(&trie_key), hex(&value));
+        }
+    }
+    snapshot
+}
+
+/// Adds the entry of the encoded `key` of `mapping` to the `snapshot`, returns `false` if it
+/// can't be decoded.
+fn decode_mapping_entry(
+    abi: &ContractAbi,
+    mapping: &StorageMapping,
+    key: &[u8],
+    value: &[u8],
+    snapshot: &mut Snapshot,
+) -> bool {
+    let key = match mapping.key_ty {
+        Some(ty) => {
+            let input = &mut &key[..];
+            match abi.types.decode(ty, input) {
+                Ok(decoded) if input.is_empty() => decoded.to_json(),
+                _ => return false,
+            }
+        }
+        None => Value::String(hex(key)),
+    };
+    let value = match abi.types.decode(mapping.value_ty, &mut &value[..]) {
+        Ok(value) => value.to_json(),
+        Err(_) => return false,
+    };
+    snapshot
+        .mappings
+        .entry(mapping.path.clone())
+        .or_default()
+        .push(MappingEntry { key, value });
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+    use subxt::sp_runtime::codec::Encode;
+
+    #[test]
+    fn decodes_the_entries_of_the_layout() {
+        let mut metadata = metadata_v1()["V1"].clone();
+        metadata["version"] = json!("4");
+        metadata["storage"] = json!({ "root": {
+            "root_key": "0x00000000",
+            "layout": { "struct": { "name": "Bank", "fields": [
+                { "name": "total", "layout": { "leaf": { "key": "0x00000000", "ty": 0 } } },
+                { "name": "balances", "layout": { "root": {
+                    "root_key": "0x2a000000",
+                    "layout": { "leaf": { "key": "0x2a000000", "ty": 0 } },
+                    "ty": 8
+                } } },
+                { "name": "owner", "layout": { "leaf": { "key": "0x00000000", "ty": 1 } } }
+            ] } }
+        } });
+        metadata["types"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "id": 8, "type": {
+            "def": { "composite": { "fields": [] } },
+            "params": [{ "name": "K", "type": 1 }, { "name": "V", "type": 0 }],
+            "path": ["ink_storage", "lazy", "mapping", "Mapping"]
+        } }));
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+        let storage = &abi.storage;
+
+        let balances = storage.mapping("balances").unwrap();
+        let entries = vec![
+            (storage.trie_key(&[0; 4]), (100u128, [1u8; 32]).encode()),
+            (
+                storage.trie_key(&storage.entry_key(balances, &[2; 32])),
+                60u128.encode(),
+            ),
+            (vec![9; 20], vec![1, 2]),
+        ];
+        let snapshot = decode(&abi, entries, &[]);
+
+        assert_eq!(snapshot.fields["total"], json!(100));
+        assert_eq!(
+            snapshot.fields["owner"],
+            json!(format!("0x{}", "01".repeat(32)))
+        );
+        assert_eq!(
+            snapshot.mappings["balances"],
+            vec![MappingEntry {
+                key: json!(format!("0x{}", "02".repeat(32))),
+                value: json!(60),
+            }]
+        );
+        assert_eq!(snapshot.raw[&hex(&[9; 20])], "0x0102");
+    }
+}
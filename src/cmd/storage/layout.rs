@@ -0,0 +1,275 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Prints the storage layout of a contract with `cargo contract storage layout`.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use structopt::StructOpt;
+
+use super::{dump::parse_map_key, map_key};
+use crate::{
+    abi::{ContractAbi, KeyScheme, StorageLayout},
+    cmd::{dry_run::hex, extrinsic_output::load_abi},
+};
+
+/// Prints the fields and mappings of the storage layout of a contract with the keys of the
+/// child trie their values are stored under.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "layout")]
+pub struct LayoutCommand {
+    /// The key of a `Mapping` entry to derive the storage key of, as `<path>=<key>`, e.g.
+    /// `balances=5GrwvaEF...`
+    #[structopt(long = "map-key", parse(try_from_str = parse_map_key))]
+    map_keys: Vec<(String, String)>,
+    /// Path to the Cargo.toml of the contract whose metadata describes the storage
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the layout as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The storage layout with the keys of its values.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Layout {
+    /// How the keys of the layout map to the keys of the child trie.
+    scheme: &'static str,
+    cells: Vec<Cell>,
+    mappings: Vec<Mapping>,
+    unsupported: Vec<String>,
+}
+
+/// A value stored under a single key, with the fields packed into it.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Cell {
+    path: String,
+    key: String,
+    trie_key: String,
+    fields: Vec<Field>,
+    complete: bool,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+struct Field {
+    path: String,
+    #[serde(rename = "type")]
+    ty: String,
+}
+
+/// A `Mapping`, with how the keys of its entries are derived.
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Mapping {
+    path: String,
+    prefix: String,
+    key_type: Option<String>,
+    value_type: String,
+    /// How the key of the child trie is derived from the SCALE encoded mapping key.
+    trie_key: String,
+    /// The entries of the keys passed with `--map-key`.
+    entries: Vec<MappingEntry>,
+}
+
+#[derive(Debug, PartialEq, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct MappingEntry {
+    key: String,
+    trie_key: String,
+}
+
+impl LayoutCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Reading the storage layout requires the metadata of the contract")?;
+        let layout = layout(&abi, &self.map_keys)?;
+        if self.output_json {
+            return Ok(Some(serde_json::to_string_pretty(&layout)?));
+        }
+
+        println!("{} {}", "Key scheme:".bold(), layout.scheme);
+        for cell in &layout.cells {
+            let path = if cell.path.is_empty() {
+                "<storage>"
+            } else {
+                &cell.path
+            };
+            println!("\n{}", path.bold());
+            println!("  {:<10}{}", "key", cell.key);
+            println!("  {:<10}{}", "trie key", cell.trie_key);
+            for field in &cell.fields {
+                println!("  {:<10}{}: {}", "field", field.path, field.ty);
+            }
+            if !cell.complete {
+                println!("  followed by fields whose layout is not supported");
+            }
+        }
+        for mapping in &layout.mappings {
+            let key_type = mapping.key_type.as_deref().unwrap_or("_");
+            println!(
+                "\n{} Mapping<{}, {}>",
+                format!("{}:", mapping.path).bold(),
+                key_type,
+                mapping.value_type
+            );
+            println!("  {:<10}{}", "prefix", mapping.prefix);
+            println!("  {:<10}{}", "trie key", mapping.trie_key);
+            for entry in &mapping.entries {
+                println!("  {:<10}{} => {}", "entry", entry.key, entry.trie_key);
+            }
+        }
+        if !layout.unsupported.is_empty() {
+            println!(
+                "\n{} {}",
+                "Not supported:".bold(),
+                layout.unsupported.join(", ")
+            );
+        }
+        Ok(None)
+    }
+}
+
+/// Returns the layout of the storage of `abi` with the keys of the entries of `map_keys`.
+fn layout(abi: &ContractAbi, map_keys: &[(String, String)]) -> Result<Layout> {
+    let storage = &abi.storage;
+    let types = &abi.types;
+    let cells = storage
+        .cells
+        .iter()
+        .map(|cell| Cell {
+            path: cell.path.clone(),
+            key: hex(&cell.key),
+            trie_key: hex(&storage.trie_key(&cell.key)),
+            fields: cell
+                .fields
+                .iter()
+                .map(|(path, ty)| Field {
+                    path: path.clone(),
+                    ty: types.type_name(*ty),
+                })
+                .collect(),
+            complete: cell.complete,
+        })
+        .collect();
+    let mut mappings = storage
+        .mappings
+        .iter()
+        .map(|mapping| Mapping {
+            path: mapping.path.clone(),
+            prefix: hex(&mapping.prefix),
+            key_type: mapping.key_ty.map(|ty| types.type_name(ty)),
+            value_type: types.type_name(mapping.value_ty),
+            trie_key: entry_key_scheme(storage, &hex(&mapping.prefix)),
+            entries: Vec::new(),
+        })
+        .collect::<Vec<_>>();
+    for (path, key) in map_keys {
+        let (mapping, encoded) = map_key(abi, path, key)?;
+        let trie_key = storage.trie_key(&storage.entry_key(mapping, &encoded));
+        if let Some(output) = mappings.iter_mut().find(|output| &output.path == path) {
+            output.entries.push(MappingEntry {
+                key: key.clone(),
+                trie_key: hex(&trie_key),
+            });
+        }
+    }
+    Ok(Layout {
+        scheme: match storage.scheme {
+            KeyScheme::Hashed => "blake2_256(key)",
+            KeyScheme::Concat => "blake2_128(key) ++ key",
+        },
+        cells,
+        mappings,
+        unsupported: storage.unsupported.clone(),
+    })
+}
+
+/// Describes how the key of the child trie of a mapping entry is derived from `prefix`.
+fn entry_key_scheme(storage: &StorageLayout, prefix: &str) -> String {
+    match storage.scheme {
+        KeyScheme::Hashed => format!("blake2_256(blake2_256({} ++ key))", prefix),
+        KeyScheme::Concat => format!("blake2_128({0} ++ key) ++ {0} ++ key", prefix),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+
+    #[test]
+    fn derives_the_keys_of_fields_and_mapping_entries() {
+        let mut metadata = metadata_v1()["V1"].clone();
+        metadata["version"] = json!("4");
+        metadata["storage"] = json!({ "root": {
+            "root_key": "0x00000000",
+            "layout": { "struct": { "name": "Bank", "fields": [
+                { "name": "total", "layout": { "leaf": { "key": "0x00000000", "ty": 0 } } },
+                { "name": "balances", "layout": { "root": {
+                    "root_key": "0x2a000000",
+                    "layout": { "leaf": { "key": "0x2a000000", "ty": 0 } },
+                    "ty": 8
+                } } }
+            ] } }
+        } });
+        metadata["types"]
+            .as_array_mut()
+            .unwrap()
+            .push(json!({ "id": 8, "type": {
+            "def": { "composite": { "fields": [] } },
+            "params": [{ "name": "K", "type": 1 }, { "name": "V", "type": 0 }],
+            "path": ["ink_storage", "lazy", "mapping", "Mapping"]
+        } }));
+        let abi = ContractAbi::from_json(&metadata).unwrap();
+        let owner = format!("0x{}", "02".repeat(32));
+
+        let output = layout(&abi, &[("balances".to_string(), owner.clone())]).unwrap();
+        assert_eq!(output.scheme, "blake2_128(key) ++ key");
+        assert_eq!(output.cells[0].path, "");
+        assert_eq!(output.cells[0].key, "0x00000000");
+        assert_eq!(
+            output.cells[0].trie_key,
+            hex(&abi.storage.trie_key(&[0; 4]))
+        );
+        assert_eq!(output.cells[0].fields[0].path, "total");
+
+        let balances = &output.mappings[0];
+        assert_eq!(balances.prefix, "0x2a000000");
+        assert_eq!(
+            balances.trie_key,
+            "blake2_128(0x2a000000 ++ key) ++ 0x2a000000 ++ key"
+        );
+        let mut entry_key = vec![0x2a, 0, 0, 0];
+        entry_key.extend([2; 32]);
+        assert_eq!(
+            balances.entries,
+            vec![MappingEntry {
+                key: owner,
+                trie_key: hex(&abi.storage.trie_key(&entry_key)),
+            }]
+        );
+        assert!(layout(&abi, &[("total".to_string(), "1".to_string())]).is_err());
+    }
+}
@@ -0,0 +1,84 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reads the storage of instantiated contracts with `cargo contract storage`.
+
+mod diff;
+mod dump;
+mod layout;
+mod read;
+
+use self::{diff::DiffCommand, dump::DumpCommand, layout::LayoutCommand, read::ReadCommand};
+use super::{info::contract::ContractInfoOfStore, submit};
+use crate::abi::{parse_arg_value, ContractAbi, StorageMapping};
+use anyhow::{Context, Result};
+use sp_core::crypto::{AccountId32, Ss58Codec};
+use structopt::StructOpt;
+
+/// Reads the storage of an instantiated contract, decoded with its metadata.
+#[derive(Debug, StructOpt)]
+pub enum StorageCommand {
+    /// Read a storage entry of a contract by its key or the path of its field
+    #[structopt(name = "read")]
+    Read(ReadCommand),
+    /// Write all storage entries of a contract into a JSON snapshot
+    #[structopt(name = "dump")]
+    Dump(DumpCommand),
+    /// Show the values in the storage of a contract which changed between two blocks
+    #[structopt(name = "diff")]
+    Diff(DiffCommand),
+    /// Print the fields and mappings of the storage layout with the keys they are stored under
+    #[structopt(name = "layout")]
+    Layout(LayoutCommand),
+}
+
+impl StorageCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        match self {
+            StorageCommand::Read(read) => read.exec(),
+            StorageCommand::Dump(dump) => dump.exec(),
+            StorageCommand::Diff(diff) => diff.exec(),
+            StorageCommand::Layout(layout) => layout.exec(),
+        }
+    }
+}
+
+/// Returns the id of the child trie holding the storage of `contract`.
+async fn trie_id(url: &url::Url, contract: &AccountId32) -> Result<Vec<u8>> {
+    let cli = submit::builder(url).build().await?;
+    let info = cli
+        .fetch(&ContractInfoOfStore { contract }, None)
+        .await?
+        .with_context(|| format!("No contract is instantiated at {}", contract.to_ss58check()))?;
+    Ok(info.trie_id)
+}
+
+/// Returns the mapping at `path` and the SCALE encoded `key` of one of its entries.
+fn map_key<'a>(
+    abi: &'a ContractAbi,
+    path: &str,
+    key: &str,
+) -> Result<(&'a StorageMapping, Vec<u8>)> {
+    let mapping = abi
+        .storage
+        .mapping(path)
+        .with_context(|| format!("No mapping `{}` in the storage layout", path))?;
+    let key_ty = mapping
+        .key_ty
+        .with_context(|| format!("The key type of `{}` is unknown", path))?;
+    let key = abi.types.encode(key_ty, &parse_arg_value(key))?;
+    Ok((mapping, key))
+}
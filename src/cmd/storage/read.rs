@@ -0,0 +1,220 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Reads a storage entry of a contract with `cargo contract storage read`.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use serde_json::Value;
+use sp_core::crypto::AccountId32;
+use structopt::StructOpt;
+
+use crate::{
+    abi::{decode_hex, is_within, ContractAbi, DecodedValue, StorageCell, TypeRegistry},
+    cmd::{chain::parse_account_id, dry_run::hex, extrinsic_output::load_abi, rpc},
+    NodeOpts,
+};
+
+/// Reads a storage entry of a contract and decodes it with the storage layout of its
+/// metadata.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "read")]
+pub struct ReadCommand {
+    /// The account of the contract
+    #[structopt(long, parse(try_from_str = parse_account_id))]
+    contract: AccountId32,
+    /// The `0x` prefixed storage key, or the path of a field of the storage, e.g. `owner`
+    /// or `config.limit`
+    #[structopt(long)]
+    key: String,
+    /// The key of the entry to read if `--key` is the path of a `Mapping`
+    #[structopt(long)]
+    map_key: Option<String>,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Path to the Cargo.toml of the contract whose metadata describes the storage
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the entry as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// How the value of a storage entry is decoded.
+pub(super) enum Entry<'a> {
+    /// The fields of a cell within the path.
+    Cell(&'a StorageCell, &'a str),
+    /// The value of a mapping entry, of the type with the id.
+    Mapping(u32),
+    /// A value of a key which is not in the storage layout.
+    Raw,
+}
+
+/// The entry printed with `--output-json`.
+#[derive(Serialize)]
+struct EntryOutput {
+    key: String,
+    /// The decoded value, `null` if nothing is stored under the key.
+    value: Option<Value>,
+}
+
+impl ReadCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Decoding the storage requires the metadata of the contract")?;
+        let (key, entry) = self.entry(&abi)?;
+        let trie_key = abi.storage.trie_key(&key);
+        let value = self.node_opts.block_on(async {
+            let trie_id = super::trie_id(&self.node_opts.url()?, &self.contract).await?;
+            rpc::child_storage(&self.node_opts.url()?, &trie_id, &trie_key).await
+        })?;
+
+        let fields = value
+            .map(|value| decode_entry(&abi.types, &entry, &value))
+            .transpose()?;
+        if self.output_json {
+            let value = fields.map(|fields| match (&entry, fields.as_slice()) {
+                (Entry::Cell(..), _) => Value::Object(
+                    fields
+                        .iter()
+                        .map(|(path, value)| (path.clone(), value.to_json()))
+                        .collect(),
+                ),
+                (_, [(_, value)]) => value.to_json(),
+                _ => Value::Null,
+            });
+            let output = EntryOutput {
+                key: hex(&key),
+                value,
+            };
+            return Ok(Some(serde_json::to_string_pretty(&output)?));
+        }
+        let fields = match fields {
+            Some(fields) => fields,
+            None => return Ok(Some(format!("No value is stored under {}", hex(&key)))),
+        };
+        for (path, value) in fields {
+            let label = match (path.is_empty(), &self.map_key) {
+                (false, _) => path,
+                (true, Some(map_key)) => format!("{}[{}]", self.key, map_key),
+                (true, None) => self.key.clone(),
+            };
+            println!("{} {}", format!("{}:", label).bold(), value);
+        }
+        Ok(None)
+    }
+
+    /// Returns the storage key of `--key` and how its value is decoded.
+    fn entry<'a>(&'a self, abi: &'a ContractAbi) -> Result<(Vec<u8>, Entry<'a>)> {
+        let storage = &abi.storage;
+        if self.key.starts_with("0x") {
+            let key = decode_hex(&self.key)?;
+            let entry = match storage.cells.iter().find(|cell| cell.key == key) {
+                Some(cell) => Entry::Cell(cell, ""),
+                None => Entry::Raw,
+            };
+            return Ok((key, entry));
+        }
+        if storage.mapping(&self.key).is_some() {
+            let map_key = self
+                .map_key
+                .as_ref()
+                .with_context(|| format!("`{}` is a mapping, pass --map-key", self.key))?;
+            let (mapping, map_key) = super::map_key(abi, &self.key, map_key)?;
+            return Ok((
+                storage.entry_key(mapping, &map_key),
+                Entry::Mapping(mapping.value_ty),
+            ));
+        }
+        match storage.cell(&self.key) {
+            Some(cell) => Ok((cell.key.clone(), Entry::Cell(cell, &self.key))),
+            None if storage
+                .unsupported
+                .iter()
+                .any(|path| is_within(&self.key, path)) =>
+            {
+                anyhow::bail!(
+                    "Reading the storage layout of `{}` is not supported",
+                    self.key
+                )
+            }
+            None => anyhow::bail!("No field `{}` in the storage layout", self.key),
+        }
+    }
+}
+
+/// Decodes the `value` of `entry` into the values of its fields, with their paths.
+pub(super) fn decode_entry(
+    types: &TypeRegistry,
+    entry: &Entry,
+    value: &[u8],
+) -> Result<Vec<(String, DecodedValue)>> {
+    let input = &mut &value[..];
+    match entry {
+        Entry::Cell(cell, path) => {
+            let mut fields = Vec::new();
+            // the fields are packed, so the ones before `path` are decoded as well
+            for (field, ty) in &cell.fields {
+                let value = types
+                    .decode(*ty, input)
+                    .with_context(|| format!("Failed to decode `{}`", field))?;
+                if is_within(field, path) {
+                    fields.push((field.clone(), value));
+                }
+            }
+            Ok(fields)
+        }
+        Entry::Mapping(ty) => Ok(vec![(String::new(), types.decode(*ty, input)?)]),
+        Entry::Raw => Ok(vec![(String::new(), DecodedValue::Bytes(value.to_vec()))]),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use subxt::sp_runtime::codec::Encode;
+
+    #[test]
+    fn decodes_the_fields_of_packed_cells() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let cell = StorageCell {
+            path: String::new(),
+            key: vec![0; 4],
+            fields: vec![
+                ("total".to_string(), 0),
+                ("owner".to_string(), 1),
+                ("limit".to_string(), 0),
+            ],
+            complete: true,
+        };
+        let value = (100u128, [7u8; 32], 5u128).encode();
+
+        let fields = decode_entry(&abi.types, &Entry::Cell(&cell, "limit"), &value).unwrap();
+        assert_eq!(fields.len(), 1);
+        assert_eq!(fields[0].0, "limit");
+        assert_eq!(fields[0].1.to_json(), serde_json::json!(5));
+
+        let fields = decode_entry(&abi.types, &Entry::Cell(&cell, ""), &value).unwrap();
+        assert_eq!(fields.len(), 3);
+    }
+}
@@ -0,0 +1,657 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Submits the Calls of the commands, wrapped in the Calls of other pallets as asked for by
+//! the [`ExtrinsicOpts`]: `--proxy` dispatches the Call on behalf of another account, and
+//! `--multisig` approves the Call as one of the signatories of a multisig account.
+
+use std::{
+    fmt::{Display, Formatter, Result as DisplayResult},
+    io::ErrorKind,
+};
+
+use anyhow::Result;
+use colored::Colorize;
+use jsonrpsee::client::RequestError;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::{blake2_128, blake2_256, twox_128, twox_64},
+    H256,
+};
+use subxt::{
+    sp_runtime::codec::{Decode, Encode, Error as CodecError, Input},
+    Call, Client, ClientBuilder, DefaultNodeRuntime, Encoded, ExtrinsicSuccess,
+};
+
+use super::{
+    chain::{AccountId, ChainTypes},
+    dry_run::hex,
+    nonce::Nonce,
+    reconnect, rpc, schedule,
+    signing::{sign_and_watch, Signer},
+};
+use crate::{abi::decode_hex, ExtrinsicOpts, WaitFor};
+
+/// The outcome of a submitted Call.
+#[derive(Debug)]
+pub(crate) enum Submitted<T> {
+    /// The Call was executed, with this outcome.
+    Executed(T),
+    /// The Call was not executed yet.
+    Pending(Pending),
+}
+
+impl<T> Submitted<T> {
+    pub fn map<U>(self, f: impl FnOnce(T) -> U) -> Submitted<U> {
+        match self {
+            Submitted::Executed(outcome) => Submitted::Executed(f(outcome)),
+            Submitted::Pending(pending) => Submitted::Pending(pending),
+        }
+    }
+
+    /// Returns the outcome of the executed Call, for the commands which need it to continue.
+    pub fn executed(self) -> Result<T> {
+        match self {
+            Submitted::Executed(outcome) => Ok(outcome),
+            Submitted::Pending(pending) => anyhow::bail!("{}", pending),
+        }
+    }
+}
+
+/// A submitted Call which was not executed yet.
+#[derive(Debug)]
+pub(crate) enum Pending {
+    /// The extrinsic of the Call was broadcast with `--wait-for broadcast`, without waiting
+    /// for it to be included in a block.
+    Broadcast(H256),
+    /// The Call was approved as a multisig Call, which awaits the approval of other
+    /// signatories.
+    Multisig(PendingMultisig),
+}
+
+impl Display for Pending {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            Pending::Broadcast(hash) => write!(
+                f,
+                "Submitted the extrinsic {:?} without waiting for it to be included in a block",
+                hash
+            ),
+            Pending::Multisig(multisig) => multisig.fmt(f),
+        }
+    }
+}
+
+/// A multisig Call which awaits the approval of other signatories.
+#[derive(Debug)]
+pub(crate) struct PendingMultisig {
+    pub multisig: AccountId32,
+    pub call_hash: H256,
+    pub approvals: usize,
+    pub threshold: u16,
+}
+
+impl Display for PendingMultisig {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        write!(
+            f,
+            "Approved the call {:?} of the multisig {} ({} of {} approvals)\n\tThe other \
+             signatories approve it with the same command, `--multisig` and `--threshold`",
+            self.call_hash,
+            self.multisig.to_ss58check(),
+            self.approvals,
+            self.threshold
+        )
+    }
+}
+
+/// Connects to the node of `--url`. Fails for chains whose types are not supported.
+pub(crate) async fn client(extrinsic_opts: &ExtrinsicOpts) -> Result<Client<DefaultNodeRuntime>> {
+    let cli = builder(&extrinsic_opts.node.url()?).build().await?;
+    if let Some(types) = ChainTypes::detect(&extrinsic_opts.node.url()?).await? {
+        types.check_supported()?;
+    }
+    Ok(cli)
+}
+
+/// Returns the builder of a client of the node at `url`.
+pub(crate) fn builder(url: &url::Url) -> ClientBuilder<DefaultNodeRuntime> {
+    ClientBuilder::<DefaultNodeRuntime>::new().set_url(url.to_string())
+}
+
+/// The ways commands touching the chain fail which exit with distinct codes, for scripts to
+/// react to without parsing the error.
+#[derive(Debug)]
+pub(crate) enum SubmitError {
+    /// The extrinsic was included in a block, but its Call failed.
+    Failed(String),
+    /// The extrinsic was not included in a block or finalized, or the node did not answer a
+    /// command without extrinsics, within `--timeout`.
+    Timeout(Option<WaitFor>, u64),
+    /// The extrinsic was dropped from the transaction pool, or its block was retracted.
+    NotIncluded(String),
+    /// The execution of `--dry-run` failed, or the contract reverted.
+    DryRunFailed,
+    /// Submitting the extrinsic was not confirmed.
+    Aborted(&'static str),
+}
+
+impl SubmitError {
+    /// Returns the code the process exits with after the error.
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            SubmitError::Failed(_) => 2,
+            SubmitError::Timeout(..) => 3,
+            SubmitError::NotIncluded(_) => 4,
+            SubmitError::DryRunFailed => 5,
+            SubmitError::Aborted(_) => 7,
+        }
+    }
+}
+
+impl Display for SubmitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        match self {
+            SubmitError::Failed(err) => write!(f, "The extrinsic failed: {}", err),
+            SubmitError::Timeout(Some(wait_for), timeout) => write!(
+                f,
+                "The extrinsic was not {} within {} seconds, it may still be",
+                match wait_for {
+                    WaitFor::InBlock => "included in a block",
+                    WaitFor::Finalized | WaitFor::Broadcast => "finalized",
+                },
+                timeout
+            ),
+            SubmitError::Timeout(None, timeout) => write!(
+                f,
+                "The node did not answer within {} seconds, it may be overloaded",
+                timeout
+            ),
+            SubmitError::NotIncluded(err) => {
+                write!(f, "The extrinsic was not included in a block: {}", err)
+            }
+            SubmitError::DryRunFailed => write!(f, "The dry run failed"),
+            SubmitError::Aborted(reason) => write!(f, "{}", reason),
+        }
+    }
+}
+
+/// Returns the code the process exits with after `err`: the code of a [`SubmitError`], 6 if
+/// the node could not be reached, and 1 otherwise.
+pub(crate) fn exit_code(err: &anyhow::Error) -> i32 {
+    if let Some(err) = err.downcast_ref::<SubmitError>() {
+        return err.exit_code();
+    }
+    let unreachable = err.chain().any(|cause| {
+        let rpc = match cause.downcast_ref::<subxt::Error>() {
+            Some(subxt::Error::Rpc(err)) => Some(err),
+            _ => cause.downcast_ref::<RequestError>(),
+        };
+        rpc.is_some_and(reconnect::is_disconnect)
+            || matches!(
+                cause
+                    .downcast_ref::<std::io::Error>()
+                    .map(std::io::Error::kind),
+                Some(
+                    ErrorKind::ConnectionRefused
+                        | ErrorKind::ConnectionReset
+                        | ErrorKind::ConnectionAborted
+                        | ErrorKind::NotConnected
+                        | ErrorKind::AddrNotAvailable
+                        | ErrorKind::TimedOut
+                )
+            )
+    });
+    if unreachable {
+        6
+    } else {
+        1
+    }
+}
+
+impl std::error::Error for SubmitError {}
+
+/// Prints the outcome of the `dry_run` of an extrinsic whose Call failed, with the error the
+/// contract returned and its debug message, before returning the error `err`.
+pub(crate) fn explain_failure(err: anyhow::Error, dry_run: Option<&str>) -> anyhow::Error {
+    if let (Some(SubmitError::Failed(_)), Some(dry_run)) =
+        (err.downcast_ref::<SubmitError>(), dry_run)
+    {
+        eprint!("{}\n{}", "The dry run before submitting:".bold(), dry_run);
+    }
+    err
+}
+
+/// Returns the account which executes the Calls submitted with `extrinsic_opts`: the real
+/// account of `--proxy`, the multisig account of `--multisig`, or the account signing the
+/// extrinsic.
+pub(crate) fn origin(extrinsic_opts: &ExtrinsicOpts) -> Result<AccountId> {
+    if let Some(real) = &extrinsic_opts.proxy {
+        Ok(real.clone().into())
+    } else if extrinsic_opts.multisig.is_empty() {
+        extrinsic_opts.account_id()
+    } else {
+        Ok(Multisig::new(extrinsic_opts)?.account_id().into())
+    }
+}
+
+/// Signs and submits `call`, wrapped as asked for by `extrinsic_opts`, and waits for it to be
+/// included in a block.
+pub(crate) async fn watch<C: Call<DefaultNodeRuntime> + Send + Sync>(
+    cli: &Client<DefaultNodeRuntime>,
+    extrinsic_opts: &ExtrinsicOpts,
+    call: C,
+) -> Result<Submitted<ExtrinsicSuccess<DefaultNodeRuntime>>> {
+    schedule::wait(extrinsic_opts).await?;
+    let signer = extrinsic_opts.signer()?;
+    let nonce = Nonce::reserve(extrinsic_opts, cli.genesis(), signer.account_id()).await?;
+    let value = nonce.as_ref().map(|nonce| nonce.value);
+    let submitted = watch_signed(cli, extrinsic_opts, &*signer, value, call).await;
+    if let (Err(_), Some(nonce)) = (&submitted, nonce) {
+        nonce.release();
+    }
+    submitted
+}
+
+async fn watch_signed<C: Call<DefaultNodeRuntime> + Send + Sync>(
+    cli: &Client<DefaultNodeRuntime>,
+    extrinsic_opts: &ExtrinsicOpts,
+    signer: &dyn Signer,
+    nonce: Option<u32>,
+    call: C,
+) -> Result<Submitted<ExtrinsicSuccess<DefaultNodeRuntime>>> {
+    if extrinsic_opts.proxy.is_none() && extrinsic_opts.multisig.is_empty() {
+        return sign_and_watch(cli, extrinsic_opts, signer, nonce, call).await;
+    }
+
+    let mut call = cli.encode(call)?;
+    if let Some(real) = &extrinsic_opts.proxy {
+        let proxy = ProxyCall {
+            real: real.clone(),
+            call,
+        };
+        if extrinsic_opts.multisig.is_empty() {
+            let submitted = sign_and_watch(cli, extrinsic_opts, signer, nonce, proxy).await?;
+            if let Submitted::Executed(events) = &submitted {
+                check_proxy_executed(events)?;
+            }
+            return Ok(submitted);
+        }
+        call = cli.encode(proxy)?;
+    }
+
+    let multisig = Multisig::new(extrinsic_opts)?;
+    let signatory = extrinsic_opts.account_id()?;
+    let as_multi = multisig
+        .approve(
+            &extrinsic_opts.node.url()?,
+            signatory.substrate(MULTISIG_FLAG)?,
+            call,
+        )
+        .await?;
+    let call_hash = as_multi.call_hash();
+    let approvals = as_multi.approvals;
+    let events = match sign_and_watch(cli, extrinsic_opts, signer, nonce, as_multi).await? {
+        Submitted::Executed(events) => events,
+        pending => return Ok(pending),
+    };
+    match events.find_event_raw(MULTISIG, "MultisigExecuted") {
+        Some(executed) => {
+            // the event ends with the `DispatchResult` of the Call
+            if executed.data.get(104) == Some(&1) {
+                let err = format!("the multisig call failed: {}", hex(&executed.data[105..]));
+                return Err(SubmitError::Failed(err).into());
+            }
+            if extrinsic_opts.proxy.is_some() {
+                check_proxy_executed(&events)?;
+            }
+            Ok(Submitted::Executed(events))
+        }
+        None => Ok(Submitted::Pending(Pending::Multisig(PendingMultisig {
+            multisig: multisig.account_id(),
+            call_hash,
+            approvals: approvals + 1,
+            threshold: multisig.threshold,
+        }))),
+    }
+}
+
+/// Returns the Call to submit for `call`, wrapped as asked for by `extrinsic_opts`.
+pub(crate) async fn wrap(
+    cli: &Client<DefaultNodeRuntime>,
+    extrinsic_opts: &ExtrinsicOpts,
+    mut call: Encoded,
+) -> Result<Encoded> {
+    if let Some(real) = &extrinsic_opts.proxy {
+        call = cli.encode(ProxyCall {
+            real: real.clone(),
+            call,
+        })?;
+    }
+    if extrinsic_opts.multisig.is_empty() {
+        return Ok(call);
+    }
+    let signatory = extrinsic_opts.account_id()?;
+    let as_multi = Multisig::new(extrinsic_opts)?
+        .approve(
+            &extrinsic_opts.node.url()?,
+            signatory.substrate(MULTISIG_FLAG)?,
+            call,
+        )
+        .await?;
+    Ok(cli.encode(as_multi)?)
+}
+
+/// Fails if the Call dispatched by `Proxy::proxy` failed, which does not fail the extrinsic.
+fn check_proxy_executed(events: &ExtrinsicSuccess<DefaultNodeRuntime>) -> Result<()> {
+    let executed = events
+        .find_event_raw(PROXY, "ProxyExecuted")
+        .ok_or_else(|| anyhow::anyhow!("Failed to find ProxyExecuted event"))?;
+    // the event holds the `DispatchResult` of the Call
+    if executed.data.first() == Some(&1) {
+        let err = format!("the proxied call failed: {}", hex(&executed.data[1..]));
+        return Err(SubmitError::Failed(err).into());
+    }
+    Ok(())
+}
+
+const PROXY: &str = "Proxy";
+
+/// The `Proxy::proxy` Call, dispatching `call` on behalf of `real`, which has to have added
+/// the signer as its proxy.
+struct ProxyCall {
+    real: AccountId32,
+    call: Encoded,
+}
+
+impl Encode for ProxyCall {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = self.real.encode();
+        // any type of proxy the signer is for `real`
+        None::<u8>.encode_to(&mut encoded);
+        // the boxed Call is encoded as is, without a length
+        encoded.extend(&self.call.0);
+        encoded
+    }
+}
+
+impl Call<DefaultNodeRuntime> for ProxyCall {
+    const MODULE: &'static str = PROXY;
+    const FUNCTION: &'static str = "proxy";
+}
+
+const MULTISIG: &str = "Multisig";
+
+/// The flag approving extrinsics as a signatory of a multisig, whose accounts are 32 bytes.
+const MULTISIG_FLAG: &str = "`--multisig`";
+
+/// The block number and the index in the block of the extrinsic which created a multisig
+/// operation.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+struct Timepoint {
+    height: u32,
+    index: u32,
+}
+
+impl Encode for Timepoint {
+    fn encode(&self) -> Vec<u8> {
+        (self.height, self.index).encode()
+    }
+}
+
+impl Decode for Timepoint {
+    fn decode<I: Input>(input: &mut I) -> Result<Self, CodecError> {
+        Ok(Timepoint {
+            height: Decode::decode(input)?,
+            index: Decode::decode(input)?,
+        })
+    }
+}
+
+/// The signatories and threshold of the multisig account of `--multisig`.
+struct Multisig {
+    signatories: Vec<AccountId32>,
+    threshold: u16,
+    max_weight: u64,
+}
+
+impl Multisig {
+    fn new(extrinsic_opts: &ExtrinsicOpts) -> Result<Self> {
+        let threshold = extrinsic_opts
+            .threshold
+            .ok_or_else(|| anyhow::anyhow!("`--multisig` requires `--threshold`"))?;
+        let mut signatories = extrinsic_opts.multisig.clone();
+        signatories.push(
+            extrinsic_opts
+                .account_id()?
+                .substrate(MULTISIG_FLAG)?
+                .clone(),
+        );
+        signatories.sort();
+        signatories.dedup();
+        if threshold < 2 || usize::from(threshold) > signatories.len() {
+            anyhow::bail!(
+                "The threshold has to be between 2 and the {} signatories",
+                signatories.len()
+            )
+        }
+        Ok(Multisig {
+            signatories,
+            threshold,
+            max_weight: extrinsic_opts.multisig_max_weight,
+        })
+    }
+
+    /// Returns the account of the multisig, derived from its signatories and threshold as by
+    /// the multisig pallet.
+    fn account_id(&self) -> AccountId32 {
+        let entropy = (b"modlpy/utilisuba", &self.signatories, self.threshold).encode();
+        AccountId32::from(blake2_256(&entropy))
+    }
+
+    /// Returns the `Multisig::as_multi` Call approving `call` as `signer`, which creates the
+    /// multisig operation if no other signatory approved it yet.
+    async fn approve(
+        &self,
+        url: &url::Url,
+        signer: &AccountId32,
+        call: Encoded,
+    ) -> Result<AsMultiCall> {
+        let call_hash = blake2_256(&call.0);
+        let operation = self.operation(url, &call_hash).await?;
+        if let Some(operation) = &operation {
+            if operation.approvals.contains(signer) {
+                anyhow::bail!(
+                    "{} already approved the call {}",
+                    signer.to_ss58check(),
+                    hex(&call_hash)
+                )
+            }
+        }
+        Ok(AsMultiCall {
+            threshold: self.threshold,
+            other_signatories: self
+                .signatories
+                .iter()
+                .filter(|signatory| *signatory != signer)
+                .cloned()
+                .collect(),
+            maybe_timepoint: operation.as_ref().map(|operation| operation.when),
+            call,
+            max_weight: self.max_weight,
+            approvals: operation.map_or(0, |operation| operation.approvals.len()),
+        })
+    }
+
+    /// Returns the pending operation of the multisig approving the Call `call_hash`, if any.
+    async fn operation(&self, url: &url::Url, call_hash: &[u8; 32]) -> Result<Option<Operation>> {
+        let multisig = self.account_id();
+        let multisig: &[u8] = multisig.as_ref();
+        let mut key = twox_128(MULTISIG.as_bytes()).to_vec();
+        key.extend(&twox_128(b"Multisigs"));
+        key.extend(&twox_64(multisig));
+        key.extend(multisig);
+        key.extend(&blake2_128(call_hash));
+        key.extend(call_hash);
+        let operation: Option<String> =
+            rpc::request(url, "state_getStorage", vec![hex(&key).into()]).await?;
+        let operation = match operation {
+            Some(operation) => decode_hex(&operation)?,
+            None => return Ok(None),
+        };
+        let types = ChainTypes::detect(url).await?.unwrap_or_default();
+        Ok(Some(Operation::decode(&mut &operation[..], &types)?))
+    }
+}
+
+/// A pending operation of a multisig, as stored by the multisig pallet.
+struct Operation {
+    when: Timepoint,
+    approvals: Vec<AccountId32>,
+}
+
+impl Operation {
+    /// Decodes the operation of a chain with `types`, whose deposit is one of its balances.
+    fn decode<I: Input>(input: &mut I, types: &ChainTypes) -> Result<Self, CodecError> {
+        let when = Decode::decode(input)?;
+        // the deposit and the depositor
+        let deposit_bytes = types.balance_bytes.unwrap_or(16);
+        input.read(&mut vec![0; deposit_bytes])?;
+        let _ = AccountId32::decode(input)?;
+        Ok(Operation {
+            when,
+            approvals: Decode::decode(input)?,
+        })
+    }
+}
+
+/// The `Multisig::as_multi` Call.
+struct AsMultiCall {
+    threshold: u16,
+    other_signatories: Vec<AccountId32>,
+    maybe_timepoint: Option<Timepoint>,
+    call: Encoded,
+    max_weight: u64,
+    /// The approvals of the operation before this one, not part of the Call.
+    approvals: usize,
+}
+
+impl AsMultiCall {
+    fn call_hash(&self) -> H256 {
+        H256(blake2_256(&self.call.0))
+    }
+}
+
+impl Encode for AsMultiCall {
+    fn encode(&self) -> Vec<u8> {
+        let mut encoded = self.threshold.encode();
+        self.other_signatories.encode_to(&mut encoded);
+        self.maybe_timepoint.encode_to(&mut encoded);
+        self.call.0.encode_to(&mut encoded);
+        // the Call is passed by every signatory instead of being stored
+        false.encode_to(&mut encoded);
+        self.max_weight.encode_to(&mut encoded);
+        encoded
+    }
+}
+
+impl Call<DefaultNodeRuntime> for AsMultiCall {
+    const MODULE: &'static str = MULTISIG;
+    const FUNCTION: &'static str = "as_multi";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use structopt::StructOpt;
+
+    const ALICE: &str = "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY";
+    const BOB: &str = "5FHneW46xGXgs5mUiveU4sbTyGBzmstUspZC92UhjJM694ty";
+    const CHARLIE: &str = "5FLSigC9HGRKVhB9FiEo4Y3koPsNmBmLJbpXg2mp1hXcS59Y";
+
+    fn multisig(from: &str, signatories: &str, threshold: &str) -> Result<Multisig> {
+        let extrinsic_opts = ExtrinsicOpts::from_iter(&[
+            "cargo-contract",
+            "--offline",
+            "unsigned.json",
+            "--from",
+            from,
+            "--multisig",
+            signatories,
+            "--threshold",
+            threshold,
+        ]);
+        Multisig::new(&extrinsic_opts)
+    }
+
+    #[test]
+    fn derives_the_multisig_account_of_all_signatories() {
+        let by_alice = multisig(ALICE, &format!("{},{}", BOB, CHARLIE), "2").unwrap();
+        let by_charlie = multisig(CHARLIE, &format!("{},{},{}", BOB, ALICE, CHARLIE), "2").unwrap();
+        assert_eq!(by_alice.signatories.len(), 3);
+        assert_eq!(by_alice.account_id(), by_charlie.account_id());
+
+        let threshold_3 = multisig(ALICE, &format!("{},{}", BOB, CHARLIE), "3").unwrap();
+        assert_ne!(by_alice.account_id(), threshold_3.account_id());
+
+        assert!(multisig(ALICE, BOB, "3").is_err());
+        assert!(multisig(ALICE, BOB, "1").is_err());
+    }
+
+    #[test]
+    fn proxies_on_behalf_of_the_real_account() {
+        let extrinsic_opts = ExtrinsicOpts::from_iter(&[
+            "cargo-contract",
+            "--offline",
+            "unsigned.json",
+            "--from",
+            ALICE,
+            "--proxy",
+            BOB,
+        ]);
+        let bob = AccountId32::from_ss58check(BOB).unwrap();
+        assert_eq!(origin(&extrinsic_opts).unwrap(), bob.clone().into());
+
+        let proxy = ProxyCall {
+            real: bob.clone(),
+            call: Encoded(vec![8, 3, 1]),
+        };
+        let mut expected = AsRef::<[u8]>::as_ref(&bob).to_vec();
+        expected.extend(&[0, 8, 3, 1]);
+        assert_eq!(proxy.encode(), expected);
+    }
+
+    #[test]
+    fn decodes_the_operations_of_chains_with_64_bit_balances() {
+        let bob = AccountId32::from_ss58check(BOB).unwrap();
+        let when = Timepoint {
+            height: 7,
+            index: 1,
+        };
+        let mut operation = when.encode();
+        operation.extend(&5u64.to_le_bytes());
+        operation.extend(AsRef::<[u8]>::as_ref(&bob));
+        operation.extend(vec![bob.clone()].encode());
+        let types = ChainTypes {
+            account_id_bytes: 32,
+            balance_bytes: Some(8),
+        };
+
+        let decoded = Operation::decode(&mut &operation[..], &types).unwrap();
+        assert_eq!(decoded.when, when);
+        assert_eq!(decoded.approvals, vec![bob]);
+    }
+}
@@ -14,10 +14,15 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
-use crate::{maybe_println, util, workspace::ManifestPath, Verbosity, VerbosityFlags};
+use super::node;
+use crate::{
+    maybe_println, util,
+    workspace::{Manifest, ManifestPath},
+    Verbosity, VerbosityFlags,
+};
 use anyhow::Result;
 use colored::Colorize;
-use std::{convert::TryFrom, path::PathBuf};
+use std::{convert::TryFrom, env, path::PathBuf};
 use structopt::StructOpt;
 
 /// Executes smart-contract tests off-chain by delegating to `cargo test`.
@@ -27,6 +32,13 @@ pub struct TestCommand {
     /// Path to the `Cargo.toml` of the contract to test.
     #[structopt(long, parse(from_os_str))]
     manifest_path: Option<PathBuf>,
+    /// Run the end-to-end tests against a `substrate-contracts-node` started for them.
+    ///
+    /// The node is taken from `$CONTRACTS_NODE`, the release installed with
+    /// `cargo contract node install` configured in the `[node]` section, or else found in the
+    /// `PATH`. Its url is exported to the tests as `$CONTRACTS_NODE_URL`.
+    #[structopt(long)]
+    e2e: bool,
     #[structopt(flatten)]
     verbosity: VerbosityFlags,
 }
@@ -36,7 +48,11 @@ impl TestCommand {
         let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
         let verbosity = TryFrom::<&VerbosityFlags>::try_from(&self.verbosity)?;
 
-        execute(&manifest_path, verbosity)
+        if self.e2e {
+            execute_e2e(&manifest_path, verbosity)
+        } else {
+            execute(&manifest_path, verbosity)
+        }
     }
 }
 
@@ -70,6 +86,42 @@ pub(crate) fn execute(manifest_path: &ManifestPath, verbosity: Verbosity) -> Res
     Ok(TestResult { stdout, verbosity })
 }
 
+/// Executes `cargo +nightly test` with a `substrate-contracts-node` running for the
+/// end-to-end tests, enabling the `e2e-tests` feature if the contract declares it.
+pub(crate) fn execute_e2e(
+    manifest_path: &ManifestPath,
+    verbosity: Verbosity,
+) -> Result<TestResult> {
+    util::assert_channel()?;
+    let program = node::locate()?;
+
+    maybe_println!(
+        verbosity,
+        " {} {}",
+        format!("[{}/{}]", 1, 2).bold(),
+        "Starting the contracts node".bright_green().bold()
+    );
+    let node = node::spawn(&program)?;
+    env::set_var(node::NODE_VAR, &program);
+    env::set_var(node::URL_VAR, node.url());
+
+    maybe_println!(
+        verbosity,
+        " {} {}",
+        format!("[{}/{}]", 2, 2).bold(),
+        "Running tests".bright_green().bold()
+    );
+    let features = if Manifest::new(manifest_path.clone())?.has_feature("e2e-tests") {
+        vec!["--features", "e2e-tests"]
+    } else {
+        Vec::new()
+    };
+    let stdout = util::invoke_cargo("test", features, manifest_path.directory(), verbosity)?;
+    drop(node);
+
+    Ok(TestResult { stdout, verbosity })
+}
+
 #[cfg(feature = "test-ci-only")]
 #[cfg(test)]
 mod tests_ci_only {
@@ -0,0 +1,270 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! The native token of a chain, to show balances in its units next to the plancks they are
+//! given in, e.g. `12345000000 (1.2345 ROC)`, and to give balances in its units, e.g. `1.5DOT`.
+
+use std::str::FromStr;
+
+use anyhow::{Context, Result};
+use serde_json::Value;
+use structopt::StructOpt;
+
+use super::rpc;
+use crate::NodeOpts;
+
+/// Converts a balance between plancks and units of the token of a chain.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "convert-balance")]
+pub struct ConvertBalanceCommand {
+    /// The balance in plancks, e.g. `1500000000000`, or in units of the token, e.g. `1.5DOT`
+    balance: Balance,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// The symbol of the token, given together with `--decimals` instead of fetching the
+    /// token from the node
+    #[structopt(long, requires = "decimals")]
+    symbol: Option<String>,
+    /// The decimals of the token, given together with `--symbol`
+    #[structopt(long, requires = "symbol")]
+    decimals: Option<u32>,
+}
+
+impl ConvertBalanceCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let token = match (&self.symbol, self.decimals) {
+            (Some(symbol), Some(decimals)) => Token {
+                symbol: symbol.clone(),
+                decimals,
+            },
+            _ => {
+                let url = self.node_opts.url()?;
+                self.node_opts
+                    .block_on(Token::fetch(&url))?
+                    .with_context(|| {
+                        format!(
+                            "The chain at {} does not report its token, give --symbol and \
+                             --decimals",
+                            url
+                        )
+                    })?
+            }
+        };
+        let plancks = match &self.balance {
+            Balance::Plancks(plancks) => *plancks,
+            Balance::Units { amount, symbol } => token.plancks(amount, symbol)?,
+        };
+        Ok(Some(format_balance(plancks, Some(&token))))
+    }
+}
+
+/// The symbol and decimals of the native token, as reported by the `system_properties` RPC.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Token {
+    pub symbol: String,
+    pub decimals: u32,
+}
+
+impl Token {
+    /// Returns the token of the chain at `url`, or `None` if the chain does not report it.
+    pub async fn fetch(url: &url::Url) -> anyhow::Result<Option<Token>> {
+        let properties: Value = rpc::request(url, "system_properties", Vec::new()).await?;
+        Ok(Self::from_properties(&properties))
+    }
+
+    /// Returns the token of the chain `properties`, of which chains with several tokens report
+    /// the native token first.
+    fn from_properties(properties: &Value) -> Option<Token> {
+        let first = |name: &str| match properties.get(name)? {
+            Value::Array(values) => values.first().cloned(),
+            value => Some(value.clone()),
+        };
+        Some(Token {
+            symbol: first("tokenSymbol")?.as_str()?.to_string(),
+            decimals: first("tokenDecimals")?.as_u64()? as u32,
+        })
+    }
+
+    /// Returns the plancks of the decimal `amount` of the token `symbol`, which has to be the
+    /// symbol of this token.
+    pub fn plancks(&self, amount: &str, symbol: &str) -> Result<u128> {
+        if !symbol.eq_ignore_ascii_case(&self.symbol) {
+            anyhow::bail!("The token of the chain is {}, not {}", self.symbol, symbol)
+        }
+        let (integer, fraction) = match amount.split_once('.') {
+            Some((integer, fraction)) => (integer, fraction),
+            None => (amount, ""),
+        };
+        if fraction.len() > self.decimals as usize {
+            anyhow::bail!(
+                "`{}` has more decimals than the {} of {}",
+                amount,
+                self.decimals,
+                self.symbol
+            )
+        }
+        let digits = format!(
+            "{}{:0<width$}",
+            integer,
+            fraction,
+            width = self.decimals as usize
+        );
+        digits
+            .parse::<u128>()
+            .ok()
+            .filter(|_| !integer.is_empty() || !fraction.is_empty())
+            .with_context(|| format!("Invalid amount `{}{}`", amount, symbol))
+    }
+
+    /// Formats `amount` plancks in units of the token without trailing zeros, e.g.
+    /// `1.2345 ROC`. Returns `None` if the token has more decimals than a `u128` holds.
+    pub fn format(&self, amount: u128) -> Option<String> {
+        let unit = 10u128.checked_pow(self.decimals)?;
+        let fraction = format!("{:0width$}", amount % unit, width = self.decimals as usize);
+        let fraction = fraction.trim_end_matches('0');
+        if fraction.is_empty() {
+            Some(format!("{} {}", amount / unit, self.symbol))
+        } else {
+            Some(format!("{}.{} {}", amount / unit, fraction, self.symbol))
+        }
+    }
+}
+
+/// Formats `amount` plancks, followed by their value in units of `token` if it is known.
+pub(crate) fn format_balance(amount: u128, token: Option<&Token>) -> String {
+    match token.and_then(|token| token.format(amount)) {
+        Some(units) => format!("{} ({})", amount, units),
+        None => amount.to_string(),
+    }
+}
+
+/// Fetches the token of the chain at `url` for showing balances, which are shown in plancks
+/// only if it is unknown.
+pub(crate) async fn fetch_or_none(url: &url::Url) -> Option<Token> {
+    Token::fetch(url)
+        .await
+        .map_err(|err| log::debug!("Fetching the token of the chain failed: {:?}", err))
+        .ok()
+        .flatten()
+}
+
+/// A balance given on the command line: plancks, e.g. `1500000000000`, or an amount of the
+/// token of the chain, e.g. `1.5DOT`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) enum Balance {
+    Plancks(u128),
+    Units { amount: String, symbol: String },
+}
+
+impl FromStr for Balance {
+    type Err = anyhow::Error;
+
+    fn from_str(input: &str) -> Result<Self> {
+        let input = input.trim();
+        let (amount, symbol) = input.split_at(
+            input
+                .find(|c: char| c.is_alphabetic())
+                .unwrap_or(input.len()),
+        );
+        let (amount, symbol) = (amount.trim(), symbol.trim());
+        if !symbol.is_empty() {
+            return Ok(Balance::Units {
+                amount: amount.to_string(),
+                symbol: symbol.to_string(),
+            });
+        }
+        amount.parse().map(Balance::Plancks).map_err(|_| {
+            anyhow::anyhow!(
+                "Invalid balance `{}`, expected plancks or an amount of the token like `1.5DOT`",
+                input
+            )
+        })
+    }
+}
+
+impl Balance {
+    /// Returns the plancks of the balance, fetching the token of the chain at `url` for an
+    /// amount of the token.
+    pub fn plancks(&self, url: &url::Url) -> Result<u128> {
+        match self {
+            Balance::Plancks(plancks) => Ok(*plancks),
+            Balance::Units { amount, symbol } => {
+                let token = async_std::task::block_on(Token::fetch(url))?.with_context(|| {
+                    format!(
+                        "The chain does not report its token, give `{}{}` in plancks",
+                        amount, symbol
+                    )
+                })?;
+                token.plancks(amount, symbol)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn formats_balances_in_token_units() {
+        let properties =
+            json!({ "ss58Format": 42, "tokenDecimals": [12, 10], "tokenSymbol": ["ROC", "DOT"] });
+        let token = Token::from_properties(&properties).unwrap();
+        assert_eq!(
+            token,
+            Token {
+                symbol: "ROC".to_string(),
+                decimals: 12
+            }
+        );
+
+        assert_eq!(
+            format_balance(1_234_500_000_000, Some(&token)),
+            "1234500000000 (1.2345 ROC)"
+        );
+        assert_eq!(
+            format_balance(3_000_000_000_000, Some(&token)),
+            "3000000000000 (3 ROC)"
+        );
+        assert_eq!(format_balance(100, Some(&token)), "100 (0.0000000001 ROC)");
+        assert_eq!(format_balance(100, None), "100");
+
+        let token = Token::from_properties(&json!({ "tokenDecimals": 0, "tokenSymbol": "UNIT" }));
+        assert_eq!(format_balance(7, token.as_ref()), "7 (7 UNIT)");
+        assert_eq!(Token::from_properties(&json!({})), None);
+    }
+
+    #[test]
+    fn parses_balances_in_token_units() {
+        let token = Token {
+            symbol: "DOT".to_string(),
+            decimals: 10,
+        };
+        let plancks = |input: &str| match input.parse::<Balance>()? {
+            Balance::Units { amount, symbol } => token.plancks(&amount, &symbol),
+            Balance::Plancks(plancks) => Ok(plancks),
+        };
+        assert_eq!(plancks("1.5DOT").unwrap(), 15_000_000_000);
+        assert_eq!(plancks("0.25 dot").unwrap(), 2_500_000_000);
+        assert_eq!(plancks("2DOT").unwrap(), 20_000_000_000);
+        assert_eq!(plancks("1000").unwrap(), 1000);
+        assert!(plancks("1.5").is_err());
+        assert!(plancks("1.5KSM").is_err());
+        assert!(plancks("0.00000000001DOT").is_err());
+        assert!(plancks("DOT").is_err());
+    }
+}
@@ -0,0 +1,269 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Upgrades the code of an instantiated contract with `cargo contract upgrade`.
+
+use std::{
+    ffi::OsStr,
+    path::{Path, PathBuf},
+};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde_json::Value;
+use sp_core::{hashing::blake2_256, H256};
+use structopt::StructOpt;
+
+use super::{
+    call::{encode_call, submit_call},
+    chain::{parse_account, AccountId},
+    confirm::Summary,
+    deploy::{code_stored, put_code},
+    dry_run,
+    extrinsic_output::{load_abi, ExtrinsicOutput},
+    gas::GasOpts,
+    metadata::{diff::diff_storage, read_json},
+    submit::Submitted,
+};
+use crate::{
+    abi::{decode_hex, ContractAbi},
+    ExtrinsicOpts,
+};
+
+/// Upgrades the code of a contract by calling its message which sets the code hash, uploading
+/// the new code first if it is not on chain yet.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "upgrade")]
+pub struct UpgradeCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// The account of the contract to upgrade
+    #[structopt(long, parse(try_from_str = parse_account))]
+    contract: AccountId,
+    /// The new code: a `.wasm` or `.contract` file, or the `0x` prefixed hash of code on chain
+    #[structopt(long)]
+    new_code: String,
+    /// The label of the message of the contract which sets its code hash
+    #[structopt(long, default_value = "set_code_hash")]
+    message: String,
+    /// The metadata or `.contract` file of the new code, whose storage layout is checked against
+    /// the one of the contract. Defaults to the metadata next to the `--new-code` file
+    #[structopt(long, parse(from_os_str))]
+    new_metadata: Option<PathBuf>,
+    #[structopt(flatten)]
+    gas: GasOpts,
+    /// Maximum balance to be charged for the storage the call adds, unbounded if not given
+    #[structopt(long)]
+    storage_deposit_limit: Option<u128>,
+    /// Path to the Cargo.toml of the contract whose metadata encodes the call
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file of the instantiated contract, instead of the built
+    /// metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+    /// Print the events and block of the extrinsic as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The code a contract is upgraded to.
+struct NewCode {
+    code_hash: H256,
+    /// The Wasm of the code, unless only its hash is known.
+    wasm: Option<Vec<u8>>,
+    /// The metadata of the code, if it was found.
+    abi: Option<ContractAbi>,
+    /// The language the code is written in, from its metadata.
+    language: Option<String>,
+}
+
+impl NewCode {
+    /// Loads the code of `--new-code`, with the metadata of `.contract` files or the one next to
+    /// `.wasm` files.
+    fn load(new_code: &str) -> Result<Self> {
+        if new_code.starts_with("0x") {
+            let code_hash = decode_hex(new_code)?;
+            if code_hash.len() != 32 {
+                anyhow::bail!("Code hash should be 32 bytes in length")
+            }
+            return Ok(NewCode {
+                code_hash: H256::from_slice(&code_hash),
+                wasm: None,
+                abi: None,
+                language: None,
+            });
+        }
+        let path = Path::new(new_code);
+        let (wasm, metadata) = if path.extension() == Some(OsStr::new("contract")) {
+            let bundle = read_json(path)?;
+            let wasm = bundle["source"]["wasm"]
+                .as_str()
+                .with_context(|| format!("{} contains no Wasm", path.display()))?;
+            (decode_hex(wasm)?, Some(bundle))
+        } else {
+            let wasm = std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()))?;
+            let metadata = path.with_file_name("metadata.json");
+            let metadata = if metadata.exists() {
+                Some(read_json(&metadata)?)
+            } else {
+                None
+            };
+            (wasm, metadata)
+        };
+        let (abi, language) = match metadata {
+            Some(metadata) => (
+                Some(ContractAbi::from_json(&metadata)?),
+                metadata["source"]["language"].as_str().map(String::from),
+            ),
+            None => (None, None),
+        };
+        Ok(NewCode {
+            code_hash: H256(blake2_256(&wasm)),
+            wasm: Some(wasm),
+            abi,
+            language,
+        })
+    }
+}
+
+impl UpgradeCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Calling the contract requires the metadata of the contract")?;
+        let spec = abi.message(&self.message)?;
+        let new_code = NewCode::load(&self.new_code)?;
+        let code_hash = new_code.code_hash;
+        let new_abi = match &self.new_metadata {
+            Some(path) => Some(ContractAbi::load(path)?),
+            None => new_code.abi,
+        };
+        self.check_storage_layout(&abi, new_abi.as_ref());
+        let data = abi.encode_call(spec, &[Value::String(format!("{:?}", code_hash))])?;
+
+        let stored = async_std::task::block_on(code_stored(&self.extrinsic_opts, code_hash))?;
+        if !stored {
+            let wasm = new_code.wasm.with_context(|| {
+                format!(
+                    "The code {:?} is not on chain, pass its `.wasm` or `.contract` file",
+                    code_hash
+                )
+            })?;
+            match put_code(&self.extrinsic_opts, wasm, new_code.language.as_deref())? {
+                Submitted::Executed((_, Some(output))) => eprintln!(
+                    "Uploaded the code {:?} in block {:?}",
+                    code_hash, output.block_hash
+                ),
+                Submitted::Executed((_, None)) => (),
+                Submitted::Pending(pending) => {
+                    return Ok(Some(format!(
+                        "Submitted the upload of the new code, the contract is not upgraded \
+                        yet: {}",
+                        pending
+                    )))
+                }
+            }
+        }
+
+        // the code must be on chain for the dry run of the call setting it
+        let required_gas = match dry_run::call(
+            &self.extrinsic_opts,
+            &self.contract,
+            0,
+            self.gas.dry_run_limit(),
+            self.storage_deposit_limit,
+            &data,
+            None,
+        ) {
+            Ok(result) => Some(result.required_gas()),
+            Err(err) => {
+                log::debug!("Dry run before the upgrade failed: {:?}", err);
+                None
+            }
+        };
+        let gas_limit = self.gas.limit(required_gas);
+        Summary::new("Upgrade the contract")
+            .line("Contract", self.contract.to_string())
+            .line("Code hash", format!("{:?}", code_hash))
+            .line("Message", &self.message)
+            .line("Gas limit", gas_limit)
+            .confirm(&self.extrinsic_opts, |cli| {
+                encode_call(
+                    cli,
+                    &self.contract,
+                    0,
+                    gas_limit,
+                    self.storage_deposit_limit,
+                    &data,
+                )
+            })?;
+        let submitted = submit_call(
+            &self.extrinsic_opts,
+            &self.contract,
+            0,
+            gas_limit,
+            self.storage_deposit_limit,
+            &data,
+            Some(&abi),
+        )?;
+        let output = match submitted {
+            Submitted::Executed(output) => ExtrinsicOutput {
+                code_hash: Some(code_hash),
+                gas_limit: Some(gas_limit),
+                ..output
+            },
+            Submitted::Pending(pending) => return Ok(Some(pending.to_string())),
+        };
+        if self.output_json {
+            return Ok(Some(output.to_json()?));
+        }
+        Ok(Some(format!(
+            "Upgraded the contract to the code {:?} in block {:?}{}{}{}",
+            code_hash,
+            output.block_hash,
+            output.display_explorer_links(&self.extrinsic_opts)?,
+            output.display_gas(),
+            output.display_contract_events()
+        )))
+    }
+
+    /// Warns about the changes of the storage layout which break reading the storage of the
+    /// contract with the new code.
+    fn check_storage_layout(&self, abi: &ContractAbi, new_abi: Option<&ContractAbi>) {
+        let warning = "warning:".yellow().bold();
+        let new_abi = match new_abi {
+            Some(new_abi) => new_abi,
+            None => {
+                eprintln!(
+                    "{} The storage layout of the new code is not checked, pass its metadata \
+                    with --new-metadata",
+                    warning
+                );
+                return;
+            }
+        };
+        for change in diff_storage(abi, new_abi)
+            .iter()
+            .filter(|change| change.breaking)
+        {
+            eprintln!(
+                "{} The new code is incompatible with the storage of the contract, {}: {}",
+                warning, change.item, change.description
+            );
+        }
+    }
+}
@@ -0,0 +1,107 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+use std::path::PathBuf;
+
+use anyhow::Result;
+use sp_core::{hashing::blake2_256, H256};
+use subxt::contracts::PutCodeCall;
+
+use super::{
+    deploy::{contract_language, load_contract_code, put_code},
+    extrinsic_output::ExtrinsicOutput,
+    offline,
+    submit::Submitted,
+};
+use crate::ExtrinsicOpts;
+
+/// Uploads the contract code without instantiating it, so that it can be instantiated many
+/// times with `cargo contract instantiate --code-hash`.
+///
+/// Creates an extrinsic with the `Contracts::put_code` Call, which is the `upload_code` of the
+/// contracts pallet version targeted by `subxt`, and returns the code hash of the stored code
+/// and the outcome of the extrinsic, `None` if the code was already stored.
+pub(crate) fn execute_upload(
+    extrinsic_opts: &ExtrinsicOpts,
+    contract_wasm_path: Option<&PathBuf>,
+) -> Result<Submitted<(H256, Option<ExtrinsicOutput>)>> {
+    let code = load_contract_code(contract_wasm_path)?;
+    let language = contract_language(contract_wasm_path)?;
+    put_code(extrinsic_opts, code, language.as_deref())
+}
+
+/// Writes the unsigned extrinsic uploading the contract code to the file of `--offline`,
+/// returning the code hash it will be stored under and where the extrinsic was written.
+pub(crate) fn offline_upload(
+    extrinsic_opts: &ExtrinsicOpts,
+    contract_wasm_path: Option<&PathBuf>,
+) -> Result<(H256, String)> {
+    let code = load_contract_code(contract_wasm_path)?;
+    let written = offline::write_unsigned(extrinsic_opts, |cli| {
+        cli.encode(PutCodeCall {
+            _runtime: Default::default(),
+            code: &code,
+        })
+    })?;
+    Ok((H256(blake2_256(&code)), written))
+}
+
+/// The code hash and size of contract code which would be uploaded.
+pub(crate) struct UploadDryRun {
+    pub code_hash: H256,
+    pub code_len: usize,
+}
+
+/// Returns the code hash the contract code would be stored under, without submitting it.
+///
+/// The code hash is the blake2 hash of the code, as computed by the contracts pallet.
+pub(crate) fn dry_run_upload(contract_wasm_path: Option<&PathBuf>) -> Result<UploadDryRun> {
+    let code = load_contract_code(contract_wasm_path)?;
+    Ok(UploadDryRun {
+        code_hash: H256(blake2_256(&code)),
+        code_len: code.len(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use crate::{cmd::metadata::blake2_hash, util::tests::with_tmp_dir};
+
+    const CONTRACT: &str = r#"
+(module
+    (func (export "call"))
+    (func (export "deploy"))
+)
+"#;
+
+    #[test]
+    fn dry_run_returns_code_hash() {
+        with_tmp_dir(|path| {
+            let wasm = wabt::wat2wasm(CONTRACT).expect("invalid wabt");
+            let wasm_path = path.join("test.wasm");
+            fs::write(&wasm_path, &wasm)?;
+
+            let dry_run = super::dry_run_upload(Some(&wasm_path))?;
+
+            // the same hash as recorded in the metadata of the contract
+            assert_eq!(dry_run.code_hash.0, blake2_hash(&wasm).0);
+            assert_eq!(dry_run.code_len, wasm.len());
+            Ok(())
+        })
+    }
+}
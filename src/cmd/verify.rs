@@ -0,0 +1,168 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Verifies that an instantiated contract runs the code of the local project with
+//! `cargo contract verify`.
+
+use std::{convert::TryFrom, ffi::OsStr, path::PathBuf};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use sp_core::{
+    crypto::{AccountId32, Ss58Codec},
+    hashing::blake2_256,
+    H256,
+};
+use structopt::StructOpt;
+
+use super::{
+    build::build_release_code, chain::parse_account_id, info::contract::ContractInfoOfStore,
+    metadata::read_json, submit,
+};
+use crate::{
+    abi::decode_hex, crate_metadata::CrateMetadata, workspace::ManifestPath, NodeOpts, Verbosity,
+};
+
+/// Compares the code hash of an instantiated contract with the one of the local code.
+///
+/// The local code is rebuilt in release mode, unless a bundle is given or `--no-build` is set.
+/// This is a quick check without a reproducible build, so the local toolchain has to match the
+/// one the contract was built with.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "verify")]
+pub struct VerifyCommand {
+    /// The account of the contract
+    #[structopt(long, parse(try_from_str = parse_account_id))]
+    contract: AccountId32,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Path to the Cargo.toml of the contract project to verify
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The `.contract` or `.wasm` file to verify instead of the code of the project
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    bundle: Option<PathBuf>,
+    /// Verify the already built Wasm of the project instead of rebuilding it
+    #[structopt(long, conflicts_with = "bundle")]
+    no_build: bool,
+    /// Print the outcome as JSON
+    #[structopt(long)]
+    output_json: bool,
+}
+
+/// The outcome of verifying a contract.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Verification {
+    contract: String,
+    /// The code hash of the contract on chain.
+    code_hash: H256,
+    /// The code hash of the local code.
+    local_code_hash: H256,
+    matches: bool,
+}
+
+impl VerifyCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let local_code_hash = H256(blake2_256(&self.local_code()?));
+        // the build of the local code is not bounded by the timeout
+        let contract = self
+            .node_opts
+            .block_on(async {
+                let cli = submit::builder(&self.node_opts.url()?).build().await?;
+                let contract = cli
+                    .fetch(
+                        &ContractInfoOfStore {
+                            contract: &self.contract,
+                        },
+                        None,
+                    )
+                    .await?;
+                Ok::<_, anyhow::Error>(contract)
+            })?
+            .with_context(|| {
+                format!(
+                    "No contract is instantiated at {}",
+                    self.contract.to_ss58check()
+                )
+            })?;
+
+        let verification = Verification {
+            contract: self.contract.to_ss58check(),
+            code_hash: contract.code_hash,
+            local_code_hash,
+            matches: contract.code_hash == local_code_hash,
+        };
+        if self.output_json {
+            println!("{}", serde_json::to_string_pretty(&verification)?);
+        } else {
+            println!(
+                "{} {:?}\n{} {:?}",
+                "Code hash on chain:".bold(),
+                verification.code_hash,
+                "Local code hash:".bold(),
+                verification.local_code_hash
+            );
+        }
+        if !verification.matches {
+            anyhow::bail!(
+                "The local code differs from the code of the contract {}",
+                verification.contract
+            )
+        }
+        if self.output_json {
+            return Ok(None);
+        }
+        Ok(Some(format!(
+            "{}",
+            "The local code matches the code of the contract"
+                .green()
+                .bold()
+        )))
+    }
+
+    /// Returns the Wasm of `--bundle`, or of the project, which is rebuilt unless `--no-build`
+    /// is set.
+    fn local_code(&self) -> Result<Vec<u8>> {
+        if let Some(path) = &self.bundle {
+            if path.extension() == Some(OsStr::new("contract")) {
+                let bundle = read_json(path)?;
+                let wasm = bundle["source"]["wasm"]
+                    .as_str()
+                    .with_context(|| format!("{} contains no Wasm", path.display()))?;
+                return decode_hex(wasm);
+            }
+            return std::fs::read(path)
+                .with_context(|| format!("Failed to read {}", path.display()));
+        }
+
+        let manifest_path = ManifestPath::try_from(self.manifest_path.as_ref())?;
+        let wasm_path = if self.no_build {
+            CrateMetadata::collect(&manifest_path)?.dest_wasm
+        } else {
+            let verbosity = if self.output_json {
+                Verbosity::Quiet
+            } else {
+                Verbosity::Default
+            };
+            build_release_code(manifest_path, verbosity)?
+                .dest_wasm
+                .context("The build produced no Wasm")?
+        };
+        std::fs::read(&wasm_path).with_context(|| format!("Failed to read {}", wasm_path.display()))
+    }
+}
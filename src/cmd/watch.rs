@@ -0,0 +1,199 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Streams the events of a contract with `cargo contract watch`, a lightweight indexer for
+//! development: new blocks are polled for the `Contracts::ContractEmitted` events of the
+//! contract, which are decoded with its metadata.
+
+use std::{path::PathBuf, time::Duration};
+
+use anyhow::{Context, Result};
+use colored::Colorize;
+use serde::Serialize;
+use sp_core::H256;
+use structopt::StructOpt;
+
+use super::{
+    chain::{parse_account, AccountId},
+    events::{Raw, RuntimeEvents},
+    extrinsic_output::{decode_contract_event, load_abi, ContractEventOutput},
+    rpc,
+};
+use crate::{abi::ContractAbi, NodeOpts};
+
+/// How long to wait for new blocks.
+const POLL_INTERVAL: Duration = Duration::from_secs(3);
+
+/// Streams the events emitted by a contract, decoded with its metadata.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "watch")]
+pub struct WatchCommand {
+    /// The account of the contract whose events to stream
+    #[structopt(long, parse(try_from_str = parse_account))]
+    contract: AccountId,
+    #[structopt(flatten)]
+    node_opts: NodeOpts,
+    /// Stream the events of finalized blocks only, which are never retracted
+    #[structopt(long)]
+    finalized: bool,
+    /// The number of the first block to stream the events of, instead of the next block
+    #[structopt(long)]
+    from_block: Option<u64>,
+    /// Print an event per line as JSON
+    #[structopt(long)]
+    output_json: bool,
+    /// Path to the Cargo.toml of the contract whose metadata decodes the events
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+}
+
+/// An event of the contract and the block it was emitted in.
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct WatchedEvent<'a> {
+    block_number: u64,
+    block_hash: H256,
+    #[serde(flatten)]
+    event: &'a ContractEventOutput,
+}
+
+impl WatchCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+            .context("Decoding the events requires the metadata of the contract")?;
+        async_std::task::block_on(async {
+            // the events are decoded with the metadata of the runtime of the best block
+            let (runtime, mut next) = self
+                .node_opts
+                .timeout(async {
+                    let best: H256 =
+                        rpc::request(&self.node_opts.url()?, "chain_getBlockHash", vec![]).await?;
+                    let runtime = RuntimeEvents::fetch(&self.node_opts.url()?, &best).await?;
+                    let next = match self.from_block {
+                        Some(from_block) => from_block,
+                        None => self.head().await? + 1,
+                    };
+                    Ok((runtime, next))
+                })
+                .await?;
+            if !self.output_json {
+                eprintln!(
+                    "{} the events of {} from block #{}",
+                    "Watching".green().bold(),
+                    self.contract,
+                    next
+                );
+            }
+            loop {
+                let head = self.node_opts.timeout(self.head()).await?;
+                while next <= head {
+                    self.node_opts
+                        .timeout(self.stream_block(next, &runtime, &abi))
+                        .await?;
+                    next += 1;
+                }
+                async_std::task::sleep(POLL_INTERVAL).await;
+            }
+        })
+    }
+
+    /// Returns the number of the best block, or of the finalized block with `--finalized`.
+    async fn head(&self) -> Result<u64> {
+        if self.finalized {
+            let finalized: H256 =
+                rpc::request(&self.node_opts.url()?, "chain_getFinalizedHead", vec![]).await?;
+            rpc::block_number(&self.node_opts.url()?, Some(&finalized)).await
+        } else {
+            rpc::block_number(&self.node_opts.url()?, None).await
+        }
+    }
+
+    /// Prints the events of the contract emitted in the block `number`.
+    async fn stream_block(
+        &self,
+        number: u64,
+        runtime: &RuntimeEvents,
+        abi: &ContractAbi,
+    ) -> Result<()> {
+        let hash: H256 = rpc::request(
+            &self.node_opts.url()?,
+            "chain_getBlockHash",
+            vec![number.into()],
+        )
+        .await?;
+        let events = rpc::events(&self.node_opts.url()?, &hash).await?;
+        for (_, event) in runtime.decode(&events)? {
+            let event = match event {
+                Raw::Event(event) => event,
+                Raw::Error(_) => continue,
+            };
+            let event = match decode_contract_event(&event, &self.contract, abi) {
+                Some(event) => event,
+                None => continue,
+            };
+            if self.output_json {
+                let watched = WatchedEvent {
+                    block_number: number,
+                    block_hash: hash,
+                    event: &event,
+                };
+                println!("{}", serde_json::to_string(&watched)?);
+            } else {
+                println!("{} {}", format!("#{}", number).bold(), event.decoded);
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+    use serde_json::json;
+    use subxt::sp_runtime::codec::Encode;
+
+    #[test]
+    fn serializes_an_event_per_line() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let mut data = vec![0, 0];
+        data.extend(5u128.encode());
+        let event = ContractEventOutput {
+            contract: "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY".to_string(),
+            decoded: abi.decode_event(&data).unwrap(),
+        };
+        let watched = WatchedEvent {
+            block_number: 7,
+            block_hash: H256::repeat_byte(1),
+            event: &event,
+        };
+
+        let line = serde_json::to_string(&watched).unwrap();
+        assert!(!line.contains('\n'));
+        assert_eq!(
+            serde_json::from_str::<serde_json::Value>(&line).unwrap(),
+            json!({
+                "blockNumber": 7,
+                "blockHash": format!("0x{}", "01".repeat(32)),
+                "contract": "5GrwvaEF5zXb26Fz9rcQpDWS57CtERHpNehXCPcNoHGKutQY",
+                "event": { "name": "Deposited", "fields": { "from": "None", "value": 5 } },
+            })
+        );
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Calls contracts on sibling parachains by sending an XCM `Transact` with
+//! `cargo contract xcm-call`, which is experimental.
+//!
+//! The message withdraws the fee asset from the account the destination chain derives for the
+//! sender on this chain, buys execution with it, dispatches the `Contracts::call` and deposits
+//! the surplus to the account of the sender on the destination chain.
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use codec::{Compact, Encode};
+use sp_core::crypto::AccountId32;
+use structopt::StructOpt;
+use subxt::{Call, DefaultNodeRuntime};
+
+use super::{
+    call::encode_call,
+    chain::{parse_account, AccountId},
+    confirm::Summary,
+    dry_run::Weight,
+    encode::encode_args,
+    extrinsic_output::load_abi,
+    gas::parse_weight,
+    submit::{self, Submitted},
+};
+use crate::{ExtrinsicOpts, HexData};
+
+/// The pallet sending XCM messages on behalf of accounts.
+const XCM_PALLET: &str = "PolkadotXcm";
+
+/// Calls a contract on a sibling parachain with an XCM `Transact`.
+#[derive(Debug, StructOpt)]
+#[structopt(name = "xcm-call")]
+pub struct XcmCallCommand {
+    #[structopt(flatten)]
+    extrinsic_opts: ExtrinsicOpts,
+    /// The id of the sibling parachain the contract is instantiated on
+    #[structopt(long)]
+    dest_para_id: u32,
+    /// Websockets url of a node of the destination parachain, whose runtime encodes the call
+    #[structopt(long, parse(try_from_str))]
+    dest_url: url::Url,
+    /// The account of the contract to call on the destination parachain
+    #[structopt(long, parse(try_from_str = parse_account))]
+    contract: AccountId,
+    /// Hex encoded data to call a contract message, starting with its selector
+    #[structopt(long, required_unless = "message", conflicts_with = "message")]
+    data: Option<HexData>,
+    /// The label of the message to call, whose arguments are encoded with the metadata
+    #[structopt(long)]
+    message: Option<String>,
+    /// The arguments of the message, as JSON or as plain strings
+    #[structopt(long, requires = "message")]
+    args: Vec<String>,
+    /// The value to transfer to the contract, in plancks of the destination parachain
+    #[structopt(long, default_value = "0")]
+    value: u128,
+    /// The gas limit of the call as `<ref time>` or `<ref time>,<proof size>`. There is no
+    /// dry run on the destination parachain to derive it from
+    #[structopt(long, parse(try_from_str = parse_weight))]
+    gas: Weight,
+    /// Maximum balance to be charged for the storage the call adds, unbounded if not given
+    #[structopt(long)]
+    storage_deposit_limit: Option<u128>,
+    /// The amount of the fee asset, in its plancks, to buy execution on the destination
+    /// parachain with
+    #[structopt(long)]
+    xcm_fee: u128,
+    /// The asset paying for the execution: `relay`, the token of the relay chain, or `native`,
+    /// the token of the destination parachain
+    #[structopt(long, default_value = "relay", value_name = "relay | native")]
+    xcm_fee_asset: FeeAsset,
+    /// The maximum weight of dispatching the call, the gas limit plus 25 percent by default
+    #[structopt(long, parse(try_from_str = parse_weight))]
+    transact_weight: Option<Weight>,
+    /// Path to the Cargo.toml of the contract whose metadata encodes the arguments
+    #[structopt(long, parse(from_os_str))]
+    manifest_path: Option<PathBuf>,
+    /// The metadata or `.contract` file to use instead of the built metadata
+    #[structopt(long, parse(from_os_str), conflicts_with = "manifest-path")]
+    metadata: Option<PathBuf>,
+}
+
+/// The asset paying for the execution of the message on the destination parachain.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum FeeAsset {
+    Relay,
+    Native,
+}
+
+impl std::str::FromStr for FeeAsset {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "relay" => Ok(FeeAsset::Relay),
+            "native" => Ok(FeeAsset::Native),
+            _ => Err(format!("Unknown fee asset `{}`", input)),
+        }
+    }
+}
+
+/// The `PolkadotXcm::send` Call with the SCALE encoded destination and message, whose types
+/// `subxt` does not know.
+struct XcmSendCall<'a> {
+    dest: &'a [u8],
+    message: &'a [u8],
+}
+
+impl subxt::sp_runtime::codec::Encode for XcmSendCall<'_> {
+    fn encode(&self) -> Vec<u8> {
+        [self.dest, self.message].concat()
+    }
+}
+
+impl Call<DefaultNodeRuntime> for XcmSendCall<'_> {
+    const MODULE: &'static str = XCM_PALLET;
+    const FUNCTION: &'static str = "send";
+}
+
+impl XcmCallCommand {
+    pub fn exec(&self) -> Result<Option<String>> {
+        let data = match (&self.data, &self.message) {
+            (Some(data), _) => data.0.clone(),
+            (None, label) => {
+                let abi = load_abi(self.manifest_path.as_ref(), self.metadata.as_ref())?
+                    .context("Encoding the arguments requires the metadata of the contract")?;
+                let label = label.as_deref().context("Missing `--message`")?;
+                encode_args(&abi, abi.message(label)?, &self.args, None)?
+            }
+        };
+        // the call is encoded with the pallet and call indices of the destination runtime
+        let call = async_std::task::block_on(async {
+            let cli = submit::builder(&self.dest_url).build().await?;
+            let call = encode_call(
+                &cli,
+                &self.contract,
+                self.value,
+                self.gas,
+                self.storage_deposit_limit,
+                &data,
+            )?;
+            Ok::<_, anyhow::Error>(call.0)
+        })?;
+        let transact_weight = self.transact_weight.unwrap_or(Weight {
+            ref_time: self.gas.ref_time.saturating_add(self.gas.ref_time / 4),
+            proof_size: Some(self.gas.proof_size.map_or(0, |size| size + size / 4)),
+        });
+        let dest = sibling(self.dest_para_id);
+        let origin = submit::origin(&self.extrinsic_opts)?;
+        let message = transact(
+            &call,
+            self.xcm_fee_asset,
+            self.xcm_fee,
+            transact_weight,
+            origin.substrate("XCM")?,
+        );
+        let send = || XcmSendCall {
+            dest: &dest,
+            message: &message,
+        };
+
+        Summary::new("Send an XCM Transact calling the contract")
+            .line("Destination", format!("parachain {}", self.dest_para_id))
+            .line("Contract", self.contract.to_string())
+            .line("Data", format!("0x{}", hex::encode(&data)))
+            .line("Gas limit", self.gas)
+            .line(
+                "Execution fee",
+                match self.xcm_fee_asset {
+                    FeeAsset::Relay => format!("{} plancks of the relay chain token", self.xcm_fee),
+                    FeeAsset::Native => {
+                        format!("{} plancks of the destination token", self.xcm_fee)
+                    }
+                },
+            )
+            .confirm(&self.extrinsic_opts, |cli| cli.encode(send()))?;
+        let events = async_std::task::block_on(async {
+            let cli = submit::client(&self.extrinsic_opts).await?;
+            submit::watch(&cli, &self.extrinsic_opts, send()).await
+        })?;
+        let events = match events {
+            Submitted::Executed(events) => events,
+            Submitted::Pending(pending) => return Ok(Some(pending.to_string())),
+        };
+        events
+            .find_event_raw(XCM_PALLET, "Sent")
+            .context("Failed to find the Sent event")?;
+        Ok(Some(format!(
+            "Sent the message to parachain {} in block {:?}. Whether the contract was called is \
+            only known from the events of the destination parachain",
+            self.dest_para_id, events.block
+        )))
+    }
+}
+
+/// Encodes the `VersionedMultiLocation` of the sibling parachain `para_id`, in XCM v3:
+/// `{ parents: 1, interior: X1(Parachain(para_id)) }`.
+fn sibling(para_id: u32) -> Vec<u8> {
+    let mut encoded = vec![3, 1, 1, 0];
+    Compact(para_id).encode_to(&mut encoded);
+    encoded
+}
+
+/// Encodes the `VersionedXcm` v3 message dispatching `call` on the destination, paid with
+/// `fee` of `fee_asset`: `WithdrawAsset`, `BuyExecution`, `Transact`, `RefundSurplus` and
+/// `DepositAsset` of the surplus to `beneficiary`.
+fn transact(
+    call: &[u8],
+    fee_asset: FeeAsset,
+    fee: u128,
+    weight: Weight,
+    beneficiary: &AccountId32,
+) -> Vec<u8> {
+    // the `MultiLocation` of the fee asset, as seen from the destination
+    let location: &[u8] = match fee_asset {
+        FeeAsset::Relay => &[1, 0],
+        FeeAsset::Native => &[0, 0],
+    };
+    let mut asset = vec![0];
+    asset.extend_from_slice(location);
+    asset.push(0);
+    Compact(fee).encode_to(&mut asset);
+
+    let mut encoded = vec![3];
+    Compact(5u32).encode_to(&mut encoded);
+    // WithdrawAsset(vec![asset])
+    encoded.push(0);
+    Compact(1u32).encode_to(&mut encoded);
+    encoded.extend_from_slice(&asset);
+    // BuyExecution { fees: asset, weight_limit: Unlimited }
+    encoded.push(19);
+    encoded.extend_from_slice(&asset);
+    encoded.push(0);
+    // Transact { origin_kind: SovereignAccount, require_weight_at_most, call }
+    encoded.push(6);
+    encoded.push(1);
+    Compact(weight.ref_time).encode_to(&mut encoded);
+    Compact(weight.proof_size.unwrap_or_default()).encode_to(&mut encoded);
+    call.encode_to(&mut encoded);
+    // RefundSurplus
+    encoded.push(20);
+    // DepositAsset { assets: Wild(AllCounted(1)), beneficiary: X1(AccountId32) }
+    encoded.push(13);
+    encoded.extend_from_slice(&[1, 2]);
+    Compact(1u32).encode_to(&mut encoded);
+    encoded.extend_from_slice(&[0, 1, 1, 0]);
+    encoded.extend_from_slice(beneficiary.as_ref());
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encodes_the_transact_message() {
+        assert_eq!(sibling(2000), vec![3, 1, 1, 0, 0x41, 0x1f]);
+
+        let weight = Weight {
+            ref_time: 1,
+            proof_size: Some(2),
+        };
+        let beneficiary = AccountId32::from([7; 32]);
+        let message = transact(&[0xaa, 0xbb], FeeAsset::Relay, 100, weight, &beneficiary);
+        #[rustfmt::skip]
+        let mut expected = vec![
+            3, 20,
+            0, 4, 0, 1, 0, 0, 0x91, 0x01,
+            19, 0, 1, 0, 0, 0x91, 0x01, 0,
+            6, 1, 4, 8, 8, 0xaa, 0xbb,
+            20,
+            13, 1, 2, 4, 0, 1, 1, 0,
+        ];
+        expected.extend_from_slice(&[7; 32]);
+        assert_eq!(message, expected);
+    }
+}
@@ -0,0 +1,318 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates JSON Schemas of the arguments of the constructors and messages of a contract.
+//!
+//! The schemas describe the JSON representation of the arguments also used by the
+//! TypeScript client: numbers which may exceed `Number.MAX_SAFE_INTEGER` may be given as
+//! decimal strings, bytes as `0x` prefixed hex strings and enum variants as their name or
+//! an object with the variant as its single key.
+
+use super::type_names;
+use crate::abi::{ContractAbi, Field, MessageSpec, Primitive, Type, TypeDef};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+
+/// The version of JSON Schema the schemas conform to.
+const SCHEMA: &str = "http://json-schema.org/draft-07/schema#";
+
+/// The nesting depth after which types are no longer expanded, guarding against recursion.
+const MAX_DEPTH: usize = 16;
+
+/// Returns a schema of the arguments of every constructor and message, by label.
+pub fn generate(abi: &ContractAbi) -> Result<Value> {
+    let names = type_names(abi);
+    let gen = Generator { abi, names: &names };
+    let schemas = |specs: &[MessageSpec]| -> Result<Map<String, Value>> {
+        specs
+            .iter()
+            .map(|spec| Ok((spec.label.clone(), gen.args_schema(spec)?)))
+            .collect()
+    };
+    Ok(json!({
+        "constructors": schemas(&abi.constructors)?,
+        "messages": schemas(&abi.messages)?,
+    }))
+}
+
+struct Generator<'a> {
+    abi: &'a ContractAbi,
+    names: &'a BTreeMap<u32, String>,
+}
+
+impl<'a> Generator<'a> {
+    /// Returns the schema of the arguments of `spec`, an object with a property per
+    /// argument.
+    fn args_schema(&self, spec: &MessageSpec) -> Result<Value> {
+        let mut refs = BTreeSet::new();
+        let mut properties = Map::new();
+        for arg in &spec.args {
+            properties.insert(arg.label.clone(), self.schema(arg.ty.id, 0, &mut refs)?);
+        }
+
+        // definitions may refer to further definitions
+        let mut definitions = Map::new();
+        let mut pending = refs.iter().copied().collect::<Vec<_>>();
+        while let Some(id) = pending.pop() {
+            let name = &self.names[&id];
+            if definitions.contains_key(name) {
+                continue;
+            }
+            let mut nested = BTreeSet::new();
+            let ty = self.abi.types.resolve(id)?;
+            let mut definition = self.structure(ty, 0, &mut nested)?;
+            with_description(&mut definition, &ty.docs);
+            definitions.insert(name.clone(), definition);
+            pending.extend(nested);
+        }
+
+        let mut schema = json!({
+            "$schema": SCHEMA,
+            "title": spec.label,
+            "type": "object",
+            "properties": properties,
+            "required": spec.args.iter().map(|arg| &arg.label).collect::<Vec<_>>(),
+            "additionalProperties": false,
+        });
+        with_description(&mut schema, &spec.docs);
+        if !definitions.is_empty() {
+            schema["definitions"] = Value::Object(definitions);
+        }
+        Ok(schema)
+    }
+
+    /// Returns the schema of the type `id`, referring to the definitions of named types.
+    fn schema(&self, id: u32, depth: usize, refs: &mut BTreeSet<u32>) -> Result<Value> {
+        if self.names.contains_key(&id) {
+            refs.insert(id);
+            return Ok(json!({ "$ref": format!("#/definitions/{}", self.names[&id]) }));
+        }
+        if depth > MAX_DEPTH {
+            // any value is accepted
+            return Ok(json!({}));
+        }
+        self.structure(self.abi.types.resolve(id)?, depth + 1, refs)
+    }
+
+    /// Returns the schema of the structure of `ty`.
+    fn structure(&self, ty: &Type, depth: usize, refs: &mut BTreeSet<u32>) -> Result<Value> {
+        let is_byte = |id: u32| {
+            matches!(
+                self.abi.types.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(Primitive::U8))
+            )
+        };
+        Ok(match &ty.def {
+            TypeDef::Primitive(primitive) => primitive_schema(*primitive),
+            TypeDef::Sequence(ty) if is_byte(*ty) => hex_schema(None),
+            TypeDef::Array { len, ty } if is_byte(*ty) => hex_schema(Some(*len)),
+            TypeDef::Sequence(ty) => json!({
+                "type": "array",
+                "items": self.schema(*ty, depth, refs)?,
+            }),
+            TypeDef::Array { len, ty } => json!({
+                "type": "array",
+                "items": self.schema(*ty, depth, refs)?,
+                "minItems": len,
+                "maxItems": len,
+            }),
+            TypeDef::Tuple(fields) if fields.is_empty() => json!({ "type": "null" }),
+            TypeDef::Tuple(fields) => {
+                let items = fields
+                    .iter()
+                    .map(|ty| self.schema(*ty, depth, refs))
+                    .collect::<Result<Vec<_>>>()?;
+                tuple_schema(items)
+            }
+            TypeDef::Compact(ty) => self.schema(*ty, depth, refs)?,
+            TypeDef::BitSequence => hex_schema(None),
+            TypeDef::Composite(_) if ty.path.last().map(String::as_str) == Some("AccountId") => {
+                // an SS58 address or the hex encoded public key
+                json!({ "type": "string" })
+            }
+            TypeDef::Composite(_) if ty.path.last().map(String::as_str) == Some("Hash") => {
+                hex_schema(Some(32))
+            }
+            TypeDef::Composite(fields) => self.fields(fields, depth, refs)?,
+            TypeDef::Variant(variants) if ty.path.last().map(String::as_str) == Some("Option") => {
+                let some = match variants.iter().find(|variant| variant.name == "Some") {
+                    Some(variant) => self.fields(&variant.fields, depth, refs)?,
+                    None => json!({}),
+                };
+                json!({ "anyOf": [some, { "type": "null" }] })
+            }
+            TypeDef::Variant(variants) => {
+                let variants = variants
+                    .iter()
+                    .map(|variant| {
+                        if variant.fields.is_empty() {
+                            return Ok(json!({ "const": variant.name }));
+                        }
+                        Ok(json!({
+                            "type": "object",
+                            "properties": {
+                                variant.name.clone(): self.fields(&variant.fields, depth, refs)?,
+                            },
+                            "required": [variant.name],
+                            "additionalProperties": false,
+                        }))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                json!({ "oneOf": variants })
+            }
+        })
+    }
+
+    /// Returns the schema of a struct or variant with `fields`.
+    fn fields(&self, fields: &[Field], depth: usize, refs: &mut BTreeSet<u32>) -> Result<Value> {
+        Ok(match fields {
+            [] => json!({ "type": "null" }),
+            [field] if field.name.is_none() => self.schema(field.ty, depth, refs)?,
+            fields if fields.iter().all(|field| field.name.is_some()) => {
+                let mut properties = Map::new();
+                for field in fields {
+                    let mut schema = self.schema(field.ty, depth, refs)?;
+                    with_description(&mut schema, &field.docs);
+                    properties.insert(field.name.clone().unwrap_or_default(), schema);
+                }
+                json!({
+                    "type": "object",
+                    "properties": properties,
+                    "required": fields.iter().map(|field| &field.name).collect::<Vec<_>>(),
+                    "additionalProperties": false,
+                })
+            }
+            fields => {
+                let items = fields
+                    .iter()
+                    .map(|field| self.schema(field.ty, depth, refs))
+                    .collect::<Result<Vec<_>>>()?;
+                tuple_schema(items)
+            }
+        })
+    }
+}
+
+/// Adds the `docs` to `schema` as its description, unless the schema is a reference, whose
+/// siblings are ignored by validators.
+fn with_description(schema: &mut Value, docs: &[String]) {
+    if docs.is_empty() || schema.get("$ref").is_some() {
+        return;
+    }
+    let description = docs
+        .iter()
+        .map(|line| line.trim())
+        .collect::<Vec<_>>()
+        .join("\n");
+    if let Some(schema) = schema.as_object_mut() {
+        schema.insert("description".into(), Value::String(description));
+    }
+}
+
+/// Returns the schema of an array with an item of each of the schemas `items`.
+fn tuple_schema(items: Vec<Value>) -> Value {
+    let len = items.len();
+    json!({
+        "type": "array",
+        "items": items,
+        "minItems": len,
+        "maxItems": len,
+    })
+}
+
+/// Returns the schema of `0x` prefixed hex strings of `len` bytes, of any length if `None`.
+fn hex_schema(len: Option<u32>) -> Value {
+    let pattern = match len {
+        Some(len) => format!("^0x[0-9a-fA-F]{{{}}}$", len * 2),
+        None => "^0x([0-9a-fA-F]{2})*$".to_string(),
+    };
+    json!({ "type": "string", "pattern": pattern })
+}
+
+/// Returns the schema of `primitive`.
+///
+/// Integers which may exceed `Number.MAX_SAFE_INTEGER` may also be given as strings.
+fn primitive_schema(primitive: Primitive) -> Value {
+    let integer = |minimum: i64, maximum: i64| json!({ "type": "integer", "minimum": minimum, "maximum": maximum });
+    let numeric = |signed: bool| {
+        let (minimum, pattern) = if signed {
+            (None, "^-?[0-9]+$")
+        } else {
+            (Some(0), "^[0-9]+$")
+        };
+        let mut integer = json!({ "type": "integer" });
+        if let Some(minimum) = minimum {
+            integer["minimum"] = json!(minimum);
+        }
+        json!({ "anyOf": [integer, { "type": "string", "pattern": pattern }] })
+    };
+    match primitive {
+        Primitive::Bool => json!({ "type": "boolean" }),
+        Primitive::Char => json!({ "type": "string", "minLength": 1, "maxLength": 1 }),
+        Primitive::Str => json!({ "type": "string" }),
+        Primitive::U8 => integer(0, u8::MAX.into()),
+        Primitive::U16 => integer(0, u16::MAX.into()),
+        Primitive::U32 => integer(0, u32::MAX.into()),
+        Primitive::I8 => integer(i8::MIN.into(), i8::MAX.into()),
+        Primitive::I16 => integer(i16::MIN.into(), i16::MAX.into()),
+        Primitive::I32 => integer(i32::MIN.into(), i32::MAX.into()),
+        Primitive::U64 | Primitive::U128 | Primitive::U256 => numeric(false),
+        Primitive::I64 | Primitive::I128 | Primitive::I256 => numeric(true),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn generates_schemas_of_arguments() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let schemas = generate(&abi).unwrap();
+
+        assert_eq!(
+            schemas["constructors"]["new"],
+            json!({
+                "$schema": SCHEMA,
+                "title": "new",
+                "description": "Creates a bank.",
+                "type": "object",
+                "properties": {
+                    "limit": {
+                        "anyOf": [
+                            { "type": "integer", "minimum": 0 },
+                            { "type": "string", "pattern": "^[0-9]+$" },
+                        ]
+                    }
+                },
+                "required": ["limit"],
+                "additionalProperties": false,
+            })
+        );
+        let balance_of = &schemas["messages"]["Ledger::balance_of"];
+        assert_eq!(
+            balance_of["properties"]["owner"],
+            json!({ "$ref": "#/definitions/AccountId" })
+        );
+        assert_eq!(
+            balance_of["definitions"],
+            json!({ "AccountId": { "type": "string" } })
+        );
+        assert_eq!(schemas["messages"]["deposit"]["properties"], json!({}));
+    }
+}
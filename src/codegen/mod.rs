@@ -0,0 +1,144 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates code for interacting with a contract from its [`ContractAbi`].
+
+pub mod json_schema;
+pub mod python;
+pub mod rust;
+pub mod solidity;
+pub mod typescript;
+
+use crate::abi::{ContractAbi, MessageSpec, TypeDef};
+use heck::{CamelCase as _, SnakeCase as _};
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+/// Returns the ids of all types used by the constructors, messages and events of the
+/// contract, including the types nested in them.
+pub fn used_types(abi: &ContractAbi) -> BTreeSet<u32> {
+    let mut pending = abi
+        .constructors
+        .iter()
+        .chain(&abi.messages)
+        .flat_map(|spec| {
+            spec.args
+                .iter()
+                .map(|arg| arg.ty.id)
+                .chain(spec.return_type.as_ref().map(|ty| ty.id))
+        })
+        .chain(
+            abi.events
+                .iter()
+                .flat_map(|event| event.args.iter().map(|arg| arg.ty.id)),
+        )
+        .collect::<Vec<_>>();
+
+    let mut used = BTreeSet::new();
+    while let Some(id) = pending.pop() {
+        if !used.insert(id) {
+            continue;
+        }
+        let ty = match abi.types.resolve(id) {
+            Ok(ty) => ty,
+            Err(_) => continue,
+        };
+        pending.extend(ty.params.iter().filter_map(|(_, ty)| *ty));
+        match &ty.def {
+            TypeDef::Composite(fields) => pending.extend(fields.iter().map(|field| field.ty)),
+            TypeDef::Variant(variants) => pending.extend(
+                variants
+                    .iter()
+                    .flat_map(|variant| variant.fields.iter().map(|field| field.ty)),
+            ),
+            TypeDef::Sequence(ty) | TypeDef::Compact(ty) | TypeDef::Array { ty, .. } => {
+                pending.push(*ty)
+            }
+            TypeDef::Tuple(fields) => pending.extend(fields),
+            TypeDef::Primitive(_) | TypeDef::BitSequence => (),
+        }
+    }
+    used
+}
+
+/// Returns the names of the named, non generic types used by the contract.
+///
+/// Types are named after the last segment of their path, unless several types share it.
+pub fn type_names(abi: &ContractAbi) -> BTreeMap<u32, String> {
+    let named = used_types(abi)
+        .into_iter()
+        .filter_map(|id| {
+            let ty = abi.types.resolve(id).ok()?;
+            let is_generic = ty.params.iter().any(|(_, ty)| ty.is_some());
+            match &ty.def {
+                TypeDef::Composite(_) | TypeDef::Variant(_)
+                    if !ty.path.is_empty() && !is_generic =>
+                {
+                    Some((id, ty))
+                }
+                _ => None,
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let mut counts = HashMap::new();
+    for (_, ty) in &named {
+        *counts.entry(ty.path.last()).or_insert(0) += 1;
+    }
+    named
+        .iter()
+        .map(|(id, ty)| {
+            let name = if counts[&ty.path.last()] > 1 {
+                ty.path.join("_").to_camel_case()
+            } else {
+                ty.path
+                    .last()
+                    .expect("only types with a path are named")
+                    .to_camel_case()
+            };
+            (*id, name)
+        })
+        .collect()
+}
+
+/// Returns the function names of `specs` in Rust and similar languages.
+///
+/// Trait messages are named after the message, unless several traits have a message of
+/// that name, e.g. `balance_of` for `PSP22::balance_of`.
+pub fn fn_names(specs: &[MessageSpec]) -> Vec<String> {
+    let short_name = |spec: &MessageSpec| {
+        spec.label
+            .rsplit("::")
+            .next()
+            .unwrap_or_default()
+            .to_snake_case()
+    };
+    specs
+        .iter()
+        .map(|spec| {
+            let name = short_name(spec);
+            if specs
+                .iter()
+                .filter(|other| short_name(other) == name)
+                .count()
+                > 1
+            {
+                spec.label.replace("::", "_").to_snake_case()
+            } else {
+                name
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,581 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates a Python module with the types and typed calls of a contract for
+//! `cargo contract generate-client --lang python`.
+//!
+//! The types are dataclasses converting from and to the values of `scalecodec`, and the
+//! calls are submitted with the `ContractCode` and `ContractInstance` of
+//! `py-substrate-interface`, which encode them with the metadata of the contract.
+
+use super::{fn_names, type_names};
+use crate::abi::{ContractAbi, Field, MessageSpec, Type, TypeDef};
+use anyhow::Result;
+use std::{collections::BTreeMap, fmt::Write as _};
+
+/// The first line of generated files, only files starting with it are overwritten.
+pub const GENERATED_HEADER: &str = "# This file is generated by `cargo contract generate-client`";
+
+/// The nesting depth after which values are no longer converted, guarding against recursion.
+const MAX_DEPTH: usize = 16;
+
+/// The keywords of Python, which are suffixed with `_` if used as identifiers.
+const KEYWORDS: [&str; 35] = [
+    "False", "None", "True", "and", "as", "assert", "async", "await", "break", "class", "continue",
+    "def", "del", "elif", "else", "except", "finally", "for", "from", "global", "if", "import",
+    "in", "is", "lambda", "nonlocal", "not", "or", "pass", "raise", "return", "try", "while",
+    "with", "yield",
+];
+
+/// The helpers of the generated module.
+const PRELUDE: &str = r#"from __future__ import annotations
+
+from dataclasses import dataclass
+from typing import Any, Callable, Dict, List, Optional, Tuple
+
+
+def _to_scale(value: Any) -> Any:
+    """Converts the generated types into the values of `scalecodec`."""
+    if hasattr(value, "to_scale"):
+        return value.to_scale()
+    if isinstance(value, (list, tuple)):
+        return [_to_scale(item) for item in value]
+    if isinstance(value, dict):
+        return {key: _to_scale(item) for key, item in value.items()}
+    return value
+
+
+def _result(value: Any, ok: Callable[[Any], Any], err: Callable[[Any], Any]) -> Any:
+    """Converts a decoded `Result`, which is either `{"Ok": ...}` or `{"Err": ...}`."""
+    if "Ok" in value:
+        return {"Ok": ok(value["Ok"])}
+    return {"Err": err(value["Err"])}
+
+
+@dataclass
+class Call:
+    """A call of a constructor or message of the contract."""
+
+    method: str
+    selector: str
+    args: Dict[str, Any]
+    payable: bool
+    mutates: bool
+    decode: Callable[[Any], Any]
+
+    def read(self, contract: Any, keypair: Any, **kwargs: Any) -> Any:
+        """Dry-runs the message with a `ContractInstance`, returning the decoded value."""
+        result = contract.read(keypair, self.method, args=_to_scale(self.args), **kwargs)
+        return self.decode(result.contract_result_data.value)
+
+    def exec(self, contract: Any, keypair: Any, **kwargs: Any) -> Any:
+        """Submits the message with a `ContractInstance`, returning the receipt."""
+        return contract.exec(keypair, self.method, args=_to_scale(self.args), **kwargs)
+
+    def deploy(self, code: Any, keypair: Any, **kwargs: Any) -> Any:
+        """Instantiates the contract from a `ContractCode` with this constructor."""
+        return code.deploy(keypair, constructor=self.method, args=_to_scale(self.args), **kwargs)
+"#;
+
+/// Generates the Python module of the contract.
+pub fn generate(abi: &ContractAbi) -> Result<String> {
+    let names = type_names(abi);
+    let gen = Generator { abi, names: &names };
+    let contract = abi.name.as_deref().unwrap_or("the contract");
+
+    let mut out = String::new();
+    // writing to a `String` never fails
+    let _ = writeln!(out, "{} from the metadata of", GENERATED_HEADER);
+    let _ = writeln!(out, "# {}, do not edit it.", contract);
+    let mut docs = abi.docs.clone();
+    if docs.is_empty() {
+        docs.push(format!("Typed calls of {}.", contract));
+    }
+    write_docstring(&mut out, &docs, "");
+    let _ = writeln!(out);
+    out.push_str(PRELUDE);
+
+    for (id, name) in &names {
+        let ty = abi.types.resolve(*id)?;
+        if !is_address(ty) {
+            let _ = writeln!(out, "\n");
+            gen.write_type(&mut out, name, ty)?;
+        }
+    }
+    gen.write_calls(&mut out, "Constructors", &abi.constructors)?;
+    gen.write_calls(&mut out, "Messages", &abi.messages)?;
+    Ok(out)
+}
+
+/// Returns `true` for the types of the environment given as SS58 or hex strings.
+fn is_address(ty: &Type) -> bool {
+    matches!(ty.def, TypeDef::Composite(_))
+        && matches!(
+            ty.path.last().map(String::as_str),
+            Some("AccountId") | Some("Hash")
+        )
+}
+
+/// Returns `name` as a Python identifier.
+fn ident(name: &str) -> String {
+    if KEYWORDS.contains(&name) {
+        format!("{}_", name)
+    } else {
+        name.to_string()
+    }
+}
+
+fn write_docstring(out: &mut String, docs: &[String], indent: &str) {
+    let lines = docs.iter().map(|line| line.trim()).collect::<Vec<_>>();
+    match lines.as_slice() {
+        [] => (),
+        [line] => {
+            let _ = writeln!(out, "{}\"\"\"{}\"\"\"", indent, line);
+        }
+        lines => {
+            let _ = writeln!(out, "{}\"\"\"{}", indent, lines[0]);
+            for line in &lines[1..] {
+                if line.is_empty() {
+                    let _ = writeln!(out);
+                } else {
+                    let _ = writeln!(out, "{}{}", indent, line);
+                }
+            }
+            let _ = writeln!(out, "{}\"\"\"", indent);
+        }
+    }
+}
+
+/// The names of the fields of a struct, `field0` etc. for tuple structs.
+fn field_names(fields: &[Field]) -> Vec<String> {
+    fields
+        .iter()
+        .enumerate()
+        .map(|(i, field)| match &field.name {
+            Some(name) => ident(name),
+            None => format!("field{}", i),
+        })
+        .collect()
+}
+
+struct Generator<'a> {
+    abi: &'a ContractAbi,
+    names: &'a BTreeMap<u32, String>,
+}
+
+impl<'a> Generator<'a> {
+    /// Writes the dataclass of the named type `ty`.
+    fn write_type(&self, out: &mut String, name: &str, ty: &Type) -> Result<()> {
+        let _ = writeln!(out, "@dataclass");
+        let _ = writeln!(out, "class {}:", name);
+        write_docstring(out, &ty.docs, "    ");
+        match &ty.def {
+            TypeDef::Composite(fields) => self.write_struct(out, name, fields)?,
+            TypeDef::Variant(variants) => {
+                let _ = writeln!(out, "    variant: str");
+                let _ = writeln!(out, "    value: Any = None");
+                for variant in variants {
+                    let names = field_names(&variant.fields);
+                    let params = variant
+                        .fields
+                        .iter()
+                        .zip(&names)
+                        .map(|(field, name)| Ok(format!(", {}: {}", name, self.py_type(field.ty)?)))
+                        .collect::<Result<String>>()?;
+                    let _ = writeln!(out, "\n    @classmethod");
+                    let _ = writeln!(out, "    def {}(cls{}) -> {}:", variant.name, params, name);
+                    write_docstring(out, &variant.docs, "        ");
+                    let value = match variant.fields.as_slice() {
+                        [] => String::new(),
+                        [field] if field.name.is_none() => format!(", {}", names[0]),
+                        fields if fields.iter().all(|field| field.name.is_some()) => {
+                            let entries = fields
+                                .iter()
+                                .zip(&names)
+                                .map(|(field, name)| {
+                                    format!(
+                                        "{:?}: {}",
+                                        field.name.as_deref().unwrap_or_default(),
+                                        name
+                                    )
+                                })
+                                .collect::<Vec<_>>();
+                            format!(", {{{}}}", entries.join(", "))
+                        }
+                        _ => format!(", [{}]", names.join(", ")),
+                    };
+                    let _ = writeln!(out, "        return cls({:?}{})", variant.name, value);
+                }
+
+                let _ = writeln!(out, "\n    def to_scale(self) -> Any:");
+                let _ = writeln!(out, "        if self.value is None:");
+                let _ = writeln!(out, "            return self.variant");
+                let _ = writeln!(
+                    out,
+                    "        return {{self.variant: _to_scale(self.value)}}"
+                );
+
+                let _ = writeln!(out, "\n    @classmethod");
+                let _ = writeln!(out, "    def from_scale(cls, value: Any) -> {}:", name);
+                let _ = writeln!(out, "        if isinstance(value, str):");
+                let _ = writeln!(out, "            return cls(value)");
+                let _ = writeln!(out, "        variant, inner = next(iter(value.items()))");
+                let _ = writeln!(out, "        if inner is None:");
+                let _ = writeln!(out, "            return cls(variant)");
+                for variant in variants {
+                    if let Some(convert) = self.convert_fields(&variant.fields, "inner", 0)? {
+                        let _ = writeln!(out, "        if variant == {:?}:", variant.name);
+                        let _ = writeln!(out, "            return cls(variant, {})", convert);
+                    }
+                }
+                let _ = writeln!(out, "        return cls(variant, inner)");
+            }
+            _ => unreachable!("only structs and enums are named"),
+        }
+        Ok(())
+    }
+
+    /// Writes the fields and conversions of the struct `name`.
+    fn write_struct(&self, out: &mut String, name: &str, fields: &[Field]) -> Result<()> {
+        let names = field_names(fields);
+        for (field, name) in fields.iter().zip(&names) {
+            let _ = writeln!(out, "    {}: {}", name, self.py_type(field.ty)?);
+        }
+        if !fields.is_empty() {
+            let _ = writeln!(out);
+        }
+
+        let _ = writeln!(out, "    def to_scale(self) -> Any:");
+        let value = match fields {
+            [] => "None".to_string(),
+            [field] if field.name.is_none() => "_to_scale(self.field0)".to_string(),
+            fields if fields.iter().all(|field| field.name.is_some()) => {
+                let entries = fields
+                    .iter()
+                    .zip(&names)
+                    .map(|(field, name)| {
+                        format!(
+                            "{:?}: _to_scale(self.{})",
+                            field.name.as_deref().unwrap_or_default(),
+                            name
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{{}}}", entries.join(", "))
+            }
+            _ => format!(
+                "[{}]",
+                names
+                    .iter()
+                    .map(|name| format!("_to_scale(self.{})", name))
+                    .collect::<Vec<_>>()
+                    .join(", ")
+            ),
+        };
+        let _ = writeln!(out, "        return {}", value);
+
+        let _ = writeln!(out, "\n    @classmethod");
+        let _ = writeln!(out, "    def from_scale(cls, value: Any) -> {}:", name);
+        let args = match fields {
+            [] => Vec::new(),
+            [field] if field.name.is_none() => vec![self.convert_or_keep(field.ty, "value", 0)?],
+            fields => fields
+                .iter()
+                .enumerate()
+                .map(|(i, field)| {
+                    let value = match &field.name {
+                        Some(name) => format!("value[{:?}]", name),
+                        None => format!("value[{}]", i),
+                    };
+                    self.convert_or_keep(field.ty, &value, 0)
+                })
+                .collect::<Result<_>>()?,
+        };
+        let _ = writeln!(out, "        return cls({})", args.join(", "));
+        Ok(())
+    }
+
+    /// Writes the class `name` with a static method building a call of each of `specs`.
+    fn write_calls(&self, out: &mut String, name: &str, specs: &[MessageSpec]) -> Result<()> {
+        let _ = writeln!(out, "\n");
+        let _ = writeln!(out, "class {}:", name);
+        let _ = writeln!(
+            out,
+            "    \"\"\"The {} of the contract.\"\"\"",
+            name.to_lowercase()
+        );
+        for (spec, fn_name) in specs.iter().zip(fn_names(specs)) {
+            let params = spec
+                .args
+                .iter()
+                .map(|arg| {
+                    Ok(format!(
+                        "{}: {}",
+                        ident(&arg.label),
+                        self.py_type(arg.ty.id)?
+                    ))
+                })
+                .collect::<Result<Vec<_>>>()?;
+            let args = spec
+                .args
+                .iter()
+                .map(|arg| format!("{:?}: {}", arg.label, ident(&arg.label)))
+                .collect::<Vec<_>>();
+            let decode = match &spec.return_type {
+                Some(ty) => match self.convert(ty.id, "value", 0)? {
+                    Some(convert) => format!("lambda value: {}", convert),
+                    None => "lambda value: value".to_string(),
+                },
+                None => "lambda value: value".to_string(),
+            };
+
+            let _ = writeln!(out, "\n    @staticmethod");
+            let _ = writeln!(
+                out,
+                "    def {}({}) -> Call:",
+                ident(&fn_name),
+                params.join(", ")
+            );
+            write_docstring(out, &spec.docs, "        ");
+            let _ = writeln!(out, "        return Call(");
+            let _ = writeln!(out, "            method={:?},", spec.label);
+            let _ = writeln!(out, "            selector=\"{}\",", spec.selector);
+            let _ = writeln!(out, "            args={{{}}},", args.join(", "));
+            let _ = writeln!(
+                out,
+                "            payable={},",
+                if spec.payable { "True" } else { "False" }
+            );
+            let _ = writeln!(
+                out,
+                "            mutates={},",
+                if spec.mutates { "True" } else { "False" }
+            );
+            let _ = writeln!(out, "            decode={},", decode);
+            let _ = writeln!(out, "        )");
+        }
+        Ok(())
+    }
+
+    /// Returns the type hint of the type `id`.
+    fn py_type(&self, id: u32) -> Result<String> {
+        let ty = self.abi.types.resolve(id)?;
+        if let Some(name) = self.names.get(&id) {
+            return Ok(if is_address(ty) {
+                "str".to_string()
+            } else {
+                name.clone()
+            });
+        }
+        let is_byte = |id: u32| {
+            matches!(
+                self.abi.types.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(crate::abi::Primitive::U8))
+            )
+        };
+        Ok(match &ty.def {
+            TypeDef::Primitive(primitive) => match primitive.name() {
+                "bool" => "bool",
+                "char" | "String" => "str",
+                _ => "int",
+            }
+            .to_string(),
+            // byte sequences are passed around as hex strings
+            TypeDef::Sequence(ty) | TypeDef::Array { ty, .. } if is_byte(*ty) => "str".to_string(),
+            TypeDef::Sequence(ty) | TypeDef::Array { ty, .. } => {
+                format!("List[{}]", self.py_type(*ty)?)
+            }
+            TypeDef::Tuple(fields) if fields.is_empty() => "None".to_string(),
+            TypeDef::Tuple(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|ty| self.py_type(*ty))
+                    .collect::<Result<Vec<_>>>()?;
+                format!("Tuple[{}]", fields.join(", "))
+            }
+            TypeDef::Compact(ty) => self.py_type(*ty)?,
+            TypeDef::BitSequence => "str".to_string(),
+            TypeDef::Variant(_) if ty.path.last().map(String::as_str) == Some("Option") => {
+                match ty.params.first().and_then(|(_, ty)| *ty) {
+                    Some(some) => format!("Optional[{}]", self.py_type(some)?),
+                    None => "Any".to_string(),
+                }
+            }
+            TypeDef::Variant(_) if ty.path.last().map(String::as_str) == Some("Result") => {
+                "Dict[str, Any]".to_string()
+            }
+            TypeDef::Composite(_) | TypeDef::Variant(_) => "Any".to_string(),
+        })
+    }
+
+    /// Returns the expression converting `value` into the type `id`, or `value` itself if
+    /// it needs no conversion.
+    fn convert_or_keep(&self, id: u32, value: &str, depth: usize) -> Result<String> {
+        Ok(self
+            .convert(id, value, depth)?
+            .unwrap_or_else(|| value.to_string()))
+    }
+
+    /// Returns the expression converting the `scalecodec` value `value` into the type `id`,
+    /// `None` if it needs no conversion.
+    fn convert(&self, id: u32, value: &str, depth: usize) -> Result<Option<String>> {
+        if depth > MAX_DEPTH {
+            return Ok(None);
+        }
+        let ty = self.abi.types.resolve(id)?;
+        if let Some(name) = self.names.get(&id) {
+            return Ok(if is_address(ty) {
+                None
+            } else {
+                Some(format!("{}.from_scale({})", name, value))
+            });
+        }
+        let item = format!("x{}", depth);
+        Ok(match &ty.def {
+            TypeDef::Sequence(ty) | TypeDef::Array { ty, .. } => self
+                .convert(*ty, &item, depth + 1)?
+                .map(|convert| format!("[{} for {} in {}]", convert, item, value)),
+            TypeDef::Tuple(fields) => {
+                let converted = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, ty)| self.convert(*ty, &format!("{}[{}]", value, i), depth + 1))
+                    .collect::<Result<Vec<_>>>()?;
+                if converted.iter().all(Option::is_none) {
+                    None
+                } else {
+                    let items = converted
+                        .into_iter()
+                        .enumerate()
+                        .map(|(i, convert)| convert.unwrap_or_else(|| format!("{}[{}]", value, i)))
+                        .collect::<Vec<_>>();
+                    Some(format!("({},)", items.join(", ")))
+                }
+            }
+            TypeDef::Compact(ty) => self.convert(*ty, value, depth + 1)?,
+            TypeDef::Variant(variants) if ty.path.last().map(String::as_str) == Some("Option") => {
+                let some = variants.iter().find(|variant| variant.name == "Some");
+                match some {
+                    Some(some) => self
+                        .convert_fields(&some.fields, value, depth + 1)?
+                        .map(|convert| format!("None if {} is None else {}", value, convert)),
+                    None => None,
+                }
+            }
+            TypeDef::Variant(variants) if ty.path.last().map(String::as_str) == Some("Result") => {
+                let convert = |name: &str| -> Result<Option<String>> {
+                    match variants.iter().find(|variant| variant.name == name) {
+                        Some(variant) => self.convert_fields(&variant.fields, &item, depth + 1),
+                        None => Ok(None),
+                    }
+                };
+                let (ok, err) = (convert("Ok")?, convert("Err")?);
+                if ok.is_none() && err.is_none() {
+                    None
+                } else {
+                    let lambda = |convert: Option<String>| {
+                        format!(
+                            "lambda {}: {}",
+                            item,
+                            convert.unwrap_or_else(|| item.clone())
+                        )
+                    };
+                    Some(format!(
+                        "_result({}, {}, {})",
+                        value,
+                        lambda(ok),
+                        lambda(err)
+                    ))
+                }
+            }
+            _ => None,
+        })
+    }
+
+    /// Returns the expression converting the value of a variant with `fields`.
+    fn convert_fields(
+        &self,
+        fields: &[Field],
+        value: &str,
+        depth: usize,
+    ) -> Result<Option<String>> {
+        match fields {
+            [] => Ok(None),
+            [field] if field.name.is_none() => self.convert(field.ty, value, depth),
+            fields => {
+                let named = fields.iter().all(|field| field.name.is_some());
+                let converted = fields
+                    .iter()
+                    .enumerate()
+                    .map(|(i, field)| {
+                        let field_value = match &field.name {
+                            Some(name) if named => format!("{}[{:?}]", value, name),
+                            _ => format!("{}[{}]", value, i),
+                        };
+                        Ok((
+                            field,
+                            field_value.clone(),
+                            self.convert(field.ty, &field_value, depth)?,
+                        ))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if converted.iter().all(|(_, _, convert)| convert.is_none()) {
+                    return Ok(None);
+                }
+                let items = converted
+                    .into_iter()
+                    .map(|(field, field_value, convert)| {
+                        let convert = convert.unwrap_or(field_value);
+                        match &field.name {
+                            Some(name) if named => format!("{:?}: {}", name, convert),
+                            _ => convert,
+                        }
+                    })
+                    .collect::<Vec<_>>();
+                Ok(Some(if named {
+                    format!("{{{}}}", items.join(", "))
+                } else {
+                    format!("[{}]", items.join(", "))
+                }))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn generates_dataclasses_and_calls() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let module = generate(&abi).unwrap();
+
+        assert!(module.starts_with(GENERATED_HEADER));
+        // account ids are given as SS58 addresses
+        assert!(!module.contains("class AccountId"));
+        assert!(module.contains(
+            "    @classmethod\n    def LimitExceeded(cls, limit: int) -> Error:\n        return cls(\"LimitExceeded\", {\"limit\": limit})\n"
+        ));
+        assert!(module.contains(
+            "    @staticmethod\n    def balance_of(owner: str) -> Call:\n        return Call(\n            method=\"Ledger::balance_of\",\n            selector=\"0x0f755a56\",\n            args={\"owner\": owner},\n"
+        ));
+        assert!(module.contains(
+            "            decode=lambda value: _result(value, lambda x0: x0, lambda x0: Error.from_scale(x0)),\n"
+        ));
+    }
+}
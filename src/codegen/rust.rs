@@ -0,0 +1,408 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates Rust code for the types of a contract and the typed client crate of
+//! `cargo contract generate-client --lang rust`.
+
+use super::{fn_names, type_names};
+use crate::abi::{ContractAbi, Field, MessageSpec, Type, TypeDef, TypeRef};
+use anyhow::Result;
+use std::{cell::Cell, collections::BTreeMap, fmt::Write as _};
+
+/// The first line of generated files, only files starting with it are overwritten.
+pub const GENERATED_HEADER: &str = "// This file is generated by `cargo contract generate-client`";
+
+/// The types of the contract environment, which are in scope of every ink! contract.
+const ENV_TYPES: [&str; 5] = ["AccountId", "Balance", "Hash", "Timestamp", "BlockNumber"];
+
+/// The files of a generated client crate.
+pub struct ClientCrate {
+    /// The contents of the `Cargo.toml`.
+    pub manifest: String,
+    /// The contents of the `src/lib.rs`.
+    pub lib: String,
+}
+
+/// Generates a client crate with typed calls of the constructors and messages of `abi`.
+///
+/// Every call is built by a function taking the arguments of the constructor or message,
+/// and carries the type it returns.
+pub fn generate_client(abi: &ContractAbi) -> Result<ClientCrate> {
+    let contract = abi.name.as_deref().unwrap_or("contract");
+    let mut manifest = String::new();
+    // writing to a `String` never fails
+    let _ = writeln!(manifest, "[package]");
+    let _ = writeln!(manifest, "name = \"{}-client\"", contract.replace('_', "-"));
+    let _ = writeln!(manifest, "version = \"0.1.0\"");
+    let _ = writeln!(manifest, "edition = \"2018\"");
+    let _ = writeln!(manifest, "publish = false\n");
+    let _ = writeln!(manifest, "[dependencies]");
+    let _ = writeln!(
+        manifest,
+        "scale = {{ package = \"parity-scale-codec\", version = \"2\", features = [\"derive\"] }}"
+    );
+
+    let names = type_names(abi);
+    let types = TypeWriter::new(
+        abi,
+        &names,
+        "",
+        &["#[derive(Debug, Clone, PartialEq, Eq, scale::Encode, scale::Decode)]"],
+        false,
+    );
+    let mut body = String::new();
+    for (id, name) in &names {
+        types.write_type(&mut body, name, abi.types.resolve(*id)?)?;
+    }
+    write_calls(&mut body, &types, "constructors", &abi.constructors)?;
+    write_calls(&mut body, &types, "messages", &abi.messages)?;
+
+    let mut lib = String::new();
+    let _ = writeln!(lib, "{} from the metadata of", GENERATED_HEADER);
+    let _ = writeln!(lib, "// {}, do not edit it.\n", contract);
+    if abi.docs.is_empty() {
+        let _ = writeln!(lib, "//! Typed calls of the `{}` contract.", contract);
+    }
+    for line in &abi.docs {
+        let _ = writeln!(lib, "//!{}", doc_line(line));
+    }
+    let _ = writeln!(lib);
+    lib.push_str(CALL);
+    lib.push('\n');
+    lib.push_str(&body);
+    Ok(ClientCrate { manifest, lib })
+}
+
+/// The definition of the calls built by the generated functions.
+const CALL: &str = r#"use std::marker::PhantomData;
+
+/// A call of a constructor or message, returning a value of type `R`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Call<R> {
+    /// The label of the constructor or message in the metadata.
+    pub label: &'static str,
+    /// The selector of the constructor or message.
+    pub selector: [u8; 4],
+    /// Whether the call may transfer value to the contract.
+    pub payable: bool,
+    /// Whether the message mutates the storage, i.e. needs to be submitted as a transaction.
+    pub mutates: bool,
+    /// The SCALE encoded arguments.
+    pub args: Vec<u8>,
+    returns: PhantomData<fn() -> R>,
+}
+
+impl<R> Call<R> {
+    fn new(label: &'static str, selector: [u8; 4], payable: bool, mutates: bool) -> Self {
+        Self {
+            label,
+            selector,
+            payable,
+            mutates,
+            args: Vec::new(),
+            returns: PhantomData,
+        }
+    }
+
+    fn push_arg<T: scale::Encode>(mut self, arg: &T) -> Self {
+        arg.encode_to(&mut self.args);
+        self
+    }
+
+    /// Returns the input data of the call, the selector followed by the arguments.
+    pub fn data(&self) -> Vec<u8> {
+        let mut data = self.selector.to_vec();
+        data.extend_from_slice(&self.args);
+        data
+    }
+}
+
+impl<R: scale::Decode> Call<R> {
+    /// Decodes the value returned by the call.
+    pub fn decode_output(&self, mut output: &[u8]) -> Result<R, scale::Error> {
+        R::decode(&mut output)
+    }
+}
+"#;
+
+/// Writes the module `kind` with a function building a call for each of `specs`.
+fn write_calls(
+    w: &mut String,
+    types: &TypeWriter,
+    kind: &str,
+    specs: &[MessageSpec],
+) -> Result<()> {
+    let _ = writeln!(w, "/// The {} of the contract.", kind);
+    let _ = writeln!(w, "pub mod {} {{", kind);
+    let _ = writeln!(w, "    #[allow(unused_imports)]");
+    let _ = writeln!(w, "    use super::*;");
+    for (spec, name) in specs.iter().zip(fn_names(specs)) {
+        let _ = writeln!(w);
+        write_docs(w, &spec.docs, "    ");
+        let args = spec
+            .args
+            .iter()
+            .map(|arg| Ok(format!("{}: {}", arg.label, types.arg_type(&arg.ty)?)))
+            .collect::<Result<Vec<_>>>()?;
+        let returns = match &spec.return_type {
+            Some(ty) => types.arg_type(ty)?,
+            None => "()".to_string(),
+        };
+        let _ = writeln!(
+            w,
+            "    pub fn {}({}) -> Call<{}> {{",
+            name,
+            args.join(", "),
+            returns
+        );
+        let selector = spec
+            .selector
+            .0
+            .iter()
+            .map(|byte| format!("0x{:02x}", byte))
+            .collect::<Vec<_>>();
+        let _ = write!(
+            w,
+            "        Call::new({:?}, [{}], {}, {})",
+            spec.label,
+            selector.join(", "),
+            spec.payable,
+            spec.mutates
+        );
+        for arg in &spec.args {
+            let _ = write!(w, "\n            .push_arg(&{})", arg.label);
+        }
+        let _ = writeln!(w, "\n    }}");
+    }
+    let _ = writeln!(w, "}}\n");
+    Ok(())
+}
+
+/// Returns a line of documentation to append to `///`.
+fn doc_line(line: &str) -> String {
+    let line = line.trim_end();
+    if line.starts_with(' ') || line.is_empty() {
+        line.to_string()
+    } else {
+        format!(" {}", line)
+    }
+}
+
+/// Writes the documentation `docs`, indented by `indent`.
+pub fn write_docs(w: &mut String, docs: &[String], indent: &str) {
+    for line in docs {
+        let _ = writeln!(w, "{}///{}", indent, doc_line(line));
+    }
+}
+
+/// Writes the Rust definitions of the types of a contract.
+pub struct TypeWriter<'a> {
+    abi: &'a ContractAbi,
+    names: &'a BTreeMap<u32, String>,
+    /// The indentation of the definitions.
+    indent: &'a str,
+    /// The attributes of every definition, e.g. its derives.
+    attributes: &'a [&'a str],
+    /// Whether the types of the contract environment are in scope.
+    env_types: bool,
+    uses_vec: Cell<bool>,
+    uses_string: Cell<bool>,
+}
+
+impl<'a> TypeWriter<'a> {
+    /// Creates a writer of the types `names`, usually the [`type_names`] of `abi`.
+    pub fn new(
+        abi: &'a ContractAbi,
+        names: &'a BTreeMap<u32, String>,
+        indent: &'a str,
+        attributes: &'a [&'a str],
+        env_types: bool,
+    ) -> Self {
+        Self {
+            abi,
+            names,
+            indent,
+            attributes,
+            env_types,
+            uses_vec: Cell::new(false),
+            uses_string: Cell::new(false),
+        }
+    }
+
+    /// Returns `true` if any of the written types uses a `Vec`.
+    pub fn uses_vec(&self) -> bool {
+        self.uses_vec.get()
+    }
+
+    /// Returns `true` if any of the written types uses a `String`.
+    pub fn uses_string(&self) -> bool {
+        self.uses_string.get()
+    }
+
+    /// Writes the definition of the named type `ty`.
+    pub fn write_type(&self, w: &mut String, name: &str, ty: &Type) -> Result<()> {
+        let indent = self.indent;
+        write_docs(w, &ty.docs, indent);
+        for attribute in self.attributes {
+            let _ = writeln!(w, "{}{}", indent, attribute);
+        }
+        match &ty.def {
+            TypeDef::Composite(fields) => {
+                let _ = write!(w, "{}pub struct {}", indent, name);
+                self.write_fields(w, fields, indent, "pub ")?;
+                if fields.is_empty() || fields.iter().any(|field| field.name.is_none()) {
+                    w.push(';');
+                }
+                let _ = writeln!(w, "\n");
+            }
+            TypeDef::Variant(variants) => {
+                let _ = writeln!(w, "{}pub enum {} {{", indent, name);
+                let variant_indent = format!("{}    ", indent);
+                for variant in variants {
+                    write_docs(w, &variant.docs, &variant_indent);
+                    let _ = write!(w, "{}{}", variant_indent, variant.name);
+                    self.write_fields(w, &variant.fields, &variant_indent, "")?;
+                    let _ = writeln!(w, ",");
+                }
+                let _ = writeln!(w, "{}}}\n", indent);
+            }
+            _ => unreachable!("only structs and enums are named"),
+        }
+        Ok(())
+    }
+
+    /// Writes the fields of a struct or enum variant, indented by `indent`.
+    fn write_fields(
+        &self,
+        w: &mut String,
+        fields: &[Field],
+        indent: &str,
+        visibility: &str,
+    ) -> Result<()> {
+        if fields.is_empty() {
+            return Ok(());
+        }
+        if fields.iter().all(|field| field.name.is_some()) {
+            let _ = writeln!(w, " {{");
+            for field in fields {
+                write_docs(w, &field.docs, &format!("{}    ", indent));
+                let _ = writeln!(
+                    w,
+                    "{}    {}{}: {},",
+                    indent,
+                    visibility,
+                    field.name.as_deref().unwrap_or_default(),
+                    self.rust_type(field.ty)?
+                );
+            }
+            let _ = write!(w, "{}}}", indent);
+        } else {
+            let fields = fields
+                .iter()
+                .map(|field| Ok(format!("{}{}", visibility, self.rust_type(field.ty)?)))
+                .collect::<Result<Vec<_>>>()?;
+            let _ = write!(w, "({})", fields.join(", "));
+        }
+        Ok(())
+    }
+
+    /// Returns the Rust type of an argument, preferring the name of the environment type
+    /// it was declared with, e.g. `Balance` instead of `u128`, if those are in scope.
+    pub fn arg_type(&self, ty: &TypeRef) -> Result<String> {
+        match ty.display_name.as_slice() {
+            [name] if self.env_types && ENV_TYPES.contains(&name.as_str()) => Ok(name.clone()),
+            _ => self.rust_type(ty.id),
+        }
+    }
+
+    /// Returns the Rust type of the type `id`.
+    pub fn rust_type(&self, id: u32) -> Result<String> {
+        if let Some(name) = self.names.get(&id) {
+            return Ok(name.clone());
+        }
+        let ty = self.abi.types.resolve(id)?;
+        Ok(match &ty.def {
+            TypeDef::Primitive(primitive) => {
+                let name = primitive.name();
+                if name == "String" {
+                    self.uses_string.set(true);
+                }
+                name.to_string()
+            }
+            TypeDef::Sequence(ty) => {
+                self.uses_vec.set(true);
+                format!("Vec<{}>", self.rust_type(*ty)?)
+            }
+            TypeDef::Array { len, ty } => format!("[{}; {}]", self.rust_type(*ty)?, len),
+            TypeDef::Tuple(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|ty| self.rust_type(*ty))
+                    .collect::<Result<Vec<_>>>()?;
+                match fields.as_slice() {
+                    [field] => format!("({},)", field),
+                    fields => format!("({})", fields.join(", ")),
+                }
+            }
+            TypeDef::Compact(ty) => self.rust_type(*ty)?,
+            TypeDef::BitSequence => anyhow::bail!("Bit sequences are not supported by ink!"),
+            TypeDef::Composite(_) | TypeDef::Variant(_) => {
+                let name = ty
+                    .path
+                    .last()
+                    .ok_or_else(|| anyhow::anyhow!("Type {} has no name", id))?;
+                let params = ty
+                    .params
+                    .iter()
+                    .filter_map(|(_, ty)| ty.map(|ty| self.rust_type(ty)))
+                    .collect::<Result<Vec<_>>>()?;
+                if params.is_empty() {
+                    name.clone()
+                } else {
+                    format!("{}<{}>", name, params.join(", "))
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn generates_typed_calls() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let client = generate_client(&abi).unwrap();
+
+        assert!(client.manifest.contains("name = \"bank-client\""));
+        assert!(client.lib.starts_with(GENERATED_HEADER));
+        assert!(client.lib.contains("pub struct AccountId(pub [u8; 32]);"));
+        assert!(client
+            .lib
+            .contains("pub enum Error {\n    InsufficientBalance,"));
+        assert!(client.lib.contains(
+            "    /// Creates a bank.\n    pub fn new(limit: u128) -> Call<()> {\n        Call::new(\"new\", [0x9b, 0xae, 0x9d, 0x5e], true, true)\n            .push_arg(&limit)\n    }"
+        ));
+        assert!(client
+            .lib
+            .contains("    pub fn balance_of(owner: AccountId) -> Call<u128> {"));
+        assert!(client
+            .lib
+            .contains("    pub fn withdraw(value: u128) -> Call<Result<(), Error>> {"));
+    }
+}
@@ -0,0 +1,336 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Maps the ABI of an ink! contract to the JSON ABI of Solidity contracts.
+//!
+//! Not every ink! type has a Solidity counterpart: constructors, messages and events using
+//! e.g. an `Option` or an enum with data are left out of the Solidity ABI.
+
+use super::fn_names;
+use crate::abi::{ContractAbi, Field, MessageSpec, Primitive, TypeDef, TypeRef};
+use anyhow::Result;
+use serde_json::{json, Map, Value};
+
+/// The Solidity ABI of a contract.
+pub struct SolidityAbi {
+    /// The entries of the ABI JSON.
+    pub entries: Vec<Value>,
+    /// The constructors, messages and events which have no Solidity counterpart and the
+    /// reason why.
+    pub skipped: Vec<(String, String)>,
+}
+
+/// Maps the constructors, messages and events of `abi` to a Solidity ABI.
+///
+/// As a Solidity contract has a single constructor, only the first constructor of the ink!
+/// contract is part of it.
+pub fn generate(abi: &ContractAbi) -> SolidityAbi {
+    let mut solidity = SolidityAbi {
+        entries: Vec::new(),
+        skipped: Vec::new(),
+    };
+    let mapper = TypeMapper { abi };
+
+    for (i, constructor) in abi.constructors.iter().enumerate() {
+        if i > 0 {
+            solidity.skipped.push((
+                constructor.label.clone(),
+                "Solidity contracts have a single constructor".to_string(),
+            ));
+            continue;
+        }
+        match mapper.params(constructor) {
+            Ok(inputs) => solidity.entries.push(json!({
+                "type": "constructor",
+                "inputs": inputs,
+                "stateMutability": if constructor.payable { "payable" } else { "nonpayable" },
+            })),
+            Err(err) => solidity
+                .skipped
+                .push((constructor.label.clone(), err.to_string())),
+        }
+    }
+
+    for (message, name) in abi.messages.iter().zip(fn_names(&abi.messages)) {
+        let outputs = match &message.return_type {
+            Some(ty) => mapper.outputs(ty),
+            None => Ok(Vec::new()),
+        };
+        match (mapper.params(message), outputs) {
+            (Ok(inputs), Ok(outputs)) => solidity.entries.push(json!({
+                "type": "function",
+                "name": name,
+                "inputs": inputs,
+                "outputs": outputs,
+                "stateMutability": match (message.mutates, message.payable) {
+                    (_, true) => "payable",
+                    (true, false) => "nonpayable",
+                    (false, false) => "view",
+                },
+            })),
+            (Err(err), _) | (_, Err(err)) => solidity
+                .skipped
+                .push((message.label.clone(), err.to_string())),
+        }
+    }
+
+    for event in &abi.events {
+        let inputs = event
+            .args
+            .iter()
+            .map(|arg| {
+                let mut param = mapper.param(&arg.label, arg.ty.id)?;
+                param.insert("indexed".into(), Value::Bool(arg.indexed));
+                Ok(Value::Object(param))
+            })
+            .collect::<Result<Vec<_>>>();
+        match inputs {
+            Ok(inputs) => solidity.entries.push(json!({
+                "type": "event",
+                "name": event.label,
+                "inputs": inputs,
+                "anonymous": false,
+            })),
+            Err(err) => solidity
+                .skipped
+                .push((event.label.clone(), err.to_string())),
+        }
+    }
+    solidity
+}
+
+struct TypeMapper<'a> {
+    abi: &'a ContractAbi,
+}
+
+impl<'a> TypeMapper<'a> {
+    /// Returns the Solidity parameters of the arguments of `spec`.
+    fn params(&self, spec: &MessageSpec) -> Result<Vec<Value>> {
+        spec.args
+            .iter()
+            .map(|arg| Ok(Value::Object(self.param(&arg.label, arg.ty.id)?)))
+            .collect()
+    }
+
+    /// Returns the Solidity outputs of a message returning `ty`.
+    ///
+    /// A `Result` is mapped to the value it holds if it is `Ok`, the error is not part of
+    /// the Solidity ABI.
+    fn outputs(&self, ty: &TypeRef) -> Result<Vec<Value>> {
+        let mut id = ty.id;
+        while let Some(ok) = self.result_ok(id)? {
+            id = ok;
+        }
+        match &self.abi.types.resolve(id)?.def {
+            TypeDef::Tuple(fields) if fields.is_empty() => Ok(Vec::new()),
+            _ => Ok(vec![Value::Object(self.param("", id)?)]),
+        }
+    }
+
+    /// Returns the type of the `Ok` variant if the type `id` is a `Result`.
+    fn result_ok(&self, id: u32) -> Result<Option<u32>> {
+        let ty = self.abi.types.resolve(id)?;
+        match &ty.def {
+            TypeDef::Variant(variants) if ty.path.last().map(String::as_str) == Some("Result") => {
+                Ok(variants
+                    .iter()
+                    .find(|variant| variant.name == "Ok")
+                    .and_then(|variant| variant.fields.first())
+                    .map(|field| field.ty))
+            }
+            _ => Ok(None),
+        }
+    }
+
+    /// Returns the Solidity parameter `name` of the type `id`.
+    fn param(&self, name: &str, id: u32) -> Result<Map<String, Value>> {
+        let (sol_type, components) = self.sol_type(id, 0)?;
+        let mut param = Map::new();
+        param.insert("name".into(), Value::String(name.to_string()));
+        if let (Some(_), Some(struct_name)) = (&components, self.abi.types.resolve(id)?.path.last())
+        {
+            // the Solidity compiler names structs in the `internalType`, e.g. `struct Order[]`
+            let internal_type = format!(
+                "struct {}{}",
+                struct_name,
+                sol_type.trim_start_matches("tuple")
+            );
+            param.insert("internalType".into(), Value::String(internal_type));
+        }
+        param.insert("type".into(), Value::String(sol_type));
+        if let Some(components) = components {
+            param.insert("components".into(), Value::Array(components));
+        }
+        Ok(param)
+    }
+
+    /// Returns the Solidity type of the type `id` and the components of tuples.
+    fn sol_type(&self, id: u32, depth: usize) -> Result<(String, Option<Vec<Value>>)> {
+        if depth > 16 {
+            anyhow::bail!("Recursive types are not supported")
+        }
+        let ty = self.abi.types.resolve(id)?;
+        let is_byte = |id: u32| {
+            matches!(
+                self.abi.types.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(Primitive::U8))
+            )
+        };
+        Ok(match &ty.def {
+            TypeDef::Primitive(primitive) => (primitive_type(*primitive).to_string(), None),
+            TypeDef::Compact(ty) => self.sol_type(*ty, depth + 1)?,
+            TypeDef::Sequence(ty) if is_byte(*ty) => ("bytes".to_string(), None),
+            TypeDef::Array { len, ty } if is_byte(*ty) && *len <= 32 => {
+                (format!("bytes{}", len), None)
+            }
+            TypeDef::Sequence(ty) => {
+                let (ty, components) = self.sol_type(*ty, depth + 1)?;
+                (format!("{}[]", ty), components)
+            }
+            TypeDef::Array { len, ty } => {
+                let (ty, components) = self.sol_type(*ty, depth + 1)?;
+                (format!("{}[{}]", ty, len), components)
+            }
+            TypeDef::Tuple(fields) if fields.is_empty() => {
+                anyhow::bail!("The unit type `()` has no Solidity counterpart")
+            }
+            TypeDef::Tuple(fields) => {
+                let components = fields
+                    .iter()
+                    .map(|ty| self.component("", *ty, depth))
+                    .collect::<Result<_>>()?;
+                ("tuple".to_string(), Some(components))
+            }
+            TypeDef::Composite(_)
+                if matches!(
+                    ty.path.last().map(String::as_str),
+                    Some("H160") | Some("Address")
+                ) =>
+            {
+                ("address".to_string(), None)
+            }
+            // newtypes like `AccountId` are encoded like the type they wrap
+            TypeDef::Composite(fields) if fields.len() == 1 => {
+                self.sol_type(fields[0].ty, depth + 1)?
+            }
+            TypeDef::Composite(fields) if !fields.is_empty() => {
+                ("tuple".to_string(), Some(self.components(fields, depth)?))
+            }
+            TypeDef::Variant(variants)
+                if !variants.is_empty()
+                    && variants.len() <= 256
+                    && variants.iter().all(|variant| variant.fields.is_empty()) =>
+            {
+                ("uint8".to_string(), None)
+            }
+            _ => anyhow::bail!(
+                "The type `{}` has no Solidity counterpart",
+                ty.path.last().map(String::as_str).unwrap_or("?")
+            ),
+        })
+    }
+
+    fn components(&self, fields: &[Field], depth: usize) -> Result<Vec<Value>> {
+        fields
+            .iter()
+            .map(|field| self.component(field.name.as_deref().unwrap_or_default(), field.ty, depth))
+            .collect()
+    }
+
+    fn component(&self, name: &str, id: u32, depth: usize) -> Result<Value> {
+        let (ty, components) = self.sol_type(id, depth + 1)?;
+        let mut component = Map::new();
+        component.insert("name".into(), Value::String(name.to_string()));
+        component.insert("type".into(), Value::String(ty));
+        if let Some(components) = components {
+            component.insert("components".into(), Value::Array(components));
+        }
+        Ok(Value::Object(component))
+    }
+}
+
+/// Returns the Solidity type of `primitive`.
+fn primitive_type(primitive: Primitive) -> &'static str {
+    match primitive {
+        Primitive::Bool => "bool",
+        Primitive::Str => "string",
+        Primitive::Char => "uint32",
+        Primitive::U8 => "uint8",
+        Primitive::U16 => "uint16",
+        Primitive::U32 => "uint32",
+        Primitive::U64 => "uint64",
+        Primitive::U128 => "uint128",
+        Primitive::U256 => "uint256",
+        Primitive::I8 => "int8",
+        Primitive::I16 => "int16",
+        Primitive::I32 => "int32",
+        Primitive::I64 => "int64",
+        Primitive::I128 => "int128",
+        Primitive::I256 => "int256",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::abi::tests::metadata_v1;
+
+    #[test]
+    fn maps_messages_to_solidity() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let solidity = generate(&abi);
+
+        assert_eq!(
+            solidity.entries[0],
+            json!({
+                "type": "constructor",
+                "inputs": [{ "name": "limit", "type": "uint128" }],
+                "stateMutability": "payable",
+            })
+        );
+        assert_eq!(
+            solidity.entries[1],
+            json!({
+                "type": "function",
+                "name": "deposit",
+                "inputs": [],
+                "outputs": [],
+                "stateMutability": "payable",
+            })
+        );
+        assert_eq!(
+            solidity.entries[2],
+            json!({
+                "type": "function",
+                "name": "balance_of",
+                "inputs": [{ "name": "owner", "type": "bytes32" }],
+                "outputs": [{ "name": "", "type": "uint128" }],
+                "stateMutability": "view",
+            })
+        );
+        // the error of a `Result` is not part of the ABI
+        assert_eq!(solidity.entries[3]["outputs"], json!([]));
+        assert_eq!(solidity.entries[3]["stateMutability"], "nonpayable");
+
+        assert_eq!(
+            solidity.skipped,
+            vec![(
+                "Deposited".to_string(),
+                "The type `Option` has no Solidity counterpart".to_string()
+            )]
+        );
+    }
+}
@@ -0,0 +1,403 @@
+// Copyright 2018-2021 Parity Technologies (UK) Ltd.
+// This file is part of cargo-contract.
+//
+// cargo-contract is free software: you can redistribute it and/or modify
+// it under the terms of the GNU General Public License as published by
+// the Free Software Foundation, either version 3 of the License, or
+// (at your option) any later version.
+//
+// cargo-contract is distributed in the hope that it will be useful,
+// but WITHOUT ANY WARRANTY; without even the implied warranty of
+// MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+// GNU General Public License for more details.
+//
+// You should have received a copy of the GNU General Public License
+// along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
+
+//! Generates the TypeScript types of a contract for the `client/` package created by
+//! `cargo contract new --with-frontend-client`.
+
+use super::type_names;
+use crate::{
+    abi::{ContractAbi, Field, MessageSpec, Primitive, Type, TypeDef},
+    maybe_println, Verbosity,
+};
+use anyhow::Result;
+use colored::Colorize;
+use heck::MixedCase as _;
+use std::{collections::BTreeMap, fmt::Write as _, fs, path::Path};
+
+/// The first line of generated files, only files starting with it are overwritten.
+pub const GENERATED_HEADER: &str = "// This file is generated by `cargo contract build`";
+
+/// The nesting depth after which types are no longer expanded, guarding against recursion.
+const MAX_DEPTH: usize = 16;
+
+/// Regenerates the TypeScript types of the `client/` package next to the contract at
+/// `project_dir`, if there is one.
+///
+/// Does nothing if the project has no `client/src/contract.ts` starting with the
+/// [`GENERATED_HEADER`].
+pub fn update_client(project_dir: &Path, metadata_path: &Path, verbosity: Verbosity) -> Result<()> {
+    let client_src = project_dir.join("client").join("src");
+    let contract_ts = client_src.join("contract.ts");
+    match fs::read_to_string(&contract_ts) {
+        Ok(contents) if contents.starts_with(GENERATED_HEADER) => (),
+        _ => return Ok(()),
+    }
+
+    let abi = ContractAbi::load(metadata_path)?;
+    fs::write(&contract_ts, generate(&abi)?)?;
+    fs::copy(metadata_path, client_src.join("metadata.json"))?;
+    maybe_println!(
+        verbosity,
+        " {} {}",
+        "[==]".bold(),
+        format!("Updated the TypeScript client in {}", client_src.display())
+            .bright_green()
+            .bold()
+    );
+    Ok(())
+}
+
+/// Generates the `contract.ts` module with the types of the contract.
+pub fn generate(abi: &ContractAbi) -> Result<String> {
+    let names = type_names(abi);
+    let gen = Generator { abi, names: &names };
+    let mut out = String::new();
+
+    let contract = abi.name.as_deref().unwrap_or("the contract");
+    // writing to a `String` never fails
+    let _ = writeln!(out, "{} from the metadata of", GENERATED_HEADER);
+    let _ = writeln!(out, "// {}, do not edit it.\n", contract);
+    write_docs(&mut out, &abi.docs, "");
+    let _ = writeln!(
+        out,
+        "/** Numbers which may exceed `Number.MAX_SAFE_INTEGER`. */"
+    );
+    let _ = writeln!(out, "export type Numeric = number | string;\n");
+
+    for (id, name) in &names {
+        let ty = abi.types.resolve(*id)?;
+        write_docs(&mut out, &ty.docs, "");
+        let _ = writeln!(out, "{};\n", gen.declaration(name, ty));
+    }
+
+    gen.write_args(
+        &mut out,
+        "ConstructorArgs",
+        "constructors",
+        &abi.constructors,
+    );
+    gen.write_args(&mut out, "MessageArgs", "messages", &abi.messages);
+
+    let _ = writeln!(out, "/** The return types of the messages, by method. */");
+    let _ = writeln!(out, "export interface MessageReturns {{");
+    for message in &abi.messages {
+        let ty = match &message.return_type {
+            Some(ty) => gen.ts_type(ty.id, 0),
+            None => "null".to_string(),
+        };
+        let _ = writeln!(out, "  {}: {};", method(&message.label), ty);
+    }
+    let _ = writeln!(out, "}}\n");
+
+    let _ = writeln!(out, "/** The arguments of the events, by event. */");
+    let _ = writeln!(out, "export interface EventArgs {{");
+    for event in &abi.events {
+        write_docs(&mut out, &event.docs, "  ");
+        let _ = writeln!(out, "  {}: {{", event.label);
+        for arg in &event.args {
+            let _ = writeln!(out, "    {}: {};", arg.label, gen.ts_type(arg.ty.id, 0));
+        }
+        let _ = writeln!(out, "  }};");
+    }
+    let _ = writeln!(out, "}}\n");
+
+    let _ = writeln!(
+        out,
+        "/** Describes a constructor or message of the contract. */"
+    );
+    let _ = writeln!(out, "export interface MessageInfo {{");
+    let _ = writeln!(out, "  /** The label in the contract, e.g. `transfer`. */");
+    let _ = writeln!(out, "  label: string;");
+    let _ = writeln!(out, "  /** The name used by `@polkadot/api-contract`. */");
+    let _ = writeln!(out, "  method: string;");
+    let _ = writeln!(out, "  selector: string;");
+    let _ = writeln!(out, "  mutates: boolean;");
+    let _ = writeln!(out, "  payable: boolean;");
+    let _ = writeln!(out, "  args: string[];");
+    let _ = writeln!(out, "}}\n");
+    write_infos(&mut out, "constructors", &abi.constructors);
+    write_infos(&mut out, "messages", &abi.messages);
+
+    Ok(out)
+}
+
+/// Returns the name `@polkadot/api-contract` uses for the message `label`.
+fn method(label: &str) -> String {
+    label.replace("::", "_").to_mixed_case()
+}
+
+fn write_docs(out: &mut String, docs: &[String], indent: &str) {
+    let docs = docs
+        .iter()
+        .map(|line| line.trim())
+        .filter(|line| !line.is_empty())
+        .collect::<Vec<_>>();
+    if docs.is_empty() {
+        return;
+    }
+    let _ = writeln!(out, "{}/**", indent);
+    for line in docs {
+        let _ = writeln!(out, "{} * {}", indent, line.replace("*/", "*\\/"));
+    }
+    let _ = writeln!(out, "{} */", indent);
+}
+
+fn write_infos(out: &mut String, name: &str, specs: &[MessageSpec]) {
+    let _ = writeln!(out, "export const {}: MessageInfo[] = [", name);
+    for spec in specs {
+        let args = spec
+            .args
+            .iter()
+            .map(|arg| format!("'{}'", arg.label))
+            .collect::<Vec<_>>();
+        let _ = writeln!(
+            out,
+            "  {{ label: '{}', method: '{}', selector: '{}', mutates: {}, payable: {}, args: [{}] }},",
+            spec.label,
+            method(&spec.label),
+            spec.selector,
+            spec.mutates,
+            spec.payable,
+            args.join(", ")
+        );
+    }
+    let _ = writeln!(out, "];");
+    if name == "constructors" {
+        let _ = writeln!(out);
+    }
+}
+
+struct Generator<'a> {
+    abi: &'a ContractAbi,
+    names: &'a BTreeMap<u32, String>,
+}
+
+impl<'a> Generator<'a> {
+    /// Returns the TypeScript declaration of the named type `ty`.
+    fn declaration(&self, name: &str, ty: &Type) -> String {
+        match (ty.path.last().map(String::as_str), &ty.def) {
+            (Some("AccountId") | Some("Hash"), _) => {
+                format!("export type {} = string", name)
+            }
+            (_, TypeDef::Composite(fields)) if fields.iter().all(|f| f.name.is_some()) => {
+                let mut out = format!("export interface {} {{\n", name);
+                for field in fields {
+                    write_docs(&mut out, &field.docs, "  ");
+                    let _ = writeln!(
+                        out,
+                        "  {}: {};",
+                        field.name.as_deref().unwrap_or_default(),
+                        self.ts_type(field.ty, 1)
+                    );
+                }
+                // interfaces are no statements, the `;` appended by the caller is harmless
+                out.push('}');
+                out
+            }
+            _ => format!("export type {} = {}", name, self.structure(ty, 1)),
+        }
+    }
+
+    /// Writes the interface `name` with the argument types of `specs`.
+    fn write_args(&self, out: &mut String, name: &str, kind: &str, specs: &[MessageSpec]) {
+        let _ = writeln!(out, "/** The arguments of the {}, by method. */", kind);
+        let _ = writeln!(out, "export interface {} {{", name);
+        for spec in specs {
+            write_docs(out, &spec.docs, "  ");
+            let args = spec
+                .args
+                .iter()
+                .map(|arg| format!("{}: {}", arg.label, self.ts_type(arg.ty.id, 0)))
+                .collect::<Vec<_>>();
+            let _ = writeln!(out, "  {}: [{}];", method(&spec.label), args.join(", "));
+        }
+        let _ = writeln!(out, "}}\n");
+    }
+
+    /// Returns the TypeScript type of the type `id`, as decoded by `@polkadot/api`.
+    fn ts_type(&self, id: u32, depth: usize) -> String {
+        if let Some(name) = self.names.get(&id) {
+            return name.clone();
+        }
+        if depth > MAX_DEPTH {
+            return "unknown".to_string();
+        }
+        match self.abi.types.resolve(id) {
+            Ok(ty) => self.structure(ty, depth + 1),
+            Err(_) => "unknown".to_string(),
+        }
+    }
+
+    /// Returns the TypeScript type of the structure of `ty`.
+    fn structure(&self, ty: &Type, depth: usize) -> String {
+        let is_byte = |id: u32| {
+            matches!(
+                self.abi.types.resolve(id).map(|ty| &ty.def),
+                Ok(TypeDef::Primitive(Primitive::U8))
+            )
+        };
+        match &ty.def {
+            TypeDef::Primitive(primitive) => match primitive {
+                Primitive::Bool => "boolean",
+                Primitive::Char | Primitive::Str => "string",
+                Primitive::U8
+                | Primitive::U16
+                | Primitive::U32
+                | Primitive::I8
+                | Primitive::I16
+                | Primitive::I32 => "number",
+                _ => "Numeric",
+            }
+            .to_string(),
+            // byte sequences are passed around as hex strings
+            TypeDef::Sequence(ty) | TypeDef::Array { ty, .. } if is_byte(*ty) => {
+                "string".to_string()
+            }
+            TypeDef::Sequence(ty) | TypeDef::Array { ty, .. } => {
+                format!("{}[]", wrap(self.ts_type(*ty, depth)))
+            }
+            TypeDef::Tuple(fields) if fields.is_empty() => "null".to_string(),
+            TypeDef::Tuple(fields) => {
+                let fields = fields
+                    .iter()
+                    .map(|ty| self.ts_type(*ty, depth))
+                    .collect::<Vec<_>>();
+                format!("[{}]", fields.join(", "))
+            }
+            TypeDef::Compact(ty) => self.ts_type(*ty, depth),
+            TypeDef::BitSequence => "string".to_string(),
+            TypeDef::Composite(_)
+                if matches!(
+                    ty.path.last().map(String::as_str),
+                    Some("AccountId") | Some("Hash")
+                ) =>
+            {
+                "string".to_string()
+            }
+            TypeDef::Composite(fields) => self.fields(fields, depth),
+            TypeDef::Variant(variants) if ty.path.last().map(String::as_str) == Some("Option") => {
+                let some = variants
+                    .iter()
+                    .find(|variant| variant.name == "Some")
+                    .map(|variant| self.fields(&variant.fields, depth))
+                    .unwrap_or_else(|| "unknown".to_string());
+                format!("{} | null", some)
+            }
+            TypeDef::Variant(variants) if variants.is_empty() => "never".to_string(),
+            TypeDef::Variant(variants) => variants
+                .iter()
+                .map(|variant| {
+                    if variant.fields.is_empty() {
+                        format!("'{}'", variant.name)
+                    } else {
+                        format!(
+                            "{{ {}: {} }}",
+                            variant.name,
+                            self.fields(&variant.fields, depth)
+                        )
+                    }
+                })
+                .collect::<Vec<_>>()
+                .join(" | "),
+        }
+    }
+
+    /// Returns the TypeScript type of a struct or variant with `fields`.
+    fn fields(&self, fields: &[Field], depth: usize) -> String {
+        match fields {
+            [] => "null".to_string(),
+            [field] if field.name.is_none() => self.ts_type(field.ty, depth),
+            fields if fields.iter().all(|field| field.name.is_some()) => {
+                let fields = fields
+                    .iter()
+                    .map(|field| {
+                        format!(
+                            "{}: {}",
+                            field.name.as_deref().unwrap_or_default(),
+                            self.ts_type(field.ty, depth)
+                        )
+                    })
+                    .collect::<Vec<_>>();
+                format!("{{ {} }}", fields.join("; "))
+            }
+            fields => {
+                let fields = fields
+                    .iter()
+                    .map(|field| self.ts_type(field.ty, depth))
+                    .collect::<Vec<_>>();
+                format!("[{}]", fields.join(", "))
+            }
+        }
+    }
+}
+
+/// Wraps union types in parentheses, e.g. for `(string | null)[]`.
+fn wrap(ty: String) -> String {
+    if ty.contains(" | ") {
+        format!("({})", ty)
+    } else {
+        ty
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{abi::tests::metadata_v1, util::tests::with_tmp_dir};
+
+    #[test]
+    fn generates_types_of_messages() {
+        let abi = ContractAbi::from_json(&metadata_v1()).unwrap();
+        let ts = generate(&abi).unwrap();
+
+        assert!(ts.starts_with(GENERATED_HEADER));
+        assert!(ts.contains("export type AccountId = string;"));
+        assert!(ts.contains(
+            "export type Error = 'InsufficientBalance' | { LimitExceeded: { limit: Numeric } };"
+        ));
+        assert!(ts.contains("  new: [limit: Numeric];"));
+        assert!(ts.contains("  ledgerBalanceOf: [owner: AccountId];"));
+        assert!(ts.contains("  deposit: null;"));
+        assert!(ts.contains("  withdraw: { Ok: null } | { Err: Error };"));
+        assert!(ts.contains("    from: AccountId | null;"));
+        assert!(ts.contains(
+            "{ label: 'Ledger::balance_of', method: 'ledgerBalanceOf', selector: '0x0f755a56', \
+             mutates: false, payable: false, args: ['owner'] },"
+        ));
+    }
+
+    #[test]
+    fn only_updates_generated_clients() {
+        with_tmp_dir(|path| {
+            let metadata_path = path.join("metadata.json");
+            fs::write(&metadata_path, metadata_v1().to_string())?;
+            let client_src = path.join("client").join("src");
+            fs::create_dir_all(&client_src)?;
+            let contract_ts = client_src.join("contract.ts");
+
+            fs::write(&contract_ts, "// my own types")?;
+            update_client(path, &metadata_path, Verbosity::Quiet)?;
+            assert_eq!(fs::read_to_string(&contract_ts)?, "// my own types");
+            assert!(!client_src.join("metadata.json").exists());
+
+            fs::write(&contract_ts, GENERATED_HEADER)?;
+            update_client(path, &metadata_path, Verbosity::Quiet)?;
+            assert!(fs::read_to_string(&contract_ts)?.contains("export interface MessageArgs"));
+            assert!(client_src.join("metadata.json").exists());
+            Ok(())
+        })
+    }
+}
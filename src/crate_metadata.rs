@@ -19,7 +19,10 @@ use anyhow::{Context, Result};
 use cargo_metadata::{Metadata as CargoMetadata, MetadataCommand, Package};
 use semver::Version;
 use serde_json::{Map, Value};
-use std::{fs, path::PathBuf};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+};
 use toml::value;
 use url::Url;
 
@@ -35,6 +38,7 @@ pub struct CrateMetadata {
     pub ink_version: Version,
     pub documentation: Option<Url>,
     pub homepage: Option<Url>,
+    pub source_url: Option<Url>,
     pub user: Option<Map<String, Value>>,
     pub target_directory: PathBuf,
 }
@@ -75,24 +79,22 @@ impl CrateMetadata {
         dest_wasm.push(lib_name.clone());
         dest_wasm.set_extension("wasm");
 
+        // ink! 3 contracts depend on `ink_lang`, ink! 4 contracts on the `ink` crate
         let ink_version = metadata
             .packages
             .iter()
-            .find_map(|package| {
-                if package.name == "ink_lang" {
-                    Some(
-                        Version::parse(&package.version.to_string())
-                            .expect("Invalid ink_lang version string"),
-                    )
-                } else {
-                    None
-                }
+            .find(|package| match package.name.as_str() {
+                "ink_lang" => true,
+                "ink" => package.version.major >= 4,
+                _ => false,
             })
-            .ok_or_else(|| anyhow::anyhow!("No 'ink_lang' dependency found"))?;
+            .map(|package| package.version.clone())
+            .ok_or_else(|| anyhow::anyhow!("No 'ink_lang' or 'ink' dependency found"))?;
 
         let ExtraMetadata {
             documentation,
             homepage,
+            source_url,
             user,
         } = get_cargo_toml_metadata(manifest_path)?;
 
@@ -106,11 +108,28 @@ impl CrateMetadata {
             ink_version,
             documentation,
             homepage,
+            source_url,
             user,
             target_directory: target_directory.into(),
         };
         Ok(crate_metadata)
     }
+
+    /// Moves the build artifacts of the contract into `target_directory`.
+    pub fn with_target_directory(mut self, target_directory: PathBuf) -> Self {
+        let relocate = |path: &Path| {
+            let relative = path
+                .strip_prefix(&self.target_directory)
+                .expect("artifacts are placed in the target directory");
+            target_directory.join(relative)
+        };
+        let original_wasm = relocate(&self.original_wasm);
+        let dest_wasm = relocate(&self.dest_wasm);
+        self.original_wasm = original_wasm;
+        self.dest_wasm = dest_wasm;
+        self.target_directory = target_directory;
+        self
+    }
 }
 
 /// Get the result of `cargo metadata`, together with the root package id.
@@ -141,6 +160,7 @@ fn get_cargo_metadata(manifest_path: &ManifestPath) -> Result<(CargoMetadata, Pa
 struct ExtraMetadata {
     documentation: Option<Url>,
     homepage: Option<Url>,
+    source_url: Option<Url>,
     user: Option<Map<String, Value>>,
 }
 
@@ -149,10 +169,14 @@ fn get_cargo_toml_metadata(manifest_path: &ManifestPath) -> Result<ExtraMetadata
     let toml = fs::read_to_string(manifest_path)?;
     let toml: value::Table = toml::from_str(&toml)?;
 
-    let get_url = |field_name| -> Result<Option<Url>> {
-        toml.get("package")
-            .ok_or_else(|| anyhow::anyhow!("package section not found"))?
-            .get(field_name)
+    let package = toml
+        .get("package")
+        .ok_or_else(|| anyhow::anyhow!("package section not found"))?;
+    let contract = package.get("metadata").and_then(|v| v.get("contract"));
+
+    let get_url = |section: Option<&value::Value>, field_name| -> Result<Option<Url>> {
+        section
+            .and_then(|v| v.get(field_name))
             .and_then(|v| v.as_str())
             .map(Url::parse)
             .transpose()
@@ -160,13 +184,15 @@ fn get_cargo_toml_metadata(manifest_path: &ManifestPath) -> Result<ExtraMetadata
             .map_err(Into::into)
     };
 
-    let documentation = get_url("documentation")?;
-    let homepage = get_url("homepage")?;
+    let documentation = get_url(Some(package), "documentation")?;
+    // a homepage in the `[package.metadata.contract]` takes precedence over the package's
+    let homepage = match get_url(contract, "homepage")? {
+        Some(homepage) => Some(homepage),
+        None => get_url(Some(package), "homepage")?,
+    };
+    let source_url = get_url(contract, "source-url")?;
 
-    let user = toml
-        .get("package")
-        .and_then(|v| v.get("metadata"))
-        .and_then(|v| v.get("contract"))
+    let user = contract
         .and_then(|v| v.get("user"))
         .and_then(|v| v.as_table())
         .map(|v| {
@@ -178,6 +204,7 @@ fn get_cargo_toml_metadata(manifest_path: &ManifestPath) -> Result<ExtraMetadata
     Ok(ExtraMetadata {
         documentation,
         homepage,
+        source_url,
         user,
     })
 }
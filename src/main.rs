@@ -14,7 +14,9 @@
 // You should have received a copy of the GNU General Public License
 // along with cargo-contract.  If not, see <http://www.gnu.org/licenses/>.
 
+mod abi;
 mod cmd;
+mod codegen;
 mod crate_metadata;
 mod util;
 mod validate_wasm;
@@ -22,18 +24,30 @@ mod workspace;
 
 use self::workspace::ManifestPath;
 
-use crate::cmd::{metadata::MetadataResult, BuildCommand, CheckCommand, TestCommand};
+use crate::cmd::{
+    metadata::MetadataResult, BenchmarkCommand, BuildCommand, BundleCommand, CheckCommand,
+    DecodeCommand, DecodeTypeCommand, DeploymentCommand, DocCommand, EncodeCommand, FuzzCommand,
+    GenerateClientCommand, HashCommand, InfoCommand, MetadataCommand, NewCommand, NodeCommand,
+    SandboxCommand, SelectorCommand, TestCommand,
+};
 
 #[cfg(feature = "extrinsics")]
-use sp_core::{crypto::Pair, sr25519, H256};
+use crate::cmd::AccountId;
+#[cfg(feature = "extrinsics")]
+use crate::cmd::{
+    AccountCommand, AddressBookCommand, BatchCommand, CallCommand, ConvertAddressCommand,
+    ConvertBalanceCommand, DecodeErrorCommand, DownloadCommand, EstimateGasCommand, EthereumSigner,
+    InstantiateCommand, KeyCommand, KeypairSigner, ReplCommand, RpcCommand, Signer, StorageCommand,
+    SubmitSignedCommand, Submitted, UpgradeCommand, VerifyCommand, WatchCommand, XcmCallCommand,
+};
+#[cfg(feature = "extrinsics")]
+use sp_core::{crypto::AccountId32, ecdsa, sr25519, H256};
 use std::{
     convert::TryFrom,
     fmt::{Display, Formatter, Result as DisplayResult},
     path::PathBuf,
     str::FromStr,
 };
-#[cfg(feature = "extrinsics")]
-use subxt::PairSigner;
 
 use anyhow::{Error, Result};
 use colored::Colorize;
@@ -70,33 +84,403 @@ impl std::str::FromStr for HexData {
     }
 }
 
+/// The url of the node of commands without `--url` and `--network`.
+#[cfg(feature = "extrinsics")]
+const DEFAULT_URL: &str = "ws://localhost:9944";
+
+/// Arguments required for connecting to a substrate node
+#[cfg(feature = "extrinsics")]
+#[derive(Debug, StructOpt)]
+pub(crate) struct NodeOpts {
+    /// Websockets url of a substrate node [default: the url of `--network`, or
+    /// ws://localhost:9944]
+    #[structopt(name = "url", long, parse(try_from_str))]
+    url: Option<url::Url>,
+    /// The network of `contract.toml` or of the config directory whose url, account and
+    /// explorer to use
+    #[structopt(long)]
+    network: Option<String>,
+    /// The number of seconds to wait for the node, or for a submitted extrinsic, before giving
+    /// up, exiting with code 3
+    #[structopt(long)]
+    timeout: Option<u64>,
+}
+
+#[cfg(feature = "extrinsics")]
+impl NodeOpts {
+    /// Returns the url of `--url`, or else of the `--network`, which has to be configured.
+    pub fn url(&self) -> Result<url::Url> {
+        if let Some(url) = &self.url {
+            return Ok(url.clone());
+        }
+        let configured = match &self.network {
+            Some(name) => Some(
+                self.network()?
+                    .ok_or_else(|| cmd::network::not_configured(name))?,
+            ),
+            None => None,
+        };
+        match configured.and_then(|network| network.url) {
+            Some(url) => Ok(url),
+            None => Ok(url::Url::parse(DEFAULT_URL)?),
+        }
+    }
+
+    /// Returns the configuration of the `--network`, if it is configured.
+    pub fn network(&self) -> Result<Option<cmd::network::Network>> {
+        match &self.network {
+            Some(name) => cmd::network::lookup(name),
+            None => Ok(None),
+        }
+    }
+
+    /// Awaits `future`, failing with [`cmd::SubmitError::Timeout`] if the node does not answer
+    /// within `--timeout`.
+    pub async fn timeout<T>(
+        &self,
+        future: impl std::future::Future<Output = Result<T>>,
+    ) -> Result<T> {
+        match self.timeout {
+            Some(timeout) => {
+                async_std::future::timeout(std::time::Duration::from_secs(timeout), future)
+                    .await
+                    .map_err(|_| cmd::SubmitError::Timeout(None, timeout))?
+            }
+            None => future.await,
+        }
+    }
+
+    /// Blocks on `future` like [`NodeOpts::timeout`].
+    pub fn block_on<T>(&self, future: impl std::future::Future<Output = Result<T>>) -> Result<T> {
+        async_std::task::block_on(self.timeout(future))
+    }
+}
+
 /// Arguments required for creating and sending an extrinsic to a substrate node
 #[cfg(feature = "extrinsics")]
 #[derive(Debug, StructOpt)]
 pub(crate) struct ExtrinsicOpts {
-    /// Websockets url of a substrate node
-    #[structopt(
-        name = "url",
-        long,
-        parse(try_from_str),
-        default_value = "ws://localhost:9944"
-    )]
-    url: url::Url,
-    /// Secret key URI for the account deploying the contract.
+    #[structopt(flatten)]
+    node: NodeOpts,
+    /// Secret key URI for the account deploying the contract, or `-` to enter it at a hidden
+    /// prompt. Defaults to `$CARGO_CONTRACT_SURI`, which keeps it out of the process arguments
     #[structopt(name = "suri", long, short)]
-    suri: String,
+    suri: Option<Secret>,
+    /// The name of an account stored with `cargo contract account` to use instead of `--suri`
+    #[structopt(long, conflicts_with = "suri")]
+    account: Option<String>,
     /// Password for the secret key
     #[structopt(name = "password", long, short)]
-    password: Option<String>,
+    password: Option<Secret>,
+    /// The signature scheme of the secret key of `--suri` or `--account`: `ethereum` signs
+    /// with the private key of an Ethereum account for chains with `AccountId20`
+    #[structopt(
+        long,
+        default_value = "sr25519",
+        possible_values = &["sr25519", "ecdsa", "ethereum"]
+    )]
+    scheme: Scheme,
+    /// Sign with the `keypair` of `--suri` or `--account`, or with the Polkadot app of a
+    /// connected `ledger`
+    #[structopt(
+        name = "signer",
+        long = "signer",
+        possible_values = &["keypair", "ledger"],
+        conflicts_with_all = &["suri", "account"]
+    )]
+    signer_kind: Option<SignerKind>,
+    /// Sign with an external program, e.g. a bridge to a KMS or a wallet, which is handed the
+    /// payloads to sign as JSON on stdin and answers with the signatures on stdout
+    #[structopt(long, conflicts_with_all = &["suri", "account", "signer"])]
+    signer_cmd: Option<String>,
+    /// Write the unsigned extrinsic to this file instead of submitting it, to sign it on
+    /// another machine and submit it with `cargo contract submit-signed`
+    #[structopt(long, parse(from_os_str), requires = "from")]
+    offline: Option<PathBuf>,
+    /// The address of the account signing the extrinsic written with `--offline`
+    #[structopt(
+        long,
+        parse(try_from_str = cmd::parse_account),
+        requires = "offline",
+        conflicts_with_all = &["suri", "account", "signer", "signer-cmd"]
+    )]
+    from: Option<AccountId>,
+    /// The nonce to sign the extrinsic with, instead of the next nonce of the account
+    #[structopt(long, conflicts_with = "track-nonce")]
+    nonce: Option<u32>,
+    /// Track the nonces of the account in a local file, so that commands submitting
+    /// extrinsics of the same account in quick succession use distinct nonces
+    #[structopt(long)]
+    track_nonce: bool,
+    /// A tip for the block author, in the smallest unit of the chain, to prioritize the
+    /// extrinsic
+    #[structopt(long, default_value = "0")]
+    tip: u128,
+    /// Pay the fees in the asset with this id instead of the native token, on chains signing
+    /// extrinsics with the `ChargeAssetTxPayment` signed extension
+    #[structopt(long)]
+    fee_asset: Option<u32>,
+    /// The number of blocks the extrinsic is valid for from the last finalized block, rounded
+    /// up to a power of two [default: 64]
+    #[structopt(long, conflicts_with = "immortal")]
+    era: Option<u64>,
+    /// Make the extrinsic valid forever instead of for `--era` blocks
+    #[structopt(long)]
+    immortal: bool,
+    /// Wait for the extrinsic to be included `in-block`, to be `finalized`, or only to be
+    /// `broadcast` to the network
+    #[structopt(
+        long,
+        default_value = "finalized",
+        possible_values = &["in-block", "finalized", "broadcast"]
+    )]
+    wait_for: WaitFor,
+    /// Hold the extrinsic back until the best block reaches this number, then submit it
+    #[structopt(long, conflicts_with = "offline")]
+    at_block: Option<u64>,
+    /// Hold the extrinsic back for this duration after it is confirmed, e.g. `90s` or `1h 30m`
+    #[structopt(long, parse(try_from_str = cmd::schedule::parse_duration), conflicts_with = "offline")]
+    after: Option<std::time::Duration>,
+    /// Submit without showing a summary of the extrinsic and asking for confirmation, e.g. in
+    /// scripts
+    #[structopt(short = "y", long)]
+    skip_confirm: bool,
+    /// Simulate the extrinsics on a local fork of the chain at this url, served by chopsticks,
+    /// instead of submitting them to the chain
+    #[structopt(long)]
+    fork_from: Option<url::Url>,
+    /// Let `npx` install the pinned release of chopsticks for `--fork-from` if it is not cached
+    #[structopt(long, requires = "fork-from")]
+    install_chopsticks: bool,
+    /// Link the submitted extrinsic and the instantiated contract on this block explorer:
+    /// `polkadot-js`, or the url of a Subscan explorer of the chain
+    #[structopt(long)]
+    explorer: Option<cmd::Explorer>,
+    /// The number of times to reconnect to the node after the connection dropped while
+    /// submitting or watching the extrinsic
+    #[structopt(long, default_value = "3")]
+    retries: u32,
+    /// The number of seconds to wait before reconnecting, doubled after every retry
+    #[structopt(long, default_value = "2")]
+    retry_backoff: u64,
+    /// The comma separated identifiers of the signed extensions of the runtime, in its order,
+    /// instead of the ones listed in its metadata
+    #[structopt(long, use_delimiter = true)]
+    signed_extensions: Vec<String>,
+    /// Dispatch the extrinsic on behalf of this account, which added the signer as its proxy
+    #[structopt(long, parse(try_from_str = cmd::parse_account_id))]
+    proxy: Option<AccountId32>,
+    /// Approve the extrinsic as one of these comma separated signatories of a multisig
+    /// account, which submits it once `--threshold` of them approved it
+    #[structopt(
+        long,
+        parse(try_from_str = cmd::parse_account_id),
+        use_delimiter = true,
+        requires = "threshold"
+    )]
+    multisig: Vec<AccountId32>,
+    /// The number of signatories of `--multisig` which have to approve the extrinsic
+    #[structopt(long, requires = "multisig")]
+    threshold: Option<u16>,
+    /// The maximum weight of the extrinsic approved with `--multisig`, which has to cover its
+    /// execution once it is approved by the last signatory
+    #[structopt(long, default_value = "10000000000")]
+    multisig_max_weight: u64,
+    /// The secret key URI of the account, once decrypted or entered at the prompt.
+    #[structopt(skip)]
+    decrypted_suri: std::cell::RefCell<Option<Secret>>,
+    /// Whether the extrinsics were held back for `--at-block` and `--after` already.
+    #[structopt(skip)]
+    scheduled: std::cell::Cell<bool>,
 }
 
 #[cfg(feature = "extrinsics")]
 impl ExtrinsicOpts {
-    pub fn signer(&self) -> Result<PairSigner<subxt::DefaultNodeRuntime, sr25519::Pair>> {
-        let pair =
-            sr25519::Pair::from_string(&self.suri, self.password.as_ref().map(String::as_ref))
-                .map_err(|_| anyhow::anyhow!("Secret string error"))?;
-        Ok(PairSigner::new(pair))
+    pub fn signer(&self) -> Result<Box<dyn Signer>> {
+        if self.signer_kind == Some(SignerKind::Ledger) {
+            return Ok(Box::new(cmd::ledger::LedgerSigner::connect()?));
+        }
+        if let Some(command) = &self.signer_cmd {
+            return Ok(Box::new(cmd::signer_cmd::CommandSigner::connect(command)?));
+        }
+        let suri = self.suri()?;
+        let suri = suri.expose();
+        let password = self.password.as_ref().map(Secret::expose);
+        Ok(match self.scheme {
+            Scheme::Sr25519 => Box::new(KeypairSigner::<sr25519::Pair>::from_suri(suri, password)?),
+            Scheme::Ecdsa => Box::new(KeypairSigner::<ecdsa::Pair>::from_suri(suri, password)?),
+            Scheme::Ethereum => Box::new(EthereumSigner::from_suri(suri, password)?),
+        })
+    }
+
+    /// Returns the account signing the extrinsics, the `--from` account of extrinsics written
+    /// with `--offline`.
+    pub fn account_id(&self) -> Result<AccountId> {
+        match &self.from {
+            Some(from) => Ok(from.clone()),
+            None => Ok(self.signer()?.account_id().clone()),
+        }
+    }
+
+    /// Returns the block explorer of `--explorer`, or else of the `--network`.
+    pub fn explorer(&self) -> Result<Option<cmd::Explorer>> {
+        match &self.explorer {
+            Some(explorer) => Ok(Some(explorer.clone())),
+            None => Ok(self.node.network()?.and_then(|network| network.explorer)),
+        }
+    }
+
+    /// Returns the secret key URI of `--suri`, of the `--account` decrypted with its password,
+    /// of `$CARGO_CONTRACT_SURI` or of the `suri` or `account` of the `--network`, in this
+    /// order. The password of the account and the secret key URI of `--suri -` are asked for
+    /// once.
+    pub fn suri(&self) -> Result<Secret> {
+        let account = match (&self.suri, &self.account) {
+            (Some(suri), _) if suri.expose() != "-" => return Ok(suri.clone()),
+            (Some(_), _) => None,
+            (None, Some(account)) => Some(account.clone()),
+            (None, None) => {
+                if let Ok(suri) = std::env::var(SURI_VAR) {
+                    return Ok(Secret(suri));
+                }
+                match self.node.network()? {
+                    Some(cmd::network::Network {
+                        suri: Some(suri), ..
+                    }) => return Ok(Secret(suri)),
+                    Some(cmd::network::Network {
+                        account: Some(account),
+                        ..
+                    }) => Some(account),
+                    _ => anyhow::bail!("Either `--suri`, `--account` or ${} is required", SURI_VAR),
+                }
+            }
+        };
+        let mut decrypted = self.decrypted_suri.borrow_mut();
+        if decrypted.is_none() {
+            let suri = match &account {
+                Some(name) => cmd::account::decrypt_account(name)?,
+                None => rpassword::prompt_password("Secret URI: ")?,
+            };
+            *decrypted = Some(Secret(suri));
+        }
+        Ok(decrypted.clone().unwrap_or_default())
+    }
+}
+
+/// The environment variable with the secret key URI signing the extrinsics if neither
+/// `--suri` nor `--account` is given.
+#[cfg(feature = "extrinsics")]
+pub(crate) const SURI_VAR: &str = "CARGO_CONTRACT_SURI";
+
+/// A secret key URI or password, which is redacted from the `Debug` output so that it does not
+/// end up in logs and error messages.
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Default, PartialEq, Eq)]
+pub(crate) struct Secret(String);
+
+#[cfg(feature = "extrinsics")]
+impl Secret {
+    pub fn expose(&self) -> &str {
+        &self.0
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+impl std::fmt::Debug for Secret {
+    fn fmt(&self, f: &mut Formatter<'_>) -> DisplayResult {
+        f.write_str("Secret(<redacted>)")
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+impl From<&str> for Secret {
+    fn from(secret: &str) -> Self {
+        Secret(secret.to_string())
+    }
+}
+
+#[cfg(feature = "extrinsics")]
+impl FromStr for Secret {
+    type Err = std::convert::Infallible;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        Ok(input.into())
+    }
+}
+
+/// What signs the extrinsics.
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum SignerKind {
+    /// The keypair of `--suri` or `--account`.
+    Keypair,
+    /// The Polkadot app of a Ledger hardware wallet.
+    Ledger,
+}
+
+#[cfg(feature = "extrinsics")]
+impl FromStr for SignerKind {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "keypair" => Ok(SignerKind::Keypair),
+            "ledger" => Ok(SignerKind::Ledger),
+            _ => Err(format!("Unknown signer `{}`", input)),
+        }
+    }
+}
+
+/// The signature scheme of a secret key.
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum Scheme {
+    Sr25519,
+    /// The secp256k1 keys of chains with `MultiSignature`, whose accounts are the hashes of
+    /// the public keys.
+    Ecdsa,
+    /// The secp256k1 keys of chains with Ethereum-style `AccountId20`, whose accounts are the
+    /// Ethereum addresses of the keys.
+    Ethereum,
+}
+
+#[cfg(feature = "extrinsics")]
+impl FromStr for Scheme {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "sr25519" => Ok(Scheme::Sr25519),
+            "ecdsa" => Ok(Scheme::Ecdsa),
+            "ethereum" => Ok(Scheme::Ethereum),
+            _ => Err(format!("Unknown signature scheme `{}`", input)),
+        }
+    }
+}
+
+/// How long to wait for submitted extrinsics.
+#[cfg(feature = "extrinsics")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum WaitFor {
+    /// Until the extrinsic is included in a block, which may still be retracted.
+    InBlock,
+    /// Until the block the extrinsic is included in is finalized.
+    Finalized,
+    /// Until the extrinsic is accepted by the node, without knowing whether it is executed.
+    Broadcast,
+}
+
+#[cfg(feature = "extrinsics")]
+impl FromStr for WaitFor {
+    type Err = String;
+
+    fn from_str(input: &str) -> std::result::Result<Self, Self::Err> {
+        match input {
+            "in-block" => Ok(WaitFor::InBlock),
+            "finalized" => Ok(WaitFor::Finalized),
+            "broadcast" => Ok(WaitFor::Broadcast),
+            _ => Err(format!("Unknown status `{}` to wait for", input)),
+        }
     }
 }
 
@@ -437,13 +821,7 @@ impl BuildResult {
 enum Command {
     /// Setup and create a new smart contract project
     #[structopt(name = "new")]
-    New {
-        /// The name of the newly created smart contract
-        name: String,
-        /// The optional target directory for the contract project
-        #[structopt(short, long, parse(from_os_str))]
-        target_dir: Option<PathBuf>,
-    },
+    New(NewCommand),
     /// Compiles the contract, generates metadata, bundles both together in a `<name>.contract` file
     #[structopt(name = "build")]
     Build(BuildCommand),
@@ -453,6 +831,52 @@ enum Command {
     /// Test the smart contract off-chain
     #[structopt(name = "test")]
     Test(TestCommand),
+    /// Install the `substrate-contracts-node` releases the end-to-end tests run against
+    #[structopt(name = "node")]
+    Node(NodeCommand),
+    /// Instantiate and call contracts in an in-process sandbox, without a node
+    #[structopt(name = "sandbox")]
+    Sandbox(SandboxCommand),
+    /// Call the messages of a contract with random arguments in the sandbox to find failures
+    #[structopt(name = "fuzz")]
+    Fuzz(FuzzCommand),
+    /// Dry-run each message of the contract in the sandbox or on a node and compare its costs
+    /// with a saved baseline
+    #[structopt(name = "benchmark")]
+    Benchmark(BenchmarkCommand),
+    /// Convert the metadata of a built contract into other formats
+    #[structopt(name = "metadata")]
+    Metadata(MetadataCommand),
+    /// Print information about the interface of a built contract
+    #[structopt(name = "info")]
+    Info(InfoCommand),
+    /// Render the documentation of the interface of a built contract
+    #[structopt(name = "doc")]
+    Doc(DocCommand),
+    /// Extract the components of `.contract` bundles
+    #[structopt(name = "bundle")]
+    Bundle(BundleCommand),
+    /// Generate a typed client of a built contract
+    #[structopt(name = "generate-client")]
+    GenerateClient(GenerateClientCommand),
+    /// Merge `.contract` bundles and network configuration into a deployment manifest
+    #[structopt(name = "deployment")]
+    Deployment(DeploymentCommand),
+    /// Decode the input of a constructor or message or the data of an event of a contract
+    #[structopt(name = "decode")]
+    Decode(DecodeCommand),
+    /// Decode SCALE encoded bytes as a value of a type of the metadata of a contract
+    #[structopt(name = "decode-type")]
+    DecodeType(DecodeTypeCommand),
+    /// Encode the data calling a message or constructor of a contract
+    #[structopt(name = "encode")]
+    Encode(EncodeCommand),
+    /// Compute the selector of a message or constructor from its name
+    #[structopt(name = "selector")]
+    Selector(SelectorCommand),
+    /// Hash bytes or a string with a hasher of substrate chains
+    #[structopt(name = "hash")]
+    Hash(HashCommand),
     /// Upload the smart contract code to the chain
     #[cfg(feature = "extrinsics")]
     #[structopt(name = "deploy")]
@@ -462,26 +886,123 @@ enum Command {
         /// Path to wasm contract code, defaults to `./target/ink/<name>.wasm`
         #[structopt(parse(from_os_str))]
         wasm_path: Option<PathBuf>,
+        /// Upload and instantiate all contracts of this deployment manifest instead, in its order
+        #[structopt(
+            long,
+            parse(from_os_str),
+            conflicts_with = "wasm-path",
+            requires = "network"
+        )]
+        deployment: Option<PathBuf>,
     },
-    /// Instantiate a deployed smart contract
+    /// Upload the smart contract code to the chain without instantiating it
     #[cfg(feature = "extrinsics")]
-    #[structopt(name = "instantiate")]
-    Instantiate {
+    #[structopt(name = "upload")]
+    Upload {
+        #[structopt(flatten)]
+        extrinsic_opts: ExtrinsicOpts,
+        /// Path to wasm contract code, defaults to `./target/ink/<name>.wasm`
+        #[structopt(parse(from_os_str))]
+        wasm_path: Option<PathBuf>,
+        /// Report the code hash the code would be stored under without uploading it
+        #[structopt(long)]
+        dry_run: bool,
+        /// Print the code hash, events and block of the extrinsic as JSON
+        #[structopt(long, conflicts_with = "dry-run")]
+        output_json: bool,
+    },
+    /// Remove uploaded smart contract code from the chain, refunding its deposit
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "remove")]
+    Remove {
         #[structopt(flatten)]
         extrinsic_opts: ExtrinsicOpts,
-        /// Transfers an initial balance to the instantiated contract
-        #[structopt(name = "endowment", long, default_value = "0")]
-        endowment: u128,
-        /// Maximum amount of gas to be used for this command
-        #[structopt(name = "gas", long, default_value = "500000000")]
-        gas_limit: u64,
-        /// The hash of the smart contract code already uploaded to the chain
+        /// The hash of the smart contract code to remove
         #[structopt(long, parse(try_from_str = parse_code_hash))]
         code_hash: H256,
-        /// Hex encoded data to call a contract constructor
+        /// Report whether the code can be removed without removing it
         #[structopt(long)]
-        data: HexData,
+        dry_run: bool,
     },
+    /// Instantiate a deployed smart contract
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "instantiate")]
+    Instantiate(InstantiateCommand),
+    /// Call a message of an instantiated smart contract
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "call")]
+    Call(CallCommand),
+    /// Estimate the gas and fee of calling a message or constructor, without submitting it
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "estimate-gas")]
+    EstimateGas(EstimateGasCommand),
+    /// Generate, import and list the accounts used with `--account`
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "account")]
+    Account(AccountCommand),
+    /// Generate, derive and inspect keys and their addresses on different networks
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "key")]
+    Key(KeyCommand),
+    /// Convert an address between SS58 prefixes, hex account ids and Ethereum addresses
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "convert-address")]
+    ConvertAddress(ConvertAddressCommand),
+    /// Convert a balance between plancks and units of the token of a chain
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "convert-balance")]
+    ConvertBalance(ConvertBalanceCommand),
+    /// Name and explain a dispatch error, resolving module errors with the metadata of a chain
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "decode-error")]
+    DecodeError(DecodeErrorCommand),
+    /// Add, remove and list the named contract addresses given instead of `--contract`
+    /// addresses
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "addressbook")]
+    AddressBook(AddressBookCommand),
+    /// Submit an extrinsic written with `--offline`, with its signature produced on another
+    /// machine
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "submit-signed")]
+    SubmitSigned(SubmitSignedCommand),
+    /// Submit the uploads, instantiations and calls of a batch file atomically, in a single
+    /// extrinsic
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "batch")]
+    Batch(BatchCommand),
+    /// Stream the events emitted by a contract in new blocks, decoded with its metadata
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "watch")]
+    Watch(WatchCommand),
+    /// Start an interactive session which dry runs and submits the messages of a contract
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "repl")]
+    Repl(ReplCommand),
+    /// Read the storage of a contract, decoded with the storage layout of its metadata
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "storage")]
+    Storage(StorageCommand),
+    /// Upgrade the code of a contract by calling its message which sets the code hash
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "upgrade")]
+    Upgrade(UpgradeCommand),
+    /// Verify that a contract runs the code of the local project by comparing their code hashes
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "verify")]
+    Verify(VerifyCommand),
+    /// Download the code of a contract, bundled with its metadata from a registry
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "download")]
+    Download(DownloadCommand),
+    /// Call a function of the `ContractsApi` runtime API with raw SCALE encoded arguments
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "rpc")]
+    Rpc(RpcCommand),
+    /// Call a contract on a sibling parachain with an XCM `Transact` (experimental)
+    #[cfg(feature = "extrinsics")]
+    #[structopt(name = "xcm-call")]
+    XcmCall(XcmCallCommand),
 }
 
 #[cfg(feature = "extrinsics")]
@@ -498,8 +1019,22 @@ fn parse_code_hash(input: &str) -> Result<H256> {
 fn main() {
     env_logger::init();
 
-    let Opts::Contract(args) = Opts::from_args();
-    match exec(args.cmd) {
+    let result = cli_args().and_then(|cli_args| {
+        // the fork of `--fork-from` is served until the command finished
+        #[cfg(feature = "extrinsics")]
+        let (cli_args, fork) = cmd::fork::expand_args(cli_args)?;
+        let Opts::Contract(args) = match Opts::from_iter_safe(cli_args) {
+            Ok(opts) => opts,
+            Err(err) => {
+                // exiting skips the destructors, so the fork is stopped before
+                #[cfg(feature = "extrinsics")]
+                drop(fork);
+                err.exit()
+            }
+        };
+        exec(args.cmd)
+    });
+    match result {
         Ok(maybe_msg) => {
             if let Some(msg) = maybe_msg {
                 println!("\t{}", msg)
@@ -511,14 +1046,27 @@ fn main() {
                 "ERROR:".bright_red().bold(),
                 format!("{:?}", err).bright_red()
             );
-            std::process::exit(1);
+            // the outcomes of commands touching the chain have distinct exit codes
+            #[cfg(feature = "extrinsics")]
+            let code = cmd::exit_code(&err);
+            #[cfg(not(feature = "extrinsics"))]
+            let code = 1;
+            std::process::exit(code);
         }
     }
 }
 
+/// Returns the command line arguments, with the contracts of `--contract` resolved.
+fn cli_args() -> Result<Vec<std::ffi::OsString>> {
+    let args = std::env::args_os().collect();
+    #[cfg(feature = "extrinsics")]
+    let args = cmd::addressbook::expand_args(args)?;
+    Ok(args)
+}
+
 fn exec(cmd: Command) -> Result<Option<String>> {
     match &cmd {
-        Command::New { name, target_dir } => cmd::new::execute(name, target_dir.as_ref()),
+        Command::New(new) => new.exec(),
         Command::Build(build) => {
             let result = build.exec()?;
 
@@ -552,31 +1100,157 @@ fn exec(cmd: Command) -> Result<Option<String>> {
                 Ok(None)
             }
         }
+        Command::Node(node) => node.exec(),
+        Command::Sandbox(sandbox) => sandbox.exec(),
+        Command::Fuzz(fuzz) => fuzz.exec(),
+        Command::Benchmark(benchmark) => benchmark.exec(),
+        Command::Metadata(metadata) => metadata.exec(),
+        Command::Info(info) => info.exec(),
+        Command::Doc(doc) => doc.exec(),
+        Command::Bundle(bundle) => bundle.exec(),
+        Command::GenerateClient(generate_client) => generate_client.exec(),
+        Command::Deployment(deployment) => deployment.exec(),
+        Command::Decode(decode) => decode.exec(),
+        Command::DecodeType(decode_type) => decode_type.exec(),
+        Command::Encode(encode) => encode.exec(),
+        Command::Selector(selector) => selector.exec(),
+        Command::Hash(hash) => hash.exec(),
         #[cfg(feature = "extrinsics")]
         Command::Deploy {
             extrinsic_opts,
+            deployment: Some(deployment),
+            ..
+        } => {
+            let network = extrinsic_opts.node.network.as_deref().unwrap_or_default();
+            let accounts = cmd::execute_deployment(extrinsic_opts, deployment, network)?;
+            let accounts = accounts
+                .iter()
+                .map(|(contract, account)| format!("{}: {}", contract, account))
+                .collect::<Vec<_>>();
+            Ok(Some(format!(
+                "Contract accounts:\n\t{}",
+                accounts.join("\n\t")
+            )))
+        }
+        #[cfg(feature = "extrinsics")]
+        Command::Deploy {
+            extrinsic_opts,
+            wasm_path,
+            ..
+        } => match cmd::execute_deploy(extrinsic_opts, wasm_path.as_ref())? {
+            Submitted::Executed(code_hash) => Ok(Some(format!("Code hash: {:?}", code_hash))),
+            Submitted::Pending(pending) => Ok(Some(pending.to_string())),
+        },
+        #[cfg(feature = "extrinsics")]
+        Command::Upload {
             wasm_path,
+            dry_run: true,
+            ..
         } => {
-            let code_hash = cmd::execute_deploy(extrinsic_opts, wasm_path.as_ref())?;
-            Ok(Some(format!("Code hash: {:?}", code_hash)))
+            let dry_run = cmd::dry_run_upload(wasm_path.as_ref())?;
+            Ok(Some(format!(
+                "Code hash: {:?}\n\tCode size: {} bytes\n\tThe code was not uploaded (dry run)",
+                dry_run.code_hash, dry_run.code_len
+            )))
         }
         #[cfg(feature = "extrinsics")]
-        Command::Instantiate {
+        Command::Upload {
             extrinsic_opts,
-            endowment,
-            code_hash,
-            gas_limit,
-            data,
+            wasm_path,
+            output_json,
+            ..
         } => {
-            let contract_account = cmd::execute_instantiate(
-                extrinsic_opts,
-                *endowment,
-                *gas_limit,
-                *code_hash,
-                data.clone(),
-            )?;
-            Ok(Some(format!("Contract account: {:?}", contract_account)))
+            if extrinsic_opts.offline.is_some() {
+                let (code_hash, written) = cmd::offline_upload(extrinsic_opts, wasm_path.as_ref())?;
+                return Ok(Some(format!("Code hash: {:?}\n{}", code_hash, written)));
+            }
+            let (code_hash, output) = match cmd::execute_upload(extrinsic_opts, wasm_path.as_ref())?
+            {
+                Submitted::Executed(uploaded) => uploaded,
+                Submitted::Pending(pending) => return Ok(Some(pending.to_string())),
+            };
+            let output = match output {
+                Some(output) => output,
+                None if *output_json => {
+                    let skipped = serde_json::json!({ "code_hash": code_hash, "uploaded": false });
+                    return Ok(Some(serde_json::to_string_pretty(&skipped)?));
+                }
+                None => return Ok(Some(format!("Code hash: {:?}", code_hash))),
+            };
+            if *output_json {
+                return Ok(Some(output.to_json()?));
+            }
+            Ok(Some(format!(
+                "Code hash: {:?}{}",
+                code_hash,
+                output.display_explorer_links(extrinsic_opts)?
+            )))
         }
+        #[cfg(feature = "extrinsics")]
+        Command::Remove {
+            extrinsic_opts,
+            code_hash,
+            dry_run: true,
+        } => match cmd::dry_run_remove(extrinsic_opts, *code_hash)? {
+            None => anyhow::bail!("No code is stored under the code hash {:?}", code_hash),
+            Some(info) if info.refcount > 0 => Ok(Some(format!(
+                "The code is still used by {} contracts and cannot be removed",
+                info.refcount
+            ))),
+            Some(info) => Ok(Some(format!(
+                "The code can be removed by its owner {}, refunding a deposit of {}",
+                info.owner, info.deposit
+            ))),
+        },
+        #[cfg(feature = "extrinsics")]
+        Command::Remove {
+            extrinsic_opts,
+            code_hash,
+            ..
+        } => match cmd::execute_remove(extrinsic_opts, *code_hash)? {
+            Submitted::Executed(code_hash) => {
+                Ok(Some(format!("Removed the code with hash {:?}", code_hash)))
+            }
+            Submitted::Pending(pending) => Ok(Some(pending.to_string())),
+        },
+        #[cfg(feature = "extrinsics")]
+        Command::Instantiate(instantiate) => instantiate.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Call(call) => call.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::EstimateGas(estimate_gas) => estimate_gas.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Account(account) => account.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Key(key) => key.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::ConvertAddress(convert_address) => convert_address.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::ConvertBalance(convert_balance) => convert_balance.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::DecodeError(decode_error) => decode_error.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::AddressBook(address_book) => address_book.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::SubmitSigned(submit_signed) => submit_signed.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Batch(batch) => batch.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Watch(watch) => watch.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Repl(repl) => repl.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Storage(storage) => storage.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Upgrade(upgrade) => upgrade.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Verify(verify) => verify.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Download(download) => download.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::Rpc(rpc) => rpc.exec(),
+        #[cfg(feature = "extrinsics")]
+        Command::XcmCall(xcm_call) => xcm_call.exec(),
     }
 }
 
@@ -17,7 +17,17 @@
 use crate::Verbosity;
 use anyhow::{Context, Result};
 use rustc_version::Channel;
-use std::{ffi::OsStr, path::Path, process::Command};
+use serde::de::DeserializeOwned;
+use std::{
+    env,
+    ffi::OsStr,
+    fs,
+    path::{Path, PathBuf},
+    process::Command,
+};
+
+/// The configuration of the project, in the current directory.
+pub(crate) const PROJECT_CONFIG: &str = "contract.toml";
 
 /// Check whether the current rust channel is valid: `nightly` is recommended.
 pub fn assert_channel() -> Result<()> {
@@ -92,6 +102,65 @@ pub(crate) fn base_name(path: &Path) -> &str {
         .expect("must be valid utf-8")
 }
 
+/// Returns the directory of the configuration and state of `cargo-contract`, like its
+/// accounts.
+///
+/// It is `$CARGO_CONTRACT_CONFIG_DIR` if set, otherwise the `cargo-contract` directory of the
+/// user's config directory.
+pub(crate) fn config_dir() -> Result<PathBuf> {
+    match (
+        env::var_os("CARGO_CONTRACT_CONFIG_DIR"),
+        env::var_os("XDG_CONFIG_HOME"),
+        env::var_os("HOME"),
+    ) {
+        (Some(dir), _, _) => Ok(PathBuf::from(dir)),
+        (None, Some(dir), _) => Ok(PathBuf::from(dir).join("cargo-contract")),
+        (None, None, Some(home)) => Ok(PathBuf::from(home).join(".config").join("cargo-contract")),
+        (None, None, None) => {
+            anyhow::bail!("Unable to find a config directory, set CARGO_CONTRACT_CONFIG_DIR")
+        }
+    }
+}
+
+/// Returns the configuration of the project and the `config.toml` of the config directory, in
+/// this order, or `None` for a file which does not exist.
+pub(crate) fn load_configs<T: DeserializeOwned>() -> Result<(Option<T>, Option<T>)> {
+    let load = |path: &Path| -> Result<Option<T>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let config = fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        toml::from_str(&config)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    };
+    Ok((
+        load(Path::new(PROJECT_CONFIG))?,
+        load(&config_dir()?.join("config.toml"))?,
+    ))
+}
+
+/// Returns the directory of the files `cargo-contract` caches, like fetched templates and
+/// installed nodes.
+///
+/// It is `$CARGO_CONTRACT_CACHE_DIR` if set, otherwise the `cargo-contract` directory of the
+/// user's cache directory.
+pub(crate) fn cache_dir() -> Result<PathBuf> {
+    match (
+        env::var_os("CARGO_CONTRACT_CACHE_DIR"),
+        env::var_os("XDG_CACHE_HOME"),
+        env::var_os("HOME"),
+    ) {
+        (Some(dir), _, _) => Ok(PathBuf::from(dir)),
+        (None, Some(dir), _) => Ok(PathBuf::from(dir).join("cargo-contract")),
+        (None, None, Some(home)) => Ok(PathBuf::from(home).join(".cache").join("cargo-contract")),
+        (None, None, None) => {
+            anyhow::bail!("Unable to find a cache directory, set CARGO_CONTRACT_CACHE_DIR")
+        }
+    }
+}
+
 /// Prints to stdout if `verbosity.is_verbose()` is `true`.
 #[macro_export]
 macro_rules! maybe_println {
@@ -152,7 +221,7 @@ pub mod tests {
         with_tmp_dir(|tmp_dir| {
             let unique_name = format!("new_project_{}", COUNTER.fetch_add(1, Ordering::SeqCst));
 
-            crate::cmd::new::execute(&unique_name, Some(tmp_dir))
+            crate::cmd::new::execute(&unique_name, Some(tmp_dir), &Default::default())
                 .expect("new project creation failed");
             let working_dir = tmp_dir.join(unique_name);
             let manifest_path = ManifestPath::new(working_dir.join("Cargo.toml"))?;
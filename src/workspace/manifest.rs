@@ -176,6 +176,27 @@ impl Manifest {
             .map(Into::into)
     }
 
+    /// Extract `compact-metadata` from `[package.metadata.contract]`
+    pub fn get_profile_compact_metadata(&mut self) -> Option<bool> {
+        self.toml
+            .get("package")?
+            .as_table()?
+            .get("metadata")?
+            .as_table()?
+            .get("contract")?
+            .as_table()?
+            .get("compact-metadata")?
+            .as_bool()
+    }
+
+    /// Returns `true` if the `[features]` section declares `feature`.
+    pub fn has_feature(&self, feature: &str) -> bool {
+        self.toml
+            .get("features")
+            .and_then(value::Value::as_table)
+            .is_some_and(|features| features.contains_key(feature))
+    }
+
     /// Set `optimization-passes` in `[package.metadata.contract]`
     #[cfg(feature = "test-ci-only")]
     #[cfg(test)]
@@ -479,16 +500,20 @@ impl Manifest {
                 .as_str()
                 .ok_or_else(|| anyhow::anyhow!("[package] name should be a string"))?;
 
-            let ink_metadata = self
+            let dependencies = self
                 .toml
                 .get("dependencies")
-                .ok_or_else(|| anyhow::anyhow!("[dependencies] section not found"))?
-                .get("ink_metadata")
-                .ok_or_else(|| anyhow::anyhow!("ink_metadata dependency not found"))?
+                .ok_or_else(|| anyhow::anyhow!("[dependencies] section not found"))?;
+            // ink! 3 contracts depend on `ink_metadata`, ink! 4 contracts on the `ink` crate
+            let (name, dependency) = ["ink_metadata", "ink"]
+                .iter()
+                .find_map(|name| Some((*name, dependencies.get(*name)?)))
+                .ok_or_else(|| anyhow::anyhow!("ink_metadata or ink dependency not found"))?;
+            let dependency = dependency
                 .as_table()
-                .ok_or_else(|| anyhow::anyhow!("ink_metadata dependency should be a table"))?;
+                .ok_or_else(|| anyhow::anyhow!("{} dependency should be a table", name))?;
 
-            metadata::generate_package(dir, contract_package_name, ink_metadata.clone())?;
+            metadata::generate_package(dir, contract_package_name, name, dependency.clone())?;
         }
 
         let updated_toml = toml::to_string(&self.toml)?;
@@ -24,11 +24,13 @@ use toml::value;
 /// # Note
 ///
 /// `ink!` dependencies are copied from the containing contract workspace to ensure the same
-/// versions are utilized.
+/// versions are utilized. `ink_dependency_name` is `ink_metadata` for ink! 3 contracts and `ink`
+/// for ink! 4 contracts, which export their metadata as `ink::metadata::InkProject`.
 pub(super) fn generate_package<P: AsRef<Path>>(
     target_dir: P,
     contract_package_name: &str,
-    mut ink_metadata_dependency: value::Table,
+    ink_dependency_name: &str,
+    mut ink_dependency: value::Table,
 ) -> Result<()> {
     let dir = target_dir.as_ref();
     log::debug!(
@@ -38,7 +40,11 @@ pub(super) fn generate_package<P: AsRef<Path>>(
     );
 
     let cargo_toml = include_str!("../../templates/tools/generate-metadata/_Cargo.toml");
-    let main_rs = include_str!("../../templates/tools/generate-metadata/main.rs");
+    let main_rs = if ink_dependency_name == "ink" {
+        include_str!("../../templates/tools/generate-metadata/main_ink4.rs")
+    } else {
+        include_str!("../../templates/tools/generate-metadata/main.rs")
+    };
 
     let mut cargo_toml: value::Table = toml::from_str(cargo_toml)?;
     let deps = cargo_toml
@@ -55,13 +61,13 @@ pub(super) fn generate_package<P: AsRef<Path>>(
         .expect("contract dependency is a table specified in the template");
     contract.insert("package".into(), contract_package_name.into());
 
-    // make ink_metadata dependency use default features
-    ink_metadata_dependency.remove("default-features");
-    ink_metadata_dependency.remove("features");
-    ink_metadata_dependency.remove("optional");
+    // make the ink! dependency use default features
+    ink_dependency.remove("default-features");
+    ink_dependency.remove("features");
+    ink_dependency.remove("optional");
 
     // add ink dependencies copied from contract manifest
-    deps.insert("ink_metadata".into(), ink_metadata_dependency.into());
+    deps.insert(ink_dependency_name.into(), ink_dependency.into());
     let cargo_toml = toml::to_string(&cargo_toml)?;
 
     fs::write(dir.join("Cargo.toml"), cargo_toml)?;
@@ -0,0 +1,18 @@
+// This file is generated by `cargo contract build`, do not edit it.
+//
+// Run `cargo +nightly contract build` in the directory of the contract to generate the
+// types of the {{camel_name}} contract and to copy its `metadata.json` next to this file.
+
+export type Numeric = number | string;
+
+export interface MessageInfo {
+  label: string;
+  method: string;
+  selector: string;
+  mutates: boolean;
+  payable: boolean;
+  args: string[];
+}
+
+export const constructors: MessageInfo[] = [];
+export const messages: MessageInfo[] = [];
@@ -0,0 +1,39 @@
+import { ApiPromise, Keyring, WsProvider } from '@polkadot/api';
+import { ContractPromise } from '@polkadot/api-contract';
+
+import { messages } from './contract';
+// Copied here by `cargo contract build`.
+import metadata from './metadata.json';
+
+const NODE_URL = process.env.NODE_URL || 'ws://127.0.0.1:9944';
+const CONTRACT_ADDRESS = process.env.CONTRACT_ADDRESS;
+
+async function main(): Promise<void> {
+  if (!CONTRACT_ADDRESS) {
+    throw new Error('Set CONTRACT_ADDRESS to the address of an instantiated {{camel_name}} contract');
+  }
+
+  const api = await ApiPromise.create({ provider: new WsProvider(NODE_URL) });
+  const contract = new ContractPromise(api, metadata, CONTRACT_ADDRESS);
+  const alice = new Keyring({ type: 'sr25519' }).addFromUri('//Alice');
+
+  // Query every message which neither mutates the storage nor takes arguments.
+  for (const message of messages.filter((m) => !m.mutates && m.args.length === 0)) {
+    const { result, output } = await contract.query[message.method](alice.address, {
+      value: 0,
+      gasLimit: -1,
+    });
+    if (result.isOk) {
+      console.log(`${message.label}: ${output?.toHuman()}`);
+    } else {
+      console.error(`${message.label} failed: ${result.asErr.toHuman()}`);
+    }
+  }
+
+  await api.disconnect();
+}
+
+main().catch((error) => {
+  console.error(error);
+  process.exit(1);
+});
@@ -0,0 +1 @@
+// Replaced by the contract generated from the metadata.
@@ -0,0 +1,406 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[ink::contract]
+mod {{name}} {
+    use ink::{prelude::vec::Vec, storage::Mapping};
+
+    /// The identifier of a proposal.
+    pub type ProposalId = u32;
+
+    /// A proposal to spend funds of the DAO treasury.
+    #[derive(scale::Encode, scale::Decode)]
+    #[cfg_attr(
+        feature = "std",
+        derive(
+            Debug,
+            PartialEq,
+            Eq,
+            scale_info::TypeInfo,
+            ink::storage::traits::StorageLayout
+        )
+    )]
+    pub struct Proposal {
+        /// The account receiving the funds.
+        pub to: AccountId,
+        /// The amount of funds to transfer.
+        pub value: Balance,
+        /// The number of members voting in favor of the proposal.
+        pub yes_votes: u32,
+        /// The number of members voting against the proposal.
+        pub no_votes: u32,
+        /// `true` once the proposal was executed.
+        pub executed: bool,
+    }
+
+    /// The errors which can be returned by the DAO messages.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// Returned if the caller is not a member of the DAO.
+        NotMember,
+        /// Returned if the proposal does not exist.
+        ProposalNotFound,
+        /// Returned if the proposal was already executed.
+        AlreadyExecuted,
+        /// Returned if the caller already voted on the proposal.
+        AlreadyVoted,
+        /// Returned if the proposal did not reach the quorum of yes votes.
+        QuorumNotReached,
+        /// Returned if the transfer of the funds failed.
+        TransferFailed,
+    }
+
+    /// The result type of the DAO messages.
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Defines the storage of your DAO.
+    #[ink(storage)]
+    pub struct {{camel_name}} {
+        /// The accounts allowed to create and vote on proposals.
+        members: Vec<AccountId>,
+        /// The percentage of members which need to vote in favor of a proposal.
+        quorum: u8,
+        /// All proposals which have been created.
+        proposals: Mapping<ProposalId, Proposal>,
+        /// The members which voted on a proposal.
+        votes: Mapping<(ProposalId, AccountId), ()>,
+        /// The id of the next proposal.
+        next_id: ProposalId,
+    }
+
+    /// Event emitted when a member creates a proposal.
+    #[ink(event)]
+    pub struct ProposalCreated {
+        #[ink(topic)]
+        id: ProposalId,
+        #[ink(topic)]
+        proposer: AccountId,
+        to: AccountId,
+        value: Balance,
+    }
+
+    /// Event emitted when a member votes on a proposal.
+    #[ink(event)]
+    pub struct Voted {
+        #[ink(topic)]
+        id: ProposalId,
+        #[ink(topic)]
+        voter: AccountId,
+        approve: bool,
+    }
+
+    /// Event emitted when a proposal is executed.
+    #[ink(event)]
+    pub struct ProposalExecuted {
+        #[ink(topic)]
+        id: ProposalId,
+    }
+
+    impl {{camel_name}} {
+        /// Creates a new DAO with the given `members`, which executes proposals once
+        /// `quorum` percent of the members voted in favor.
+        ///
+        /// # Panics
+        ///
+        /// If `quorum` is zero or higher than 100.
+        #[ink(constructor, payable)]
+        pub fn new(members: Vec<AccountId>, quorum: u8) -> Self {
+            assert!(
+                quorum > 0 && quorum <= 100,
+                "the quorum must be a percentage between 1 and 100"
+            );
+            let mut member_set = Vec::new();
+            for member in members {
+                if !member_set.contains(&member) {
+                    member_set.push(member);
+                }
+            }
+            Self {
+                members: member_set,
+                quorum,
+                proposals: Mapping::default(),
+                votes: Mapping::default(),
+                next_id: 0,
+            }
+        }
+
+        /// Proposes to transfer `value` from the treasury to `to`.
+        #[ink(message)]
+        pub fn propose(&mut self, to: AccountId, value: Balance) -> Result<ProposalId> {
+            let proposer = self.ensure_member()?;
+            let id = self.next_id;
+            self.next_id += 1;
+            self.proposals.insert(
+                id,
+                &Proposal {
+                    to,
+                    value,
+                    yes_votes: 0,
+                    no_votes: 0,
+                    executed: false,
+                },
+            );
+            self.env().emit_event(ProposalCreated {
+                id,
+                proposer,
+                to,
+                value,
+            });
+            Ok(id)
+        }
+
+        /// Votes in favor of (`approve == true`) or against the proposal `id`.
+        #[ink(message)]
+        pub fn vote(&mut self, id: ProposalId, approve: bool) -> Result<()> {
+            let voter = self.ensure_member()?;
+            if self.votes.contains((id, voter)) {
+                return Err(Error::AlreadyVoted)
+            }
+            let mut proposal = self.pending_proposal(id)?;
+            if approve {
+                proposal.yes_votes += 1;
+            } else {
+                proposal.no_votes += 1;
+            }
+            self.proposals.insert(id, &proposal);
+            self.votes.insert((id, voter), &());
+            self.env().emit_event(Voted { id, voter, approve });
+            Ok(())
+        }
+
+        /// Executes the proposal `id` once it reached the quorum.
+        #[ink(message)]
+        pub fn execute(&mut self, id: ProposalId) -> Result<()> {
+            self.ensure_member()?;
+            let required_votes = self.required_votes();
+            let mut proposal = self.pending_proposal(id)?;
+            if proposal.yes_votes < required_votes {
+                return Err(Error::QuorumNotReached)
+            }
+            proposal.executed = true;
+            self.proposals.insert(id, &proposal);
+            self.env()
+                .transfer(proposal.to, proposal.value)
+                .map_err(|_| Error::TransferFailed)?;
+            self.env().emit_event(ProposalExecuted { id });
+            Ok(())
+        }
+
+        /// Returns the proposal `id`, if it exists.
+        #[ink(message)]
+        pub fn proposal(&self, id: ProposalId) -> Option<Proposal> {
+            self.proposals.get(id)
+        }
+
+        /// Returns `true` if `account` is a member of the DAO.
+        #[ink(message)]
+        pub fn is_member(&self, account: AccountId) -> bool {
+            self.members.contains(&account)
+        }
+
+        /// Returns the number of yes votes a proposal needs to be executed.
+        #[ink(message)]
+        pub fn required_votes(&self) -> u32 {
+            let members = self.members.len() as u32 * self.quorum as u32;
+            // round up, a quorum must never be reachable with fewer votes
+            (members + 99) / 100
+        }
+
+        /// Returns the caller if it is a member of the DAO.
+        fn ensure_member(&self) -> Result<AccountId> {
+            let caller = self.env().caller();
+            if !self.is_member(caller) {
+                return Err(Error::NotMember)
+            }
+            Ok(caller)
+        }
+
+        /// Returns the proposal `id` if it exists and was not executed yet.
+        fn pending_proposal(&self, id: ProposalId) -> Result<Proposal> {
+            let proposal = self.proposals.get(id).ok_or(Error::ProposalNotFound)?;
+            if proposal.executed {
+                return Err(Error::AlreadyExecuted)
+            }
+            Ok(proposal)
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use ink::env::test;
+
+        type Environment = ink::env::DefaultEnvironment;
+
+        fn default_accounts() -> test::DefaultAccounts<Environment> {
+            test::default_accounts::<Environment>()
+        }
+
+        /// Makes `sender` the caller of the subsequent messages.
+        fn set_sender(sender: AccountId) {
+            test::set_caller::<Environment>(sender);
+        }
+
+        fn dao() -> {{camel_name}} {
+            let accounts = default_accounts();
+            {{camel_name}}::new(vec![accounts.alice, accounts.bob, accounts.charlie], 50)
+        }
+
+        /// The constructor registers all members.
+        #[ink::test]
+        fn new_works() {
+            let accounts = default_accounts();
+            let {{name}} = dao();
+            assert!({{name}}.is_member(accounts.bob));
+            assert!(!{{name}}.is_member(accounts.eve));
+            assert_eq!({{name}}.required_votes(), 2);
+        }
+
+        /// Members can vote once per proposal.
+        #[ink::test]
+        fn voting_works() {
+            let accounts = default_accounts();
+            let mut {{name}} = dao();
+            let id = {{name}}.propose(accounts.eve, 10).unwrap();
+            assert_eq!({{name}}.vote(id, true), Ok(()));
+            assert_eq!({{name}}.vote(id, false), Err(Error::AlreadyVoted));
+            assert_eq!({{name}}.proposal(id).unwrap().yes_votes, 1);
+            assert_eq!({{name}}.execute(id), Err(Error::QuorumNotReached));
+
+            let emitted_events = test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+        }
+
+        /// A proposal reaching the quorum can be executed exactly once.
+        #[ink::test]
+        fn execute_works_with_quorum() {
+            let accounts = default_accounts();
+            let mut {{name}} = dao();
+            let id = {{name}}.propose(accounts.eve, 10).unwrap();
+            assert_eq!({{name}}.vote(id, true), Ok(()));
+
+            set_sender(accounts.bob);
+            assert_eq!({{name}}.vote(id, true), Ok(()));
+            assert_eq!({{name}}.execute(id), Ok(()));
+            assert!({{name}}.proposal(id).unwrap().executed);
+            assert_eq!({{name}}.execute(id), Err(Error::AlreadyExecuted));
+        }
+
+        /// Accounts which are not members cannot create proposals.
+        #[ink::test]
+        fn non_members_are_rejected() {
+            let accounts = default_accounts();
+            let mut {{name}} = dao();
+            set_sender(accounts.eve);
+            assert_eq!({{name}}.propose(accounts.eve, 10), Err(Error::NotMember));
+        }
+    }
+{{#e2e}}
+
+    /// End-to-end tests instantiate the contract on a running node and call it with
+    /// real transactions.
+    ///
+    /// They are only compiled with the `e2e-tests` feature. Start a node, e.g.
+    /// `substrate-contracts-node`, and run them with `cargo test --features e2e-tests`.
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// A helper function used for calling contract messages.
+        use ink_e2e::build_message;
+
+        /// The End-to-End test `Result` type.
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// Alice proposes a transfer to Charlie and approves it, then Bob executes it.
+        #[ink_e2e::test]
+        async fn execute_works_with_quorum(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let members = vec![
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Alice),
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Bob),
+            ];
+            let constructor = {{camel_name}}Ref::new(members, 50);
+            let contract_account_id = client
+                .instantiate("{{name}}", &ink_e2e::alice(), constructor, 1_000_000, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let charlie_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+
+            let propose = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.propose(charlie_account.clone(), 10));
+            let _propose_result = client
+                .call(&ink_e2e::alice(), propose, 0, None)
+                .await
+                .expect("propose failed");
+
+            // When
+            let vote = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.vote(0, true));
+            let _vote_result = client
+                .call(&ink_e2e::alice(), vote, 0, None)
+                .await
+                .expect("vote failed");
+            let execute = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.execute(0));
+            let _execute_result = client
+                .call(&ink_e2e::bob(), execute, 0, None)
+                .await
+                .expect("execute failed");
+
+            // Then
+            let proposal = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.proposal(0));
+            let proposal_result = client
+                .call_dry_run(&ink_e2e::alice(), &proposal, 0, None)
+                .await;
+            assert!(matches!(
+                proposal_result.return_value(),
+                Some(Proposal { executed: true, .. })
+            ));
+
+            Ok(())
+        }
+
+        /// A proposal without enough yes votes cannot be executed.
+        #[ink_e2e::test]
+        async fn execute_fails_without_quorum(
+            mut client: ink_e2e::Client<C, E>,
+        ) -> E2EResult<()> {
+            let members = vec![
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Alice),
+                ink_e2e::account_id(ink_e2e::AccountKeyring::Bob),
+            ];
+            let constructor = {{camel_name}}Ref::new(members, 100);
+            let contract_account_id = client
+                .instantiate("{{name}}", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+            let charlie_account = ink_e2e::account_id(ink_e2e::AccountKeyring::Charlie);
+
+            let propose = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.propose(charlie_account.clone(), 10));
+            let _propose_result = client
+                .call(&ink_e2e::alice(), propose, 0, None)
+                .await
+                .expect("propose failed");
+
+            let execute = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.execute(0));
+            let execute_result = client
+                .call_dry_run(&ink_e2e::alice(), &execute, 0, None)
+                .await;
+            assert_eq!(execute_result.return_value(), Err(Error::QuorumNotReached));
+
+            Ok(())
+        }
+    }
+{{/e2e}}
+}
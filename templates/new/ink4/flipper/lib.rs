@@ -0,0 +1,121 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[ink::contract]
+mod {{name}} {
+
+    /// Defines the storage of your contract.
+    /// Add new fields to the below struct in order
+    /// to add new static storage fields to your contract.
+    #[ink(storage)]
+    pub struct {{camel_name}} {
+        /// Stores a single `bool` value on the storage.
+        value: bool,
+    }
+
+    impl {{camel_name}} {
+        /// Constructor that initializes the `bool` value to the given `init_value`.
+        #[ink(constructor)]
+        pub fn new(init_value: bool) -> Self {
+            Self { value: init_value }
+        }
+
+        /// Constructor that initializes the `bool` value to `false`.
+        ///
+        /// Constructors can delegate to other constructors.
+        #[ink(constructor)]
+        pub fn default() -> Self {
+            Self::new(Default::default())
+        }
+
+        /// A message that can be called on instantiated contracts.
+        /// This one flips the value of the stored `bool` from `true`
+        /// to `false` and vice versa.
+        #[ink(message)]
+        pub fn flip(&mut self) {
+            self.value = !self.value;
+        }
+
+        /// Simply returns the current value of our `bool`.
+        #[ink(message)]
+        pub fn get(&self) -> bool {
+            self.value
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// We test if the default constructor does its job.
+        #[ink::test]
+        fn default_works() {
+            let {{name}} = {{camel_name}}::default();
+            assert_eq!({{name}}.get(), false);
+        }
+
+        /// We test a simple use case of our contract.
+        #[ink::test]
+        fn it_works() {
+            let mut {{name}} = {{camel_name}}::new(false);
+            assert_eq!({{name}}.get(), false);
+            {{name}}.flip();
+            assert_eq!({{name}}.get(), true);
+        }
+    }
+{{#e2e}}
+
+    /// End-to-end tests instantiate the contract on a running node and call it with
+    /// real transactions.
+    ///
+    /// They are only compiled with the `e2e-tests` feature. Start a node, e.g.
+    /// `substrate-contracts-node`, and run them with `cargo test --features e2e-tests`.
+    #[cfg(all(test, feature = "e2e-tests"))]
+    mod e2e_tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// A helper function used for calling contract messages.
+        use ink_e2e::build_message;
+
+        /// The End-to-End test `Result` type.
+        type E2EResult<T> = std::result::Result<T, Box<dyn std::error::Error>>;
+
+        /// We instantiate the contract and flip its value.
+        #[ink_e2e::test]
+        async fn it_works(mut client: ink_e2e::Client<C, E>) -> E2EResult<()> {
+            // Given
+            let constructor = {{camel_name}}Ref::new(false);
+            let contract_account_id = client
+                .instantiate("{{name}}", &ink_e2e::alice(), constructor, 0, None)
+                .await
+                .expect("instantiate failed")
+                .account_id;
+
+            let get = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.get());
+            let get_result = client.call_dry_run(&ink_e2e::alice(), &get, 0, None).await;
+            assert!(matches!(get_result.return_value(), false));
+
+            // When
+            let flip = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.flip());
+            let _flip_result = client
+                .call(&ink_e2e::bob(), flip, 0, None)
+                .await
+                .expect("flip failed");
+
+            // Then
+            let get = build_message::<{{camel_name}}Ref>(contract_account_id.clone())
+                .call(|{{name}}| {{name}}.get());
+            let get_result = client.call_dry_run(&ink_e2e::bob(), &get, 0, None).await;
+            assert!(matches!(get_result.return_value(), true));
+
+            Ok(())
+        }
+    }
+{{/e2e}}
+}
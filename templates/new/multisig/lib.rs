@@ -0,0 +1,327 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod {{name}} {
+    use ink_prelude::vec::Vec;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        traits::{PackedLayout, SpreadLayout},
+    };
+
+    /// The identifier of a submitted transaction.
+    pub type TransactionId = u32;
+
+    /// A transfer of funds out of the wallet, waiting for enough confirmations.
+    #[derive(scale::Encode, scale::Decode, SpreadLayout, PackedLayout)]
+    #[cfg_attr(
+        feature = "std",
+        derive(
+            Debug,
+            PartialEq,
+            Eq,
+            scale_info::TypeInfo,
+            ink_storage::traits::StorageLayout
+        )
+    )]
+    pub struct Transaction {
+        /// The account receiving the funds.
+        pub to: AccountId,
+        /// The amount of funds to transfer.
+        pub value: Balance,
+        /// `true` once the transaction was executed.
+        pub executed: bool,
+    }
+
+    /// The errors which can be returned by the multisig messages.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum Error {
+        /// Returned if the caller is not an owner of the wallet.
+        NotOwner,
+        /// Returned if the threshold is zero or higher than the number of owners.
+        InvalidThreshold,
+        /// Returned if the transaction does not exist.
+        TransactionNotFound,
+        /// Returned if the transaction was already executed.
+        AlreadyExecuted,
+        /// Returned if the caller already confirmed the transaction.
+        AlreadyConfirmed,
+        /// Returned if the transaction has not reached the threshold yet.
+        NotEnoughConfirmations,
+        /// Returned if the transfer of the funds failed.
+        TransferFailed,
+    }
+
+    /// The result type of the multisig messages.
+    pub type Result<T> = core::result::Result<T, Error>;
+
+    /// Defines the storage of your multisig wallet.
+    #[ink(storage)]
+    pub struct {{camel_name}} {
+        /// The accounts allowed to submit and confirm transactions.
+        owners: StorageHashMap<AccountId, ()>,
+        /// The number of confirmations required to execute a transaction.
+        threshold: u32,
+        /// All transactions which have been submitted.
+        transactions: StorageHashMap<TransactionId, Transaction>,
+        /// The owners which confirmed a transaction.
+        confirmations: StorageHashMap<(TransactionId, AccountId), ()>,
+        /// The number of confirmations per transaction.
+        confirmation_count: StorageHashMap<TransactionId, u32>,
+        /// The id of the next submitted transaction.
+        next_id: TransactionId,
+    }
+
+    /// Event emitted when an owner submits a transaction.
+    #[ink(event)]
+    pub struct Submission {
+        #[ink(topic)]
+        id: TransactionId,
+        #[ink(topic)]
+        to: AccountId,
+        value: Balance,
+    }
+
+    /// Event emitted when an owner confirms a transaction.
+    #[ink(event)]
+    pub struct Confirmation {
+        #[ink(topic)]
+        id: TransactionId,
+        #[ink(topic)]
+        owner: AccountId,
+        confirmations: u32,
+    }
+
+    /// Event emitted when a transaction is executed.
+    #[ink(event)]
+    pub struct Execution {
+        #[ink(topic)]
+        id: TransactionId,
+    }
+
+    impl {{camel_name}} {
+        /// Creates a new wallet controlled by `owners`, which executes transactions
+        /// once `threshold` of them confirmed.
+        ///
+        /// # Panics
+        ///
+        /// If `threshold` is zero or higher than the number of owners.
+        #[ink(constructor)]
+        pub fn new(owners: Vec<AccountId>, threshold: u32) -> Self {
+            let mut owner_set = StorageHashMap::new();
+            for owner in owners {
+                owner_set.insert(owner, ());
+            }
+            assert!(
+                threshold > 0 && threshold <= owner_set.len(),
+                "the threshold must be between 1 and the number of owners"
+            );
+            Self {
+                owners: owner_set,
+                threshold,
+                transactions: StorageHashMap::new(),
+                confirmations: StorageHashMap::new(),
+                confirmation_count: StorageHashMap::new(),
+                next_id: 0,
+            }
+        }
+
+        /// Submits a transfer of `value` to `to`, confirming it for the caller.
+        #[ink(message)]
+        pub fn submit_transaction(&mut self, to: AccountId, value: Balance) -> Result<TransactionId> {
+            self.ensure_owner()?;
+            let id = self.next_id;
+            self.next_id += 1;
+            self.transactions.insert(
+                id,
+                Transaction {
+                    to,
+                    value,
+                    executed: false,
+                },
+            );
+            self.env().emit_event(Submission { id, to, value });
+            self.confirm_transaction(id)?;
+            Ok(id)
+        }
+
+        /// Confirms the transaction `id` for the caller.
+        #[ink(message)]
+        pub fn confirm_transaction(&mut self, id: TransactionId) -> Result<()> {
+            let owner = self.ensure_owner()?;
+            self.ensure_pending(id)?;
+            if self.confirmations.contains_key(&(id, owner)) {
+                return Err(Error::AlreadyConfirmed)
+            }
+            self.confirmations.insert((id, owner), ());
+            let confirmations = self.confirmations(id) + 1;
+            self.confirmation_count.insert(id, confirmations);
+            self.env().emit_event(Confirmation {
+                id,
+                owner,
+                confirmations,
+            });
+            Ok(())
+        }
+
+        /// Executes the transaction `id` once it has enough confirmations.
+        #[ink(message)]
+        pub fn execute_transaction(&mut self, id: TransactionId) -> Result<()> {
+            self.ensure_owner()?;
+            self.ensure_pending(id)?;
+            if self.confirmations(id) < self.threshold {
+                return Err(Error::NotEnoughConfirmations)
+            }
+            let transaction = self
+                .transactions
+                .get_mut(&id)
+                .ok_or(Error::TransactionNotFound)?;
+            transaction.executed = true;
+            let (to, value) = (transaction.to, transaction.value);
+            self.env()
+                .transfer(to, value)
+                .map_err(|_| Error::TransferFailed)?;
+            self.env().emit_event(Execution { id });
+            Ok(())
+        }
+
+        /// Returns the transaction `id`, if it exists.
+        #[ink(message)]
+        pub fn transaction(&self, id: TransactionId) -> Option<Transaction> {
+            self.transactions.get(&id).map(|transaction| Transaction {
+                to: transaction.to,
+                value: transaction.value,
+                executed: transaction.executed,
+            })
+        }
+
+        /// Returns the number of confirmations of the transaction `id`.
+        #[ink(message)]
+        pub fn confirmations(&self, id: TransactionId) -> u32 {
+            self.confirmation_count.get(&id).copied().unwrap_or(0)
+        }
+
+        /// Returns the number of confirmations required to execute a transaction.
+        #[ink(message)]
+        pub fn threshold(&self) -> u32 {
+            self.threshold
+        }
+
+        /// Returns `true` if `account` is an owner of the wallet.
+        #[ink(message)]
+        pub fn is_owner(&self, account: AccountId) -> bool {
+            self.owners.contains_key(&account)
+        }
+
+        /// Returns the caller if it is an owner of the wallet.
+        fn ensure_owner(&self) -> Result<AccountId> {
+            let caller = self.env().caller();
+            if !self.is_owner(caller) {
+                return Err(Error::NotOwner)
+            }
+            Ok(caller)
+        }
+
+        /// Returns an error if the transaction `id` does not exist or was already executed.
+        fn ensure_pending(&self, id: TransactionId) -> Result<()> {
+            match self.transactions.get(&id) {
+                None => Err(Error::TransactionNotFound),
+                Some(transaction) if transaction.executed => Err(Error::AlreadyExecuted),
+                Some(_) => Ok(()),
+            }
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+        use ink_env::{call, test};
+
+        /// Imports `ink_lang` so we can use `#[ink::test]`.
+        use ink_lang as ink;
+
+        type Environment = ink_env::DefaultEnvironment;
+
+        fn default_accounts() -> test::DefaultAccounts<Environment> {
+            test::default_accounts::<Environment>()
+                .expect("off-chain environment should have been initialized already")
+        }
+
+        /// Makes `sender` the caller of the subsequent messages.
+        fn set_sender(sender: AccountId) {
+            let callee = ink_env::account_id::<Environment>().unwrap_or([0x0; 32].into());
+            test::push_execution_context::<Environment>(
+                sender,
+                callee,
+                1000000,
+                1000000,
+                test::CallData::new(call::Selector::new([0x00; 4])),
+            );
+        }
+
+        fn wallet() -> {{camel_name}} {
+            let accounts = default_accounts();
+            {{camel_name}}::new(vec![accounts.alice, accounts.bob, accounts.charlie], 2)
+        }
+
+        /// The constructor registers all owners.
+        #[ink::test]
+        fn new_works() {
+            let accounts = default_accounts();
+            let {{name}} = wallet();
+            assert!({{name}}.is_owner(accounts.alice));
+            assert!({{name}}.is_owner(accounts.charlie));
+            assert!(!{{name}}.is_owner(accounts.eve));
+            assert_eq!({{name}}.threshold(), 2);
+        }
+
+        /// Submitting a transaction confirms it for the submitter.
+        #[ink::test]
+        fn submit_confirms_for_the_caller() {
+            let accounts = default_accounts();
+            let mut {{name}} = wallet();
+            let id = {{name}}.submit_transaction(accounts.eve, 10).unwrap();
+            assert_eq!({{name}}.confirmations(id), 1);
+            assert_eq!({{name}}.confirm_transaction(id), Err(Error::AlreadyConfirmed));
+            assert_eq!(
+                {{name}}.execute_transaction(id),
+                Err(Error::NotEnoughConfirmations)
+            );
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+        }
+
+        /// Once the threshold is reached the transaction can be executed exactly once.
+        #[ink::test]
+        fn execute_works_with_enough_confirmations() {
+            let accounts = default_accounts();
+            let mut {{name}} = wallet();
+            let id = {{name}}.submit_transaction(accounts.eve, 10).unwrap();
+
+            set_sender(accounts.bob);
+            assert_eq!({{name}}.confirm_transaction(id), Ok(()));
+            assert_eq!({{name}}.execute_transaction(id), Ok(()));
+            assert!({{name}}.transaction(id).unwrap().executed);
+            assert_eq!({{name}}.execute_transaction(id), Err(Error::AlreadyExecuted));
+        }
+
+        /// Accounts which are not owners cannot submit transactions.
+        #[ink::test]
+        fn non_owners_are_rejected() {
+            let accounts = default_accounts();
+            let mut {{name}} = wallet();
+            set_sender(accounts.eve);
+            assert_eq!(
+                {{name}}.submit_transaction(accounts.eve, 10),
+                Err(Error::NotOwner)
+            );
+        }
+    }
+}
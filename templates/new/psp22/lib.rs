@@ -0,0 +1,298 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod {{name}} {
+    use ink_lang as ink;
+    use ink_storage::{
+        collections::HashMap as StorageHashMap,
+        lazy::Lazy,
+    };
+
+    /// The errors which can be returned by the PSP22 messages.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PSP22Error {
+        /// Returned if the caller does not own enough tokens.
+        InsufficientBalance,
+        /// Returned if the caller is not allowed to spend enough tokens of the owner.
+        InsufficientAllowance,
+    }
+
+    /// The result type of the PSP22 messages.
+    pub type Result<T> = core::result::Result<T, PSP22Error>;
+
+    /// The PSP22 fungible token standard.
+    ///
+    /// The selectors of the messages are derived from the trait name, so the
+    /// contract is compatible with all wallets and dapps speaking PSP22.
+    #[ink::trait_definition]
+    pub trait PSP22 {
+        /// Returns the total token supply.
+        #[ink(message)]
+        fn total_supply(&self) -> Balance;
+
+        /// Returns the account balance for the specified `owner`.
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance;
+
+        /// Returns the amount which `spender` is still allowed to withdraw from `owner`.
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance;
+
+        /// Transfers `value` amount of tokens from the caller's account to account `to`.
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()>;
+
+        /// Transfers `value` tokens on the behalf of `from` to the account `to`.
+        #[ink(message)]
+        fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()>;
+
+        /// Allows `spender` to withdraw from the caller's account multiple times, up to
+        /// the `value` amount.
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()>;
+
+        /// Atomically increases the allowance granted to `spender` by the caller.
+        #[ink(message)]
+        fn increase_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<()>;
+
+        /// Atomically decreases the allowance granted to `spender` by the caller.
+        #[ink(message)]
+        fn decrease_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<()>;
+    }
+
+    /// Defines the storage of your token contract.
+    #[ink(storage)]
+    pub struct {{camel_name}} {
+        /// Total token supply.
+        total_supply: Lazy<Balance>,
+        /// Mapping from owner to number of owned tokens.
+        balances: StorageHashMap<AccountId, Balance>,
+        /// Mapping of the token amount which an account is allowed to withdraw
+        /// from another account.
+        allowances: StorageHashMap<(AccountId, AccountId), Balance>,
+    }
+
+    /// Event emitted when a token transfer occurs.
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        value: Balance,
+    }
+
+    /// Event emitted when an approval occurs that `spender` is allowed to withdraw
+    /// up to the amount of `value` tokens from `owner`.
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        spender: AccountId,
+        value: Balance,
+    }
+
+    impl {{camel_name}} {
+        /// Creates a new token contract with the specified initial supply,
+        /// which is entirely assigned to the caller.
+        #[ink(constructor)]
+        pub fn new(initial_supply: Balance) -> Self {
+            let caller = Self::env().caller();
+            let mut balances = StorageHashMap::new();
+            balances.insert(caller, initial_supply);
+            let instance = Self {
+                total_supply: Lazy::new(initial_supply),
+                balances,
+                allowances: StorageHashMap::new(),
+            };
+            Self::env().emit_event(Transfer {
+                from: None,
+                to: Some(caller),
+                value: initial_supply,
+            });
+            instance
+        }
+
+        /// Transfers `value` amount of tokens from `from` to `to`, emitting a
+        /// [`Transfer`] event.
+        fn transfer_from_to(
+            &mut self,
+            from: AccountId,
+            to: AccountId,
+            value: Balance,
+        ) -> Result<()> {
+            let from_balance = self.balance_of(from);
+            if from_balance < value {
+                return Err(PSP22Error::InsufficientBalance)
+            }
+            self.balances.insert(from, from_balance - value);
+            let to_balance = self.balance_of(to);
+            self.balances.insert(to, to_balance + value);
+            self.env().emit_event(Transfer {
+                from: Some(from),
+                to: Some(to),
+                value,
+            });
+            Ok(())
+        }
+
+        /// Sets the allowance of `spender` on the tokens of `owner`, emitting an
+        /// [`Approval`] event.
+        fn set_allowance(&mut self, owner: AccountId, spender: AccountId, value: Balance) {
+            self.allowances.insert((owner, spender), value);
+            self.env().emit_event(Approval {
+                owner,
+                spender,
+                value,
+            });
+        }
+    }
+
+    impl PSP22 for {{camel_name}} {
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            *self.total_supply
+        }
+
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> Balance {
+            self.balances.get(&owner).copied().unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, spender: AccountId) -> Balance {
+            self.allowances.get(&(owner, spender)).copied().unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, value: Balance) -> Result<()> {
+            let from = self.env().caller();
+            self.transfer_from_to(from, to, value)
+        }
+
+        #[ink(message)]
+        fn transfer_from(&mut self, from: AccountId, to: AccountId, value: Balance) -> Result<()> {
+            let caller = self.env().caller();
+            let allowance = self.allowance(from, caller);
+            if allowance < value {
+                return Err(PSP22Error::InsufficientAllowance)
+            }
+            self.transfer_from_to(from, to, value)?;
+            self.allowances.insert((from, caller), allowance - value);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, spender: AccountId, value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            self.set_allowance(owner, spender, value);
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn increase_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self.allowance(owner, spender);
+            self.set_allowance(owner, spender, allowance.saturating_add(delta_value));
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn decrease_allowance(&mut self, spender: AccountId, delta_value: Balance) -> Result<()> {
+            let owner = self.env().caller();
+            let allowance = self.allowance(owner, spender);
+            if allowance < delta_value {
+                return Err(PSP22Error::InsufficientAllowance)
+            }
+            self.set_allowance(owner, spender, allowance - delta_value);
+            Ok(())
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// Imports `ink_lang` so we can use `#[ink::test]`.
+        use ink_lang as ink;
+
+        fn default_accounts() -> ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment> {
+            ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("off-chain environment should have been initialized already")
+        }
+
+        /// The constructor assigns the whole supply to the caller and emits a `Transfer`.
+        #[ink::test]
+        fn new_works() {
+            let accounts = default_accounts();
+            let {{name}} = {{camel_name}}::new(100);
+            assert_eq!({{name}}.total_supply(), 100);
+            assert_eq!({{name}}.balance_of(accounts.alice), 100);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 1);
+        }
+
+        /// Tokens can be transferred and the balances are updated accordingly.
+        #[ink::test]
+        fn transfer_works() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new(100);
+            assert_eq!({{name}}.transfer(accounts.bob, 10), Ok(()));
+            assert_eq!({{name}}.balance_of(accounts.alice), 90);
+            assert_eq!({{name}}.balance_of(accounts.bob), 10);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+        }
+
+        /// Transferring more than the balance fails without changing any balances.
+        #[ink::test]
+        fn transfer_fails_with_insufficient_balance() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new(100);
+            assert_eq!(
+                {{name}}.transfer(accounts.bob, 101),
+                Err(PSP22Error::InsufficientBalance)
+            );
+            assert_eq!({{name}}.balance_of(accounts.alice), 100);
+            assert_eq!({{name}}.balance_of(accounts.bob), 0);
+        }
+
+        /// Allowances can be granted, increased and decreased.
+        #[ink::test]
+        fn allowances_work() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new(100);
+            assert_eq!({{name}}.approve(accounts.bob, 10), Ok(()));
+            assert_eq!({{name}}.allowance(accounts.alice, accounts.bob), 10);
+            assert_eq!({{name}}.increase_allowance(accounts.bob, 5), Ok(()));
+            assert_eq!({{name}}.allowance(accounts.alice, accounts.bob), 15);
+            assert_eq!({{name}}.decrease_allowance(accounts.bob, 15), Ok(()));
+            assert_eq!({{name}}.allowance(accounts.alice, accounts.bob), 0);
+            assert_eq!(
+                {{name}}.decrease_allowance(accounts.bob, 1),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+        }
+
+        /// `transfer_from` is only possible up to the granted allowance.
+        #[ink::test]
+        fn transfer_from_fails_without_allowance() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new(100);
+            assert_eq!(
+                {{name}}.transfer_from(accounts.bob, accounts.eve, 10),
+                Err(PSP22Error::InsufficientAllowance)
+            );
+        }
+    }
+}
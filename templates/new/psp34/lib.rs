@@ -0,0 +1,320 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod {{name}} {
+    use ink_lang as ink;
+    use ink_storage::collections::HashMap as StorageHashMap;
+
+    /// The identifier of a single token.
+    pub type Id = u32;
+
+    /// The errors which can be returned by the PSP34 messages.
+    #[derive(Debug, PartialEq, Eq, scale::Encode, scale::Decode)]
+    #[cfg_attr(feature = "std", derive(scale_info::TypeInfo))]
+    pub enum PSP34Error {
+        /// Returned if the caller is neither the owner nor an approved operator.
+        NotApproved,
+        /// Returned if the token does not exist.
+        TokenNotExists,
+        /// Returned if a token with the same id was already minted.
+        TokenExists,
+        /// Returned if the owner tries to approve themselves.
+        SelfApprove,
+    }
+
+    /// The result type of the PSP34 messages.
+    pub type Result<T> = core::result::Result<T, PSP34Error>;
+
+    /// The PSP34 non-fungible token standard.
+    ///
+    /// The selectors of the messages are derived from the trait name, so the
+    /// contract is compatible with all wallets and dapps speaking PSP34.
+    #[ink::trait_definition]
+    pub trait PSP34 {
+        /// Returns the number of tokens owned by `owner`.
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32;
+
+        /// Returns the owner of the token, if it exists.
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId>;
+
+        /// Returns `true` if `operator` may transfer the token `id` of `owner`,
+        /// or all tokens of `owner` if `id` is `None`.
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool;
+
+        /// Approves or disapproves `operator` to transfer the token `id` of the caller,
+        /// or all tokens of the caller if `id` is `None`.
+        #[ink(message)]
+        fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<()>;
+
+        /// Transfers the token `id` to the account `to`.
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, id: Id) -> Result<()>;
+
+        /// Returns the number of tokens in existence.
+        #[ink(message)]
+        fn total_supply(&self) -> Balance;
+    }
+
+    /// Defines the storage of your NFT contract.
+    #[ink(storage)]
+    #[derive(Default)]
+    pub struct {{camel_name}} {
+        /// Mapping from token to owner.
+        token_owner: StorageHashMap<Id, AccountId>,
+        /// Mapping from token to a single approved operator.
+        token_approvals: StorageHashMap<Id, AccountId>,
+        /// Mapping from owner to number of owned tokens.
+        owned_tokens_count: StorageHashMap<AccountId, u32>,
+        /// Mapping from owner to operators approved for all of its tokens.
+        operator_approvals: StorageHashMap<(AccountId, AccountId), ()>,
+        /// The number of tokens in existence.
+        total_supply: Balance,
+    }
+
+    /// Event emitted when a token is minted, transferred or burned.
+    #[ink(event)]
+    pub struct Transfer {
+        #[ink(topic)]
+        from: Option<AccountId>,
+        #[ink(topic)]
+        to: Option<AccountId>,
+        #[ink(topic)]
+        id: Id,
+    }
+
+    /// Event emitted when an operator is (dis)approved for a single token or for
+    /// all tokens of an owner.
+    #[ink(event)]
+    pub struct Approval {
+        #[ink(topic)]
+        owner: AccountId,
+        #[ink(topic)]
+        operator: AccountId,
+        id: Option<Id>,
+        approved: bool,
+    }
+
+    impl {{camel_name}} {
+        /// Creates a new NFT contract without any tokens.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Default::default()
+        }
+
+        /// Mints the token `id` and assigns it to the caller.
+        #[ink(message)]
+        pub fn mint(&mut self, id: Id) -> Result<()> {
+            let caller = self.env().caller();
+            if self.token_owner.contains_key(&id) {
+                return Err(PSP34Error::TokenExists)
+            }
+            self.token_owner.insert(id, caller);
+            self.change_count(caller, true);
+            self.total_supply += 1;
+            self.env().emit_event(Transfer {
+                from: None,
+                to: Some(caller),
+                id,
+            });
+            Ok(())
+        }
+
+        /// Burns the token `id` owned by the caller.
+        #[ink(message)]
+        pub fn burn(&mut self, id: Id) -> Result<()> {
+            let caller = self.env().caller();
+            let owner = self.owner_of(id).ok_or(PSP34Error::TokenNotExists)?;
+            if owner != caller {
+                return Err(PSP34Error::NotApproved)
+            }
+            self.token_owner.take(&id);
+            self.token_approvals.take(&id);
+            self.change_count(caller, false);
+            self.total_supply -= 1;
+            self.env().emit_event(Transfer {
+                from: Some(caller),
+                to: None,
+                id,
+            });
+            Ok(())
+        }
+
+        /// Increments or decrements the number of tokens owned by `owner`.
+        fn change_count(&mut self, owner: AccountId, increment: bool) {
+            let count = self.balance_of(owner);
+            let count = if increment { count + 1 } else { count - 1 };
+            self.owned_tokens_count.insert(owner, count);
+        }
+    }
+
+    impl PSP34 for {{camel_name}} {
+        #[ink(message)]
+        fn balance_of(&self, owner: AccountId) -> u32 {
+            self.owned_tokens_count.get(&owner).copied().unwrap_or(0)
+        }
+
+        #[ink(message)]
+        fn owner_of(&self, id: Id) -> Option<AccountId> {
+            self.token_owner.get(&id).cloned()
+        }
+
+        #[ink(message)]
+        fn allowance(&self, owner: AccountId, operator: AccountId, id: Option<Id>) -> bool {
+            if self.operator_approvals.contains_key(&(owner, operator)) {
+                return true
+            }
+            match id {
+                Some(id) => self.token_approvals.get(&id) == Some(&operator),
+                None => false,
+            }
+        }
+
+        #[ink(message)]
+        fn approve(&mut self, operator: AccountId, id: Option<Id>, approved: bool) -> Result<()> {
+            let caller = self.env().caller();
+            if caller == operator {
+                return Err(PSP34Error::SelfApprove)
+            }
+            match id {
+                Some(id) => {
+                    let owner = self.owner_of(id).ok_or(PSP34Error::TokenNotExists)?;
+                    if owner != caller {
+                        return Err(PSP34Error::NotApproved)
+                    }
+                    if approved {
+                        self.token_approvals.insert(id, operator);
+                    } else {
+                        self.token_approvals.take(&id);
+                    }
+                }
+                None => {
+                    if approved {
+                        self.operator_approvals.insert((caller, operator), ());
+                    } else {
+                        self.operator_approvals.take(&(caller, operator));
+                    }
+                }
+            }
+            self.env().emit_event(Approval {
+                owner: caller,
+                operator,
+                id,
+                approved,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn transfer(&mut self, to: AccountId, id: Id) -> Result<()> {
+            let caller = self.env().caller();
+            let owner = self.owner_of(id).ok_or(PSP34Error::TokenNotExists)?;
+            if owner != caller && !self.allowance(owner, caller, Some(id)) {
+                return Err(PSP34Error::NotApproved)
+            }
+            self.token_approvals.take(&id);
+            self.change_count(owner, false);
+            self.change_count(to, true);
+            self.token_owner.insert(id, to);
+            self.env().emit_event(Transfer {
+                from: Some(owner),
+                to: Some(to),
+                id,
+            });
+            Ok(())
+        }
+
+        #[ink(message)]
+        fn total_supply(&self) -> Balance {
+            self.total_supply
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// Imports `ink_lang` so we can use `#[ink::test]`.
+        use ink_lang as ink;
+
+        fn default_accounts() -> ink_env::test::DefaultAccounts<ink_env::DefaultEnvironment> {
+            ink_env::test::default_accounts::<ink_env::DefaultEnvironment>()
+                .expect("off-chain environment should have been initialized already")
+        }
+
+        /// Minting assigns the token to the caller.
+        #[ink::test]
+        fn mint_works() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new();
+            assert_eq!({{name}}.mint(1), Ok(()));
+            assert_eq!({{name}}.owner_of(1), Some(accounts.alice));
+            assert_eq!({{name}}.balance_of(accounts.alice), 1);
+            assert_eq!({{name}}.total_supply(), 1);
+            assert_eq!({{name}}.mint(1), Err(PSP34Error::TokenExists));
+        }
+
+        /// The owner can transfer a token, emitting a `Transfer` event.
+        #[ink::test]
+        fn transfer_works() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new();
+            assert_eq!({{name}}.mint(1), Ok(()));
+            assert_eq!({{name}}.transfer(accounts.bob, 1), Ok(()));
+            assert_eq!({{name}}.owner_of(1), Some(accounts.bob));
+            assert_eq!({{name}}.balance_of(accounts.alice), 0);
+            assert_eq!({{name}}.balance_of(accounts.bob), 1);
+
+            let emitted_events = ink_env::test::recorded_events().collect::<Vec<_>>();
+            assert_eq!(emitted_events.len(), 2);
+        }
+
+        /// Tokens which have not been minted cannot be transferred.
+        #[ink::test]
+        fn transfer_fails_for_unknown_token() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new();
+            assert_eq!(
+                {{name}}.transfer(accounts.bob, 1),
+                Err(PSP34Error::TokenNotExists)
+            );
+        }
+
+        /// Approvals can be granted for single tokens and for all tokens.
+        #[ink::test]
+        fn approvals_work() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new();
+            assert_eq!({{name}}.mint(1), Ok(()));
+            assert_eq!({{name}}.approve(accounts.bob, Some(1), true), Ok(()));
+            assert!({{name}}.allowance(accounts.alice, accounts.bob, Some(1)));
+            assert!(!{{name}}.allowance(accounts.alice, accounts.bob, None));
+            assert_eq!({{name}}.approve(accounts.charlie, None, true), Ok(()));
+            assert!({{name}}.allowance(accounts.alice, accounts.charlie, Some(2)));
+            assert_eq!(
+                {{name}}.approve(accounts.alice, None, true),
+                Err(PSP34Error::SelfApprove)
+            );
+        }
+
+        /// Burning removes the token from the supply.
+        #[ink::test]
+        fn burn_works() {
+            let accounts = default_accounts();
+            let mut {{name}} = {{camel_name}}::new();
+            assert_eq!({{name}}.mint(1), Ok(()));
+            assert_eq!({{name}}.burn(1), Ok(()));
+            assert_eq!({{name}}.owner_of(1), None);
+            assert_eq!({{name}}.balance_of(accounts.alice), 0);
+            assert_eq!({{name}}.total_supply(), 0);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+//! The `{{camel_name}}` interface shared between contracts.
+//!
+//! Contracts add this crate as a dependency with `default-features = false` and forward
+//! their `std` feature to `{{name}}/std`. They implement the trait in their
+//! `#[ink::contract]` module:
+//!
+//! ```ignore
+//! impl {{name}}::{{camel_name}} for MyContract {
+//!     #[ink(message)]
+//!     fn get(&self) -> bool {
+//!         self.value
+//!     }
+//!
+//!     #[ink(message)]
+//!     fn flip(&mut self) {
+//!         self.value = !self.value;
+//!     }
+//! }
+//! ```
+//!
+//! The selectors of the messages are derived from the trait name, so every contract
+//! implementing `{{camel_name}}` can be called the same way.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+/// Defines the messages every implementing contract provides.
+#[ink::trait_definition]
+pub trait {{camel_name}} {
+    /// Returns the current value.
+    #[ink(message)]
+    fn get(&self) -> bool;
+
+    /// Flips the current value.
+    #[ink(message)]
+    fn flip(&mut self);
+}
@@ -0,0 +1,64 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+#[ink::contract]
+mod {{name}} {
+    use {{types}}::Value;
+
+    /// Defines the storage of your contract.
+    #[ink(storage)]
+    pub struct {{camel_name}} {
+        value: Value,
+    }
+
+    impl {{camel_name}} {
+        /// Constructor that initializes the stored value to its default.
+        #[ink(constructor)]
+        pub fn new() -> Self {
+            Self {
+                value: Value::default(),
+            }
+        }
+
+        /// Sets the number of the stored value.
+        #[ink(message)]
+        pub fn set(&mut self, number: u32) {
+            self.value.set(number);
+        }
+
+        /// Returns the stored value.
+        #[ink(message)]
+        pub fn get(&self) -> Value {
+            self.value
+        }
+    }
+
+    /// Unit tests in Rust are normally defined within such a `#[cfg(test)]`
+    /// module and test functions are marked with a `#[test]` attribute.
+    /// The below code is technically just normal Rust code.
+    #[cfg(test)]
+    mod tests {
+        /// Imports all the definitions from the outer scope so we can use them here.
+        use super::*;
+
+        /// Imports `ink_lang` so we can use `#[ink::test]`.
+        use ink_lang as ink;
+
+        /// Setting the number counts the updates.
+        #[ink::test]
+        fn set_works() {
+            let mut {{name}} = {{camel_name}}::new();
+            assert_eq!({{name}}.get(), Value::default());
+            {{name}}.set(42);
+            {{name}}.set(43);
+            assert_eq!(
+                {{name}}.get(),
+                Value {
+                    number: 43,
+                    updates: 2
+                }
+            );
+        }
+    }
+}
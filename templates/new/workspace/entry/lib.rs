@@ -0,0 +1,40 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_lang as ink;
+
+/// The entry point of the workspace, calling the other contracts of the workspace.
+#[ink::contract]
+mod {{name}} {
+    use {{types}}::Value;
+{{callee_imports}}
+    /// Defines the storage of your contract.
+    #[ink(storage)]
+    pub struct {{camel_name}} {
+        value: Value,
+{{callee_fields}}    }
+
+    impl {{camel_name}} {
+        /// Instantiates the other contracts of the workspace from their uploaded code,
+        /// using `version` as salt.
+        #[ink(constructor)]
+        pub fn new(version: u32{{callee_params}}) -> Self {
+            let total_balance = Self::env().balance();
+            let salt = version.to_le_bytes();
+{{callee_instantiations}}            Self {
+                value: Value::default(),
+{{callee_inits}}            }
+        }
+
+        /// Sets the number of the stored value, here and in the other contracts.
+        #[ink(message)]
+        pub fn set(&mut self, number: u32) {
+            self.value.set(number);
+{{callee_calls}}        }
+
+        /// Returns the stored value.
+        #[ink(message)]
+        pub fn get(&self) -> Value {
+            self.value
+        }
+    }
+}
@@ -0,0 +1,39 @@
+//! Types shared by the contracts of the workspace.
+//!
+//! Every contract depends on this crate, so the types of their messages always agree.
+
+#![cfg_attr(not(feature = "std"), no_std)]
+
+use ink_storage::traits::{PackedLayout, SpreadLayout};
+
+/// The value stored by every contract of the workspace.
+#[derive(
+    Debug,
+    Default,
+    Clone,
+    Copy,
+    PartialEq,
+    Eq,
+    scale::Encode,
+    scale::Decode,
+    SpreadLayout,
+    PackedLayout,
+)]
+#[cfg_attr(
+    feature = "std",
+    derive(scale_info::TypeInfo, ink_storage::traits::StorageLayout)
+)]
+pub struct Value {
+    /// The number last set.
+    pub number: u32,
+    /// How often the number was set.
+    pub updates: u32,
+}
+
+impl Value {
+    /// Sets the number, counting the update.
+    pub fn set(&mut self, number: u32) {
+        self.number = number;
+        self.updates += 1;
+    }
+}